@@ -0,0 +1,468 @@
+use crate::eigen::{combine, free_dofs, generalized_eigen, restrict};
+use crate::elasticity::LinearStatic;
+use crate::factorization::SparseLdlt;
+use crate::solver::LinearSolver;
+use crate::sparse::CsrMatrix;
+
+// Linear structural dynamics M a + C v + K u = f(t) of a linear elastic
+// problem with Rayleigh damping C = a M + b K. The loads of the problem are
+// scaled by a load curve, further nodal forces follow their own curves, and
+// prescribed displacements are held at their values.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Integrator {
+    // Implicit, unconditionally stable for 2 beta >= gamma >= 1/2. The
+    // average acceleration method, beta = 1/4 and gamma = 1/2, conserves
+    // the energy of free vibration.
+    Newmark { beta: f64, gamma: f64 },
+    // Hilber-Hughes-Taylor, damping high frequencies for alpha in
+    // [-1/3, 0) while staying second order accurate.
+    Hht { alpha: f64 },
+    // Explicit, with a lumped mass matrix and steps below the stable one.
+    CentralDifference,
+}
+
+impl Integrator {
+    pub fn average_acceleration() -> Self {
+        Integrator::Newmark {
+            beta: 0.25,
+            gamma: 0.5,
+        }
+    }
+
+    // Alpha, beta and gamma of the generalized scheme
+    //   M a' + (1 + alpha) (C v' + K u') - alpha (C v + K u)
+    //     = (1 + alpha) f' - alpha f
+    // with the Newmark updates of u' and v' from the start of the step.
+    fn parameters(&self) -> Result<(f64, f64, f64), &'static str> {
+        match *self {
+            Integrator::Newmark { beta, gamma } => {
+                if beta.is_nan() || gamma.is_nan() || beta <= 0.0 || gamma < 0.5 {
+                    return Err("Newmark parameters need beta > 0 and gamma >= 1/2");
+                }
+                Ok((0.0, beta, gamma))
+            }
+            Integrator::Hht { alpha } => {
+                if alpha.is_nan() || !(-1.0 / 3.0..=0.0).contains(&alpha) {
+                    return Err("HHT alpha must lie in [-1/3, 0]");
+                }
+                Ok((alpha, 0.25 * (1.0 - alpha).powi(2), 0.5 - alpha))
+            }
+            Integrator::CentralDifference => Ok((0.0, 0.0, 0.5)),
+        }
+    }
+}
+
+// Piecewise linear function of time, constant before the first and after
+// the last point. The points are private so that there is always at least
+// one, in increasing time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadCurve {
+    times: Vec<f64>,
+    values: Vec<f64>,
+}
+
+impl LoadCurve {
+    pub fn new(points: &[(f64, f64)]) -> Result<Self, &'static str> {
+        if points.is_empty() {
+            return Err("Load curve needs a point");
+        }
+        if points.iter().any(|(t, v)| t.is_nan() || v.is_nan())
+            || points.windows(2).any(|w| w[1].0 <= w[0].0)
+        {
+            return Err("Load curve times must increase");
+        }
+        Ok(LoadCurve {
+            times: points.iter().map(|p| p.0).collect(),
+            values: points.iter().map(|p| p.1).collect(),
+        })
+    }
+
+    pub fn constant(value: f64) -> Self {
+        LoadCurve {
+            times: vec![0.0],
+            values: vec![value],
+        }
+    }
+
+    pub fn points(&self) -> Vec<(f64, f64)> {
+        self.times
+            .iter()
+            .copied()
+            .zip(self.values.iter().copied())
+            .collect()
+    }
+
+    pub fn value(&self, time: f64) -> f64 {
+        let last = self.times.len() - 1;
+        if time <= self.times[0] {
+            return self.values[0];
+        }
+        if time >= self.times[last] {
+            return self.values[last];
+        }
+        let i = self.times.partition_point(|&t| t <= time);
+        let (t0, t1) = (self.times[i - 1], self.times[i]);
+        let s = (time - t0) / (t1 - t0);
+        (1.0 - s) * self.values[i - 1] + s * self.values[i]
+    }
+}
+
+// Rayleigh coefficients of the mass and stiffness for a damping ratio at
+// two angular frequencies. The ratio is lower in between and higher
+// outside.
+pub fn rayleigh_coefficients(ratio: f64, first: f64, second: f64) -> (f64, f64) {
+    let sum = first + second;
+    (2.0 * ratio * first * second / sum, 2.0 * ratio / sum)
+}
+
+// Response of a node at the output times.
+#[derive(Debug, Clone)]
+pub struct NodeHistory {
+    pub node: usize,
+    pub displacements: Vec<[f64; 3]>,
+    pub velocities: Vec<[f64; 3]>,
+    pub accelerations: Vec<[f64; 3]>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DynamicSolution {
+    pub times: Vec<f64>,
+    pub histories: Vec<NodeHistory>,
+    // At the end time.
+    pub displacements: Vec<[f64; 3]>,
+    pub velocities: Vec<[f64; 3]>,
+    pub accelerations: Vec<[f64; 3]>,
+}
+
+// Solver of systems with one matrix, dividing by a diagonal one and
+// factorizing once for a direct solver.
+enum Repeated<'m> {
+    Diagonal(Vec<f64>),
+    Factors(SparseLdlt),
+    Iterative(LinearSolver, &'m CsrMatrix),
+}
+
+impl<'m> Repeated<'m> {
+    fn new(a: &'m CsrMatrix, solver: LinearSolver) -> Result<Self, &'static str> {
+        if (0..a.nrows).all(|i| a.row(i).0.iter().all(|&j| j == i)) {
+            let diagonal = a.diagonal();
+            if diagonal.contains(&0.0) {
+                return Err("Matrix is singular");
+            }
+            return Ok(Repeated::Diagonal(diagonal));
+        }
+        Ok(match solver {
+            LinearSolver::Direct(ordering) => Repeated::Factors(SparseLdlt::new(a, ordering)?),
+            solver => Repeated::Iterative(solver, a),
+        })
+    }
+
+    fn solve(&self, b: &[f64]) -> Result<Vec<f64>, &'static str> {
+        match self {
+            Repeated::Diagonal(d) => Ok(b.iter().zip(d.iter()).map(|(b, d)| b / d).collect()),
+            Repeated::Factors(factors) => factors.solve(b),
+            Repeated::Iterative(solver, a) => solver.solve(a, b),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StructuralDynamics<'a> {
+    problem: LinearStatic<'a>,
+    lumped: bool,
+    mass_damping: f64,
+    stiffness_damping: f64,
+    load_curve: LoadCurve,
+    // Dof, force and curve of the nodal forces with their own curves.
+    loads: Vec<(usize, f64, LoadCurve)>,
+    // Initial state.
+    displacements: Vec<f64>,
+    velocities: Vec<f64>,
+    history_nodes: Vec<usize>,
+}
+
+impl<'a> StructuralDynamics<'a> {
+    pub fn new(problem: LinearStatic<'a>, lumped: bool) -> Self {
+        let n = problem.num_dofs();
+        StructuralDynamics {
+            problem,
+            lumped,
+            mass_damping: 0.0,
+            stiffness_damping: 0.0,
+            load_curve: LoadCurve::constant(1.0),
+            loads: Vec::new(),
+            displacements: vec![0.0; n],
+            velocities: vec![0.0; n],
+            history_nodes: Vec::new(),
+        }
+    }
+
+    pub fn problem(&self) -> &LinearStatic<'a> {
+        &self.problem
+    }
+
+    pub fn problem_mut(&mut self) -> &mut LinearStatic<'a> {
+        &mut self.problem
+    }
+
+    pub fn set_rayleigh_damping(
+        &mut self,
+        mass_damping: f64,
+        stiffness_damping: f64,
+    ) -> Result<(), &'static str> {
+        if [mass_damping, stiffness_damping]
+            .iter()
+            .any(|c| c.is_nan() || *c < 0.0)
+        {
+            return Err("Damping coefficients must not be negative");
+        }
+        self.mass_damping = mass_damping;
+        self.stiffness_damping = stiffness_damping;
+        Ok(())
+    }
+
+    // Curve scaling the loads of the problem, one by default.
+    pub fn set_load_curve(&mut self, curve: LoadCurve) {
+        self.load_curve = curve;
+    }
+
+    // Nodal force of the given value times a curve.
+    pub fn add_load(
+        &mut self,
+        node: usize,
+        direction: usize,
+        value: f64,
+        curve: LoadCurve,
+    ) -> Result<(), &'static str> {
+        let dof = self.problem.dof(node, direction)?;
+        self.loads.push((dof, value, curve));
+        Ok(())
+    }
+
+    // Initial displacements and velocities, zero by default. Prescribed
+    // displacements override them.
+    pub fn set_initial_conditions(
+        &mut self,
+        displacements: &[[f64; 3]],
+        velocities: &[[f64; 3]],
+    ) -> Result<(), &'static str> {
+        let count = self.problem.mesh().num_nodes();
+        if displacements.len() != count || velocities.len() != count {
+            return Err("Initial condition count does not match the mesh");
+        }
+        let k = self.problem.analysis().dofs_per_node();
+        for node in 0..count {
+            for d in 0..k {
+                self.displacements[node * k + d] = displacements[node][d];
+                self.velocities[node * k + d] = velocities[node][d];
+            }
+        }
+        Ok(())
+    }
+
+    // Nodes whose response is recorded at every step.
+    pub fn set_history_nodes(&mut self, nodes: &[usize]) -> Result<(), &'static str> {
+        if nodes.iter().any(|&n| n >= self.problem.mesh().num_nodes()) {
+            return Err("Node index out of range");
+        }
+        self.history_nodes = nodes.to_vec();
+        Ok(())
+    }
+
+    // Reduced stiffness and mass matrices of the free dofs.
+    fn matrices(&self) -> Result<Reduced, &'static str> {
+        let constraints = self.problem.constraints();
+        let (free, index) = free_dofs(self.problem.num_dofs(), |dof| {
+            constraints.contains_key(&dof)
+        });
+        let k = restrict(&self.problem.stiffness()?, &free, &index);
+        let m = restrict(&self.problem.mass(self.lumped)?, &free, &index);
+        if m.diagonal().iter().any(|&d| d <= 0.0) {
+            return Err("Density is not set");
+        }
+        Ok((free, index, k, m))
+    }
+
+    // Largest stable step of the central difference method, 2 / omega for
+    // the highest natural frequency, from the smallest eigenvalue of
+    // M x = mu K x. Damping, taken at the end of the step, does not lower it.
+    pub fn stable_time_step(&self) -> Result<f64, &'static str> {
+        let (_, _, k, m) = self.matrices()?;
+        self.critical_step(&k, &m)
+    }
+
+    fn critical_step(&self, k: &CsrMatrix, m: &CsrMatrix) -> Result<f64, &'static str> {
+        let ordering = self.problem.linear_solver().ordering();
+        let pairs = generalized_eigen(m, k, 1, 0.0, ordering)?;
+        Ok(2.0 * pairs.values[0].sqrt())
+    }
+
+    // Response from the initial state up to the end time in equal steps of
+    // at most the given one.
+    pub fn solve(
+        &self,
+        end: f64,
+        step: f64,
+        integrator: Integrator,
+    ) -> Result<DynamicSolution, &'static str> {
+        if end.is_nan() || step.is_nan() || end <= 0.0 || step <= 0.0 {
+            return Err("End time and step must be positive");
+        }
+        if integrator == Integrator::CentralDifference && !self.lumped {
+            return Err("Central difference needs a lumped mass matrix");
+        }
+        let (alpha, beta, gamma) = integrator.parameters()?;
+        let (free, index, k, m) = self.matrices()?;
+        let n = self.problem.num_dofs();
+        let mut fixed = vec![0.0; n];
+        for (&dof, &value) in self.problem.constraints().iter() {
+            fixed[dof] = value;
+        }
+        // Forces of the prescribed displacements on the free dofs.
+        let coupling = self.problem.stiffness()?.multiply(&fixed);
+        let pattern = self.problem.load_vector()?;
+        let forces = |time: f64| -> Vec<f64> {
+            let scale = self.load_curve.value(time);
+            let mut f: Vec<f64> = free
+                .iter()
+                .map(|&i| scale * pattern[i] - coupling[i])
+                .collect();
+            for (dof, value, curve) in self.loads.iter() {
+                if index[*dof] != usize::MAX {
+                    f[index[*dof]] += value * curve.value(time);
+                }
+            }
+            f
+        };
+        let (a0, a1) = (self.mass_damping, self.stiffness_damping);
+        // K u + C v
+        let internal = |u: &[f64], v: &[f64]| -> Vec<f64> {
+            let (ku, kv, mv) = (k.multiply(u), k.multiply(v), m.multiply(v));
+            (0..u.len())
+                .map(|i| ku[i] + a0 * mv[i] + a1 * kv[i])
+                .collect()
+        };
+
+        let count = (end / step - 1e-9).ceil().max(1.0) as usize;
+        let dt = end / count as f64;
+        if integrator == Integrator::CentralDifference && dt > self.critical_step(&k, &m)? {
+            return Err("Time step exceeds the stable step of central difference");
+        }
+        let solver = self.problem.linear_solver();
+        let mut u: Vec<f64> = free.iter().map(|&i| self.displacements[i]).collect();
+        let mut v: Vec<f64> = free.iter().map(|&i| self.velocities[i]).collect();
+        let mut previous = forces(0.0);
+        let r = internal(&u, &v);
+        let rhs: Vec<f64> = previous.iter().zip(r.iter()).map(|(f, r)| f - r).collect();
+        let mut a = Repeated::new(&m, solver)?.solve(&rhs)?;
+        // M + (1 + alpha) (gamma dt C + beta dt^2 K)
+        let (c1, c2) = ((1.0 + alpha) * gamma * dt, (1.0 + alpha) * beta * dt * dt);
+        let mut system = combine(&m, &k, (c1 * a1 + c2) / (1.0 + c1 * a0));
+        for value in system.values.iter_mut() {
+            *value *= 1.0 + c1 * a0;
+        }
+        let system = Repeated::new(&system, solver)?;
+
+        let mut times = vec![0.0];
+        let mut histories: Vec<NodeHistory> = self
+            .history_nodes
+            .iter()
+            .map(|&node| NodeHistory {
+                node,
+                displacements: Vec::with_capacity(count + 1),
+                velocities: Vec::with_capacity(count + 1),
+                accelerations: Vec::with_capacity(count + 1),
+            })
+            .collect();
+        self.record(&mut histories, &index, &fixed, &u, &v, &a);
+        for i in 1..=count {
+            let time = i as f64 * dt;
+            let current = forces(time);
+            let predicted_u: Vec<f64> = (0..u.len())
+                .map(|j| u[j] + dt * v[j] + (0.5 - beta) * dt * dt * a[j])
+                .collect();
+            let predicted_v: Vec<f64> = (0..v.len())
+                .map(|j| v[j] + (1.0 - gamma) * dt * a[j])
+                .collect();
+            let (new, old) = (internal(&predicted_u, &predicted_v), internal(&u, &v));
+            let rhs: Vec<f64> = (0..u.len())
+                .map(|j| (1.0 + alpha) * (current[j] - new[j]) - alpha * (previous[j] - old[j]))
+                .collect();
+            a = system.solve(&rhs)?;
+            for j in 0..u.len() {
+                u[j] = predicted_u[j] + beta * dt * dt * a[j];
+                v[j] = predicted_v[j] + gamma * dt * a[j];
+            }
+            previous = current;
+            times.push(time);
+            self.record(&mut histories, &index, &fixed, &u, &v, &a);
+        }
+
+        let (displacements, velocities, accelerations) = self.nodal(&free, &fixed, &u, &v, &a);
+        Ok(DynamicSolution {
+            times,
+            histories,
+            displacements,
+            velocities,
+            accelerations,
+        })
+    }
+
+    // Appends the current state of the history nodes, the prescribed
+    // displacements being at rest.
+    fn record(
+        &self,
+        histories: &mut [NodeHistory],
+        index: &[usize],
+        fixed: &[f64],
+        u: &[f64],
+        v: &[f64],
+        a: &[f64],
+    ) {
+        let k = self.problem.analysis().dofs_per_node();
+        for history in histories.iter_mut() {
+            let mut state = [[0.0; 3]; 3];
+            for (d, dof) in (history.node * k..(history.node + 1) * k).enumerate() {
+                match index[dof] {
+                    usize::MAX => state[0][d] = fixed[dof],
+                    j => {
+                        state[0][d] = u[j];
+                        state[1][d] = v[j];
+                        state[2][d] = a[j];
+                    }
+                }
+            }
+            history.displacements.push(state[0]);
+            history.velocities.push(state[1]);
+            history.accelerations.push(state[2]);
+        }
+    }
+
+    // Nodal displacements, velocities and accelerations of the free dof
+    // values, the prescribed displacements being at rest.
+    fn nodal(&self, free: &[usize], fixed: &[f64], u: &[f64], v: &[f64], a: &[f64]) -> NodalState {
+        let k = self.problem.analysis().dofs_per_node();
+        let count = self.problem.mesh().num_nodes();
+        let mut state = (
+            vec![[0.0; 3]; count],
+            vec![[0.0; 3]; count],
+            vec![[0.0; 3]; count],
+        );
+        for (dof, value) in fixed.iter().enumerate() {
+            state.0[dof / k][dof % k] = *value;
+        }
+        for (j, &dof) in free.iter().enumerate() {
+            state.0[dof / k][dof % k] = u[j];
+            state.1[dof / k][dof % k] = v[j];
+            state.2[dof / k][dof % k] = a[j];
+        }
+        state
+    }
+}
+
+// Nodal displacements, velocities and accelerations.
+type NodalState = (Vec<[f64; 3]>, Vec<[f64; 3]>, Vec<[f64; 3]>);
+
+// Free dofs, their index map and the reduced stiffness and mass matrices.
+type Reduced = (Vec<usize>, Vec<usize>, CsrMatrix, CsrMatrix);
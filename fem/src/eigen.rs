@@ -0,0 +1,486 @@
+use crate::factorization::SparseLdlt;
+use crate::ordering::Ordering;
+use crate::sparse::CsrMatrix;
+
+// Generalized symmetric eigenvalue problems A x = lambda B x of structural
+// dynamics and stability, solved by shift-invert Lanczos: the operator
+// (A - sigma B)^-1 B maps the eigenvalues nearest above the shift sigma to
+// its largest ones. Converged pairs are locked and the remaining ones sought
+// in their complement, with a Sturm sequence check on the inertia of
+// A - lambda B so that no eigenvalue in the range is skipped, including
+// repeated ones.
+
+#[derive(Debug, Clone)]
+pub struct Eigenpairs {
+    pub values: Vec<f64>,
+    pub vectors: Vec<Vec<f64>>,
+}
+
+// Natural vibration modes, lowest first, with mass normalized shapes of N
+// values per node: displacements, followed by rotations for frames.
+#[derive(Debug, Clone)]
+pub struct ModalSolution<const N: usize = 3> {
+    pub angular_frequencies: Vec<f64>,
+    // In cycles per unit time.
+    pub frequencies: Vec<f64>,
+    pub mode_shapes: Vec<Vec<[f64; N]>>,
+}
+
+// Load factors at which the structure buckles under multiples of its
+// loads, lowest first, with shapes scaled to a largest value of one.
+#[derive(Debug, Clone)]
+pub struct BucklingSolution<const N: usize = 3> {
+    pub load_factors: Vec<f64>,
+    pub mode_shapes: Vec<Vec<[f64; N]>>,
+}
+
+// Relative residual below which a Ritz pair is accepted.
+const TOLERANCE: f64 = 1e-9;
+const MAX_RESTARTS: usize = 20;
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b.iter()).map(|(a, b)| a * b).sum()
+}
+
+// Eigenvalues in ascending order and the corresponding orthonormal
+// eigenvectors of a dense symmetric matrix, by cyclic Jacobi rotations.
+pub fn symmetric_eigen(matrix: &[Vec<f64>]) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let n = matrix.len();
+    let mut a = matrix.to_vec();
+    let mut v: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect();
+    let norm: f64 = a.iter().flatten().map(|x| x * x).sum::<f64>().sqrt();
+    for _ in 0..100 {
+        let off: f64 = (0..n)
+            .flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i][j] * a[i][j])
+            .sum::<f64>()
+            .sqrt();
+        if off <= 1e-15 * norm {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p][q].abs() <= 1e-300 {
+                    continue;
+                }
+                let tau = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = tau.signum() / (tau.abs() + (1.0 + tau * tau).sqrt());
+                let c = 1.0 / (1.0 + t * t).sqrt();
+                let s = t * c;
+                for row in a.iter_mut() {
+                    let (akp, akq) = (row[p], row[q]);
+                    row[p] = c * akp - s * akq;
+                    row[q] = s * akp + c * akq;
+                }
+                let (upper, lower) = a.split_at_mut(q);
+                for (apk, aqk) in upper[p].iter_mut().zip(lower[0].iter_mut()) {
+                    let (x, y) = (*apk, *aqk);
+                    *apk = c * x - s * y;
+                    *aqk = s * x + c * y;
+                }
+                for row in v.iter_mut() {
+                    let (vp, vq) = (row[p], row[q]);
+                    row[p] = c * vp - s * vq;
+                    row[q] = s * vp + c * vq;
+                }
+            }
+        }
+    }
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| a[i][i].total_cmp(&a[j][j]));
+    let values = order.iter().map(|&i| a[i][i]).collect();
+    let vectors = order
+        .iter()
+        .map(|&i| v.iter().map(|row| row[i]).collect())
+        .collect();
+    (values, vectors)
+}
+
+// Converged value of the operator, its vector x and W x.
+type Locked = (f64, Vec<f64>, Vec<f64>);
+
+// Inner product used to orthogonalize the Lanczos vectors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Inner {
+    // B, positive semidefinite as a mass matrix.
+    B,
+    // A - sigma B, positive definite as the stiffness in buckling.
+    Shifted,
+}
+
+struct Problem<'a> {
+    a: &'a CsrMatrix,
+    b: &'a CsrMatrix,
+    shift: f64,
+    factor: SparseLdlt,
+    inner: Inner,
+    ordering: Ordering,
+    // Negative pivots of A - sigma B.
+    below: usize,
+}
+
+impl Problem<'_> {
+    fn operator(&self, x: &[f64]) -> Result<Vec<f64>, &'static str> {
+        self.factor.solve(&self.b.multiply(x))
+    }
+
+    fn weight(&self, x: &[f64]) -> Vec<f64> {
+        match self.inner {
+            Inner::B => self.b.multiply(x),
+            Inner::Shifted => {
+                let (ax, bx) = (self.a.multiply(x), self.b.multiply(x));
+                ax.iter().zip(bx).map(|(a, b)| a - self.shift * b).collect()
+            }
+        }
+    }
+
+    // Number of eigenvalues in [shift, lambda).
+    fn count_below(&self, lambda: f64) -> Result<usize, &'static str> {
+        let shifted = combine(self.a, self.b, -lambda);
+        let factor = SparseLdlt::new(&shifted, self.ordering)?;
+        Ok(factor.inertia().1.saturating_sub(self.below))
+    }
+
+    // One Lanczos run of at most `steps` steps in the complement of the
+    // locked vectors, returning the converged Ritz pairs with positive
+    // values, largest first, and whether these are all there are.
+    fn lanczos(
+        &self,
+        locked: &[Locked],
+        steps: usize,
+        seed: u64,
+    ) -> Result<(Vec<Locked>, bool), &'static str> {
+        let n = self.a.nrows;
+        let mut state = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        let start: Vec<f64> = (0..n)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+            })
+            .collect();
+        // Starting in the range of the operator keeps the vectors clear of
+        // the null space of a singular mass matrix.
+        let mut q = self.operator(&start)?;
+        let initial = dot(&q, &self.weight(&q)).max(0.0).sqrt();
+        let mut basis: Vec<(Vec<f64>, Vec<f64>)> = Vec::new();
+        let mut alphas = Vec::new();
+        let mut betas: Vec<f64> = Vec::new();
+        let mut scale = 0.0f64;
+        loop {
+            // Full reorthogonalization, twice for stability.
+            for _ in 0..2 {
+                for (_, v, wv) in locked.iter() {
+                    let c = dot(wv, &q);
+                    q.iter_mut().zip(v.iter()).for_each(|(q, v)| *q -= c * v);
+                }
+                for (v, wv) in basis.iter() {
+                    let c = dot(wv, &q);
+                    q.iter_mut().zip(v.iter()).for_each(|(q, v)| *q -= c * v);
+                }
+            }
+            let wq = self.weight(&q);
+            let norm = dot(&q, &wq).max(0.0).sqrt();
+            // A vanishing norm means an invariant subspace, where the Ritz
+            // pairs are exact. A start vector lost to the locked vectors
+            // leaves only the infinite eigenvalues of a singular mass.
+            let invariant = norm <= 1e-12 * scale
+                || norm == 0.0
+                || (basis.is_empty() && norm <= 1e-10 * initial);
+            if let Some(beta) = betas.last_mut() {
+                *beta = if invariant { 0.0 } else { norm };
+            }
+            if invariant || basis.len() == steps {
+                break;
+            }
+            let v: Vec<f64> = q.iter().map(|q| q / norm).collect();
+            let wv: Vec<f64> = wq.iter().map(|w| w / norm).collect();
+            q = self.operator(&v)?;
+            let alpha = dot(&wv, &q);
+            alphas.push(alpha);
+            betas.push(0.0);
+            scale = scale.max(alpha.abs());
+            basis.push((v, wv));
+        }
+        let m = basis.len();
+        if m == 0 && !locked.is_empty() {
+            return Ok((Vec::new(), true));
+        }
+        if m == 0 {
+            return Err("Eigenvalue problem has no finite eigenvalues");
+        }
+        let mut t = vec![vec![0.0; m]; m];
+        for i in 0..m {
+            t[i][i] = alphas[i];
+            if i + 1 < m {
+                t[i][i + 1] = betas[i];
+                t[i + 1][i] = betas[i];
+            }
+        }
+        let residual = betas[m - 1];
+        let (values, vectors) = symmetric_eigen(&t);
+        let largest = values.iter().fold(0.0f64, |m, v| m.max(v.abs()));
+        // A largest value at or below zero, converged to a small fraction of
+        // the spread, leaves no eigenvalues above the shift in the
+        // complement.
+        let top = m - 1;
+        let exhausted = values[top] <= 1e-12 * largest
+            && (residual * vectors[top][m - 1]).abs() <= 1e-6 * largest;
+        let mut pairs = Vec::new();
+        for (theta, s) in values.iter().zip(vectors.iter()).rev() {
+            if *theta <= 1e-12 * largest {
+                break;
+            }
+            // Stop at the first unconverged pair so that none is skipped.
+            if (residual * s[m - 1]).abs() > TOLERANCE * theta {
+                break;
+            }
+            let mut x = vec![0.0; n];
+            for (c, (v, _)) in s.iter().zip(basis.iter()) {
+                x.iter_mut().zip(v.iter()).for_each(|(x, v)| *x += c * v);
+            }
+            let wx = self.weight(&x);
+            pairs.push((*theta, x, wx));
+        }
+        Ok((pairs, exhausted))
+    }
+
+    fn solve(&self, count: usize) -> Result<Vec<(f64, Vec<f64>)>, &'static str> {
+        let n = self.a.nrows;
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        if count > n {
+            return Err("More eigenvalues requested than the problem size");
+        }
+        let mut locked: Vec<Locked> = Vec::new();
+        let mut wanted = count;
+        let mut steps = (2 * count + 20).min(n);
+        for restart in 0..MAX_RESTARTS {
+            if locked.len() < wanted {
+                let available = n - locked.len();
+                let (pairs, exhausted) =
+                    self.lanczos(&locked, steps.min(available), restart as u64)?;
+                let need = wanted - locked.len();
+                // A run stopped by an invariant subspace, as with repeated
+                // eigenvalues, still makes progress through its locked pairs.
+                if pairs.len() < need {
+                    if exhausted || (pairs.is_empty() && steps >= available) {
+                        return Err("Fewer eigenvalues than requested above the shift");
+                    }
+                    steps = (2 * steps).min(n);
+                }
+                locked.extend(pairs.into_iter().take(need));
+                if locked.len() < wanted {
+                    continue;
+                }
+            }
+            // Sturm check just above the largest eigenvalue found.
+            let lambda = locked
+                .iter()
+                .map(|(theta, _, _)| 1.0 / theta)
+                .fold(0.0f64, f64::max);
+            let expected = self.count_below(self.shift + lambda * (1.0 + 1e-6))?;
+            if expected <= locked.len() {
+                locked.sort_by(|a, b| b.0.total_cmp(&a.0));
+                locked.truncate(count);
+                return Ok(locked
+                    .into_iter()
+                    .map(|(theta, x, _)| (self.shift + 1.0 / theta, x))
+                    .collect());
+            }
+            wanted = expected.min(n);
+        }
+        Err("Eigenvalue solver did not converge")
+    }
+}
+
+// A + c B
+pub(crate) fn combine(a: &CsrMatrix, b: &CsrMatrix, c: f64) -> CsrMatrix {
+    let mut triplets = Vec::with_capacity(a.nnz() + b.nnz());
+    for (matrix, factor) in [(a, 1.0), (b, c)] {
+        for i in 0..matrix.nrows {
+            let (columns, values) = matrix.row(i);
+            triplets.extend(
+                columns
+                    .iter()
+                    .zip(values.iter())
+                    .map(|(&j, &v)| (i, j, factor * v)),
+            );
+        }
+    }
+    CsrMatrix::from_triplets_unchecked(a.nrows, a.ncols, &triplets)
+}
+
+fn setup<'a>(
+    a: &'a CsrMatrix,
+    b: &'a CsrMatrix,
+    shift: f64,
+    inner: Inner,
+    ordering: Ordering,
+) -> Result<Problem<'a>, &'static str> {
+    if a.nrows != a.ncols || b.nrows != a.nrows || b.ncols != a.ncols {
+        return Err("Matrix sizes do not match");
+    }
+    let factor =
+        SparseLdlt::new(&combine(a, b, -shift), ordering).map_err(|_| "Shift is an eigenvalue")?;
+    let below = factor.inertia().1;
+    if inner == Inner::Shifted && below > 0 {
+        return Err("Stiffness matrix is not positive definite");
+    }
+    Ok(Problem {
+        a,
+        b,
+        shift,
+        factor,
+        inner,
+        ordering,
+        below,
+    })
+}
+
+// The `count` eigenvalues of K x = lambda M x nearest above the shift, in
+// ascending order, for a symmetric K and a positive semidefinite M. The
+// vectors are M-orthonormal.
+pub fn generalized_eigen(
+    k: &CsrMatrix,
+    m: &CsrMatrix,
+    count: usize,
+    shift: f64,
+    ordering: Ordering,
+) -> Result<Eigenpairs, &'static str> {
+    let pairs = setup(k, m, shift, Inner::B, ordering)?.solve(count)?;
+    let (values, vectors) = pairs.into_iter().unzip();
+    Ok(Eigenpairs { values, vectors })
+}
+
+// The `count` smallest positive load factors of K x = lambda (-G) x for a
+// positive definite stiffness K and the geometric stiffness G of the
+// reference load. The vectors are K-orthonormal.
+pub fn buckling_eigen(
+    k: &CsrMatrix,
+    g: &CsrMatrix,
+    count: usize,
+    ordering: Ordering,
+) -> Result<Eigenpairs, &'static str> {
+    let minus: CsrMatrix = CsrMatrix {
+        values: g.values.iter().map(|v| -v).collect(),
+        ..g.clone()
+    };
+    let pairs = setup(k, &minus, 0.0, Inner::Shifted, ordering)?.solve(count)?;
+    let (values, vectors) = pairs.into_iter().unzip();
+    Ok(Eigenpairs { values, vectors })
+}
+
+// The lowest natural vibration modes of a structure from its stiffness and
+// mass matrices, with the constrained dofs held at zero. Unsupported
+// structures have rigid body modes of zero frequency.
+pub(crate) fn vibration_modes<const N: usize>(
+    k: &CsrMatrix,
+    m: &CsrMatrix,
+    constrained: impl Fn(usize) -> bool,
+    dofs_per_node: usize,
+    count: usize,
+    ordering: Ordering,
+) -> Result<ModalSolution<N>, &'static str> {
+    let nodes = k.nrows / dofs_per_node;
+    let (free, index) = free_dofs(k.nrows, constrained);
+    let k = restrict(k, &free, &index);
+    let m = restrict(m, &free, &index);
+    // A shift just below zero keeps K - shift M regular.
+    let ratio = k.diagonal().iter().sum::<f64>() / m.diagonal().iter().sum::<f64>();
+    let pairs = generalized_eigen(&k, &m, count, -1e-8 * ratio, ordering)?;
+    let angular_frequencies: Vec<f64> = pairs.values.iter().map(|v| v.max(0.0).sqrt()).collect();
+    Ok(ModalSolution {
+        frequencies: angular_frequencies
+            .iter()
+            .map(|w| w / (2.0 * std::f64::consts::PI))
+            .collect(),
+        angular_frequencies,
+        mode_shapes: pairs
+            .vectors
+            .iter()
+            .map(|v| nodal_shape(&free, v, 1.0, dofs_per_node, nodes))
+            .collect(),
+    })
+}
+
+// Linear buckling modes of a structure from its stiffness and the geometric
+// stiffness of the reference load, with the constrained dofs held at zero.
+pub(crate) fn buckling_modes<const N: usize>(
+    k: &CsrMatrix,
+    g: &CsrMatrix,
+    constrained: impl Fn(usize) -> bool,
+    dofs_per_node: usize,
+    count: usize,
+    ordering: Ordering,
+) -> Result<BucklingSolution<N>, &'static str> {
+    let nodes = k.nrows / dofs_per_node;
+    let (free, index) = free_dofs(k.nrows, constrained);
+    let k = restrict(k, &free, &index);
+    let g = restrict(g, &free, &index);
+    let pairs = buckling_eigen(&k, &g, count, ordering)?;
+    let mode_shapes = pairs
+        .vectors
+        .iter()
+        .map(|v| {
+            let largest = v.iter().fold(0.0f64, |m, v| m.max(v.abs()));
+            nodal_shape(&free, v, 1.0 / largest, dofs_per_node, nodes)
+        })
+        .collect();
+    Ok(BucklingSolution {
+        load_factors: pairs.values,
+        mode_shapes,
+    })
+}
+
+// Scaled free dof values spread over the nodes, zero at constrained dofs.
+fn nodal_shape<const N: usize>(
+    free: &[usize],
+    vector: &[f64],
+    scale: f64,
+    dofs_per_node: usize,
+    nodes: usize,
+) -> Vec<[f64; N]> {
+    let mut shape = vec![[0.0; N]; nodes];
+    for (&dof, v) in free.iter().zip(vector.iter()) {
+        shape[dof / dofs_per_node][dof % dofs_per_node] = scale * v;
+    }
+    shape
+}
+
+// Restriction of a square matrix to the free dofs, numbered by `index`
+// with usize::MAX at constrained dofs.
+pub(crate) fn restrict(matrix: &CsrMatrix, free: &[usize], index: &[usize]) -> CsrMatrix {
+    let mut triplets = Vec::with_capacity(matrix.nnz());
+    for (r, &i) in free.iter().enumerate() {
+        let (columns, values) = matrix.row(i);
+        for (&j, &v) in columns.iter().zip(values.iter()) {
+            if index[j] != usize::MAX {
+                triplets.push((r, index[j], v));
+            }
+        }
+    }
+    CsrMatrix::from_triplets_unchecked(free.len(), free.len(), &triplets)
+}
+
+// Free dofs and their numbering, usize::MAX at constrained dofs.
+pub(crate) fn free_dofs<F>(n: usize, constrained: F) -> (Vec<usize>, Vec<usize>)
+where
+    F: Fn(usize) -> bool,
+{
+    let mut index = vec![usize::MAX; n];
+    let mut free = Vec::new();
+    for (dof, slot) in index.iter_mut().enumerate() {
+        if !constrained(dof) {
+            *slot = free.len();
+            free.push(dof);
+        }
+    }
+    (free, index)
+}
@@ -0,0 +1,877 @@
+use crate::eigen::{BucklingSolution, ModalSolution, buckling_modes, vibration_modes};
+use crate::material::{IsotropicElastic, von_mises};
+use crate::mesh::{ElementType, Facet, Mesh};
+use crate::quadrature::{QuadratureRule, full_degree};
+use crate::shape::{Jacobian, evaluate, node_coordinates};
+use crate::solver::LinearSolver;
+use crate::sparse::{CooMatrix, CsrMatrix};
+use geom::Point3d;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Analysis {
+    // Surface elements in the xy plane.
+    PlaneStress { thickness: f64 },
+    PlaneStrain,
+    // Surface elements in the xy plane with x the radius and y the axis of
+    // revolution. Loads, reactions and volumes are per full revolution.
+    Axisymmetric,
+    Solid,
+}
+
+impl Analysis {
+    pub fn dofs_per_node(&self) -> usize {
+        match self {
+            Analysis::Solid => 3,
+            _ => 2,
+        }
+    }
+
+    fn num_strains(&self) -> usize {
+        match self {
+            Analysis::Solid => 6,
+            Analysis::Axisymmetric => 4,
+            _ => 3,
+        }
+    }
+
+    fn material_matrix(&self, material: &IsotropicElastic) -> Vec<Vec<f64>> {
+        match self {
+            Analysis::Solid => material.solid_matrix().iter().map(|r| r.to_vec()).collect(),
+            Analysis::Axisymmetric => material
+                .axisymmetric_matrix()
+                .iter()
+                .map(|r| r.to_vec())
+                .collect(),
+            Analysis::PlaneStrain => material
+                .plane_strain_matrix()
+                .iter()
+                .map(|r| r.to_vec())
+                .collect(),
+            Analysis::PlaneStress { .. } => material
+                .plane_stress_matrix()
+                .iter()
+                .map(|r| r.to_vec())
+                .collect(),
+        }
+    }
+
+    // Reduced free thermal strain of a temperature rise times the expansion
+    // coefficient. Plane strain adds the expansion held back along z.
+    fn thermal_strain(&self, material: &IsotropicElastic, thermal: f64) -> Vec<f64> {
+        match self {
+            Analysis::Solid => vec![thermal, thermal, thermal, 0.0, 0.0, 0.0],
+            Analysis::Axisymmetric => vec![thermal, thermal, thermal, 0.0],
+            Analysis::PlaneStrain => {
+                let t = (1.0 + material.poisson) * thermal;
+                vec![t, t, 0.0]
+            }
+            Analysis::PlaneStress { .. } => vec![thermal, thermal, 0.0],
+        }
+    }
+
+    // Full strain and stress components from the reduced ones of the
+    // analysis, given the free thermal strain.
+    fn expand(
+        &self,
+        material: &IsotropicElastic,
+        strain: &[f64],
+        stress: &[f64],
+        thermal: f64,
+    ) -> ([f64; 6], [f64; 6]) {
+        match self {
+            Analysis::Solid => {
+                let mut e = [0.0; 6];
+                let mut s = [0.0; 6];
+                e.copy_from_slice(strain);
+                s.copy_from_slice(stress);
+                (e, s)
+            }
+            Analysis::Axisymmetric => (
+                [strain[0], strain[1], strain[2], strain[3], 0.0, 0.0],
+                [stress[0], stress[1], stress[2], stress[3], 0.0, 0.0],
+            ),
+            Analysis::PlaneStrain => {
+                let zz = material.poisson * (stress[0] + stress[1]) - material.young * thermal;
+                (
+                    [strain[0], strain[1], 0.0, strain[2], 0.0, 0.0],
+                    [stress[0], stress[1], zz, stress[2], 0.0, 0.0],
+                )
+            }
+            Analysis::PlaneStress { .. } => {
+                let nu = material.poisson;
+                let zz = (-nu * (strain[0] + strain[1]) + (1.0 + nu) * thermal) / (1.0 - nu);
+                (
+                    [strain[0], strain[1], zz, strain[2], 0.0, 0.0],
+                    [stress[0], stress[1], 0.0, stress[2], 0.0, 0.0],
+                )
+            }
+        }
+    }
+}
+
+// Nodal strains and stresses.
+type NodalFields = (Vec<[f64; 6]>, Vec<[f64; 6]>);
+
+// Strain-displacement matrix and integration scale at a point.
+struct Kinematics {
+    n: Vec<f64>,
+    // Cartesian shape function gradients.
+    g: Vec<[f64; 3]>,
+    b: Vec<Vec<f64>>,
+    // Jacobian determinant times thickness or circumference.
+    scale: f64,
+}
+
+// Quadrature degree that fully integrates the stiffness of an undistorted
+// element.
+pub(crate) fn stiffness_degree(element_type: ElementType) -> usize {
+    let simplex = matches!(element_type.linear(), ElementType::Tri3 | ElementType::Tet4);
+    if element_type.is_quadratic() && !simplex {
+        4
+    } else {
+        2
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StaticSolution {
+    pub displacements: Vec<[f64; 3]>,
+    // Nodal forces needed to hold the constraints, zero at free dofs up to
+    // the solver tolerance.
+    pub reactions: Vec<[f64; 3]>,
+    // Averaged over the elements sharing each node.
+    pub strains: Vec<[f64; 6]>,
+    pub stresses: Vec<[f64; 6]>,
+}
+
+impl StaticSolution {
+    pub fn von_mises(&self) -> Vec<f64> {
+        self.stresses.iter().map(von_mises).collect()
+    }
+}
+
+// Linear elastic small displacement analysis. Dofs are numbered node by
+// node: x and y displacements, plus z for solids.
+#[derive(Debug, Clone)]
+pub struct LinearStatic<'a> {
+    mesh: &'a Mesh,
+    analysis: Analysis,
+    materials: Vec<IsotropicElastic>,
+    // Thermal expansion coefficients of the elements and nodal temperature
+    // rises, empty without a thermal load.
+    expansions: Vec<f64>,
+    temperature_rises: Vec<f64>,
+    // Mass densities of the elements for vibration analysis.
+    densities: Vec<f64>,
+    constraints: BTreeMap<usize, f64>,
+    forces: Vec<f64>,
+    pressures: Vec<(Facet, f64)>,
+    body_force: [f64; 3],
+    linear_solver: LinearSolver,
+}
+
+impl<'a> LinearStatic<'a> {
+    pub fn new(
+        mesh: &'a Mesh,
+        analysis: Analysis,
+        material: IsotropicElastic,
+    ) -> Result<Self, &'static str> {
+        let dimension = if analysis == Analysis::Solid { 3 } else { 2 };
+        if mesh
+            .elements
+            .iter()
+            .any(|e| e.element_type.dimension() != dimension)
+        {
+            return Err("Element type does not match the analysis");
+        }
+        mesh.validate()?;
+        Ok(LinearStatic {
+            mesh,
+            analysis,
+            materials: vec![material; mesh.num_elements()],
+            expansions: vec![0.0; mesh.num_elements()],
+            temperature_rises: Vec::new(),
+            densities: vec![0.0; mesh.num_elements()],
+            constraints: BTreeMap::new(),
+            forces: vec![0.0; mesh.num_nodes() * analysis.dofs_per_node()],
+            pressures: Vec::new(),
+            body_force: [0.0; 3],
+            linear_solver: LinearSolver::default(),
+        })
+    }
+
+    pub fn mesh(&self) -> &Mesh {
+        self.mesh
+    }
+
+    pub fn analysis(&self) -> Analysis {
+        self.analysis
+    }
+
+    pub fn num_dofs(&self) -> usize {
+        self.mesh.num_nodes() * self.analysis.dofs_per_node()
+    }
+
+    pub fn set_material(
+        &mut self,
+        elements: &[usize],
+        material: IsotropicElastic,
+    ) -> Result<(), &'static str> {
+        if elements.iter().any(|&e| e >= self.materials.len()) {
+            return Err("Element index out of range");
+        }
+        for &e in elements.iter() {
+            self.materials[e] = material;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn material(&self, element: usize) -> IsotropicElastic {
+        self.materials[element]
+    }
+
+    pub(crate) fn constraints(&self) -> &BTreeMap<usize, f64> {
+        &self.constraints
+    }
+
+    pub(crate) fn linear_solver(&self) -> LinearSolver {
+        self.linear_solver
+    }
+
+    pub(crate) fn has_thermal_load(&self) -> bool {
+        !self.temperature_rises.is_empty() && self.expansions.iter().any(|&a| a != 0.0)
+    }
+
+    pub(crate) fn dof(&self, node: usize, direction: usize) -> Result<usize, &'static str> {
+        let k = self.analysis.dofs_per_node();
+        if node >= self.mesh.num_nodes() || direction >= k {
+            return Err("Node or direction out of range");
+        }
+        Ok(node * k + direction)
+    }
+
+    // Prescribed displacement of a node in direction 0 (x), 1 (y) or 2 (z).
+    pub fn fix(&mut self, node: usize, direction: usize, value: f64) -> Result<(), &'static str> {
+        let dof = self.dof(node, direction)?;
+        self.constraints.insert(dof, value);
+        Ok(())
+    }
+
+    // Clamps all directions of the given nodes.
+    pub fn fix_nodes(&mut self, nodes: &[usize]) -> Result<(), &'static str> {
+        for &n in nodes.iter() {
+            for d in 0..self.analysis.dofs_per_node() {
+                self.fix(n, d, 0.0)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn add_force(
+        &mut self,
+        node: usize,
+        direction: usize,
+        value: f64,
+    ) -> Result<(), &'static str> {
+        let dof = self.dof(node, direction)?;
+        self.forces[dof] += value;
+        Ok(())
+    }
+
+    // Uniform pressure on an element facet, positive pushing into the
+    // element.
+    pub fn add_pressure(&mut self, facet: &Facet, pressure: f64) -> Result<(), &'static str> {
+        if facet.element >= self.mesh.num_elements()
+            || self.mesh.elements[facet.element]
+                .element_type
+                .facet_type(facet.local)
+                .is_none()
+        {
+            return Err("Facet does not belong to the mesh");
+        }
+        self.pressures.push((facet.clone(), pressure));
+        Ok(())
+    }
+
+    pub fn set_linear_solver(&mut self, linear_solver: LinearSolver) {
+        self.linear_solver = linear_solver;
+    }
+
+    pub fn set_expansion(
+        &mut self,
+        elements: &[usize],
+        coefficient: f64,
+    ) -> Result<(), &'static str> {
+        if elements.iter().any(|&e| e >= self.expansions.len()) {
+            return Err("Element index out of range");
+        }
+        for &e in elements.iter() {
+            self.expansions[e] = coefficient;
+        }
+        Ok(())
+    }
+
+    pub fn set_density(&mut self, elements: &[usize], density: f64) -> Result<(), &'static str> {
+        if density.is_nan() || density < 0.0 {
+            return Err("Density must not be negative");
+        }
+        if elements.iter().any(|&e| e >= self.densities.len()) {
+            return Err("Element index out of range");
+        }
+        for &e in elements.iter() {
+            self.densities[e] = density;
+        }
+        Ok(())
+    }
+
+    // Nodal temperatures, e.g. from a heat conduction solution, loading the
+    // structure through thermal expansion relative to the reference
+    // temperature at which it is stress free.
+    pub fn set_temperatures(
+        &mut self,
+        temperatures: &[f64],
+        reference: f64,
+    ) -> Result<(), &'static str> {
+        if temperatures.len() != self.mesh.num_nodes() {
+            return Err("Temperature count does not match the mesh");
+        }
+        self.temperature_rises = temperatures.iter().map(|t| t - reference).collect();
+        Ok(())
+    }
+
+    // Free thermal strain, expansion coefficient times temperature rise, at
+    // a point of an element.
+    fn thermal(&self, element: usize, n: &[f64]) -> f64 {
+        if self.temperature_rises.is_empty() || self.expansions[element] == 0.0 {
+            return 0.0;
+        }
+        let rise: f64 = self.mesh.elements[element]
+            .nodes
+            .iter()
+            .zip(n.iter())
+            .map(|(&node, n)| n * self.temperature_rises[node])
+            .sum();
+        self.expansions[element] * rise
+    }
+
+    // Force per unit volume, e.g. gravity times density.
+    pub fn set_body_force(&mut self, force: [f64; 3]) {
+        self.body_force = force;
+    }
+
+    fn kinematics(
+        &self,
+        element: usize,
+        points: &[Point3d],
+        xi: [f64; 3],
+    ) -> Result<Kinematics, &'static str> {
+        let element_type = self.mesh.elements[element].element_type;
+        let (n, d) = evaluate(element_type, xi);
+        let jacobian = Jacobian::new(element_type, points, &d)?;
+        let g = jacobian.gradients(&d);
+        let mut position = [0.0; 3];
+        for (n, p) in n.iter().zip(points.iter()) {
+            let (x, y, z) = p.get_coords();
+            position = [
+                position[0] + n * x,
+                position[1] + n * y,
+                position[2] + n * z,
+            ];
+        }
+        let k = self.analysis.dofs_per_node();
+        let mut b = vec![vec![0.0; n.len() * k]; self.analysis.num_strains()];
+        let mut scale = jacobian.determinant;
+        for (a, g) in g.iter().enumerate() {
+            let c = a * k;
+            b[0][c] = g[0];
+            b[1][c + 1] = g[1];
+            match self.analysis {
+                Analysis::Solid => {
+                    b[2][c + 2] = g[2];
+                    b[3][c] = g[1];
+                    b[3][c + 1] = g[0];
+                    b[4][c + 1] = g[2];
+                    b[4][c + 2] = g[1];
+                    b[5][c] = g[2];
+                    b[5][c + 2] = g[0];
+                }
+                Analysis::Axisymmetric => {
+                    // On the axis the hoop strain equals the radial strain.
+                    b[2][c] = if position[0] > 1e-12 {
+                        n[a] / position[0]
+                    } else {
+                        g[0]
+                    };
+                    b[3][c] = g[1];
+                    b[3][c + 1] = g[0];
+                }
+                _ => {
+                    b[2][c] = g[1];
+                    b[2][c + 1] = g[0];
+                }
+            }
+        }
+        match self.analysis {
+            Analysis::PlaneStress { thickness } => scale *= thickness,
+            Analysis::Axisymmetric => scale *= 2.0 * std::f64::consts::PI * position[0],
+            _ => {}
+        }
+        Ok(Kinematics { n, g, b, scale })
+    }
+
+    pub(crate) fn element_dofs(&self, element: usize) -> Vec<usize> {
+        let k = self.analysis.dofs_per_node();
+        self.mesh.elements[element]
+            .nodes
+            .iter()
+            .flat_map(|&n| (0..k).map(move |d| n * k + d))
+            .collect()
+    }
+
+    // Dense element stiffness matrix in element dof order.
+    pub fn element_stiffness(&self, element: usize) -> Result<Vec<Vec<f64>>, &'static str> {
+        let element_type = self.mesh.elements[element].element_type;
+        let points = self.mesh.element_points(element);
+        let d = self.analysis.material_matrix(&self.materials[element]);
+        let size = element_type.num_nodes() * self.analysis.dofs_per_node();
+        let mut ke = vec![vec![0.0; size]; size];
+        for (xi, w) in QuadratureRule::new(element_type, stiffness_degree(element_type)).iter() {
+            let kin = self.kinematics(element, &points, xi)?;
+            // D B, then accumulate B^T (D B).
+            let db: Vec<Vec<f64>> = d
+                .iter()
+                .map(|row| {
+                    (0..size)
+                        .map(|j| row.iter().zip(kin.b.iter()).map(|(d, b)| d * b[j]).sum())
+                        .collect()
+                })
+                .collect();
+            let factor = w * kin.scale;
+            for (bi, dbi) in kin.b.iter().zip(db.iter()) {
+                for i in 0..size {
+                    if bi[i] == 0.0 {
+                        continue;
+                    }
+                    let bf = bi[i] * factor;
+                    for (kij, dbij) in ke[i].iter_mut().zip(dbi.iter()) {
+                        *kij += bf * dbij;
+                    }
+                }
+            }
+        }
+        Ok(ke)
+    }
+
+    pub fn stiffness(&self) -> Result<CsrMatrix, &'static str> {
+        let n = self.num_dofs();
+        let mut k = CooMatrix::new(n, n);
+        for e in 0..self.mesh.num_elements() {
+            let dofs = self.element_dofs(e);
+            k.push_block(&dofs, &dofs, &self.element_stiffness(e)?)?;
+        }
+        Ok(k.to_csr())
+    }
+
+    // Dense element mass matrix in element dof order. Lumping scales the
+    // diagonal of the consistent matrix to the element mass (HRZ), which
+    // keeps the masses positive at the corners of quadratic elements.
+    pub fn element_mass(
+        &self,
+        element: usize,
+        lumped: bool,
+    ) -> Result<Vec<Vec<f64>>, &'static str> {
+        let element_type = self.mesh.elements[element].element_type;
+        let points = self.mesh.element_points(element);
+        let density = self.densities[element];
+        let k = self.analysis.dofs_per_node();
+        let count = element_type.num_nodes();
+        let mut nodal = vec![vec![0.0; count]; count];
+        let mut total = 0.0;
+        for (xi, w) in QuadratureRule::new(element_type, full_degree(element_type)).iter() {
+            let kin = self.kinematics(element, &points, xi)?;
+            let factor = w * kin.scale * density;
+            total += factor;
+            for (row, na) in nodal.iter_mut().zip(kin.n.iter()) {
+                for (m, nb) in row.iter_mut().zip(kin.n.iter()) {
+                    *m += factor * na * nb;
+                }
+            }
+        }
+        if lumped {
+            let diagonal: f64 = (0..count).map(|a| nodal[a][a]).sum();
+            for (a, row) in nodal.iter_mut().enumerate() {
+                let m = row[a] * total / diagonal;
+                row.fill(0.0);
+                row[a] = m;
+            }
+        }
+        let mut me = vec![vec![0.0; count * k]; count * k];
+        for a in 0..count {
+            for b in 0..count {
+                for d in 0..k {
+                    me[a * k + d][b * k + d] = nodal[a][b];
+                }
+            }
+        }
+        Ok(me)
+    }
+
+    pub fn mass(&self, lumped: bool) -> Result<CsrMatrix, &'static str> {
+        let n = self.num_dofs();
+        let mut m = CooMatrix::new(n, n);
+        for e in 0..self.mesh.num_elements() {
+            let dofs = self.element_dofs(e);
+            m.push_block(&dofs, &dofs, &self.element_mass(e, lumped)?)?;
+        }
+        Ok(m.to_csr())
+    }
+
+    // Dense element geometric stiffness matrix of the stresses due to the
+    // given displacements, in element dof order.
+    pub fn element_geometric_stiffness(
+        &self,
+        displacements: &[[f64; 3]],
+        element: usize,
+    ) -> Result<Vec<Vec<f64>>, &'static str> {
+        let element_type = self.mesh.elements[element].element_type;
+        let points = self.mesh.element_points(element);
+        let k = self.analysis.dofs_per_node();
+        let size = element_type.num_nodes() * k;
+        let mut kg = vec![vec![0.0; size]; size];
+        for (xi, w) in QuadratureRule::new(element_type, stiffness_degree(element_type)).iter() {
+            let kin = self.kinematics(element, &points, xi)?;
+            let s = self.strain_stress(displacements, element, xi)?.1;
+            // In-plane components only for surface analyses, where the
+            // gradients have no z part.
+            let tensor = [[s[0], s[3], s[5]], [s[3], s[1], s[4]], [s[5], s[4], s[2]]];
+            let factor = w * kin.scale;
+            for (a, ga) in kin.g.iter().enumerate() {
+                let sg: Vec<f64> = (0..3)
+                    .map(|i| (0..3).map(|j| tensor[i][j] * ga[j]).sum())
+                    .collect();
+                for (b, gb) in kin.g.iter().enumerate() {
+                    let value = factor * (0..3).map(|i| sg[i] * gb[i]).sum::<f64>();
+                    for d in 0..k {
+                        kg[a * k + d][b * k + d] += value;
+                    }
+                    // Hoop stress acting on the radial displacements.
+                    if self.analysis == Analysis::Axisymmetric {
+                        kg[a * k][b * k] += factor * s[2] * kin.b[2][a * k] * kin.b[2][b * k];
+                    }
+                }
+            }
+        }
+        Ok(kg)
+    }
+
+    pub fn geometric_stiffness(
+        &self,
+        displacements: &[[f64; 3]],
+    ) -> Result<CsrMatrix, &'static str> {
+        if displacements.len() != self.mesh.num_nodes() {
+            return Err("Displacement count does not match the mesh");
+        }
+        let n = self.num_dofs();
+        let mut kg = CooMatrix::new(n, n);
+        for e in 0..self.mesh.num_elements() {
+            let dofs = self.element_dofs(e);
+            kg.push_block(
+                &dofs,
+                &dofs,
+                &self.element_geometric_stiffness(displacements, e)?,
+            )?;
+        }
+        Ok(kg.to_csr())
+    }
+
+    // Consistent nodal forces of all loads.
+    pub fn load_vector(&self) -> Result<Vec<f64>, &'static str> {
+        let mut f = self.forces.clone();
+        let k = self.analysis.dofs_per_node();
+        if self.body_force.iter().any(|&b| b != 0.0) {
+            for e in 0..self.mesh.num_elements() {
+                let element_type = self.mesh.elements[e].element_type;
+                let points = self.mesh.element_points(e);
+                let dofs = self.element_dofs(e);
+                for (xi, w) in
+                    QuadratureRule::new(element_type, stiffness_degree(element_type)).iter()
+                {
+                    let kin = self.kinematics(e, &points, xi)?;
+                    for (a, n) in kin.n.iter().enumerate() {
+                        for d in 0..k {
+                            f[dofs[a * k + d]] += w * kin.scale * n * self.body_force[d];
+                        }
+                    }
+                }
+            }
+        }
+        // Initial strain loads B^T D e0 of thermal expansion.
+        for e in 0..self.mesh.num_elements() {
+            if self.temperature_rises.is_empty() || self.expansions[e] == 0.0 {
+                continue;
+            }
+            let element_type = self.mesh.elements[e].element_type;
+            let points = self.mesh.element_points(e);
+            let dofs = self.element_dofs(e);
+            let material = &self.materials[e];
+            let d = self.analysis.material_matrix(material);
+            for (xi, w) in QuadratureRule::new(element_type, stiffness_degree(element_type)).iter()
+            {
+                let kin = self.kinematics(e, &points, xi)?;
+                let strain = self
+                    .analysis
+                    .thermal_strain(material, self.thermal(e, &kin.n));
+                let stress: Vec<f64> = d
+                    .iter()
+                    .map(|row| row.iter().zip(strain.iter()).map(|(d, e)| d * e).sum())
+                    .collect();
+                for (row, s) in kin.b.iter().zip(stress.iter()) {
+                    for (&dof, b) in dofs.iter().zip(row.iter()) {
+                        f[dof] += w * kin.scale * b * s;
+                    }
+                }
+            }
+        }
+        for (facet, pressure) in self.pressures.iter() {
+            for (node, traction) in self.facet_forces(facet, *pressure)? {
+                for d in 0..k {
+                    f[node * k + d] += traction[d];
+                }
+            }
+        }
+        Ok(f)
+    }
+
+    // Nodal forces of a uniform pressure on a facet.
+    fn facet_forces(
+        &self,
+        facet: &Facet,
+        pressure: f64,
+    ) -> Result<Vec<(usize, [f64; 3])>, &'static str> {
+        let element = &self.mesh.elements[facet.element];
+        let facet_type = element
+            .element_type
+            .facet_type(facet.local)
+            .ok_or("Facet does not belong to the mesh")?;
+        let nodes = element.facet_nodes(facet.local);
+        let points: Vec<Point3d> = nodes.iter().map(|&n| self.mesh.nodes[n]).collect();
+        let inside = centroid(&self.mesh.element_points(facet.element));
+        let mut forces = vec![[0.0; 3]; nodes.len()];
+        let degree = if facet_type.is_quadratic() { 4 } else { 2 };
+        for (xi, w) in QuadratureRule::new(facet_type, degree).iter() {
+            let (n, d) = evaluate(facet_type, xi);
+            let jacobian = Jacobian::new(facet_type, &points, &d)?;
+            let m = &jacobian.matrix;
+            let mut normal = if facet_type.dimension() == 2 {
+                cross(&m[0], &m[1])
+            } else {
+                [m[0][1], -m[0][0], 0.0]
+            };
+            let length =
+                (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+            let mut position = [0.0; 3];
+            for (n, p) in n.iter().zip(points.iter()) {
+                let (x, y, z) = p.get_coords();
+                position = [
+                    position[0] + n * x,
+                    position[1] + n * y,
+                    position[2] + n * z,
+                ];
+            }
+            let outward = (0..3)
+                .map(|k| normal[k] * (position[k] - inside[k]))
+                .sum::<f64>();
+            let sign = if outward < 0.0 { -1.0 } else { 1.0 };
+            normal = normal.map(|v| sign * v / length);
+            let scale = match self.analysis {
+                Analysis::PlaneStress { thickness } => thickness,
+                Analysis::Axisymmetric => 2.0 * std::f64::consts::PI * position[0],
+                _ => 1.0,
+            };
+            let factor = -pressure * w * jacobian.determinant * scale;
+            for (force, n) in forces.iter_mut().zip(n.iter()) {
+                for k in 0..3 {
+                    force[k] += factor * n * normal[k];
+                }
+            }
+        }
+        Ok(nodes.into_iter().zip(forces).collect())
+    }
+
+    pub fn solve(&self) -> Result<StaticSolution, &'static str> {
+        let k = self.stiffness()?;
+        let f = self.load_vector()?;
+        let n = self.num_dofs();
+        let mut u = vec![0.0; n];
+        let mut index = vec![usize::MAX; n];
+        let mut free = Vec::new();
+        for dof in 0..n {
+            match self.constraints.get(&dof) {
+                Some(&value) => u[dof] = value,
+                None => {
+                    index[dof] = free.len();
+                    free.push(dof);
+                }
+            }
+        }
+        // Eliminate the prescribed dofs.
+        let mut triplets = Vec::with_capacity(k.nnz());
+        let mut rhs: Vec<f64> = free.iter().map(|&i| f[i]).collect();
+        for (r, &i) in free.iter().enumerate() {
+            let (columns, values) = k.row(i);
+            for (&j, &v) in columns.iter().zip(values.iter()) {
+                if index[j] != usize::MAX {
+                    triplets.push((r, index[j], v));
+                } else {
+                    rhs[r] -= v * u[j];
+                }
+            }
+        }
+        let reduced = CsrMatrix::from_triplets(free.len(), free.len(), &triplets)?;
+        let solution = self.linear_solver.solve(&reduced, &rhs)?;
+        for (&dof, value) in free.iter().zip(solution) {
+            u[dof] = value;
+        }
+        let ku = k.multiply(&u);
+        let dofs = self.analysis.dofs_per_node();
+        let mut displacements = vec![[0.0; 3]; self.mesh.num_nodes()];
+        let mut reactions = vec![[0.0; 3]; self.mesh.num_nodes()];
+        for node in 0..self.mesh.num_nodes() {
+            for d in 0..dofs {
+                displacements[node][d] = u[node * dofs + d];
+                reactions[node][d] = ku[node * dofs + d] - f[node * dofs + d];
+            }
+        }
+        let (strains, stresses) = self.nodal_stresses(&displacements)?;
+        Ok(StaticSolution {
+            displacements,
+            reactions,
+            strains,
+            stresses,
+        })
+    }
+
+    // The lowest natural vibration modes. Unsupported structures have rigid
+    // body modes of zero frequency.
+    pub fn modes(&self, count: usize, lumped: bool) -> Result<ModalSolution, &'static str> {
+        if self.densities.iter().all(|&d| d == 0.0) {
+            return Err("Density is not set");
+        }
+        vibration_modes(
+            &self.stiffness()?,
+            &self.mass(lumped)?,
+            |dof| self.constraints.contains_key(&dof),
+            self.analysis.dofs_per_node(),
+            count,
+            self.linear_solver.ordering(),
+        )
+    }
+
+    // Linear buckling under the current loads, from the geometric stiffness
+    // of the static stresses. All loads, including prescribed displacements
+    // and temperatures, are scaled by the load factors.
+    pub fn buckling(&self, count: usize) -> Result<BucklingSolution, &'static str> {
+        let solution = self.solve()?;
+        buckling_modes(
+            &self.stiffness()?,
+            &self.geometric_stiffness(&solution.displacements)?,
+            |dof| self.constraints.contains_key(&dof),
+            self.analysis.dofs_per_node(),
+            count,
+            self.linear_solver.ordering(),
+        )
+    }
+
+    // Strain and stress at a natural point of an element.
+    pub fn strain_stress(
+        &self,
+        displacements: &[[f64; 3]],
+        element: usize,
+        xi: [f64; 3],
+    ) -> Result<([f64; 6], [f64; 6]), &'static str> {
+        let points = self.mesh.element_points(element);
+        let kin = self.kinematics(element, &points, xi)?;
+        let k = self.analysis.dofs_per_node();
+        let ue: Vec<f64> = self.mesh.elements[element]
+            .nodes
+            .iter()
+            .flat_map(|&n| displacements[n][..k].to_vec())
+            .collect();
+        let strain: Vec<f64> = kin
+            .b
+            .iter()
+            .map(|row| row.iter().zip(ue.iter()).map(|(b, u)| b * u).sum())
+            .collect();
+        let material = &self.materials[element];
+        let d = self.analysis.material_matrix(material);
+        let thermal = self.thermal(element, &kin.n);
+        let free = self.analysis.thermal_strain(material, thermal);
+        let stress: Vec<f64> = d
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(strain.iter().zip(free.iter()))
+                    .map(|(d, (e, e0))| d * (e - e0))
+                    .sum()
+            })
+            .collect();
+        Ok(self.analysis.expand(material, &strain, &stress, thermal))
+    }
+
+    // Stresses at the stiffness integration points of an element.
+    pub fn integration_point_stresses(
+        &self,
+        displacements: &[[f64; 3]],
+        element: usize,
+    ) -> Result<Vec<[f64; 6]>, &'static str> {
+        let element_type = self.mesh.elements[element].element_type;
+        QuadratureRule::new(element_type, stiffness_degree(element_type))
+            .points
+            .iter()
+            .map(|&xi| Ok(self.strain_stress(displacements, element, xi)?.1))
+            .collect()
+    }
+
+    fn nodal_stresses(&self, displacements: &[[f64; 3]]) -> Result<NodalFields, &'static str> {
+        let count = self.mesh.num_nodes();
+        let mut strains = vec![[0.0; 6]; count];
+        let mut stresses = vec![[0.0; 6]; count];
+        let mut shared = vec![0usize; count];
+        for (e, element) in self.mesh.elements.iter().enumerate() {
+            let coordinates = node_coordinates(element.element_type);
+            for (&node, &xi) in element.nodes.iter().zip(coordinates.iter()) {
+                let (strain, stress) = self.strain_stress(displacements, e, xi)?;
+                for c in 0..6 {
+                    strains[node][c] += strain[c];
+                    stresses[node][c] += stress[c];
+                }
+                shared[node] += 1;
+            }
+        }
+        for node in 0..count {
+            if shared[node] > 0 {
+                let m = shared[node] as f64;
+                strains[node] = strains[node].map(|v| v / m);
+                stresses[node] = stresses[node].map(|v| v / m);
+            }
+        }
+        Ok((strains, stresses))
+    }
+}
+
+fn centroid(points: &[Point3d]) -> [f64; 3] {
+    let m = points.len() as f64;
+    points.iter().fold([0.0; 3], |c, p| {
+        let (x, y, z) = p.get_coords();
+        [c[0] + x / m, c[1] + y / m, c[2] + z / m]
+    })
+}
+
+fn cross(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
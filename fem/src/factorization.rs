@@ -0,0 +1,187 @@
+use crate::ordering::Ordering;
+use crate::sparse::{CsrMatrix, inverse_permutation};
+
+// Sparse LDL^T factorization of a symmetric matrix, P A P^T = L D L^T with
+// unit lower triangular L. Uses the up-looking algorithm of Davis' LDL on the
+// elimination tree; only the lower triangle of A is read.
+#[derive(Debug, Clone)]
+pub struct SparseLdlt {
+    pub permutation: Vec<usize>,
+    inverse: Vec<usize>,
+    // Strictly lower part of L by columns.
+    column_offsets: Vec<usize>,
+    rows: Vec<usize>,
+    values: Vec<f64>,
+    pub diagonal: Vec<f64>,
+}
+
+impl SparseLdlt {
+    pub fn new(a: &CsrMatrix, ordering: Ordering) -> Result<Self, &'static str> {
+        let permutation = ordering.permutation(a)?;
+        SparseLdlt::with_permutation(a, permutation)
+    }
+
+    pub fn with_permutation(a: &CsrMatrix, permutation: Vec<usize>) -> Result<Self, &'static str> {
+        let n = a.nrows;
+        if a.ncols != n || permutation.len() != n {
+            return Err("Permutation size does not match the matrix");
+        }
+        let inverse = inverse_permutation(&permutation)?;
+
+        // Elimination tree and column counts. Row k of the permuted lower
+        // triangle is read from row permutation[k] of A.
+        let none = usize::MAX;
+        let mut parent = vec![none; n];
+        let mut flag = vec![none; n];
+        let mut counts = vec![0; n];
+        for k in 0..n {
+            flag[k] = k;
+            for &j in a.row(permutation[k]).0.iter() {
+                let mut i = inverse[j];
+                if i < k {
+                    while flag[i] != k {
+                        if parent[i] == none {
+                            parent[i] = k;
+                        }
+                        counts[i] += 1;
+                        flag[i] = k;
+                        i = parent[i];
+                    }
+                }
+            }
+        }
+        let mut column_offsets = vec![0; n + 1];
+        for k in 0..n {
+            column_offsets[k + 1] = column_offsets[k] + counts[k];
+        }
+        let nnz = column_offsets[n];
+        let mut rows = vec![0; nnz];
+        let mut values = vec![0.0; nnz];
+        let mut diagonal = vec![0.0; n];
+
+        // Numeric factorization, one row of L at a time.
+        let scale = a.diagonal().iter().fold(0.0f64, |m, d| m.max(d.abs()));
+        let mut y = vec![0.0; n];
+        let mut pattern = vec![0; n];
+        let mut filled = vec![0; n];
+        flag.fill(none);
+        for k in 0..n {
+            let mut top = n;
+            flag[k] = k;
+            let (columns, entries) = a.row(permutation[k]);
+            for (&j, &v) in columns.iter().zip(entries.iter()) {
+                let mut i = inverse[j];
+                if i > k {
+                    continue;
+                }
+                y[i] += v;
+                let mut len = 0;
+                while flag[i] != k {
+                    pattern[len] = i;
+                    len += 1;
+                    flag[i] = k;
+                    i = parent[i];
+                }
+                while len > 0 {
+                    top -= 1;
+                    len -= 1;
+                    pattern[top] = pattern[len];
+                }
+            }
+            diagonal[k] = y[k];
+            y[k] = 0.0;
+            for &i in pattern[top..n].iter() {
+                let yi = y[i];
+                y[i] = 0.0;
+                let start = column_offsets[i];
+                let end = start + filled[i];
+                for p in start..end {
+                    y[rows[p]] -= values[p] * yi;
+                }
+                let l = yi / diagonal[i];
+                diagonal[k] -= l * yi;
+                rows[end] = k;
+                values[end] = l;
+                filled[i] += 1;
+            }
+            if diagonal[k].abs() <= 1e-14 * scale || !diagonal[k].is_finite() {
+                return Err("Matrix is singular");
+            }
+        }
+        Ok(SparseLdlt {
+            permutation,
+            inverse,
+            column_offsets,
+            rows,
+            values,
+            diagonal,
+        })
+    }
+
+    // Factorization that also requires A to be positive definite, i.e. a
+    // Cholesky factorization with L sqrt(D) as the factor.
+    pub fn cholesky(a: &CsrMatrix, ordering: Ordering) -> Result<Self, &'static str> {
+        let factorization = SparseLdlt::new(a, ordering)?;
+        if !factorization.is_positive_definite() {
+            return Err("Matrix is not positive definite");
+        }
+        Ok(factorization)
+    }
+
+    pub fn size(&self) -> usize {
+        self.diagonal.len()
+    }
+
+    // Nonzeros strictly below the diagonal of L.
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_positive_definite(&self) -> bool {
+        self.diagonal.iter().all(|&d| d > 0.0)
+    }
+
+    // Numbers of positive and negative eigenvalues of A, by Sylvester's law
+    // of inertia.
+    pub fn inertia(&self) -> (usize, usize) {
+        let positive = self.diagonal.iter().filter(|&&d| d > 0.0).count();
+        (positive, self.size() - positive)
+    }
+
+    // Unit lower triangular factor in the permuted numbering.
+    pub fn lower(&self) -> CsrMatrix {
+        let mut triplets: Vec<(usize, usize, f64)> =
+            (0..self.size()).map(|k| (k, k, 1.0)).collect();
+        for j in 0..self.size() {
+            for p in self.column_offsets[j]..self.column_offsets[j + 1] {
+                triplets.push((self.rows[p], j, self.values[p]));
+            }
+        }
+        CsrMatrix::from_triplets_unchecked(self.size(), self.size(), &triplets)
+    }
+
+    pub fn solve(&self, b: &[f64]) -> Result<Vec<f64>, &'static str> {
+        let n = self.size();
+        if b.len() != n {
+            return Err("Matrix and right hand side sizes do not match");
+        }
+        let mut x: Vec<f64> = self.permutation.iter().map(|&p| b[p]).collect();
+        for j in 0..n {
+            let xj = x[j];
+            for p in self.column_offsets[j]..self.column_offsets[j + 1] {
+                x[self.rows[p]] -= self.values[p] * xj;
+            }
+        }
+        for (x, d) in x.iter_mut().zip(self.diagonal.iter()) {
+            *x /= d;
+        }
+        for j in (0..n).rev() {
+            let mut xj = x[j];
+            for p in self.column_offsets[j]..self.column_offsets[j + 1] {
+                xj -= self.values[p] * x[self.rows[p]];
+            }
+            x[j] = xj;
+        }
+        Ok(self.inverse.iter().map(|&k| x[k]).collect())
+    }
+}
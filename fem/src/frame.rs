@@ -0,0 +1,960 @@
+use crate::eigen::{BucklingSolution, ModalSolution, buckling_modes, vibration_modes};
+use crate::material::IsotropicElastic;
+use crate::quadrature::gauss_legendre;
+use crate::solver::{LinearSolver, dense_solve};
+use crate::sparse::{CooMatrix, CsrMatrix};
+use geom::{CoordinateSystem3d, Direction3d, Point3d};
+use std::collections::BTreeMap;
+
+// Frame dofs are ux, uy, uz, rx, ry, rz at every node. Member quantities
+// are in the local frame: x along the member from its first node, y and z
+// the principal axes of the section.
+
+// Section properties about the local principal axes. Shear areas enter
+// Timoshenko members only.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Section {
+    pub area: f64,
+    // Second moments for bending about local y (deflection along z) and
+    // local z (deflection along y).
+    pub iy: f64,
+    pub iz: f64,
+    // Saint-Venant torsion constant.
+    pub torsion: f64,
+    pub shear_area_y: f64,
+    pub shear_area_z: f64,
+}
+
+impl Section {
+    pub fn new(
+        area: f64,
+        iy: f64,
+        iz: f64,
+        torsion: f64,
+        shear_area_y: f64,
+        shear_area_z: f64,
+    ) -> Result<Self, &'static str> {
+        if [area, iy, iz, torsion, shear_area_y, shear_area_z]
+            .iter()
+            .any(|v| v.is_nan() || *v <= 0.0)
+        {
+            return Err("Section properties must be positive");
+        }
+        Ok(Section {
+            area,
+            iy,
+            iz,
+            torsion,
+            shear_area_y,
+            shear_area_z,
+        })
+    }
+
+    // Solid rectangle of width b along local y and depth h along local z.
+    pub fn rectangle(b: f64, h: f64) -> Result<Self, &'static str> {
+        let (long, short) = if b > h { (b, h) } else { (h, b) };
+        let ratio = short / long;
+        let torsion =
+            long * short.powi(3) * (1.0 / 3.0 - 0.21 * ratio * (1.0 - ratio.powi(4) / 12.0));
+        let area = b * h;
+        Section::new(
+            area,
+            b * h.powi(3) / 12.0,
+            h * b.powi(3) / 12.0,
+            torsion,
+            area * 5.0 / 6.0,
+            area * 5.0 / 6.0,
+        )
+    }
+
+    pub fn circle(radius: f64) -> Result<Self, &'static str> {
+        let area = std::f64::consts::PI * radius * radius;
+        let i = area * radius * radius / 4.0;
+        Section::new(area, i, i, 2.0 * i, 0.9 * area, 0.9 * area)
+    }
+
+    pub fn tube(radius: f64, thickness: f64) -> Result<Self, &'static str> {
+        if thickness <= 0.0 || thickness > radius {
+            return Err("Tube thickness must lie in (0, radius]");
+        }
+        let inner = radius - thickness;
+        let area = std::f64::consts::PI * (radius * radius - inner * inner);
+        let i = std::f64::consts::PI * (radius.powi(4) - inner.powi(4)) / 4.0;
+        Section::new(area, i, i, 2.0 * i, 0.5 * area, 0.5 * area)
+    }
+
+    // Doubly symmetric I section of depth h along local z and flange width
+    // b, bending about local y as the strong axis. Thin walled torsion
+    // constant; the web carries the shear along z.
+    pub fn i_section(h: f64, b: f64, web: f64, flange: f64) -> Result<Self, &'static str> {
+        if web <= 0.0 || flange <= 0.0 || 2.0 * flange >= h || web >= b {
+            return Err("Invalid I section dimensions");
+        }
+        let inner = h - 2.0 * flange;
+        Section::new(
+            2.0 * b * flange + inner * web,
+            (b * h.powi(3) - (b - web) * inner.powi(3)) / 12.0,
+            (2.0 * flange * b.powi(3) + inner * web.powi(3)) / 12.0,
+            (2.0 * b * flange.powi(3) + (h - flange) * web.powi(3)) / 3.0,
+            2.0 * b * flange * 5.0 / 6.0,
+            h * web,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberKind {
+    // Axial force only, pinned at both ends.
+    Truss,
+    EulerBernoulli,
+    // Includes shear deformation through the section shear areas.
+    Timoshenko,
+}
+
+// Load per unit length varying linearly from the first to the second node.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistributedLoad {
+    pub start: [f64; 3],
+    pub end: [f64; 3],
+    // Components in local member axes rather than global axes.
+    pub local: bool,
+}
+
+impl DistributedLoad {
+    pub fn uniform(load: [f64; 3], local: bool) -> Self {
+        DistributedLoad {
+            start: load,
+            end: load,
+            local,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub nodes: [usize; 2],
+    pub kind: MemberKind,
+    pub section: Section,
+    pub material: IsotropicElastic,
+    // Orientation of the section: the local y axis follows the vxdir of the
+    // coordinate system, made normal to the member. Without one, local y is
+    // horizontal (global z cross the member axis), or global y for
+    // vertical members.
+    pub orientation: Option<CoordinateSystem3d>,
+    // Released local dofs (ux, uy, uz, rx, ry, rz) at each end.
+    pub releases: [[bool; 6]; 2],
+    pub loads: Vec<DistributedLoad>,
+    // Mass per unit volume for vibration analysis.
+    pub density: f64,
+}
+
+impl Member {
+    pub fn new(
+        nodes: [usize; 2],
+        kind: MemberKind,
+        section: Section,
+        material: IsotropicElastic,
+    ) -> Self {
+        Member {
+            nodes,
+            kind,
+            section,
+            material,
+            orientation: None,
+            releases: [[false; 6]; 2],
+            loads: Vec::new(),
+            density: 0.0,
+        }
+    }
+
+    // Hinge at one end: both bending moments released.
+    pub fn release_moments(&mut self, end: usize) {
+        self.releases[end][4] = true;
+        self.releases[end][5] = true;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FrameSolution {
+    pub displacements: Vec<[f64; 6]>,
+    pub reactions: Vec<[f64; 6]>,
+    // Forces and moments the nodes apply to each member end, in local axes.
+    pub end_forces: Vec<[[f64; 6]; 2]>,
+}
+
+// Internal forces at a station along a member, acting on the face whose
+// outward normal is local +x: axial force (tension positive), shears Vy
+// and Vz, torque, and bending moments My and Mz with the right hand rule,
+// so that the fibre stress is N/A + My z/Iy - Mz y/Iz.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InternalForces {
+    pub position: f64,
+    pub forces: [f64; 6],
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Frame {
+    pub nodes: Vec<Point3d>,
+    pub members: Vec<Member>,
+    // Restrict to the xy plane: uz, rx and ry are fixed at every node.
+    pub planar: bool,
+    constraints: BTreeMap<usize, f64>,
+    loads: Vec<f64>,
+    linear_solver: LinearSolver,
+}
+
+impl Frame {
+    pub fn new(nodes: Vec<Point3d>) -> Self {
+        let loads = vec![0.0; 6 * nodes.len()];
+        Frame {
+            nodes,
+            loads,
+            ..Default::default()
+        }
+    }
+
+    pub fn planar(nodes: Vec<Point3d>) -> Self {
+        Frame {
+            planar: true,
+            ..Frame::new(nodes)
+        }
+    }
+
+    pub fn add_node(&mut self, point: Point3d) -> usize {
+        self.nodes.push(point);
+        self.loads.extend([0.0; 6]);
+        self.nodes.len() - 1
+    }
+
+    pub fn add_member(&mut self, member: Member) -> Result<usize, &'static str> {
+        let [a, b] = member.nodes;
+        if a >= self.nodes.len() || b >= self.nodes.len() {
+            return Err("Node index out of range");
+        }
+        if distance(&self.nodes[a], &self.nodes[b]) <= 0.0 {
+            return Err("Member has zero length");
+        }
+        self.members.push(member);
+        let index = self.members.len() - 1;
+        if let Err(e) = self.local_frame(index) {
+            self.members.pop();
+            return Err(e);
+        }
+        Ok(index)
+    }
+
+    pub fn set_linear_solver(&mut self, linear_solver: LinearSolver) {
+        self.linear_solver = linear_solver;
+    }
+
+    fn dof(&self, node: usize, dof: usize) -> Result<usize, &'static str> {
+        if node >= self.nodes.len() || dof >= 6 {
+            return Err("Node or dof out of range");
+        }
+        Ok(6 * node + dof)
+    }
+
+    // Prescribed displacement or rotation of a node dof.
+    pub fn fix(&mut self, node: usize, dof: usize, value: f64) -> Result<(), &'static str> {
+        let dof = self.dof(node, dof)?;
+        self.constraints.insert(dof, value);
+        Ok(())
+    }
+
+    // Fixes the translations of a node.
+    pub fn pin(&mut self, node: usize) -> Result<(), &'static str> {
+        (0..3).try_for_each(|d| self.fix(node, d, 0.0))
+    }
+
+    // Fixes all dofs of a node.
+    pub fn clamp(&mut self, node: usize) -> Result<(), &'static str> {
+        (0..6).try_for_each(|d| self.fix(node, d, 0.0))
+    }
+
+    // Nodal force or moment in global axes.
+    pub fn add_load(&mut self, node: usize, dof: usize, value: f64) -> Result<(), &'static str> {
+        let dof = self.dof(node, dof)?;
+        self.loads[dof] += value;
+        Ok(())
+    }
+
+    pub fn add_distributed_load(
+        &mut self,
+        member: usize,
+        load: DistributedLoad,
+    ) -> Result<(), &'static str> {
+        self.members
+            .get_mut(member)
+            .ok_or("Member index out of range")?
+            .loads
+            .push(load);
+        Ok(())
+    }
+
+    pub fn length(&self, member: usize) -> f64 {
+        let [a, b] = self.members[member].nodes;
+        distance(&self.nodes[a], &self.nodes[b])
+    }
+
+    // Local frame of a member with origin at its first node, main direction
+    // along the member, vxdir the local y and vydir the local z axis.
+    pub fn local_frame(&self, member: usize) -> Result<CoordinateSystem3d, &'static str> {
+        let m = self
+            .members
+            .get(member)
+            .ok_or("Member index out of range")?;
+        let (a, b) = (self.nodes[m.nodes[0]], self.nodes[m.nodes[1]]);
+        let (dx, dy, dz) = (
+            b.get_x() - a.get_x(),
+            b.get_y() - a.get_y(),
+            b.get_z() - a.get_z(),
+        );
+        let length = (dx * dx + dy * dy + dz * dz).sqrt();
+        let axis = [dx / length, dy / length, dz / length];
+        let reference = match m.orientation {
+            Some(cs) => cs.vxdir.get_coords(),
+            None if axis[0].hypot(axis[1]) > 1e-9 => (-axis[1], axis[0], 0.0),
+            None => (0.0, 1.0, 0.0),
+        };
+        let along = reference.0 * axis[0] + reference.1 * axis[1] + reference.2 * axis[2];
+        let normal = (
+            reference.0 - along * axis[0],
+            reference.1 - along * axis[1],
+            reference.2 - along * axis[2],
+        );
+        if (normal.0 * normal.0 + normal.1 * normal.1 + normal.2 * normal.2).sqrt() < 1e-9 {
+            return Err("Member orientation is parallel to the member");
+        }
+        Ok(CoordinateSystem3d::from_location_direction_xdirection(
+            a,
+            Direction3d::from_coords(axis[0], axis[1], axis[2]),
+            Direction3d::from_coords(normal.0, normal.1, normal.2),
+        ))
+    }
+
+    // Rows are the local x, y and z axes in global coordinates.
+    fn rotation(&self, member: usize) -> Result<[[f64; 3]; 3], &'static str> {
+        let cs = self.local_frame(member)?;
+        let row = |d: &Direction3d| {
+            let (x, y, z) = d.get_coords();
+            [x, y, z]
+        };
+        Ok([row(&cs.axis.direction), row(&cs.vxdir), row(&cs.vydir)])
+    }
+
+    // Local stiffness and equivalent nodal loads before releases.
+    fn local_matrices(&self, member: usize) -> Result<(Vec<Vec<f64>>, Vec<f64>), &'static str> {
+        let m = &self.members[member];
+        let l = self.length(member);
+        let s = &m.section;
+        let e = m.material.young;
+        let g = e / (2.0 * (1.0 + m.material.poisson));
+        let mut k = vec![vec![0.0; 12]; 12];
+        let mut add = |dofs: [usize; 2], value: f64| {
+            k[dofs[0]][dofs[1]] += value;
+        };
+        let axial = e * s.area / l;
+        for (i, j, sign) in [(0, 0, 1.0), (6, 6, 1.0), (0, 6, -1.0), (6, 0, -1.0)] {
+            add([i, j], sign * axial);
+        }
+        let (phi_y, phi_z) = match m.kind {
+            MemberKind::Timoshenko => (
+                12.0 * e * s.iz / (g * s.shear_area_y * l * l),
+                12.0 * e * s.iy / (g * s.shear_area_z * l * l),
+            ),
+            _ => (0.0, 0.0),
+        };
+        if m.kind != MemberKind::Truss {
+            let torsion = g * s.torsion / l;
+            for (i, j, sign) in [(3, 3, 1.0), (9, 9, 1.0), (3, 9, -1.0), (9, 3, -1.0)] {
+                add([i, j], sign * torsion);
+            }
+            // Bending in the xy plane on (uy, rz) and in the xz plane on
+            // (uz, ry), where ry = -duz/dx flips the coupling terms.
+            for (dofs, inertia, phi, sign) in [
+                ([1, 5, 7, 11], s.iz, phi_y, 1.0),
+                ([2, 4, 8, 10], s.iy, phi_z, -1.0),
+            ] {
+                let c = e * inertia / ((1.0 + phi) * l.powi(3));
+                let block = [
+                    [12.0, 6.0 * l * sign, -12.0, 6.0 * l * sign],
+                    [
+                        6.0 * l * sign,
+                        (4.0 + phi) * l * l,
+                        -6.0 * l * sign,
+                        (2.0 - phi) * l * l,
+                    ],
+                    [-12.0, -6.0 * l * sign, 12.0, -6.0 * l * sign],
+                    [
+                        6.0 * l * sign,
+                        (2.0 - phi) * l * l,
+                        -6.0 * l * sign,
+                        (4.0 + phi) * l * l,
+                    ],
+                ];
+                for (a, row) in block.iter().enumerate() {
+                    for (b, v) in row.iter().enumerate() {
+                        add([dofs[a], dofs[b]], c * v);
+                    }
+                }
+            }
+        }
+
+        // Equivalent nodal loads from the interdependent interpolation,
+        // which reproduces the fixed end forces of Timoshenko beams.
+        let mut f = vec![0.0; 12];
+        let rotation = self.rotation(member)?;
+        let (points, weights) = gauss_legendre(3);
+        for load in m.loads.iter() {
+            let (q1, q2) = if load.local {
+                (load.start, load.end)
+            } else {
+                (
+                    multiply(&rotation, &load.start),
+                    multiply(&rotation, &load.end),
+                )
+            };
+            for (x, w) in points.iter().zip(weights.iter()) {
+                let xi = 0.5 * (x + 1.0);
+                let weight = 0.5 * w * l;
+                let q: Vec<f64> = (0..3).map(|c| q1[c] + (q2[c] - q1[c]) * xi).collect();
+                f[0] += weight * q[0] * (1.0 - xi);
+                f[6] += weight * q[0] * xi;
+                if m.kind == MemberKind::Truss {
+                    for c in 1..3 {
+                        f[c] += weight * q[c] * (1.0 - xi);
+                        f[6 + c] += weight * q[c] * xi;
+                    }
+                    continue;
+                }
+                for (dofs, phi, sign, c) in [
+                    ([1, 5, 7, 11], phi_y, 1.0, 1),
+                    ([2, 4, 8, 10], phi_z, -1.0, 2),
+                ] {
+                    let n = hermite(xi, l, phi);
+                    for a in 0..4 {
+                        let s = if a % 2 == 1 { sign } else { 1.0 };
+                        f[dofs[a]] += weight * q[c] * s * n[a];
+                    }
+                }
+            }
+        }
+        Ok((k, f))
+    }
+
+    // Local stiffness and equivalent nodal loads with the released dofs
+    // condensed out.
+    fn condensed_matrices(&self, member: usize) -> Result<(Vec<Vec<f64>>, Vec<f64>), &'static str> {
+        let (mut k, mut f) = self.local_matrices(member)?;
+        let m = &self.members[member];
+        if m.kind == MemberKind::Truss {
+            return Ok((k, f));
+        }
+        let released: Vec<usize> = (0..12).filter(|&i| m.releases[i / 6][i % 6]).collect();
+        if released.is_empty() {
+            return Ok((k, f));
+        }
+        let krr: Vec<Vec<f64>> = released
+            .iter()
+            .map(|&i| released.iter().map(|&j| k[i][j]).collect())
+            .collect();
+        // Columns: K_rc and f_r.
+        let rhs: Vec<Vec<f64>> = released
+            .iter()
+            .map(|&i| k[i].iter().copied().chain([f[i]]).collect())
+            .collect();
+        let x = dense_solve(&krr, &rhs).map_err(|_| "Member releases make the member unstable")?;
+        let original = k.clone();
+        for i in 0..12 {
+            for (r, &ri) in released.iter().enumerate() {
+                let kir = original[i][ri];
+                if kir == 0.0 {
+                    continue;
+                }
+                for j in 0..12 {
+                    k[i][j] -= kir * x[r][j];
+                }
+                f[i] -= kir * x[r][12];
+            }
+        }
+        for &r in released.iter() {
+            k[r].fill(0.0);
+            for row in k.iter_mut() {
+                row[r] = 0.0;
+            }
+            f[r] = 0.0;
+        }
+        Ok((k, f))
+    }
+
+    fn transformation(&self, member: usize) -> Result<Vec<Vec<f64>>, &'static str> {
+        let r = self.rotation(member)?;
+        let mut t = vec![vec![0.0; 12]; 12];
+        for block in 0..4 {
+            for i in 0..3 {
+                for j in 0..3 {
+                    t[3 * block + i][3 * block + j] = r[i][j];
+                }
+            }
+        }
+        Ok(t)
+    }
+
+    fn member_dofs(&self, member: usize) -> Vec<usize> {
+        let [a, b] = self.members[member].nodes;
+        (0..6)
+            .map(|d| 6 * a + d)
+            .chain((0..6).map(|d| 6 * b + d))
+            .collect()
+    }
+
+    // T^T K T of a local member matrix.
+    fn to_global(&self, member: usize, k: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, &'static str> {
+        let t = self.transformation(member)?;
+        Ok(congruence(&t, k))
+    }
+
+    // Global stiffness matrix and equivalent load vector of one member.
+    pub fn member_matrices(
+        &self,
+        member: usize,
+    ) -> Result<(Vec<Vec<f64>>, Vec<f64>), &'static str> {
+        let (k, f) = self.condensed_matrices(member)?;
+        let t = self.transformation(member)?;
+        // T^T f
+        let load = (0..12)
+            .map(|i| (0..12).map(|a| t[a][i] * f[a]).sum())
+            .collect();
+        Ok((congruence(&t, &k), load))
+    }
+
+    // Local dofs in terms of the unreleased ones, eliminating the released
+    // dofs as in the condensed stiffness. None without releases.
+    fn release_transformation(&self, member: usize) -> Result<Option<Vec<Vec<f64>>>, &'static str> {
+        let m = &self.members[member];
+        let released: Vec<usize> = (0..12).filter(|&i| m.releases[i / 6][i % 6]).collect();
+        if m.kind == MemberKind::Truss || released.is_empty() {
+            return Ok(None);
+        }
+        let k = self.local_matrices(member)?.0;
+        let krr: Vec<Vec<f64>> = released
+            .iter()
+            .map(|&i| released.iter().map(|&j| k[i][j]).collect())
+            .collect();
+        let rhs: Vec<Vec<f64>> = released.iter().map(|&i| k[i].clone()).collect();
+        let x = dense_solve(&krr, &rhs).map_err(|_| "Member releases make the member unstable")?;
+        let mut t: Vec<Vec<f64>> = (0..12)
+            .map(|i| (0..12).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+            .collect();
+        for (r, &i) in released.iter().enumerate() {
+            for j in 0..12 {
+                t[i][j] = if released.contains(&j) { 0.0 } else { -x[r][j] };
+            }
+        }
+        Ok(Some(t))
+    }
+
+    // Local mass matrix: linear axial and torsional and cubic bending
+    // interpolation, without rotary inertia, or half the member mass at
+    // each end when lumped.
+    fn local_mass(&self, member: usize, lumped: bool) -> Vec<Vec<f64>> {
+        let m = &self.members[member];
+        let l = self.length(member);
+        let s = &m.section;
+        let mass = m.density * s.area * l;
+        let mut k = vec![vec![0.0; 12]; 12];
+        if lumped {
+            for i in [0, 1, 2, 6, 7, 8] {
+                k[i][i] = mass / 2.0;
+            }
+            return k;
+        }
+        let mut linear = |i: usize, j: usize, total: f64| {
+            k[i][i] += total / 3.0;
+            k[j][j] += total / 3.0;
+            k[i][j] += total / 6.0;
+            k[j][i] += total / 6.0;
+        };
+        linear(0, 6, mass);
+        if m.kind == MemberKind::Truss {
+            linear(1, 7, mass);
+            linear(2, 8, mass);
+            return k;
+        }
+        linear(3, 9, m.density * (s.iy + s.iz) * l);
+        for (dofs, sign) in [([1, 5, 7, 11], 1.0), ([2, 4, 8, 10], -1.0)] {
+            let block = [
+                [156.0, 22.0 * l * sign, 54.0, -13.0 * l * sign],
+                [22.0 * l * sign, 4.0 * l * l, 13.0 * l * sign, -3.0 * l * l],
+                [54.0, 13.0 * l * sign, 156.0, -22.0 * l * sign],
+                [
+                    -13.0 * l * sign,
+                    -3.0 * l * l,
+                    -22.0 * l * sign,
+                    4.0 * l * l,
+                ],
+            ];
+            for (a, row) in block.iter().enumerate() {
+                for (b, v) in row.iter().enumerate() {
+                    k[dofs[a]][dofs[b]] += mass / 420.0 * v;
+                }
+            }
+        }
+        k
+    }
+
+    // Local geometric stiffness of an axial force, tension positive, with
+    // cubic bending interpolation also for Timoshenko members.
+    fn local_geometric_stiffness(&self, member: usize, axial: f64) -> Vec<Vec<f64>> {
+        let m = &self.members[member];
+        let l = self.length(member);
+        let mut k = vec![vec![0.0; 12]; 12];
+        let mut linear = |i: usize, j: usize, value: f64| {
+            k[i][i] += value;
+            k[j][j] += value;
+            k[i][j] -= value;
+            k[j][i] -= value;
+        };
+        if m.kind == MemberKind::Truss {
+            linear(1, 7, axial / l);
+            linear(2, 8, axial / l);
+            return k;
+        }
+        let s = &m.section;
+        linear(3, 9, axial * (s.iy + s.iz) / (s.area * l));
+        for (dofs, sign) in [([1, 5, 7, 11], 1.0), ([2, 4, 8, 10], -1.0)] {
+            let block = [
+                [36.0, 3.0 * l * sign, -36.0, 3.0 * l * sign],
+                [3.0 * l * sign, 4.0 * l * l, -3.0 * l * sign, -l * l],
+                [-36.0, -3.0 * l * sign, 36.0, -3.0 * l * sign],
+                [3.0 * l * sign, -l * l, -3.0 * l * sign, 4.0 * l * l],
+            ];
+            for (a, row) in block.iter().enumerate() {
+                for (b, v) in row.iter().enumerate() {
+                    k[dofs[a]][dofs[b]] += axial / (30.0 * l) * v;
+                }
+            }
+        }
+        k
+    }
+
+    // Condenses the released dofs out of a local matrix, then rotates it to
+    // global axes.
+    fn condensed_global(
+        &self,
+        member: usize,
+        local: Vec<Vec<f64>>,
+    ) -> Result<Vec<Vec<f64>>, &'static str> {
+        let local = match self.release_transformation(member)? {
+            Some(t) => congruence(&t, &local),
+            None => local,
+        };
+        self.to_global(member, &local)
+    }
+
+    // Global mass matrix of one member.
+    pub fn member_mass(&self, member: usize, lumped: bool) -> Result<Vec<Vec<f64>>, &'static str> {
+        let local = self.local_mass(member, lumped);
+        if lumped {
+            return self.to_global(member, &local);
+        }
+        self.condensed_global(member, local)
+    }
+
+    // Global geometric stiffness matrix of one member under an axial force.
+    pub fn member_geometric_stiffness(
+        &self,
+        member: usize,
+        axial: f64,
+    ) -> Result<Vec<Vec<f64>>, &'static str> {
+        let local = self.local_geometric_stiffness(member, axial);
+        self.condensed_global(member, local)
+    }
+
+    pub fn stiffness(&self) -> Result<CsrMatrix, &'static str> {
+        let n = 6 * self.nodes.len();
+        let mut k = CooMatrix::new(n, n);
+        for member in 0..self.members.len() {
+            let dofs = self.member_dofs(member);
+            k.push_block(&dofs, &dofs, &self.member_matrices(member)?.0)?;
+        }
+        Ok(k.to_csr())
+    }
+
+    pub fn load_vector(&self) -> Result<Vec<f64>, &'static str> {
+        let mut f = self.loads.clone();
+        for member in 0..self.members.len() {
+            let load = self.member_matrices(member)?.1;
+            for (&dof, v) in self.member_dofs(member).iter().zip(load) {
+                f[dof] += v;
+            }
+        }
+        Ok(f)
+    }
+
+    pub fn mass(&self, lumped: bool) -> Result<CsrMatrix, &'static str> {
+        let n = 6 * self.nodes.len();
+        let mut m = CooMatrix::new(n, n);
+        for member in 0..self.members.len() {
+            let dofs = self.member_dofs(member);
+            m.push_block(&dofs, &dofs, &self.member_mass(member, lumped)?)?;
+        }
+        Ok(m.to_csr())
+    }
+
+    // Geometric stiffness of the member axial forces of a solution, taken
+    // as the mean of the two end values.
+    pub fn geometric_stiffness(&self, solution: &FrameSolution) -> Result<CsrMatrix, &'static str> {
+        if solution.end_forces.len() != self.members.len() {
+            return Err("Solution does not match the frame");
+        }
+        let n = 6 * self.nodes.len();
+        let mut kg = CooMatrix::new(n, n);
+        for (member, ends) in solution.end_forces.iter().enumerate() {
+            let axial = 0.5 * (ends[1][0] - ends[0][0]);
+            let dofs = self.member_dofs(member);
+            kg.push_block(
+                &dofs,
+                &dofs,
+                &self.member_geometric_stiffness(member, axial)?,
+            )?;
+        }
+        Ok(kg.to_csr())
+    }
+
+    // Prescribed dofs, including those fixed by a planar frame and dofs
+    // without stiffness or load.
+    fn all_constraints(&self, k: &CsrMatrix, f: &[f64]) -> BTreeMap<usize, f64> {
+        let mut constraints = self.constraints.clone();
+        if self.planar {
+            for node in 0..self.nodes.len() {
+                for d in [2, 3, 4] {
+                    constraints.entry(6 * node + d).or_insert(0.0);
+                }
+            }
+        }
+        // Dofs without stiffness, such as rotations at nodes joined only by
+        // trusses, are held in place.
+        let diagonal = k.diagonal();
+        let scale = diagonal.iter().fold(0.0f64, |m, d| m.max(d.abs()));
+        for (dof, d) in diagonal.iter().enumerate() {
+            if d.abs() <= 1e-12 * scale && f[dof] == 0.0 {
+                constraints.entry(dof).or_insert(0.0);
+            }
+        }
+        constraints
+    }
+
+    pub fn solve(&self) -> Result<FrameSolution, &'static str> {
+        let k = self.stiffness()?;
+        let f = self.load_vector()?;
+        let n = k.nrows;
+        let constraints = self.all_constraints(&k, &f);
+        let mut u = vec![0.0; n];
+        let mut index = vec![usize::MAX; n];
+        let mut free = Vec::new();
+        for dof in 0..n {
+            match constraints.get(&dof) {
+                Some(&value) => u[dof] = value,
+                None => {
+                    index[dof] = free.len();
+                    free.push(dof);
+                }
+            }
+        }
+        let mut reduced = CooMatrix::new(free.len(), free.len());
+        let mut rhs: Vec<f64> = free.iter().map(|&i| f[i]).collect();
+        for (r, &i) in free.iter().enumerate() {
+            let (columns, values) = k.row(i);
+            for (&j, &v) in columns.iter().zip(values.iter()) {
+                if index[j] != usize::MAX {
+                    reduced.push(r, index[j], v)?;
+                } else {
+                    rhs[r] -= v * u[j];
+                }
+            }
+        }
+        let solution = self
+            .linear_solver
+            .solve(&reduced.to_csr(), &rhs)
+            .map_err(|e| {
+                if e == "Matrix is singular" {
+                    "Frame is unstable"
+                } else {
+                    e
+                }
+            })?;
+        for (&dof, value) in free.iter().zip(solution) {
+            u[dof] = value;
+        }
+        let ku = k.multiply(&u);
+        let displacements: Vec<[f64; 6]> = u.chunks(6).map(|c| c.try_into().unwrap()).collect();
+        let reactions = (0..self.nodes.len())
+            .map(|node| std::array::from_fn(|d| ku[6 * node + d] - f[6 * node + d]))
+            .collect();
+        let end_forces = (0..self.members.len())
+            .map(|m| self.end_forces(m, &u))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(FrameSolution {
+            displacements,
+            reactions,
+            end_forces,
+        })
+    }
+
+    // The lowest natural vibration modes. Unsupported frames have rigid
+    // body modes of zero frequency.
+    pub fn modes(&self, count: usize, lumped: bool) -> Result<ModalSolution<6>, &'static str> {
+        if self.members.iter().all(|m| m.density == 0.0) {
+            return Err("Density is not set");
+        }
+        let k = self.stiffness()?;
+        let constraints = self.all_constraints(&k, &vec![0.0; k.nrows]);
+        vibration_modes(
+            &k,
+            &self.mass(lumped)?,
+            |dof| constraints.contains_key(&dof),
+            6,
+            count,
+            self.linear_solver.ordering(),
+        )
+    }
+
+    // Linear buckling under multiples of the current loads, from the
+    // geometric stiffness of the member axial forces.
+    pub fn buckling(&self, count: usize) -> Result<BucklingSolution<6>, &'static str> {
+        let solution = self.solve()?;
+        let k = self.stiffness()?;
+        let constraints = self.all_constraints(&k, &self.load_vector()?);
+        buckling_modes(
+            &k,
+            &self.geometric_stiffness(&solution)?,
+            |dof| constraints.contains_key(&dof),
+            6,
+            count,
+            self.linear_solver.ordering(),
+        )
+        .map_err(|e| {
+            if e == "Stiffness matrix is not positive definite" {
+                "Frame is unstable"
+            } else {
+                e
+            }
+        })
+    }
+
+    fn end_forces(&self, member: usize, u: &[f64]) -> Result<[[f64; 6]; 2], &'static str> {
+        let (k, f) = self.condensed_matrices(member)?;
+        let t = self.transformation(member)?;
+        let global: Vec<f64> = self.member_dofs(member).iter().map(|&d| u[d]).collect();
+        let local: Vec<f64> = t
+            .iter()
+            .map(|row| row.iter().zip(global.iter()).map(|(t, u)| t * u).sum())
+            .collect();
+        let mut forces = [[0.0; 6]; 2];
+        for i in 0..12 {
+            let ku: f64 = k[i].iter().zip(local.iter()).map(|(k, u)| k * u).sum();
+            forces[i / 6][i % 6] = ku - f[i];
+        }
+        Ok(forces)
+    }
+
+    // Internal force diagram of a member at equally spaced stations,
+    // including both ends.
+    pub fn internal_forces(
+        &self,
+        solution: &FrameSolution,
+        member: usize,
+        stations: usize,
+    ) -> Result<Vec<InternalForces>, &'static str> {
+        if member >= self.members.len() || stations < 2 {
+            return Err("Member index out of range or too few stations");
+        }
+        let l = self.length(member);
+        let rotation = self.rotation(member)?;
+        let start = solution.end_forces[member][0];
+        let loads: Vec<([f64; 3], [f64; 3])> = self.members[member]
+            .loads
+            .iter()
+            .map(|load| {
+                if load.local {
+                    (load.start, load.end)
+                } else {
+                    (
+                        multiply(&rotation, &load.start),
+                        multiply(&rotation, &load.end),
+                    )
+                }
+            })
+            .collect();
+        Ok((0..stations)
+            .map(|s| {
+                let x = l * s as f64 / (stations - 1) as f64;
+                // Resultant of the loads on [0, x] and its moment about x.
+                let mut force = [start[0], start[1], start[2]];
+                let mut moment = [start[3], start[4] + x * start[2], start[5] - x * start[1]];
+                for (q1, q2) in loads.iter() {
+                    for c in 0..3 {
+                        let dq = q2[c] - q1[c];
+                        force[c] += q1[c] * x + dq * x * x / (2.0 * l);
+                        let arm = -q1[c] * x * x / 2.0 - dq * x.powi(3) / (6.0 * l);
+                        match c {
+                            1 => moment[2] += arm,
+                            2 => moment[1] -= arm,
+                            _ => {}
+                        }
+                    }
+                }
+                InternalForces {
+                    position: x,
+                    forces: [
+                        -force[0], -force[1], -force[2], -moment[0], -moment[1], -moment[2],
+                    ],
+                }
+            })
+            .collect())
+    }
+}
+
+// T^T K T for square matrices.
+fn congruence(t: &[Vec<f64>], k: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let n = t.len();
+    let kt: Vec<Vec<f64>> = k
+        .iter()
+        .map(|row| {
+            (0..n)
+                .map(|j| (0..n).map(|a| row[a] * t[a][j]).sum())
+                .collect()
+        })
+        .collect();
+    (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| (0..n).map(|a| t[a][i] * kt[a][j]).sum())
+                .collect()
+        })
+        .collect()
+}
+
+fn distance(a: &Point3d, b: &Point3d) -> f64 {
+    let (ax, ay, az) = a.get_coords();
+    let (bx, by, bz) = b.get_coords();
+    ((bx - ax).powi(2) + (by - ay).powi(2) + (bz - az).powi(2)).sqrt()
+}
+
+fn multiply(r: &[[f64; 3]; 3], v: &[f64; 3]) -> [f64; 3] {
+    std::array::from_fn(|i| r[i][0] * v[0] + r[i][1] * v[1] + r[i][2] * v[2])
+}
+
+// Displacement and rotation weights of a beam under transverse load, exact
+// for Timoshenko beams with shear parameter phi.
+fn hermite(xi: f64, l: f64, phi: f64) -> [f64; 4] {
+    let c = 1.0 / (1.0 + phi);
+    let (x2, x3) = (xi * xi, xi * xi * xi);
+    [
+        c * (1.0 - 3.0 * x2 + 2.0 * x3 + phi * (1.0 - xi)),
+        c * l * (xi - 2.0 * x2 + x3 + 0.5 * phi * (xi - x2)),
+        c * (3.0 * x2 - 2.0 * x3 + phi * xi),
+        c * l * (-x2 + x3 - 0.5 * phi * (xi - x2)),
+    ]
+}
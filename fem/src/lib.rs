@@ -1,14 +1,35 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+pub mod dynamics;
+pub mod eigen;
+pub mod elasticity;
+pub mod factorization;
+pub mod frame;
+pub mod material;
+pub mod mesh;
+pub mod nonlinear;
+pub mod ordering;
+pub mod quadrature;
+pub mod shape;
+pub mod shell;
+pub mod solver;
+pub mod sparse;
+pub mod thermal;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use dynamics::{DynamicSolution, Integrator, LoadCurve, NodeHistory, StructuralDynamics};
+pub use eigen::{BucklingSolution, Eigenpairs, ModalSolution};
+pub use elasticity::{Analysis, LinearStatic, StaticSolution};
+pub use factorization::SparseLdlt;
+pub use frame::{
+    DistributedLoad, Frame, FrameSolution, InternalForces, Member, MemberKind, Section,
+};
+pub use material::{Hyperelastic, IsotropicElastic, J2Plasticity, MaterialModel, PlasticState};
+pub use mesh::{Element, ElementType, Facet, Mesh};
+pub use nonlinear::{Formulation, NonlinearSolution, NonlinearStatic, PathControl};
+pub use ordering::Ordering;
+pub use quadrature::QuadratureRule;
+pub use shape::Jacobian;
+pub use shell::{Lamina, Layer, Layup, Shell, ShellSolution, ThicknessStress};
+pub use solver::{LinearSolver, Preconditioner};
+pub use sparse::{CooMatrix, CsrMatrix};
+pub use thermal::{
+    HeatConduction, ThermalMaterial, ThermalSolution, TimeStepping, TransientSolution,
+};
@@ -0,0 +1,407 @@
+// Strain and stress components are ordered xx, yy, zz, xy, yz, zx, with
+// engineering shear strains.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IsotropicElastic {
+    pub young: f64,
+    pub poisson: f64,
+}
+
+impl IsotropicElastic {
+    pub fn new(young: f64, poisson: f64) -> Result<Self, &'static str> {
+        if young.is_nan() || young <= 0.0 {
+            return Err("Young's modulus must be positive");
+        }
+        if poisson.is_nan() || poisson <= -1.0 || poisson >= 0.5 {
+            return Err("Poisson's ratio must lie in (-1, 0.5)");
+        }
+        Ok(IsotropicElastic { young, poisson })
+    }
+
+    // Lame's first parameter and the shear modulus.
+    pub fn lame(&self) -> (f64, f64) {
+        let (e, nu) = (self.young, self.poisson);
+        (
+            e * nu / ((1.0 + nu) * (1.0 - 2.0 * nu)),
+            e / (2.0 * (1.0 + nu)),
+        )
+    }
+
+    pub fn solid_matrix(&self) -> [[f64; 6]; 6] {
+        let (lambda, mu) = self.lame();
+        let mut d = [[0.0; 6]; 6];
+        for (i, row) in d.iter_mut().enumerate() {
+            if i < 3 {
+                row[..3].fill(lambda);
+                row[i] += 2.0 * mu;
+            } else {
+                row[i] = mu;
+            }
+        }
+        d
+    }
+
+    // Components xx, yy, xy with zero out-of-plane stress.
+    pub fn plane_stress_matrix(&self) -> [[f64; 3]; 3] {
+        let (e, nu) = (self.young, self.poisson);
+        let c = e / (1.0 - nu * nu);
+        [
+            [c, c * nu, 0.0],
+            [c * nu, c, 0.0],
+            [0.0, 0.0, c * (1.0 - nu) / 2.0],
+        ]
+    }
+
+    // Components xx, yy, xy with zero out-of-plane strain.
+    pub fn plane_strain_matrix(&self) -> [[f64; 3]; 3] {
+        let (lambda, mu) = self.lame();
+        [
+            [lambda + 2.0 * mu, lambda, 0.0],
+            [lambda, lambda + 2.0 * mu, 0.0],
+            [0.0, 0.0, mu],
+        ]
+    }
+
+    // Components rr, zz, hoop, rz.
+    pub fn axisymmetric_matrix(&self) -> [[f64; 4]; 4] {
+        let (lambda, mu) = self.lame();
+        let mut d = [[0.0; 4]; 4];
+        for (i, row) in d.iter_mut().take(3).enumerate() {
+            row[..3].fill(lambda);
+            row[i] += 2.0 * mu;
+        }
+        d[3][3] = mu;
+        d
+    }
+}
+
+pub fn von_mises(stress: &[f64; 6]) -> f64 {
+    let [sx, sy, sz, txy, tyz, tzx] = *stress;
+    (0.5 * ((sx - sy).powi(2) + (sy - sz).powi(2) + (sz - sx).powi(2))
+        + 3.0 * (txy * txy + tyz * tyz + tzx * tzx))
+        .sqrt()
+}
+
+// Pairs of tensor indices of the components.
+pub(crate) const PAIRS: [(usize, usize); 6] = [(0, 0), (1, 1), (2, 2), (0, 1), (1, 2), (2, 0)];
+
+// Symmetric tensor of components, halving the shear ones for strains.
+fn tensor(components: &[f64; 6], engineering: bool) -> [[f64; 3]; 3] {
+    let f = if engineering { 0.5 } else { 1.0 };
+    let c = components;
+    [
+        [c[0], f * c[3], f * c[5]],
+        [f * c[3], c[1], f * c[4]],
+        [f * c[5], f * c[4], c[2]],
+    ]
+}
+
+pub(crate) fn determinant(t: &[[f64; 3]; 3]) -> f64 {
+    t[0][0] * (t[1][1] * t[2][2] - t[1][2] * t[2][1])
+        - t[0][1] * (t[1][0] * t[2][2] - t[1][2] * t[2][0])
+        + t[0][2] * (t[1][0] * t[2][1] - t[1][1] * t[2][0])
+}
+
+pub(crate) fn inverse(t: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let det = determinant(t);
+    let mut inverse = [[0.0; 3]; 3];
+    for (i, row) in inverse.iter_mut().enumerate() {
+        for (j, v) in row.iter_mut().enumerate() {
+            let (a, b) = ((j + 1) % 3, (j + 2) % 3);
+            let (p, q) = ((i + 1) % 3, (i + 2) % 3);
+            *v = (t[a][p] * t[b][q] - t[a][q] * t[b][p]) / det;
+        }
+    }
+    inverse
+}
+
+// Deviatoric part and its norm, counting the shear components twice as in
+// the full tensor.
+fn deviator(stress: &[f64; 6]) -> ([f64; 6], f64) {
+    let mean = (stress[0] + stress[1] + stress[2]) / 3.0;
+    let mut s = *stress;
+    for v in s.iter_mut().take(3) {
+        *v -= mean;
+    }
+    let norm =
+        (s[0] * s[0] + s[1] * s[1] + s[2] * s[2] + 2.0 * (s[3] * s[3] + s[4] * s[4] + s[5] * s[5]))
+            .sqrt();
+    (s, norm)
+}
+
+// Isotropic hyperelastic material with the strain energy
+//   c10 (I1 - 3) + c01 (I2 - 3) + bulk / 2 (J - 1)^2
+// in the invariants I1, I2 of the isochoric part of the right Cauchy-Green
+// tensor and the volume ratio J. Neo-Hookean for c01 = 0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hyperelastic {
+    pub c10: f64,
+    pub c01: f64,
+    pub bulk: f64,
+}
+
+impl Hyperelastic {
+    pub fn mooney_rivlin(c10: f64, c01: f64, bulk: f64) -> Result<Self, &'static str> {
+        if c10.is_nan() || c01.is_nan() || c10 + c01 <= 0.0 {
+            return Err("Shear modulus must be positive");
+        }
+        if bulk.is_nan() || bulk <= 0.0 {
+            return Err("Bulk modulus must be positive");
+        }
+        Ok(Hyperelastic { c10, c01, bulk })
+    }
+
+    pub fn neo_hookean(shear: f64, bulk: f64) -> Result<Self, &'static str> {
+        Hyperelastic::mooney_rivlin(0.5 * shear, 0.0, bulk)
+    }
+
+    // Initial shear modulus.
+    pub fn shear(&self) -> f64 {
+        2.0 * (self.c10 + self.c01)
+    }
+
+    // Second Piola-Kirchhoff stress and its derivative with respect to the
+    // Green-Lagrange strain, from the derivatives of the energy in the
+    // invariants I1, I2, I3 of C = I + 2 E.
+    pub fn stress(&self, strain: &[f64; 6]) -> Result<([f64; 6], [[f64; 6]; 6]), &'static str> {
+        let mut c = tensor(strain, true).map(|r| r.map(|v| 2.0 * v));
+        for (i, row) in c.iter_mut().enumerate() {
+            row[i] += 1.0;
+        }
+        let i3 = determinant(&c);
+        if i3.is_nan() || i3 <= 0.0 {
+            return Err("Deformation is not invertible");
+        }
+        let i1 = c[0][0] + c[1][1] + c[2][2];
+        let square: f64 = (0..3)
+            .flat_map(|i| (0..3).map(move |j| (i, j)))
+            .map(|(i, j)| c[i][j] * c[j][i])
+            .sum();
+        let i2 = 0.5 * (i1 * i1 - square);
+        let inverse = inverse(&c);
+        let (c10, c01, bulk) = (self.c10, self.c01, self.bulk);
+        let w1 = c10 * i3.powf(-1.0 / 3.0);
+        let w2 = c01 * i3.powf(-2.0 / 3.0);
+        let w3 = -c10 * i1 * i3.powf(-4.0 / 3.0) / 3.0 - 2.0 * c01 * i2 * i3.powf(-5.0 / 3.0) / 3.0
+            + 0.5 * bulk * (1.0 - 1.0 / i3.sqrt());
+        let w13 = -c10 * i3.powf(-4.0 / 3.0) / 3.0;
+        let w23 = -2.0 * c01 * i3.powf(-5.0 / 3.0) / 3.0;
+        let w33 = 4.0 * c10 * i1 * i3.powf(-7.0 / 3.0) / 9.0
+            + 10.0 * c01 * i2 * i3.powf(-8.0 / 3.0) / 9.0
+            + 0.25 * bulk * i3.powf(-1.5);
+        // Derivatives of the invariants.
+        let d1 = [1.0, 1.0, 1.0, 0.0, 0.0, 0.0];
+        let d2 = PAIRS.map(|(i, j)| if i == j { i1 - c[i][j] } else { -c[i][j] });
+        let d3 = PAIRS.map(|(i, j)| i3 * inverse[i][j]);
+        let mut stress = [0.0; 6];
+        for (k, s) in stress.iter_mut().enumerate() {
+            *s = 2.0 * (w1 * d1[k] + w2 * d2[k] + w3 * d3[k]);
+        }
+        let mut tangent = [[0.0; 6]; 6];
+        for (k, row) in tangent.iter_mut().enumerate() {
+            let (i, j) = PAIRS[k];
+            for (l, v) in row.iter_mut().enumerate() {
+                let (p, q) = PAIRS[l];
+                let mut t = w13 * (d1[k] * d3[l] + d3[k] * d1[l])
+                    + w23 * (d2[k] * d3[l] + d3[k] * d2[l])
+                    + w33 * d3[k] * d3[l];
+                // Second derivatives of I2 and I3.
+                let identity = 0.5 * (f64::from(i == p && j == q) + f64::from(i == q && j == p));
+                t += w2 * (d1[k] * d1[l] - identity);
+                t += w3
+                    * i3
+                    * (inverse[i][j] * inverse[p][q]
+                        - 0.5 * (inverse[i][p] * inverse[j][q] + inverse[i][q] * inverse[j][p]));
+                *v = 4.0 * t;
+            }
+        }
+        Ok((stress, tangent))
+    }
+}
+
+// Stress, tangent and updated internal variables at a material point.
+pub type MaterialResponse = ([f64; 6], [[f64; 6]; 6], PlasticState);
+
+// Internal variables of J2 plasticity at a material point, with the plastic
+// strain in engineering shear components.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PlasticState {
+    pub plastic_strain: [f64; 6],
+    pub back_stress: [f64; 6],
+    pub equivalent_plastic_strain: f64,
+}
+
+// Von Mises plasticity with linear isotropic and kinematic hardening, the
+// moduli being the slopes of yield stress and back stress against the
+// equivalent plastic strain in uniaxial tension. The strain is split
+// additively, which in large deformation holds for small elastic strains.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct J2Plasticity {
+    pub elastic: IsotropicElastic,
+    pub yield_stress: f64,
+    pub isotropic_hardening: f64,
+    pub kinematic_hardening: f64,
+}
+
+impl J2Plasticity {
+    pub fn new(
+        elastic: IsotropicElastic,
+        yield_stress: f64,
+        isotropic_hardening: f64,
+        kinematic_hardening: f64,
+    ) -> Result<Self, &'static str> {
+        if yield_stress.is_nan() || yield_stress <= 0.0 {
+            return Err("Yield stress must be positive");
+        }
+        if [isotropic_hardening, kinematic_hardening]
+            .iter()
+            .any(|h| h.is_nan() || *h < 0.0)
+        {
+            return Err("Hardening moduli must not be negative");
+        }
+        Ok(J2Plasticity {
+            elastic,
+            yield_stress,
+            isotropic_hardening,
+            kinematic_hardening,
+        })
+    }
+
+    // Stress, consistent tangent and updated state for a total strain from
+    // the state of the last equilibrium, by radial return.
+    pub fn stress(&self, strain: &[f64; 6], state: &PlasticState) -> MaterialResponse {
+        let d = self.elastic.solid_matrix();
+        let (_, mu) = self.elastic.lame();
+        let elastic: Vec<f64> = (0..6)
+            .map(|i| strain[i] - state.plastic_strain[i])
+            .collect();
+        let mut stress = [0.0; 6];
+        for (s, row) in stress.iter_mut().zip(d.iter()) {
+            *s = row.iter().zip(elastic.iter()).map(|(d, e)| d * e).sum();
+        }
+        let mut relative = deviator(&stress).0;
+        for (r, b) in relative.iter_mut().zip(state.back_stress.iter()) {
+            *r -= b;
+        }
+        let norm = deviator(&relative).1;
+        let (h, k) = (self.isotropic_hardening, self.kinematic_hardening);
+        let radius =
+            (2.0f64 / 3.0).sqrt() * (self.yield_stress + h * state.equivalent_plastic_strain);
+        if norm <= radius {
+            return (stress, d, *state);
+        }
+        let gamma = (norm - radius) / (2.0 * mu + 2.0 * (h + k) / 3.0);
+        let n = relative.map(|r| r / norm);
+        let mut updated = *state;
+        for i in 0..6 {
+            stress[i] -= 2.0 * mu * gamma * n[i];
+            updated.plastic_strain[i] += if i < 3 {
+                gamma * n[i]
+            } else {
+                2.0 * gamma * n[i]
+            };
+            updated.back_stress[i] += 2.0 * k * gamma * n[i] / 3.0;
+        }
+        updated.equivalent_plastic_strain += (2.0f64 / 3.0).sqrt() * gamma;
+        // Consistent tangent of the return map.
+        let theta = 1.0 - 2.0 * mu * gamma / norm;
+        let bar = 1.0 / (1.0 + (h + k) / (3.0 * mu)) - (1.0 - theta);
+        let bulk = self.elastic.young / (3.0 * (1.0 - 2.0 * self.elastic.poisson));
+        let mut tangent = [[0.0; 6]; 6];
+        for (i, row) in tangent.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                let deviatoric = match (i < 3, j < 3) {
+                    (true, true) => f64::from(i == j) - 1.0 / 3.0,
+                    _ => 0.5 * f64::from(i == j),
+                };
+                let volumetric = if i < 3 && j < 3 { bulk } else { 0.0 };
+                *v = volumetric + 2.0 * mu * theta * deviatoric - 2.0 * mu * bar * n[i] * n[j];
+            }
+        }
+        (stress, tangent, updated)
+    }
+}
+
+// Material of nonlinear analyses, relating the Green-Lagrange strain to the
+// second Piola-Kirchhoff stress, or the small strain to the stress in
+// geometrically linear analyses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MaterialModel {
+    // Linear in the strain, Saint Venant-Kirchhoff in large deformation.
+    Elastic(IsotropicElastic),
+    Hyperelastic(Hyperelastic),
+    Plastic(J2Plasticity),
+}
+
+impl MaterialModel {
+    // Stress, tangent and the updated state of the point.
+    pub fn stress(
+        &self,
+        strain: &[f64; 6],
+        state: &PlasticState,
+    ) -> Result<MaterialResponse, &'static str> {
+        match self {
+            MaterialModel::Elastic(material) => {
+                let d = material.solid_matrix();
+                let stress = d.map(|row| row.iter().zip(strain.iter()).map(|(d, e)| d * e).sum());
+                Ok((stress, d, *state))
+            }
+            MaterialModel::Hyperelastic(material) => {
+                let (stress, tangent) = material.stress(strain)?;
+                Ok((stress, tangent, *state))
+            }
+            MaterialModel::Plastic(material) => Ok(material.stress(strain, state)),
+        }
+    }
+}
+
+// Cauchy stress of the second Piola-Kirchhoff stress for the deformation
+// gradient, sigma = F S F^T / J.
+pub fn cauchy_stress(deformation: &[[f64; 3]; 3], stress: &[f64; 6]) -> [f64; 6] {
+    let f = deformation;
+    let s = tensor(stress, false);
+    let j = determinant(f);
+    PAIRS.map(|(i, k)| {
+        let mut v = 0.0;
+        for p in 0..3 {
+            for q in 0..3 {
+                v += f[i][p] * s[p][q] * f[k][q];
+            }
+        }
+        v / j
+    })
+}
+
+// Spatial tangent of a material one, c = F F F F C / J, in components.
+pub(crate) fn spatial_tangent(
+    deformation: &[[f64; 3]; 3],
+    tangent: &[[f64; 6]; 6],
+) -> [[f64; 6]; 6] {
+    let f = deformation;
+    let j = determinant(f);
+    // Push forward of stress components.
+    let t: [[f64; 6]; 6] = PAIRS.map(|(i, k)| {
+        let mut row = [0.0; 6];
+        for (r, &(p, q)) in row.iter_mut().zip(PAIRS.iter()) {
+            *r = if p == q {
+                f[i][p] * f[k][q]
+            } else {
+                f[i][p] * f[k][q] + f[i][q] * f[k][p]
+            };
+        }
+        row
+    });
+    let mut ct = [[0.0; 6]; 6];
+    for (a, row) in ct.iter_mut().enumerate() {
+        for (b, v) in row.iter_mut().enumerate() {
+            *v = (0..6).map(|p| tangent[a][p] * t[b][p]).sum();
+        }
+    }
+    let mut c = [[0.0; 6]; 6];
+    for (a, row) in c.iter_mut().enumerate() {
+        for (b, v) in row.iter_mut().enumerate() {
+            *v = (0..6).map(|p| t[a][p] * ct[p][b]).sum::<f64>() / j;
+        }
+    }
+    c
+}
@@ -0,0 +1,925 @@
+use geom::Point3d;
+use std::collections::{BTreeMap, HashMap};
+
+// Node ordering follows VTK: corner nodes first, then edge midside nodes in
+// edge order, then face and body centres.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ElementType {
+    Line2,
+    Line3,
+    Tri3,
+    Tri6,
+    Quad4,
+    Quad8,
+    Quad9,
+    Tet4,
+    Tet10,
+    Hex8,
+    Hex20,
+    Hex27,
+    Wedge6,
+    Pyramid5,
+}
+
+const LINE2_EDGES: &[&[usize]] = &[&[0, 1]];
+const LINE3_EDGES: &[&[usize]] = &[&[0, 1, 2]];
+const TRI3_EDGES: &[&[usize]] = &[&[0, 1], &[1, 2], &[2, 0]];
+const TRI6_EDGES: &[&[usize]] = &[&[0, 1, 3], &[1, 2, 4], &[2, 0, 5]];
+const QUAD4_EDGES: &[&[usize]] = &[&[0, 1], &[1, 2], &[2, 3], &[3, 0]];
+const QUAD8_EDGES: &[&[usize]] = &[&[0, 1, 4], &[1, 2, 5], &[2, 3, 6], &[3, 0, 7]];
+const TET4_EDGES: &[&[usize]] = &[&[0, 1], &[1, 2], &[2, 0], &[0, 3], &[1, 3], &[2, 3]];
+const TET10_EDGES: &[&[usize]] = &[
+    &[0, 1, 4],
+    &[1, 2, 5],
+    &[2, 0, 6],
+    &[0, 3, 7],
+    &[1, 3, 8],
+    &[2, 3, 9],
+];
+const HEX8_EDGES: &[&[usize]] = &[
+    &[0, 1],
+    &[1, 2],
+    &[2, 3],
+    &[3, 0],
+    &[4, 5],
+    &[5, 6],
+    &[6, 7],
+    &[7, 4],
+    &[0, 4],
+    &[1, 5],
+    &[2, 6],
+    &[3, 7],
+];
+const HEX20_EDGES: &[&[usize]] = &[
+    &[0, 1, 8],
+    &[1, 2, 9],
+    &[2, 3, 10],
+    &[3, 0, 11],
+    &[4, 5, 12],
+    &[5, 6, 13],
+    &[6, 7, 14],
+    &[7, 4, 15],
+    &[0, 4, 16],
+    &[1, 5, 17],
+    &[2, 6, 18],
+    &[3, 7, 19],
+];
+const WEDGE6_EDGES: &[&[usize]] = &[
+    &[0, 1],
+    &[1, 2],
+    &[2, 0],
+    &[3, 4],
+    &[4, 5],
+    &[5, 3],
+    &[0, 3],
+    &[1, 4],
+    &[2, 5],
+];
+const PYRAMID5_EDGES: &[&[usize]] = &[
+    &[0, 1],
+    &[1, 2],
+    &[2, 3],
+    &[3, 0],
+    &[0, 4],
+    &[1, 4],
+    &[2, 4],
+    &[3, 4],
+];
+
+// Faces of solid elements are ordered counterclockwise seen from outside.
+const TET4_FACES: &[&[usize]] = &[&[0, 2, 1], &[0, 1, 3], &[1, 2, 3], &[2, 0, 3]];
+const TET10_FACES: &[&[usize]] = &[
+    &[0, 2, 1, 6, 5, 4],
+    &[0, 1, 3, 4, 8, 7],
+    &[1, 2, 3, 5, 9, 8],
+    &[2, 0, 3, 6, 7, 9],
+];
+const HEX8_FACES: &[&[usize]] = &[
+    &[0, 3, 2, 1],
+    &[4, 5, 6, 7],
+    &[0, 1, 5, 4],
+    &[1, 2, 6, 5],
+    &[2, 3, 7, 6],
+    &[3, 0, 4, 7],
+];
+const HEX20_FACES: &[&[usize]] = &[
+    &[0, 3, 2, 1, 11, 10, 9, 8],
+    &[4, 5, 6, 7, 12, 13, 14, 15],
+    &[0, 1, 5, 4, 8, 17, 12, 16],
+    &[1, 2, 6, 5, 9, 18, 13, 17],
+    &[2, 3, 7, 6, 10, 19, 14, 18],
+    &[3, 0, 4, 7, 11, 16, 15, 19],
+];
+const HEX27_FACES: &[&[usize]] = &[
+    &[0, 3, 2, 1, 11, 10, 9, 8, 24],
+    &[4, 5, 6, 7, 12, 13, 14, 15, 25],
+    &[0, 1, 5, 4, 8, 17, 12, 16, 22],
+    &[1, 2, 6, 5, 9, 18, 13, 17, 21],
+    &[2, 3, 7, 6, 10, 19, 14, 18, 23],
+    &[3, 0, 4, 7, 11, 16, 15, 19, 20],
+];
+const WEDGE6_FACES: &[&[usize]] = &[
+    &[0, 2, 1],
+    &[3, 4, 5],
+    &[0, 1, 4, 3],
+    &[1, 2, 5, 4],
+    &[2, 0, 3, 5],
+];
+const PYRAMID5_FACES: &[&[usize]] = &[
+    &[0, 3, 2, 1],
+    &[0, 1, 4],
+    &[1, 2, 4],
+    &[2, 3, 4],
+    &[3, 0, 4],
+];
+const TRI3_FACES: &[&[usize]] = &[&[0, 1, 2]];
+const TRI6_FACES: &[&[usize]] = &[&[0, 1, 2, 3, 4, 5]];
+const QUAD4_FACES: &[&[usize]] = &[&[0, 1, 2, 3]];
+const QUAD8_FACES: &[&[usize]] = &[&[0, 1, 2, 3, 4, 5, 6, 7]];
+const QUAD9_FACES: &[&[usize]] = &[&[0, 1, 2, 3, 4, 5, 6, 7, 8]];
+const LINE_ENDS: &[&[usize]] = &[&[0], &[1]];
+
+impl ElementType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ElementType::Line2 => "Line2",
+            ElementType::Line3 => "Line3",
+            ElementType::Tri3 => "Tri3",
+            ElementType::Tri6 => "Tri6",
+            ElementType::Quad4 => "Quad4",
+            ElementType::Quad8 => "Quad8",
+            ElementType::Quad9 => "Quad9",
+            ElementType::Tet4 => "Tet4",
+            ElementType::Tet10 => "Tet10",
+            ElementType::Hex8 => "Hex8",
+            ElementType::Hex20 => "Hex20",
+            ElementType::Hex27 => "Hex27",
+            ElementType::Wedge6 => "Wedge6",
+            ElementType::Pyramid5 => "Pyramid5",
+        }
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        match self {
+            ElementType::Line2 => 2,
+            ElementType::Line3 => 3,
+            ElementType::Tri3 => 3,
+            ElementType::Tri6 => 6,
+            ElementType::Quad4 => 4,
+            ElementType::Quad8 => 8,
+            ElementType::Quad9 => 9,
+            ElementType::Tet4 => 4,
+            ElementType::Tet10 => 10,
+            ElementType::Hex8 => 8,
+            ElementType::Hex20 => 20,
+            ElementType::Hex27 => 27,
+            ElementType::Wedge6 => 6,
+            ElementType::Pyramid5 => 5,
+        }
+    }
+
+    pub fn num_corners(&self) -> usize {
+        self.linear().num_nodes()
+    }
+
+    pub fn dimension(&self) -> usize {
+        match self {
+            ElementType::Line2 | ElementType::Line3 => 1,
+            ElementType::Tri3
+            | ElementType::Tri6
+            | ElementType::Quad4
+            | ElementType::Quad8
+            | ElementType::Quad9 => 2,
+            _ => 3,
+        }
+    }
+
+    pub fn is_quadratic(&self) -> bool {
+        self.num_nodes() != self.num_corners()
+    }
+
+    // The element with the same shape and only the corner nodes.
+    pub fn linear(&self) -> ElementType {
+        match self {
+            ElementType::Line3 => ElementType::Line2,
+            ElementType::Tri6 => ElementType::Tri3,
+            ElementType::Quad8 | ElementType::Quad9 => ElementType::Quad4,
+            ElementType::Tet10 => ElementType::Tet4,
+            ElementType::Hex20 | ElementType::Hex27 => ElementType::Hex8,
+            other => *other,
+        }
+    }
+
+    // Local node indices of each edge: the two end nodes followed by the
+    // midside node of quadratic elements.
+    pub fn edges(&self) -> &'static [&'static [usize]] {
+        match self {
+            ElementType::Line2 => LINE2_EDGES,
+            ElementType::Line3 => LINE3_EDGES,
+            ElementType::Tri3 => TRI3_EDGES,
+            ElementType::Tri6 => TRI6_EDGES,
+            ElementType::Quad4 => QUAD4_EDGES,
+            ElementType::Quad8 | ElementType::Quad9 => QUAD8_EDGES,
+            ElementType::Tet4 => TET4_EDGES,
+            ElementType::Tet10 => TET10_EDGES,
+            ElementType::Hex8 => HEX8_EDGES,
+            ElementType::Hex20 | ElementType::Hex27 => HEX20_EDGES,
+            ElementType::Wedge6 => WEDGE6_EDGES,
+            ElementType::Pyramid5 => PYRAMID5_EDGES,
+        }
+    }
+
+    // Local node indices of each face, in the node order of the face type.
+    // A surface element is its own single face; lines have none.
+    pub fn faces(&self) -> &'static [&'static [usize]] {
+        match self {
+            ElementType::Line2 | ElementType::Line3 => &[],
+            ElementType::Tri3 => TRI3_FACES,
+            ElementType::Tri6 => TRI6_FACES,
+            ElementType::Quad4 => QUAD4_FACES,
+            ElementType::Quad8 => QUAD8_FACES,
+            ElementType::Quad9 => QUAD9_FACES,
+            ElementType::Tet4 => TET4_FACES,
+            ElementType::Tet10 => TET10_FACES,
+            ElementType::Hex8 => HEX8_FACES,
+            ElementType::Hex20 => HEX20_FACES,
+            ElementType::Hex27 => HEX27_FACES,
+            ElementType::Wedge6 => WEDGE6_FACES,
+            ElementType::Pyramid5 => PYRAMID5_FACES,
+        }
+    }
+
+    // Boundary entities one dimension down: faces of solids, edges of
+    // surfaces and end nodes of lines.
+    pub fn facets(&self) -> &'static [&'static [usize]] {
+        match self.dimension() {
+            1 => LINE_ENDS,
+            2 => self.edges(),
+            _ => self.faces(),
+        }
+    }
+
+    pub fn facet_type(&self, facet: usize) -> Option<ElementType> {
+        let nodes = self.facets().get(facet)?;
+        match self.dimension() {
+            1 => None,
+            2 => Some(if nodes.len() == 2 {
+                ElementType::Line2
+            } else {
+                ElementType::Line3
+            }),
+            _ => Some(match nodes.len() {
+                3 => ElementType::Tri3,
+                4 => ElementType::Quad4,
+                6 => ElementType::Tri6,
+                8 => ElementType::Quad8,
+                _ => ElementType::Quad9,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Element {
+    pub element_type: ElementType,
+    pub nodes: Vec<usize>,
+}
+
+impl Element {
+    pub fn new(element_type: ElementType, nodes: Vec<usize>) -> Result<Self, &'static str> {
+        if nodes.len() != element_type.num_nodes() {
+            return Err("Node count does not match element type");
+        }
+        Ok(Element {
+            element_type,
+            nodes,
+        })
+    }
+
+    pub fn corners(&self) -> &[usize] {
+        &self.nodes[..self.element_type.num_corners()]
+    }
+
+    pub fn facet_nodes(&self, facet: usize) -> Vec<usize> {
+        self.element_type.facets()[facet]
+            .iter()
+            .map(|&i| self.nodes[i])
+            .collect()
+    }
+}
+
+// Face, edge or end node of an element, with global node indices.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Facet {
+    pub element: usize,
+    pub local: usize,
+    pub nodes: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub nodes: Vec<Point3d>,
+    pub elements: Vec<Element>,
+    pub node_sets: BTreeMap<String, Vec<usize>>,
+    pub element_sets: BTreeMap<String, Vec<usize>>,
+}
+
+// Topological identity of an edge or face: its sorted corner nodes.
+fn key(nodes: &[usize], corners: usize) -> Vec<usize> {
+    let mut key = nodes[..corners].to_vec();
+    key.sort_unstable();
+    key
+}
+
+// (element, local facet) pairs keyed by facet dimension and corner nodes.
+type FacetGroups = HashMap<(usize, Vec<usize>), Vec<(usize, usize)>>;
+
+// Corner nodes come first in every edge and face.
+fn corner_count(element_type: ElementType, local: &[usize]) -> usize {
+    let corners = element_type.num_corners();
+    local.iter().filter(|&&i| i < corners).count()
+}
+
+impl Mesh {
+    pub fn new() -> Self {
+        Mesh::default()
+    }
+
+    pub fn from_nodes_elements(
+        nodes: Vec<Point3d>,
+        elements: Vec<Element>,
+    ) -> Result<Self, &'static str> {
+        let mesh = Mesh {
+            nodes,
+            elements,
+            ..Mesh::default()
+        };
+        mesh.validate()?;
+        Ok(mesh)
+    }
+
+    // Triangles in the xy plane from a 2D triangulation; unreferenced
+    // points are dropped.
+    pub fn from_triangulation(triangulation: &geom::Triangulation2d) -> Self {
+        let mut mesh = Mesh::new();
+        mesh.nodes = triangulation
+            .points
+            .iter()
+            .map(|p| Point3d::from_coords(p.get_x(), p.get_y(), 0.0))
+            .collect();
+        for t in triangulation.triangles.iter() {
+            mesh.elements.push(Element {
+                element_type: ElementType::Tri3,
+                nodes: t.to_vec(),
+            });
+        }
+        mesh.remove_unused_nodes();
+        mesh
+    }
+
+    // Shell elements from a surface mesh such as a tessellated geom surface:
+    // triangles and quadrilaterals are kept, larger polygons are split into
+    // triangle fans. Unreferenced points are dropped.
+    pub fn from_surface_mesh(surface: &geom::Mesh) -> Self {
+        let mut mesh = Mesh::new();
+        mesh.nodes = surface.points.clone();
+        for face in surface.faces.iter() {
+            if face.len() == 4 {
+                mesh.elements.push(Element {
+                    element_type: ElementType::Quad4,
+                    nodes: face.clone(),
+                });
+                continue;
+            }
+            for i in 1..face.len().saturating_sub(1) {
+                mesh.elements.push(Element {
+                    element_type: ElementType::Tri3,
+                    nodes: vec![face[0], face[i], face[i + 1]],
+                });
+            }
+        }
+        mesh.remove_unused_nodes();
+        mesh
+    }
+
+    // Tetrahedra are renumbered to positive volume, with the fourth node on
+    // the side the first face's normal points to. Unreferenced points are
+    // dropped.
+    pub fn from_tetrahedralization(tetrahedralization: &geom::Tetrahedralization) -> Self {
+        let mut mesh = Mesh::new();
+        mesh.nodes = tetrahedralization.points.clone();
+        for t in tetrahedralization.tetrahedra.iter() {
+            let mut nodes = t.to_vec();
+            if mesh.signed_volume(&nodes) < 0.0 {
+                nodes.swap(1, 2);
+            }
+            mesh.elements.push(Element {
+                element_type: ElementType::Tet4,
+                nodes,
+            });
+        }
+        mesh.remove_unused_nodes();
+        mesh
+    }
+
+    // Uniform mesh of [0, length] on the x axis.
+    pub fn line(length: f64, n: usize, element_type: ElementType) -> Result<Self, &'static str> {
+        if n == 0 {
+            return Err("Number of divisions must be positive");
+        }
+        if element_type.linear() != ElementType::Line2 {
+            return Err("Unsupported element type for a line mesh");
+        }
+        let mut mesh = Mesh::new();
+        for i in 0..=n {
+            mesh.nodes
+                .push(Point3d::from_coords(length * i as f64 / n as f64, 0.0, 0.0));
+        }
+        for i in 0..n {
+            mesh.elements.push(Element {
+                element_type: ElementType::Line2,
+                nodes: vec![i, i + 1],
+            });
+        }
+        mesh.elevate(element_type)
+    }
+
+    // Uniform mesh of [0, lx] x [0, ly] in the xy plane. Quads are split
+    // along the same diagonal for triangles.
+    pub fn rectangle(
+        lx: f64,
+        ly: f64,
+        nx: usize,
+        ny: usize,
+        element_type: ElementType,
+    ) -> Result<Self, &'static str> {
+        if nx == 0 || ny == 0 {
+            return Err("Number of divisions must be positive");
+        }
+        let linear = element_type.linear();
+        if linear != ElementType::Quad4 && linear != ElementType::Tri3 {
+            return Err("Unsupported element type for a rectangle mesh");
+        }
+        let mut mesh = Mesh::new();
+        for j in 0..=ny {
+            for i in 0..=nx {
+                mesh.nodes.push(Point3d::from_coords(
+                    lx * i as f64 / nx as f64,
+                    ly * j as f64 / ny as f64,
+                    0.0,
+                ));
+            }
+        }
+        let id = |i: usize, j: usize| j * (nx + 1) + i;
+        for j in 0..ny {
+            for i in 0..nx {
+                let c = [id(i, j), id(i + 1, j), id(i + 1, j + 1), id(i, j + 1)];
+                if linear == ElementType::Quad4 {
+                    mesh.elements.push(Element {
+                        element_type: ElementType::Quad4,
+                        nodes: c.to_vec(),
+                    });
+                } else {
+                    for t in [[c[0], c[1], c[2]], [c[0], c[2], c[3]]] {
+                        mesh.elements.push(Element {
+                            element_type: ElementType::Tri3,
+                            nodes: t.to_vec(),
+                        });
+                    }
+                }
+            }
+        }
+        mesh.elevate(element_type)
+    }
+
+    // Uniform mesh of [0, lx] x [0, ly] x [0, lz]. Cells are split into six
+    // tetrahedra around their main diagonal, or into two wedges along z.
+    pub fn block(
+        lx: f64,
+        ly: f64,
+        lz: f64,
+        nx: usize,
+        ny: usize,
+        nz: usize,
+        element_type: ElementType,
+    ) -> Result<Self, &'static str> {
+        if nx == 0 || ny == 0 || nz == 0 {
+            return Err("Number of divisions must be positive");
+        }
+        let linear = element_type.linear();
+        if !matches!(
+            linear,
+            ElementType::Hex8 | ElementType::Tet4 | ElementType::Wedge6
+        ) {
+            return Err("Unsupported element type for a block mesh");
+        }
+        let mut mesh = Mesh::new();
+        for k in 0..=nz {
+            for j in 0..=ny {
+                for i in 0..=nx {
+                    mesh.nodes.push(Point3d::from_coords(
+                        lx * i as f64 / nx as f64,
+                        ly * j as f64 / ny as f64,
+                        lz * k as f64 / nz as f64,
+                    ));
+                }
+            }
+        }
+        let id = |i: usize, j: usize, k: usize| (k * (ny + 1) + j) * (nx + 1) + i;
+        for k in 0..nz {
+            for j in 0..ny {
+                for i in 0..nx {
+                    let c = [
+                        id(i, j, k),
+                        id(i + 1, j, k),
+                        id(i + 1, j + 1, k),
+                        id(i, j + 1, k),
+                        id(i, j, k + 1),
+                        id(i + 1, j, k + 1),
+                        id(i + 1, j + 1, k + 1),
+                        id(i, j + 1, k + 1),
+                    ];
+                    match linear {
+                        ElementType::Hex8 => mesh.elements.push(Element {
+                            element_type: ElementType::Hex8,
+                            nodes: c.to_vec(),
+                        }),
+                        ElementType::Wedge6 => {
+                            for t in [[0, 1, 2], [0, 2, 3]] {
+                                mesh.elements.push(Element {
+                                    element_type: ElementType::Wedge6,
+                                    nodes: vec![
+                                        c[t[0]],
+                                        c[t[1]],
+                                        c[t[2]],
+                                        c[t[0] + 4],
+                                        c[t[1] + 4],
+                                        c[t[2] + 4],
+                                    ],
+                                });
+                            }
+                        }
+                        _ => {
+                            for path in [[1, 2], [1, 5], [3, 2], [3, 7], [4, 5], [4, 7]] {
+                                let mut nodes = vec![c[0], c[path[0]], c[path[1]], c[6]];
+                                if mesh.signed_volume(&nodes) < 0.0 {
+                                    nodes.swap(1, 2);
+                                }
+                                mesh.elements.push(Element {
+                                    element_type: ElementType::Tet4,
+                                    nodes,
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        mesh.elevate(element_type)
+    }
+
+    fn signed_volume(&self, tet: &[usize]) -> f64 {
+        let p = |i: usize| self.nodes[tet[i]].get_coords();
+        let (o, a, b, c) = (p(0), p(1), p(2), p(3));
+        let a = [a.0 - o.0, a.1 - o.1, a.2 - o.2];
+        let b = [b.0 - o.0, b.1 - o.1, b.2 - o.2];
+        let c = [c.0 - o.0, c.1 - o.1, c.2 - o.2];
+        (a[1] * b[2] - a[2] * b[1]) * c[0]
+            + (a[2] * b[0] - a[0] * b[2]) * c[1]
+            + (a[0] * b[1] - a[1] * b[0]) * c[2]
+    }
+
+    fn elevate(self, element_type: ElementType) -> Result<Self, &'static str> {
+        if element_type.is_quadratic() {
+            self.to_quadratic(
+                element_type != ElementType::Quad9 && element_type != ElementType::Hex27,
+            )
+        } else {
+            Ok(self)
+        }
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn num_elements(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn add_node(&mut self, point: Point3d) -> usize {
+        self.nodes.push(point);
+        self.nodes.len() - 1
+    }
+
+    pub fn add_element(
+        &mut self,
+        element_type: ElementType,
+        nodes: Vec<usize>,
+    ) -> Result<usize, &'static str> {
+        let element = Element::new(element_type, nodes)?;
+        if element.nodes.iter().any(|&n| n >= self.nodes.len()) {
+            return Err("Element node index out of range");
+        }
+        self.elements.push(element);
+        Ok(self.elements.len() - 1)
+    }
+
+    pub fn add_node_set(&mut self, name: &str, mut nodes: Vec<usize>) -> Result<(), &'static str> {
+        if nodes.iter().any(|&n| n >= self.nodes.len()) {
+            return Err("Node set index out of range");
+        }
+        nodes.sort_unstable();
+        nodes.dedup();
+        self.node_sets.insert(name.to_string(), nodes);
+        Ok(())
+    }
+
+    pub fn add_element_set(
+        &mut self,
+        name: &str,
+        mut elements: Vec<usize>,
+    ) -> Result<(), &'static str> {
+        if elements.iter().any(|&e| e >= self.elements.len()) {
+            return Err("Element set index out of range");
+        }
+        elements.sort_unstable();
+        elements.dedup();
+        self.element_sets.insert(name.to_string(), elements);
+        Ok(())
+    }
+
+    pub fn node_set(&self, name: &str) -> Option<&[usize]> {
+        self.node_sets.get(name).map(|s| s.as_slice())
+    }
+
+    pub fn element_set(&self, name: &str) -> Option<&[usize]> {
+        self.element_sets.get(name).map(|s| s.as_slice())
+    }
+
+    // Nodes whose position satisfies the predicate, e.g. to select the nodes
+    // on a boundary plane for constraints.
+    pub fn find_nodes<F>(&self, predicate: F) -> Vec<usize>
+    where
+        F: Fn(&Point3d) -> bool,
+    {
+        (0..self.nodes.len())
+            .filter(|&i| predicate(&self.nodes[i]))
+            .collect()
+    }
+
+    pub fn element_points(&self, element: usize) -> Vec<Point3d> {
+        self.elements[element]
+            .nodes
+            .iter()
+            .map(|&n| self.nodes[n])
+            .collect()
+    }
+
+    pub fn validate(&self) -> Result<(), &'static str> {
+        for element in self.elements.iter() {
+            if element.nodes.len() != element.element_type.num_nodes() {
+                return Err("Node count does not match element type");
+            }
+            if element.nodes.iter().any(|&n| n >= self.nodes.len()) {
+                return Err("Element node index out of range");
+            }
+            let mut sorted = element.nodes.clone();
+            sorted.sort_unstable();
+            if sorted.windows(2).any(|w| w[0] == w[1]) {
+                return Err("Element references a node twice");
+            }
+        }
+        if self
+            .node_sets
+            .values()
+            .any(|s| s.iter().any(|&n| n >= self.nodes.len()))
+        {
+            return Err("Node set index out of range");
+        }
+        if self
+            .element_sets
+            .values()
+            .any(|s| s.iter().any(|&e| e >= self.elements.len()))
+        {
+            return Err("Element set index out of range");
+        }
+        Ok(())
+    }
+
+    // Elements incident to each node.
+    pub fn node_elements(&self) -> Vec<Vec<usize>> {
+        let mut incident = vec![Vec::new(); self.nodes.len()];
+        for (e, element) in self.elements.iter().enumerate() {
+            for &n in element.nodes.iter() {
+                incident[n].push(e);
+            }
+        }
+        incident
+    }
+
+    // Nodes sharing an element with each node, sorted and excluding itself.
+    // This is the sparsity pattern of assembled matrices.
+    pub fn node_neighbors(&self) -> Vec<Vec<usize>> {
+        let mut neighbors = vec![Vec::new(); self.nodes.len()];
+        for element in self.elements.iter() {
+            for &a in element.nodes.iter() {
+                neighbors[a].extend(element.nodes.iter().copied().filter(|&b| b != a));
+            }
+        }
+        for list in neighbors.iter_mut() {
+            list.sort_unstable();
+            list.dedup();
+        }
+        neighbors
+    }
+
+    // Elements sharing a facet with each element.
+    pub fn element_neighbors(&self) -> Vec<Vec<usize>> {
+        let mut neighbors = vec![Vec::new(); self.elements.len()];
+        for group in self.facet_groups().values() {
+            for &(a, _) in group.iter() {
+                for &(b, _) in group.iter() {
+                    if a != b {
+                        neighbors[a].push(b);
+                    }
+                }
+            }
+        }
+        for list in neighbors.iter_mut() {
+            list.sort_unstable();
+            list.dedup();
+        }
+        neighbors
+    }
+
+    fn facet_groups(&self) -> FacetGroups {
+        let mut groups: HashMap<_, Vec<_>> = HashMap::new();
+        for (e, element) in self.elements.iter().enumerate() {
+            let element_type = element.element_type;
+            for (f, local) in element_type.facets().iter().enumerate() {
+                let nodes: Vec<usize> = local.iter().map(|&i| element.nodes[i]).collect();
+                let corners = corner_count(element_type, local);
+                groups
+                    .entry((element_type.dimension(), key(&nodes, corners)))
+                    .or_default()
+                    .push((e, f));
+            }
+        }
+        groups
+    }
+
+    // Facets not shared with another element of the same dimension: the
+    // outer surface of solids, the boundary edges of surfaces and the free
+    // ends of lines. Sorted by element and local index.
+    pub fn boundary_facets(&self) -> Vec<Facet> {
+        let mut boundary: Vec<Facet> = self
+            .facet_groups()
+            .into_values()
+            .filter(|group| group.len() == 1)
+            .map(|group| {
+                let (element, local) = group[0];
+                Facet {
+                    element,
+                    local,
+                    nodes: self.elements[element].facet_nodes(local),
+                }
+            })
+            .collect();
+        boundary.sort_by_key(|f| (f.element, f.local));
+        boundary
+    }
+
+    // Boundary facets whose nodes all satisfy the predicate, e.g. to select
+    // a loaded surface.
+    pub fn find_boundary_facets<F>(&self, predicate: F) -> Vec<Facet>
+    where
+        F: Fn(&Point3d) -> bool,
+    {
+        self.boundary_facets()
+            .into_iter()
+            .filter(|f| f.nodes.iter().all(|&n| predicate(&self.nodes[n])))
+            .collect()
+    }
+
+    // Unique edges with global node indices, in order of first appearance.
+    pub fn edges(&self) -> Vec<Vec<usize>> {
+        self.unique(|t| t.edges())
+    }
+
+    // Unique faces of solid and surface elements, in order of first
+    // appearance.
+    pub fn faces(&self) -> Vec<Vec<usize>> {
+        self.unique(|t| t.faces())
+    }
+
+    fn unique<E>(&self, entities: E) -> Vec<Vec<usize>>
+    where
+        E: Fn(&ElementType) -> &'static [&'static [usize]],
+    {
+        let mut seen = HashMap::new();
+        let mut result = Vec::new();
+        for element in self.elements.iter() {
+            for local in entities(&element.element_type).iter() {
+                let nodes: Vec<usize> = local.iter().map(|&i| element.nodes[i]).collect();
+                let k = key(&nodes, corner_count(element.element_type, local));
+                if seen.insert(k, result.len()).is_none() {
+                    result.push(nodes);
+                }
+            }
+        }
+        result
+    }
+
+    // Drops nodes not referenced by any element and renumbers the rest.
+    // Returns the new index of each old node.
+    pub fn remove_unused_nodes(&mut self) -> Vec<Option<usize>> {
+        let mut used = vec![false; self.nodes.len()];
+        for element in self.elements.iter() {
+            for &n in element.nodes.iter() {
+                used[n] = true;
+            }
+        }
+        let mut map = vec![None; self.nodes.len()];
+        let mut nodes = Vec::new();
+        for (i, p) in self.nodes.iter().enumerate() {
+            if used[i] {
+                map[i] = Some(nodes.len());
+                nodes.push(*p);
+            }
+        }
+        self.nodes = nodes;
+        for element in self.elements.iter_mut() {
+            for n in element.nodes.iter_mut() {
+                *n = map[*n].unwrap();
+            }
+        }
+        for set in self.node_sets.values_mut() {
+            *set = set.iter().filter_map(|&n| map[n]).collect();
+        }
+        map
+    }
+
+    // Converts linear elements to quadratic ones with straight sides,
+    // sharing the new midside nodes between neighbours. Serendipity elements
+    // omit face and body centres. Node sets keep only the corner nodes.
+    pub fn to_quadratic(&self, serendipity: bool) -> Result<Self, &'static str> {
+        let mut mesh = Mesh {
+            nodes: self.nodes.clone(),
+            elements: Vec::with_capacity(self.elements.len()),
+            node_sets: self.node_sets.clone(),
+            element_sets: self.element_sets.clone(),
+        };
+        let mut shared: HashMap<Vec<usize>, usize> = HashMap::new();
+        let mut centre = |mesh: &mut Mesh, corners: Vec<usize>, unique: bool| {
+            let mut k = corners.clone();
+            k.sort_unstable();
+            if let (false, Some(&n)) = (unique, shared.get(&k)) {
+                return n;
+            }
+            let s = corners.iter().fold((0.0, 0.0, 0.0), |s, &c| {
+                let p = mesh.nodes[c].get_coords();
+                (s.0 + p.0, s.1 + p.1, s.2 + p.2)
+            });
+            let m = corners.len() as f64;
+            let n = mesh.add_node(Point3d::from_coords(s.0 / m, s.1 / m, s.2 / m));
+            shared.insert(k, n);
+            n
+        };
+        for element in self.elements.iter() {
+            let (target, faces, body) = match element.element_type {
+                ElementType::Line2 => (ElementType::Line3, false, false),
+                ElementType::Tri3 => (ElementType::Tri6, false, false),
+                ElementType::Quad4 if serendipity => (ElementType::Quad8, false, false),
+                ElementType::Quad4 => (ElementType::Quad9, false, true),
+                ElementType::Tet4 => (ElementType::Tet10, false, false),
+                ElementType::Hex8 if serendipity => (ElementType::Hex20, false, false),
+                ElementType::Hex8 => (ElementType::Hex27, true, true),
+                _ => return Err("No quadratic counterpart for element type"),
+            };
+            let mut nodes = element.nodes.clone();
+            for edge in target.edges() {
+                nodes.push(centre(
+                    &mut mesh,
+                    vec![nodes[edge[0]], nodes[edge[1]]],
+                    false,
+                ));
+            }
+            if faces {
+                // Face centres in node order: x-, x+, y-, y+, z-, z+.
+                for f in [5, 3, 2, 4, 0, 1] {
+                    let corners = target.faces()[f][..4].iter().map(|&i| nodes[i]).collect();
+                    nodes.push(centre(&mut mesh, corners, false));
+                }
+            }
+            if body {
+                nodes.push(centre(&mut mesh, element.nodes.clone(), true));
+            }
+            mesh.elements.push(Element {
+                element_type: target,
+                nodes,
+            });
+        }
+        Ok(mesh)
+    }
+}
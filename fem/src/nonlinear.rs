@@ -0,0 +1,824 @@
+use crate::eigen::{free_dofs, restrict};
+use crate::elasticity::{Analysis, LinearStatic, stiffness_degree};
+use crate::factorization::SparseLdlt;
+use crate::material::{
+    MaterialModel, PAIRS, PlasticState, cauchy_stress, determinant, inverse, spatial_tangent,
+};
+use crate::quadrature::QuadratureRule;
+use crate::shape::{Jacobian, evaluate};
+use crate::solver::LinearSolver;
+use crate::sparse::{CooMatrix, CsrMatrix};
+
+// Static analysis with large deformation and nonlinear materials. The loads,
+// constraints and mesh are those of a linear problem; loads keep their
+// reference magnitude and direction. Each solve moves the structure in
+// proportion to a load factor from the equilibrium of the previous solve,
+// or the undeformed state, to the current loads and prescribed
+// displacements, so load histories are followed by changing the loads of
+// the problem between solves.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Formulation {
+    // Small displacements with nonlinear materials only.
+    SmallStrain,
+    // Green-Lagrange strains and second Piola-Kirchhoff stresses on the
+    // undeformed configuration.
+    TotalLagrangian,
+    // The total Lagrangian stresses and tangent pushed forward to Cauchy
+    // stresses and the spatial tangent on the current configuration. It
+    // follows the same equilibrium path as TotalLagrangian.
+    UpdatedLagrangian,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathControl {
+    // Equal load factor increments up to one, halved while Newton's method
+    // fails.
+    Load { increments: usize },
+    // Arc-length continuation through limit points, bounding the norm of
+    // the free displacement increments, until the load factor reaches one
+    // or the increment limit is hit.
+    ArcLength { length: f64, max_increments: usize },
+}
+
+#[derive(Debug, Clone)]
+pub struct NonlinearSolution {
+    // Load factor, displacements and Newton iterations of each converged
+    // increment.
+    pub load_factors: Vec<f64>,
+    pub displacements: Vec<Vec<[f64; 3]>>,
+    pub iterations: Vec<usize>,
+    // At the end of the step. Cauchy stresses and equivalent plastic strains
+    // are averaged over the integration points of an element, then over the
+    // elements sharing a node.
+    pub reactions: Vec<[f64; 3]>,
+    pub stresses: Vec<[f64; 6]>,
+    pub plastic_strains: Vec<f64>,
+}
+
+// Element internal forces, tangent, and Cauchy stresses and material states
+// at the integration points.
+type ElementResponse = (Vec<f64>, Vec<Vec<f64>>, Vec<[f64; 6]>, Vec<PlasticState>);
+
+#[derive(Debug, Clone)]
+struct Response {
+    forces: Vec<f64>,
+    stiffness: CsrMatrix,
+    stresses: Vec<Vec<[f64; 6]>>,
+    states: Vec<Vec<PlasticState>>,
+}
+
+// Point on the equilibrium path. The response is evaluated from the
+// material states of the last converged point, which become those of this
+// point once it is accepted.
+#[derive(Debug, Clone)]
+struct PathPoint {
+    displacements: Vec<f64>,
+    load_factor: f64,
+    states: Vec<Vec<PlasticState>>,
+    response: Response,
+}
+
+// Loads and prescribed displacements of a step, from the start values plus
+// the load factor times the changes.
+struct Step {
+    forces: Vec<f64>,
+    pattern: Vec<f64>,
+    displacements: Vec<f64>,
+    prescribed: Vec<f64>,
+    free: Vec<usize>,
+    index: Vec<usize>,
+}
+
+impl Step {
+    fn external(&self, load_factor: f64) -> Vec<f64> {
+        self.forces
+            .iter()
+            .zip(self.pattern.iter())
+            .map(|(f, p)| f + load_factor * p)
+            .collect()
+    }
+
+    fn constrain(&self, displacements: &mut [f64], load_factor: f64) {
+        for (dof, u) in displacements.iter_mut().enumerate() {
+            if self.index[dof] == usize::MAX {
+                *u = self.displacements[dof] + load_factor * self.prescribed[dof];
+            }
+        }
+    }
+
+    // Out of balance force at the free dofs and the norm it is measured
+    // against.
+    fn residual(&self, point: &PathPoint) -> (Vec<f64>, f64) {
+        let external = self.external(point.load_factor);
+        let internal = &point.response.forces;
+        let residual = self
+            .free
+            .iter()
+            .map(|&i| external[i] - internal[i])
+            .collect();
+        let scale = self
+            .free
+            .iter()
+            .map(|&i| external[i] * external[i])
+            .sum::<f64>()
+            .sqrt()
+            .max(internal.iter().map(|f| f * f).sum::<f64>().sqrt());
+        (residual, scale)
+    }
+
+    // Derivative of the residual with respect to the load factor.
+    fn load(&self, stiffness: &CsrMatrix) -> Vec<f64> {
+        let coupling = stiffness.multiply(&self.prescribed_displacements());
+        self.free
+            .iter()
+            .map(|&i| self.pattern[i] - coupling[i])
+            .collect()
+    }
+
+    fn prescribed_displacements(&self) -> Vec<f64> {
+        (0..self.index.len())
+            .map(|dof| {
+                if self.index[dof] == usize::MAX {
+                    self.prescribed[dof]
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+
+    fn free_values(&self, displacements: &[f64]) -> Vec<f64> {
+        self.free.iter().map(|&i| displacements[i]).collect()
+    }
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b.iter()).map(|(a, b)| a * b).sum()
+}
+
+#[derive(Debug, Clone)]
+pub struct NonlinearStatic<'a> {
+    problem: LinearStatic<'a>,
+    formulation: Formulation,
+    materials: Vec<MaterialModel>,
+    // Relative norm of the out of balance forces at convergence.
+    tolerance: f64,
+    max_iterations: usize,
+    line_search: bool,
+    // Equilibrium reached by the last solve.
+    displacements: Vec<f64>,
+    forces: Vec<f64>,
+    states: Vec<Vec<PlasticState>>,
+}
+
+impl<'a> NonlinearStatic<'a> {
+    // The elastic materials of the problem become Saint Venant-Kirchhoff
+    // ones until replaced.
+    pub fn new(problem: LinearStatic<'a>, formulation: Formulation) -> Result<Self, &'static str> {
+        if let Analysis::PlaneStress { .. } = problem.analysis() {
+            return Err("Plane stress is not supported in nonlinear analysis");
+        }
+        let mesh = problem.mesh();
+        let materials = (0..mesh.num_elements())
+            .map(|e| MaterialModel::Elastic(problem.material(e)))
+            .collect();
+        let states = mesh
+            .elements
+            .iter()
+            .map(|element| {
+                let count = QuadratureRule::new(
+                    element.element_type,
+                    stiffness_degree(element.element_type),
+                )
+                .points
+                .len();
+                vec![PlasticState::default(); count]
+            })
+            .collect();
+        let n = problem.num_dofs();
+        Ok(NonlinearStatic {
+            problem,
+            formulation,
+            materials,
+            tolerance: 1e-8,
+            max_iterations: 25,
+            line_search: true,
+            displacements: vec![0.0; n],
+            forces: vec![0.0; n],
+            states,
+        })
+    }
+
+    pub fn problem(&self) -> &LinearStatic<'a> {
+        &self.problem
+    }
+
+    // For changing the loads and constraints between solves.
+    pub fn problem_mut(&mut self) -> &mut LinearStatic<'a> {
+        &mut self.problem
+    }
+
+    pub fn formulation(&self) -> Formulation {
+        self.formulation
+    }
+
+    pub fn set_material(
+        &mut self,
+        elements: &[usize],
+        material: MaterialModel,
+    ) -> Result<(), &'static str> {
+        if elements.iter().any(|&e| e >= self.materials.len()) {
+            return Err("Element index out of range");
+        }
+        for &e in elements.iter() {
+            self.materials[e] = material;
+        }
+        Ok(())
+    }
+
+    pub fn set_tolerance(
+        &mut self,
+        tolerance: f64,
+        max_iterations: usize,
+    ) -> Result<(), &'static str> {
+        if tolerance.is_nan() || tolerance <= 0.0 || max_iterations == 0 {
+            return Err("Tolerance and iteration limit must be positive");
+        }
+        self.tolerance = tolerance;
+        self.max_iterations = max_iterations;
+        Ok(())
+    }
+
+    // Line search along the Newton corrections under load control.
+    pub fn set_line_search(&mut self, line_search: bool) {
+        self.line_search = line_search;
+    }
+
+    // Displacements of the last equilibrium.
+    pub fn displacements(&self) -> Vec<[f64; 3]> {
+        self.nodal(&self.displacements)
+    }
+
+    fn nodal(&self, vector: &[f64]) -> Vec<[f64; 3]> {
+        let k = self.problem.analysis().dofs_per_node();
+        let mut nodal = vec![[0.0; 3]; self.problem.mesh().num_nodes()];
+        for (dof, v) in vector.iter().enumerate() {
+            nodal[dof / k][dof % k] = *v;
+        }
+        nodal
+    }
+
+    fn element_response(
+        &self,
+        element: usize,
+        displacements: &[f64],
+        committed: &[PlasticState],
+    ) -> Result<ElementResponse, &'static str> {
+        let mesh = self.problem.mesh();
+        let analysis = self.problem.analysis();
+        let axisymmetric = analysis == Analysis::Axisymmetric;
+        let element_type = mesh.elements[element].element_type;
+        let points = mesh.element_points(element);
+        let dofs = self.problem.element_dofs(element);
+        let ue: Vec<f64> = dofs.iter().map(|&d| displacements[d]).collect();
+        let material = self.materials[element];
+        let k = analysis.dofs_per_node();
+        let size = dofs.len();
+        let identity = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        let mut forces = vec![0.0; size];
+        let mut stiffness = vec![vec![0.0; size]; size];
+        let mut stresses = Vec::new();
+        let mut states = Vec::new();
+        let rule = QuadratureRule::new(element_type, stiffness_degree(element_type));
+        for ((xi, w), state) in rule.iter().zip(committed.iter()) {
+            let (n, d) = evaluate(element_type, xi);
+            let jacobian = Jacobian::new(element_type, &points, &d)?;
+            let g = jacobian.gradients(&d);
+            let radius: f64 = n
+                .iter()
+                .zip(points.iter())
+                .map(|(n, p)| n * p.get_x())
+                .sum();
+            let mut volume = w * jacobian.determinant;
+            if axisymmetric {
+                volume *= 2.0 * std::f64::consts::PI * radius;
+            }
+            // Deformation gradient, with the hoop stretch for axisymmetry.
+            let mut f = identity;
+            for (a, ga) in g.iter().enumerate() {
+                for (i, row) in f.iter_mut().enumerate().take(k) {
+                    for (fij, gj) in row.iter_mut().zip(ga.iter()) {
+                        *fij += ue[a * k + i] * gj;
+                    }
+                }
+            }
+            if axisymmetric {
+                let radial: f64 = n.iter().enumerate().map(|(a, n)| n * ue[a * k]).sum();
+                f[2][2] = if radius > 1e-12 {
+                    1.0 + radial / radius
+                } else {
+                    f[0][0]
+                };
+            }
+            let strain = match self.formulation {
+                Formulation::SmallStrain => PAIRS.map(|(i, j)| {
+                    if i == j {
+                        f[i][i] - 1.0
+                    } else {
+                        f[i][j] + f[j][i]
+                    }
+                }),
+                _ => {
+                    if determinant(&f) <= 0.0 {
+                        return Err("Element is inverted");
+                    }
+                    PAIRS.map(|(i, j)| {
+                        let c: f64 = (0..3).map(|p| f[p][i] * f[p][j]).sum();
+                        if i == j { 0.5 * (c - 1.0) } else { c }
+                    })
+                }
+            };
+            let (s, c, updated) = material.stress(&strain, state)?;
+            states.push(updated);
+            // Stress, tangent, gradients and radius of the configuration the
+            // equations are written on, and the deformation in the strain
+            // variation.
+            let (stress, tangent, gradients, radius, frame) = match self.formulation {
+                Formulation::SmallStrain => {
+                    stresses.push(s);
+                    (s, c, g, radius, identity)
+                }
+                Formulation::TotalLagrangian => {
+                    stresses.push(cauchy_stress(&f, &s));
+                    (s, c, g, radius, f)
+                }
+                Formulation::UpdatedLagrangian => {
+                    let inverse = inverse(&f);
+                    let current: Vec<[f64; 3]> = g
+                        .iter()
+                        .map(|ga| [0, 1, 2].map(|j| (0..3).map(|m| ga[m] * inverse[m][j]).sum()))
+                        .collect();
+                    volume *= determinant(&f);
+                    let sigma = cauchy_stress(&f, &s);
+                    stresses.push(sigma);
+                    (
+                        sigma,
+                        spatial_tangent(&f, &c),
+                        current,
+                        radius * f[2][2],
+                        identity,
+                    )
+                }
+            };
+            // Hoop strain per radial displacement, the radial strain on the
+            // axis.
+            let hoop: Vec<f64> = n
+                .iter()
+                .zip(gradients.iter())
+                .map(|(n, ga)| if radius > 1e-12 { n / radius } else { ga[0] })
+                .collect();
+            let mut b = vec![vec![0.0; size]; 6];
+            for (a, ga) in gradients.iter().enumerate() {
+                for i in 0..k {
+                    let column = a * k + i;
+                    for (row, &(p, q)) in b.iter_mut().zip(PAIRS.iter()) {
+                        row[column] = if p == q {
+                            frame[i][p] * ga[p]
+                        } else {
+                            frame[i][p] * ga[q] + frame[i][q] * ga[p]
+                        };
+                    }
+                    if axisymmetric {
+                        b[2][column] = if i == 0 { frame[2][2] * hoop[a] } else { 0.0 };
+                    }
+                }
+            }
+            for (row, s) in b.iter().zip(stress.iter()) {
+                for (force, b) in forces.iter_mut().zip(row.iter()) {
+                    *force += volume * b * s;
+                }
+            }
+            let cb: Vec<Vec<f64>> = tangent
+                .iter()
+                .map(|row| {
+                    (0..size)
+                        .map(|j| row.iter().zip(b.iter()).map(|(c, b)| c * b[j]).sum())
+                        .collect()
+                })
+                .collect();
+            for (bi, cbi) in b.iter().zip(cb.iter()) {
+                for i in 0..size {
+                    if bi[i] == 0.0 {
+                        continue;
+                    }
+                    let factor = volume * bi[i];
+                    for (kij, cbij) in stiffness[i].iter_mut().zip(cbi.iter()) {
+                        *kij += factor * cbij;
+                    }
+                }
+            }
+            if self.formulation == Formulation::SmallStrain {
+                continue;
+            }
+            // Initial stress stiffness.
+            let tensor = [
+                [stress[0], stress[3], stress[5]],
+                [stress[3], stress[1], stress[4]],
+                [stress[5], stress[4], stress[2]],
+            ];
+            for (a, ga) in gradients.iter().enumerate() {
+                let sg: Vec<f64> = (0..3)
+                    .map(|i| (0..3).map(|j| tensor[i][j] * ga[j]).sum())
+                    .collect();
+                for (c, gc) in gradients.iter().enumerate() {
+                    let value = volume * dot(&sg, gc);
+                    for i in 0..k {
+                        stiffness[a * k + i][c * k + i] += value;
+                    }
+                    if axisymmetric {
+                        stiffness[a * k][c * k] += volume * stress[2] * hoop[a] * hoop[c];
+                    }
+                }
+            }
+        }
+        Ok((forces, stiffness, stresses, states))
+    }
+
+    fn response(
+        &self,
+        displacements: &[f64],
+        committed: &[Vec<PlasticState>],
+    ) -> Result<Response, &'static str> {
+        let n = self.problem.num_dofs();
+        let mut forces = vec![0.0; n];
+        let mut stiffness = CooMatrix::new(n, n);
+        let mut stresses = Vec::new();
+        let mut states = Vec::new();
+        for (e, committed) in committed.iter().enumerate() {
+            let (fe, ke, s, state) = self.element_response(e, displacements, committed)?;
+            let dofs = self.problem.element_dofs(e);
+            for (&dof, f) in dofs.iter().zip(fe.iter()) {
+                forces[dof] += f;
+            }
+            stiffness.push_block(&dofs, &dofs, &ke)?;
+            stresses.push(s);
+            states.push(state);
+        }
+        Ok(Response {
+            forces,
+            stiffness: stiffness.to_csr(),
+            stresses,
+            states,
+        })
+    }
+
+    // Solutions of the free dof equations of a tangent for several right
+    // hand sides, factorizing once with a direct solver.
+    fn solve_free(
+        &self,
+        step: &Step,
+        stiffness: &CsrMatrix,
+        rhs: &[&[f64]],
+    ) -> Result<Vec<Vec<f64>>, &'static str> {
+        let reduced = restrict(stiffness, &step.free, &step.index);
+        match self.problem.linear_solver() {
+            LinearSolver::Direct(ordering) => {
+                let factors = SparseLdlt::new(&reduced, ordering)?;
+                rhs.iter().map(|b| factors.solve(b)).collect()
+            }
+            solver => rhs.iter().map(|b| solver.solve(&reduced, b)).collect(),
+        }
+    }
+
+    fn moved(
+        &self,
+        step: &Step,
+        point: &PathPoint,
+        correction: &[f64],
+        scale: f64,
+        load_factor: f64,
+    ) -> Result<PathPoint, &'static str> {
+        let mut displacements = point.displacements.clone();
+        for (&dof, du) in step.free.iter().zip(correction.iter()) {
+            displacements[dof] += scale * du;
+        }
+        step.constrain(&mut displacements, load_factor);
+        let response = self.response(&displacements, &point.states)?;
+        Ok(PathPoint {
+            displacements,
+            load_factor,
+            states: point.states.clone(),
+            response,
+        })
+    }
+
+    // Newton iterations from a predicted point, at a fixed load factor or
+    // with the load factor bound by the arc length from a start point.
+    // Returns the converged point and the iteration count.
+    fn correct(
+        &self,
+        step: &Step,
+        mut point: PathPoint,
+        arc: Option<(&[f64], f64)>,
+    ) -> Result<(PathPoint, usize), &'static str> {
+        for iteration in 0.. {
+            let (residual, scale) = step.residual(&point);
+            if dot(&residual, &residual).sqrt() <= self.tolerance * scale {
+                return Ok((point, iteration));
+            }
+            if iteration == self.max_iterations {
+                break;
+            }
+            let stiffness = &point.response.stiffness;
+            point = match arc {
+                None => {
+                    let du = self.solve_free(step, stiffness, &[&residual])?.remove(0);
+                    self.line_search(step, &point, &du, &residual)?
+                }
+                Some((start, length)) => {
+                    let load = step.load(stiffness);
+                    let solutions = self.solve_free(step, stiffness, &[&residual, &load])?;
+                    let (du, dq) = (&solutions[0], &solutions[1]);
+                    // Increment of the step, and with the residual
+                    // correction.
+                    let increment: Vec<f64> = step
+                        .free_values(&point.displacements)
+                        .iter()
+                        .zip(start.iter())
+                        .map(|(u, s)| u - s)
+                        .collect();
+                    let partial: Vec<f64> = increment
+                        .iter()
+                        .zip(du.iter())
+                        .map(|(a, b)| a + b)
+                        .collect();
+                    let a = dot(dq, dq);
+                    let b = 2.0 * dot(&partial, dq);
+                    let c = dot(&partial, &partial) - length * length;
+                    let discriminant = b * b - 4.0 * a * c;
+                    if a == 0.0 || discriminant < 0.0 {
+                        return Err("Arc-length constraint has no solution");
+                    }
+                    let root = discriminant.sqrt();
+                    // The root keeping the increment closest in direction.
+                    let lambda = [(-b + root) / (2.0 * a), (-b - root) / (2.0 * a)]
+                        .into_iter()
+                        .map(|l| {
+                            let turn = dot(&increment, &partial) + l * dot(&increment, dq);
+                            (l, turn)
+                        })
+                        .max_by(|x, y| x.1.total_cmp(&y.1))
+                        .unwrap()
+                        .0;
+                    let correction: Vec<f64> = du
+                        .iter()
+                        .zip(dq.iter())
+                        .map(|(u, q)| u + lambda * q)
+                        .collect();
+                    self.moved(step, &point, &correction, 1.0, point.load_factor + lambda)?
+                }
+            };
+        }
+        Err("Newton iteration did not converge")
+    }
+
+    // Scales a Newton correction to reduce the out of balance force along
+    // it, by secant steps on its projection.
+    fn line_search(
+        &self,
+        step: &Step,
+        point: &PathPoint,
+        correction: &[f64],
+        residual: &[f64],
+    ) -> Result<PathPoint, &'static str> {
+        let g0 = dot(correction, residual);
+        let mut scale = 1.0;
+        let mut trial = self.moved(step, point, correction, scale, point.load_factor)?;
+        if !self.line_search || g0 <= 0.0 {
+            return Ok(trial);
+        }
+        for _ in 0..5 {
+            let g = dot(correction, &step.residual(&trial).0);
+            if g.abs() <= 0.5 * g0 {
+                break;
+            }
+            let next = (scale * g0 / (g0 - g)).clamp(0.1, 1.0);
+            if next.is_nan() || (next - scale).abs() < 1e-3 {
+                break;
+            }
+            scale = next;
+            trial = self.moved(step, point, correction, scale, point.load_factor)?;
+        }
+        Ok(trial)
+    }
+
+    fn load_increment(
+        &self,
+        step: &Step,
+        point: &PathPoint,
+        increment: f64,
+    ) -> Result<(PathPoint, usize), &'static str> {
+        let stiffness = &point.response.stiffness;
+        let load = step.load(stiffness);
+        let rhs: Vec<f64> = step
+            .residual(point)
+            .0
+            .iter()
+            .zip(load.iter())
+            .map(|(r, q)| r + increment * q)
+            .collect();
+        let du = self.solve_free(step, stiffness, &[&rhs])?.remove(0);
+        let predicted = self.moved(step, point, &du, 1.0, point.load_factor + increment)?;
+        self.correct(step, predicted, None)
+    }
+
+    fn arc_increment(
+        &self,
+        step: &Step,
+        point: &PathPoint,
+        length: f64,
+        direction: Option<&[f64]>,
+    ) -> Result<(PathPoint, usize), &'static str> {
+        let stiffness = &point.response.stiffness;
+        let load = step.load(stiffness);
+        let dq = self.solve_free(step, stiffness, &[&load])?.remove(0);
+        let norm = dot(&dq, &dq).sqrt();
+        if norm == 0.0 {
+            return Err("Arc-length control needs a load");
+        }
+        // Continue in the direction of the previous increment.
+        let sign = match direction {
+            Some(d) if dot(d, &dq) < 0.0 => -1.0,
+            _ => 1.0,
+        };
+        let lambda = sign * length / norm;
+        let predicted = self.moved(step, point, &dq, lambda, point.load_factor + lambda)?;
+        let start = step.free_values(&point.displacements);
+        self.correct(step, predicted, Some((&start, length)))
+    }
+
+    pub fn solve(&mut self, control: PathControl) -> Result<NonlinearSolution, &'static str> {
+        if self.problem.has_thermal_load() {
+            return Err("Thermal loads are not supported in nonlinear analysis");
+        }
+        let n = self.problem.num_dofs();
+        let constraints = self.problem.constraints();
+        let (free, index) = free_dofs(n, |dof| constraints.contains_key(&dof));
+        let target = self.problem.load_vector()?;
+        let mut prescribed = vec![0.0; n];
+        for (&dof, &value) in constraints.iter() {
+            prescribed[dof] = value - self.displacements[dof];
+        }
+        let step = Step {
+            pattern: target
+                .iter()
+                .zip(self.forces.iter())
+                .map(|(t, f)| t - f)
+                .collect(),
+            forces: self.forces.clone(),
+            displacements: self.displacements.clone(),
+            prescribed,
+            free,
+            index,
+        };
+        let mut point = PathPoint {
+            displacements: self.displacements.clone(),
+            load_factor: 0.0,
+            states: self.states.clone(),
+            response: self.response(&self.displacements, &self.states)?,
+        };
+        let mut load_factors = Vec::new();
+        let mut displacements = Vec::new();
+        let mut iterations = Vec::new();
+        // Increments shorter than this fraction of the requested ones fail.
+        let smallest = 1e-3;
+        match control {
+            PathControl::Load { increments } => {
+                if increments == 0 {
+                    return Err("Increment count must be positive");
+                }
+                let nominal = 1.0 / increments as f64;
+                let mut size = nominal;
+                while point.load_factor < 1.0 - 1e-12 {
+                    let increment = size.min(1.0 - point.load_factor);
+                    match self.load_increment(&step, &point, increment) {
+                        Ok((next, count)) => {
+                            point = next;
+                            point.states = point.response.states.clone();
+                            load_factors.push(point.load_factor);
+                            displacements.push(self.nodal(&point.displacements));
+                            iterations.push(count);
+                            size = (2.0 * size).min(nominal);
+                        }
+                        Err(error) => {
+                            size *= 0.5;
+                            if size < smallest * nominal {
+                                return Err(error);
+                            }
+                        }
+                    }
+                }
+            }
+            PathControl::ArcLength {
+                length,
+                max_increments,
+            } => {
+                if length.is_nan() || length <= 0.0 {
+                    return Err("Arc length must be positive");
+                }
+                let mut size = length;
+                let mut direction: Option<Vec<f64>> = None;
+                while point.load_factor < 1.0 && load_factors.len() < max_increments {
+                    let (mut next, mut count) =
+                        match self.arc_increment(&step, &point, size, direction.as_deref()) {
+                            Ok(result) => result,
+                            Err(error) => {
+                                size *= 0.5;
+                                if size < smallest * length {
+                                    return Err(error);
+                                }
+                                continue;
+                            }
+                        };
+                    // Land on the full load when passing it.
+                    if next.load_factor > 1.0
+                        && let Ok(last) =
+                            self.load_increment(&step, &point, 1.0 - point.load_factor)
+                    {
+                        (next, count) = last;
+                    }
+                    let (old, new) = (
+                        step.free_values(&point.displacements),
+                        step.free_values(&next.displacements),
+                    );
+                    direction = Some(new.iter().zip(old.iter()).map(|(a, b)| a - b).collect());
+                    point = next;
+                    point.states = point.response.states.clone();
+                    load_factors.push(point.load_factor);
+                    displacements.push(self.nodal(&point.displacements));
+                    iterations.push(count);
+                    // Aim at about six iterations an increment.
+                    let factor = (6.0 / count.max(1) as f64).sqrt().clamp(0.5, 2.0);
+                    size = (size * factor).min(length);
+                }
+            }
+        }
+        let external = step.external(point.load_factor);
+        let reactions: Vec<f64> = point
+            .response
+            .forces
+            .iter()
+            .zip(external.iter())
+            .map(|(f, e)| f - e)
+            .collect();
+        let (stresses, plastic_strains) = self.nodal_fields(&point.response);
+        self.displacements = point.displacements;
+        self.forces = external;
+        self.states = point.states;
+        Ok(NonlinearSolution {
+            load_factors,
+            displacements,
+            iterations,
+            reactions: self.nodal(&reactions),
+            stresses,
+            plastic_strains,
+        })
+    }
+
+    fn nodal_fields(&self, response: &Response) -> (Vec<[f64; 6]>, Vec<f64>) {
+        let mesh = self.problem.mesh();
+        let count = mesh.num_nodes();
+        let mut stresses = vec![[0.0; 6]; count];
+        let mut plastic_strains = vec![0.0; count];
+        let mut shared = vec![0usize; count];
+        for (e, element) in mesh.elements.iter().enumerate() {
+            let points = response.stresses[e].len() as f64;
+            let mut stress = [0.0; 6];
+            for s in response.stresses[e].iter() {
+                for c in 0..6 {
+                    stress[c] += s[c] / points;
+                }
+            }
+            let plastic: f64 = response.states[e]
+                .iter()
+                .map(|s| s.equivalent_plastic_strain / points)
+                .sum();
+            for &node in element.nodes.iter() {
+                for c in 0..6 {
+                    stresses[node][c] += stress[c];
+                }
+                plastic_strains[node] += plastic;
+                shared[node] += 1;
+            }
+        }
+        for node in 0..count {
+            if shared[node] > 0 {
+                let m = shared[node] as f64;
+                stresses[node] = stresses[node].map(|v| v / m);
+                plastic_strains[node] /= m;
+            }
+        }
+        (stresses, plastic_strains)
+    }
+}
@@ -0,0 +1,178 @@
+use crate::sparse::CsrMatrix;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+// Orderings return a permutation whose entry k is the original index of the
+// k-th row and column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Ordering {
+    Natural,
+    // Reverse Cuthill-McKee, reducing bandwidth and profile.
+    ReverseCuthillMcKee,
+    // Approximate minimum degree, reducing fill in sparse factorizations.
+    #[default]
+    ApproximateMinimumDegree,
+}
+
+impl Ordering {
+    pub fn permutation(&self, a: &CsrMatrix) -> Result<Vec<usize>, &'static str> {
+        if a.nrows != a.ncols {
+            return Err("Matrix is not square");
+        }
+        Ok(match self {
+            Ordering::Natural => (0..a.nrows).collect(),
+            Ordering::ReverseCuthillMcKee => reverse_cuthill_mckee(a),
+            Ordering::ApproximateMinimumDegree => approximate_minimum_degree(a),
+        })
+    }
+}
+
+// Off-diagonal adjacency of the symmetrized pattern of A.
+fn adjacency(a: &CsrMatrix) -> Vec<Vec<usize>> {
+    let mut adjacency = vec![Vec::new(); a.nrows];
+    for i in 0..a.nrows {
+        for &j in a.row(i).0.iter() {
+            if i != j {
+                adjacency[i].push(j);
+                adjacency[j].push(i);
+            }
+        }
+    }
+    for list in adjacency.iter_mut() {
+        list.sort_unstable();
+        list.dedup();
+    }
+    adjacency
+}
+
+// Breadth first visit from a root with neighbours taken in order of
+// increasing degree. Returns the visit order and the level of each visited
+// node in that order.
+fn level_structure(
+    adjacency: &[Vec<usize>],
+    root: usize,
+    visited: &mut [bool],
+) -> (Vec<usize>, Vec<usize>) {
+    let mut order = vec![root];
+    let mut level = vec![0];
+    visited[root] = true;
+    let mut head = 0;
+    while head < order.len() {
+        let mut next: Vec<usize> = adjacency[order[head]]
+            .iter()
+            .copied()
+            .filter(|&w| !visited[w])
+            .collect();
+        next.sort_by_key(|&w| (adjacency[w].len(), w));
+        for w in next {
+            visited[w] = true;
+            order.push(w);
+            level.push(level[head] + 1);
+        }
+        head += 1;
+    }
+    for &v in order.iter() {
+        visited[v] = false;
+    }
+    (order, level)
+}
+
+pub fn reverse_cuthill_mckee(a: &CsrMatrix) -> Vec<usize> {
+    let adjacency = adjacency(a);
+    let n = adjacency.len();
+    let mut visited = vec![false; n];
+    let mut permutation = Vec::with_capacity(n);
+    let mut starts: Vec<usize> = (0..n).collect();
+    starts.sort_by_key(|&v| (adjacency[v].len(), v));
+    for start in starts {
+        if visited[start] {
+            continue;
+        }
+        // Pseudo-peripheral root: restart from the lowest degree node of the
+        // last level while the level structure gets deeper.
+        let (mut order, mut level) = level_structure(&adjacency, start, &mut visited);
+        loop {
+            let depth = *level.last().unwrap();
+            let candidate = order
+                .iter()
+                .zip(level.iter())
+                .filter(|(_, l)| **l == depth)
+                .map(|(&v, _)| v)
+                .min_by_key(|&v| (adjacency[v].len(), v))
+                .unwrap();
+            let (next_order, next_level) = level_structure(&adjacency, candidate, &mut visited);
+            if *next_level.last().unwrap() <= depth {
+                break;
+            }
+            order = next_order;
+            level = next_level;
+        }
+        for &v in order.iter() {
+            visited[v] = true;
+        }
+        permutation.extend(order);
+    }
+    permutation.reverse();
+    permutation
+}
+
+// Minimum degree on the quotient graph: eliminated nodes become elements
+// that stand for the cliques they create, so the fill is never formed
+// explicitly. Degrees are the upper bound of Amestoy, Davis and Duff,
+// summing the sizes of adjacent elements instead of their union.
+pub fn approximate_minimum_degree(a: &CsrMatrix) -> Vec<usize> {
+    let mut variables = adjacency(a);
+    let n = variables.len();
+    let mut elements: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut eliminated = vec![false; n];
+    let mut absorbed = vec![false; n];
+    let mut degree: Vec<usize> = variables.iter().map(|v| v.len()).collect();
+    let mut mark = vec![usize::MAX; n];
+    let mut heap: BinaryHeap<Reverse<(usize, usize)>> =
+        (0..n).map(|i| Reverse((degree[i], i))).collect();
+    let mut permutation = Vec::with_capacity(n);
+    while let Some(Reverse((d, p))) = heap.pop() {
+        if eliminated[p] || d != degree[p] {
+            continue;
+        }
+        let k = permutation.len();
+        permutation.push(p);
+        eliminated[p] = true;
+        mark[p] = k;
+
+        // Variables of the new element: neighbours of p and members of the
+        // elements it absorbs.
+        let mut clique = Vec::new();
+        for &j in variables[p].iter() {
+            if !eliminated[j] && mark[j] != k {
+                mark[j] = k;
+                clique.push(j);
+            }
+        }
+        for e in std::mem::take(&mut elements[p]) {
+            for &j in members[e].iter() {
+                if !eliminated[j] && mark[j] != k {
+                    mark[j] = k;
+                    clique.push(j);
+                }
+            }
+            absorbed[e] = true;
+            members[e].clear();
+        }
+        variables[p].clear();
+        members[p] = clique.clone();
+
+        let remaining = n - k - 1;
+        for &i in clique.iter() {
+            elements[i].retain(|&e| !absorbed[e]);
+            elements[i].push(p);
+            // Edges inside the clique are represented by the new element.
+            variables[i].retain(|&j| !eliminated[j] && mark[j] != k);
+            let external: usize = elements[i].iter().map(|&e| members[e].len() - 1).sum();
+            degree[i] = remaining.min(variables[i].len() + external);
+            heap.push(Reverse((degree[i], i)));
+        }
+    }
+    permutation
+}
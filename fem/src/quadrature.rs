@@ -0,0 +1,294 @@
+use crate::mesh::ElementType;
+
+// Integration points in natural coordinates (see `shape`) with weights
+// summing to the measure of the reference element.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuadratureRule {
+    pub points: Vec<[f64; 3]>,
+    pub weights: Vec<f64>,
+}
+
+// Gauss-Legendre points and weights on [-1, 1], exact for polynomials of
+// degree 2n - 1. Points are ascending.
+pub fn gauss_legendre(n: usize) -> (Vec<f64>, Vec<f64>) {
+    let mut points = vec![0.0; n];
+    let mut weights = vec![0.0; n];
+    for i in 0..n.div_ceil(2) {
+        let mut x = (std::f64::consts::PI * (i as f64 + 0.75) / (n as f64 + 0.5)).cos();
+        let mut derivative = 1.0;
+        for _ in 0..100 {
+            let (p, dp) = legendre(n, x);
+            derivative = dp;
+            let step = p / dp;
+            x -= step;
+            if step.abs() < 1e-16 {
+                break;
+            }
+        }
+        let w = 2.0 / ((1.0 - x * x) * derivative * derivative);
+        points[i] = -x;
+        points[n - 1 - i] = x;
+        weights[i] = w;
+        weights[n - 1 - i] = w;
+    }
+    if n % 2 == 1 {
+        points[n / 2] = 0.0;
+    }
+    (points, weights)
+}
+
+// Gauss-Lobatto points and weights on [-1, 1] including both ends, exact
+// for polynomials of degree 2n - 3.
+pub fn gauss_lobatto(n: usize) -> Result<(Vec<f64>, Vec<f64>), &'static str> {
+    if n < 2 {
+        return Err("Gauss-Lobatto rules need at least two points");
+    }
+    let m = n - 1;
+    let mut points = vec![0.0; n];
+    let mut weights = vec![0.0; n];
+    for i in 0..n.div_ceil(2) {
+        let mut x = (std::f64::consts::PI * i as f64 / m as f64).cos();
+        if i > 0 {
+            // Interior points are the roots of the derivative of P_m.
+            for _ in 0..100 {
+                let (p, dp) = legendre(m, x);
+                let ddp = (2.0 * x * dp - (m * (m + 1)) as f64 * p) / (1.0 - x * x);
+                let step = dp / ddp;
+                x -= step;
+                if step.abs() < 1e-16 {
+                    break;
+                }
+            }
+        }
+        let (p, _) = legendre(m, x);
+        let w = 2.0 / ((m * n) as f64 * p * p);
+        points[i] = -x;
+        points[n - 1 - i] = x;
+        weights[i] = w;
+        weights[n - 1 - i] = w;
+    }
+    if n % 2 == 1 {
+        points[n / 2] = 0.0;
+    }
+    Ok((points, weights))
+}
+
+// Legendre polynomial P_n and its derivative.
+fn legendre(n: usize, x: f64) -> (f64, f64) {
+    let (mut p0, mut p1) = (1.0, x);
+    if n == 0 {
+        return (1.0, 0.0);
+    }
+    for k in 2..=n {
+        let p2 = ((2 * k - 1) as f64 * x * p1 - (k - 1) as f64 * p0) / k as f64;
+        p0 = p1;
+        p1 = p2;
+    }
+    (p1, n as f64 * (x * p1 - p0) / (x * x - 1.0))
+}
+
+// Gauss-Legendre points mapped to [0, 1].
+fn unit_gauss(n: usize) -> Vec<(f64, f64)> {
+    let (x, w) = gauss_legendre(n);
+    x.iter()
+        .zip(w.iter())
+        .map(|(x, w)| ((x + 1.0) / 2.0, w / 2.0))
+        .collect()
+}
+
+// Symmetric rules with positive weights on the unit triangle, as
+// (barycentric orbit, weight) with weights summing to one.
+const TRIANGLE_1: &[([f64; 3], f64)] = &[([1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0], 1.0)];
+const TRIANGLE_2: &[([f64; 3], f64)] = &[([2.0 / 3.0, 1.0 / 6.0, 1.0 / 6.0], 1.0 / 3.0)];
+const TRIANGLE_4: &[([f64; 3], f64)] = &[
+    (
+        [0.108103018168070, 0.445948490915965, 0.445948490915965],
+        0.223381589678011,
+    ),
+    (
+        [0.816847572980459, 0.091576213509771, 0.091576213509771],
+        0.109951743655322,
+    ),
+];
+const TRIANGLE_5: &[([f64; 3], f64)] = &[
+    ([1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0], 0.225),
+    (
+        [0.059715871789770, 0.470142064105115, 0.470142064105115],
+        0.132394152788506,
+    ),
+    (
+        [0.797426985353087, 0.101286507323456, 0.101286507323456],
+        0.125939180544827,
+    ),
+];
+
+fn triangle(degree: usize) -> Vec<([f64; 2], f64)> {
+    let orbits = match degree {
+        0 | 1 => TRIANGLE_1,
+        2 => TRIANGLE_2,
+        3 | 4 => TRIANGLE_4,
+        5 => TRIANGLE_5,
+        _ => {
+            // Collapsed Gauss rule: r = u, s = v (1 - u).
+            let n = (degree + 2).div_ceil(2);
+            let g = unit_gauss(n);
+            let mut rule = Vec::with_capacity(n * n);
+            for &(u, wu) in g.iter() {
+                for &(v, wv) in g.iter() {
+                    rule.push(([u, v * (1.0 - u)], wu * wv * (1.0 - u)));
+                }
+            }
+            return rule;
+        }
+    };
+    let mut rule = Vec::new();
+    for &(l, w) in orbits.iter() {
+        // Distinct permutations of the orbit (l[1] == l[2] in all tables).
+        let permutations: &[[usize; 3]] = if l[0] == l[1] {
+            &[[0, 1, 2]]
+        } else {
+            &[[0, 1, 2], [1, 0, 2], [1, 2, 0]]
+        };
+        for p in permutations.iter() {
+            rule.push(([l[p[1]], l[p[2]]], w / 2.0));
+        }
+    }
+    rule
+}
+
+fn tetrahedron(degree: usize) -> Vec<([f64; 3], f64)> {
+    match degree {
+        0 | 1 => vec![([0.25; 3], 1.0 / 6.0)],
+        2 => {
+            let (a, b) = (0.5854101966249685, 0.1381966011250105);
+            [[b, b, b], [a, b, b], [b, a, b], [b, b, a]]
+                .into_iter()
+                .map(|p| (p, 1.0 / 24.0))
+                .collect()
+        }
+        _ => {
+            // Collapsed Gauss rule: r = u, s = v (1 - u), t = w (1 - u) (1 - v).
+            let n = (degree + 3).div_ceil(2);
+            let g = unit_gauss(n);
+            let mut rule = Vec::with_capacity(n * n * n);
+            for &(u, wu) in g.iter() {
+                for &(v, wv) in g.iter() {
+                    for &(w, ww) in g.iter() {
+                        let point = [u, v * (1.0 - u), w * (1.0 - u) * (1.0 - v)];
+                        let weight = wu * wv * ww * (1.0 - u) * (1.0 - u) * (1.0 - v);
+                        rule.push((point, weight));
+                    }
+                }
+            }
+            rule
+        }
+    }
+}
+
+impl QuadratureRule {
+    // Gauss-type rule exact for polynomials of the given total degree in
+    // the natural coordinates (per direction for tensor product elements).
+    // Pyramid rules integrate the collapsed polynomial space exactly.
+    pub fn new(element_type: ElementType, degree: usize) -> Self {
+        let gauss = |n: usize| {
+            let (x, w) = gauss_legendre(n);
+            x.into_iter().zip(w).collect::<Vec<_>>()
+        };
+        let n = degree / 2 + 1;
+        let mut rule = QuadratureRule {
+            points: Vec::new(),
+            weights: Vec::new(),
+        };
+        match element_type.linear() {
+            ElementType::Line2 | ElementType::Quad4 | ElementType::Hex8 => {
+                return QuadratureRule::tensor(&gauss(n), element_type.dimension());
+            }
+            ElementType::Tri3 => {
+                for (p, w) in triangle(degree) {
+                    rule.push([p[0], p[1], 0.0], w);
+                }
+            }
+            ElementType::Tet4 => {
+                for (p, w) in tetrahedron(degree) {
+                    rule.push(p, w);
+                }
+            }
+            ElementType::Wedge6 => {
+                for (p, w) in triangle(degree) {
+                    for &(z, wz) in gauss(n).iter() {
+                        rule.push([p[0], p[1], z], w * wz);
+                    }
+                }
+            }
+            _ => {
+                // Base square shrinking to the apex: x (1 - t), y (1 - t), t.
+                let g = gauss((degree + 3).div_ceil(2));
+                for &(t, wt) in unit_gauss((degree + 3).div_ceil(2)).iter() {
+                    for &(x, wx) in g.iter() {
+                        for &(y, wy) in g.iter() {
+                            let s = 1.0 - t;
+                            rule.push([x * s, y * s, t], wx * wy * wt * s * s);
+                        }
+                    }
+                }
+            }
+        }
+        rule
+    }
+
+    // Gauss-Lobatto rule with n points per direction, placing points on the
+    // nodes of lines, quadrilaterals and hexahedra for nodal lumping.
+    pub fn lobatto(element_type: ElementType, n: usize) -> Result<Self, &'static str> {
+        match element_type.linear() {
+            ElementType::Line2 | ElementType::Quad4 | ElementType::Hex8 => {
+                let (x, w) = gauss_lobatto(n)?;
+                let line: Vec<(f64, f64)> = x.into_iter().zip(w).collect();
+                Ok(QuadratureRule::tensor(&line, element_type.dimension()))
+            }
+            _ => Err("Gauss-Lobatto rules need a tensor product element"),
+        }
+    }
+
+    fn tensor(line: &[(f64, f64)], dimension: usize) -> Self {
+        let mut rule = QuadratureRule {
+            points: Vec::new(),
+            weights: Vec::new(),
+        };
+        let single = [(0.0, 1.0)];
+        let axis = |k: usize| if k < dimension { line } else { &single[..] };
+        for &(z, wz) in axis(2).iter() {
+            for &(y, wy) in axis(1).iter() {
+                for &(x, wx) in axis(0).iter() {
+                    rule.push([x, y, z], wx * wy * wz);
+                }
+            }
+        }
+        rule
+    }
+
+    fn push(&mut self, point: [f64; 3], weight: f64) {
+        self.points.push(point);
+        self.weights.push(weight);
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ([f64; 3], f64)> + '_ {
+        self.points
+            .iter()
+            .copied()
+            .zip(self.weights.iter().copied())
+    }
+}
+
+// Degree integrating the mass matrix of an undistorted element exactly,
+// which also fully integrates its stiffness.
+pub fn full_degree(element_type: ElementType) -> usize {
+    if element_type.is_quadratic() { 4 } else { 2 }
+}
@@ -0,0 +1,482 @@
+use crate::mesh::ElementType;
+use geom::Point3d;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+// Natural coordinates:
+// - lines, quadrilaterals and hexahedra span [-1, 1] in each direction;
+// - triangles and tetrahedra use the unit simplex, r, s, t >= 0,
+//   r + s + t <= 1, with node 0 at the origin;
+// - wedges are the unit triangle in (r, s) times [-1, 1] in t;
+// - pyramids have the base [-1, 1]^2 at t = 0 and the apex at t = 1.
+
+// Value with its gradient with respect to the three natural coordinates,
+// so each shape function is written once and differentiated exactly.
+#[derive(Debug, Clone, Copy)]
+struct Dual {
+    v: f64,
+    d: [f64; 3],
+}
+
+impl Dual {
+    fn constant(v: f64) -> Self {
+        Dual { v, d: [0.0; 3] }
+    }
+
+    fn variables(xi: [f64; 3]) -> [Dual; 3] {
+        let mut x = xi.map(Dual::constant);
+        for (i, x) in x.iter_mut().enumerate() {
+            x.d[i] = 1.0;
+        }
+        x
+    }
+}
+
+impl Add for Dual {
+    type Output = Dual;
+    fn add(self, o: Dual) -> Dual {
+        Dual {
+            v: self.v + o.v,
+            d: [self.d[0] + o.d[0], self.d[1] + o.d[1], self.d[2] + o.d[2]],
+        }
+    }
+}
+
+impl Sub for Dual {
+    type Output = Dual;
+    fn sub(self, o: Dual) -> Dual {
+        self + (-o)
+    }
+}
+
+impl Neg for Dual {
+    type Output = Dual;
+    fn neg(self) -> Dual {
+        Dual {
+            v: -self.v,
+            d: self.d.map(|d| -d),
+        }
+    }
+}
+
+impl Mul for Dual {
+    type Output = Dual;
+    fn mul(self, o: Dual) -> Dual {
+        Dual {
+            v: self.v * o.v,
+            d: [
+                self.d[0] * o.v + self.v * o.d[0],
+                self.d[1] * o.v + self.v * o.d[1],
+                self.d[2] * o.v + self.v * o.d[2],
+            ],
+        }
+    }
+}
+
+impl Div for Dual {
+    type Output = Dual;
+    fn div(self, o: Dual) -> Dual {
+        let q = self.v / o.v;
+        Dual {
+            v: q,
+            d: [
+                (self.d[0] - q * o.d[0]) / o.v,
+                (self.d[1] - q * o.d[1]) / o.v,
+                (self.d[2] - q * o.d[2]) / o.v,
+            ],
+        }
+    }
+}
+
+impl Add<f64> for Dual {
+    type Output = Dual;
+    fn add(self, o: f64) -> Dual {
+        self + Dual::constant(o)
+    }
+}
+
+impl Sub<f64> for Dual {
+    type Output = Dual;
+    fn sub(self, o: f64) -> Dual {
+        self - Dual::constant(o)
+    }
+}
+
+impl Mul<f64> for Dual {
+    type Output = Dual;
+    fn mul(self, o: f64) -> Dual {
+        Dual {
+            v: self.v * o,
+            d: self.d.map(|d| d * o),
+        }
+    }
+}
+
+const SQUARE: [[f64; 2]; 4] = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]];
+
+fn corner_coordinates(element_type: ElementType) -> Vec<[f64; 3]> {
+    match element_type.linear() {
+        ElementType::Line2 => vec![[-1.0, 0.0, 0.0], [1.0, 0.0, 0.0]],
+        ElementType::Tri3 => vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+        ElementType::Quad4 => SQUARE.iter().map(|c| [c[0], c[1], 0.0]).collect(),
+        ElementType::Tet4 => vec![
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ],
+        ElementType::Hex8 => [-1.0, 1.0]
+            .iter()
+            .flat_map(|&z| SQUARE.iter().map(move |c| [c[0], c[1], z]))
+            .collect(),
+        ElementType::Wedge6 => [-1.0, 1.0]
+            .iter()
+            .flat_map(|&z| [[0.0, 0.0, z], [1.0, 0.0, z], [0.0, 1.0, z]])
+            .collect(),
+        _ => {
+            let mut c: Vec<[f64; 3]> = SQUARE.iter().map(|c| [c[0], c[1], 0.0]).collect();
+            c.push([0.0, 0.0, 1.0]);
+            c
+        }
+    }
+}
+
+fn average(coordinates: &[[f64; 3]], nodes: &[usize]) -> [f64; 3] {
+    let m = nodes.len() as f64;
+    let mut c = [0.0; 3];
+    for &n in nodes.iter() {
+        for k in 0..3 {
+            c[k] += coordinates[n][k] / m;
+        }
+    }
+    c
+}
+
+// Natural coordinates of the nodes. Midside nodes sit halfway along their
+// edge, face and body nodes at the centre of their corners.
+pub fn node_coordinates(element_type: ElementType) -> Vec<[f64; 3]> {
+    let mut coordinates = corner_coordinates(element_type);
+    let corners = coordinates.len();
+    for k in corners..element_type.num_nodes() {
+        let edge = element_type.edges().iter().find(|e| e.get(2) == Some(&k));
+        let face = element_type.faces().iter().find(|f| f.last() == Some(&k));
+        let c = match (edge, face) {
+            (Some(e), _) => average(&coordinates, &e[..2]),
+            (None, Some(f)) => average(&coordinates, &f[..4]),
+            _ => average(&coordinates, &(0..corners).collect::<Vec<_>>()),
+        };
+        coordinates.push(c);
+    }
+    coordinates
+}
+
+// Lagrange polynomial on the nodes -1, 0, 1 that is one at `node`.
+fn lagrange(x: Dual, node: f64) -> Dual {
+    if node < -0.5 {
+        x * (x - 1.0) * 0.5
+    } else if node > 0.5 {
+        x * (x + 1.0) * 0.5
+    } else {
+        (Dual::constant(1.0) - x) * (x + 1.0)
+    }
+}
+
+fn linear(x: Dual, node: f64) -> Dual {
+    (x * node + 1.0) * 0.5
+}
+
+fn evaluate_dual(element_type: ElementType, xi: [f64; 3]) -> Vec<Dual> {
+    let x = Dual::variables(xi);
+    let one = Dual::constant(1.0);
+    let nodes = node_coordinates(element_type);
+    let dimension = element_type.dimension();
+    match element_type {
+        ElementType::Line2 | ElementType::Quad4 | ElementType::Hex8 => nodes
+            .iter()
+            .map(|c| (0..dimension).fold(one, |n, k| n * linear(x[k], c[k])))
+            .collect(),
+        ElementType::Line3 | ElementType::Quad9 | ElementType::Hex27 => nodes
+            .iter()
+            .map(|c| (0..dimension).fold(one, |n, k| n * lagrange(x[k], c[k])))
+            .collect(),
+        ElementType::Quad8 | ElementType::Hex20 => nodes
+            .iter()
+            .map(|c| {
+                let zero = (0..dimension).find(|&k| c[k] == 0.0);
+                let mut n = one;
+                for k in 0..dimension {
+                    n = if Some(k) == zero {
+                        n * (one - x[k] * x[k])
+                    } else {
+                        n * (x[k] * c[k] + 1.0)
+                    };
+                }
+                match zero {
+                    Some(_) => n * (2.0 / (1 << dimension) as f64),
+                    None => {
+                        let s = (0..dimension)
+                            .fold(Dual::constant(1.0 - dimension as f64), |s, k| {
+                                s + x[k] * c[k]
+                            });
+                        n * s * (1.0 / (1 << dimension) as f64)
+                    }
+                }
+            })
+            .collect(),
+        ElementType::Tri3 | ElementType::Tri6 | ElementType::Tet4 | ElementType::Tet10 => {
+            // Barycentric coordinates, the first one belonging to node 0.
+            let mut l = vec![one - x[0] - x[1]];
+            l.extend(x[..dimension].iter().copied());
+            if dimension == 3 {
+                l[0] = l[0] - x[2];
+            }
+            if !element_type.is_quadratic() {
+                return l;
+            }
+            let mut n: Vec<Dual> = l.iter().map(|&l| l * (l * 2.0 - 1.0)).collect();
+            for e in element_type.edges() {
+                n.push(l[e[0]] * l[e[1]] * 4.0);
+            }
+            n
+        }
+        ElementType::Wedge6 => {
+            let l = [one - x[0] - x[1], x[0], x[1]];
+            nodes
+                .iter()
+                .enumerate()
+                .map(|(i, c)| l[i % 3] * linear(x[2], c[2]))
+                .collect()
+        }
+        ElementType::Pyramid5 => {
+            // Rational functions of Bedrosian; the apex is approached but not
+            // evaluated exactly, where the derivatives are undefined.
+            let gap = Dual {
+                v: (1.0 - xi[2]).max(1e-12),
+                d: [0.0, 0.0, -1.0],
+            };
+            let mut n: Vec<Dual> = SQUARE
+                .iter()
+                .map(|c| {
+                    let bilinear = (x[0] * c[0] + 1.0) * (x[1] * c[1] + 1.0);
+                    let twist = x[0] * x[1] * x[2] * (c[0] * c[1]) / gap;
+                    (bilinear - x[2] + twist) * 0.25
+                })
+                .collect();
+            n.push(x[2]);
+            n
+        }
+    }
+}
+
+pub fn shape_functions(element_type: ElementType, xi: [f64; 3]) -> Vec<f64> {
+    evaluate_dual(element_type, xi)
+        .iter()
+        .map(|n| n.v)
+        .collect()
+}
+
+// Derivatives with respect to the natural coordinates; components beyond
+// the element dimension are zero.
+pub fn shape_derivatives(element_type: ElementType, xi: [f64; 3]) -> Vec<[f64; 3]> {
+    evaluate_dual(element_type, xi)
+        .iter()
+        .map(|n| n.d)
+        .collect()
+}
+
+pub fn evaluate(element_type: ElementType, xi: [f64; 3]) -> (Vec<f64>, Vec<[f64; 3]>) {
+    let n = evaluate_dual(element_type, xi);
+    (
+        n.iter().map(|n| n.v).collect(),
+        n.iter().map(|n| n.d).collect(),
+    )
+}
+
+pub fn interpolate(element_type: ElementType, points: &[Point3d], xi: [f64; 3]) -> Point3d {
+    let n = shape_functions(element_type, xi);
+    let mut x = [0.0; 3];
+    for (n, p) in n.iter().zip(points.iter()) {
+        let (px, py, pz) = p.get_coords();
+        x = [x[0] + n * px, x[1] + n * py, x[2] + n * pz];
+    }
+    Point3d::from_coords(x[0], x[1], x[2])
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+// Mapping from natural to global coordinates at a point. Lines and surfaces
+// may be embedded anywhere in space: the determinant is then the length or
+// area scale, and gradients lie in the tangent line or plane.
+#[derive(Debug, Clone, Copy)]
+pub struct Jacobian {
+    pub dimension: usize,
+    // Row i is the derivative of the position with respect to natural
+    // coordinate i; rows beyond the dimension are zero.
+    pub matrix: [[f64; 3]; 3],
+    pub determinant: f64,
+    // Dual basis of the rows, so that the global gradient of a field is
+    // sum_i dual[i] * d(field)/d(xi_i).
+    pub dual: [[f64; 3]; 3],
+}
+
+impl Jacobian {
+    pub fn new(
+        element_type: ElementType,
+        points: &[Point3d],
+        derivatives: &[[f64; 3]],
+    ) -> Result<Self, &'static str> {
+        if points.len() != element_type.num_nodes() || derivatives.len() != points.len() {
+            return Err("Point count does not match element type");
+        }
+        let dimension = element_type.dimension();
+        let mut matrix = [[0.0; 3]; 3];
+        for (p, d) in points.iter().zip(derivatives.iter()) {
+            let (x, y, z) = p.get_coords();
+            for i in 0..dimension {
+                matrix[i][0] += d[i] * x;
+                matrix[i][1] += d[i] * y;
+                matrix[i][2] += d[i] * z;
+            }
+        }
+        let mut dual = [[0.0; 3]; 3];
+        let determinant = match dimension {
+            1 => {
+                let g = dot(&matrix[0], &matrix[0]);
+                dual[0] = matrix[0].map(|v| v / g);
+                g.sqrt()
+            }
+            2 => {
+                // A surface has no fixed orientation, so the area scale is
+                // signed against the normal of its corner polygon (Newell)
+                // and folded elements come out non-positive.
+                let corners = &points[..element_type.num_corners()];
+                let (ox, oy, oz) = corners[0].get_coords();
+                let relative: Vec<[f64; 3]> = corners
+                    .iter()
+                    .map(|p| {
+                        let (x, y, z) = p.get_coords();
+                        [x - ox, y - oy, z - oz]
+                    })
+                    .collect();
+                let mut normal = [0.0; 3];
+                for (k, p) in relative.iter().enumerate() {
+                    let n = cross(p, &relative[(k + 1) % relative.len()]);
+                    for i in 0..3 {
+                        normal[i] += n[i];
+                    }
+                }
+                let (a, b) = (&matrix[0], &matrix[1]);
+                let det = dot(&cross(a, b), &normal) / dot(&normal, &normal).sqrt();
+                if det <= 0.0 {
+                    return Err("Element Jacobian is not positive");
+                }
+                let (g00, g01, g11) = (dot(a, a), dot(a, b), dot(b, b));
+                let gram = g00 * g11 - g01 * g01;
+                for k in 0..3 {
+                    dual[0][k] = (g11 * a[k] - g01 * b[k]) / gram;
+                    dual[1][k] = (g00 * b[k] - g01 * a[k]) / gram;
+                }
+                det
+            }
+            _ => {
+                let c = [
+                    cross(&matrix[1], &matrix[2]),
+                    cross(&matrix[2], &matrix[0]),
+                    cross(&matrix[0], &matrix[1]),
+                ];
+                let det = dot(&matrix[0], &c[0]);
+                if det <= 0.0 {
+                    return Err("Element Jacobian is not positive");
+                }
+                for i in 0..3 {
+                    dual[i] = c[i].map(|v| v / det);
+                }
+                det
+            }
+        };
+        if !determinant.is_finite() || determinant <= 0.0 {
+            return Err("Degenerate element Jacobian");
+        }
+        Ok(Jacobian {
+            dimension,
+            matrix,
+            determinant,
+            dual,
+        })
+    }
+
+    pub fn at(
+        element_type: ElementType,
+        points: &[Point3d],
+        xi: [f64; 3],
+    ) -> Result<Self, &'static str> {
+        Jacobian::new(element_type, points, &shape_derivatives(element_type, xi))
+    }
+
+    // Global gradients from natural derivatives.
+    pub fn gradients(&self, derivatives: &[[f64; 3]]) -> Vec<[f64; 3]> {
+        derivatives
+            .iter()
+            .map(|d| {
+                let mut g = [0.0; 3];
+                for (dual, d) in self.dual[..self.dimension].iter().zip(d.iter()) {
+                    for (g, v) in g.iter_mut().zip(dual.iter()) {
+                        *g += v * d;
+                    }
+                }
+                g
+            })
+            .collect()
+    }
+}
+
+// Natural coordinates of a global point by Newton iteration, for solid
+// elements and surface elements lying in a plane. Fails if the iteration
+// does not converge; the result may lie outside the element.
+pub fn inverse_map(
+    element_type: ElementType,
+    points: &[Point3d],
+    point: &Point3d,
+) -> Result<[f64; 3], &'static str> {
+    if element_type.dimension() < 2 {
+        return Err("Inverse mapping needs a surface or solid element");
+    }
+    let coordinates = node_coordinates(element_type);
+    let mut xi = average(&coordinates, &(0..coordinates.len()).collect::<Vec<_>>());
+    let target = point.get_coords();
+    let size = points
+        .iter()
+        .map(|p| {
+            let d = [
+                p.get_x() - points[0].get_x(),
+                p.get_y() - points[0].get_y(),
+                p.get_z() - points[0].get_z(),
+            ];
+            dot(&d, &d).sqrt()
+        })
+        .fold(0.0, f64::max);
+    for _ in 0..50 {
+        let x = interpolate(element_type, points, xi).get_coords();
+        let r = [target.0 - x.0, target.1 - x.1, target.2 - x.2];
+        let jacobian = Jacobian::at(element_type, points, xi)?;
+        let mut converged = true;
+        for (xi, dual) in xi.iter_mut().zip(jacobian.dual.iter()) {
+            let step = dot(dual, &r);
+            *xi += step;
+            converged &= step.abs() < 1e-14;
+        }
+        if converged || dot(&r, &r).sqrt() <= 1e-14 * size {
+            return Ok(xi);
+        }
+    }
+    Err("Inverse mapping did not converge")
+}
@@ -0,0 +1,950 @@
+use crate::material::IsotropicElastic;
+use crate::mesh::{ElementType, Mesh};
+use crate::quadrature::{QuadratureRule, full_degree, gauss_legendre};
+use crate::shape::{evaluate, node_coordinates};
+use crate::solver::LinearSolver;
+use crate::sparse::{CooMatrix, CsrMatrix};
+use std::collections::BTreeMap;
+
+// Shell dofs are ux, uy, uz, rx, ry, rz at every node in global axes.
+// Elements are degenerated solids: the position through the thickness is
+// x = sum N_a (X_a + zeta h / 2 V_a) with V_a the unit normal of the element
+// at node a, and the displacement adds zeta h / 2 (theta_a x V_a). Flat
+// meshes in the xy plane are Mindlin-Reissner plates.
+//
+// Transverse shear locking is avoided with the MITC3 and MITC4 assumed
+// strain fields on Tri3 and Quad4 and with selective reduced integration on
+// Quad8 and Quad9. The rotation about the normal is tied to the in-plane
+// rotation of the midsurface by a Hughes-Brezzi penalty.
+//
+// Local axes of an element point: z along the normal, x the projection of
+// the reference direction (global x by default) and y = z cross x. Strains
+// and stresses are in local axes ordered xx, yy, zz, xy, yz, zx, with
+// sigma_zz = 0.
+
+// Shear correction factor of the transverse shear stiffness.
+const SHEAR_CORRECTION: f64 = 5.0 / 6.0;
+// Drilling penalty relative to the in-plane shear stiffness; small enough
+// not to stiffen membranes, large enough to keep drilling rotations in
+// check.
+const DRILLING: f64 = 1e-3;
+
+// Orthotropic ply in its material axes, 1 along the fibres.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lamina {
+    pub e1: f64,
+    pub e2: f64,
+    pub nu12: f64,
+    pub g12: f64,
+    pub g13: f64,
+    pub g23: f64,
+}
+
+impl Lamina {
+    pub fn new(
+        e1: f64,
+        e2: f64,
+        nu12: f64,
+        g12: f64,
+        g13: f64,
+        g23: f64,
+    ) -> Result<Self, &'static str> {
+        if [e1, e2, g12, g13, g23]
+            .iter()
+            .any(|v| v.is_nan() || *v <= 0.0)
+        {
+            return Err("Lamina moduli must be positive");
+        }
+        if nu12.is_nan() || nu12 * nu12 >= e1 / e2 {
+            return Err("Lamina Poisson ratio is out of range");
+        }
+        Ok(Lamina {
+            e1,
+            e2,
+            nu12,
+            g12,
+            g13,
+            g23,
+        })
+    }
+
+    pub fn isotropic(material: &IsotropicElastic) -> Self {
+        let g = material.young / (2.0 * (1.0 + material.poisson));
+        Lamina {
+            e1: material.young,
+            e2: material.young,
+            nu12: material.poisson,
+            g12: g,
+            g13: g,
+            g23: g,
+        }
+    }
+
+    // Plane stress stiffness in the material axes, ordered 11, 22, 12.
+    pub fn plane_stress_matrix(&self) -> [[f64; 3]; 3] {
+        let nu21 = self.nu12 * self.e2 / self.e1;
+        let d = 1.0 - self.nu12 * nu21;
+        [
+            [self.e1 / d, self.nu12 * self.e2 / d, 0.0],
+            [self.nu12 * self.e2 / d, self.e2 / d, 0.0],
+            [0.0, 0.0, self.g12],
+        ]
+    }
+}
+
+// Ply of a layup with its fibres at `angle` radians from the local x axis,
+// counterclockwise about the normal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layer {
+    pub thickness: f64,
+    pub lamina: Lamina,
+    pub angle: f64,
+}
+
+impl Layer {
+    pub fn new(thickness: f64, lamina: Lamina, angle: f64) -> Self {
+        Layer {
+            thickness,
+            lamina,
+            angle,
+        }
+    }
+
+    // In-plane stiffness (xx, yy, xy) and transverse shear stiffness
+    // (yz, zx) in the local axes, without shear correction.
+    pub fn matrices(&self) -> ([[f64; 3]; 3], [[f64; 2]; 2]) {
+        let q = self.lamina.plane_stress_matrix();
+        let (n, m) = self.angle.sin_cos();
+        // Material strains from local ones.
+        let t = [
+            [m * m, n * n, m * n],
+            [n * n, m * m, -m * n],
+            [-2.0 * m * n, 2.0 * m * n, m * m - n * n],
+        ];
+        let in_plane = std::array::from_fn(|i| {
+            std::array::from_fn(|j| {
+                (0..3)
+                    .map(|a| (0..3).map(|b| t[a][i] * q[a][b] * t[b][j]).sum::<f64>())
+                    .sum()
+            })
+        });
+        // Material (23, 13) shear strains from local (yz, zx) ones.
+        let r = [[m, -n], [n, m]];
+        let g = [self.lamina.g23, self.lamina.g13];
+        let shear = std::array::from_fn(|i| {
+            std::array::from_fn(|j| (0..2).map(|a| r[a][i] * g[a] * r[a][j]).sum())
+        });
+        (in_plane, shear)
+    }
+
+    // Stresses in the material axes from stresses in the local axes.
+    pub fn material_stress(&self, stress: &[f64; 6]) -> [f64; 6] {
+        let (n, m) = self.angle.sin_cos();
+        let [xx, yy, _, xy, yz, zx] = *stress;
+        [
+            m * m * xx + n * n * yy + 2.0 * m * n * xy,
+            n * n * xx + m * m * yy - 2.0 * m * n * xy,
+            0.0,
+            m * n * (yy - xx) + (m * m - n * n) * xy,
+            m * yz - n * zx,
+            n * yz + m * zx,
+        ]
+    }
+}
+
+// Plies stacked from the bottom to the top face along the element normal,
+// with the midsurface halfway through the total thickness.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layup {
+    pub layers: Vec<Layer>,
+}
+
+impl Layup {
+    pub fn new(layers: Vec<Layer>) -> Result<Self, &'static str> {
+        if layers.is_empty() {
+            return Err("Layup has no layers");
+        }
+        if layers
+            .iter()
+            .any(|l| l.thickness.is_nan() || l.thickness <= 0.0)
+        {
+            return Err("Layer thickness must be positive");
+        }
+        Ok(Layup { layers })
+    }
+
+    pub fn homogeneous(thickness: f64, material: &IsotropicElastic) -> Result<Self, &'static str> {
+        Layup::new(vec![Layer::new(
+            thickness,
+            Lamina::isotropic(material),
+            0.0,
+        )])
+    }
+
+    pub fn thickness(&self) -> f64 {
+        self.layers.iter().map(|l| l.thickness).sum()
+    }
+
+    // Layer interface coordinates from -h / 2 to h / 2.
+    pub fn interfaces(&self) -> Vec<f64> {
+        let mut z = vec![-0.5 * self.thickness()];
+        for layer in self.layers.iter() {
+            z.push(z.last().unwrap() + layer.thickness);
+        }
+        z
+    }
+
+    // Classical laminate stiffnesses A, B and D relating the membrane
+    // forces and bending moments to membrane strains and curvatures.
+    pub fn abd(&self) -> [[[f64; 3]; 3]; 3] {
+        let z = self.interfaces();
+        let mut abd = [[[0.0; 3]; 3]; 3];
+        for (k, layer) in self.layers.iter().enumerate() {
+            let q = layer.matrices().0;
+            for (p, m) in abd.iter_mut().enumerate() {
+                let power = p as i32 + 1;
+                let factor = (z[k + 1].powi(power) - z[k].powi(power)) / power as f64;
+                for i in 0..3 {
+                    for j in 0..3 {
+                        m[i][j] += q[i][j] * factor;
+                    }
+                }
+            }
+        }
+        abd
+    }
+
+    // Transverse shear stiffness (yz, zx) including the shear correction.
+    pub fn shear_stiffness(&self) -> [[f64; 2]; 2] {
+        let mut s = [[0.0; 2]; 2];
+        for layer in self.layers.iter() {
+            let c = layer.matrices().1;
+            for i in 0..2 {
+                for j in 0..2 {
+                    s[i][j] += SHEAR_CORRECTION * c[i][j] * layer.thickness;
+                }
+            }
+        }
+        s
+    }
+
+    // Integration points through the thickness as (layer, zeta, weight),
+    // two per layer, with zeta = 2 z / h and weights in zeta.
+    fn thickness_points(&self) -> Vec<(usize, f64, f64)> {
+        let h = self.thickness();
+        let z = self.interfaces();
+        let (x, w) = gauss_legendre(2);
+        (0..self.layers.len())
+            .flat_map(|k| {
+                let (mid, half) = (0.5 * (z[k] + z[k + 1]), 0.5 * (z[k + 1] - z[k]));
+                x.iter()
+                    .zip(w.iter())
+                    .map(move |(x, w)| (k, 2.0 * (mid + half * x) / h, 2.0 * half * w / h))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShellSolution {
+    pub displacements: Vec<[f64; 6]>,
+    pub reactions: Vec<[f64; 6]>,
+}
+
+// Stress at a height z above the midsurface, in the local axes and in the
+// material axes of its layer. Transverse shear follows from equilibrium of
+// the bending stresses and vanishes on both faces.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThicknessStress {
+    pub z: f64,
+    pub layer: usize,
+    pub stress: [f64; 6],
+    pub material_stress: [f64; 6],
+}
+
+// Midsurface node positions and unit normals of an element.
+struct Surface {
+    element_type: ElementType,
+    points: Vec<[f64; 3]>,
+    directors: Vec<[f64; 3]>,
+    thickness: f64,
+}
+
+// Shape functions, covariant basis, local axes and the components of the
+// contravariant basis on the local axes, c[k][i] = e_k . g^i, at a point.
+struct Basis {
+    n: Vec<f64>,
+    d: Vec<[f64; 3]>,
+    g: [[f64; 3]; 3],
+    c: [[f64; 3]; 3],
+    axes: [[f64; 3]; 3],
+    determinant: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Shell<'a> {
+    mesh: &'a Mesh,
+    layups: Vec<Layup>,
+    reference: [f64; 3],
+    constraints: BTreeMap<usize, f64>,
+    loads: Vec<f64>,
+    pressures: Vec<(usize, f64)>,
+    body_force: [f64; 3],
+    linear_solver: LinearSolver,
+}
+
+impl<'a> Shell<'a> {
+    pub fn new(mesh: &'a Mesh, layup: Layup) -> Result<Self, &'static str> {
+        if mesh.elements.iter().any(|e| {
+            !matches!(
+                e.element_type,
+                ElementType::Tri3 | ElementType::Quad4 | ElementType::Quad8 | ElementType::Quad9
+            )
+        }) {
+            return Err("Shells need Tri3, Quad4, Quad8 or Quad9 elements");
+        }
+        mesh.validate()?;
+        Ok(Shell {
+            mesh,
+            layups: vec![layup; mesh.num_elements()],
+            reference: [1.0, 0.0, 0.0],
+            constraints: BTreeMap::new(),
+            loads: vec![0.0; 6 * mesh.num_nodes()],
+            pressures: Vec::new(),
+            body_force: [0.0; 3],
+            linear_solver: LinearSolver::default(),
+        })
+    }
+
+    pub fn mesh(&self) -> &Mesh {
+        self.mesh
+    }
+
+    pub fn num_dofs(&self) -> usize {
+        6 * self.mesh.num_nodes()
+    }
+
+    pub fn layup(&self, element: usize) -> Option<&Layup> {
+        self.layups.get(element)
+    }
+
+    pub fn set_layup(&mut self, elements: &[usize], layup: Layup) -> Result<(), &'static str> {
+        if elements.iter().any(|&e| e >= self.layups.len()) {
+            return Err("Element index out of range");
+        }
+        for &e in elements.iter() {
+            self.layups[e] = layup.clone();
+        }
+        Ok(())
+    }
+
+    // Direction projected on the elements to give their local x axes, from
+    // which ply angles are measured. Where it is nearly normal to the
+    // surface, global y and then global x are used instead.
+    pub fn set_reference_direction(&mut self, direction: [f64; 3]) -> Result<(), &'static str> {
+        self.reference = normalize(direction).map_err(|_| "Reference direction is zero")?;
+        Ok(())
+    }
+
+    pub fn set_linear_solver(&mut self, linear_solver: LinearSolver) {
+        self.linear_solver = linear_solver;
+    }
+
+    fn dof(&self, node: usize, dof: usize) -> Result<usize, &'static str> {
+        if node >= self.mesh.num_nodes() || dof >= 6 {
+            return Err("Node or dof out of range");
+        }
+        Ok(6 * node + dof)
+    }
+
+    // Prescribed displacement or rotation of a node dof in global axes.
+    pub fn fix(&mut self, node: usize, dof: usize, value: f64) -> Result<(), &'static str> {
+        let dof = self.dof(node, dof)?;
+        self.constraints.insert(dof, value);
+        Ok(())
+    }
+
+    // Fixes the translations of the given nodes, a simple support.
+    pub fn pin_nodes(&mut self, nodes: &[usize]) -> Result<(), &'static str> {
+        for &n in nodes.iter() {
+            (0..3).try_for_each(|d| self.fix(n, d, 0.0))?;
+        }
+        Ok(())
+    }
+
+    pub fn clamp_nodes(&mut self, nodes: &[usize]) -> Result<(), &'static str> {
+        for &n in nodes.iter() {
+            (0..6).try_for_each(|d| self.fix(n, d, 0.0))?;
+        }
+        Ok(())
+    }
+
+    // Nodal force or moment in global axes.
+    pub fn add_load(&mut self, node: usize, dof: usize, value: f64) -> Result<(), &'static str> {
+        let dof = self.dof(node, dof)?;
+        self.loads[dof] += value;
+        Ok(())
+    }
+
+    // Uniform load per unit area of an element along its normal.
+    pub fn add_pressure(&mut self, element: usize, pressure: f64) -> Result<(), &'static str> {
+        if element >= self.mesh.num_elements() {
+            return Err("Element index out of range");
+        }
+        self.pressures.push((element, pressure));
+        Ok(())
+    }
+
+    // Force per unit volume, such as self weight.
+    pub fn set_body_force(&mut self, force: [f64; 3]) {
+        self.body_force = force;
+    }
+
+    fn surface(&self, element: usize) -> Result<Surface, &'static str> {
+        let e = self
+            .mesh
+            .elements
+            .get(element)
+            .ok_or("Element index out of range")?;
+        let points: Vec<[f64; 3]> = e
+            .nodes
+            .iter()
+            .map(|&n| {
+                let (x, y, z) = self.mesh.nodes[n].get_coords();
+                [x, y, z]
+            })
+            .collect();
+        let directors = node_coordinates(e.element_type)
+            .iter()
+            .map(|&xi| {
+                let d = evaluate(e.element_type, xi).1;
+                let (g1, g2) = tangents(&points, &d);
+                normalize(cross(&g1, &g2))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Surface {
+            element_type: e.element_type,
+            points,
+            directors,
+            thickness: self.layups[element].thickness(),
+        })
+    }
+
+    fn axes(&self, normal: [f64; 3]) -> [[f64; 3]; 3] {
+        let x = [self.reference, [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]]
+            .iter()
+            .map(|r| {
+                let p = dot(r, &normal);
+                [
+                    r[0] - p * normal[0],
+                    r[1] - p * normal[1],
+                    r[2] - p * normal[2],
+                ]
+            })
+            .find(|x| dot(x, x) > 1e-2)
+            .and_then(|x| normalize(x).ok())
+            .unwrap();
+        [x, cross(&normal, &x), normal]
+    }
+
+    fn basis(&self, surface: &Surface, xi: [f64; 2], zeta: f64) -> Result<Basis, &'static str> {
+        let (n, d) = evaluate(surface.element_type, [xi[0], xi[1], 0.0]);
+        let half = 0.5 * surface.thickness;
+        let mut g = [[0.0; 3]; 3];
+        for (a, (p, v)) in surface
+            .points
+            .iter()
+            .zip(surface.directors.iter())
+            .enumerate()
+        {
+            for k in 0..3 {
+                let x = p[k] + zeta * half * v[k];
+                g[0][k] += d[a][0] * x;
+                g[1][k] += d[a][1] * x;
+                g[2][k] += n[a] * half * v[k];
+            }
+        }
+        let determinant = dot(&g[0], &cross(&g[1], &g[2]));
+        if determinant.is_nan() || determinant <= 0.0 {
+            return Err("Degenerate shell element");
+        }
+        let dual = [
+            cross(&g[1], &g[2]),
+            cross(&g[2], &g[0]),
+            cross(&g[0], &g[1]),
+        ];
+        let axes = self.axes(normalize(dual[2])?);
+        let c =
+            std::array::from_fn(|k| std::array::from_fn(|i| dot(&axes[k], &dual[i]) / determinant));
+        Ok(Basis {
+            n,
+            d,
+            g,
+            c,
+            axes,
+            determinant,
+        })
+    }
+
+    // Covariant strains 11, 22, 12, 13, 23, 33 of every element dof.
+    fn covariant(
+        &self,
+        surface: &Surface,
+        xi: [f64; 2],
+        zeta: f64,
+    ) -> Result<(Vec<[f64; 6]>, Basis), &'static str> {
+        let basis = self.basis(surface, xi, zeta)?;
+        let half = 0.5 * surface.thickness;
+        let pairs = [(0, 0), (1, 1), (0, 1), (0, 2), (1, 2), (2, 2)];
+        let mut rows = Vec::with_capacity(6 * surface.points.len());
+        for (a, v) in surface.directors.iter().enumerate() {
+            for dof in 0..6 {
+                // Derivatives of the displacement along the natural
+                // coordinates.
+                let mut du = [[0.0; 3]; 3];
+                if dof < 3 {
+                    du[0][dof] = basis.d[a][0];
+                    du[1][dof] = basis.d[a][1];
+                } else {
+                    let mut e = [0.0; 3];
+                    e[dof - 3] = 1.0;
+                    let s = cross(&e, v);
+                    for k in 0..3 {
+                        du[0][k] = basis.d[a][0] * zeta * half * s[k];
+                        du[1][k] = basis.d[a][1] * zeta * half * s[k];
+                        du[2][k] = basis.n[a] * half * s[k];
+                    }
+                }
+                rows.push(
+                    pairs.map(|(i, j)| 0.5 * (dot(&basis.g[i], &du[j]) + dot(&basis.g[j], &du[i]))),
+                );
+            }
+        }
+        Ok((rows, basis))
+    }
+
+    // Covariant strains with the assumed transverse shear fields of MITC3
+    // and MITC4, tied at edge points at the same zeta.
+    fn assumed(
+        &self,
+        surface: &Surface,
+        xi: [f64; 2],
+        zeta: f64,
+    ) -> Result<(Vec<[f64; 6]>, Basis), &'static str> {
+        let (mut rows, basis) = self.covariant(surface, xi, zeta)?;
+        let tie = |p: [f64; 2]| self.covariant(surface, p, zeta).map(|r| r.0);
+        match surface.element_type {
+            ElementType::Quad4 => {
+                let (top, bottom) = (tie([0.0, 1.0])?, tie([0.0, -1.0])?);
+                let (left, right) = (tie([-1.0, 0.0])?, tie([1.0, 0.0])?);
+                for (p, row) in rows.iter_mut().enumerate() {
+                    row[3] = 0.5 * (1.0 + xi[1]) * top[p][3] + 0.5 * (1.0 - xi[1]) * bottom[p][3];
+                    row[4] = 0.5 * (1.0 + xi[0]) * right[p][4] + 0.5 * (1.0 - xi[0]) * left[p][4];
+                }
+            }
+            ElementType::Tri3 => {
+                let (first, second) = (tie([0.5, 0.0])?, tie([0.5, 0.5])?);
+                let third = tie([0.0, 0.5])?;
+                for (p, row) in rows.iter_mut().enumerate() {
+                    let c = third[p][4] - first[p][3] - second[p][4] + second[p][3];
+                    row[3] = first[p][3] + c * xi[1];
+                    row[4] = third[p][4] - c * xi[0];
+                }
+            }
+            _ => {}
+        }
+        Ok((rows, basis))
+    }
+
+    // Local strains xx, yy, xy, yz, zx of every element dof.
+    fn strain_rows(
+        &self,
+        surface: &Surface,
+        xi: [f64; 2],
+        zeta: f64,
+    ) -> Result<(Vec<[f64; 5]>, Basis), &'static str> {
+        let (rows, basis) = self.assumed(surface, xi, zeta)?;
+        let c = &basis.c;
+        let local = rows
+            .iter()
+            .map(|r| {
+                let e = [[r[0], r[2], r[3]], [r[2], r[1], r[4]], [r[3], r[4], r[5]]];
+                let strain = |k: usize, l: usize| -> f64 {
+                    (0..3)
+                        .map(|i| (0..3).map(|j| e[i][j] * c[k][i] * c[l][j]).sum::<f64>())
+                        .sum()
+                };
+                [
+                    strain(0, 0),
+                    strain(1, 1),
+                    2.0 * strain(0, 1),
+                    2.0 * strain(1, 2),
+                    2.0 * strain(0, 2),
+                ]
+            })
+            .collect();
+        Ok((local, basis))
+    }
+
+    // Element stiffness matrix and equivalent loads of pressure and body
+    // force, in global axes.
+    pub fn element_matrices(
+        &self,
+        element: usize,
+    ) -> Result<(Vec<Vec<f64>>, Vec<f64>), &'static str> {
+        let surface = self.surface(element)?;
+        let layup = &self.layups[element];
+        let element_type = surface.element_type;
+        let size = 6 * surface.points.len();
+        let mut k = vec![vec![0.0; size]; size];
+        let mut f = vec![0.0; size];
+        let matrices: Vec<_> = layup.layers.iter().map(|l| l.matrices()).collect();
+        let shear: Vec<[[f64; 2]; 2]> = matrices
+            .iter()
+            .map(|m| m.1.map(|r| r.map(|v| SHEAR_CORRECTION * v)))
+            .collect();
+        let thickness = layup.thickness_points();
+        let mitc = matches!(element_type, ElementType::Tri3 | ElementType::Quad4);
+        let pressure: f64 = self
+            .pressures
+            .iter()
+            .filter(|p| p.0 == element)
+            .map(|p| p.1)
+            .sum();
+        let drilling = DRILLING * layup.abd()[0][2][2];
+
+        for (xi, w) in QuadratureRule::new(element_type, full_degree(element_type)).iter() {
+            let xi = [xi[0], xi[1]];
+            for &(layer, zeta, wz) in thickness.iter() {
+                let (b, basis) = self.strain_rows(&surface, xi, zeta)?;
+                let weight = w * wz * basis.determinant;
+                add_product(&mut k, &b, 0, &matrices[layer].0, weight);
+                if mitc {
+                    add_product(&mut k, &b, 3, &shear[layer], weight);
+                }
+                for (a, n) in basis.n.iter().enumerate() {
+                    for d in 0..3 {
+                        f[6 * a + d] += n * self.body_force[d] * weight;
+                    }
+                }
+            }
+
+            // Drilling penalty on the rotation about the normal less the
+            // in-plane rotation of the midsurface.
+            let basis = self.basis(&surface, xi, 0.0)?;
+            let area = w * cross(&basis.g[0], &basis.g[1])
+                .iter()
+                .map(|v| v * v)
+                .sum::<f64>()
+                .sqrt();
+            let [e1, e2, e3] = basis.axes;
+            let c = &basis.c;
+            let mut r = vec![0.0; size];
+            for (a, v) in surface.directors.iter().enumerate() {
+                let d = basis.d[a];
+                for m in 0..3 {
+                    r[6 * a + m] = -0.5
+                        * (0..2)
+                            .map(|i| d[i] * (c[0][i] * e2[m] - c[1][i] * e1[m]))
+                            .sum::<f64>();
+                    let mut e = [0.0; 3];
+                    e[m] = 1.0;
+                    let s = cross(&e, v).map(|s| 0.5 * surface.thickness * basis.n[a] * s);
+                    r[6 * a + 3 + m] = basis.n[a] * e3[m]
+                        - 0.5 * (c[0][2] * dot(&e2, &s) - c[1][2] * dot(&e1, &s));
+                }
+            }
+            for (p, rp) in r.iter().enumerate() {
+                for (q, rq) in r.iter().enumerate() {
+                    k[p][q] += drilling * rp * rq * area;
+                }
+            }
+            for (a, n) in basis.n.iter().enumerate() {
+                for d in 0..3 {
+                    f[6 * a + d] += n * pressure * e3[d] * area;
+                }
+            }
+        }
+
+        if !mitc {
+            for (xi, w) in QuadratureRule::new(element_type, 2).iter() {
+                for &(layer, zeta, wz) in thickness.iter() {
+                    let (b, basis) = self.strain_rows(&surface, [xi[0], xi[1]], zeta)?;
+                    add_product(&mut k, &b, 3, &shear[layer], w * wz * basis.determinant);
+                }
+            }
+        }
+        Ok((k, f))
+    }
+
+    fn element_dofs(&self, element: usize) -> Vec<usize> {
+        self.mesh.elements[element]
+            .nodes
+            .iter()
+            .flat_map(|&n| (0..6).map(move |d| 6 * n + d))
+            .collect()
+    }
+
+    pub fn stiffness(&self) -> Result<CsrMatrix, &'static str> {
+        let n = self.num_dofs();
+        let mut k = CooMatrix::new(n, n);
+        for element in 0..self.mesh.num_elements() {
+            let dofs = self.element_dofs(element);
+            k.push_block(&dofs, &dofs, &self.element_matrices(element)?.0)?;
+        }
+        Ok(k.to_csr())
+    }
+
+    pub fn load_vector(&self) -> Result<Vec<f64>, &'static str> {
+        let mut f = self.loads.clone();
+        for element in 0..self.mesh.num_elements() {
+            let load = self.element_matrices(element)?.1;
+            for (&dof, v) in self.element_dofs(element).iter().zip(load) {
+                f[dof] += v;
+            }
+        }
+        Ok(f)
+    }
+
+    pub fn solve(&self) -> Result<ShellSolution, &'static str> {
+        let k = self.stiffness()?;
+        let f = self.load_vector()?;
+        let n = k.nrows;
+        // Nodes outside the elements are held in place.
+        let mut constraints = self.constraints.clone();
+        for (dof, d) in k.diagonal().iter().enumerate() {
+            if *d == 0.0 && f[dof] == 0.0 {
+                constraints.entry(dof).or_insert(0.0);
+            }
+        }
+        let mut u = vec![0.0; n];
+        let mut index = vec![usize::MAX; n];
+        let mut free = Vec::new();
+        for dof in 0..n {
+            match constraints.get(&dof) {
+                Some(&value) => u[dof] = value,
+                None => {
+                    index[dof] = free.len();
+                    free.push(dof);
+                }
+            }
+        }
+        let mut reduced = CooMatrix::new(free.len(), free.len());
+        let mut rhs: Vec<f64> = free.iter().map(|&i| f[i]).collect();
+        for (r, &i) in free.iter().enumerate() {
+            let (columns, values) = k.row(i);
+            for (&j, &v) in columns.iter().zip(values.iter()) {
+                if index[j] != usize::MAX {
+                    reduced.push(r, index[j], v)?;
+                } else {
+                    rhs[r] -= v * u[j];
+                }
+            }
+        }
+        let solution = self
+            .linear_solver
+            .solve(&reduced.to_csr(), &rhs)
+            .map_err(|e| {
+                if e == "Matrix is singular" {
+                    "Shell is unstable"
+                } else {
+                    e
+                }
+            })?;
+        for (&dof, value) in free.iter().zip(solution) {
+            u[dof] = value;
+        }
+        let ku = k.multiply(&u);
+        Ok(ShellSolution {
+            displacements: u.chunks(6).map(|c| c.try_into().unwrap()).collect(),
+            reactions: (0..self.mesh.num_nodes())
+                .map(|node| std::array::from_fn(|d| ku[6 * node + d] - f[6 * node + d]))
+                .collect(),
+        })
+    }
+
+    // Local axes x, y, z of an element at a point of its midsurface.
+    pub fn local_axes(&self, element: usize, xi: [f64; 2]) -> Result<[[f64; 3]; 3], &'static str> {
+        Ok(self.basis(&self.surface(element)?, xi, 0.0)?.axes)
+    }
+
+    // Local strains xx, yy, xy, yz, zx at a height z above the midsurface.
+    fn strain(
+        &self,
+        solution: &ShellSolution,
+        element: usize,
+        surface: &Surface,
+        xi: [f64; 2],
+        z: f64,
+    ) -> Result<[f64; 5], &'static str> {
+        let u: Vec<f64> = self.mesh.elements[element]
+            .nodes
+            .iter()
+            .flat_map(|&n| solution.displacements[n])
+            .collect();
+        let rows = self
+            .strain_rows(surface, xi, 2.0 * z / surface.thickness)?
+            .0;
+        let mut strain = [0.0; 5];
+        for (row, u) in rows.iter().zip(u.iter()) {
+            for (s, b) in strain.iter_mut().zip(row.iter()) {
+                *s += b * u;
+            }
+        }
+        Ok(strain)
+    }
+
+    // Stress resultants per unit length in local axes: membrane forces Nxx,
+    // Nyy, Nxy, moments Mxx, Myy, Mxy (of the stresses about the
+    // midsurface) and transverse shear forces Qx, Qy.
+    pub fn resultants(
+        &self,
+        solution: &ShellSolution,
+        element: usize,
+        xi: [f64; 2],
+    ) -> Result<[f64; 8], &'static str> {
+        let surface = self.surface(element)?;
+        let layup = &self.layups[element];
+        let h = layup.thickness();
+        let mut resultants = [0.0; 8];
+        for (layer, zeta, wz) in layup.thickness_points() {
+            let z = 0.5 * zeta * h;
+            let dz = 0.5 * wz * h;
+            let e = self.strain(solution, element, &surface, xi, z)?;
+            let (q, s) = layup.layers[layer].matrices();
+            for i in 0..3 {
+                let sigma: f64 = (0..3).map(|j| q[i][j] * e[j]).sum();
+                resultants[i] += sigma * dz;
+                resultants[3 + i] += sigma * z * dz;
+            }
+            // Qx from the zx and Qy from the yz component.
+            for i in 0..2 {
+                let tau: f64 = (0..2).map(|j| s[i][j] * e[3 + j]).sum();
+                resultants[7 - i] += SHEAR_CORRECTION * tau * dz;
+            }
+        }
+        Ok(resultants)
+    }
+
+    // Stresses at the bottom, middle and top of every layer at a point of
+    // an element. In-plane stresses come from the strains at each height;
+    // the transverse shear forces are distributed through the thickness as
+    // in a beam, by integrating the equilibrium of the bending stresses.
+    pub fn stress_profile(
+        &self,
+        solution: &ShellSolution,
+        element: usize,
+        xi: [f64; 2],
+    ) -> Result<Vec<ThicknessStress>, &'static str> {
+        let surface = self.surface(element)?;
+        let layup = &self.layups[element];
+        let z = layup.interfaces();
+        let resultants = self.resultants(solution, element, xi)?;
+        let matrices: Vec<_> = layup.layers.iter().map(|l| l.matrices().0).collect();
+
+        // Neutral surfaces and bending stiffnesses for bending along x and
+        // y, from the xx and yy stiffnesses of the layers.
+        let bending: Vec<(f64, f64)> = (0..2)
+            .map(|c| {
+                let (mut a, mut s) = (0.0, 0.0);
+                for (k, q) in matrices.iter().enumerate() {
+                    a += q[c][c] * (z[k + 1] - z[k]);
+                    s += q[c][c] * (z[k + 1].powi(2) - z[k].powi(2)) / 2.0;
+                }
+                let neutral = s / a;
+                let d = matrices
+                    .iter()
+                    .enumerate()
+                    .map(|(k, q)| {
+                        q[c][c] * ((z[k + 1] - neutral).powi(3) - (z[k] - neutral).powi(3)) / 3.0
+                    })
+                    .sum::<f64>();
+                (neutral, d)
+            })
+            .collect();
+        // Integral of Q_cc (z - neutral) from the bottom face to z in layer k.
+        let first_moment = |c: usize, k: usize, height: f64| -> f64 {
+            let neutral = bending[c].0;
+            let below: f64 = (0..k)
+                .map(|j| {
+                    matrices[j][c][c] * ((z[j + 1] - neutral).powi(2) - (z[j] - neutral).powi(2))
+                        / 2.0
+                })
+                .sum();
+            below
+                + matrices[k][c][c] * ((height - neutral).powi(2) - (z[k] - neutral).powi(2)) / 2.0
+        };
+
+        let mut profile = Vec::with_capacity(3 * layup.layers.len());
+        for (k, layer) in layup.layers.iter().enumerate() {
+            for height in [z[k], 0.5 * (z[k] + z[k + 1]), z[k + 1]] {
+                let e = self.strain(solution, element, &surface, xi, height)?;
+                let q = &matrices[k];
+                let sigma: [f64; 3] = std::array::from_fn(|i| (0..3).map(|j| q[i][j] * e[j]).sum());
+                let zx = -resultants[6] * first_moment(0, k, height) / bending[0].1;
+                let yz = -resultants[7] * first_moment(1, k, height) / bending[1].1;
+                let stress = [sigma[0], sigma[1], 0.0, sigma[2], yz, zx];
+                profile.push(ThicknessStress {
+                    z: height,
+                    layer: k,
+                    stress,
+                    material_stress: layer.material_stress(&stress),
+                });
+            }
+        }
+        Ok(profile)
+    }
+}
+
+// k += B^T D B weight over the strain components starting at `offset`.
+fn add_product<const N: usize>(
+    k: &mut [Vec<f64>],
+    b: &[[f64; 5]],
+    offset: usize,
+    d: &[[f64; N]; N],
+    weight: f64,
+) {
+    let db: Vec<[f64; N]> = b
+        .iter()
+        .map(|row| {
+            std::array::from_fn(|i| weight * (0..N).map(|j| d[i][j] * row[offset + j]).sum::<f64>())
+        })
+        .collect();
+    for (row, bp) in k.iter_mut().zip(b.iter()) {
+        for (kpq, dq) in row.iter_mut().zip(db.iter()) {
+            *kpq += (0..N).map(|i| bp[offset + i] * dq[i]).sum::<f64>();
+        }
+    }
+}
+
+fn tangents(points: &[[f64; 3]], derivatives: &[[f64; 3]]) -> ([f64; 3], [f64; 3]) {
+    let mut g = ([0.0; 3], [0.0; 3]);
+    for (p, d) in points.iter().zip(derivatives.iter()) {
+        for ((a, b), x) in g.0.iter_mut().zip(g.1.iter_mut()).zip(p.iter()) {
+            *a += d[0] * x;
+            *b += d[1] * x;
+        }
+    }
+    g
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(v: [f64; 3]) -> Result<[f64; 3], &'static str> {
+    let length = dot(&v, &v).sqrt();
+    if length.is_nan() || length <= 1e-300 {
+        return Err("Degenerate shell element");
+    }
+    Ok(v.map(|c| c / length))
+}
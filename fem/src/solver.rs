@@ -0,0 +1,562 @@
+use crate::factorization::SparseLdlt;
+use crate::ordering::Ordering;
+use crate::sparse::CsrMatrix;
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b.iter()).map(|(a, b)| a * b).sum()
+}
+
+fn norm(a: &[f64]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+// y += alpha x
+fn axpy(alpha: f64, x: &[f64], y: &mut [f64]) {
+    for (y, x) in y.iter_mut().zip(x.iter()) {
+        *y += alpha * x;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Preconditioner {
+    Identity,
+    // Inverse of the diagonal.
+    #[default]
+    Jacobi,
+    // Symmetric successive over-relaxation with 0 < omega < 2.
+    Ssor {
+        omega: f64,
+    },
+    // Zero fill incomplete Cholesky, for symmetric positive definite
+    // matrices.
+    IncompleteCholesky,
+    // Zero fill incomplete LU, for general matrices.
+    IncompleteLu,
+}
+
+// Preconditioner set up for one matrix.
+enum Factors<'a> {
+    Identity,
+    Jacobi(Vec<f64>),
+    Ssor {
+        a: &'a CsrMatrix,
+        diagonal: Vec<f64>,
+        omega: f64,
+    },
+    // Lower triangle with the diagonal last in each row.
+    Cholesky(CsrMatrix),
+    // Unit lower and upper triangles in one matrix, with the position of
+    // each diagonal entry.
+    Lu(CsrMatrix, Vec<usize>),
+}
+
+impl<'a> Factors<'a> {
+    fn new(a: &'a CsrMatrix, preconditioner: Preconditioner) -> Result<Self, &'static str> {
+        let diagonal = a.diagonal();
+        let needs_diagonal = preconditioner != Preconditioner::Identity;
+        if needs_diagonal && diagonal.contains(&0.0) {
+            return Err("Preconditioner needs a nonzero diagonal");
+        }
+        Ok(match preconditioner {
+            Preconditioner::Identity => Factors::Identity,
+            Preconditioner::Jacobi => Factors::Jacobi(diagonal.iter().map(|d| 1.0 / d).collect()),
+            Preconditioner::Ssor { omega } => {
+                if omega <= 0.0 || omega >= 2.0 {
+                    return Err("Relaxation factor must lie in (0, 2)");
+                }
+                Factors::Ssor { a, diagonal, omega }
+            }
+            Preconditioner::IncompleteCholesky => Factors::Cholesky(incomplete_cholesky(a)?),
+            Preconditioner::IncompleteLu => {
+                let (lu, positions) = incomplete_lu(a)?;
+                Factors::Lu(lu, positions)
+            }
+        })
+    }
+
+    // z = M^-1 r
+    fn apply(&self, r: &[f64]) -> Vec<f64> {
+        let n = r.len();
+        match self {
+            Factors::Identity => r.to_vec(),
+            Factors::Jacobi(inverse) => r.iter().zip(inverse.iter()).map(|(r, d)| r * d).collect(),
+            Factors::Ssor { a, diagonal, omega } => {
+                // M = (D/w + L) (D/w)^-1 (D/w + U) w / (2 - w)
+                let mut y = vec![0.0; n];
+                for i in 0..n {
+                    let (columns, values) = a.row(i);
+                    let mut s = r[i];
+                    for (&j, &v) in columns.iter().zip(values.iter()) {
+                        if j < i {
+                            s -= v * y[j];
+                        }
+                    }
+                    y[i] = s * omega / diagonal[i];
+                }
+                for (y, d) in y.iter_mut().zip(diagonal.iter()) {
+                    *y *= d * (2.0 - omega) / (omega * omega);
+                }
+                let mut z = vec![0.0; n];
+                for i in (0..n).rev() {
+                    let (columns, values) = a.row(i);
+                    let mut s = y[i];
+                    for (&j, &v) in columns.iter().zip(values.iter()) {
+                        if j > i {
+                            s -= v * z[j];
+                        }
+                    }
+                    z[i] = s * omega / diagonal[i];
+                }
+                z
+            }
+            Factors::Cholesky(l) => {
+                let mut y = r.to_vec();
+                for i in 0..n {
+                    let (columns, values) = l.row(i);
+                    let last = values.len() - 1;
+                    let s: f64 = columns[..last]
+                        .iter()
+                        .zip(values[..last].iter())
+                        .map(|(&j, v)| v * y[j])
+                        .sum();
+                    y[i] = (y[i] - s) / values[last];
+                }
+                for i in (0..n).rev() {
+                    let (columns, values) = l.row(i);
+                    let last = values.len() - 1;
+                    y[i] /= values[last];
+                    let yi = y[i];
+                    for (&j, v) in columns[..last].iter().zip(values[..last].iter()) {
+                        y[j] -= v * yi;
+                    }
+                }
+                y
+            }
+            Factors::Lu(lu, positions) => {
+                let mut y = r.to_vec();
+                for i in 0..n {
+                    let (columns, values) = lu.row(i);
+                    let d = positions[i] - lu.row_offsets[i];
+                    let s: f64 = columns[..d]
+                        .iter()
+                        .zip(values[..d].iter())
+                        .map(|(&j, v)| v * y[j])
+                        .sum();
+                    y[i] -= s;
+                }
+                for i in (0..n).rev() {
+                    let (columns, values) = lu.row(i);
+                    let d = positions[i] - lu.row_offsets[i];
+                    let s: f64 = columns[d + 1..]
+                        .iter()
+                        .zip(values[d + 1..].iter())
+                        .map(|(&j, v)| v * y[j])
+                        .sum();
+                    y[i] = (y[i] - s) / values[d];
+                }
+                y
+            }
+        }
+    }
+}
+
+// Zero fill incomplete Cholesky factor, keeping the pattern of the lower
+// triangle of A.
+fn incomplete_cholesky(a: &CsrMatrix) -> Result<CsrMatrix, &'static str> {
+    let n = a.nrows;
+    let mut l = CsrMatrix {
+        nrows: n,
+        ncols: n,
+        row_offsets: vec![0],
+        columns: Vec::new(),
+        values: Vec::new(),
+    };
+    let mut work = vec![0.0; n];
+    for i in 0..n {
+        let (columns, values) = a.row(i);
+        let start = l.columns.len();
+        for (&j, &v) in columns.iter().zip(values.iter()) {
+            if j < i {
+                l.columns.push(j);
+                work[j] = v;
+            }
+        }
+        let end = l.columns.len();
+        for p in start..end {
+            let k = l.columns[p];
+            // Row k of L is complete, with its diagonal last.
+            let (kcolumns, kvalues) = l.row(k);
+            let last = kvalues.len() - 1;
+            let s: f64 = kcolumns[..last]
+                .iter()
+                .zip(kvalues[..last].iter())
+                .map(|(&j, v)| v * work[j])
+                .sum();
+            work[k] = (work[k] - s) / kvalues[last];
+        }
+        let mut d = a.get(i, i);
+        for p in start..end {
+            let j = l.columns[p];
+            d -= work[j] * work[j];
+            l.values.push(work[j]);
+            work[j] = 0.0;
+        }
+        if d.is_nan() || d <= 0.0 {
+            return Err("Incomplete Cholesky factorization broke down");
+        }
+        l.columns.push(i);
+        l.values.push(d.sqrt());
+        l.row_offsets.push(l.columns.len());
+    }
+    Ok(l)
+}
+
+// Zero fill incomplete LU factorization in the pattern of A.
+fn incomplete_lu(a: &CsrMatrix) -> Result<(CsrMatrix, Vec<usize>), &'static str> {
+    let n = a.nrows;
+    let mut lu = a.clone();
+    let mut positions = vec![usize::MAX; n];
+    for (i, position) in positions.iter_mut().enumerate() {
+        let start = lu.row_offsets[i];
+        match lu.row(i).0.binary_search(&i) {
+            Ok(d) => *position = start + d,
+            Err(_) => return Err("Zero pivot in incomplete LU factorization"),
+        }
+    }
+    let mut index = vec![usize::MAX; n];
+    for i in 0..n {
+        let range = lu.row_offsets[i]..lu.row_offsets[i + 1];
+        for p in range.clone() {
+            index[lu.columns[p]] = p;
+        }
+        for p in range.start..positions[i] {
+            let k = lu.columns[p];
+            let pivot = lu.values[positions[k]];
+            if pivot == 0.0 {
+                return Err("Zero pivot in incomplete LU factorization");
+            }
+            let factor = lu.values[p] / pivot;
+            lu.values[p] = factor;
+            for q in positions[k] + 1..lu.row_offsets[k + 1] {
+                let target = index[lu.columns[q]];
+                if target != usize::MAX {
+                    lu.values[target] -= factor * lu.values[q];
+                }
+            }
+        }
+        if lu.values[positions[i]] == 0.0 {
+            return Err("Zero pivot in incomplete LU factorization");
+        }
+        for p in range {
+            index[lu.columns[p]] = usize::MAX;
+        }
+    }
+    Ok((lu, positions))
+}
+
+// Solver for the symmetric systems of an analysis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinearSolver {
+    Direct(Ordering),
+    ConjugateGradient {
+        preconditioner: Preconditioner,
+        tolerance: f64,
+        max_iterations: usize,
+    },
+}
+
+impl Default for LinearSolver {
+    fn default() -> Self {
+        LinearSolver::Direct(Ordering::ApproximateMinimumDegree)
+    }
+}
+
+impl LinearSolver {
+    // Ordering for factorizations needed whatever the solver, such as the
+    // shift-invert of eigenvalue problems.
+    pub fn ordering(&self) -> Ordering {
+        match *self {
+            LinearSolver::Direct(ordering) => ordering,
+            _ => Ordering::default(),
+        }
+    }
+
+    pub fn solve(&self, a: &CsrMatrix, b: &[f64]) -> Result<Vec<f64>, &'static str> {
+        match *self {
+            LinearSolver::Direct(ordering) => SparseLdlt::new(a, ordering)?.solve(b),
+            LinearSolver::ConjugateGradient {
+                preconditioner,
+                tolerance,
+                max_iterations,
+            } => preconditioned_conjugate_gradient(a, b, preconditioner, tolerance, max_iterations)
+                .map(|(x, _)| x),
+        }
+    }
+}
+
+fn check_sizes(a: &CsrMatrix, b: &[f64]) -> Result<(), &'static str> {
+    if a.nrows != b.len() || a.ncols != b.len() {
+        return Err("Matrix and right hand side sizes do not match");
+    }
+    Ok(())
+}
+
+// Jacobi preconditioned conjugate gradients for symmetric positive definite
+// systems, stopping when the residual norm drops below `tolerance` times the
+// norm of the right hand side.
+pub fn conjugate_gradient(
+    a: &CsrMatrix,
+    b: &[f64],
+    tolerance: f64,
+    max_iterations: usize,
+) -> Result<Vec<f64>, &'static str> {
+    preconditioned_conjugate_gradient(a, b, Preconditioner::Jacobi, tolerance, max_iterations)
+        .map(|(x, _)| x)
+}
+
+// Returns the solution and the number of iterations taken. The
+// preconditioner must be symmetric positive definite too.
+pub fn preconditioned_conjugate_gradient(
+    a: &CsrMatrix,
+    b: &[f64],
+    preconditioner: Preconditioner,
+    tolerance: f64,
+    max_iterations: usize,
+) -> Result<(Vec<f64>, usize), &'static str> {
+    check_sizes(a, b)?;
+    let factors = Factors::new(a, preconditioner)?;
+    let mut x = vec![0.0; b.len()];
+    let mut r = b.to_vec();
+    let target = tolerance * norm(b);
+    if target == 0.0 {
+        return Ok((x, 0));
+    }
+    let mut z = factors.apply(&r);
+    let mut p = z.clone();
+    let mut rz = dot(&r, &z);
+    for iteration in 1..=max_iterations {
+        let q = a.multiply(&p);
+        let curvature = dot(&p, &q);
+        if curvature <= 0.0 {
+            return Err("Matrix is not positive definite");
+        }
+        let alpha = rz / curvature;
+        axpy(alpha, &p, &mut x);
+        axpy(-alpha, &q, &mut r);
+        if norm(&r) <= target {
+            return Ok((x, iteration));
+        }
+        z = factors.apply(&r);
+        let next = dot(&r, &z);
+        let beta = next / rz;
+        rz = next;
+        for (p, z) in p.iter_mut().zip(z.iter()) {
+            *p = z + beta * *p;
+        }
+    }
+    Err("Conjugate gradients did not converge")
+}
+
+// Restarted GMRES with right preconditioning, so the monitored residual is
+// the true one. Returns the solution and the number of iterations taken.
+pub fn gmres(
+    a: &CsrMatrix,
+    b: &[f64],
+    preconditioner: Preconditioner,
+    restart: usize,
+    tolerance: f64,
+    max_iterations: usize,
+) -> Result<(Vec<f64>, usize), &'static str> {
+    check_sizes(a, b)?;
+    if restart == 0 {
+        return Err("Restart length must be positive");
+    }
+    let factors = Factors::new(a, preconditioner)?;
+    let n = b.len();
+    let mut x = vec![0.0; n];
+    let target = tolerance * norm(b);
+    if target == 0.0 {
+        return Ok((x, 0));
+    }
+    let mut iterations = 0;
+    loop {
+        let ax = a.multiply(&x);
+        let r: Vec<f64> = b.iter().zip(ax.iter()).map(|(b, ax)| b - ax).collect();
+        let beta = norm(&r);
+        if beta <= target {
+            return Ok((x, iterations));
+        }
+        if iterations >= max_iterations {
+            return Err("GMRES did not converge");
+        }
+        let mut basis = vec![r.iter().map(|r| r / beta).collect::<Vec<f64>>()];
+        let mut h = vec![vec![0.0; restart]; restart + 1];
+        let mut rotations: Vec<(f64, f64)> = Vec::with_capacity(restart);
+        let mut g = vec![0.0; restart + 1];
+        g[0] = beta;
+        let mut size = 0;
+        while size < restart && iterations < max_iterations {
+            let j = size;
+            iterations += 1;
+            let mut w = a.multiply(&factors.apply(&basis[j]));
+            // Modified Gram-Schmidt.
+            for (i, v) in basis.iter().enumerate() {
+                h[i][j] = dot(&w, v);
+                axpy(-h[i][j], v, &mut w);
+            }
+            h[j + 1][j] = norm(&w);
+            for (i, &(c, s)) in rotations.iter().enumerate() {
+                let t = c * h[i][j] + s * h[i + 1][j];
+                h[i + 1][j] = -s * h[i][j] + c * h[i + 1][j];
+                h[i][j] = t;
+            }
+            let d = h[j][j].hypot(h[j + 1][j]);
+            let (c, s) = if d == 0.0 {
+                (1.0, 0.0)
+            } else {
+                (h[j][j] / d, h[j + 1][j] / d)
+            };
+            let breakdown = h[j + 1][j] == 0.0;
+            if !breakdown {
+                let next = w.iter().map(|w| w / h[j + 1][j]).collect();
+                basis.push(next);
+            }
+            h[j][j] = d;
+            h[j + 1][j] = 0.0;
+            rotations.push((c, s));
+            g[j + 1] = -s * g[j];
+            g[j] *= c;
+            size += 1;
+            if g[j + 1].abs() <= target || breakdown {
+                break;
+            }
+        }
+        // Back substitution for the least squares coefficients.
+        let mut y = vec![0.0; size];
+        for i in (0..size).rev() {
+            let s: f64 = (i + 1..size).map(|k| h[i][k] * y[k]).sum();
+            if h[i][i] == 0.0 {
+                return Err("GMRES broke down");
+            }
+            y[i] = (g[i] - s) / h[i][i];
+        }
+        let mut update = vec![0.0; n];
+        for (y, v) in y.iter().zip(basis.iter()) {
+            axpy(*y, v, &mut update);
+        }
+        axpy(1.0, &factors.apply(&update), &mut x);
+    }
+}
+
+// BiCGSTAB with right preconditioning. Returns the solution and the number
+// of iterations taken.
+pub fn bicgstab(
+    a: &CsrMatrix,
+    b: &[f64],
+    preconditioner: Preconditioner,
+    tolerance: f64,
+    max_iterations: usize,
+) -> Result<(Vec<f64>, usize), &'static str> {
+    check_sizes(a, b)?;
+    let factors = Factors::new(a, preconditioner)?;
+    let n = b.len();
+    let mut x = vec![0.0; n];
+    let target = tolerance * norm(b);
+    if target == 0.0 {
+        return Ok((x, 0));
+    }
+    let mut r = b.to_vec();
+    let shadow = r.clone();
+    let (mut rho, mut alpha, mut omega) = (1.0, 1.0, 1.0);
+    let mut v = vec![0.0; n];
+    let mut p = vec![0.0; n];
+    for iteration in 1..=max_iterations {
+        let next = dot(&shadow, &r);
+        if next == 0.0 || omega == 0.0 {
+            return Err("BiCGSTAB broke down");
+        }
+        let beta = next / rho * alpha / omega;
+        rho = next;
+        for ((p, r), v) in p.iter_mut().zip(r.iter()).zip(v.iter()) {
+            *p = r + beta * (*p - omega * v);
+        }
+        let ph = factors.apply(&p);
+        v = a.multiply(&ph);
+        let sv = dot(&shadow, &v);
+        if sv == 0.0 {
+            return Err("BiCGSTAB broke down");
+        }
+        alpha = rho / sv;
+        axpy(-alpha, &v, &mut r);
+        axpy(alpha, &ph, &mut x);
+        if norm(&r) <= target {
+            return Ok((x, iteration));
+        }
+        let sh = factors.apply(&r);
+        let t = a.multiply(&sh);
+        let tt = dot(&t, &t);
+        omega = if tt == 0.0 { 0.0 } else { dot(&t, &r) / tt };
+        axpy(omega, &sh, &mut x);
+        axpy(-omega, &t, &mut r);
+        if norm(&r) <= target {
+            return Ok((x, iteration));
+        }
+    }
+    Err("BiCGSTAB did not converge")
+}
+
+// Gaussian elimination with partial pivoting for small dense systems, such
+// as condensing element dofs. Each column of `b` is a right hand side.
+pub fn dense_solve(a: &[Vec<f64>], b: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, &'static str> {
+    let n = a.len();
+    if a.iter().any(|r| r.len() != n) || b.len() != n {
+        return Err("Matrix and right hand side sizes do not match");
+    }
+    let mut a = a.to_vec();
+    let mut x = b.to_vec();
+    let scale = a
+        .iter()
+        .flat_map(|r| r.iter())
+        .fold(0.0f64, |m, v| m.max(v.abs()));
+    for k in 0..n {
+        let pivot = (k..n)
+            .max_by(|&i, &j| a[i][k].abs().total_cmp(&a[j][k].abs()))
+            .unwrap();
+        if a[pivot][k].abs() <= 1e-14 * scale {
+            return Err("Matrix is singular");
+        }
+        a.swap(k, pivot);
+        x.swap(k, pivot);
+        for i in k + 1..n {
+            let factor = a[i][k] / a[k][k];
+            if factor == 0.0 {
+                continue;
+            }
+            let (upper, lower) = a.split_at_mut(i);
+            for (aij, akj) in lower[0][k..].iter_mut().zip(upper[k][k..].iter()) {
+                *aij -= factor * akj;
+            }
+            let (upper, lower) = x.split_at_mut(i);
+            for (xi, xk) in lower[0].iter_mut().zip(upper[k].iter()) {
+                *xi -= factor * xk;
+            }
+        }
+    }
+    for k in (0..n).rev() {
+        for i in 0..k {
+            let factor = a[i][k] / a[k][k];
+            if factor == 0.0 {
+                continue;
+            }
+            let (upper, lower) = x.split_at_mut(k);
+            for (xi, xk) in upper[i].iter_mut().zip(lower[0].iter()) {
+                *xi -= factor * xk;
+            }
+        }
+        let d = a[k][k];
+        for v in x[k].iter_mut() {
+            *v /= d;
+        }
+    }
+    Ok(x)
+}
@@ -0,0 +1,272 @@
+// Coordinate format for assembly. Entries may repeat and are summed on
+// conversion.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CooMatrix {
+    pub nrows: usize,
+    pub ncols: usize,
+    pub rows: Vec<usize>,
+    pub columns: Vec<usize>,
+    pub values: Vec<f64>,
+}
+
+impl CooMatrix {
+    pub fn new(nrows: usize, ncols: usize) -> Self {
+        CooMatrix {
+            nrows,
+            ncols,
+            ..Default::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn push(&mut self, i: usize, j: usize, value: f64) -> Result<(), &'static str> {
+        if i >= self.nrows || j >= self.ncols {
+            return Err("Matrix index out of range");
+        }
+        self.rows.push(i);
+        self.columns.push(j);
+        self.values.push(value);
+        Ok(())
+    }
+
+    // Adds a dense block, e.g. an element matrix, at the given global rows
+    // and columns. Zero entries are skipped.
+    pub fn push_block(
+        &mut self,
+        rows: &[usize],
+        columns: &[usize],
+        block: &[Vec<f64>],
+    ) -> Result<(), &'static str> {
+        if block.len() != rows.len() || block.iter().any(|r| r.len() != columns.len()) {
+            return Err("Block size does not match its indices");
+        }
+        for (&i, row) in rows.iter().zip(block.iter()) {
+            for (&j, &v) in columns.iter().zip(row.iter()) {
+                if v != 0.0 {
+                    self.push(i, j, v)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_csr(&self) -> CsrMatrix {
+        let triplets: Vec<(usize, usize, f64)> = (0..self.len())
+            .map(|k| (self.rows[k], self.columns[k], self.values[k]))
+            .collect();
+        CsrMatrix::from_triplets_unchecked(self.nrows, self.ncols, &triplets)
+    }
+}
+
+// Compressed sparse row matrix with sorted, unique columns in each row.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CsrMatrix {
+    pub nrows: usize,
+    pub ncols: usize,
+    pub row_offsets: Vec<usize>,
+    pub columns: Vec<usize>,
+    pub values: Vec<f64>,
+}
+
+impl CsrMatrix {
+    // Duplicate entries are summed, as produced by element assembly.
+    pub fn from_triplets(
+        nrows: usize,
+        ncols: usize,
+        triplets: &[(usize, usize, f64)],
+    ) -> Result<Self, &'static str> {
+        if triplets.iter().any(|&(i, j, _)| i >= nrows || j >= ncols) {
+            return Err("Matrix index out of range");
+        }
+        Ok(CsrMatrix::from_triplets_unchecked(nrows, ncols, triplets))
+    }
+
+    // For triplets whose indices are in range by construction.
+    pub(crate) fn from_triplets_unchecked(
+        nrows: usize,
+        ncols: usize,
+        triplets: &[(usize, usize, f64)],
+    ) -> Self {
+        let mut counts = vec![0; nrows + 1];
+        for &(i, _, _) in triplets.iter() {
+            counts[i + 1] += 1;
+        }
+        for i in 0..nrows {
+            counts[i + 1] += counts[i];
+        }
+        let mut next = counts.clone();
+        let mut entries = vec![(0, 0.0); triplets.len()];
+        for &(i, j, v) in triplets.iter() {
+            entries[next[i]] = (j, v);
+            next[i] += 1;
+        }
+        let mut matrix = CsrMatrix {
+            nrows,
+            ncols,
+            row_offsets: Vec::with_capacity(nrows + 1),
+            columns: Vec::new(),
+            values: Vec::new(),
+        };
+        matrix.row_offsets.push(0);
+        for i in 0..nrows {
+            let row = &mut entries[counts[i]..counts[i + 1]];
+            row.sort_unstable_by_key(|e| e.0);
+            for &(j, v) in row.iter() {
+                if matrix.columns.len() > matrix.row_offsets[i] && matrix.columns.last() == Some(&j)
+                {
+                    *matrix.values.last_mut().unwrap() += v;
+                } else {
+                    matrix.columns.push(j);
+                    matrix.values.push(v);
+                }
+            }
+            matrix.row_offsets.push(matrix.columns.len());
+        }
+        matrix
+    }
+
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn row(&self, i: usize) -> (&[usize], &[f64]) {
+        let range = self.row_offsets[i]..self.row_offsets[i + 1];
+        (&self.columns[range.clone()], &self.values[range])
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> f64 {
+        let (columns, values) = self.row(i);
+        match columns.binary_search(&j) {
+            Ok(k) => values[k],
+            Err(_) => 0.0,
+        }
+    }
+
+    pub fn diagonal(&self) -> Vec<f64> {
+        (0..self.nrows.min(self.ncols))
+            .map(|i| self.get(i, i))
+            .collect()
+    }
+
+    pub fn multiply(&self, x: &[f64]) -> Vec<f64> {
+        (0..self.nrows)
+            .map(|i| {
+                let (columns, values) = self.row(i);
+                columns
+                    .iter()
+                    .zip(values.iter())
+                    .map(|(&j, v)| v * x[j])
+                    .sum()
+            })
+            .collect()
+    }
+
+    pub fn identity(n: usize) -> Self {
+        CsrMatrix {
+            nrows: n,
+            ncols: n,
+            row_offsets: (0..=n).collect(),
+            columns: (0..n).collect(),
+            values: vec![1.0; n],
+        }
+    }
+
+    pub fn from_dense(rows: &[Vec<f64>]) -> Self {
+        let ncols = rows.first().map_or(0, |r| r.len());
+        let triplets: Vec<(usize, usize, f64)> = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(i, r)| {
+                r.iter()
+                    .enumerate()
+                    .filter(|(_, v)| **v != 0.0)
+                    .map(move |(j, &v)| (i, j, v))
+            })
+            .collect();
+        CsrMatrix::from_triplets_unchecked(rows.len(), ncols, &triplets)
+    }
+
+    pub fn to_dense(&self) -> Vec<Vec<f64>> {
+        let mut dense = vec![vec![0.0; self.ncols]; self.nrows];
+        for (i, row) in dense.iter_mut().enumerate() {
+            let (columns, values) = self.row(i);
+            for (&j, &v) in columns.iter().zip(values.iter()) {
+                row[j] = v;
+            }
+        }
+        dense
+    }
+
+    pub fn transpose(&self) -> Self {
+        let triplets: Vec<(usize, usize, f64)> = (0..self.nrows)
+            .flat_map(|i| {
+                let (columns, values) = self.row(i);
+                columns
+                    .iter()
+                    .zip(values.iter())
+                    .map(move |(&j, &v)| (j, i, v))
+            })
+            .collect();
+        CsrMatrix::from_triplets_unchecked(self.ncols, self.nrows, &triplets)
+    }
+
+    pub fn is_symmetric(&self, tolerance: f64) -> bool {
+        self.nrows == self.ncols
+            && (0..self.nrows).all(|i| {
+                let (columns, values) = self.row(i);
+                columns
+                    .iter()
+                    .zip(values.iter())
+                    .all(|(&j, &v)| (v - self.get(j, i)).abs() <= tolerance * v.abs().max(1.0))
+            })
+    }
+
+    // Symmetric permutation P A P^T where row k of the result is row
+    // permutation[k] of A.
+    pub fn permute(&self, permutation: &[usize]) -> Result<Self, &'static str> {
+        if self.nrows != self.ncols || permutation.len() != self.nrows {
+            return Err("Permutation size does not match the matrix");
+        }
+        let inverse = inverse_permutation(permutation)?;
+        let triplets: Vec<(usize, usize, f64)> = (0..self.nrows)
+            .flat_map(|i| {
+                let (columns, values) = self.row(i);
+                let inverse = &inverse;
+                columns
+                    .iter()
+                    .zip(values.iter())
+                    .map(move |(&j, &v)| (inverse[i], inverse[j], v))
+            })
+            .collect();
+        Ok(CsrMatrix::from_triplets_unchecked(
+            self.nrows, self.ncols, &triplets,
+        ))
+    }
+
+    // Largest distance of a nonzero from the diagonal.
+    pub fn bandwidth(&self) -> usize {
+        (0..self.nrows)
+            .flat_map(|i| self.row(i).0.iter().map(move |&j| i.abs_diff(j)))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+// Inverse of a permutation, checking that it is one.
+pub fn inverse_permutation(permutation: &[usize]) -> Result<Vec<usize>, &'static str> {
+    let mut inverse = vec![usize::MAX; permutation.len()];
+    for (k, &p) in permutation.iter().enumerate() {
+        if p >= permutation.len() || inverse[p] != usize::MAX {
+            return Err("Invalid permutation");
+        }
+        inverse[p] = k;
+    }
+    Ok(inverse)
+}
@@ -0,0 +1,698 @@
+use crate::elasticity::Analysis;
+use crate::mesh::{Facet, Mesh};
+use crate::quadrature::{QuadratureRule, full_degree};
+use crate::shape::{Jacobian, evaluate, node_coordinates};
+use crate::solver::LinearSolver;
+use crate::sparse::{CooMatrix, CsrMatrix};
+use geom::Point3d;
+use std::collections::BTreeMap;
+
+// Heat conduction with one temperature dof per node. The analysis gives the
+// geometry as in elasticity: plane problems have a thickness (unit for
+// plane strain), axisymmetric ones are per full revolution. Radiation needs
+// absolute temperatures.
+
+pub const STEFAN_BOLTZMANN: f64 = 5.670374419e-8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThermalMaterial {
+    pub conductivity: f64,
+    pub density: f64,
+    pub specific_heat: f64,
+}
+
+impl ThermalMaterial {
+    pub fn new(conductivity: f64, density: f64, specific_heat: f64) -> Result<Self, &'static str> {
+        if [conductivity, density, specific_heat]
+            .iter()
+            .any(|v| v.is_nan() || *v <= 0.0)
+        {
+            return Err("Thermal properties must be positive");
+        }
+        Ok(ThermalMaterial {
+            conductivity,
+            density,
+            specific_heat,
+        })
+    }
+
+    // Heat capacity per unit volume.
+    pub fn capacity(&self) -> f64 {
+        self.density * self.specific_heat
+    }
+
+    // Thermal diffusivity.
+    pub fn diffusivity(&self) -> f64 {
+        self.conductivity / self.capacity()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Boundary {
+    // Heat flux into the body.
+    Flux(f64),
+    Convection { coefficient: f64, ambient: f64 },
+    Radiation { emissivity: f64, ambient: f64 },
+}
+
+// Theta-method time stepping: 0 explicit, 0.5 Crank-Nicolson, 1 backward
+// Euler. With a positive tolerance the step is adapted by step doubling to
+// keep the largest estimated temperature error of a step below it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeStepping {
+    pub theta: f64,
+    pub step: f64,
+    pub min_step: f64,
+    pub max_step: f64,
+    pub tolerance: f64,
+}
+
+impl TimeStepping {
+    pub fn fixed(theta: f64, step: f64) -> Self {
+        TimeStepping {
+            theta,
+            step,
+            min_step: step,
+            max_step: step,
+            tolerance: 0.0,
+        }
+    }
+
+    pub fn adaptive(theta: f64, step: f64, tolerance: f64) -> Self {
+        TimeStepping {
+            theta,
+            step,
+            min_step: 1e-6 * step,
+            max_step: f64::INFINITY,
+            tolerance,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ThermalSolution {
+    pub temperatures: Vec<f64>,
+    // Heat flowing into the body at nodes of fixed temperature, zero
+    // elsewhere up to the solver tolerance.
+    pub heat_flows: Vec<f64>,
+    // Heat flux -k grad T averaged over the elements sharing each node.
+    pub fluxes: Vec<[f64; 3]>,
+}
+
+// Temperatures at the accepted time steps, starting with the initial ones.
+#[derive(Debug, Clone)]
+pub struct TransientSolution {
+    pub times: Vec<f64>,
+    pub temperatures: Vec<Vec<f64>>,
+    // Steps repeated with a smaller step size.
+    pub rejected: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct HeatConduction<'a> {
+    mesh: &'a Mesh,
+    analysis: Analysis,
+    materials: Vec<ThermalMaterial>,
+    constraints: BTreeMap<usize, f64>,
+    heat: Vec<f64>,
+    generation: Vec<f64>,
+    boundaries: Vec<(Facet, Boundary)>,
+    lumped: bool,
+    linear_solver: LinearSolver,
+}
+
+impl<'a> HeatConduction<'a> {
+    pub fn new(
+        mesh: &'a Mesh,
+        analysis: Analysis,
+        material: ThermalMaterial,
+    ) -> Result<Self, &'static str> {
+        let dimension = if analysis == Analysis::Solid { 3 } else { 2 };
+        if mesh
+            .elements
+            .iter()
+            .any(|e| e.element_type.dimension() != dimension)
+        {
+            return Err("Element type does not match the analysis");
+        }
+        mesh.validate()?;
+        Ok(HeatConduction {
+            mesh,
+            analysis,
+            materials: vec![material; mesh.num_elements()],
+            constraints: BTreeMap::new(),
+            heat: vec![0.0; mesh.num_nodes()],
+            generation: vec![0.0; mesh.num_elements()],
+            boundaries: Vec::new(),
+            lumped: false,
+            linear_solver: LinearSolver::default(),
+        })
+    }
+
+    pub fn mesh(&self) -> &Mesh {
+        self.mesh
+    }
+
+    pub fn set_material(
+        &mut self,
+        elements: &[usize],
+        material: ThermalMaterial,
+    ) -> Result<(), &'static str> {
+        if elements.iter().any(|&e| e >= self.materials.len()) {
+            return Err("Element index out of range");
+        }
+        for &e in elements.iter() {
+            self.materials[e] = material;
+        }
+        Ok(())
+    }
+
+    pub fn fix(&mut self, node: usize, temperature: f64) -> Result<(), &'static str> {
+        if node >= self.mesh.num_nodes() {
+            return Err("Node index out of range");
+        }
+        self.constraints.insert(node, temperature);
+        Ok(())
+    }
+
+    pub fn fix_nodes(&mut self, nodes: &[usize], temperature: f64) -> Result<(), &'static str> {
+        nodes.iter().try_for_each(|&n| self.fix(n, temperature))
+    }
+
+    // Concentrated heat input at a node.
+    pub fn add_heat(&mut self, node: usize, power: f64) -> Result<(), &'static str> {
+        if node >= self.mesh.num_nodes() {
+            return Err("Node index out of range");
+        }
+        self.heat[node] += power;
+        Ok(())
+    }
+
+    // Heat generated per unit volume.
+    pub fn set_generation(&mut self, elements: &[usize], power: f64) -> Result<(), &'static str> {
+        if elements.iter().any(|&e| e >= self.generation.len()) {
+            return Err("Element index out of range");
+        }
+        for &e in elements.iter() {
+            self.generation[e] = power;
+        }
+        Ok(())
+    }
+
+    fn add_boundary(&mut self, facet: &Facet, boundary: Boundary) -> Result<(), &'static str> {
+        if facet.element >= self.mesh.num_elements()
+            || self.mesh.elements[facet.element]
+                .element_type
+                .facet_type(facet.local)
+                .is_none()
+        {
+            return Err("Facet does not belong to the mesh");
+        }
+        self.boundaries.push((facet.clone(), boundary));
+        Ok(())
+    }
+
+    // Uniform heat flux into the body through a facet.
+    pub fn add_heat_flux(&mut self, facet: &Facet, flux: f64) -> Result<(), &'static str> {
+        self.add_boundary(facet, Boundary::Flux(flux))
+    }
+
+    // Heat loss h (T - ambient) per unit area.
+    pub fn add_convection(
+        &mut self,
+        facet: &Facet,
+        coefficient: f64,
+        ambient: f64,
+    ) -> Result<(), &'static str> {
+        if coefficient.is_nan() || coefficient < 0.0 {
+            return Err("Film coefficient must not be negative");
+        }
+        self.add_boundary(
+            facet,
+            Boundary::Convection {
+                coefficient,
+                ambient,
+            },
+        )
+    }
+
+    // Heat loss emissivity sigma (T^4 - ambient^4) per unit area, to
+    // surroundings at the absolute ambient temperature.
+    pub fn add_radiation(
+        &mut self,
+        facet: &Facet,
+        emissivity: f64,
+        ambient: f64,
+    ) -> Result<(), &'static str> {
+        if emissivity.is_nan() || !(0.0..=1.0).contains(&emissivity) {
+            return Err("Emissivity must lie in [0, 1]");
+        }
+        self.add_boundary(
+            facet,
+            Boundary::Radiation {
+                emissivity,
+                ambient,
+            },
+        )
+    }
+
+    // Row sum lumped capacity, which keeps transient temperatures free of
+    // oscillations at small steps.
+    pub fn set_lumped_capacity(&mut self, lumped: bool) {
+        self.lumped = lumped;
+    }
+
+    pub fn set_linear_solver(&mut self, linear_solver: LinearSolver) {
+        self.linear_solver = linear_solver;
+    }
+
+    // Jacobian determinant times thickness or circumference.
+    fn scale(&self, jacobian: &Jacobian, n: &[f64], points: &[Point3d]) -> f64 {
+        match self.analysis {
+            Analysis::PlaneStress { thickness } => jacobian.determinant * thickness,
+            Analysis::Axisymmetric => {
+                let r: f64 = n
+                    .iter()
+                    .zip(points.iter())
+                    .map(|(n, p)| n * p.get_x())
+                    .sum();
+                jacobian.determinant * 2.0 * std::f64::consts::PI * r
+            }
+            _ => jacobian.determinant,
+        }
+    }
+
+    // Element conductivity and capacity matrices and the heat generated.
+    fn element_matrices(&self, element: usize) -> Result<ElementMatrices, &'static str> {
+        let element_type = self.mesh.elements[element].element_type;
+        let points = self.mesh.element_points(element);
+        let material = &self.materials[element];
+        let size = points.len();
+        let mut conductivity = vec![vec![0.0; size]; size];
+        let mut capacity = vec![vec![0.0; size]; size];
+        let mut generation = vec![0.0; size];
+        // The capacity matrix needs a full rule also on quadratic simplices.
+        for (xi, w) in QuadratureRule::new(element_type, full_degree(element_type)).iter() {
+            let (n, d) = evaluate(element_type, xi);
+            let jacobian = Jacobian::new(element_type, &points, &d)?;
+            let g = jacobian.gradients(&d);
+            let factor = w * self.scale(&jacobian, &n, &points);
+            for a in 0..size {
+                generation[a] += factor * n[a] * self.generation[element];
+                for b in 0..size {
+                    let gg: f64 = (0..3).map(|k| g[a][k] * g[b][k]).sum();
+                    conductivity[a][b] += factor * material.conductivity * gg;
+                    capacity[a][b] += factor * material.capacity() * n[a] * n[b];
+                }
+            }
+        }
+        if self.lumped {
+            for (a, row) in capacity.iter_mut().enumerate() {
+                let sum: f64 = row.iter().sum();
+                row.fill(0.0);
+                row[a] = sum;
+            }
+        }
+        Ok((conductivity, capacity, generation))
+    }
+
+    // Integration points of a facet as shape functions and weights including
+    // the thickness or circumference.
+    fn facet_points(&self, facet: &Facet) -> Result<Vec<(Vec<f64>, f64)>, &'static str> {
+        let element = &self.mesh.elements[facet.element];
+        let facet_type = element
+            .element_type
+            .facet_type(facet.local)
+            .ok_or("Facet does not belong to the mesh")?;
+        let nodes = element.facet_nodes(facet.local);
+        let points: Vec<Point3d> = nodes.iter().map(|&n| self.mesh.nodes[n]).collect();
+        // Exact for the radiation tangent of linear facets and the convection
+        // matrix of quadratic ones.
+        let degree = if facet_type.is_quadratic() { 6 } else { 4 };
+        QuadratureRule::new(facet_type, degree)
+            .iter()
+            .map(|(xi, w)| {
+                let (n, d) = evaluate(facet_type, xi);
+                let jacobian = Jacobian::new(facet_type, &points, &d)?;
+                let scale = self.scale(&jacobian, &n, &points);
+                Ok((n, w * scale))
+            })
+            .collect()
+    }
+
+    // Conductivity matrix including convection.
+    pub fn conductivity(&self) -> Result<CsrMatrix, &'static str> {
+        let n = self.mesh.num_nodes();
+        let mut k = CooMatrix::new(n, n);
+        for e in 0..self.mesh.num_elements() {
+            let nodes = &self.mesh.elements[e].nodes;
+            k.push_block(nodes, nodes, &self.element_matrices(e)?.0)?;
+        }
+        for (facet, boundary) in self.boundaries.iter() {
+            if let Boundary::Convection { coefficient, .. } = boundary {
+                let nodes = self.mesh.elements[facet.element].facet_nodes(facet.local);
+                let mut block = vec![vec![0.0; nodes.len()]; nodes.len()];
+                for (n, w) in self.facet_points(facet)? {
+                    for (a, row) in block.iter_mut().enumerate() {
+                        for (b, v) in row.iter_mut().enumerate() {
+                            *v += coefficient * w * n[a] * n[b];
+                        }
+                    }
+                }
+                k.push_block(&nodes, &nodes, &block)?;
+            }
+        }
+        Ok(k.to_csr())
+    }
+
+    pub fn capacity(&self) -> Result<CsrMatrix, &'static str> {
+        let n = self.mesh.num_nodes();
+        let mut c = CooMatrix::new(n, n);
+        for e in 0..self.mesh.num_elements() {
+            let nodes = &self.mesh.elements[e].nodes;
+            c.push_block(nodes, nodes, &self.element_matrices(e)?.1)?;
+        }
+        Ok(c.to_csr())
+    }
+
+    // Heat inputs independent of the temperature: nodal heat, generation,
+    // fluxes and the ambient part of convection.
+    pub fn load_vector(&self) -> Result<Vec<f64>, &'static str> {
+        let mut f = self.heat.clone();
+        for e in 0..self.mesh.num_elements() {
+            if self.generation[e] == 0.0 {
+                continue;
+            }
+            let generation = self.element_matrices(e)?.2;
+            for (&node, q) in self.mesh.elements[e].nodes.iter().zip(generation) {
+                f[node] += q;
+            }
+        }
+        for (facet, boundary) in self.boundaries.iter() {
+            let flux = match *boundary {
+                Boundary::Flux(flux) => flux,
+                Boundary::Convection {
+                    coefficient,
+                    ambient,
+                } => coefficient * ambient,
+                Boundary::Radiation { .. } => continue,
+            };
+            let nodes = self.mesh.elements[facet.element].facet_nodes(facet.local);
+            for (n, w) in self.facet_points(facet)? {
+                for (&node, n) in nodes.iter().zip(n.iter()) {
+                    f[node] += flux * w * n;
+                }
+            }
+        }
+        Ok(f)
+    }
+
+    // Net radiation heat input and its derivative with respect to the
+    // temperatures, as triplets.
+    fn radiation(&self, temperatures: &[f64]) -> Result<(Vec<f64>, Vec<Triplet>), &'static str> {
+        let mut q = vec![0.0; temperatures.len()];
+        let mut tangent = Vec::new();
+        for (facet, boundary) in self.boundaries.iter() {
+            if let Boundary::Radiation {
+                emissivity,
+                ambient,
+            } = *boundary
+            {
+                let nodes = self.mesh.elements[facet.element].facet_nodes(facet.local);
+                let c = emissivity * STEFAN_BOLTZMANN;
+                for (n, w) in self.facet_points(facet)? {
+                    let t: f64 = nodes
+                        .iter()
+                        .zip(n.iter())
+                        .map(|(&node, n)| n * temperatures[node])
+                        .sum();
+                    for (a, &i) in nodes.iter().enumerate() {
+                        q[i] += c * (ambient.powi(4) - t.powi(4)) * w * n[a];
+                        for (b, &j) in nodes.iter().enumerate() {
+                            tangent.push((i, j, -4.0 * c * t.powi(3) * w * n[a] * n[b]));
+                        }
+                    }
+                }
+            }
+        }
+        Ok((q, tangent))
+    }
+
+    fn is_linear(&self) -> bool {
+        !self
+            .boundaries
+            .iter()
+            .any(|(_, b)| matches!(b, Boundary::Radiation { .. }))
+    }
+
+    // Newton iteration on the free temperatures for a residual and tangent
+    // evaluated at full temperature vectors. Fixed temperatures are applied
+    // to the start.
+    fn newton<F>(&self, start: &[f64], system: F) -> Result<Vec<f64>, &'static str>
+    where
+        F: Fn(&[f64]) -> Result<(Vec<f64>, CsrMatrix), &'static str>,
+    {
+        let n = start.len();
+        let mut t = start.to_vec();
+        let mut index = vec![usize::MAX; n];
+        let mut free = Vec::new();
+        for node in 0..n {
+            match self.constraints.get(&node) {
+                Some(&value) => t[node] = value,
+                None => {
+                    index[node] = free.len();
+                    free.push(node);
+                }
+            }
+        }
+        for _ in 0..50 {
+            let (residual, tangent) = system(&t)?;
+            let mut triplets = Vec::new();
+            for (r, &i) in free.iter().enumerate() {
+                let (columns, values) = tangent.row(i);
+                for (&j, &v) in columns.iter().zip(values.iter()) {
+                    if index[j] != usize::MAX {
+                        triplets.push((r, index[j], v));
+                    }
+                }
+            }
+            let reduced = CsrMatrix::from_triplets(free.len(), free.len(), &triplets)?;
+            let rhs: Vec<f64> = free.iter().map(|&i| -residual[i]).collect();
+            let step = self.linear_solver.solve(&reduced, &rhs)?;
+            let mut change = 0.0f64;
+            for (&node, dt) in free.iter().zip(step) {
+                t[node] += dt;
+                change = change.max(dt.abs());
+            }
+            let size = t.iter().fold(0.0f64, |m, t| m.max(t.abs()));
+            if self.is_linear() || change <= 1e-10 * size.max(1.0) {
+                return Ok(t);
+            }
+        }
+        Err("Heat transfer iterations did not converge")
+    }
+
+    pub fn solve_steady(&self) -> Result<ThermalSolution, &'static str> {
+        let k = self.conductivity()?;
+        let f = self.load_vector()?;
+        let n = self.mesh.num_nodes();
+        // Radiation needs a start away from zero; use the largest fixed or
+        // ambient temperature.
+        let start = self
+            .constraints
+            .values()
+            .chain(self.boundaries.iter().filter_map(|(_, b)| match b {
+                Boundary::Radiation { ambient, .. } => Some(ambient),
+                _ => None,
+            }))
+            .fold(0.0f64, |m, &t| m.max(t));
+        let residual = |t: &[f64]| -> Result<(Vec<f64>, CsrMatrix), &'static str> {
+            let (q, tangent) = self.radiation(t)?;
+            let kt = k.multiply(t);
+            let r = (0..n).map(|i| kt[i] - f[i] - q[i]).collect();
+            Ok((r, combine(&[(1.0, &k)], &tangent, -1.0)))
+        };
+        let temperatures = self.newton(&vec![start; n], residual)?;
+        let heat_flows = residual(&temperatures)?.0;
+        let fluxes = self.nodal_fluxes(&temperatures)?;
+        Ok(ThermalSolution {
+            temperatures,
+            heat_flows,
+            fluxes,
+        })
+    }
+
+    // One theta-method step from temperatures at time t to t + step.
+    fn step(
+        &self,
+        k: &CsrMatrix,
+        c: &CsrMatrix,
+        f: &[f64],
+        temperatures: &[f64],
+        step: f64,
+        theta: f64,
+    ) -> Result<Vec<f64>, &'static str> {
+        let n = temperatures.len();
+        // Out of balance heat of the previous state.
+        let (q, _) = self.radiation(temperatures)?;
+        let kt = k.multiply(temperatures);
+        let previous: Vec<f64> = (0..n).map(|i| kt[i] - f[i] - q[i]).collect();
+        self.newton(temperatures, |t| {
+            let (q, tangent) = self.radiation(t)?;
+            let kt = k.multiply(t);
+            let dt: Vec<f64> = t
+                .iter()
+                .zip(temperatures.iter())
+                .map(|(a, b)| a - b)
+                .collect();
+            let cdt = c.multiply(&dt);
+            let r = (0..n)
+                .map(|i| {
+                    cdt[i] / step + theta * (kt[i] - f[i] - q[i]) + (1.0 - theta) * previous[i]
+                })
+                .collect();
+            Ok((r, combine(&[(1.0 / step, c), (theta, k)], &tangent, -theta)))
+        })
+    }
+
+    // Transient temperatures from initial ones up to the end time, with
+    // loads and fixed temperatures held constant.
+    pub fn solve_transient(
+        &self,
+        initial: &[f64],
+        end: f64,
+        stepping: &TimeStepping,
+    ) -> Result<TransientSolution, &'static str> {
+        if initial.len() != self.mesh.num_nodes() {
+            return Err("Temperature count does not match the mesh");
+        }
+        let theta = stepping.theta;
+        if theta.is_nan()
+            || !(0.0..=1.0).contains(&theta)
+            || stepping.step.is_nan()
+            || stepping.step <= 0.0
+        {
+            return Err("Theta must lie in [0, 1] and the step be positive");
+        }
+        let k = self.conductivity()?;
+        let c = self.capacity()?;
+        let f = self.load_vector()?;
+        // Local error of the step grows like step^(order + 1).
+        let order = if (theta - 0.5).abs() < 1e-12 { 2 } else { 1 };
+        let exponent = 1.0 / (order as f64 + 1.0);
+
+        let mut temperatures = initial.to_vec();
+        for (&node, &value) in self.constraints.iter() {
+            temperatures[node] = value;
+        }
+        let mut solution = TransientSolution {
+            times: vec![0.0],
+            temperatures: vec![temperatures.clone()],
+            rejected: 0,
+        };
+        let mut time = 0.0;
+        let mut step = stepping.step;
+        while time < end * (1.0 - 1e-12) {
+            let h = step.min(end - time);
+            let full = self.step(&k, &c, &f, &temperatures, h, theta)?;
+            if stepping.tolerance > 0.0 {
+                let half = self.step(&k, &c, &f, &temperatures, 0.5 * h, theta)?;
+                let half = self.step(&k, &c, &f, &half, 0.5 * h, theta)?;
+                let error = full
+                    .iter()
+                    .zip(half.iter())
+                    .fold(0.0f64, |m, (a, b)| m.max((a - b).abs()));
+                let ratio = (stepping.tolerance / error.max(1e-300)).powf(exponent);
+                if error > stepping.tolerance {
+                    if h <= stepping.min_step {
+                        return Err("Time step fell below the minimum step");
+                    }
+                    step = (0.9 * ratio).max(0.2) * h;
+                    step = step.max(stepping.min_step);
+                    solution.rejected += 1;
+                    continue;
+                }
+                temperatures = half;
+                step = ((0.9 * ratio).min(5.0) * h)
+                    .min(stepping.max_step)
+                    .max(stepping.min_step);
+            } else {
+                temperatures = full;
+            }
+            time += h;
+            solution.times.push(time);
+            solution.temperatures.push(temperatures.clone());
+        }
+        Ok(solution)
+    }
+
+    // Heat flux -k grad T at a natural point of an element.
+    pub fn flux(
+        &self,
+        temperatures: &[f64],
+        element: usize,
+        xi: [f64; 3],
+    ) -> Result<[f64; 3], &'static str> {
+        let element_type = self.mesh.elements[element].element_type;
+        let points = self.mesh.element_points(element);
+        let d = evaluate(element_type, xi).1;
+        let g = Jacobian::new(element_type, &points, &d)?.gradients(&d);
+        let k = self.materials[element].conductivity;
+        let mut flux = [0.0; 3];
+        for (g, &node) in g.iter().zip(self.mesh.elements[element].nodes.iter()) {
+            for (f, g) in flux.iter_mut().zip(g.iter()) {
+                *f -= k * g * temperatures[node];
+            }
+        }
+        Ok(flux)
+    }
+
+    fn nodal_fluxes(&self, temperatures: &[f64]) -> Result<Vec<[f64; 3]>, &'static str> {
+        let count = self.mesh.num_nodes();
+        let mut fluxes = vec![[0.0; 3]; count];
+        let mut shared = vec![0usize; count];
+        for (e, element) in self.mesh.elements.iter().enumerate() {
+            let coordinates = node_coordinates(element.element_type);
+            for (&node, &xi) in element.nodes.iter().zip(coordinates.iter()) {
+                let flux = self.flux(temperatures, e, xi)?;
+                for c in 0..3 {
+                    fluxes[node][c] += flux[c];
+                }
+                shared[node] += 1;
+            }
+        }
+        for (flux, &m) in fluxes.iter_mut().zip(shared.iter()) {
+            if m > 0 {
+                *flux = flux.map(|v| v / m as f64);
+            }
+        }
+        Ok(fluxes)
+    }
+}
+
+// Row, column and value of a sparse matrix entry.
+type Triplet = (usize, usize, f64);
+
+// Element conductivity, capacity and generated heat.
+type ElementMatrices = (Vec<Vec<f64>>, Vec<Vec<f64>>, Vec<f64>);
+
+// Weighted sum of matrices plus scaled extra triplets.
+fn combine(matrices: &[(f64, &CsrMatrix)], triplets: &[Triplet], scale: f64) -> CsrMatrix {
+    let n = matrices[0].1.nrows;
+    let mut all: Vec<Triplet> = triplets
+        .iter()
+        .map(|&(i, j, v)| (i, j, scale * v))
+        .collect();
+    for (factor, m) in matrices.iter() {
+        for i in 0..n {
+            let (columns, values) = m.row(i);
+            all.extend(
+                columns
+                    .iter()
+                    .zip(values.iter())
+                    .map(|(&j, &v)| (i, j, factor * v)),
+            );
+        }
+    }
+    CsrMatrix::from_triplets_unchecked(n, n, &all)
+}
@@ -0,0 +1,299 @@
+use fem::dynamics::rayleigh_coefficients;
+use fem::{
+    Analysis, ElementType, Integrator, IsotropicElastic, LinearStatic, LoadCurve, Mesh,
+    StructuralDynamics,
+};
+
+// A unit square element clamped at x = 0 and held in y, with unit stiffness
+// E A / L and, lumped, unit free mass. Equal displacements of the free
+// nodes 1 and 2 vibrate at unit angular frequency.
+fn oscillator(mesh: &Mesh, lumped: bool) -> StructuralDynamics<'_> {
+    let material = IsotropicElastic::new(1.0, 0.0).unwrap();
+    let mut problem = LinearStatic::new(mesh, Analysis::PlaneStrain, material).unwrap();
+    problem.set_density(&[0], 2.0).unwrap();
+    for n in 0..4 {
+        problem.fix(n, 1, 0.0).unwrap();
+    }
+    for n in mesh.find_nodes(|p| p.get_x() < 1e-12) {
+        problem.fix(n, 0, 0.0).unwrap();
+    }
+    let mut dynamics = StructuralDynamics::new(problem, lumped);
+    dynamics.set_history_nodes(&free_nodes(mesh)).unwrap();
+    dynamics
+}
+
+fn free_nodes(mesh: &Mesh) -> Vec<usize> {
+    mesh.find_nodes(|p| p.get_x() > 1.0 - 1e-12)
+}
+
+// Both free nodes displaced by the given amounts.
+fn displace(dynamics: &mut StructuralDynamics, mesh: &Mesh, amounts: [f64; 2]) {
+    let mut u = vec![[0.0; 3]; mesh.num_nodes()];
+    for (&n, a) in free_nodes(mesh).iter().zip(amounts) {
+        u[n][0] = a;
+    }
+    let v = vec![[0.0; 3]; mesh.num_nodes()];
+    dynamics.set_initial_conditions(&u, &v).unwrap();
+}
+
+fn history(solution: &fem::DynamicSolution, which: usize) -> Vec<f64> {
+    solution.histories[which]
+        .displacements
+        .iter()
+        .map(|u| u[0])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_curve() {
+        let curve = LoadCurve::new(&[(0.0, 0.0), (1.0, 2.0), (3.0, -2.0)]).unwrap();
+        for (t, expected) in [
+            (-1.0, 0.0),
+            (0.5, 1.0),
+            (1.0, 2.0),
+            (2.5, -1.0),
+            (5.0, -2.0),
+        ] {
+            assert!((curve.value(t) - expected).abs() < 1e-15);
+        }
+        assert_eq!(curve.points(), vec![(0.0, 0.0), (1.0, 2.0), (3.0, -2.0)]);
+        assert_eq!(LoadCurve::constant(3.0).value(10.0), 3.0);
+        assert!(LoadCurve::new(&[]).is_err());
+        assert!(LoadCurve::new(&[(1.0, 0.0), (1.0, 1.0)]).is_err());
+
+        let (ratio, first, second) = (0.05, 2.0, 30.0);
+        let (a, b) = rayleigh_coefficients(ratio, first, second);
+        for w in [first, second] {
+            assert!((a / (2.0 * w) + b * w / 2.0 - ratio).abs() < 1e-15);
+        }
+    }
+
+    #[test]
+    fn test_newmark() {
+        let mesh = Mesh::rectangle(1.0, 1.0, 1, 1, ElementType::Quad4).unwrap();
+        let u0 = 0.01;
+        // The consistent mass of the free nodes is a third of the lumped one.
+        for (lumped, omega) in [(true, 1.0), (false, 1.5f64.sqrt())] {
+            let mut dynamics = oscillator(&mesh, lumped);
+            displace(&mut dynamics, &mesh, [u0, u0]);
+            let dt = 0.1;
+            let solution = dynamics
+                .solve(20.0, dt, Integrator::average_acceleration())
+                .unwrap();
+            assert_eq!(solution.times.len(), 201);
+            // Exact in time but for the period elongation.
+            let discrete = 2.0 / dt * (omega * dt / 2.0).atan();
+            for (t, u) in solution.times.iter().zip(history(&solution, 0)) {
+                assert!((u - u0 * (discrete * t).cos()).abs() < 1e-12);
+            }
+            // Energy is conserved.
+            let node = &solution.histories[1];
+            for (u, v) in node.displacements.iter().zip(node.velocities.iter()) {
+                let energy = (omega * u[0]).powi(2) + v[0] * v[0];
+                assert!((energy - (omega * u0).powi(2)).abs() < 1e-15);
+            }
+            assert_eq!(
+                solution.displacements[node.node],
+                *node.displacements.last().unwrap()
+            );
+            assert_eq!(solution.histories[0].displacements[0][1], 0.0);
+        }
+        let dynamics = oscillator(&mesh, true);
+        let invalid = Integrator::Newmark {
+            beta: 0.25,
+            gamma: 0.4,
+        };
+        assert!(dynamics.solve(1.0, 0.1, invalid).is_err());
+        assert!(
+            dynamics
+                .solve(0.0, 0.1, Integrator::average_acceleration())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_central_difference() {
+        let mesh = Mesh::rectangle(1.0, 1.0, 1, 1, ElementType::Quad4).unwrap();
+        let u0 = 0.01;
+        let mut dynamics = oscillator(&mesh, true);
+        displace(&mut dynamics, &mesh, [u0, u0]);
+        let dt = 0.2;
+        let solution = dynamics
+            .solve(10.0, dt, Integrator::CentralDifference)
+            .unwrap();
+        let discrete = (1.0 - dt * dt / 2.0).acos() / dt;
+        for (t, u) in solution.times.iter().zip(history(&solution, 0)) {
+            assert!((u - u0 * (discrete * t).cos()).abs() < 1e-12);
+        }
+
+        // Stable below 2 / omega of the highest mode only, and refused above.
+        let modes = dynamics.problem().modes(2, true).unwrap();
+        let stable = dynamics.stable_time_step().unwrap();
+        assert!((stable - 2.0 / modes.angular_frequencies[1]).abs() < 1e-10 * stable);
+        displace(&mut dynamics, &mesh, [u0, 0.0]);
+        let largest = |step: f64| -> f64 {
+            let solution = dynamics
+                .solve(400.0 * step, step, Integrator::CentralDifference)
+                .unwrap();
+            history(&solution, 0)
+                .iter()
+                .fold(0.0f64, |m, u| m.max(u.abs()))
+        };
+        assert!(largest(0.98 * stable) < 10.0 * u0);
+        let step = 1.02 * stable;
+        assert!(
+            dynamics
+                .solve(400.0 * step, step, Integrator::CentralDifference)
+                .is_err()
+        );
+
+        let consistent = oscillator(&mesh, false);
+        assert!(
+            consistent
+                .solve(1.0, 0.1, Integrator::CentralDifference)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_hht() {
+        let mesh = Mesh::rectangle(1.0, 1.0, 1, 1, ElementType::Quad4).unwrap();
+        let u0 = 0.01;
+        let mut dynamics = oscillator(&mesh, true);
+        displace(&mut dynamics, &mesh, [u0, u0]);
+        let same = dynamics
+            .solve(5.0, 0.1, Integrator::Hht { alpha: 0.0 })
+            .unwrap();
+        let newmark = dynamics
+            .solve(5.0, 0.1, Integrator::average_acceleration())
+            .unwrap();
+        assert_eq!(history(&same, 0), history(&newmark, 0));
+
+        // Small steps stay accurate, large ones damp the response.
+        let accurate = dynamics
+            .solve(6.0, 0.01, Integrator::Hht { alpha: -0.1 })
+            .unwrap();
+        for (t, u) in accurate.times.iter().zip(history(&accurate, 0)) {
+            assert!((u - u0 * t.cos()).abs() < 1e-5 * u0 * (1.0 + t));
+        }
+        let damped = dynamics
+            .solve(1000.0, 10.0, Integrator::Hht { alpha: -0.3 })
+            .unwrap();
+        let last = history(&damped, 0).last().unwrap().abs();
+        assert!(last < 1e-3 * u0);
+        assert!(
+            dynamics
+                .solve(1.0, 0.1, Integrator::Hht { alpha: -0.5 })
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_damping() {
+        let mesh = Mesh::rectangle(1.0, 1.0, 1, 1, ElementType::Quad4).unwrap();
+        let u0 = 0.01;
+        let ratio = 0.05;
+        let mut dynamics = oscillator(&mesh, true);
+        displace(&mut dynamics, &mesh, [u0, u0]);
+        dynamics.set_rayleigh_damping(2.0 * ratio, 0.0).unwrap();
+        let solution = dynamics
+            .solve(15.0, 0.005, Integrator::average_acceleration())
+            .unwrap();
+        let damped = (1.0 - ratio * ratio).sqrt();
+        for (t, u) in solution.times.iter().zip(history(&solution, 0)) {
+            let expected = u0
+                * (-ratio * t).exp()
+                * ((damped * t).cos() + ratio / damped * (damped * t).sin());
+            assert!((u - expected).abs() < 1e-4 * u0);
+        }
+        assert!(dynamics.set_rayleigh_damping(-1.0, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_step_load() {
+        // A suddenly applied force overshoots the static displacement
+        // twice.
+        let mesh = Mesh::rectangle(1.0, 1.0, 1, 1, ElementType::Quad4).unwrap();
+        let force = 0.02;
+        let mut dynamics = oscillator(&mesh, true);
+        for n in free_nodes(&mesh) {
+            dynamics.problem_mut().add_force(n, 0, force / 2.0).unwrap();
+        }
+        let step = dynamics
+            .solve(7.0, 0.01, Integrator::average_acceleration())
+            .unwrap();
+        let largest = history(&step, 0).iter().fold(0.0f64, |m, &u| m.max(u));
+        assert!((largest - 2.0 * force).abs() < 1e-4 * force);
+
+        // The same with nodal loads of their own curve, here a ramp over
+        // the period, which the structure follows quasi statically.
+        let ramp = LoadCurve::new(&[(0.0, 0.0), (100.0, 1.0)]).unwrap();
+        let mut dynamics = oscillator(&mesh, true);
+        for n in free_nodes(&mesh) {
+            dynamics.add_load(n, 0, force / 2.0, ramp.clone()).unwrap();
+        }
+        let slow = dynamics
+            .solve(100.0, 0.1, Integrator::average_acceleration())
+            .unwrap();
+        let last = *history(&slow, 0).last().unwrap();
+        assert!((last - force).abs() < 0.02 * force);
+        let mut scaled = oscillator(&mesh, true);
+        for n in free_nodes(&mesh) {
+            scaled.problem_mut().add_force(n, 0, force / 2.0).unwrap();
+        }
+        scaled.set_load_curve(ramp);
+        let same = scaled
+            .solve(100.0, 0.1, Integrator::average_acceleration())
+            .unwrap();
+        assert_eq!(history(&slow, 0), history(&same, 0));
+        assert!(
+            dynamics
+                .add_load(100, 0, 1.0, LoadCurve::constant(1.0))
+                .is_err()
+        );
+        assert!(dynamics.set_history_nodes(&[100]).is_err());
+    }
+
+    #[test]
+    fn test_mode_vibration() {
+        // A cantilever started in its first mode vibrates in that shape.
+        let mesh = Mesh::rectangle(10.0, 1.0, 20, 2, ElementType::Quad8).unwrap();
+        let material = IsotropicElastic::new(1000.0, 0.3).unwrap();
+        let mut problem = LinearStatic::new(&mesh, Analysis::PlaneStrain, material).unwrap();
+        problem
+            .fix_nodes(&mesh.find_nodes(|p| p.get_x() < 1e-12))
+            .unwrap();
+        let elements: Vec<usize> = (0..mesh.num_elements()).collect();
+        problem.set_density(&elements, 1.0).unwrap();
+        let modes = problem.modes(1, false).unwrap();
+        let omega = modes.angular_frequencies[0];
+        let shape = &modes.mode_shapes[0];
+        let tip = mesh.find_nodes(|p| p.get_x() > 10.0 - 1e-12 && p.get_y() > 1.0 - 1e-12)[0];
+
+        let mut dynamics = StructuralDynamics::new(problem, false);
+        let rest = vec![[0.0; 3]; mesh.num_nodes()];
+        dynamics.set_initial_conditions(shape, &rest).unwrap();
+        dynamics.set_history_nodes(&[tip]).unwrap();
+        let period = 2.0 * std::f64::consts::PI / omega;
+        let dt = period / 40.0;
+        let solution = dynamics
+            .solve(2.0 * period, dt, Integrator::average_acceleration())
+            .unwrap();
+        let discrete = 2.0 / dt * (omega * dt / 2.0).atan();
+        let node = &solution.histories[0];
+        for (t, u) in solution.times.iter().zip(node.displacements.iter()) {
+            for d in 0..2 {
+                let expected = shape[tip][d] * (discrete * t).cos();
+                assert!((u[d] - expected).abs() < 1e-8 * shape[tip][1].abs());
+            }
+        }
+        for (u, expected) in solution.displacements.iter().zip(shape.iter()) {
+            let c = (discrete * solution.times.last().unwrap()).cos();
+            assert!((u[1] - c * expected[1]).abs() < 1e-8 * shape[tip][1].abs());
+        }
+    }
+}
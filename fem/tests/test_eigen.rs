@@ -0,0 +1,210 @@
+use fem::eigen::{buckling_eigen, generalized_eigen, symmetric_eigen};
+use fem::solver::dense_solve;
+use fem::{CooMatrix, CsrMatrix, Ordering};
+use std::f64::consts::PI;
+
+// Spring chain fixed at both ends: tridiagonal 2, -1.
+fn chain(n: usize) -> CsrMatrix {
+    let mut a = CooMatrix::new(n, n);
+    for i in 0..n {
+        a.push(i, i, 2.0).unwrap();
+        if i > 0 {
+            a.push(i, i - 1, -1.0).unwrap();
+            a.push(i - 1, i, -1.0).unwrap();
+        }
+    }
+    a.to_csr()
+}
+
+// Five point Laplacian on an m x m grid.
+fn grid(m: usize) -> CsrMatrix {
+    let n = m * m;
+    let mut a = CooMatrix::new(n, n);
+    for j in 0..m {
+        for i in 0..m {
+            let k = j * m + i;
+            a.push(k, k, 4.0).unwrap();
+            if i > 0 {
+                a.push(k, k - 1, -1.0).unwrap();
+            }
+            if i + 1 < m {
+                a.push(k, k + 1, -1.0).unwrap();
+            }
+            if j > 0 {
+                a.push(k, k - m, -1.0).unwrap();
+            }
+            if j + 1 < m {
+                a.push(k, k + m, -1.0).unwrap();
+            }
+        }
+    }
+    a.to_csr()
+}
+
+fn diagonal(values: &[f64]) -> CsrMatrix {
+    let triplets: Vec<(usize, usize, f64)> =
+        values.iter().enumerate().map(|(i, &v)| (i, i, v)).collect();
+    CsrMatrix::from_triplets(values.len(), values.len(), &triplets).unwrap()
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b.iter()).map(|(a, b)| a * b).sum()
+}
+
+// Largest entry of K x - lambda M x.
+fn residual(k: &CsrMatrix, m: &CsrMatrix, lambda: f64, x: &[f64]) -> f64 {
+    let (kx, mx) = (k.multiply(x), m.multiply(x));
+    kx.iter()
+        .zip(mx.iter())
+        .map(|(k, m)| (k - lambda * m).abs())
+        .fold(0.0, f64::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symmetric_eigen() {
+        let a = vec![
+            vec![2.0, -1.0, 0.0],
+            vec![-1.0, 2.0, -1.0],
+            vec![0.0, -1.0, 2.0],
+        ];
+        let (values, vectors) = symmetric_eigen(&a);
+        let root = 2f64.sqrt();
+        for (value, expected) in values.iter().zip([2.0 - root, 2.0, 2.0 + root]) {
+            assert!((value - expected).abs() < 1e-12);
+        }
+        for (i, (value, v)) in values.iter().zip(vectors.iter()).enumerate() {
+            for (row, vi) in a.iter().zip(v.iter()) {
+                assert!((dot(row, v) - value * vi).abs() < 1e-12);
+            }
+            for (j, w) in vectors.iter().enumerate() {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((dot(v, w) - expected).abs() < 1e-12);
+            }
+        }
+        assert!(symmetric_eigen(&[]).0.is_empty());
+    }
+
+    #[test]
+    fn test_generalized_eigen() {
+        let n = 200;
+        let k = chain(n);
+        let m = diagonal(&vec![1.0; n]);
+        let pairs = generalized_eigen(&k, &m, 6, 0.0, Ordering::default()).unwrap();
+        for (i, (value, x)) in pairs.values.iter().zip(pairs.vectors.iter()).enumerate() {
+            let expected = 2.0 - 2.0 * (PI * (i + 1) as f64 / (n + 1) as f64).cos();
+            assert!((value - expected).abs() < 1e-10 * expected);
+            assert!(residual(&k, &m, *value, x) < 1e-8 * expected);
+            assert!((dot(x, &m.multiply(x)) - 1.0).abs() < 1e-10);
+        }
+
+        // Above a shift in the middle of the spectrum.
+        let shift = 1.01;
+        let pairs = generalized_eigen(&k, &m, 3, shift, Ordering::default()).unwrap();
+        let mut above: Vec<f64> = (1..=n)
+            .map(|i| 2.0 - 2.0 * (PI * i as f64 / (n + 1) as f64).cos())
+            .filter(|&v| v >= shift)
+            .collect();
+        above.truncate(3);
+        for (value, expected) in pairs.values.iter().zip(above.iter()) {
+            assert!((value - expected).abs() < 1e-10);
+        }
+
+        assert!(generalized_eigen(&k, &m, n + 1, 0.0, Ordering::default()).is_err());
+        assert!(generalized_eigen(&k, &diagonal(&[1.0; 3]), 1, 0.0, Ordering::default()).is_err());
+    }
+
+    #[test]
+    fn test_repeated_eigenvalues() {
+        // The square grid has pairs of equal eigenvalues, which a single
+        // Lanczos sequence cannot resolve on its own.
+        let size = 20;
+        let k = grid(size);
+        let m = diagonal(&vec![1.0; size * size]);
+        let pairs = generalized_eigen(&k, &m, 8, 0.0, Ordering::default()).unwrap();
+        assert_eq!(pairs.values.len(), 8);
+        let one = |i: usize| 2.0 - 2.0 * (PI * i as f64 / (size + 1) as f64).cos();
+        let mut expected: Vec<f64> = (1..6)
+            .flat_map(|i| (1..6).map(move |j| (i, j)))
+            .map(|(i, j)| one(i) + one(j))
+            .collect();
+        expected.sort_by(f64::total_cmp);
+        for (value, expected) in pairs.values.iter().zip(expected.iter()) {
+            assert!((value - expected).abs() < 1e-10, "{value} {expected}");
+        }
+        for (i, x) in pairs.vectors.iter().enumerate() {
+            for (j, y) in pairs.vectors.iter().enumerate() {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((dot(x, &m.multiply(y)) - expected).abs() < 1e-8);
+            }
+        }
+    }
+
+    #[test]
+    fn test_singular_mass() {
+        // Massless dofs, as rotations with a lumped mass matrix, against
+        // static condensation.
+        let n = 12;
+        let k = chain(n);
+        let masses: Vec<f64> = (0..n)
+            .map(|i| if i % 2 == 0 { 1.0 + i as f64 } else { 0.0 })
+            .collect();
+        let m = diagonal(&masses);
+        let dense = k.to_dense();
+        let (kept, dropped): (Vec<usize>, Vec<usize>) = (0..n).partition(|&i| masses[i] > 0.0);
+        let block = |rows: &[usize], columns: &[usize]| -> Vec<Vec<f64>> {
+            rows.iter()
+                .map(|&i| columns.iter().map(|&j| dense[i][j]).collect())
+                .collect()
+        };
+        let x = dense_solve(&block(&dropped, &dropped), &block(&dropped, &kept)).unwrap();
+        let coupling = block(&kept, &dropped);
+        let mut condensed = block(&kept, &kept);
+        for (i, row) in condensed.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v -= (0..dropped.len())
+                    .map(|r| coupling[i][r] * x[r][j])
+                    .sum::<f64>();
+            }
+        }
+        // M^-1/2 K M^-1/2
+        for (i, row) in condensed.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v /= (masses[kept[i]] * masses[kept[j]]).sqrt();
+            }
+        }
+        let expected = symmetric_eigen(&condensed).0;
+
+        let pairs = generalized_eigen(&k, &m, 4, 0.0, Ordering::default()).unwrap();
+        for (value, x) in pairs.values.iter().zip(pairs.vectors.iter()) {
+            assert!(residual(&k, &m, *value, x) < 1e-9);
+        }
+        for (value, expected) in pairs.values.iter().zip(expected.iter()) {
+            assert!((value - expected).abs() < 1e-10 * expected);
+        }
+        // Only as many finite eigenvalues as masses.
+        assert!(generalized_eigen(&k, &m, n / 2 + 1, 0.0, Ordering::default()).is_err());
+    }
+
+    #[test]
+    fn test_buckling_eigen() {
+        let k = diagonal(&[1.0, 2.0, 3.0, 4.0]);
+        // Compression in three dofs, tension in one.
+        let g = diagonal(&[-1.0, -4.0, 1.0, -1.0]);
+        let pairs = buckling_eigen(&k, &g, 3, Ordering::default()).unwrap();
+        for (value, expected) in pairs.values.iter().zip([0.5, 1.0, 4.0]) {
+            assert!((value - expected).abs() < 1e-12);
+        }
+        for (x, dof) in pairs.vectors.iter().zip([1, 0, 3]) {
+            assert!((x[dof].abs() - 1.0 / k.get(dof, dof).sqrt()).abs() < 1e-12);
+        }
+        assert!(buckling_eigen(&k, &g, 4, Ordering::default()).is_err());
+        assert!(buckling_eigen(&k, &diagonal(&[0.0; 4]), 1, Ordering::default()).is_err());
+        assert!(buckling_eigen(&chain(4), &g, 1, Ordering::default()).is_ok());
+        let indefinite = diagonal(&[1.0, -1.0, 1.0, 1.0]);
+        assert!(buckling_eigen(&indefinite, &g, 1, Ordering::default()).is_err());
+    }
+}
@@ -0,0 +1,425 @@
+use fem::material::von_mises;
+use fem::{Analysis, ElementType, IsotropicElastic, LinearStatic, Mesh};
+use geom::Point3d;
+
+fn steel() -> IsotropicElastic {
+    IsotropicElastic::new(200.0, 0.3).unwrap()
+}
+
+// Largest absolute difference between a nodal field component and a value.
+fn max_error(values: &[[f64; 6]], component: usize, expected: f64) -> f64 {
+    values
+        .iter()
+        .map(|v| (v[component] - expected).abs())
+        .fold(0.0, f64::max)
+}
+
+// Uniaxial tension of [0, lx] x [0, ly] held on rollers at x = 0 and y = 0.
+fn plane_tension(element_type: ElementType, analysis: Analysis, stress: f64) {
+    let (lx, ly) = (4.0, 1.0);
+    let mesh = Mesh::rectangle(lx, ly, 4, 2, element_type).unwrap();
+    let material = steel();
+    let mut problem = LinearStatic::new(&mesh, analysis, material).unwrap();
+    for n in mesh.find_nodes(|p| p.get_x() < 1e-12) {
+        problem.fix(n, 0, 0.0).unwrap();
+    }
+    for n in mesh.find_nodes(|p| p.get_y() < 1e-12) {
+        problem.fix(n, 1, 0.0).unwrap();
+    }
+    for facet in mesh.find_boundary_facets(|p| p.get_x() > lx - 1e-12) {
+        problem.add_pressure(&facet, -stress).unwrap();
+    }
+    let solution = problem.solve().unwrap();
+
+    let (e, nu) = (material.young, material.poisson);
+    // Effective modulus and Poisson's ratio in the plane.
+    let (e, nu) = match analysis {
+        Analysis::PlaneStrain => (e / (1.0 - nu * nu), nu / (1.0 - nu)),
+        _ => (e, nu),
+    };
+    for (p, u) in mesh.nodes.iter().zip(solution.displacements.iter()) {
+        assert!((u[0] - stress * p.get_x() / e).abs() < 1e-9);
+        assert!((u[1] + nu * stress * p.get_y() / e).abs() < 1e-9);
+    }
+    assert!(max_error(&solution.stresses, 0, stress) < 1e-8);
+    assert!(max_error(&solution.stresses, 1, 0.0) < 1e-8);
+    assert!(max_error(&solution.stresses, 3, 0.0) < 1e-8);
+    assert!(max_error(&solution.strains, 0, stress / e) < 1e-10);
+    let thickness = match analysis {
+        Analysis::PlaneStress { thickness } => {
+            let zz = -material.poisson * stress / material.young;
+            assert!(max_error(&solution.strains, 2, zz) < 1e-10);
+            thickness
+        }
+        _ => {
+            let zz = material.poisson * stress;
+            assert!(max_error(&solution.stresses, 2, zz) < 1e-8);
+            1.0
+        }
+    };
+    let reaction: f64 = solution.reactions.iter().map(|r| r[0]).sum();
+    assert!((reaction + stress * ly * thickness).abs() < 1e-8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_material() {
+        assert!(IsotropicElastic::new(0.0, 0.3).is_err());
+        assert!(IsotropicElastic::new(1.0, 0.5).is_err());
+        let material = steel();
+        let (lambda, mu) = material.lame();
+        assert!((mu - 200.0 / 2.6).abs() < 1e-12);
+        let d = material.solid_matrix();
+        assert!((d[0][0] - lambda - 2.0 * mu).abs() < 1e-12);
+        assert!((d[1][2] - lambda).abs() < 1e-12);
+        assert!((d[5][5] - mu).abs() < 1e-12);
+        assert!((von_mises(&[3.0, 0.0, 0.0, 0.0, 0.0, 0.0]) - 3.0).abs() < 1e-15);
+        assert!((von_mises(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0]) - 3f64.sqrt()).abs() < 1e-15);
+        assert!(von_mises(&[2.0, 2.0, 2.0, 0.0, 0.0, 0.0]).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_plane_patch() {
+        for t in [
+            ElementType::Tri3,
+            ElementType::Tri6,
+            ElementType::Quad4,
+            ElementType::Quad8,
+            ElementType::Quad9,
+        ] {
+            plane_tension(t, Analysis::PlaneStress { thickness: 0.5 }, 10.0);
+            plane_tension(t, Analysis::PlaneStrain, -3.0);
+        }
+
+        let mesh = Mesh::block(1.0, 1.0, 1.0, 1, 1, 1, ElementType::Hex8).unwrap();
+        assert_eq!(
+            LinearStatic::new(&mesh, Analysis::PlaneStrain, steel()).err(),
+            Some("Element type does not match the analysis")
+        );
+    }
+
+    #[test]
+    fn test_solid_patch() {
+        let (lx, ly, lz) = (3.0, 1.0, 2.0);
+        let stress = 5.0;
+        let material = steel();
+        for t in [
+            ElementType::Hex8,
+            ElementType::Hex20,
+            ElementType::Hex27,
+            ElementType::Tet4,
+            ElementType::Tet10,
+            ElementType::Wedge6,
+        ] {
+            let mut mesh = Mesh::block(lx, ly, lz, 3, 1, 2, t).unwrap();
+            // Distort the interior so the patch test is not trivial.
+            for p in mesh.nodes.iter_mut() {
+                let (x, y, z) = p.get_coords();
+                if x > 1e-9 && x < lx - 1e-9 && z > 1e-9 && z < lz - 1e-9 {
+                    *p = Point3d::from_coords(x + 0.1 * z, y, z - 0.05 * x);
+                }
+            }
+            let mut problem = LinearStatic::new(&mesh, Analysis::Solid, material).unwrap();
+            for d in 0..3 {
+                for n in mesh.find_nodes(|p| [p.get_x(), p.get_y(), p.get_z()][d] < 1e-12) {
+                    problem.fix(n, d, 0.0).unwrap();
+                }
+            }
+            for facet in mesh.find_boundary_facets(|p| p.get_x() > lx - 1e-12) {
+                problem.add_pressure(&facet, -stress).unwrap();
+            }
+            let solution = problem.solve().unwrap();
+            let (e, nu) = (material.young, material.poisson);
+            for (p, u) in mesh.nodes.iter().zip(solution.displacements.iter()) {
+                let (x, y, z) = p.get_coords();
+                assert!((u[0] - stress * x / e).abs() < 1e-9, "{}", t.as_str());
+                assert!((u[1] + nu * stress * y / e).abs() < 1e-9);
+                assert!((u[2] + nu * stress * z / e).abs() < 1e-9);
+            }
+            assert!(max_error(&solution.stresses, 0, stress) < 1e-8);
+            for c in 1..6 {
+                assert!(max_error(&solution.stresses, c, 0.0) < 1e-8);
+            }
+            assert!(
+                solution
+                    .von_mises()
+                    .iter()
+                    .all(|v| (v - stress).abs() < 1e-8)
+            );
+            let reaction: f64 = solution.reactions.iter().map(|r| r[0]).sum();
+            assert!((reaction + stress * ly * lz).abs() < 1e-8);
+        }
+    }
+
+    #[test]
+    fn test_cantilever() {
+        // Tip loaded plane stress cantilever against Timoshenko beam theory.
+        let (l, h, t, load) = (10.0, 1.0, 0.1, 0.01);
+        let material = steel();
+        let mesh = Mesh::rectangle(l, h, 20, 2, ElementType::Quad8).unwrap();
+        let mut problem =
+            LinearStatic::new(&mesh, Analysis::PlaneStress { thickness: t }, material).unwrap();
+        problem
+            .fix_nodes(&mesh.find_nodes(|p| p.get_x() < 1e-12))
+            .unwrap();
+        let tip = mesh.find_nodes(|p| p.get_x() > l - 1e-12);
+        for &n in tip.iter() {
+            problem.add_force(n, 1, -load / tip.len() as f64).unwrap();
+        }
+        let solution = problem.solve().unwrap();
+        let inertia = t * h * h * h / 12.0;
+        let shear = material.young / (2.0 * (1.0 + material.poisson));
+        let expected =
+            load * l.powi(3) / (3.0 * material.young * inertia) + 1.2 * load * l / (shear * h * t);
+        let deflection = -tip
+            .iter()
+            .map(|&n| solution.displacements[n][1])
+            .sum::<f64>()
+            / tip.len() as f64;
+        assert!(
+            (deflection / expected - 1.0).abs() < 0.02,
+            "{} {}",
+            deflection,
+            expected
+        );
+
+        // Bending stress at midspan, compressive on the bottom fibre.
+        let middle = mesh.find_nodes(|p| (p.get_x() - l / 2.0).abs() < 1e-9 && p.get_y() < 1e-9)[0];
+        let moment = load * l / 2.0;
+        let expected = -moment * (h / 2.0) / inertia;
+        let stress = solution.stresses[middle][0];
+        assert!(
+            (stress / expected - 1.0).abs() < 0.02,
+            "{} {}",
+            stress,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_thick_cylinder() {
+        // Lame solution for an internally pressurised cylinder in plane
+        // strain.
+        let (a, b, p) = (1.0, 2.0, 1.0);
+        let material = steel();
+        let (e, nu) = (material.young, material.poisson);
+        let mut mesh = Mesh::rectangle(b - a, 0.25, 16, 1, ElementType::Quad8).unwrap();
+        for q in mesh.nodes.iter_mut() {
+            q.set_x(q.get_x() + a);
+        }
+        let mut problem = LinearStatic::new(&mesh, Analysis::Axisymmetric, material).unwrap();
+        for n in 0..mesh.num_nodes() {
+            problem.fix(n, 1, 0.0).unwrap();
+        }
+        let inner = mesh.find_boundary_facets(|q| (q.get_x() - a).abs() < 1e-12);
+        assert_eq!(inner.len(), 1);
+        problem.add_pressure(&inner[0], p).unwrap();
+        let solution = problem.solve().unwrap();
+
+        let c = p * a * a / (b * b - a * a);
+        for (q, u) in mesh.nodes.iter().zip(solution.displacements.iter()) {
+            let r = q.get_x();
+            let exact = (1.0 + nu) / e * c * ((1.0 - 2.0 * nu) * r + b * b / r);
+            assert!((u[0] / exact - 1.0).abs() < 1e-4, "{} {}", u[0], exact);
+        }
+        for n in mesh.find_nodes(|q| (q.get_x() - 1.5).abs() < 1e-9) {
+            let s = solution.stresses[n];
+            let hoop = c * (1.0 + b * b / 2.25);
+            let radial = c * (1.0 - b * b / 2.25);
+            assert!((s[2] / hoop - 1.0).abs() < 1e-2);
+            assert!((s[0] / radial - 1.0).abs() < 1e-2);
+        }
+        // Axial reactions on the two ends cancel.
+        let axial: f64 = solution.reactions.iter().map(|r| r[1]).sum();
+        assert!(axial.abs() < 1e-8);
+    }
+
+    #[test]
+    fn test_body_force() {
+        let material = steel();
+        let weight = 0.5;
+        let mesh = Mesh::block(1.0, 2.0, 3.0, 2, 2, 3, ElementType::Tet10).unwrap();
+        let mut problem = LinearStatic::new(&mesh, Analysis::Solid, material).unwrap();
+        problem
+            .fix_nodes(&mesh.find_nodes(|p| p.get_z() < 1e-12))
+            .unwrap();
+        problem.set_body_force([0.0, 0.0, -weight]);
+        let load: f64 = problem.load_vector().unwrap().iter().sum();
+        assert!((load + weight * 6.0).abs() < 1e-12);
+        let solution = problem.solve().unwrap();
+        let reaction: f64 = solution.reactions.iter().map(|r| r[2]).sum();
+        assert!((reaction - weight * 6.0).abs() < 1e-8);
+
+        // Self weight of a ring per full revolution.
+        let (a, b, h) = (1.0, 3.0, 2.0);
+        let mut mesh = Mesh::rectangle(b - a, h, 4, 2, ElementType::Tri6).unwrap();
+        for q in mesh.nodes.iter_mut() {
+            q.set_x(q.get_x() + a);
+        }
+        let mut problem = LinearStatic::new(&mesh, Analysis::Axisymmetric, material).unwrap();
+        problem.set_body_force([0.0, -weight, 0.0]);
+        for n in mesh.find_nodes(|p| p.get_y() < 1e-12) {
+            problem.fix(n, 1, 0.0).unwrap();
+        }
+        let solution = problem.solve().unwrap();
+        let reaction: f64 = solution.reactions.iter().map(|r| r[1]).sum();
+        let expected = weight * std::f64::consts::PI * (b * b - a * a) * h;
+        assert!((reaction / expected - 1.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_mass() {
+        let density = 7.85;
+        for element_type in [ElementType::Quad4, ElementType::Quad8, ElementType::Tri6] {
+            let mesh = Mesh::rectangle(2.0, 1.0, 4, 2, element_type).unwrap();
+            for analysis in [
+                Analysis::PlaneStress { thickness: 0.1 },
+                Analysis::Axisymmetric,
+            ] {
+                let mut problem = LinearStatic::new(&mesh, analysis, steel()).unwrap();
+                let elements: Vec<usize> = (0..mesh.num_elements()).collect();
+                assert!(problem.set_density(&elements, -1.0).is_err());
+                assert!(problem.modes(1, false).is_err());
+                problem.set_density(&elements, density).unwrap();
+                let expected = match analysis {
+                    // Ring of radii 0 and 2.
+                    Analysis::Axisymmetric => density * std::f64::consts::PI * 4.0,
+                    _ => density * 0.2,
+                };
+                for lumped in [false, true] {
+                    let m = problem.mass(lumped).unwrap();
+                    assert!(m.is_symmetric(1e-14));
+                    // Rigid translation along x and y.
+                    for d in 0..2 {
+                        let u: Vec<f64> = (0..m.nrows)
+                            .map(|i| if i % 2 == d { 1.0 } else { 0.0 })
+                            .collect();
+                        let total: f64 = m
+                            .multiply(&u)
+                            .iter()
+                            .zip(u.iter())
+                            .map(|(a, b)| a * b)
+                            .sum();
+                        assert!((total / expected - 1.0).abs() < 1e-12);
+                    }
+                    if lumped {
+                        assert!(m.nnz() == m.nrows && m.diagonal().iter().all(|&d| d > 0.0));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_modes() {
+        // Bending modes of a slender plane stress cantilever against Euler
+        // beam theory.
+        let (l, h, t, density) = (10.0, 0.5, 0.1, 7.85);
+        let material = steel();
+        let mesh = Mesh::rectangle(l, h, 40, 2, ElementType::Quad8).unwrap();
+        let mut problem =
+            LinearStatic::new(&mesh, Analysis::PlaneStress { thickness: t }, material).unwrap();
+        problem
+            .fix_nodes(&mesh.find_nodes(|p| p.get_x() < 1e-12))
+            .unwrap();
+        let elements: Vec<usize> = (0..mesh.num_elements()).collect();
+        problem.set_density(&elements, density).unwrap();
+        let beam = (material.young * t * h.powi(3) / 12.0 / (density * t * h * l.powi(4))).sqrt();
+        let roots: [f64; 3] = [1.875104, 4.694091, 7.854757];
+        let consistent = problem.modes(3, false).unwrap();
+        let lumped = problem.modes(3, true).unwrap();
+        let m = problem.mass(false).unwrap();
+        for (i, root) in roots.iter().enumerate() {
+            let expected = root * root * beam;
+            let omega = consistent.angular_frequencies[i];
+            // Shear deformation and rotary inertia lower the higher modes.
+            let slenderness = (root / l).powi(2) * h * h / 12.0;
+            let correction = 0.5 * slenderness * (1.0 + 2.0 * (1.0 + material.poisson) * 1.2);
+            let expected = expected * (1.0 - correction);
+            assert!((omega / expected - 1.0).abs() < 0.005, "{omega} {expected}");
+            let lumped = lumped.angular_frequencies[i];
+            assert!(
+                (lumped / expected - 1.0).abs() < 0.005,
+                "{lumped} {expected}"
+            );
+            assert!(lumped < omega);
+            assert!(
+                (consistent.frequencies[i] - omega / (2.0 * std::f64::consts::PI)).abs() < 1e-12
+            );
+            // Mass normalized shapes.
+            let u: Vec<f64> = consistent.mode_shapes[i]
+                .iter()
+                .flat_map(|u| [u[0], u[1]])
+                .collect();
+            let mu: f64 = m
+                .multiply(&u)
+                .iter()
+                .zip(u.iter())
+                .map(|(a, b)| a * b)
+                .sum();
+            assert!((mu - 1.0).abs() < 1e-9);
+        }
+
+        // An unsupported block has six rigid body modes.
+        let mesh = Mesh::block(1.0, 1.0, 1.0, 2, 2, 2, ElementType::Hex8).unwrap();
+        let mut problem = LinearStatic::new(&mesh, Analysis::Solid, material).unwrap();
+        problem
+            .set_density(&[0, 1, 2, 3, 4, 5, 6, 7], density)
+            .unwrap();
+        let solution = problem.modes(7, true).unwrap();
+        let elastic = solution.angular_frequencies[6];
+        assert!(elastic > 0.1 * (material.young / density).sqrt());
+        assert!(
+            solution.angular_frequencies[..6]
+                .iter()
+                .all(|&w| w < 1e-4 * elastic)
+        );
+    }
+
+    #[test]
+    fn test_buckling() {
+        // Slender plane stress column clamped at the base and compressed at
+        // the top, against the Euler load of a cantilever.
+        let (l, h, t) = (10.0, 0.5, 0.1);
+        let material = steel();
+        let mut mesh = Mesh::rectangle(h, l, 2, 40, ElementType::Quad8).unwrap();
+        mesh.nodes
+            .iter_mut()
+            .for_each(|p| p.set_x(p.get_x() - h / 2.0));
+        let mut problem =
+            LinearStatic::new(&mesh, Analysis::PlaneStress { thickness: t }, material).unwrap();
+        problem
+            .fix_nodes(&mesh.find_nodes(|p| p.get_y() < 1e-12))
+            .unwrap();
+        let pressure = 1e-3;
+        for facet in mesh.find_boundary_facets(|p| p.get_y() > l - 1e-12) {
+            problem.add_pressure(&facet, pressure).unwrap();
+        }
+        let solution = problem.buckling(2).unwrap();
+        let euler =
+            std::f64::consts::PI.powi(2) * material.young * t * h.powi(3) / 12.0 / (4.0 * l * l);
+        // Engesser's reduction for shear deformation.
+        let shear = 5.0 / 6.0 * material.young / (2.0 * (1.0 + material.poisson)) * h * t;
+        for (factor, n) in solution.load_factors.iter().zip([1.0, 9.0]) {
+            let load = n * euler / (1.0 + n * euler / shear);
+            let expected = load / (pressure * h * t);
+            assert!(
+                (factor / expected - 1.0).abs() < 0.005,
+                "{factor} {expected}"
+            );
+        }
+        // Sideways first mode, largest at the top.
+        let shape = &solution.mode_shapes[0];
+        let top = mesh.find_nodes(|p| p.get_y() > l - 1e-12)[0];
+        assert!((shape[top][0].abs() - 1.0).abs() < 1e-3);
+
+        // Tension does not buckle.
+        for facet in mesh.find_boundary_facets(|p| p.get_y() > l - 1e-12) {
+            problem.add_pressure(&facet, -2.0 * pressure).unwrap();
+        }
+        assert!(problem.buckling(1).is_err());
+    }
+}
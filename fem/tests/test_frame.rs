@@ -0,0 +1,435 @@
+use fem::{DistributedLoad, Frame, IsotropicElastic, Member, MemberKind, Section};
+use geom::{CoordinateSystem3d, Point3d};
+
+fn steel() -> IsotropicElastic {
+    IsotropicElastic::new(200.0, 0.3).unwrap()
+}
+
+fn shear_modulus() -> f64 {
+    200.0 / 2.6
+}
+
+// Straight member from the origin along x split into n members.
+fn straight(length: f64, n: usize, kind: MemberKind, section: Section, planar: bool) -> Frame {
+    let nodes = (0..=n)
+        .map(|i| Point3d::from_coords(length * i as f64 / n as f64, 0.0, 0.0))
+        .collect();
+    let mut frame = if planar {
+        Frame::planar(nodes)
+    } else {
+        Frame::new(nodes)
+    };
+    for i in 0..n {
+        frame
+            .add_member(Member::new([i, i + 1], kind, section, steel()))
+            .unwrap();
+    }
+    frame
+}
+
+fn close(a: f64, b: f64, tolerance: f64) -> bool {
+    (a - b).abs() <= tolerance * b.abs().max(1e-30)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sections() {
+        let r = Section::rectangle(0.1, 0.2).unwrap();
+        assert!(close(r.iy, 0.1 * 0.008 / 12.0, 1e-12));
+        assert!(close(r.iz, 0.2 * 0.001 / 12.0, 1e-12));
+        // Square torsion constant 0.1406 a^4.
+        let s = Section::rectangle(1.0, 1.0).unwrap();
+        assert!((s.torsion - 0.1406).abs() < 1e-3);
+        let c = Section::circle(1.0).unwrap();
+        assert!(close(c.torsion, std::f64::consts::PI / 2.0, 1e-12));
+        let t = Section::tube(1.0, 0.1).unwrap();
+        assert!(t.area < c.area && t.iy < c.iy);
+        let i = Section::i_section(0.3, 0.15, 0.007, 0.0107).unwrap();
+        assert!(i.iy > 5.0 * i.iz);
+        assert!(Section::i_section(0.3, 0.15, 0.007, 0.2).is_err());
+        assert!(Section::new(1.0, 0.0, 1.0, 1.0, 1.0, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_cantilever() {
+        let (l, p) = (2.0, 3.0);
+        let section = Section::rectangle(0.1, 0.2).unwrap();
+        let e = steel().young;
+        for kind in [MemberKind::EulerBernoulli, MemberKind::Timoshenko] {
+            let mut frame = straight(l, 4, kind, section, false);
+            frame.clamp(0).unwrap();
+            frame.add_load(4, 2, -p).unwrap();
+            frame.add_load(4, 3, p).unwrap();
+            let solution = frame.solve().unwrap();
+            let mut deflection = p * l.powi(3) / (3.0 * e * section.iy);
+            if kind == MemberKind::Timoshenko {
+                deflection += p * l / (shear_modulus() * section.shear_area_z);
+            }
+            let tip = solution.displacements[4];
+            assert!(
+                close(tip[2], -deflection, 1e-10),
+                "{} {}",
+                tip[2],
+                deflection
+            );
+            assert!(close(tip[4], p * l * l / (2.0 * e * section.iy), 1e-10));
+            assert!(close(
+                tip[3],
+                p * l / (shear_modulus() * section.torsion),
+                1e-10
+            ));
+            let root = solution.reactions[0];
+            assert!(close(root[2], p, 1e-10));
+            assert!(close(root[3], -p, 1e-10));
+            assert!(close(root[4], -p * l, 1e-10));
+
+            // Hogging moment with tension on top is positive.
+            let diagram = frame.internal_forces(&solution, 0, 3).unwrap();
+            assert_eq!(diagram[2].position, 0.5);
+            assert!(close(diagram[0].forces[4], p * l, 1e-10));
+            assert!(close(diagram[2].forces[4], p * (l - 0.5), 1e-10));
+            assert!(close(diagram[1].forces[2], -p, 1e-10));
+            assert!(close(diagram[1].forces[3], p, 1e-10));
+            let last = frame.internal_forces(&solution, 3, 2).unwrap();
+            assert!(last[1].forces[4].abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn test_distributed_loads() {
+        // Simply supported planar beam under uniform load.
+        let (l, q) = (4.0, 2.0);
+        let section = Section::rectangle(0.1, 0.3).unwrap();
+        let e = steel().young;
+        let mut frame = straight(l, 2, MemberKind::EulerBernoulli, section, true);
+        frame.fix(0, 0, 0.0).unwrap();
+        frame.fix(0, 1, 0.0).unwrap();
+        frame.fix(2, 1, 0.0).unwrap();
+        for m in 0..2 {
+            frame
+                .add_distributed_load(m, DistributedLoad::uniform([0.0, -q, 0.0], false))
+                .unwrap();
+        }
+        let solution = frame.solve().unwrap();
+        let middle = 5.0 * q * l.powi(4) / (384.0 * e * section.iz);
+        assert!(close(solution.displacements[1][1], -middle, 1e-10));
+        assert!(close(
+            solution.displacements[0][5],
+            -q * l.powi(3) / (24.0 * e * section.iz),
+            1e-10
+        ));
+        assert!(close(solution.reactions[0][1], q * l / 2.0, 1e-10));
+        let diagram = frame.internal_forces(&solution, 0, 5).unwrap();
+        // Sagging moment qx(L - x)/2 and shear.
+        for station in diagram.iter() {
+            let x = station.position;
+            assert!((station.forces[5] - q * x * (l - x) / 2.0).abs() < 1e-10);
+            assert!((station.forces[1] + q * (l / 2.0 - x)).abs() < 1e-10);
+        }
+
+        // Cantilever under a load rising linearly to the tip, in local axes.
+        let mut frame = straight(l, 1, MemberKind::Timoshenko, section, false);
+        frame.clamp(0).unwrap();
+        frame
+            .add_distributed_load(
+                0,
+                DistributedLoad {
+                    start: [0.0; 3],
+                    end: [0.0, 0.0, -q],
+                    local: true,
+                },
+            )
+            .unwrap();
+        let solution = frame.solve().unwrap();
+        assert!(close(solution.reactions[0][2], q * l / 2.0, 1e-10));
+        assert!(close(solution.reactions[0][4], -q * l * l / 3.0, 1e-10));
+        // Tip deflection 11 q L^4 / (120 E I) plus the shear part q L^2 / (3 G A).
+        let tip = 11.0 * q * l.powi(4) / (120.0 * e * section.iy)
+            + q * l * l / (3.0 * shear_modulus() * section.shear_area_z);
+        assert!(close(solution.displacements[1][2], -tip, 1e-10));
+        let diagram = frame.internal_forces(&solution, 0, 3).unwrap();
+        assert!(close(diagram[0].forces[4], q * l * l / 3.0, 1e-10));
+        // Hogging moment q/L ((L^3 - x^3)/3 - x (L^2 - x^2)/2).
+        for station in diagram.iter() {
+            let x = station.position;
+            let moment = q / l * ((l.powi(3) - x.powi(3)) / 3.0 - x * (l * l - x * x) / 2.0);
+            assert!((station.forces[4] - moment).abs() < 1e-10);
+        }
+        assert!(diagram[2].forces[4].abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_releases() {
+        // Two cantilevers joined by a hinge share the load equally.
+        let (a, p) = (1.5, 4.0);
+        let section = Section::circle(0.05).unwrap();
+        let e = steel().young;
+        let mut frame = straight(2.0 * a, 2, MemberKind::EulerBernoulli, section, true);
+        frame.members[0].release_moments(1);
+        frame.clamp(0).unwrap();
+        frame.clamp(2).unwrap();
+        frame.add_load(1, 1, -p).unwrap();
+        let solution = frame.solve().unwrap();
+        let deflection = p / 2.0 * a.powi(3) / (3.0 * e * section.iz);
+        assert!(close(solution.displacements[1][1], -deflection, 1e-10));
+        assert!(solution.end_forces[0][1][5].abs() < 1e-10);
+        let diagram = frame.internal_forces(&solution, 0, 2).unwrap();
+        assert!(diagram[1].forces[5].abs() < 1e-10);
+        assert!(close(diagram[0].forces[5], -p / 2.0 * a, 1e-10));
+
+        // Hinges at both ends of every member leave a mechanism.
+        let mut frame = straight(2.0, 2, MemberKind::EulerBernoulli, section, true);
+        for m in 0..2 {
+            frame.members[m].release_moments(0);
+            frame.members[m].release_moments(1);
+        }
+        frame.pin(0).unwrap();
+        frame.pin(2).unwrap();
+        frame.add_load(1, 1, -1.0).unwrap();
+        assert!(frame.solve().is_err());
+
+        // Releasing the torsion at both ends is unstable on its own.
+        let mut frame = straight(2.0, 1, MemberKind::EulerBernoulli, section, false);
+        frame.members[0].releases[0][3] = true;
+        frame.members[0].releases[1][3] = true;
+        frame.clamp(0).unwrap();
+        assert_eq!(
+            frame.solve().err(),
+            Some("Member releases make the member unstable")
+        );
+    }
+
+    #[test]
+    fn test_truss() {
+        // Symmetric two bar truss in space: rotations and the out of plane
+        // motion of the apex carry no stiffness and are held automatically.
+        let p = 10.0;
+        let nodes = vec![
+            Point3d::from_coords(-1.0, 0.0, 0.0),
+            Point3d::from_coords(1.0, 0.0, 0.0),
+            Point3d::from_coords(0.0, 1.0, 0.0),
+        ];
+        let mut frame = Frame::new(nodes);
+        let section = Section::circle(0.02).unwrap();
+        for nodes in [[0, 2], [1, 2]] {
+            frame
+                .add_member(Member::new(nodes, MemberKind::Truss, section, steel()))
+                .unwrap();
+        }
+        frame.pin(0).unwrap();
+        frame.pin(1).unwrap();
+        frame.add_load(2, 1, -p).unwrap();
+        let solution = frame.solve().unwrap();
+        let force = -p / 2f64.sqrt();
+        for m in 0..2 {
+            for station in frame.internal_forces(&solution, m, 3).unwrap() {
+                assert!(close(station.forces[0], force, 1e-10));
+                assert!(station.forces[5].abs() < 1e-10);
+            }
+        }
+        // Shortening N L / (E A) of each bar maps to a drop of sqrt(2) times it.
+        let shortening = force * 2f64.sqrt() / (steel().young * section.area);
+        assert!(close(
+            solution.displacements[2][1],
+            shortening * 2f64.sqrt(),
+            1e-10
+        ));
+        assert!(close(solution.reactions[0][1], p / 2.0, 1e-10));
+
+        // Self weight of a truss bar is carried by its end nodes.
+        frame
+            .add_distributed_load(0, DistributedLoad::uniform([0.0, -1.0, 0.0], false))
+            .unwrap();
+        let solution = frame.solve().unwrap();
+        let total: f64 = solution.reactions.iter().map(|r| r[1]).sum();
+        assert!(close(total, p + 2f64.sqrt(), 1e-10));
+    }
+
+    #[test]
+    fn test_orientation() {
+        let (b, h, l, p) = (0.1, 0.3, 2.0, 1.0);
+        let section = Section::rectangle(b, h).unwrap();
+        let mut frame = straight(l, 1, MemberKind::EulerBernoulli, section, false);
+        frame.clamp(0).unwrap();
+        frame.add_load(1, 2, -p).unwrap();
+        let cs = frame.local_frame(0).unwrap();
+        assert_eq!(cs.vxdir.get_coords(), (0.0, 1.0, 0.0));
+        assert_eq!(cs.vydir.get_coords(), (0.0, 0.0, 1.0));
+        let strong = frame.solve().unwrap().displacements[1][2];
+
+        // Local y turned onto global z: bending about the weak axis.
+        frame.members[0].orientation =
+            Some(CoordinateSystem3d::from_location_direction_xdirection(
+                (0.0, 0.0, 0.0),
+                (1.0, 0.0, 0.0),
+                (0.0, 0.0, 1.0),
+            ));
+        let cs = frame.local_frame(0).unwrap();
+        let (x, y, z) = cs.vydir.get_coords();
+        assert!(x.abs() < 1e-15 && (y + 1.0).abs() < 1e-15 && z.abs() < 1e-15);
+        let weak = frame.solve().unwrap().displacements[1][2];
+        assert!(close(weak / strong, (h / b).powi(2), 1e-10));
+
+        // Vertical members default to local y along global y.
+        let mut frame = Frame::new(vec![Point3d::new(), Point3d::from_coords(0.0, 0.0, 3.0)]);
+        frame
+            .add_member(Member::new(
+                [0, 1],
+                MemberKind::EulerBernoulli,
+                section,
+                steel(),
+            ))
+            .unwrap();
+        assert_eq!(
+            frame.local_frame(0).unwrap().vxdir.get_coords(),
+            (0.0, 1.0, 0.0)
+        );
+        let mut member = Member::new([0, 1], MemberKind::EulerBernoulli, section, steel());
+        member.orientation = Some(CoordinateSystem3d::from_location_direction_xdirection(
+            (0.0, 0.0, 0.0),
+            (1.0, 0.0, 0.0),
+            (0.0, 0.0, 1.0),
+        ));
+        assert!(frame.add_member(member).is_err());
+        assert_eq!(frame.members.len(), 1);
+        let degenerate = Member::new([0, 0], MemberKind::Truss, section, steel());
+        assert_eq!(
+            frame.add_member(degenerate).err(),
+            Some("Member has zero length")
+        );
+    }
+
+    #[test]
+    fn test_modes() {
+        // Circular cantilever: equal bending frequencies in both planes.
+        let (l, radius, density) = (4.0, 0.05, 7.85);
+        let section = Section::circle(radius).unwrap();
+        let mut frame = straight(l, 16, MemberKind::EulerBernoulli, section, false);
+        frame.clamp(0).unwrap();
+        assert!(frame.modes(1, false).is_err());
+        frame.members.iter_mut().for_each(|m| m.density = density);
+        let beam = (steel().young * section.iy / (density * section.area * l.powi(4))).sqrt();
+        let roots: [f64; 2] = [1.875104, 4.694091];
+        let consistent = frame.modes(4, false).unwrap();
+        let lumped = frame.modes(4, true).unwrap();
+        for (i, root) in roots.iter().enumerate() {
+            let expected = root * root * beam;
+            for mode in [2 * i, 2 * i + 1] {
+                assert!(close(consistent.angular_frequencies[mode], expected, 1e-4));
+                assert!(close(lumped.angular_frequencies[mode], expected, 0.01));
+            }
+        }
+        // The two first modes bend in the two planes.
+        let tip = &consistent.mode_shapes[0][16];
+        let other = &consistent.mode_shapes[1][16];
+        assert!(
+            (tip[1] * other[1] + tip[2] * other[2]).abs() < 1e-8 * (tip[1].hypot(tip[2])).powi(2)
+        );
+        let m = frame.mass(false).unwrap();
+        let u: Vec<f64> = consistent.mode_shapes[0]
+            .iter()
+            .flatten()
+            .copied()
+            .collect();
+        let mu: f64 = m
+            .multiply(&u)
+            .iter()
+            .zip(u.iter())
+            .map(|(a, b)| a * b)
+            .sum();
+        assert!(close(mu, 1.0, 1e-9));
+
+        // Simply supported planar beam: (n pi / L)^2 sqrt(EI / rho A).
+        let section = Section::rectangle(0.1, 0.2).unwrap();
+        let mut frame = straight(l, 12, MemberKind::EulerBernoulli, section, true);
+        frame.members.iter_mut().for_each(|m| m.density = density);
+        frame.pin(0).unwrap();
+        frame.fix(12, 1, 0.0).unwrap();
+        let beam = (steel().young * section.iz / (density * section.area)).sqrt();
+        let solution = frame.modes(3, false).unwrap();
+        for (n, omega) in solution.angular_frequencies.iter().enumerate() {
+            let expected = (((n + 1) as f64) * std::f64::consts::PI / l).powi(2) * beam;
+            assert!(close(*omega, expected, 1e-3));
+        }
+    }
+
+    #[test]
+    fn test_mass() {
+        // Rigid translations carry the total mass, also through hinges.
+        let section = Section::rectangle(0.1, 0.2).unwrap();
+        let nodes = vec![
+            Point3d::from_coords(0.0, 0.0, 0.0),
+            Point3d::from_coords(0.0, 3.0, 0.0),
+            Point3d::from_coords(4.0, 3.0, 0.0),
+            Point3d::from_coords(4.0, 0.0, 1.0),
+        ];
+        let mut frame = Frame::new(nodes);
+        for (i, kind) in [
+            MemberKind::EulerBernoulli,
+            MemberKind::Timoshenko,
+            MemberKind::Truss,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mut member = Member::new([i, i + 1], kind, section, steel());
+            member.density = 2.0;
+            if i == 1 {
+                member.release_moments(0);
+            }
+            frame.add_member(member).unwrap();
+        }
+        let total: f64 = (0..3).map(|m| 2.0 * section.area * frame.length(m)).sum();
+        for lumped in [false, true] {
+            let m = frame.mass(lumped).unwrap();
+            assert!(m.is_symmetric(1e-12));
+            for d in 0..3 {
+                let u: Vec<f64> = (0..m.nrows)
+                    .map(|i| if i % 6 == d { 1.0 } else { 0.0 })
+                    .collect();
+                let mass: f64 = m
+                    .multiply(&u)
+                    .iter()
+                    .zip(u.iter())
+                    .map(|(a, b)| a * b)
+                    .sum();
+                assert!(close(mass, total, 1e-12));
+            }
+        }
+    }
+
+    #[test]
+    fn test_buckling() {
+        let (l, p) = (5.0, 1.0);
+        let section = Section::rectangle(0.1, 0.2).unwrap();
+        let ei = steel().young * section.iz;
+        let euler = std::f64::consts::PI.powi(2) * ei / (l * l);
+
+        // Pinned column, planar, compressed along its axis.
+        let mut frame = straight(l, 8, MemberKind::EulerBernoulli, section, true);
+        frame.pin(0).unwrap();
+        frame.fix(8, 1, 0.0).unwrap();
+        frame.add_load(8, 0, -p).unwrap();
+        let solution = frame.buckling(2).unwrap();
+        assert!(close(solution.load_factors[0] * p, euler, 1e-4));
+        assert!(close(solution.load_factors[1] * p, 4.0 * euler, 1e-3));
+        let shape = &solution.mode_shapes[0];
+        assert!(close(shape[4][1].abs(), 1.0, 1e-12));
+
+        // Cantilever column, a quarter of the pinned load.
+        let mut frame = straight(l, 8, MemberKind::EulerBernoulli, section, true);
+        frame.clamp(0).unwrap();
+        frame.add_load(8, 0, -p).unwrap();
+        let solution = frame.buckling(1).unwrap();
+        assert!(close(solution.load_factors[0] * p, euler / 4.0, 1e-4));
+
+        // Tension only.
+        let mut frame = straight(l, 4, MemberKind::EulerBernoulli, section, true);
+        frame.clamp(0).unwrap();
+        frame.add_load(4, 0, p).unwrap();
+        assert!(frame.buckling(1).is_err());
+    }
+}
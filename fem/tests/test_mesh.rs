@@ -0,0 +1,250 @@
+use fem::{Element, ElementType, Mesh};
+use geom::{Point2d, Point3d, Tetrahedralization, Triangulation2d};
+
+fn sub(a: &Point3d, b: &Point3d) -> [f64; 3] {
+    [
+        a.get_x() - b.get_x(),
+        a.get_y() - b.get_y(),
+        a.get_z() - b.get_z(),
+    ]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn centroid(points: &[Point3d]) -> Point3d {
+    let n = points.len() as f64;
+    let s = points.iter().fold([0.0; 3], |s, p| {
+        [s[0] + p.get_x(), s[1] + p.get_y(), s[2] + p.get_z()]
+    });
+    Point3d::from_coords(s[0] / n, s[1] / n, s[2] / n)
+}
+
+fn tet_volume(mesh: &Mesh, e: usize) -> f64 {
+    let p = mesh.element_points(e);
+    dot(
+        cross(sub(&p[1], &p[0]), sub(&p[2], &p[0])),
+        sub(&p[3], &p[0]),
+    ) / 6.0
+}
+
+// Every boundary face of a solid mesh faces away from its element.
+fn check_outward(mesh: &Mesh) {
+    for facet in mesh.boundary_facets() {
+        let p: Vec<Point3d> = facet.nodes.iter().map(|&n| mesh.nodes[n]).collect();
+        let corners = if p.len() == 3 || p.len() == 6 { 3 } else { 4 };
+        let normal = cross(sub(&p[1], &p[0]), sub(&p[corners - 1], &p[0]));
+        let inside = centroid(&mesh.element_points(facet.element));
+        assert!(dot(normal, sub(&centroid(&p[..corners]), &inside)) > 0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topology_tables() {
+        let all = [
+            ElementType::Line2,
+            ElementType::Line3,
+            ElementType::Tri3,
+            ElementType::Tri6,
+            ElementType::Quad4,
+            ElementType::Quad8,
+            ElementType::Quad9,
+            ElementType::Tet4,
+            ElementType::Tet10,
+            ElementType::Hex8,
+            ElementType::Hex20,
+            ElementType::Hex27,
+            ElementType::Wedge6,
+            ElementType::Pyramid5,
+        ];
+        for t in all {
+            // Every node appears in some edge or face, and indices are in range.
+            let mut seen = vec![false; t.num_nodes()];
+            for entity in t.edges().iter().chain(t.faces().iter()) {
+                for &i in entity.iter() {
+                    seen[i] = true;
+                }
+            }
+            if t != ElementType::Hex27 {
+                assert!(seen.iter().all(|&s| s), "{}", t.as_str());
+            }
+            for (f, facet) in t.facets().iter().enumerate() {
+                if let Some(ft) = t.facet_type(f) {
+                    assert_eq!(ft.num_nodes(), facet.len());
+                    assert_eq!(ft.dimension() + 1, t.dimension());
+                }
+            }
+        }
+        assert_eq!(ElementType::Hex27.num_corners(), 8);
+        assert!(ElementType::Quad8.is_quadratic());
+        assert!(!ElementType::Wedge6.is_quadratic());
+        assert_eq!(ElementType::Wedge6.facet_type(0), Some(ElementType::Tri3));
+        assert_eq!(ElementType::Wedge6.facet_type(2), Some(ElementType::Quad4));
+        assert_eq!(ElementType::Pyramid5.facets().len(), 5);
+        assert_eq!(ElementType::Line3.facet_type(0), None);
+    }
+
+    #[test]
+    fn test_block() {
+        let mesh = Mesh::block(2.0, 2.0, 2.0, 2, 2, 2, ElementType::Hex8).unwrap();
+        assert_eq!(mesh.num_nodes(), 27);
+        assert_eq!(mesh.num_elements(), 8);
+        assert_eq!(mesh.boundary_facets().len(), 24);
+        assert_eq!(mesh.faces().len(), 36);
+        assert_eq!(mesh.edges().len(), 54);
+        check_outward(&mesh);
+        let neighbors = mesh.element_neighbors();
+        assert!(neighbors.iter().all(|n| n.len() == 3));
+        let node_neighbors = mesh.node_neighbors();
+        assert_eq!(node_neighbors[13].len(), 26);
+        assert_eq!(node_neighbors[0].len(), 7);
+        assert_eq!(mesh.node_elements()[13].len(), 8);
+
+        let hex20 = Mesh::block(2.0, 2.0, 2.0, 2, 2, 2, ElementType::Hex20).unwrap();
+        assert_eq!(hex20.num_nodes(), 27 + 54);
+        check_outward(&hex20);
+        let hex27 = Mesh::block(2.0, 2.0, 2.0, 2, 2, 2, ElementType::Hex27).unwrap();
+        assert_eq!(hex27.num_nodes(), 125);
+        hex27.validate().unwrap();
+        // Quadratic nodes land on the half grid, at their natural positions.
+        for e in 0..hex27.num_elements() {
+            let p = hex27.element_points(e);
+            let c = centroid(&p[..8]);
+            assert!(p[26].is_equal(&c, 1e-12));
+            assert!(p[20].get_x() == p[0].get_x() && p[21].get_x() == p[1].get_x());
+            assert!(p[22].get_y() == p[0].get_y() && p[23].get_y() == p[3].get_y());
+            assert!(p[24].get_z() == p[0].get_z() && p[25].get_z() == p[4].get_z());
+            for q in p.iter() {
+                let (x, y, z) = q.get_coords();
+                assert!([x, y, z].iter().all(|v| (2.0 * v).fract() == 0.0));
+            }
+        }
+
+        let tets = Mesh::block(1.0, 2.0, 3.0, 2, 2, 2, ElementType::Tet4).unwrap();
+        assert_eq!(tets.num_elements(), 48);
+        let volume: f64 = (0..tets.num_elements()).map(|e| tet_volume(&tets, e)).sum();
+        assert!((volume - 6.0).abs() < 1e-12);
+        assert!((0..tets.num_elements()).all(|e| tet_volume(&tets, e) > 0.0));
+        // Conforming: the boundary is two triangles per boundary square.
+        assert_eq!(tets.boundary_facets().len(), 48);
+        check_outward(&tets);
+        let tet10 = Mesh::block(1.0, 2.0, 3.0, 2, 2, 2, ElementType::Tet10).unwrap();
+        assert_eq!(tet10.num_nodes(), 27 + tets.edges().len());
+        check_outward(&tet10);
+
+        let wedges = Mesh::block(1.0, 1.0, 1.0, 1, 1, 2, ElementType::Wedge6).unwrap();
+        assert_eq!(wedges.num_elements(), 4);
+        assert_eq!(wedges.boundary_facets().len(), 4 + 8);
+        check_outward(&wedges);
+        assert!(Mesh::block(1.0, 1.0, 1.0, 1, 1, 1, ElementType::Pyramid5).is_err());
+        assert!(Mesh::block(1.0, 1.0, 1.0, 0, 1, 1, ElementType::Hex8).is_err());
+    }
+
+    #[test]
+    fn test_rectangle_and_line() {
+        let quads = Mesh::rectangle(3.0, 2.0, 3, 2, ElementType::Quad4).unwrap();
+        assert_eq!(quads.num_nodes(), 12);
+        assert_eq!(quads.boundary_facets().len(), 10);
+        assert_eq!(quads.node_neighbors()[5].len(), 8);
+        assert_eq!(quads.faces().len(), 6);
+        let quad9 = Mesh::rectangle(3.0, 2.0, 3, 2, ElementType::Quad9).unwrap();
+        assert_eq!(quad9.num_nodes(), 7 * 5);
+        let quad8 = Mesh::rectangle(3.0, 2.0, 3, 2, ElementType::Quad8).unwrap();
+        assert_eq!(quad8.num_nodes(), 7 * 5 - 6);
+        let tri6 = Mesh::rectangle(3.0, 2.0, 3, 2, ElementType::Tri6).unwrap();
+        assert_eq!(tri6.num_elements(), 12);
+        assert_eq!(tri6.num_nodes(), 7 * 5);
+        let boundary = tri6.boundary_facets();
+        assert_eq!(boundary.len(), 10);
+        assert!(boundary.iter().all(|f| f.nodes.len() == 3));
+        let bottom = tri6.find_boundary_facets(|p| p.get_y() == 0.0);
+        assert_eq!(bottom.len(), 3);
+
+        let line = Mesh::line(2.0, 4, ElementType::Line3).unwrap();
+        assert_eq!(line.num_nodes(), 9);
+        let ends = line.boundary_facets();
+        assert_eq!(ends.len(), 2);
+        assert_eq!(ends[0].nodes, vec![0]);
+        assert_eq!(ends[1].nodes, vec![4]);
+        assert!(
+            line.nodes[line.elements[1].nodes[2]]
+                .is_equal(&Point3d::from_coords(0.75, 0.0, 0.0), 1e-15)
+        );
+        assert!(Mesh::line(1.0, 2, ElementType::Tri3).is_err());
+        assert!(Mesh::rectangle(1.0, 1.0, 1, 1, ElementType::Hex8).is_err());
+    }
+
+    #[test]
+    fn test_sets_and_validation() {
+        let mut mesh = Mesh::rectangle(1.0, 1.0, 2, 2, ElementType::Quad4).unwrap();
+        let left = mesh.find_nodes(|p| p.get_x() == 0.0);
+        assert_eq!(left, vec![0, 3, 6]);
+        mesh.add_node_set("left", vec![6, 0, 3, 0]).unwrap();
+        assert_eq!(mesh.node_set("left"), Some(&[0, 3, 6][..]));
+        assert!(mesh.add_node_set("bad", vec![9]).is_err());
+        mesh.add_element_set("first", vec![1, 0]).unwrap();
+        assert_eq!(mesh.element_set("first"), Some(&[0, 1][..]));
+        assert!(mesh.add_element_set("bad", vec![4]).is_err());
+        assert!(mesh.node_set("missing").is_none());
+
+        let n = mesh.add_node(Point3d::from_coords(5.0, 5.0, 0.0));
+        assert_eq!(n, 9);
+        assert!(mesh.add_element(ElementType::Tri3, vec![0, 1]).is_err());
+        assert!(mesh.add_element(ElementType::Tri3, vec![0, 1, 10]).is_err());
+        mesh.add_node_set("far", vec![n]).unwrap();
+        let map = mesh.remove_unused_nodes();
+        assert_eq!(map[n], None);
+        assert_eq!(mesh.num_nodes(), 9);
+        assert!(mesh.node_set("far").unwrap().is_empty());
+        mesh.validate().unwrap();
+
+        let nodes = vec![Point3d::new(); 3];
+        let duplicate = Element::new(ElementType::Tri3, vec![0, 1, 1]).unwrap();
+        assert!(Mesh::from_nodes_elements(nodes.clone(), vec![duplicate]).is_err());
+        let good = Element::new(ElementType::Tri3, vec![0, 1, 2]).unwrap();
+        assert!(Mesh::from_nodes_elements(nodes, vec![good]).is_ok());
+        assert!(Element::new(ElementType::Hex8, vec![0; 4]).is_err());
+
+        let quad = Mesh::rectangle(1.0, 1.0, 1, 1, ElementType::Quad4).unwrap();
+        assert!(quad.to_quadratic(true).unwrap().elements[0].element_type == ElementType::Quad8);
+        let wedge = Mesh::block(1.0, 1.0, 1.0, 1, 1, 1, ElementType::Wedge6).unwrap();
+        assert!(wedge.to_quadratic(true).is_err());
+    }
+
+    #[test]
+    fn test_from_triangulations() {
+        let mut points: Vec<Point2d> = (0..16)
+            .map(|i| Point2d::from_coords((i % 4) as f64, (i / 4) as f64))
+            .collect();
+        points.push(Point2d::from_coords(1.0, 1.0));
+        let tri = Triangulation2d::delaunay(&points).unwrap();
+        let mesh = Mesh::from_triangulation(&tri);
+        assert_eq!(mesh.num_nodes(), 16);
+        assert_eq!(mesh.num_elements(), 18);
+        assert_eq!(mesh.boundary_facets().len(), 12);
+
+        let grid: Vec<Point3d> = (0..27)
+            .map(|i| Point3d::from_coords((i % 3) as f64, (i / 3 % 3) as f64, (i / 9) as f64))
+            .collect();
+        let tet = Tetrahedralization::delaunay(&grid).unwrap();
+        let mesh = Mesh::from_tetrahedralization(&tet);
+        assert_eq!(mesh.num_nodes(), 27);
+        let volume: f64 = (0..mesh.num_elements()).map(|e| tet_volume(&mesh, e)).sum();
+        assert!((volume - 8.0).abs() < 1e-12);
+        assert!((0..mesh.num_elements()).all(|e| tet_volume(&mesh, e) > 0.0));
+        check_outward(&mesh);
+    }
+}
@@ -0,0 +1,452 @@
+use fem::material::{Hyperelastic, J2Plasticity, MaterialModel, PlasticState};
+use fem::{
+    Analysis, ElementType, Formulation, IsotropicElastic, LinearStatic, Mesh, NonlinearStatic,
+    PathControl,
+};
+use geom::Point3d;
+
+fn coordinates(p: &Point3d) -> [f64; 3] {
+    let (x, y, z) = p.get_coords();
+    [x, y, z]
+}
+
+// Unit cube on symmetry planes x = 0, y = 0 and z = 0.
+fn cube(mesh: &Mesh, material: MaterialModel, formulation: Formulation) -> NonlinearStatic<'_> {
+    let elastic = IsotropicElastic::new(1.0, 0.0).unwrap();
+    let mut problem = LinearStatic::new(mesh, Analysis::Solid, elastic).unwrap();
+    for d in 0..3 {
+        for n in mesh.find_nodes(|p| coordinates(p)[d] < 1e-12) {
+            problem.fix(n, d, 0.0).unwrap();
+        }
+    }
+    let mut nonlinear = NonlinearStatic::new(problem, formulation).unwrap();
+    nonlinear.set_material(&[0], material).unwrap();
+    nonlinear
+}
+
+// Pulls the x = 1 face of a cube to a stretch and returns the stretches
+// and the force on the face.
+fn stretch(nonlinear: &mut NonlinearStatic, mesh: &Mesh, x: f64) -> ([f64; 3], f64) {
+    let face = mesh.find_nodes(|p| p.get_x() > 1.0 - 1e-12);
+    for &n in face.iter() {
+        nonlinear.problem_mut().fix(n, 0, x - 1.0).unwrap();
+    }
+    let solution = nonlinear
+        .solve(PathControl::Load { increments: 4 })
+        .unwrap();
+    let u = nonlinear.displacements();
+    let corner = mesh.find_nodes(|p| coordinates(p).iter().all(|&c| c > 1.0 - 1e-12))[0];
+    let force = face.iter().map(|&n| solution.reactions[n][0]).sum();
+    (u[corner].map(|u| 1.0 + u), force)
+}
+
+// Cauchy stress of the hyperelastic material for principal stretches.
+fn hyperelastic_stress(material: &Hyperelastic, stretches: [f64; 3]) -> [f64; 3] {
+    let j: f64 = stretches.iter().product();
+    let b = stretches.map(|l| j.powf(-2.0 / 3.0) * l * l);
+    let i1: f64 = b.iter().sum();
+    let isochoric = b.map(|b| (material.c10 + material.c01 * i1) * b - material.c01 * b * b);
+    let mean = isochoric.iter().sum::<f64>() / 3.0;
+    isochoric.map(|s| 2.0 * (s - mean) / j + material.bulk * (j - 1.0))
+}
+
+// Largest difference of a tangent from central differences of the stress.
+fn tangent_error<F>(stress: F, strain: &[f64; 6], tangent: &[[f64; 6]; 6]) -> f64
+where
+    F: Fn(&[f64; 6]) -> [f64; 6],
+{
+    let h = 1e-6;
+    let mut error = 0.0f64;
+    for j in 0..6 {
+        let (mut plus, mut minus) = (*strain, *strain);
+        plus[j] += h;
+        minus[j] -= h;
+        let (sp, sm) = (stress(&plus), stress(&minus));
+        for i in 0..6 {
+            error = error.max(((sp[i] - sm[i]) / (2.0 * h) - tangent[i][j]).abs());
+        }
+    }
+    error
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hyperelastic() {
+        let strain = [0.12, -0.05, 0.03, 0.08, -0.04, 0.02];
+        for material in [
+            Hyperelastic::neo_hookean(2.0, 30.0).unwrap(),
+            Hyperelastic::mooney_rivlin(0.7, 0.3, 30.0).unwrap(),
+        ] {
+            let (_, tangent) = material.stress(&strain).unwrap();
+            let error = tangent_error(|e| material.stress(e).unwrap().0, &strain, &tangent);
+            assert!(error < 1e-6, "{error}");
+
+            // Stress free and linear elastic at small strains.
+            let (stress, tangent) = material.stress(&[0.0; 6]).unwrap();
+            assert!(stress.iter().all(|s| s.abs() < 1e-12));
+            let (mu, kappa) = (material.shear(), material.bulk);
+            let elastic = IsotropicElastic::new(
+                9.0 * kappa * mu / (3.0 * kappa + mu),
+                (3.0 * kappa - 2.0 * mu) / (2.0 * (3.0 * kappa + mu)),
+            )
+            .unwrap();
+            for (row, expected) in tangent.iter().zip(elastic.solid_matrix().iter()) {
+                for (v, e) in row.iter().zip(expected.iter()) {
+                    assert!((v - e).abs() < 1e-10);
+                }
+            }
+        }
+        let compressed = [-0.5, 0.0, 0.0, 0.0, 0.0, 0.0];
+        let material = Hyperelastic::neo_hookean(1.0, 10.0).unwrap();
+        assert!(material.stress(&compressed).is_err());
+        assert!(Hyperelastic::neo_hookean(0.0, 1.0).is_err());
+        assert!(Hyperelastic::mooney_rivlin(1.0, 0.0, -1.0).is_err());
+    }
+
+    #[test]
+    fn test_plasticity() {
+        let elastic = IsotropicElastic::new(200.0, 0.3).unwrap();
+        let material = J2Plasticity::new(elastic, 0.25, 5.0, 3.0).unwrap();
+        let state = PlasticState {
+            plastic_strain: [0.001, -0.0005, -0.0005, 0.0004, 0.0, 0.0],
+            back_stress: [0.02, -0.01, -0.01, 0.0, 0.005, 0.0],
+            equivalent_plastic_strain: 0.001,
+        };
+        // Elastic inside the yield surface.
+        let (_, tangent, updated) =
+            material.stress(&[0.001, -0.0004, -0.0004, 0.0, 0.0, 0.0], &state);
+        assert_eq!(updated, state);
+        assert_eq!(tangent, elastic.solid_matrix());
+        // Consistent tangent of the return map.
+        let strain = [0.004, -0.001, 0.0005, 0.003, -0.001, 0.002];
+        let (stress, tangent, updated) = material.stress(&strain, &state);
+        assert!(updated.equivalent_plastic_strain > state.equivalent_plastic_strain);
+        let error = tangent_error(|e| material.stress(e, &state).0, &strain, &tangent);
+        assert!(error < 1e-4, "{error}");
+        // On the hardened yield surface, with an incompressible plastic flow.
+        let mut relative = stress;
+        let mean = (stress[0] + stress[1] + stress[2]) / 3.0;
+        for (i, r) in relative.iter_mut().enumerate() {
+            *r -= updated.back_stress[i] + if i < 3 { mean } else { 0.0 };
+        }
+        let radius = material.yield_stress
+            + material.isotropic_hardening * updated.equivalent_plastic_strain;
+        assert!((fem::material::von_mises(&relative) - radius).abs() < 1e-10);
+        let plastic = updated.plastic_strain;
+        assert!((plastic[0] + plastic[1] + plastic[2]).abs() < 1e-14);
+        assert!(J2Plasticity::new(elastic, 0.0, 1.0, 1.0).is_err());
+        assert!(J2Plasticity::new(elastic, 1.0, -1.0, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_small_strain() {
+        // Elastic small strain analysis is the linear one.
+        let material = IsotropicElastic::new(200.0, 0.3).unwrap();
+        for analysis in [Analysis::PlaneStrain, Analysis::Axisymmetric] {
+            let mesh = Mesh::rectangle(2.0, 1.0, 4, 2, ElementType::Quad8).unwrap();
+            let mut problem = LinearStatic::new(&mesh, analysis, material).unwrap();
+            for n in mesh.find_nodes(|p| p.get_y() < 1e-12) {
+                problem.fix(n, 1, 0.0).unwrap();
+            }
+            problem.fix(0, 0, 0.0).unwrap();
+            for facet in mesh.find_boundary_facets(|p| p.get_y() > 1.0 - 1e-12) {
+                problem.add_pressure(&facet, 3.0).unwrap();
+            }
+            for facet in mesh.find_boundary_facets(|p| p.get_x() > 2.0 - 1e-12) {
+                problem.add_pressure(&facet, 1.0).unwrap();
+            }
+            let linear = problem.solve().unwrap();
+            let mut nonlinear = NonlinearStatic::new(problem, Formulation::SmallStrain).unwrap();
+            let solution = nonlinear
+                .solve(PathControl::Load { increments: 1 })
+                .unwrap();
+            assert_eq!(solution.iterations, vec![0]);
+            for (u, expected) in solution.displacements[0]
+                .iter()
+                .zip(linear.displacements.iter())
+            {
+                for d in 0..2 {
+                    assert!((u[d] - expected[d]).abs() < 1e-10);
+                }
+            }
+            for (r, expected) in solution.reactions.iter().zip(linear.reactions.iter()) {
+                assert!((r[1] - expected[1]).abs() < 1e-8);
+            }
+        }
+        let mesh = Mesh::rectangle(1.0, 1.0, 1, 1, ElementType::Quad4).unwrap();
+        let problem =
+            LinearStatic::new(&mesh, Analysis::PlaneStress { thickness: 1.0 }, material).unwrap();
+        assert!(NonlinearStatic::new(problem, Formulation::TotalLagrangian).is_err());
+    }
+
+    #[test]
+    fn test_uniaxial_hyperelastic() {
+        let mesh = Mesh::block(1.0, 1.0, 1.0, 1, 1, 1, ElementType::Hex8).unwrap();
+        for material in [
+            Hyperelastic::neo_hookean(1.0, 5.0).unwrap(),
+            Hyperelastic::mooney_rivlin(0.3, 0.2, 5.0).unwrap(),
+        ] {
+            let mut results = Vec::new();
+            for formulation in [Formulation::TotalLagrangian, Formulation::UpdatedLagrangian] {
+                let mut nonlinear = cube(&mesh, MaterialModel::Hyperelastic(material), formulation);
+                let (stretches, force) = stretch(&mut nonlinear, &mesh, 1.8);
+                assert!((stretches[0] - 1.8).abs() < 1e-12);
+                assert!((stretches[1] - stretches[2]).abs() < 1e-10);
+                // Free lateral faces, the force on the current area.
+                let stress = hyperelastic_stress(&material, stretches);
+                assert!(stress[1].abs() < 1e-8);
+                let area = stretches[1] * stretches[2];
+                assert!((force - stress[0] * area).abs() < 1e-8);
+                results.push((stretches, force));
+            }
+            assert!((results[0].1 - results[1].1).abs() < 1e-8);
+            assert!((results[0].0[1] - results[1].0[1]).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn test_formulations() {
+        // Thick tube under internal pressure, the same in both Lagrangian
+        // formulations.
+        let (inner, outer) = (1.0, 2.0);
+        let mut mesh = Mesh::rectangle(outer - inner, 0.5, 4, 1, ElementType::Quad8).unwrap();
+        for p in mesh.nodes.iter_mut() {
+            p.set_x(p.get_x() + inner);
+        }
+        let material = Hyperelastic::neo_hookean(1.0, 20.0).unwrap();
+        let mut results = Vec::new();
+        for formulation in [Formulation::TotalLagrangian, Formulation::UpdatedLagrangian] {
+            let elastic = IsotropicElastic::new(1.0, 0.3).unwrap();
+            let mut problem = LinearStatic::new(&mesh, Analysis::Axisymmetric, elastic).unwrap();
+            for n in mesh.find_nodes(|p| p.get_y() < 1e-12 || p.get_y() > 0.5 - 1e-12) {
+                problem.fix(n, 1, 0.0).unwrap();
+            }
+            for facet in mesh.find_boundary_facets(|p| p.get_x() < inner + 1e-12) {
+                problem.add_pressure(&facet, 0.4).unwrap();
+            }
+            let mut nonlinear = NonlinearStatic::new(problem, formulation).unwrap();
+            let elements: Vec<usize> = (0..mesh.num_elements()).collect();
+            nonlinear
+                .set_material(&elements, MaterialModel::Hyperelastic(material))
+                .unwrap();
+            let solution = nonlinear
+                .solve(PathControl::Load { increments: 2 })
+                .unwrap();
+            assert!(solution.iterations.iter().all(|&i| i < 8));
+            results.push(nonlinear.displacements());
+        }
+        // Large deformation.
+        assert!(results[0][0][0] > 0.2 * inner);
+        for (a, b) in results[0].iter().zip(results[1].iter()) {
+            assert!((a[0] - b[0]).abs() < 1e-8);
+        }
+    }
+
+    #[test]
+    fn test_cantilever() {
+        // Tip loaded elastica with P L^2 / (E I) = 2.
+        let (length, height, young) = (10.0, 0.5, 1000.0);
+        let mesh = Mesh::rectangle(length, height, 20, 2, ElementType::Quad8).unwrap();
+        let load = 2.0 * young * height.powi(3) / 12.0 / (length * length);
+        let tip = mesh
+            .find_nodes(|p| p.get_x() > length - 1e-12 && (p.get_y() - 0.5 * height).abs() < 1e-12)
+            [0];
+        let mut results = Vec::new();
+        for formulation in [Formulation::TotalLagrangian, Formulation::UpdatedLagrangian] {
+            let material = IsotropicElastic::new(young, 0.0).unwrap();
+            let mut problem = LinearStatic::new(&mesh, Analysis::PlaneStrain, material).unwrap();
+            problem
+                .fix_nodes(&mesh.find_nodes(|p| p.get_x() < 1e-12))
+                .unwrap();
+            problem.add_force(tip, 1, -load).unwrap();
+            let mut nonlinear = NonlinearStatic::new(problem, formulation).unwrap();
+            let solution = nonlinear
+                .solve(PathControl::Load { increments: 5 })
+                .unwrap();
+            assert_eq!(solution.load_factors.len(), 5);
+            let u = nonlinear.displacements()[tip];
+            assert!((-u[1] / length - 0.49346).abs() < 0.005 * 0.49346);
+            assert!((-u[0] / length - 0.16064).abs() < 0.01 * 0.16064);
+            results.push(u);
+        }
+        assert!((results[0][1] - results[1][1]).abs() < 1e-8);
+    }
+
+    #[test]
+    fn test_arc_length() {
+        // Compressed Saint Venant-Kirchhoff cube, whose nominal stress
+        // E s (s^2 - 1) / 2 at stretch s peaks at s^2 = 1 / 3.
+        let mesh = Mesh::block(1.0, 1.0, 1.0, 1, 1, 1, ElementType::Hex8).unwrap();
+        let young = 1.0;
+        let material = MaterialModel::Elastic(IsotropicElastic::new(young, 0.0).unwrap());
+        let load = 0.25 * young;
+        let mut nonlinear = cube(&mesh, material, Formulation::TotalLagrangian);
+        let face = mesh.find_nodes(|p| p.get_x() > 1.0 - 1e-12);
+        for &n in face.iter() {
+            nonlinear
+                .problem_mut()
+                .add_force(n, 0, -load / 4.0)
+                .unwrap();
+        }
+        // Beyond the limit load.
+        assert!(
+            nonlinear
+                .clone()
+                .solve(PathControl::Load { increments: 4 })
+                .is_err()
+        );
+
+        let solution = nonlinear
+            .solve(PathControl::ArcLength {
+                length: 0.1,
+                max_increments: 12,
+            })
+            .unwrap();
+        assert_eq!(solution.load_factors.len(), 12);
+        for (factor, u) in solution
+            .load_factors
+            .iter()
+            .zip(solution.displacements.iter())
+        {
+            let s = 1.0 + u[face[0]][0];
+            assert!((factor * load - young * s * (1.0 - s * s) / 2.0).abs() < 1e-8);
+        }
+        let peak = solution.load_factors.iter().fold(0.0f64, |m, &f| m.max(f));
+        let limit = young / (3.0 * 3f64.sqrt()) / load;
+        assert!(peak <= limit && peak > 0.995 * limit);
+        // Past the limit point.
+        assert!(*solution.load_factors.last().unwrap() < 0.95 * peak);
+    }
+
+    #[test]
+    fn test_elastoplastic() {
+        let mesh = Mesh::block(1.0, 1.0, 1.0, 1, 1, 1, ElementType::Hex8).unwrap();
+        let elastic = IsotropicElastic::new(200.0, 0.3).unwrap();
+        let (yield_stress, hardening) = (0.2, 20.0);
+        let strain = 5.0 * yield_stress / elastic.young;
+        let tangent = elastic.young * hardening / (elastic.young + hardening);
+        let peak = yield_stress + tangent * (strain - yield_stress / elastic.young);
+
+        // Isotropic hardening.
+        let material = J2Plasticity::new(elastic, yield_stress, hardening, 0.0).unwrap();
+        let mut nonlinear = cube(
+            &mesh,
+            MaterialModel::Plastic(material),
+            Formulation::SmallStrain,
+        );
+        let face = mesh.find_nodes(|p| p.get_x() > 1.0 - 1e-12);
+        for &n in face.iter() {
+            nonlinear.problem_mut().fix(n, 0, strain).unwrap();
+        }
+        let solution = nonlinear
+            .solve(PathControl::Load { increments: 5 })
+            .unwrap();
+        let force: f64 = face.iter().map(|&n| solution.reactions[n][0]).sum();
+        assert!((force - peak).abs() < 1e-8);
+        let plastic = (elastic.young * strain - yield_stress) / (elastic.young + hardening);
+        assert!(
+            solution
+                .plastic_strains
+                .iter()
+                .all(|p| (p - plastic).abs() < 1e-10)
+        );
+        assert!((solution.stresses[0][0] - peak).abs() < 1e-8);
+
+        // Kinematic hardening: elastic unloading over twice the yield stress,
+        // then a symmetric cycle.
+        let material = J2Plasticity::new(elastic, yield_stress, 0.0, hardening).unwrap();
+        let mut nonlinear = cube(
+            &mesh,
+            MaterialModel::Plastic(material),
+            Formulation::SmallStrain,
+        );
+        let mut forces = Vec::new();
+        let reverse = strain - 1.5 * yield_stress / elastic.young;
+        for target in [strain, reverse, -strain] {
+            for &n in face.iter() {
+                nonlinear.problem_mut().fix(n, 0, target).unwrap();
+            }
+            let solution = nonlinear
+                .solve(PathControl::Load { increments: 5 })
+                .unwrap();
+            forces.push(face.iter().map(|&n| solution.reactions[n][0]).sum::<f64>());
+        }
+        assert!((forces[0] - peak).abs() < 1e-8);
+        assert!((forces[1] - (peak - 1.5 * yield_stress)).abs() < 1e-8);
+        assert!((forces[2] + peak).abs() < 1e-8);
+    }
+
+    #[test]
+    fn test_unloading_past_limit_point() {
+        // A plastic bar in series with a Saint Venant-Kirchhoff one, both in
+        // uniaxial strain. The plastic bar yields before the elastic one
+        // reaches its compressive limit load E / (3 sqrt 3), then unloads
+        // below its yield stress while the elastic bar snaps through.
+        let mesh = Mesh::block(2.0, 1.0, 1.0, 2, 1, 1, ElementType::Hex8).unwrap();
+        let elastic = IsotropicElastic::new(1.0, 0.0).unwrap();
+        let mut problem = LinearStatic::new(&mesh, Analysis::Solid, elastic).unwrap();
+        for n in 0..mesh.num_nodes() {
+            problem.fix(n, 1, 0.0).unwrap();
+            problem.fix(n, 2, 0.0).unwrap();
+        }
+        let fixed = mesh.find_nodes(|p| p.get_x() < 1e-12);
+        for &n in fixed.iter() {
+            problem.fix(n, 0, 0.0).unwrap();
+        }
+        let load = 0.25;
+        let face = mesh.find_nodes(|p| p.get_x() > 2.0 - 1e-12);
+        for &n in face.iter() {
+            problem.add_force(n, 0, -load / 4.0).unwrap();
+        }
+        let yield_stress = 0.1;
+        let plastic = J2Plasticity::new(
+            IsotropicElastic::new(100.0, 0.0).unwrap(),
+            yield_stress,
+            10.0,
+            0.0,
+        )
+        .unwrap();
+        let mut nonlinear = NonlinearStatic::new(problem, Formulation::TotalLagrangian).unwrap();
+        let bar = (0..2)
+            .find(|&e| {
+                mesh.element_points(e)
+                    .iter()
+                    .all(|p| p.get_x() < 1.0 + 1e-12)
+            })
+            .unwrap();
+        nonlinear
+            .set_material(&[bar], MaterialModel::Plastic(plastic))
+            .unwrap();
+
+        // Plastic strain reached on the way up to the limit load.
+        let limit = 1.0 / (3.0 * 3f64.sqrt()) / load;
+        let mut loaded = nonlinear.clone();
+        for &n in face.iter() {
+            loaded
+                .problem_mut()
+                .add_force(n, 0, -(0.99 * limit - 1.0) * load / 4.0)
+                .unwrap();
+        }
+        let before = loaded.solve(PathControl::Load { increments: 10 }).unwrap();
+        let hardened = before.plastic_strains[fixed[0]];
+        assert!(hardened > 0.0);
+
+        let solution = nonlinear
+            .solve(PathControl::ArcLength {
+                length: 0.05,
+                max_increments: 40,
+            })
+            .unwrap();
+        let peak = solution.load_factors.iter().fold(0.0f64, |m, &f| m.max(f));
+        assert!(peak <= limit && peak > 0.99 * limit);
+        let last = *solution.load_factors.last().unwrap();
+        assert!(last * load < yield_stress);
+        // Unloading is elastic and keeps the plastic strain of the peak.
+        let residual = solution.plastic_strains[fixed[0]];
+        assert!(residual >= hardened);
+        assert!(residual < 2.0 * hardened);
+    }
+}
@@ -0,0 +1,152 @@
+use fem::ElementType;
+use fem::QuadratureRule;
+use fem::quadrature::{gauss_legendre, gauss_lobatto};
+
+fn factorial(n: usize) -> f64 {
+    (1..=n).map(|k| k as f64).product()
+}
+
+// Integral of x^a over [-1, 1].
+fn line_moment(a: usize) -> f64 {
+    if a % 2 == 1 {
+        0.0
+    } else {
+        2.0 / (a + 1) as f64
+    }
+}
+
+// Integral of r^a s^b t^c over the reference element.
+fn moment(element_type: ElementType, a: usize, b: usize, c: usize) -> f64 {
+    match element_type.linear() {
+        ElementType::Line2 => line_moment(a) * if b + c == 0 { 1.0 } else { 0.0 },
+        ElementType::Quad4 => line_moment(a) * line_moment(b) * if c == 0 { 1.0 } else { 0.0 },
+        ElementType::Hex8 => line_moment(a) * line_moment(b) * line_moment(c),
+        ElementType::Tri3 if c == 0 => factorial(a) * factorial(b) / factorial(a + b + 2),
+        ElementType::Tri3 => 0.0,
+        ElementType::Tet4 => factorial(a) * factorial(b) * factorial(c) / factorial(a + b + c + 3),
+        ElementType::Wedge6 => factorial(a) * factorial(b) / factorial(a + b + 2) * line_moment(c),
+        _ => unreachable!(),
+    }
+}
+
+fn integrate(rule: &QuadratureRule, a: usize, b: usize, c: usize) -> f64 {
+    rule.iter()
+        .map(|(p, w)| w * p[0].powi(a as i32) * p[1].powi(b as i32) * p[2].powi(c as i32))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gauss_legendre() {
+        for n in 1..=12 {
+            let (x, w) = gauss_legendre(n);
+            assert_eq!(x.len(), n);
+            assert!(x.windows(2).all(|p| p[0] < p[1]));
+            for a in 0..2 * n {
+                let sum: f64 = x
+                    .iter()
+                    .zip(w.iter())
+                    .map(|(x, w)| w * x.powi(a as i32))
+                    .sum();
+                assert!((sum - line_moment(a)).abs() < 1e-14, "n {} a {}", n, a);
+            }
+        }
+        let (x, w) = gauss_legendre(2);
+        assert!((x[1] - 1.0 / 3f64.sqrt()).abs() < 1e-15);
+        assert!(w.iter().all(|w| (w - 1.0).abs() < 1e-15));
+    }
+
+    #[test]
+    fn test_gauss_lobatto() {
+        for n in 2..=10 {
+            let (x, w) = gauss_lobatto(n).unwrap();
+            assert_eq!((x[0], x[n - 1]), (-1.0, 1.0));
+            assert!(x.windows(2).all(|p| p[0] < p[1]));
+            for a in 0..=2 * n - 3 {
+                let sum: f64 = x
+                    .iter()
+                    .zip(w.iter())
+                    .map(|(x, w)| w * x.powi(a as i32))
+                    .sum();
+                assert!((sum - line_moment(a)).abs() < 1e-14, "n {} a {}", n, a);
+            }
+        }
+        let (x, w) = gauss_lobatto(3).unwrap();
+        assert_eq!(x, vec![-1.0, 0.0, 1.0]);
+        assert!((w[1] - 4.0 / 3.0).abs() < 1e-15);
+        assert!(gauss_lobatto(1).is_err());
+
+        let rule = QuadratureRule::lobatto(ElementType::Quad9, 3).unwrap();
+        assert_eq!(rule.len(), 9);
+        assert!(QuadratureRule::lobatto(ElementType::Tri3, 3).is_err());
+    }
+
+    #[test]
+    fn test_element_rules() {
+        let types = [
+            ElementType::Line2,
+            ElementType::Quad4,
+            ElementType::Hex8,
+            ElementType::Tri3,
+            ElementType::Tet4,
+            ElementType::Wedge6,
+        ];
+        for t in types {
+            for degree in 0..=8 {
+                let rule = QuadratureRule::new(t, degree);
+                assert!(rule.weights.iter().all(|&w| w > 0.0));
+                // Total degree for simplices, per direction otherwise.
+                let simplex = matches!(t, ElementType::Tri3 | ElementType::Tet4);
+                for a in 0..=degree {
+                    for b in 0..=degree {
+                        for c in 0..=degree {
+                            let fits = if simplex {
+                                a + b + c <= degree
+                            } else if t == ElementType::Wedge6 {
+                                a + b <= degree && c <= degree
+                            } else {
+                                a.max(b).max(c) <= degree
+                            };
+                            if fits
+                                && (c == 0 || t.dimension() == 3)
+                                && (b == 0 || t.dimension() > 1)
+                            {
+                                let exact = moment(t, a, b, c);
+                                let error = (integrate(&rule, a, b, c) - exact).abs();
+                                assert!(
+                                    error < 1e-13,
+                                    "{} degree {} ({} {} {})",
+                                    t.as_str(),
+                                    degree,
+                                    a,
+                                    b,
+                                    c
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        assert_eq!(QuadratureRule::new(ElementType::Hex20, 3).len(), 8);
+        assert_eq!(QuadratureRule::new(ElementType::Tri6, 2).len(), 3);
+        assert_eq!(QuadratureRule::new(ElementType::Tet10, 2).len(), 4);
+
+        // Pyramid: volume 4/3 and first moment 1/3 in t.
+        for degree in 0..=4 {
+            let rule = QuadratureRule::new(ElementType::Pyramid5, degree);
+            assert!((integrate(&rule, 0, 0, 0) - 4.0 / 3.0).abs() < 1e-14);
+            assert!(integrate(&rule, 1, 0, 0).abs() < 1e-14);
+            if degree >= 1 {
+                assert!((integrate(&rule, 0, 0, 1) - 1.0 / 3.0).abs() < 1e-14);
+            }
+            if degree >= 2 {
+                // Integral of x^2: 2/3 * int (1 - t)^4 dt * 2 = 4/15.
+                assert!((integrate(&rule, 2, 0, 0) - 4.0 / 15.0).abs() < 1e-14);
+            }
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use fem::shape::{
+    evaluate, interpolate, inverse_map, node_coordinates, shape_derivatives, shape_functions,
+};
+use fem::{ElementType, Jacobian, Mesh, QuadratureRule};
+use geom::Point3d;
+
+const ALL: [ElementType; 14] = [
+    ElementType::Line2,
+    ElementType::Line3,
+    ElementType::Tri3,
+    ElementType::Tri6,
+    ElementType::Quad4,
+    ElementType::Quad8,
+    ElementType::Quad9,
+    ElementType::Tet4,
+    ElementType::Tet10,
+    ElementType::Hex8,
+    ElementType::Hex20,
+    ElementType::Hex27,
+    ElementType::Wedge6,
+    ElementType::Pyramid5,
+];
+
+fn random(n: usize, seed: u64) -> Vec<f64> {
+    let mut state = seed;
+    (0..n)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        })
+        .collect()
+}
+
+// Points strictly inside the reference element.
+fn interior_points(element_type: ElementType, seed: u64) -> Vec<[f64; 3]> {
+    let dimension = element_type.dimension();
+    random(60, seed)
+        .chunks(3)
+        .map(|c| {
+            let mut xi = [0.0; 3];
+            match element_type.linear() {
+                ElementType::Tri3 | ElementType::Tet4 => {
+                    let s = 1.0 + c[..dimension].iter().sum::<f64>();
+                    for k in 0..dimension {
+                        xi[k] = c[k] / s;
+                    }
+                }
+                ElementType::Wedge6 => {
+                    let s = 1.0 + c[0] + c[1];
+                    xi = [c[0] / s, c[1] / s, 2.0 * c[2] - 1.0];
+                }
+                ElementType::Pyramid5 => {
+                    let t = 0.9 * c[2];
+                    xi = [
+                        (2.0 * c[0] - 1.0) * (1.0 - t),
+                        (2.0 * c[1] - 1.0) * (1.0 - t),
+                        t,
+                    ];
+                }
+                _ => {
+                    for k in 0..dimension {
+                        xi[k] = 2.0 * c[k] - 1.0;
+                    }
+                }
+            }
+            xi
+        })
+        .collect()
+}
+
+// Reference nodes mapped by an affine transformation.
+fn affine_points(element_type: ElementType) -> Vec<Point3d> {
+    node_coordinates(element_type)
+        .iter()
+        .map(|c| {
+            Point3d::from_coords(
+                1.0 + 2.0 * c[0] + 0.5 * c[1],
+                -1.0 + 0.3 * c[0] + 3.0 * c[1] + 0.2 * c[2],
+                0.5 + 0.1 * c[1] + 4.0 * c[2],
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shape_functions() {
+        for t in ALL {
+            let nodes = node_coordinates(t);
+            assert_eq!(nodes.len(), t.num_nodes());
+            // Kronecker property at the nodes (the pyramid apex is approached).
+            for (i, xi) in nodes.iter().enumerate() {
+                let n = shape_functions(t, *xi);
+                for (j, v) in n.iter().enumerate() {
+                    let expected = if i == j { 1.0 } else { 0.0 };
+                    assert!(
+                        (v - expected).abs() < 1e-10,
+                        "{} node {} {}",
+                        t.as_str(),
+                        i,
+                        j
+                    );
+                }
+            }
+            for xi in interior_points(t, 3) {
+                let (n, d) = evaluate(t, xi);
+                assert!((n.iter().sum::<f64>() - 1.0).abs() < 1e-13);
+                // Natural coordinates are reproduced, with unit derivatives.
+                for k in 0..t.dimension() {
+                    let x: f64 = n.iter().zip(nodes.iter()).map(|(n, c)| n * c[k]).sum();
+                    assert!((x - xi[k]).abs() < 1e-13);
+                    for m in 0..3 {
+                        let g: f64 = d.iter().zip(nodes.iter()).map(|(d, c)| d[m] * c[k]).sum();
+                        let expected = if k == m { 1.0 } else { 0.0 };
+                        assert!((g - expected).abs() < 1e-12);
+                    }
+                }
+                // Derivatives agree with central differences.
+                let h = 1e-6;
+                for m in 0..t.dimension() {
+                    let (mut a, mut b) = (xi, xi);
+                    a[m] += h;
+                    b[m] -= h;
+                    let (na, nb) = (shape_functions(t, a), shape_functions(t, b));
+                    for i in 0..n.len() {
+                        let fd = (na[i] - nb[i]) / (2.0 * h);
+                        assert!((fd - d[i][m]).abs() < 1e-7, "{} {} {}", t.as_str(), i, m);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_jacobian() {
+        for t in ALL {
+            let points = affine_points(t);
+            for xi in interior_points(t, 7) {
+                let d = shape_derivatives(t, xi);
+                let jacobian = Jacobian::new(t, &points, &d).unwrap();
+                assert_eq!(jacobian.dimension, t.dimension());
+                // Gradients of a linear field are exact.
+                let field = |p: &Point3d| 2.0 * p.get_x() - p.get_y() + 0.5 * p.get_z();
+                let values: Vec<f64> = points.iter().map(field).collect();
+                let g = jacobian.gradients(&d);
+                let mut gradient = [0.0; 3];
+                for (v, g) in values.iter().zip(g.iter()) {
+                    for k in 0..3 {
+                        gradient[k] += v * g[k];
+                    }
+                }
+                if t.dimension() == 3 {
+                    for (a, b) in gradient.iter().zip([2.0, -1.0, 0.5]) {
+                        assert!((a - b).abs() < 1e-12);
+                    }
+                }
+                let p = interpolate(t, &points, xi);
+                let back = inverse_map(t, &points, &p);
+                if t.dimension() == 3 {
+                    let back = back.unwrap();
+                    for k in 0..3 {
+                        assert!((back[k] - xi[k]).abs() < 1e-10);
+                    }
+                }
+            }
+        }
+
+        // Volumes of meshed blocks by quadrature.
+        for t in [
+            ElementType::Hex8,
+            ElementType::Hex20,
+            ElementType::Hex27,
+            ElementType::Tet4,
+            ElementType::Tet10,
+            ElementType::Wedge6,
+        ] {
+            let mesh = Mesh::block(1.0, 2.0, 3.0, 2, 1, 2, t).unwrap();
+            let rule = QuadratureRule::new(t, 2);
+            let mut volume = 0.0;
+            for e in 0..mesh.num_elements() {
+                let points = mesh.element_points(e);
+                for (xi, w) in rule.iter() {
+                    volume += w * Jacobian::at(t, &points, xi).unwrap().determinant;
+                }
+            }
+            assert!((volume - 6.0).abs() < 1e-12, "{}", t.as_str());
+        }
+        let pyramid = [
+            Point3d::from_coords(0.0, 0.0, 0.0),
+            Point3d::from_coords(2.0, 0.0, 0.0),
+            Point3d::from_coords(2.0, 2.0, 0.0),
+            Point3d::from_coords(0.0, 2.0, 0.0),
+            Point3d::from_coords(1.0, 1.0, 3.0),
+        ];
+        let volume: f64 = QuadratureRule::new(ElementType::Pyramid5, 2)
+            .iter()
+            .map(|(xi, w)| {
+                w * Jacobian::at(ElementType::Pyramid5, &pyramid, xi)
+                    .unwrap()
+                    .determinant
+            })
+            .sum();
+        assert!((volume - 4.0).abs() < 1e-12);
+
+        // A tilted quadrilateral: area scale and in-plane gradients.
+        let c = 0.6f64;
+        let s = 0.8f64;
+        let quad: Vec<Point3d> = [[0.0, 0.0], [2.0, 0.0], [2.0, 1.0], [0.0, 1.0]]
+            .iter()
+            .map(|p| Point3d::from_coords(p[0], p[1] * c, p[1] * s))
+            .collect();
+        let area: f64 = QuadratureRule::new(ElementType::Quad4, 2)
+            .iter()
+            .map(|(xi, w)| {
+                w * Jacobian::at(ElementType::Quad4, &quad, xi)
+                    .unwrap()
+                    .determinant
+            })
+            .sum();
+        assert!((area - 2.0).abs() < 1e-14);
+        let d = shape_derivatives(ElementType::Quad4, [0.3, -0.2, 0.0]);
+        let jacobian = Jacobian::new(ElementType::Quad4, &quad, &d).unwrap();
+        let g = jacobian.gradients(&d);
+        // The in-plane distance along the tilted direction has unit gradient.
+        let values = [0.0, 0.0, 1.0, 1.0];
+        let mut gradient = [0.0; 3];
+        for (v, g) in values.iter().zip(g.iter()) {
+            for k in 0..3 {
+                gradient[k] += v * g[k];
+            }
+        }
+        assert!(gradient[0].abs() < 1e-14);
+        assert!((gradient[1] - c).abs() < 1e-14 && (gradient[2] - s).abs() < 1e-14);
+
+        // Surface elements in either orientation are accepted, but not
+        // folded or degenerate ones.
+        let reversed: Vec<Point3d> = quad.iter().rev().copied().collect();
+        assert!(Jacobian::at(ElementType::Quad4, &reversed, [0.3, -0.2, 0.0]).is_ok());
+        let dart = [
+            Point3d::from_coords(0.0, 0.0, 1.0),
+            Point3d::from_coords(2.0, 0.0, 1.0),
+            Point3d::from_coords(0.3, 0.3, 1.0),
+            Point3d::from_coords(0.0, 2.0, 1.0),
+        ];
+        assert!(Jacobian::at(ElementType::Quad4, &dart, [-0.5, -0.5, 0.0]).is_ok());
+        assert!(Jacobian::at(ElementType::Quad4, &dart, [0.9, 0.9, 0.0]).is_err());
+        let flat = [
+            Point3d::from_coords(0.0, 0.0, 0.0),
+            Point3d::from_coords(1.0, 1.0, 1.0),
+            Point3d::from_coords(2.0, 2.0, 2.0),
+        ];
+        assert!(Jacobian::at(ElementType::Tri3, &flat, [0.2, 0.2, 0.0]).is_err());
+
+        // Line length scale.
+        let line = [
+            Point3d::from_coords(0.0, 0.0, 0.0),
+            Point3d::from_coords(3.0, 4.0, 0.0),
+        ];
+        let jacobian = Jacobian::at(ElementType::Line2, &line, [0.0; 3]).unwrap();
+        assert!((jacobian.determinant - 2.5).abs() < 1e-15);
+
+        // Inverted and degenerate elements.
+        let mut tet = affine_points(ElementType::Tet4);
+        tet.swap(1, 2);
+        assert!(Jacobian::at(ElementType::Tet4, &tet, [0.25; 3]).is_err());
+        let flat = vec![Point3d::new(); 3];
+        assert!(Jacobian::at(ElementType::Tri3, &flat, [0.2, 0.2, 0.0]).is_err());
+        assert!(Jacobian::at(ElementType::Tri3, &tet, [0.2, 0.2, 0.0]).is_err());
+
+        // Inverse mapping on a distorted planar serendipity element.
+        let mut quad8 = affine_points(ElementType::Quad8);
+        let y = quad8[4].get_y();
+        quad8[4].set_y(y - 0.2);
+        for xi in interior_points(ElementType::Quad8, 11) {
+            let p = interpolate(ElementType::Quad8, &quad8, xi);
+            let back = inverse_map(ElementType::Quad8, &quad8, &p).unwrap();
+            assert!((back[0] - xi[0]).abs() < 1e-10 && (back[1] - xi[1]).abs() < 1e-10);
+        }
+        assert!(inverse_map(ElementType::Line2, &line, &line[0]).is_err());
+    }
+}
@@ -0,0 +1,388 @@
+use fem::{ElementType, IsotropicElastic, Lamina, Layer, Layup, Mesh, Shell, ShellSolution};
+use geom::Point3d;
+
+fn plate_rigidity(material: &IsotropicElastic, thickness: f64) -> f64 {
+    material.young * thickness.powi(3) / (12.0 * (1.0 - material.poisson.powi(2)))
+}
+
+// Unit square plate under unit downward pressure, simply supported
+// or clamped on all edges. Returns the centre deflection and the moments
+// averaged over the elements around the centre.
+fn square_plate(element_type: ElementType, n: usize, clamped: bool) -> (f64, [f64; 8]) {
+    let mesh = Mesh::rectangle(1.0, 1.0, n, n, element_type).unwrap();
+    let material = IsotropicElastic::new(1e4, 0.3).unwrap();
+    let mut shell = Shell::new(&mesh, Layup::homogeneous(0.01, &material).unwrap()).unwrap();
+    let edges = mesh
+        .find_nodes(|p| p.get_x().min(p.get_y()) < 1e-9 || p.get_x().max(p.get_y()) > 1.0 - 1e-9);
+    for node in 0..mesh.num_nodes() {
+        shell.fix(node, 0, 0.0).unwrap();
+        shell.fix(node, 1, 0.0).unwrap();
+    }
+    if clamped {
+        shell.clamp_nodes(&edges).unwrap();
+    } else {
+        shell.pin_nodes(&edges).unwrap();
+        // Hard supports also fix the rotation along the edge, matching the
+        // Kirchhoff boundary conditions.
+        for &node in edges.iter() {
+            let (x, y, _) = mesh.nodes[node].get_coords();
+            if !(1e-9..=1.0 - 1e-9).contains(&x) {
+                shell.fix(node, 3, 0.0).unwrap();
+            }
+            if !(1e-9..=1.0 - 1e-9).contains(&y) {
+                shell.fix(node, 4, 0.0).unwrap();
+            }
+        }
+    }
+    for e in 0..mesh.num_elements() {
+        shell.add_pressure(e, -1.0).unwrap();
+    }
+    let solution = shell.solve().unwrap();
+    let centre = mesh.find_nodes(|p| (p.get_x() - 0.5).abs() + (p.get_y() - 0.5).abs() < 1e-9)[0];
+
+    // Element corners at the centre.
+    let corners: Vec<[f64; 2]> = match element_type.linear() {
+        ElementType::Tri3 => vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]],
+        _ => vec![[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]],
+    };
+    let mut moments = [0.0; 8];
+    let mut count = 0.0;
+    for (e, element) in mesh.elements.iter().enumerate() {
+        if let Some(k) = element.corners().iter().position(|&n| n == centre) {
+            let r = shell.resultants(&solution, e, corners[k]).unwrap();
+            for (m, r) in moments.iter_mut().zip(r) {
+                *m += r;
+            }
+            count += 1.0;
+        }
+    }
+    (
+        solution.displacements[centre][2] * plate_rigidity(&material, 0.01),
+        moments.map(|m| m / count),
+    )
+}
+
+// Cantilever strip along x of length 1 and width 0.1, clamped at x = 0 and
+// loaded by a downward tip force of 1.
+fn cantilever(mesh: &Mesh, layup: Layup) -> (Shell<'_>, ShellSolution) {
+    let mut shell = Shell::new(mesh, layup).unwrap();
+    shell
+        .clamp_nodes(&mesh.find_nodes(|p| p.get_x() < 1e-9))
+        .unwrap();
+    let tip = mesh.find_nodes(|p| p.get_x() > 1.0 - 1e-9);
+    for &n in tip.iter() {
+        let y = mesh.nodes[n].get_y();
+        // Consistent loads of a linear edge.
+        let share = if (1e-9..0.1 - 1e-9).contains(&y) {
+            1.0
+        } else {
+            0.5
+        };
+        shell
+            .add_load(n, 2, -share / (tip.len() - 1) as f64)
+            .unwrap();
+    }
+    let solution = shell.solve().unwrap();
+    (shell, solution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layup() {
+        let steel = IsotropicElastic::new(200e9, 0.3).unwrap();
+        let layup = Layup::homogeneous(0.002, &steel).unwrap();
+        let [a, b, d] = layup.abd();
+        let c = 200e9 / (1.0 - 0.09);
+        assert!((a[0][0] - c * 0.002).abs() < 1e-6 * a[0][0]);
+        assert!((a[0][1] - 0.3 * c * 0.002).abs() < 1e-6 * a[0][0]);
+        assert!((d[1][1] - c * 0.002f64.powi(3) / 12.0).abs() < 1e-6 * d[1][1]);
+        assert!(b.iter().flatten().all(|b| b.abs() < 1e-6));
+        let g = 200e9 / 2.6;
+        let s = layup.shear_stiffness();
+        assert!((s[0][0] - 5.0 / 6.0 * g * 0.002).abs() < 1e-6 * s[0][0]);
+        assert_eq!(s[0][1], 0.0);
+
+        // Ply rotated by 90 degrees swaps the principal stiffnesses.
+        let ply = Lamina::new(140e9, 10e9, 0.3, 5e9, 5e9, 3.5e9).unwrap();
+        let (q0, s0) = Layer::new(1.0, ply, 0.0).matrices();
+        let (q90, s90) = Layer::new(1.0, ply, std::f64::consts::FRAC_PI_2).matrices();
+        assert!((q0[0][0] - q90[1][1]).abs() < 1e-6 * q0[0][0]);
+        assert!((q0[0][1] - q90[0][1]).abs() < 1e-6 * q0[0][0]);
+        assert!((s0[0][0] - s90[1][1]).abs() < 1e-6 * s0[0][0]);
+        // At 45 degrees the shear and extension couple.
+        let (q45, _) = Layer::new(1.0, ply, std::f64::consts::FRAC_PI_4).matrices();
+        assert!(q45[0][2].abs() > 1e9 && (q45[0][0] - q45[1][1]).abs() < 1e-3);
+
+        // Cross ply: [0/90] couples bending and extension, [0/90/0] does not.
+        let layer = |angle: f64| Layer::new(0.001, ply, angle);
+        let unsymmetric = Layup::new(vec![layer(0.0), layer(std::f64::consts::FRAC_PI_2)]).unwrap();
+        let b = unsymmetric.abd()[1];
+        assert!(b[0][0] < -1e3 && (b[0][0] + b[1][1]).abs() < 1e-6 * b[0][0].abs());
+        let symmetric = Layup::new(vec![
+            layer(0.0),
+            layer(std::f64::consts::FRAC_PI_2),
+            layer(0.0),
+        ])
+        .unwrap();
+        assert!(symmetric.abd()[1].iter().flatten().all(|b| b.abs() < 1e-6));
+        assert_eq!(
+            symmetric.interfaces(),
+            vec![-0.0015, -0.0005, 0.0005, 0.0015]
+        );
+
+        assert!(Lamina::new(140e9, 10e9, 4.0, 5e9, 5e9, 3.5e9).is_err());
+        assert!(Lamina::new(140e9, 0.0, 0.3, 5e9, 5e9, 3.5e9).is_err());
+        assert!(Layup::new(vec![]).is_err());
+        assert!(Layup::new(vec![Layer::new(0.0, ply, 0.0)]).is_err());
+    }
+
+    #[test]
+    fn test_plate() {
+        // Navier series: w = 0.00406 q a^4 / D and M = 0.0479 q a^2 for
+        // simple supports, w = 0.00126 q a^4 / D when clamped.
+        for (element_type, n, tolerance) in [
+            (ElementType::Quad4, 12, 0.01),
+            (ElementType::Quad9, 6, 0.01),
+            (ElementType::Quad8, 6, 0.025),
+            (ElementType::Tri3, 24, 0.02),
+        ] {
+            let (w, m) = square_plate(element_type, n, false);
+            assert!(
+                (w / -0.00406235 - 1.0).abs() < tolerance,
+                "{:?} {}",
+                element_type,
+                w
+            );
+            // Sagging puts the top face in compression.
+            assert!(
+                (m[3] / -0.0479 - 1.0).abs() < 0.05 && (m[4] / m[3] - 1.0).abs() < 0.05,
+                "{:?} {:?}",
+                element_type,
+                m
+            );
+            let (w, _) = square_plate(element_type, n, true);
+            assert!(
+                (w / -0.00126532 - 1.0).abs() < 2.0 * tolerance,
+                "{:?} {}",
+                element_type,
+                w
+            );
+        }
+    }
+
+    #[test]
+    fn test_stress_recovery() {
+        // Thin strip without Poisson coupling behaves as a beam with
+        // E I = E b t^3 / 12, free of shear locking at L / t = 100.
+        let material = IsotropicElastic::new(1e7, 0.0).unwrap();
+        let (t, b): (f64, f64) = (0.01, 0.1);
+        let inertia = b * t.powi(3) / 12.0;
+        for element_type in [ElementType::Quad4, ElementType::Tri3, ElementType::Quad9] {
+            let mesh = Mesh::rectangle(1.0, b, 20, 2, element_type).unwrap();
+            let (shell, solution) = cantilever(&mesh, Layup::homogeneous(t, &material).unwrap());
+            let tip = mesh.find_nodes(|p| p.get_x() > 1.0 - 1e-9)[0];
+            let expected = -1.0 / (3.0 * 1e7 * inertia);
+            let w = solution.displacements[tip][2];
+            assert!(
+                (w / expected - 1.0).abs() < 0.02,
+                "{:?} {}",
+                element_type,
+                w
+            );
+            let reaction: f64 = solution.reactions.iter().map(|r| r[2]).sum();
+            assert!((reaction - 1.0).abs() < 1e-6);
+
+            if element_type != ElementType::Quad4 {
+                continue;
+            }
+            // Element centre at x = 0.275, in the first row of elements.
+            let element = 5;
+            let x = 0.275;
+            let r = shell.resultants(&solution, element, [0.0, 0.0]).unwrap();
+            assert!((r[3] / ((1.0 - x) / b) - 1.0).abs() < 0.01, "{:?}", r);
+            assert!((r[6] / (-1.0 / b) - 1.0).abs() < 0.01, "{:?}", r);
+            assert!(r[0].abs() < 1e-6 && r[7].abs() < 1e-3, "{:?}", r);
+
+            let profile = shell
+                .stress_profile(&solution, element, [0.0, 0.0])
+                .unwrap();
+            assert_eq!(profile.len(), 3);
+            let sigma = 6.0 * (1.0 - x) / (b * t * t);
+            let (bottom, middle, top) = (profile[0], profile[1], profile[2]);
+            assert_eq!((bottom.z, top.z), (-0.005, 0.005));
+            assert!((top.stress[0] / sigma - 1.0).abs() < 0.01);
+            assert!((bottom.stress[0] / -sigma - 1.0).abs() < 0.01);
+            assert!(middle.stress[0].abs() < 1e-6 * sigma);
+            // Parabolic shear peaking at 3 V / 2 A.
+            assert!((middle.stress[5] / (-1.5 / (b * t)) - 1.0).abs() < 0.01);
+            assert!(top.stress[5].abs() < 1e-9 && bottom.stress[5].abs() < 1e-9);
+            assert_eq!(middle.material_stress, middle.stress);
+        }
+    }
+
+    #[test]
+    fn test_laminate() {
+        // [0/90/0] strip with the fibres of the outer plies along the span.
+        // Without Poisson coupling the tip deflection is P L^3 / (3 b D11).
+        let ply = Lamina::new(100e3, 10e3, 0.0, 5e3, 5e3, 4e3).unwrap();
+        let layer = |angle: f64| Layer::new(0.004, ply, angle);
+        let layup = Layup::new(vec![
+            layer(0.0),
+            layer(std::f64::consts::FRAC_PI_2),
+            layer(0.0),
+        ])
+        .unwrap();
+        let d11 = layup.abd()[2][0][0];
+        let mesh = Mesh::rectangle(1.0, 0.1, 20, 2, ElementType::Quad4).unwrap();
+        let (shell, solution) = cantilever(&mesh, layup);
+        let tip = mesh.find_nodes(|p| p.get_x() > 1.0 - 1e-9)[0];
+        let expected = -1.0 / (3.0 * 0.1 * d11);
+        let w = solution.displacements[tip][2];
+        assert!((w / expected - 1.0).abs() < 0.02, "{} {}", w, expected);
+
+        let profile = shell.stress_profile(&solution, 5, [0.0, 0.0]).unwrap();
+        assert_eq!(profile.len(), 9);
+        // Bending stress jumps with the stiffness at the interfaces, the
+        // transverse shear is continuous and zero on the faces.
+        assert!((profile[2].stress[0] / profile[3].stress[0] - 10.0).abs() < 1e-6);
+        assert!((profile[2].stress[5] - profile[3].stress[5]).abs() < 1e-9);
+        assert!(profile[0].stress[5].abs() < 1e-9 && profile[8].stress[5].abs() < 1e-9);
+        let peak = profile[4].stress[5];
+        assert!(
+            profile
+                .iter()
+                .all(|p| p.stress[5].abs() <= peak.abs() + 1e-9)
+        );
+        // Across the fibres of the middle ply.
+        let middle = profile[5].material_stress;
+        assert!((middle[1] - profile[5].stress[0]).abs() < 1e-9);
+        assert!((middle[5] - profile[5].stress[4]).abs() < 1e-9);
+        assert!((middle[4] + profile[5].stress[5]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_membrane() {
+        // Vertical square plate in the xz plane from a surface mesh, pulled
+        // along x: uniform membrane force with drilling rotations free.
+        let n = 4;
+        let points = (0..=n)
+            .flat_map(|j| {
+                (0..=n).map(move |i| {
+                    Point3d::from_coords(i as f64 / n as f64, 0.0, j as f64 / n as f64)
+                })
+            })
+            .collect();
+        let id = |i: usize, j: usize| j * (n + 1) + i;
+        let mut faces = Vec::new();
+        for j in 0..n {
+            for i in 0..n {
+                faces.push(vec![id(i, j), id(i + 1, j), id(i + 1, j + 1), id(i, j + 1)]);
+            }
+        }
+        // A pentagon split into triangles.
+        faces.push(vec![id(0, n), id(n, n), id(n, 0), id(0, 0), id(0, 1)]);
+        let surface = geom::Mesh::from_points_faces(points, faces.clone());
+        let mesh = Mesh::from_surface_mesh(&surface);
+        assert_eq!(mesh.num_elements(), n * n + 3);
+        assert_eq!(mesh.elements[n * n].element_type, ElementType::Tri3);
+        faces.pop();
+        let mesh = Mesh::from_surface_mesh(&geom::Mesh::from_points_faces(surface.points, faces));
+
+        let material = IsotropicElastic::new(1000.0, 0.25).unwrap();
+        let t = 0.1;
+        let mut shell = Shell::new(&mesh, Layup::homogeneous(t, &material).unwrap()).unwrap();
+        for node in mesh.find_nodes(|p| p.get_x() < 1e-9) {
+            shell.fix(node, 0, 0.0).unwrap();
+        }
+        shell.fix(id(0, 0), 1, 0.0).unwrap();
+        shell.fix(id(0, 0), 2, 0.0).unwrap();
+        shell.fix(id(0, n), 1, 0.0).unwrap();
+        shell.fix(id(n, 0), 1, 0.0).unwrap();
+        for j in 0..=n {
+            let share = if j == 0 || j == n { 0.5 } else { 1.0 };
+            shell.add_load(id(n, j), 0, share / n as f64).unwrap();
+        }
+        let solution = shell.solve().unwrap();
+        let strain = 1.0 / (1000.0 * t);
+        for j in 0..=n {
+            let u = solution.displacements[id(n, j)];
+            assert!((u[0] / strain - 1.0).abs() < 1e-6, "{:?}", u);
+            assert!(u[1].abs() < 1e-9);
+        }
+        let top = solution.displacements[id(0, n)][2];
+        assert!((top / (-0.25 * strain) - 1.0).abs() < 1e-6);
+
+        let layup = Layup::homogeneous(t, &material).unwrap();
+        let tri6 = Mesh::rectangle(1.0, 1.0, 1, 1, ElementType::Tri6).unwrap();
+        assert!(Shell::new(&tri6, layup.clone()).is_err());
+        let solid = Mesh::block(1.0, 1.0, 1.0, 1, 1, 1, ElementType::Hex8).unwrap();
+        assert!(Shell::new(&solid, layup).is_err());
+
+        let axes = shell.local_axes(0, [0.0, 0.0]).unwrap();
+        assert_eq!(axes[0], [1.0, 0.0, 0.0]);
+        assert!((axes[2][1] + 1.0).abs() < 1e-12);
+        for e in 0..mesh.num_elements() {
+            let r = shell.resultants(&solution, e, [0.5, -0.5]).unwrap();
+            assert!((r[0] - 1.0).abs() < 1e-6 && r[1].abs() < 1e-6 && r[3].abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_scordelis_lo() {
+        // Cylindrical roof under self weight on end diaphragms, quarter
+        // model. Reference vertical deflection at the midspan of the free
+        // edge 0.3024.
+        let (radius, length, angle) = (25.0, 50.0, 40f64.to_radians());
+        for (element_type, n) in [(ElementType::Quad4, 16), (ElementType::Quad9, 8)] {
+            let mut mesh = Mesh::rectangle(0.5 * length, angle, n, n, element_type).unwrap();
+            for p in mesh.nodes.iter_mut() {
+                let (x, phi) = (p.get_x(), p.get_y());
+                *p = Point3d::from_coords(x, radius * phi.sin(), radius * phi.cos());
+            }
+            let material = IsotropicElastic::new(4.32e8, 0.0).unwrap();
+            let mut shell =
+                Shell::new(&mesh, Layup::homogeneous(0.25, &material).unwrap()).unwrap();
+            shell.set_body_force([0.0, 0.0, -360.0]);
+            for node in 0..mesh.num_nodes() {
+                let (x, y, _) = mesh.nodes[node].get_coords();
+                if x < 1e-9 {
+                    // Diaphragm.
+                    for d in [1, 2] {
+                        shell.fix(node, d, 0.0).unwrap();
+                    }
+                }
+                if x > 0.5 * length - 1e-9 {
+                    for d in [0, 4, 5] {
+                        shell.fix(node, d, 0.0).unwrap();
+                    }
+                }
+                if y.abs() < 1e-9 {
+                    for d in [1, 3, 5] {
+                        shell.fix(node, d, 0.0).unwrap();
+                    }
+                }
+            }
+            let solution = shell.solve().unwrap();
+            let corner = mesh
+                .find_nodes(|p| p.get_x() > 0.5 * length - 1e-9 && p.get_y() > 16.0)
+                .into_iter()
+                .max_by(|&a, &b| mesh.nodes[a].get_y().total_cmp(&mesh.nodes[b].get_y()))
+                .unwrap();
+            let w = solution.displacements[corner][2];
+            assert!((w / -0.3024 - 1.0).abs() < 0.03, "{:?} {}", element_type, w);
+
+            // The diaphragm carries the weight of the quarter.
+            let weight = 90.0 * radius * angle * 0.5 * length;
+            let reaction: f64 = solution.reactions.iter().map(|r| r[2]).sum();
+            assert!(
+                (reaction / weight - 1.0).abs() < 1e-3,
+                "{} {}",
+                reaction,
+                weight
+            );
+        }
+    }
+}
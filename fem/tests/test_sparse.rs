@@ -0,0 +1,279 @@
+use fem::solver::{bicgstab, gmres, preconditioned_conjugate_gradient};
+use fem::{CooMatrix, CsrMatrix, LinearSolver, Ordering, Preconditioner, SparseLdlt};
+
+fn random(n: usize, seed: u64) -> Vec<f64> {
+    let mut state = seed;
+    (0..n)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        })
+        .collect()
+}
+
+// Five point Laplacian on an m x m grid, plus a convection term that makes
+// it nonsymmetric.
+fn grid(m: usize, convection: f64) -> CsrMatrix {
+    let n = m * m;
+    let mut a = CooMatrix::new(n, n);
+    for j in 0..m {
+        for i in 0..m {
+            let k = j * m + i;
+            a.push(k, k, 4.0).unwrap();
+            if i > 0 {
+                a.push(k, k - 1, -1.0 - convection).unwrap();
+            }
+            if i + 1 < m {
+                a.push(k, k + 1, -1.0 + convection).unwrap();
+            }
+            if j > 0 {
+                a.push(k, k - m, -1.0).unwrap();
+            }
+            if j + 1 < m {
+                a.push(k, k + m, -1.0).unwrap();
+            }
+        }
+    }
+    a.to_csr()
+}
+
+fn residual(a: &CsrMatrix, x: &[f64], b: &[f64]) -> f64 {
+    a.multiply(x)
+        .iter()
+        .zip(b.iter())
+        .map(|(ax, b)| (ax - b).powi(2))
+        .sum::<f64>()
+        .sqrt()
+        / b.iter().map(|b| b * b).sum::<f64>().sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storage() {
+        let mut coo = CooMatrix::new(3, 4);
+        coo.push(0, 1, 2.0).unwrap();
+        coo.push(2, 3, 1.0).unwrap();
+        coo.push(0, 1, 0.5).unwrap();
+        coo.push_block(&[1, 2], &[0, 3], &[vec![1.0, 0.0], vec![3.0, 4.0]])
+            .unwrap();
+        assert_eq!(coo.len(), 6);
+        assert!(coo.push(3, 0, 1.0).is_err());
+        assert!(coo.push_block(&[0], &[0, 1], &[vec![1.0]]).is_err());
+
+        let a = coo.to_csr();
+        assert_eq!(a.nnz(), 4);
+        assert_eq!(a.row_offsets, vec![0, 1, 2, 4]);
+        assert_eq!(a.get(0, 1), 2.5);
+        assert_eq!(a.get(2, 3), 5.0);
+        assert_eq!(a.get(1, 1), 0.0);
+        assert_eq!(a.multiply(&[1.0, 1.0, 1.0, 1.0]), vec![2.5, 1.0, 8.0]);
+        assert_eq!(CsrMatrix::from_dense(&a.to_dense()), a);
+        let triplets = [
+            (0, 1, 2.0),
+            (2, 3, 1.0),
+            (0, 1, 0.5),
+            (1, 0, 1.0),
+            (2, 0, 3.0),
+            (2, 3, 4.0),
+        ];
+        assert_eq!(CsrMatrix::from_triplets(3, 4, &triplets).unwrap(), a);
+        assert!(CsrMatrix::from_triplets(3, 4, &[(3, 0, 1.0)]).is_err());
+        assert!(CsrMatrix::from_triplets(3, 4, &[(0, 4, 1.0)]).is_err());
+        let t = a.transpose();
+        assert_eq!((t.nrows, t.ncols), (4, 3));
+        assert_eq!(t.get(3, 2), 5.0);
+        assert_eq!(t.transpose(), a);
+
+        let g = grid(4, 0.0);
+        assert!(g.is_symmetric(1e-14));
+        assert!(!grid(4, 0.2).is_symmetric(1e-14));
+        assert_eq!(g.bandwidth(), 4);
+        let p: Vec<usize> = (0..16).rev().collect();
+        let permuted = g.permute(&p).unwrap();
+        assert_eq!(permuted.get(0, 1), g.get(15, 14));
+        assert!(g.permute(&[0; 16]).is_err());
+        assert_eq!(
+            CsrMatrix::identity(3).multiply(&[1.0, 2.0, 3.0]),
+            vec![1.0, 2.0, 3.0]
+        );
+    }
+
+    #[test]
+    fn test_orderings() {
+        // Randomly numbered grid.
+        let m = 24;
+        let g = grid(m, 0.0);
+        let mut shuffle: Vec<usize> = (0..m * m).collect();
+        let r = random(m * m, 3);
+        shuffle.sort_by(|&a, &b| r[a].total_cmp(&r[b]));
+        let a = g.permute(&shuffle).unwrap();
+
+        for ordering in [
+            Ordering::Natural,
+            Ordering::ReverseCuthillMcKee,
+            Ordering::ApproximateMinimumDegree,
+        ] {
+            let p = ordering.permutation(&a).unwrap();
+            let mut sorted = p.clone();
+            sorted.sort_unstable();
+            assert_eq!(sorted, (0..m * m).collect::<Vec<usize>>());
+        }
+        let rcm = a
+            .permute(&Ordering::ReverseCuthillMcKee.permutation(&a).unwrap())
+            .unwrap();
+        assert!(rcm.bandwidth() <= m + 1, "{}", rcm.bandwidth());
+
+        // Banded fill grows like m^3, minimum degree fill far slower.
+        let banded = SparseLdlt::new(&g, Ordering::Natural).unwrap().nnz();
+        let amd = SparseLdlt::new(&a, Ordering::ApproximateMinimumDegree)
+            .unwrap()
+            .nnz();
+        let natural = SparseLdlt::new(&a, Ordering::Natural).unwrap().nnz();
+        assert!(
+            amd < banded && amd * 3 < natural,
+            "{} {} {}",
+            amd,
+            banded,
+            natural
+        );
+
+        // Disconnected components are all ordered.
+        let mut coo = CooMatrix::new(4, 4);
+        for (i, j) in [(0, 2), (1, 3)] {
+            coo.push(i, j, 1.0).unwrap();
+        }
+        let p = Ordering::ReverseCuthillMcKee
+            .permutation(&coo.to_csr())
+            .unwrap();
+        assert_eq!(p.len(), 4);
+    }
+
+    #[test]
+    fn test_ldlt() {
+        let a = grid(10, 0.0);
+        let b = random(100, 5);
+        for ordering in [
+            Ordering::Natural,
+            Ordering::ReverseCuthillMcKee,
+            Ordering::ApproximateMinimumDegree,
+        ] {
+            let f = SparseLdlt::cholesky(&a, ordering).unwrap();
+            assert!(f.is_positive_definite());
+            let x = f.solve(&b).unwrap();
+            assert!(residual(&a, &x, &b) < 1e-13);
+
+            // L D L^T reproduces the permuted matrix.
+            let l = f.lower().to_dense();
+            let pa = a.permute(&f.permutation).unwrap();
+            for i in 0..100 {
+                for j in 0..100 {
+                    let ldl: f64 = (0..100).map(|k| l[i][k] * f.diagonal[k] * l[j][k]).sum();
+                    assert!((ldl - pa.get(i, j)).abs() < 1e-12);
+                }
+            }
+        }
+
+        // Indefinite: shift the spectrum of the Laplacian, whose smallest
+        // eigenvalues are 4 - 4 cos(pi k / 11) summed over directions.
+        let mut coo = CooMatrix::new(100, 100);
+        for i in 0..100 {
+            for (&j, &v) in a.row(i).0.iter().zip(a.row(i).1.iter()) {
+                coo.push(i, j, v).unwrap();
+            }
+            coo.push(i, i, -1.0).unwrap();
+        }
+        let shifted = coo.to_csr();
+        let f = SparseLdlt::new(&shifted, Ordering::default()).unwrap();
+        let x = f.solve(&b).unwrap();
+        assert!(residual(&shifted, &x, &b) < 1e-12);
+        // Eigenvalues 4 - 2 cos(a) - 2 cos(b) below 1.
+        let h = std::f64::consts::PI / 11.0;
+        let below = (1..=10)
+            .flat_map(|p| (1..=10).map(move |q| (p, q)))
+            .filter(|&(p, q)| 4.0 - 2.0 * (h * p as f64).cos() - 2.0 * (h * q as f64).cos() < 1.0)
+            .count();
+        assert_eq!(f.inertia(), (100 - below, below));
+        assert!(SparseLdlt::cholesky(&shifted, Ordering::default()).is_err());
+
+        let mut singular = CooMatrix::new(2, 2);
+        singular
+            .push_block(&[0, 1], &[0, 1], &[vec![1.0, 1.0], vec![1.0, 1.0]])
+            .unwrap();
+        assert_eq!(
+            SparseLdlt::new(&singular.to_csr(), Ordering::Natural).err(),
+            Some("Matrix is singular")
+        );
+    }
+
+    #[test]
+    fn test_iterative() {
+        let a = grid(20, 0.0);
+        let b = random(400, 7);
+        let mut counts = Vec::new();
+        for preconditioner in [
+            Preconditioner::Identity,
+            Preconditioner::Jacobi,
+            Preconditioner::Ssor { omega: 1.5 },
+            Preconditioner::IncompleteCholesky,
+        ] {
+            let (x, iterations) =
+                preconditioned_conjugate_gradient(&a, &b, preconditioner, 1e-10, 1000).unwrap();
+            assert!(residual(&a, &x, &b) < 1e-10);
+            counts.push(iterations);
+        }
+        assert!(
+            counts[2] < counts[1] && counts[3] < counts[1],
+            "{:?}",
+            counts
+        );
+        assert!(
+            preconditioned_conjugate_gradient(&a, &b, Preconditioner::Jacobi, 1e-10, 5).is_err()
+        );
+        assert!(
+            preconditioned_conjugate_gradient(
+                &a,
+                &b,
+                Preconditioner::Ssor { omega: 2.0 },
+                1e-10,
+                5
+            )
+            .is_err()
+        );
+
+        let solver = LinearSolver::ConjugateGradient {
+            preconditioner: Preconditioner::IncompleteCholesky,
+            tolerance: 1e-12,
+            max_iterations: 500,
+        };
+        let x = solver.solve(&a, &b).unwrap();
+        let y = LinearSolver::default().solve(&a, &b).unwrap();
+        assert!(x.iter().zip(y.iter()).all(|(x, y)| (x - y).abs() < 1e-9));
+
+        let n = grid(20, 0.4);
+        for preconditioner in [
+            Preconditioner::Identity,
+            Preconditioner::Jacobi,
+            Preconditioner::IncompleteLu,
+            Preconditioner::Ssor { omega: 1.0 },
+        ] {
+            let (x, _) = gmres(&n, &b, preconditioner, 30, 1e-10, 2000).unwrap();
+            assert!(residual(&n, &x, &b) < 1e-10);
+            let (x, _) = bicgstab(&n, &b, preconditioner, 1e-10, 2000).unwrap();
+            assert!(residual(&n, &x, &b) < 1e-10);
+        }
+        let (_, plain) = gmres(&n, &b, Preconditioner::Identity, 30, 1e-10, 2000).unwrap();
+        let (_, ilu) = gmres(&n, &b, Preconditioner::IncompleteLu, 30, 1e-10, 2000).unwrap();
+        assert!(ilu < plain);
+        // Unrestarted GMRES converges in at most n steps in exact arithmetic.
+        let small = grid(3, 0.3);
+        let (x, iterations) =
+            gmres(&small, &b[..9], Preconditioner::Identity, 9, 1e-12, 9).unwrap();
+        assert!(iterations <= 9 && residual(&small, &x, &b[..9]) < 1e-12);
+        assert!(gmres(&n, &b, Preconditioner::Identity, 0, 1e-10, 10).is_err());
+    }
+}
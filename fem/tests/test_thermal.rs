@@ -0,0 +1,346 @@
+use fem::thermal::STEFAN_BOLTZMANN;
+use fem::{
+    Analysis, ElementType, HeatConduction, IsotropicElastic, LinearStatic, Mesh, ThermalMaterial,
+    TimeStepping,
+};
+use std::f64::consts::PI;
+
+fn copper() -> ThermalMaterial {
+    ThermalMaterial::new(400.0, 8900.0, 385.0).unwrap()
+}
+
+// Strip [0, length] x [0, height] with the temperature varying along x.
+fn strip(element_type: ElementType, nx: usize) -> Mesh {
+    Mesh::rectangle(2.0, 0.5, nx, 2, element_type).unwrap()
+}
+
+fn nodes_at_x(mesh: &Mesh, x: f64) -> Vec<usize> {
+    mesh.find_nodes(|p| (p.get_x() - x).abs() < 1e-12)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_material() {
+        let material = copper();
+        assert!((material.capacity() - 8900.0 * 385.0).abs() < 1e-6);
+        assert!((material.diffusivity() - 400.0 / (8900.0 * 385.0)).abs() < 1e-15);
+        assert!(ThermalMaterial::new(0.0, 1.0, 1.0).is_err());
+        assert!(ThermalMaterial::new(1.0, -1.0, 1.0).is_err());
+
+        let mesh = strip(ElementType::Quad4, 4);
+        assert!(HeatConduction::new(&mesh, Analysis::Solid, copper()).is_err());
+        let mut problem = HeatConduction::new(&mesh, Analysis::PlaneStrain, copper()).unwrap();
+        assert!(problem.fix(mesh.num_nodes(), 0.0).is_err());
+        assert!(problem.set_generation(&[mesh.num_elements()], 1.0).is_err());
+        let facet = mesh.boundary_facets()[0].clone();
+        assert!(problem.add_convection(&facet, -1.0, 0.0).is_err());
+        assert!(problem.add_radiation(&facet, 1.5, 0.0).is_err());
+        assert!(
+            problem
+                .solve_transient(&[0.0], 1.0, &TimeStepping::fixed(1.0, 0.1))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_conduction() {
+        let (length, height) = (2.0, 0.5);
+        let k = copper().conductivity;
+        for element_type in [
+            ElementType::Quad4,
+            ElementType::Tri3,
+            ElementType::Quad8,
+            ElementType::Tri6,
+        ] {
+            let mesh = strip(element_type, 6);
+            let (left, right) = (nodes_at_x(&mesh, 0.0), nodes_at_x(&mesh, length));
+
+            // Linear profile between fixed ends.
+            let mut problem = HeatConduction::new(&mesh, Analysis::PlaneStrain, copper()).unwrap();
+            problem.fix_nodes(&left, 100.0).unwrap();
+            problem.fix_nodes(&right, 20.0).unwrap();
+            let solution = problem.solve_steady().unwrap();
+            for (p, t) in mesh.nodes.iter().zip(solution.temperatures.iter()) {
+                assert!((t - (100.0 - 40.0 * p.get_x())).abs() < 1e-9);
+            }
+            for flux in solution.fluxes.iter() {
+                assert!((flux[0] - 40.0 * k).abs() < 1e-7);
+                assert!(flux[1].abs() < 1e-7);
+            }
+            let inflow: f64 = left.iter().map(|&n| solution.heat_flows[n]).sum();
+            let outflow: f64 = right.iter().map(|&n| solution.heat_flows[n]).sum();
+            assert!((inflow - 40.0 * k * height).abs() < 1e-6);
+            assert!((inflow + outflow).abs() < 1e-6);
+
+            // Parabolic profile with uniform generation, exact at the nodes of
+            // quadratic elements.
+            let q = 1e5;
+            let elements: Vec<usize> = (0..mesh.num_elements()).collect();
+            problem.set_generation(&elements, q).unwrap();
+            problem.fix_nodes(&left, 0.0).unwrap();
+            problem.fix_nodes(&right, 0.0).unwrap();
+            let solution = problem.solve_steady().unwrap();
+            let tolerance = if element_type.is_quadratic() {
+                1e-9
+            } else {
+                1e-2
+            };
+            let peak = q * length * length / (8.0 * k);
+            for (p, t) in mesh.nodes.iter().zip(solution.temperatures.iter()) {
+                let x = p.get_x();
+                assert!((t - q * x * (length - x) / (2.0 * k)).abs() < tolerance * peak);
+            }
+            let total: f64 = solution.heat_flows.iter().sum();
+            assert!((total + q * length * height).abs() < 1e-6 * q);
+        }
+    }
+
+    #[test]
+    fn test_convection() {
+        let (length, height) = (2.0, 0.5);
+        let (h, ambient) = (500.0, 20.0);
+        let k = copper().conductivity;
+        for element_type in [ElementType::Quad4, ElementType::Quad9] {
+            let mesh = strip(element_type, 4);
+            let left = nodes_at_x(&mesh, 0.0);
+            let mut problem =
+                HeatConduction::new(&mesh, Analysis::PlaneStress { thickness: 0.1 }, copper())
+                    .unwrap();
+            problem.fix_nodes(&left, 100.0).unwrap();
+            for facet in mesh.find_boundary_facets(|p| p.get_x() > length - 1e-12) {
+                problem.add_convection(&facet, h, ambient).unwrap();
+            }
+            let solution = problem.solve_steady().unwrap();
+            let end = (k / length * 100.0 + h * ambient) / (k / length + h);
+            for (p, t) in mesh.nodes.iter().zip(solution.temperatures.iter()) {
+                let expected = 100.0 + (end - 100.0) * p.get_x() / length;
+                assert!((t - expected).abs() < 1e-9);
+            }
+            // Heat entering at the fixed end leaves by convection.
+            let inflow: f64 = left.iter().map(|&n| solution.heat_flows[n]).sum();
+            assert!((inflow - h * (end - ambient) * height * 0.1).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_radiation() {
+        let (length, hot, ambient, emissivity): (f64, f64, f64, f64) = (2.0, 1000.0, 300.0, 0.8);
+        let material = ThermalMaterial::new(50.0, 1.0, 1.0).unwrap();
+        // End temperature balancing conduction and radiation, by bisection.
+        let balance = |t: f64| {
+            material.conductivity * (hot - t) / length
+                - emissivity * STEFAN_BOLTZMANN * (t.powi(4) - ambient.powi(4))
+        };
+        let (mut low, mut high) = (ambient, hot);
+        for _ in 0..100 {
+            let mid = 0.5 * (low + high);
+            if balance(mid) > 0.0 {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        let end = 0.5 * (low + high);
+        assert!(end > ambient + 100.0 && end < hot - 100.0);
+
+        for element_type in [ElementType::Quad4, ElementType::Tri6] {
+            let mesh = strip(element_type, 4);
+            let mut problem = HeatConduction::new(&mesh, Analysis::PlaneStrain, material).unwrap();
+            problem.fix_nodes(&nodes_at_x(&mesh, 0.0), hot).unwrap();
+            for facet in mesh.find_boundary_facets(|p| p.get_x() > length - 1e-12) {
+                problem.add_radiation(&facet, emissivity, ambient).unwrap();
+            }
+            let solution = problem.solve_steady().unwrap();
+            for (p, t) in mesh.nodes.iter().zip(solution.temperatures.iter()) {
+                let expected = hot + (end - hot) * p.get_x() / length;
+                assert!((t - expected).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_axisymmetric() {
+        // Wall of a pipe between radii a and b with fixed surface temperatures.
+        let (a, b, height) = (1.0, 2.0, 0.5);
+        let mut mesh = Mesh::rectangle(b - a, height, 8, 1, ElementType::Quad8).unwrap();
+        for p in mesh.nodes.iter_mut() {
+            p.set_x(p.get_x() + a);
+        }
+        let k = copper().conductivity;
+        let mut problem = HeatConduction::new(&mesh, Analysis::Axisymmetric, copper()).unwrap();
+        let inner = nodes_at_x(&mesh, a);
+        problem.fix_nodes(&inner, 200.0).unwrap();
+        problem.fix_nodes(&nodes_at_x(&mesh, b), 50.0).unwrap();
+        let solution = problem.solve_steady().unwrap();
+        let logarithm = (b / a).ln();
+        for (p, t) in mesh.nodes.iter().zip(solution.temperatures.iter()) {
+            let expected = 200.0 - 150.0 * (p.get_x() / a).ln() / logarithm;
+            assert!((t - expected).abs() < 1e-3);
+        }
+        let inflow: f64 = inner.iter().map(|&n| solution.heat_flows[n]).sum();
+        let expected = 2.0 * PI * k * height * 150.0 / logarithm;
+        assert!((inflow - expected).abs() < 1e-4 * expected);
+    }
+
+    #[test]
+    fn test_transient() {
+        // Slab of unit thickness at zero temperature with both faces raised to
+        // one at time zero.
+        let material = ThermalMaterial::new(1.0, 1.0, 1.0).unwrap();
+        let mesh = Mesh::rectangle(1.0, 0.1, 20, 1, ElementType::Quad4).unwrap();
+        let end = 0.05;
+        let exact = |x: f64, t: f64| {
+            1.0 - (0..100)
+                .map(|i| {
+                    let n = (2 * i + 1) as f64;
+                    4.0 / (n * PI) * (n * PI * x).sin() * (-(n * PI).powi(2) * t).exp()
+                })
+                .sum::<f64>()
+        };
+        let middle = mesh.find_nodes(|p| (p.get_x() - 0.5).abs() < 1e-12 && p.get_y() < 1e-12)[0];
+
+        let mut problem = HeatConduction::new(&mesh, Analysis::PlaneStrain, material).unwrap();
+        let faces: Vec<usize> = mesh.find_nodes(|p| p.get_x() < 1e-12 || p.get_x() > 1.0 - 1e-12);
+        problem.fix_nodes(&faces, 1.0).unwrap();
+        let initial = vec![0.0; mesh.num_nodes()];
+
+        // Adaptive Crank-Nicolson shrinks the first steps after the jump and
+        // grows them as the solution smooths.
+        let stepping = TimeStepping::adaptive(0.5, 1e-3, 1e-4);
+        let solution = problem.solve_transient(&initial, end, &stepping).unwrap();
+        assert!((solution.times.last().unwrap() - end).abs() < 1e-12);
+        assert_eq!(solution.times.len(), solution.temperatures.len());
+        let steps: Vec<f64> = solution.times.windows(2).map(|w| w[1] - w[0]).collect();
+        assert!(steps[steps.len() - 2] > 2.0 * steps[0]);
+        let last = solution.temperatures.last().unwrap();
+        assert!((last[middle] - exact(0.5, end)).abs() < 5e-3);
+        for (&time, temperatures) in solution
+            .times
+            .iter()
+            .zip(solution.temperatures.iter())
+            .skip(3)
+        {
+            assert!((temperatures[middle] - exact(0.5, time)).abs() < 1e-2);
+        }
+
+        // Lumped backward Euler stays within the bounds of the data.
+        problem.set_lumped_capacity(true);
+        let solution = problem
+            .solve_transient(&initial, end, &TimeStepping::fixed(1.0, 1e-3))
+            .unwrap();
+        assert_eq!(solution.times.len(), 51);
+        assert_eq!(solution.rejected, 0);
+        for temperatures in solution.temperatures.iter() {
+            assert!(
+                temperatures
+                    .iter()
+                    .all(|t| (-1e-12..=1.0 + 1e-12).contains(t))
+            );
+        }
+        let last = solution.temperatures.last().unwrap();
+        assert!((last[middle] - exact(0.5, end)).abs() < 1e-2);
+
+        // A long run approaches the steady state.
+        let solution = problem
+            .solve_transient(&initial, 2.0, &TimeStepping::adaptive(1.0, 1e-3, 1e-3))
+            .unwrap();
+        let last = solution.temperatures.last().unwrap();
+        assert!(last.iter().all(|t| (t - 1.0).abs() < 1e-3));
+    }
+
+    #[test]
+    fn test_thermal_stress() {
+        let material = IsotropicElastic::new(200.0, 0.3).unwrap();
+        let (e, nu) = (material.young, material.poisson);
+        let (alpha, rise) = (1e-5, 50.0);
+
+        // Uniform heating of a plate on rollers expands it freely in plane
+        // stress and builds up axial stress in plane strain.
+        let mesh = Mesh::rectangle(2.0, 1.0, 4, 2, ElementType::Quad8).unwrap();
+        for analysis in [
+            Analysis::PlaneStress { thickness: 0.1 },
+            Analysis::PlaneStrain,
+        ] {
+            let mut problem = LinearStatic::new(&mesh, analysis, material).unwrap();
+            for n in mesh.find_nodes(|p| p.get_x() < 1e-12) {
+                problem.fix(n, 0, 0.0).unwrap();
+            }
+            for n in mesh.find_nodes(|p| p.get_y() < 1e-12) {
+                problem.fix(n, 1, 0.0).unwrap();
+            }
+            let elements: Vec<usize> = (0..mesh.num_elements()).collect();
+            problem.set_expansion(&elements, alpha).unwrap();
+            assert!(problem.set_temperatures(&[20.0], 20.0).is_err());
+            problem
+                .set_temperatures(&vec![20.0 + rise; mesh.num_nodes()], 20.0)
+                .unwrap();
+            let solution = problem.solve().unwrap();
+            let (in_plane, zz) = match analysis {
+                Analysis::PlaneStrain => ((1.0 + nu) * alpha * rise, 0.0),
+                _ => (alpha * rise, alpha * rise),
+            };
+            for (p, u) in mesh.nodes.iter().zip(solution.displacements.iter()) {
+                assert!((u[0] - in_plane * p.get_x()).abs() < 1e-12);
+                assert!((u[1] - in_plane * p.get_y()).abs() < 1e-12);
+            }
+            for (strain, stress) in solution.strains.iter().zip(solution.stresses.iter()) {
+                assert!((strain[0] - in_plane).abs() < 1e-12);
+                assert!((strain[2] - zz).abs() < 1e-12);
+                assert!(stress[0].abs() < 1e-9 && stress[1].abs() < 1e-9);
+                let expected = if zz == 0.0 { -e * alpha * rise } else { 0.0 };
+                assert!((stress[2] - expected).abs() < 1e-9);
+            }
+            assert!(solution.reactions.iter().all(|r| r[0].abs() < 1e-9));
+        }
+
+        // A fully restrained block takes hydrostatic compression.
+        let mesh = Mesh::block(1.0, 1.0, 1.0, 2, 2, 2, ElementType::Hex8).unwrap();
+        let mut problem = LinearStatic::new(&mesh, Analysis::Solid, material).unwrap();
+        let all: Vec<usize> = (0..mesh.num_nodes()).collect();
+        problem.fix_nodes(&all).unwrap();
+        problem
+            .set_expansion(&[0, 1, 2, 3, 4, 5, 6, 7], alpha)
+            .unwrap();
+        problem
+            .set_temperatures(&vec![rise; mesh.num_nodes()], 0.0)
+            .unwrap();
+        let solution = problem.solve().unwrap();
+        let pressure = e * alpha * rise / (1.0 - 2.0 * nu);
+        for stress in solution.stresses.iter() {
+            for c in 0..3 {
+                assert!((stress[c] + pressure).abs() < 1e-9);
+                assert!(stress[c + 3].abs() < 1e-9);
+            }
+        }
+
+        // A linear temperature field from a heat conduction solve bends nothing
+        // in a free plate: the thermal strains are compatible.
+        let mesh = Mesh::rectangle(2.0, 1.0, 4, 2, ElementType::Quad9).unwrap();
+        let mut heat = HeatConduction::new(&mesh, Analysis::PlaneStrain, copper()).unwrap();
+        heat.fix_nodes(&nodes_at_x(&mesh, 0.0), 100.0).unwrap();
+        heat.fix_nodes(&nodes_at_x(&mesh, 2.0), 20.0).unwrap();
+        let temperatures = heat.solve_steady().unwrap().temperatures;
+        let mut problem =
+            LinearStatic::new(&mesh, Analysis::PlaneStress { thickness: 0.1 }, material).unwrap();
+        let origin = mesh.find_nodes(|p| p.get_x() < 1e-12 && p.get_y() < 1e-12)[0];
+        let corner = mesh.find_nodes(|p| p.get_x() > 2.0 - 1e-12 && p.get_y() < 1e-12)[0];
+        problem.fix(origin, 0, 0.0).unwrap();
+        problem.fix(origin, 1, 0.0).unwrap();
+        problem.fix(corner, 1, 0.0).unwrap();
+        let elements: Vec<usize> = (0..mesh.num_elements()).collect();
+        problem.set_expansion(&elements, alpha).unwrap();
+        problem.set_temperatures(&temperatures, 20.0).unwrap();
+        let solution = problem.solve().unwrap();
+        for stress in solution.stresses.iter() {
+            assert!(stress.iter().all(|s| s.abs() < 1e-9));
+        }
+        for (p, strain) in mesh.nodes.iter().zip(solution.strains.iter()) {
+            let expected = alpha * (80.0 - 40.0 * p.get_x());
+            assert!((strain[0] - expected).abs() < 1e-11);
+            assert!((strain[1] - expected).abs() < 1e-11);
+        }
+    }
+}
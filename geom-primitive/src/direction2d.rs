@@ -1,6 +1,7 @@
 use crate::xy::XY;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
 pub struct Direction2d<T = f64> {
-    xy: XY<T>,
+    pub(crate) xy: XY<T>,
 }
\ No newline at end of file
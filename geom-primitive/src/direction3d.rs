@@ -1,6 +1,7 @@
 use crate::xyz::XYZ;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
 pub struct Direction3d<T = f64> {
-    xyz: XYZ<T>,
+    pub(crate) xyz: XYZ<T>,
 }
\ No newline at end of file
@@ -11,6 +11,8 @@ pub mod direction3d;
 pub mod matrix2d;
 pub mod matrix3d;
 pub mod trsf2d;
+#[cfg(feature = "serde")]
+mod serialization;
 
 pub use trsfform::TrsfForm;
 pub use xy::XY;
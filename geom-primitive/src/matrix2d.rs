@@ -1,6 +1,7 @@
 use crate::xy::XY;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Matrix2d {
     m: [[f64; 2]; 2],
 }
@@ -2,6 +2,7 @@ use crate::fconst::FloatWithConst;
 use crate::xy::XY;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
 pub struct Point2d<T = f64> {
     xy: XY<T>,
 }
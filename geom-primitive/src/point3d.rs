@@ -2,6 +2,7 @@ use crate::fconst::FloatWithConst;
 use crate::xyz::XYZ;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
 pub struct Point3d<T = f64> {
     xyz: XYZ<T>,
 }
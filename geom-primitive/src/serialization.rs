@@ -0,0 +1,83 @@
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+
+use crate::fconst::FloatWithConst;
+use crate::{Direction2d, Direction3d, Matrix2d, Trsf2d, TrsfForm, XY, XYZ};
+
+impl<'de, T> Deserialize<'de> for Direction2d<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let xy = XY::<T>::deserialize(deserializer)?;
+        let modulus = xy.modulus();
+        if !modulus.is_finite() || modulus <= T::zero() {
+            return Err(D::Error::custom(
+                "direction must be a finite non-zero vector",
+            ));
+        }
+        Ok(Direction2d {
+            xy: XY::from_coordinates(xy.x / modulus, xy.y / modulus),
+        })
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Direction3d<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let xyz = XYZ::<T>::deserialize(deserializer)?;
+        let modulus = xyz.modulus();
+        if !modulus.is_finite() || modulus <= T::zero() {
+            return Err(D::Error::custom(
+                "direction must be a finite non-zero vector",
+            ));
+        }
+        Ok(Direction3d {
+            xyz: XYZ::from_coordinates(xyz.x / modulus, xyz.y / modulus, xyz.z / modulus),
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Trsf2d {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "Trsf2d")]
+        struct Raw {
+            scalefac: f64,
+            form: TrsfForm,
+            matrix: Matrix2d,
+            trans: XY,
+        }
+        let raw = Raw::deserialize(deserializer)?;
+        let [[a, b], [c, d]] = raw.matrix.matrix();
+        let tolerance = f64::EPSILON.sqrt();
+        let orthonormal = (a * a + c * c - 1.0).abs() <= tolerance
+            && (b * b + d * d - 1.0).abs() <= tolerance
+            && (a * b + c * d).abs() <= tolerance;
+        // Trsf2d::new leaves the matrix of the identity zeroed.
+        let unset = raw.form == TrsfForm::Identity && [a, b, c, d] == [0.0; 4];
+        if !(orthonormal || unset) {
+            return Err(D::Error::custom(
+                "transformation matrix must be orthonormal",
+            ));
+        }
+        if !(raw.scalefac.is_finite() && raw.scalefac != 0.0) {
+            return Err(D::Error::custom(
+                "transformation scale must be finite and non-zero",
+            ));
+        }
+        if !(raw.trans.x.is_finite() && raw.trans.y.is_finite()) {
+            return Err(D::Error::custom(
+                "transformation translation must be finite",
+            ));
+        }
+        let mut trsf = Trsf2d::new();
+        trsf.set_scalefac(raw.scalefac);
+        trsf.set_form(raw.form);
+        trsf.set_matrix(raw.matrix);
+        trsf.set_trans(raw.trans);
+        Ok(trsf)
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{Matrix2d, Point2d, TrsfForm, Vector2d, XY};
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Trsf2d {
     scalefac: f64,
     form: TrsfForm,
@@ -3,6 +3,7 @@ use crate::xy::XY;
 // use num_traits::Float;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
 pub struct Vector2d<T = f64> {
     xy: XY<T>,
 }
@@ -313,7 +314,10 @@ impl<T> IndexMut<usize> for Vector2d<T> {
 
 use std::ops::Neg;
 
-impl<T> Neg for &Vector2d<T> {
+impl<T> Neg for &Vector2d<T>
+where
+    T: Copy + crate::fconst::FloatWithConst,
+{
     type Output = Vector2d<T>;
 
     fn neg(self) -> Self::Output {
@@ -324,20 +328,29 @@ impl<T> Neg for &Vector2d<T> {
 use std::ops::{Add, AddAssign};
 
 // Vector2d += &Vector2d
-impl<T> AddAssign<&Vector2d<T>> for Vector2d<T> {
+impl<T> AddAssign<&Vector2d<T>> for Vector2d<T>
+where
+    T: Copy + crate::fconst::FloatWithConst,
+{
     fn add_assign(&mut self, other: &Vector2d<T>) {
         self.xy += &other.xy;
     }
 }
 
 // Vector2d += T
-impl<T> AddAssign<T> for Vector2d<T> {
+impl<T> AddAssign<T> for Vector2d<T>
+where
+    T: Copy + crate::fconst::FloatWithConst,
+{
     fn add_assign(&mut self, other: T) {
         self.xy += other;
     }
 }
 
-impl<T> Add<&Vector2d<T>> for &Vector2d<T> {
+impl<T> Add<&Vector2d<T>> for &Vector2d<T>
+where
+    T: Copy + crate::fconst::FloatWithConst,
+{
     type Output = Vector2d<T>;
 
     fn add(self, other: &Vector2d<T>) -> Self::Output {
@@ -347,7 +360,10 @@ impl<T> Add<&Vector2d<T>> for &Vector2d<T> {
     }
 }
 
-impl<T> Add<T> for &Vector2d<T> {
+impl<T> Add<T> for &Vector2d<T>
+where
+    T: Copy + crate::fconst::FloatWithConst,
+{
     type Output = Vector2d<T>;
 
     fn add(self, other: T) -> Self::Output {
@@ -359,7 +375,10 @@ impl<T> Add<T> for &Vector2d<T> {
 
 use std::ops::{Sub, SubAssign};
 
-impl<T> SubAssign<&Vector2d<T>> for Vector2d<T> {
+impl<T> SubAssign<&Vector2d<T>> for Vector2d<T>
+where
+    T: Copy + crate::fconst::FloatWithConst,
+{
     fn sub_assign(&mut self, other: &Vector2d<T>) {
         self.xy -= &other.xy;
     }
@@ -391,8 +410,12 @@ impl Sub<f64> for &Vector2d {
     }
 }
 
+use std::ops::{Div, DivAssign};
 
-impl<T> DivAssign<T> for Vector2d<T> {
+impl<T> DivAssign<T> for Vector2d<T>
+where
+    T: Copy + crate::fconst::FloatWithConst,
+{
     fn div_assign(&mut self, other: T) {
         self.xy /= other;
     }
@@ -408,6 +431,8 @@ impl Div<f64> for &Vector2d {
     }
 }
 
+use std::ops::{Mul, MulAssign};
+
 impl MulAssign<&Vector2d> for Vector2d {
     fn mul_assign(&mut self, other: &Vector2d) {
         self.xy.dot(&other.xy);
@@ -1,6 +1,7 @@
 use crate::xyz::XYZ;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
 pub struct Vector3d<T = f64> {
     xy: XYZ<T>,
 }
\ No newline at end of file
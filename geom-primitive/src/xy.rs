@@ -1,6 +1,7 @@
 use crate::fconst::FloatWithConst;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct XY<T = f64> {
     pub x: T,
     pub y: T,
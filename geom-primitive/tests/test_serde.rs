@@ -0,0 +1,105 @@
+#![cfg(feature = "serde")]
+
+use geom_primitive::{
+    Direction2d, Direction3d, Matrix2d, Point2d, Point3d, Trsf2d, Vector2d, Vector3d, XY, XYZ,
+};
+
+fn round_trip<T>(value: &T) -> T
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let json = serde_json::to_string(value).unwrap();
+    serde_json::from_str(&json).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xy_round_trip() {
+        let xy = XY::from_coordinates(1.5, -2.0);
+        assert_eq!(round_trip(&xy), xy);
+
+        let xyz = XYZ::from_coordinates(1.5, -2.0, 3.25);
+        assert_eq!(round_trip(&xyz), xyz);
+    }
+
+    #[test]
+    fn test_point_round_trip() {
+        let p2 = Point2d::from_coordinates(3.0, 4.0);
+        assert_eq!(serde_json::to_string(&p2).unwrap(), r#"{"x":3.0,"y":4.0}"#);
+        assert_eq!(round_trip(&p2), p2);
+
+        let p3 = Point3d::from_coordinates(3.0, 4.0, 5.0);
+        assert_eq!(
+            serde_json::to_string(&p3).unwrap(),
+            r#"{"x":3.0,"y":4.0,"z":5.0}"#
+        );
+        assert_eq!(round_trip(&p3), p3);
+    }
+
+    #[test]
+    fn test_vector_round_trip() {
+        let v2 = Vector2d::from_coordinates(-1.0, 0.5);
+        assert_eq!(round_trip(&v2), v2);
+
+        let v3: Vector3d<f64> = serde_json::from_str(r#"{"x":1.0,"y":2.0,"z":3.0}"#).unwrap();
+        assert_eq!(round_trip(&v3), v3);
+        assert_eq!(
+            serde_json::to_string(&v3).unwrap(),
+            r#"{"x":1.0,"y":2.0,"z":3.0}"#
+        );
+    }
+
+    #[test]
+    fn test_matrix_round_trip() {
+        let m = Matrix2d::from_coordinates(1.0, 2.0, 3.0, 4.0);
+        assert_eq!(round_trip(&m), m);
+
+        let t = Trsf2d::new();
+        let json = serde_json::to_string(&t).unwrap();
+        assert_eq!(serde_json::to_string(&round_trip(&t)).unwrap(), json);
+
+        let mut t = Trsf2d::new();
+        t.set_rotation(&Point2d::from_coordinates(1.0, 2.0), 0.5);
+        let json = serde_json::to_string(&t).unwrap();
+        assert_eq!(serde_json::to_string(&round_trip(&t)).unwrap(), json);
+    }
+
+    #[test]
+    fn test_trsf_rejects_invalid() {
+        let trsf = |scalefac: &str, matrix: &str| {
+            format!(
+                r#"{{"scalefac":{},"form":"Rotation","matrix":{{"m":{}}},"trans":{{"x":0.0,"y":0.0}}}}"#,
+                scalefac, matrix
+            )
+        };
+        assert!(serde_json::from_str::<Trsf2d>(&trsf("1.0", "[[0.0,-1.0],[1.0,0.0]]")).is_ok());
+        assert!(serde_json::from_str::<Trsf2d>(&trsf("1.0", "[[2.0,0.0],[0.0,1.0]]")).is_err());
+        assert!(serde_json::from_str::<Trsf2d>(&trsf("1.0", "[[0.0,0.0],[0.0,0.0]]")).is_err());
+        assert!(serde_json::from_str::<Trsf2d>(&trsf("0.0", "[[1.0,0.0],[0.0,1.0]]")).is_err());
+    }
+
+    #[test]
+    fn test_direction_normalized() {
+        let d2: Direction2d<f64> = serde_json::from_str(r#"{"x":3.0,"y":4.0}"#).unwrap();
+        let v2: Vector2d<f64> = serde_json::from_str(&serde_json::to_string(&d2).unwrap()).unwrap();
+        assert!((v2.x() - 0.6).abs() < 1e-12);
+        assert!((v2.y() - 0.8).abs() < 1e-12);
+        assert_eq!(round_trip(&d2), d2);
+
+        let d3: Direction3d<f64> = serde_json::from_str(r#"{"x":0.0,"y":0.0,"z":2.0}"#).unwrap();
+        assert_eq!(
+            serde_json::to_string(&d3).unwrap(),
+            r#"{"x":0.0,"y":0.0,"z":1.0}"#
+        );
+        assert_eq!(round_trip(&d3), d3);
+    }
+
+    #[test]
+    fn test_direction_rejects_zero() {
+        assert!(serde_json::from_str::<Direction2d<f64>>(r#"{"x":0.0,"y":0.0}"#).is_err());
+        assert!(serde_json::from_str::<Direction3d<f64>>(r#"{"x":0.0,"y":0.0,"z":0.0}"#).is_err());
+    }
+}
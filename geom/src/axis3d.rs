@@ -3,6 +3,13 @@ use crate::Point3d;
 use crate::traits::FloatWithConst;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        deserialize = "T: serde::Deserialize<'de> + Copy + Default + crate::traits::FloatWithConst"
+    ))
+)]
 pub struct Axis3d<T = f64> {
     pub location: Point3d<T>,
     pub direction: Direction3d<T>,
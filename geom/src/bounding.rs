@@ -0,0 +1,440 @@
+use crate::{
+    Circle2d, ConvexHull2d, ConvexHull3d, CoordinateSystem2d, Direction2d, Direction3d,
+    GeneralCoordinateSystem3d, Point2d, Point3d, Sphere,
+};
+use std::collections::HashSet;
+
+// Relative slack of the containment test, which absorbs the rounding of the
+// computed centers and radii.
+const SLACK: f64 = 1e-12;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Rectangle2d {
+    // Centered at the origin, with the width along vdir.
+    pub position: CoordinateSystem2d,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rectangle2d {
+    pub fn area(&self) -> f64 {
+        self.width * self.height
+    }
+
+    // Corners in counterclockwise order.
+    pub fn corners(&self) -> [Point2d; 4] {
+        let (x, y) = self.position.vdir.get_coords();
+        let (cx, cy) = self.position.origin.get_coords();
+        let (w, h) = (0.5 * self.width, 0.5 * self.height);
+        [(-w, -h), (w, -h), (w, h), (-w, h)]
+            .map(|(u, v)| Point2d::from_coords(cx + u * x - v * y, cy + u * y + v * x))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OrientedBox3d {
+    // Centered at the origin, with the extents along vxdir, vydir and the
+    // axis direction.
+    pub pos: GeneralCoordinateSystem3d,
+    pub extents: [f64; 3],
+}
+
+impl OrientedBox3d {
+    pub fn volume(&self) -> f64 {
+        self.extents.iter().product()
+    }
+
+    pub fn corners(&self) -> [Point3d; 8] {
+        let axes = [self.pos.vxdir, self.pos.vydir, self.pos.axis.direction];
+        let center = *self.pos.get_origin();
+        std::array::from_fn(|i| {
+            let mut p = [center.get_x(), center.get_y(), center.get_z()];
+            for (k, axis) in axes.iter().enumerate() {
+                let s = if i >> k & 1 == 0 { -0.5 } else { 0.5 } * self.extents[k];
+                p[0] += s * axis.get_x();
+                p[1] += s * axis.get_y();
+                p[2] += s * axis.get_z();
+            }
+            Point3d::from_coords(p[0], p[1], p[2])
+        })
+    }
+}
+
+// Deterministic shuffle, which gives Welzl's algorithm its expected linear
+// running time on sorted input.
+fn shuffled<P: Copy>(points: &[P]) -> Vec<P> {
+    let mut points = points.to_vec();
+    let mut state = 0x2545f4914f6cdd1du64;
+    for i in (1..points.len()).rev() {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        points.swap(i, (state >> 33) as usize % (i + 1));
+    }
+    points
+}
+
+type Ball<const N: usize> = ([f64; N], f64);
+
+fn contains<const N: usize>(ball: &Ball<N>, p: &[f64; N]) -> bool {
+    let d: f64 = (0..N).map(|k| (p[k] - ball.0[k]).powi(2)).sum();
+    d.sqrt() <= ball.1 * (1.0 + SLACK) + SLACK * ball.0.iter().fold(0.0, |m: f64, c| m.max(c.abs()))
+}
+
+fn diametral<const N: usize>(a: &[f64; N], b: &[f64; N]) -> Ball<N> {
+    let center = std::array::from_fn(|k| 0.5 * (a[k] + b[k]));
+    let radius = (0..N).map(|k| (a[k] - b[k]).powi(2)).sum::<f64>().sqrt() * 0.5;
+    (center, radius)
+}
+
+// Largest of the diametral balls of the pairs, which holds collinear points.
+fn widest<const N: usize>(points: &[[f64; N]]) -> Ball<N> {
+    let mut best = diametral(&points[0], &points[0]);
+    for i in 0..points.len() {
+        for j in i + 1..points.len() {
+            let ball = diametral(&points[i], &points[j]);
+            if ball.1 > best.1 {
+                best = ball;
+            }
+        }
+    }
+    best
+}
+
+fn sub3(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross3(u: &[f64; 3], v: &[f64; 3]) -> [f64; 3] {
+    [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ]
+}
+
+fn dot3(u: &[f64; 3], v: &[f64; 3]) -> f64 {
+    u[0] * v[0] + u[1] * v[1] + u[2] * v[2]
+}
+
+fn circle_ball(a: &[f64; 2], b: &[f64; 2], c: &[f64; 2]) -> Ball<2> {
+    let (bx, by) = (b[0] - a[0], b[1] - a[1]);
+    let (cx, cy) = (c[0] - a[0], c[1] - a[1]);
+    let d = 2.0 * (bx * cy - by * cx);
+    let (b2, c2) = (bx * bx + by * by, cx * cx + cy * cy);
+    let (ux, uy) = ((cy * b2 - by * c2) / d, (bx * c2 - cx * b2) / d);
+    if d == 0.0 || !ux.is_finite() || !uy.is_finite() {
+        return widest(&[*a, *b, *c]);
+    }
+    ([a[0] + ux, a[1] + uy], (ux * ux + uy * uy).sqrt())
+}
+
+fn triangle_ball(a: &[f64; 3], b: &[f64; 3], c: &[f64; 3]) -> Ball<3> {
+    let (u, v) = (sub3(b, a), sub3(c, a));
+    let n = cross3(&u, &v);
+    let d = 2.0 * dot3(&n, &n);
+    let (p, q) = (cross3(&n, &u), cross3(&v, &n));
+    let (u2, v2) = (dot3(&u, &u), dot3(&v, &v));
+    let offset: [f64; 3] = std::array::from_fn(|k| (v2 * p[k] + u2 * q[k]) / d);
+    if d == 0.0 || offset.iter().any(|o| !o.is_finite()) {
+        return widest(&[*a, *b, *c]);
+    }
+    (
+        std::array::from_fn(|k| a[k] + offset[k]),
+        dot3(&offset, &offset).sqrt(),
+    )
+}
+
+fn tetrahedron_ball(a: &[f64; 3], b: &[f64; 3], c: &[f64; 3], d: &[f64; 3]) -> Ball<3> {
+    let (u, v, w) = (sub3(b, a), sub3(c, a), sub3(d, a));
+    let det = 2.0 * dot3(&u, &cross3(&v, &w));
+    let (u2, v2, w2) = (dot3(&u, &u), dot3(&v, &v), dot3(&w, &w));
+    let (vw, wu, uv) = (cross3(&v, &w), cross3(&w, &u), cross3(&u, &v));
+    let offset: [f64; 3] = std::array::from_fn(|k| (u2 * vw[k] + v2 * wu[k] + w2 * uv[k]) / det);
+    if det == 0.0 || offset.iter().any(|o| !o.is_finite()) {
+        // Coplanar: the smallest of the triangle balls that holds the fourth
+        // point.
+        let points = [*a, *b, *c, *d];
+        return (0..4)
+            .map(|skip| {
+                let t: Vec<[f64; 3]> = (0..4).filter(|&k| k != skip).map(|k| points[k]).collect();
+                (triangle_ball(&t[0], &t[1], &t[2]), points[skip])
+            })
+            .filter(|(ball, p)| contains(ball, p))
+            .map(|(ball, _)| ball)
+            .min_by(|x, y| x.1.total_cmp(&y.1))
+            .unwrap_or_else(|| widest(&points));
+    }
+    (
+        std::array::from_fn(|k| a[k] + offset[k]),
+        dot3(&offset, &offset).sqrt(),
+    )
+}
+
+// Smallest circle containing the points, by Welzl's algorithm.
+pub fn min_enclosing_circle(points: &[Point2d]) -> Result<Circle2d, &'static str> {
+    let p: Vec<[f64; 2]> = shuffled(points)
+        .iter()
+        .map(|q| [q.get_x(), q.get_y()])
+        .collect();
+    if p.is_empty() {
+        return Err("no points to enclose");
+    }
+    if p.iter().flatten().any(|v| !v.is_finite()) {
+        return Err("points must be finite");
+    }
+    let mut ball = (p[0], 0.0);
+    for i in 1..p.len() {
+        if contains(&ball, &p[i]) {
+            continue;
+        }
+        ball = (p[i], 0.0);
+        for j in 0..i {
+            if contains(&ball, &p[j]) {
+                continue;
+            }
+            ball = diametral(&p[i], &p[j]);
+            for k in 0..j {
+                if !contains(&ball, &p[k]) {
+                    ball = circle_ball(&p[i], &p[j], &p[k]);
+                }
+            }
+        }
+    }
+    Ok(Circle2d {
+        position: CoordinateSystem2d::from_origin_vydir_vxdir(
+            Point2d::from_coords(ball.0[0], ball.0[1]),
+            Direction2d::from_coords(1.0, 0.0),
+        ),
+        radius: ball.1,
+    })
+}
+
+// Smallest sphere containing the points, by Welzl's algorithm.
+pub fn min_enclosing_sphere(points: &[Point3d]) -> Result<Sphere, &'static str> {
+    let p: Vec<[f64; 3]> = shuffled(points)
+        .iter()
+        .map(|q| [q.get_x(), q.get_y(), q.get_z()])
+        .collect();
+    if p.is_empty() {
+        return Err("no points to enclose");
+    }
+    if p.iter().flatten().any(|v| !v.is_finite()) {
+        return Err("points must be finite");
+    }
+    let mut ball = (p[0], 0.0);
+    for i in 1..p.len() {
+        if contains(&ball, &p[i]) {
+            continue;
+        }
+        ball = (p[i], 0.0);
+        for j in 0..i {
+            if contains(&ball, &p[j]) {
+                continue;
+            }
+            ball = diametral(&p[i], &p[j]);
+            for k in 0..j {
+                if contains(&ball, &p[k]) {
+                    continue;
+                }
+                ball = triangle_ball(&p[i], &p[j], &p[k]);
+                for l in 0..k {
+                    if !contains(&ball, &p[l]) {
+                        ball = tetrahedron_ball(&p[i], &p[j], &p[k], &p[l]);
+                    }
+                }
+            }
+        }
+    }
+    Ok(Sphere {
+        pos: GeneralCoordinateSystem3d::from_location_direction_xdirection(
+            Point3d::from_coords(ball.0[0], ball.0[1], ball.0[2]),
+            Direction3d::from_coords(0.0, 0.0, 1.0),
+            Direction3d::from_coords(1.0, 0.0, 0.0),
+        ),
+        radius: ball.1,
+    })
+}
+
+// Minimum area rectangle of the convex polygon, which has a side on one of
+// the polygon edges, by rotating calipers. Returns the center, the direction
+// of that side and the width along it and the height across it.
+fn calipers(hull: &[[f64; 2]]) -> ([f64; 2], [f64; 2], f64, f64) {
+    let m = hull.len();
+    if m < 3 {
+        let (a, b) = (hull[0], hull[m - 1]);
+        let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+        let length = (dx * dx + dy * dy).sqrt();
+        let dir = if length > 0.0 {
+            [dx / length, dy / length]
+        } else {
+            [1.0, 0.0]
+        };
+        return ([0.5 * (a[0] + b[0]), 0.5 * (a[1] + b[1])], dir, length, 0.0);
+    }
+    let dot = |k: usize, d: &[f64; 2], o: &[f64; 2]| {
+        (hull[k % m][0] - o[0]) * d[0] + (hull[k % m][1] - o[1]) * d[1]
+    };
+    let mut best: Option<([f64; 2], [f64; 2], f64, f64)> = None;
+    let (mut right, mut top, mut left) = (1, 1, 1);
+    for i in 0..m {
+        let o = hull[i];
+        let (dx, dy) = (hull[(i + 1) % m][0] - o[0], hull[(i + 1) % m][1] - o[1]);
+        let length = (dx * dx + dy * dy).sqrt();
+        let e = [dx / length, dy / length];
+        let n = [-e[1], e[0]];
+        right = right.max(i + 1);
+        while dot(right + 1, &e, &o) >= dot(right, &e, &o) {
+            right += 1;
+        }
+        top = top.max(right);
+        while dot(top + 1, &n, &o) >= dot(top, &n, &o) {
+            top += 1;
+        }
+        left = left.max(top);
+        while dot(left + 1, &e, &o) <= dot(left, &e, &o) {
+            left += 1;
+        }
+        let (lo, hi, height) = (dot(left, &e, &o), dot(right, &e, &o), dot(top, &n, &o));
+        let width = hi - lo;
+        if best.is_none_or(|b| width * height < b.2 * b.3) {
+            let (u, v) = (0.5 * (lo + hi), 0.5 * height);
+            let center = [o[0] + u * e[0] + v * n[0], o[1] + u * e[1] + v * n[1]];
+            best = Some((center, e, width, height));
+        }
+    }
+    best.unwrap()
+}
+
+// Minimum area enclosing rectangle.
+pub fn min_area_rectangle(points: &[Point2d]) -> Result<Rectangle2d, &'static str> {
+    let hull = ConvexHull2d::new(points)?;
+    if hull.vertices.is_empty() {
+        return Err("no points to enclose");
+    }
+    let polygon: Vec<[f64; 2]> = hull
+        .points(points)
+        .iter()
+        .map(|p| [p.get_x(), p.get_y()])
+        .collect();
+    let (center, dir, width, height) = calipers(&polygon);
+    Ok(Rectangle2d {
+        position: CoordinateSystem2d::from_origin_vydir_vxdir(
+            Point2d::from_coords(center[0], center[1]),
+            Direction2d::from_coords(dir[0], dir[1]),
+        ),
+        width,
+        height,
+    })
+}
+
+fn unit(v: [f64; 3]) -> Option<[f64; 3]> {
+    let length = dot3(&v, &v).sqrt();
+    (length > 0.0 && length.is_finite()).then(|| v.map(|c| c / length))
+}
+
+// Box with the given axis around the points: the minimum area rectangle of
+// their projection across the axis, extruded along it.
+fn box_along(axis: [f64; 3], points: &[[f64; 3]]) -> Result<OrientedBox3d, &'static str> {
+    let helper = if axis[0].abs() < 0.6 {
+        [1.0, 0.0, 0.0]
+    } else {
+        [0.0, 1.0, 0.0]
+    };
+    let u = unit(cross3(&helper, &axis)).unwrap();
+    let v = cross3(&axis, &u);
+    let projected: Vec<Point2d> = points
+        .iter()
+        .map(|p| Point2d::from_coords(dot3(p, &u), dot3(p, &v)))
+        .collect();
+    let rectangle = min_area_rectangle(&projected)?;
+    let (lo, hi) = points
+        .iter()
+        .map(|p| dot3(p, &axis))
+        .fold((f64::MAX, f64::MIN), |(lo, hi), h| (lo.min(h), hi.max(h)));
+    let (cx, cy) = rectangle.position.origin.get_coords();
+    let (ex, ey) = rectangle.position.vdir.get_coords();
+    let h = 0.5 * (lo + hi);
+    let center: [f64; 3] = std::array::from_fn(|k| cx * u[k] + cy * v[k] + h * axis[k]);
+    let xdir: [f64; 3] = std::array::from_fn(|k| ex * u[k] + ey * v[k]);
+    Ok(OrientedBox3d {
+        pos: GeneralCoordinateSystem3d::from_location_direction_xdirection(
+            Point3d::from_coords(center[0], center[1], center[2]),
+            Direction3d::from_coords(axis[0], axis[1], axis[2]),
+            Direction3d::from_coords(xdir[0], xdir[1], xdir[2]),
+        ),
+        extents: [rectangle.width, rectangle.height, hi - lo],
+    })
+}
+
+// Small oriented box around the points, not always the smallest. Only the
+// convex hull vertices are boxed, once for each distinct face normal and
+// edge direction of the hull taken as a box axis, with the minimum area
+// rectangle across it. This finds the minimum box whenever that has a face
+// flush with a hull face or an edge parallel to a hull edge, as for points
+// filling a box, but the exact minimum (O'Rourke) may have neither and be
+// slightly smaller. The time grows as the square of the number of hull
+// vertices. Coplanar points give a flat box.
+pub fn approx_min_volume_box(points: &[Point3d]) -> Result<OrientedBox3d, &'static str> {
+    if points.is_empty() {
+        return Err("no points to enclose");
+    }
+    let p: Vec<[f64; 3]> = points
+        .iter()
+        .map(|q| [q.get_x(), q.get_y(), q.get_z()])
+        .collect();
+    if p.iter().flatten().any(|v| !v.is_finite()) {
+        return Err("points must be finite");
+    }
+    let mut axes = Vec::new();
+    let vertices: Vec<[f64; 3]> = match ConvexHull3d::new(points) {
+        Ok(hull) => {
+            for (face, normal) in hull.faces.iter().zip(hull.normals.iter()) {
+                axes.push([normal.get_x(), normal.get_y(), normal.get_z()]);
+                for k in 0..3 {
+                    let (a, b) = (face[k], face[(k + 1) % 3]);
+                    if a < b {
+                        axes.extend(unit(sub3(&p[b], &p[a])));
+                    }
+                }
+            }
+            hull.vertices.iter().map(|&v| p[v]).collect()
+        }
+        Err(_) => {
+            // Collinear or coincident points: the line direction, if any.
+            let far = |key: &dyn Fn(&[f64; 3]) -> f64| {
+                *p.iter().max_by(|a, b| key(a).total_cmp(&key(b))).unwrap()
+            };
+            let a = p[0];
+            let b = far(&|q| dot3(&sub3(q, &a), &sub3(q, &a)));
+            let c = far(&|q| {
+                let n = cross3(&sub3(&b, &a), &sub3(q, &a));
+                dot3(&n, &n)
+            });
+            let normal = cross3(&sub3(&b, &a), &sub3(&c, &a));
+            axes.push(
+                unit(normal)
+                    .or(unit(sub3(&b, &a)))
+                    .unwrap_or([0.0, 0.0, 1.0]),
+            );
+            p.clone()
+        }
+    };
+    // Parallel axes give the same box.
+    let mut seen = HashSet::new();
+    axes.retain(|axis| {
+        let sign = axis
+            .iter()
+            .find(|c| c.abs() > 1e-9)
+            .map_or(1.0, |c| c.signum());
+        seen.insert(axis.map(|c| (sign * c * 1e9).round() as i64))
+    });
+    let mut best: Option<OrientedBox3d> = None;
+    for axis in axes {
+        let candidate = box_along(axis, &vertices)?;
+        if best.is_none_or(|b| candidate.volume() < b.volume()) {
+            best = Some(candidate);
+        }
+    }
+    Ok(best.unwrap())
+}
@@ -0,0 +1,108 @@
+use crate::traits::FloatWithConst;
+
+pub(crate) fn flat_knots<T: Copy>(knots: &[T], multiplicities: &[usize]) -> Vec<T> {
+    let mut flat = Vec::with_capacity(multiplicities.iter().sum());
+    for (&k, &m) in knots.iter().zip(multiplicities.iter()) {
+        flat.extend(std::iter::repeat_n(k, m));
+    }
+    flat
+}
+
+// Inverse of `flat_knots`: distinct knot values and their multiplicities.
+pub(crate) fn compress_knots<T: Copy + PartialEq>(flat: &[T]) -> (Vec<T>, Vec<usize>) {
+    let mut knots: Vec<T> = Vec::new();
+    let mut multiplicities: Vec<usize> = Vec::new();
+    for &k in flat.iter() {
+        match (knots.last(), multiplicities.last_mut()) {
+            (Some(&last), Some(m)) if last == k => *m += 1,
+            _ => {
+                knots.push(k);
+                multiplicities.push(1);
+            }
+        }
+    }
+    (knots, multiplicities)
+}
+
+pub(crate) fn check_knots<T>(
+    degree: usize,
+    num_poles: usize,
+    knots: &[T],
+    multiplicities: &[usize],
+) -> Result<(), &'static str>
+where
+    T: Copy + Default + FloatWithConst,
+{
+    if degree == 0 {
+        return Err("B-spline degree must be at least 1");
+    }
+    if knots.len() != multiplicities.len() || knots.len() < 2 {
+        return Err("B-spline needs at least two knots with one multiplicity each");
+    }
+    if knots.windows(2).any(|w| w[1] <= w[0]) {
+        return Err("B-spline knots must be strictly increasing");
+    }
+    if multiplicities.iter().any(|&m| m == 0 || m > degree + 1) {
+        return Err("B-spline knot multiplicity out of range");
+    }
+    if multiplicities.iter().sum::<usize>() != num_poles + degree + 1 {
+        return Err("B-spline knot count does not match number of poles and degree");
+    }
+    Ok(())
+}
+
+// Index of the knot span containing `u` in the flat knot vector (NURBS Book A2.1).
+pub(crate) fn find_span<T>(degree: usize, num_poles: usize, u: T, flat: &[T]) -> usize
+where
+    T: Copy + Default + FloatWithConst,
+{
+    if u >= flat[num_poles] {
+        return num_poles - 1;
+    }
+    if u <= flat[degree] {
+        let mut span = degree;
+        while span + 1 < num_poles && flat[span + 1] <= u {
+            span += 1;
+        }
+        return span;
+    }
+    let (mut low, mut high) = (degree, num_poles);
+    let mut mid = (low + high) / 2;
+    while u < flat[mid] || u >= flat[mid + 1] {
+        if u < flat[mid] {
+            high = mid;
+        } else {
+            low = mid;
+        }
+        mid = (low + high) / 2;
+    }
+    mid
+}
+
+// Non-vanishing basis functions N[span-degree..=span] at `u` (NURBS Book A2.2).
+pub(crate) fn basis_functions<T>(span: usize, u: T, degree: usize, flat: &[T]) -> Vec<T>
+where
+    T: Copy + Default + FloatWithConst,
+{
+    let mut n = vec![T::zero(); degree + 1];
+    let mut left = vec![T::zero(); degree + 1];
+    let mut right = vec![T::zero(); degree + 1];
+    n[0] = T::one();
+    for j in 1..=degree {
+        left[j] = u - flat[span + 1 - j];
+        right[j] = flat[span + j] - u;
+        let mut saved = T::zero();
+        for r in 0..j {
+            let denom = right[r + 1] + left[j - r];
+            let temp = if denom == T::zero() {
+                T::zero()
+            } else {
+                n[r] / denom
+            };
+            n[r] = saved + right[r + 1] * temp;
+            saved = left[j - r] * temp;
+        }
+        n[j] = saved;
+    }
+    n
+}
@@ -0,0 +1,97 @@
+use crate::Point2d;
+use crate::XY;
+use crate::bspline::{basis_functions, check_knots, find_span, flat_knots};
+use crate::traits::FloatWithConst;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BSplineCurve2d<T = f64> {
+    pub degree: usize,
+    pub poles: Vec<Point2d<T>>,
+    pub weights: Option<Vec<T>>,
+    pub knots: Vec<T>,
+    pub multiplicities: Vec<usize>,
+}
+
+impl<T> BSplineCurve2d<T>
+where
+    T: Copy + Default + FloatWithConst,
+{
+    pub fn from_poles_knots(
+        degree: usize,
+        poles: Vec<Point2d<T>>,
+        knots: Vec<T>,
+        multiplicities: Vec<usize>,
+    ) -> Result<Self, &'static str> {
+        check_knots(degree, poles.len(), &knots, &multiplicities)?;
+        Ok(BSplineCurve2d {
+            degree,
+            poles,
+            weights: None,
+            knots,
+            multiplicities,
+        })
+    }
+
+    pub fn from_poles_weights_knots(
+        degree: usize,
+        poles: Vec<Point2d<T>>,
+        weights: Vec<T>,
+        knots: Vec<T>,
+        multiplicities: Vec<usize>,
+    ) -> Result<Self, &'static str> {
+        if weights.len() != poles.len() {
+            return Err("B-spline weights do not match number of poles");
+        }
+        if weights.iter().any(|&w| w <= T::zero()) {
+            return Err("B-spline weights must be positive");
+        }
+        let mut curve = BSplineCurve2d::from_poles_knots(degree, poles, knots, multiplicities)?;
+        curve.weights = Some(weights);
+        Ok(curve)
+    }
+
+    pub fn from_bezier(poles: Vec<Point2d<T>>) -> Result<Self, &'static str> {
+        if poles.len() < 2 {
+            return Err("Bezier curve needs at least two poles");
+        }
+        let degree = poles.len() - 1;
+        BSplineCurve2d::from_poles_knots(
+            degree,
+            poles,
+            vec![T::zero(), T::one()],
+            vec![degree + 1, degree + 1],
+        )
+    }
+
+    pub fn is_rational(&self) -> bool {
+        self.weights.is_some()
+    }
+
+    pub fn get_flat_knots(&self) -> Vec<T> {
+        flat_knots(&self.knots, &self.multiplicities)
+    }
+
+    pub fn first_parameter(&self) -> T {
+        self.get_flat_knots()[self.degree]
+    }
+
+    pub fn last_parameter(&self) -> T {
+        self.get_flat_knots()[self.poles.len()]
+    }
+
+    pub fn value(&self, u: T) -> Point2d<T> {
+        let flat = self.get_flat_knots();
+        let span = find_span(self.degree, self.poles.len(), u, &flat);
+        let n = basis_functions(span, u, self.degree, &flat);
+        let mut sum = XY::new();
+        let mut wsum = T::zero();
+        for (j, &nj) in n.iter().enumerate() {
+            let i = span - self.degree + j;
+            let w = self.weights.as_ref().map_or(T::one(), |w| w[i]);
+            sum += &(self.poles[i].get_xy() * (nj * w));
+            wsum += nj * w;
+        }
+        Point2d::from_xy(&sum / wsum)
+    }
+}
@@ -0,0 +1,105 @@
+use crate::Point3d;
+use crate::XYZ;
+use crate::bspline::{basis_functions, check_knots, find_span, flat_knots};
+use crate::traits::FloatWithConst;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BSplineSurface<T = f64> {
+    pub u_degree: usize,
+    pub v_degree: usize,
+    pub poles: Vec<Vec<Point3d<T>>>,
+    pub weights: Option<Vec<Vec<T>>>,
+    pub u_knots: Vec<T>,
+    pub u_multiplicities: Vec<usize>,
+    pub v_knots: Vec<T>,
+    pub v_multiplicities: Vec<usize>,
+}
+
+impl<T> BSplineSurface<T>
+where
+    T: Copy + Default + FloatWithConst,
+{
+    pub fn from_poles_knots(
+        degrees: (usize, usize),
+        poles: Vec<Vec<Point3d<T>>>,
+        u_knots: (Vec<T>, Vec<usize>),
+        v_knots: (Vec<T>, Vec<usize>),
+    ) -> Result<Self, &'static str> {
+        let nu = poles.len();
+        let nv = poles.first().map_or(0, |row| row.len());
+        if poles.iter().any(|row| row.len() != nv) {
+            return Err("B-spline surface pole rows have different lengths");
+        }
+        check_knots(degrees.0, nu, &u_knots.0, &u_knots.1)?;
+        check_knots(degrees.1, nv, &v_knots.0, &v_knots.1)?;
+        Ok(BSplineSurface {
+            u_degree: degrees.0,
+            v_degree: degrees.1,
+            poles,
+            weights: None,
+            u_knots: u_knots.0,
+            u_multiplicities: u_knots.1,
+            v_knots: v_knots.0,
+            v_multiplicities: v_knots.1,
+        })
+    }
+
+    pub fn set_weights(&mut self, weights: Vec<Vec<T>>) -> Result<(), &'static str> {
+        if weights.len() != self.poles.len()
+            || weights
+                .iter()
+                .zip(self.poles.iter())
+                .any(|(w, p)| w.len() != p.len())
+        {
+            return Err("B-spline surface weights do not match poles");
+        }
+        if weights.iter().flatten().any(|&w| w <= T::zero()) {
+            return Err("B-spline weights must be positive");
+        }
+        self.weights = Some(weights);
+        Ok(())
+    }
+
+    pub fn is_rational(&self) -> bool {
+        self.weights.is_some()
+    }
+
+    pub fn num_u_poles(&self) -> usize {
+        self.poles.len()
+    }
+
+    pub fn num_v_poles(&self) -> usize {
+        self.poles.first().map_or(0, |row| row.len())
+    }
+
+    pub fn get_u_flat_knots(&self) -> Vec<T> {
+        flat_knots(&self.u_knots, &self.u_multiplicities)
+    }
+
+    pub fn get_v_flat_knots(&self) -> Vec<T> {
+        flat_knots(&self.v_knots, &self.v_multiplicities)
+    }
+
+    pub fn value(&self, u: T, v: T) -> Point3d<T> {
+        let (nu, nv) = (self.num_u_poles(), self.num_v_poles());
+        let u_flat = self.get_u_flat_knots();
+        let v_flat = self.get_v_flat_knots();
+        let u_span = find_span(self.u_degree, nu, u, &u_flat);
+        let v_span = find_span(self.v_degree, nv, v, &v_flat);
+        let bu = basis_functions(u_span, u, self.u_degree, &u_flat);
+        let bv = basis_functions(v_span, v, self.v_degree, &v_flat);
+        let mut sum = XYZ::new();
+        let mut wsum = T::zero();
+        for (a, &na) in bu.iter().enumerate() {
+            let i = u_span - self.u_degree + a;
+            for (b, &nb) in bv.iter().enumerate() {
+                let j = v_span - self.v_degree + b;
+                let w = self.weights.as_ref().map_or(T::one(), |w| w[i][j]);
+                sum += &(&self.poles[i][j].get_xyz() * (na * nb * w));
+                wsum += na * nb * w;
+            }
+        }
+        Point3d::from_xyz(&sum / wsum)
+    }
+}
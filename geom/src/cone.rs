@@ -1,6 +1,7 @@
 use crate::GeneralCoordinateSystem3d;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Cone<T = f64> {
     pub position: GeneralCoordinateSystem3d<T>,
     pub radius: T,
@@ -0,0 +1,312 @@
+use crate::predicates::{orient2d, orient3d};
+use crate::{Direction3d, Point2d, Point3d};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default)]
+pub struct ConvexHull2d {
+    // Indices of the hull vertices in counterclockwise order, starting with
+    // the lowest of the leftmost points. Points in the interior of hull edges
+    // and later duplicates of a vertex are left out.
+    pub vertices: Vec<usize>,
+}
+
+impl ConvexHull2d {
+    // Andrew's monotone chain.
+    pub fn new(points: &[Point2d]) -> Result<Self, &'static str> {
+        if points
+            .iter()
+            .any(|p| !p.get_x().is_finite() || !p.get_y().is_finite())
+        {
+            return Err("points must be finite");
+        }
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        order.sort_by(|&i, &j| {
+            let (p, q) = (points[i], points[j]);
+            p.get_x()
+                .total_cmp(&q.get_x())
+                .then(p.get_y().total_cmp(&q.get_y()))
+        });
+        order.dedup_by(|i, j| points[*i].is_equal(&points[*j], 0.0));
+        if order.len() < 3 {
+            return Ok(ConvexHull2d { vertices: order });
+        }
+        let mut vertices: Vec<usize> = Vec::with_capacity(2 * order.len());
+        // Lower chain left to right, then upper chain right to left. The last
+        // point of each chain starts the other one.
+        for chain in [order.clone(), order.into_iter().rev().collect()] {
+            let start = vertices.len();
+            for i in chain {
+                while vertices.len() >= start + 2 {
+                    let (a, b) = (vertices[vertices.len() - 2], vertices[vertices.len() - 1]);
+                    if orient2d(&points[a], &points[b], &points[i]) > 0.0 {
+                        break;
+                    }
+                    vertices.pop();
+                }
+                vertices.push(i);
+            }
+            vertices.pop();
+        }
+        Ok(ConvexHull2d { vertices })
+    }
+
+    pub fn points(&self, points: &[Point2d]) -> Vec<Point2d> {
+        self.vertices.iter().map(|&i| points[i]).collect()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConvexHull3d {
+    // Indices of the hull vertices in ascending order.
+    pub vertices: Vec<usize>,
+    // Triangles counterclockwise when seen from outside. Planar facets with
+    // more than three vertices are split into several coplanar triangles.
+    // Coplanar input gives a flat hull whose polygon is triangulated once
+    // for each side.
+    pub faces: Vec<[usize; 3]>,
+    // Outward unit normal of each face.
+    pub normals: Vec<Direction3d>,
+}
+
+impl ConvexHull3d {
+    // Quickhull after Barber, Dobkin and Huhdanpaa (1996). Visibility uses
+    // the exact orientation predicate, so points on the hull boundary that
+    // are not vertices and duplicates of vertices are never added.
+    pub fn new(points: &[Point3d]) -> Result<Self, &'static str> {
+        if points
+            .iter()
+            .any(|p| !p.get_x().is_finite() || !p.get_y().is_finite() || !p.get_z().is_finite())
+        {
+            return Err("points must be finite");
+        }
+        let mut hull = Quickhull::new(points)?;
+        hull.run();
+        Ok(hull.result())
+    }
+
+    pub fn face_points(&self, index: usize, points: &[Point3d]) -> [Point3d; 3] {
+        self.faces[index].map(|v| points[v])
+    }
+}
+
+fn sub(a: &Point3d, b: &Point3d) -> [f64; 3] {
+    [
+        a.get_x() - b.get_x(),
+        a.get_y() - b.get_y(),
+        a.get_z() - b.get_z(),
+    ]
+}
+
+fn cross(u: &[f64; 3], v: &[f64; 3]) -> [f64; 3] {
+    [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ]
+}
+
+fn dot(u: &[f64; 3], v: &[f64; 3]) -> f64 {
+    u[0] * v[0] + u[1] * v[1] + u[2] * v[2]
+}
+
+// Both sides of the hull of coplanar points with normal `n`, from the 2D
+// hull of their projection onto the coordinate plane closest to theirs.
+// Fans from neighbouring vertices keep every directed edge unique.
+fn planar(points: &[Point3d], n: &[f64; 3]) -> Result<Vec<[usize; 3]>, &'static str> {
+    let axis = (0..3)
+        .max_by(|&i, &j| n[i].abs().total_cmp(&n[j].abs()))
+        .unwrap();
+    let projected: Vec<Point2d> = points
+        .iter()
+        .map(|p| {
+            let (x, y, z) = p.get_coords();
+            match axis {
+                0 => Point2d::from_coords(y, z),
+                1 => Point2d::from_coords(z, x),
+                _ => Point2d::from_coords(x, y),
+            }
+        })
+        .collect();
+    let polygon = ConvexHull2d::new(&projected)?.vertices;
+    if polygon.len() < 3 {
+        return Err("convex hull needs three points that are not collinear");
+    }
+    let m = polygon.len();
+    let mut faces = Vec::with_capacity(2 * (m - 2));
+    for k in 1..m - 1 {
+        faces.push([polygon[0], polygon[k], polygon[k + 1]]);
+    }
+    for k in 2..m {
+        faces.push([polygon[1], polygon[(k + 1) % m], polygon[k]]);
+    }
+    Ok(faces)
+}
+
+struct Quickhull<'a> {
+    points: &'a [Point3d],
+    faces: Vec<[usize; 3]>,
+    alive: Vec<bool>,
+    // Points strictly above each face that no other face has claimed.
+    outside: Vec<Vec<usize>>,
+    // Face owning each directed edge.
+    edges: HashMap<(usize, usize), usize>,
+}
+
+impl<'a> Quickhull<'a> {
+    fn new(points: &'a [Point3d]) -> Result<Self, &'static str> {
+        // The first of several extreme points, so that later duplicates are
+        // not picked.
+        let extreme = |key: &dyn Fn(usize) -> f64| {
+            (0..points.len())
+                .rev()
+                .max_by(|&i, &j| key(i).total_cmp(&key(j)))
+        };
+        let Some(a) = extreme(&|i| -points[i].get_x()) else {
+            return Err("convex hull needs three points that are not collinear");
+        };
+        let b = extreme(&|i| {
+            let d = sub(&points[i], &points[a]);
+            dot(&d, &d)
+        })
+        .unwrap();
+        let c = extreme(&|i| {
+            let n = cross(&sub(&points[b], &points[a]), &sub(&points[i], &points[a]));
+            dot(&n, &n)
+        })
+        .unwrap();
+        let d =
+            extreme(&|i| orient3d(&points[a], &points[b], &points[c], &points[i]).abs()).unwrap();
+        let volume = orient3d(&points[a], &points[b], &points[c], &points[d]);
+        let mut hull = Quickhull {
+            points,
+            faces: Vec::new(),
+            alive: Vec::new(),
+            outside: Vec::new(),
+            edges: HashMap::new(),
+        };
+        if volume == 0.0 {
+            let n = cross(&sub(&points[b], &points[a]), &sub(&points[c], &points[a]));
+            for face in planar(points, &n)? {
+                hull.add_face(face);
+            }
+            return Ok(hull);
+        }
+        let (b, c) = if volume > 0.0 { (b, c) } else { (c, b) };
+        for face in [[a, b, c], [a, d, b], [b, d, c], [a, c, d]] {
+            hull.add_face(face);
+        }
+        let candidates: Vec<usize> = (0..points.len()).collect();
+        hull.assign(candidates, &[0, 1, 2, 3]);
+        Ok(hull)
+    }
+
+    fn above(&self, f: usize, p: usize) -> bool {
+        let [a, b, c] = self.faces[f].map(|v| self.points[v]);
+        orient3d(&a, &b, &c, &self.points[p]) < 0.0
+    }
+
+    fn add_face(&mut self, face: [usize; 3]) -> usize {
+        let f = self.faces.len();
+        for k in 0..3 {
+            self.edges.insert((face[k], face[(k + 1) % 3]), f);
+        }
+        self.faces.push(face);
+        self.alive.push(true);
+        self.outside.push(Vec::new());
+        f
+    }
+
+    // Hands each point to the first of the faces it lies strictly above;
+    // the others are inside the hull or on its boundary.
+    fn assign(&mut self, candidates: Vec<usize>, faces: &[usize]) {
+        for p in candidates {
+            if let Some(&f) = faces.iter().find(|&&f| self.above(f, p)) {
+                self.outside[f].push(p);
+            }
+        }
+    }
+
+    fn run(&mut self) {
+        let mut f = 0;
+        while f < self.faces.len() {
+            if !self.alive[f] || self.outside[f].is_empty() {
+                f += 1;
+                continue;
+            }
+            // Farthest point above the face, the first of equally far ones.
+            let [a, b, c] = self.faces[f].map(|v| self.points[v]);
+            let normal = cross(&sub(&b, &a), &sub(&c, &a));
+            let p = *self.outside[f]
+                .iter()
+                .max_by(|&&i, &&j| {
+                    let di = dot(&normal, &sub(&self.points[i], &a));
+                    let dj = dot(&normal, &sub(&self.points[j], &a));
+                    di.total_cmp(&dj).then(j.cmp(&i))
+                })
+                .unwrap();
+            // Faces visible from p are connected; their boundary is the
+            // horizon.
+            let mut visible = vec![f];
+            let mut stack = vec![f];
+            self.alive[f] = false;
+            let mut horizon = Vec::new();
+            while let Some(g) = stack.pop() {
+                let face = self.faces[g];
+                for k in 0..3 {
+                    let (u, v) = (face[k], face[(k + 1) % 3]);
+                    let n = self.edges[&(v, u)];
+                    if !self.alive[n] {
+                        continue;
+                    }
+                    if self.above(n, p) {
+                        self.alive[n] = false;
+                        visible.push(n);
+                        stack.push(n);
+                    } else {
+                        horizon.push((u, v));
+                    }
+                }
+            }
+            let mut candidates = Vec::new();
+            for &g in visible.iter() {
+                let face = self.faces[g];
+                for k in 0..3 {
+                    let edge = (face[k], face[(k + 1) % 3]);
+                    if self.edges.get(&edge) == Some(&g) {
+                        self.edges.remove(&edge);
+                    }
+                }
+                candidates.append(&mut self.outside[g]);
+            }
+            let new: Vec<usize> = horizon
+                .into_iter()
+                .map(|(u, v)| self.add_face([u, v, p]))
+                .collect();
+            candidates.retain(|&q| q != p);
+            self.assign(candidates, &new);
+        }
+    }
+
+    fn result(&self) -> ConvexHull3d {
+        let faces: Vec<[usize; 3]> = (0..self.faces.len())
+            .filter(|&f| self.alive[f])
+            .map(|f| self.faces[f])
+            .collect();
+        let normals = faces
+            .iter()
+            .map(|face| {
+                let [a, b, c] = face.map(|v| self.points[v]);
+                let n = cross(&sub(&b, &a), &sub(&c, &a));
+                Direction3d::from_coords(n[0], n[1], n[2])
+            })
+            .collect();
+        let mut vertices: Vec<usize> = faces.iter().flatten().copied().collect();
+        vertices.sort_unstable();
+        vertices.dedup();
+        ConvexHull3d {
+            vertices,
+            faces,
+            normals,
+        }
+    }
+}
@@ -3,6 +3,13 @@ use crate::Point2d;
 use crate::traits::FloatWithConst;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        deserialize = "T: serde::Deserialize<'de> + Copy + Default + crate::traits::FloatWithConst"
+    ))
+)]
 pub struct CoordinateSystem2d<T = f64> {
     pub origin: Point2d<T>,
     pub vdir: Direction2d<T>,
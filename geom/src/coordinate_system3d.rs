@@ -4,6 +4,7 @@ use crate::Point3d;
 use crate::traits::FloatWithConst;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct CoordinateSystem3d<T = f64> {
     pub axis: Axis3d<T>,
     pub vydir: Direction3d<T>,
@@ -51,6 +52,26 @@ where
         coord
     }
 
+    pub fn from_location_direction_xdirection<P, N, VX>(
+        location: P,
+        direction: N,
+        xdirection: VX,
+    ) -> Self
+    where
+        P: Into<Point3d<T>>,
+        N: Into<Direction3d<T>>,
+        VX: Into<Direction3d<T>>,
+    {
+        let direction = direction.into();
+        let vxdir = direction.cross_cross_new(&xdirection.into(), &direction);
+        let vydir = direction.cross_new(&vxdir);
+        CoordinateSystem3d {
+            axis: Axis3d::from_location_direction(location, direction),
+            vydir,
+            vxdir,
+        }
+    }
+
     pub fn get_origin(&self) -> &Point3d<T> {
         &self.axis.location
     }
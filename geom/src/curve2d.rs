@@ -0,0 +1,187 @@
+use crate::Axis2d;
+use crate::BSplineCurve2d;
+use crate::Circle2d;
+use crate::CoordinateSystem2d;
+use crate::Direction2d;
+use crate::Ellipse2d;
+use crate::Line2d;
+use crate::Point2d;
+use crate::Trsf2d;
+use crate::XY;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Curve2d {
+    Line(Line2d),
+    Circle(Circle2d),
+    Ellipse(Ellipse2d),
+    BSpline(BSplineCurve2d),
+}
+
+// Basis curve restricted to the parameters between `first` and `last`. The
+// curve runs from `first` to `last`, so `first > last` reverses the basis.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrimmedCurve2d {
+    pub basis: Curve2d,
+    pub first: f64,
+    pub last: f64,
+}
+
+fn frame_value(position: &CoordinateSystem2d, u: f64, v: f64) -> Point2d {
+    let x = position.vdir.get_xy();
+    let y = XY::from_coords(-x.y, x.x);
+    let mut xy = *position.origin.get_xy();
+    xy += &(x * u);
+    xy += &(&y * v);
+    Point2d::from_xy(xy)
+}
+
+fn transform_point(t: &Trsf2d, p: &Point2d) -> Point2d {
+    Point2d::from_xy(t.transform_xy(p.get_xy()))
+}
+
+fn transform_frame(t: &Trsf2d, position: &CoordinateSystem2d) -> CoordinateSystem2d {
+    CoordinateSystem2d {
+        origin: transform_point(t, &position.origin),
+        vdir: Direction2d::from_xy(t.transform_direction_xy(position.vdir.get_xy())),
+    }
+}
+
+impl Curve2d {
+    pub fn value(&self, u: f64) -> Point2d {
+        match self {
+            Curve2d::Line(line) => {
+                let mut xy = *line.pos.location.get_xy();
+                xy += &(line.pos.direction.get_xy() * u);
+                Point2d::from_xy(xy)
+            }
+            Curve2d::Circle(c) => frame_value(&c.position, c.radius * u.cos(), c.radius * u.sin()),
+            Curve2d::Ellipse(e) => frame_value(
+                &e.position,
+                e.major_radius * u.cos(),
+                e.minor_radius * u.sin(),
+            ),
+            Curve2d::BSpline(b) => b.value(u),
+        }
+    }
+
+    pub fn is_periodic(&self) -> bool {
+        matches!(self, Curve2d::Circle(_) | Curve2d::Ellipse(_))
+    }
+
+    pub fn first_parameter(&self) -> f64 {
+        match self {
+            Curve2d::Line(_) => f64::NEG_INFINITY,
+            Curve2d::Circle(_) | Curve2d::Ellipse(_) => 0.0,
+            Curve2d::BSpline(b) => b.first_parameter(),
+        }
+    }
+
+    pub fn last_parameter(&self) -> f64 {
+        match self {
+            Curve2d::Line(_) => f64::INFINITY,
+            Curve2d::Circle(_) | Curve2d::Ellipse(_) => 2.0 * std::f64::consts::PI,
+            Curve2d::BSpline(b) => b.last_parameter(),
+        }
+    }
+}
+
+impl TrimmedCurve2d {
+    pub fn new(basis: Curve2d, first: f64, last: f64) -> Self {
+        TrimmedCurve2d { basis, first, last }
+    }
+
+    // The whole basis curve; infinite for lines.
+    pub fn from_curve(basis: Curve2d) -> Self {
+        let (first, last) = (basis.first_parameter(), basis.last_parameter());
+        TrimmedCurve2d { basis, first, last }
+    }
+
+    // Segment from `start` to `end`, parametrised by arc length.
+    pub fn from_segment(start: &Point2d, end: &Point2d) -> Result<Self, &'static str> {
+        let delta = end.get_xy() - start.get_xy();
+        let length = delta.length();
+        if length <= 0.0 {
+            return Err("segment end points coincide");
+        }
+        Ok(TrimmedCurve2d {
+            basis: Curve2d::Line(Line2d {
+                pos: Axis2d::from_location_direction(*start, Direction2d::from_xy(delta)),
+            }),
+            first: 0.0,
+            last: length,
+        })
+    }
+
+    pub fn value(&self, u: f64) -> Point2d {
+        self.basis.value(u)
+    }
+
+    pub fn start_point(&self) -> Point2d {
+        self.basis.value(self.first)
+    }
+
+    pub fn end_point(&self) -> Point2d {
+        self.basis.value(self.last)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.basis.is_periodic()
+            && (self.last - self.first).abs() >= 2.0 * std::f64::consts::PI - 1e-12
+    }
+
+    pub fn reversed(&self) -> Self {
+        TrimmedCurve2d {
+            basis: self.basis.clone(),
+            first: self.last,
+            last: self.first,
+        }
+    }
+
+    // Image under `t`; a mirroring transformation negates the parameters of conics
+    // because their frames stay right-handed.
+    pub fn transformed(&self, t: &Trsf2d) -> Self {
+        let sign = if t.is_negative() { -1.0 } else { 1.0 };
+        match &self.basis {
+            Curve2d::Line(line) => TrimmedCurve2d {
+                basis: Curve2d::Line(Line2d {
+                    pos: Axis2d::from_location_direction(
+                        transform_point(t, &line.pos.location),
+                        Direction2d::from_xy(t.transform_direction_xy(line.pos.direction.get_xy())),
+                    ),
+                }),
+                first: self.first * t.scale,
+                last: self.last * t.scale,
+            },
+            Curve2d::Circle(c) => TrimmedCurve2d {
+                basis: Curve2d::Circle(Circle2d {
+                    position: transform_frame(t, &c.position),
+                    radius: c.radius * t.scale,
+                }),
+                first: sign * self.first,
+                last: sign * self.last,
+            },
+            Curve2d::Ellipse(e) => TrimmedCurve2d {
+                basis: Curve2d::Ellipse(Ellipse2d {
+                    position: transform_frame(t, &e.position),
+                    major_radius: e.major_radius * t.scale,
+                    minor_radius: e.minor_radius * t.scale,
+                }),
+                first: sign * self.first,
+                last: sign * self.last,
+            },
+            Curve2d::BSpline(b) => {
+                let mut b = b.clone();
+                for pole in b.poles.iter_mut() {
+                    *pole = transform_point(t, pole);
+                }
+                TrimmedCurve2d {
+                    basis: Curve2d::BSpline(b),
+                    first: self.first,
+                    last: self.last,
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,1032 @@
+use crate::Point2d;
+use crate::predicates::{incircle, orient2d};
+use std::collections::VecDeque;
+
+const NONE: usize = usize::MAX;
+const INFINITE: usize = 0;
+const FIRST: usize = 1;
+
+type Edge = (usize, usize);
+
+#[derive(Debug, Clone, Default)]
+pub struct Domain2d {
+    pub points: Vec<Point2d>,
+    pub segments: Vec<[usize; 2]>,
+    pub holes: Vec<Point2d>,
+    // Keeps the whole convex hull instead of removing the triangles outside
+    // the outermost segment loops.
+    pub convex_hull: bool,
+}
+
+impl Domain2d {
+    pub fn new() -> Self {
+        Domain2d::default()
+    }
+
+    pub fn from_points(points: Vec<Point2d>) -> Self {
+        Domain2d {
+            points,
+            ..Domain2d::default()
+        }
+    }
+
+    pub fn add_point(&mut self, point: Point2d) -> usize {
+        self.points.push(point);
+        self.points.len() - 1
+    }
+
+    pub fn add_segment(&mut self, a: usize, b: usize) {
+        self.segments.push([a, b]);
+    }
+
+    // Adds a closed polygon boundary and returns the index of its first point.
+    pub fn add_loop(&mut self, points: &[Point2d]) -> usize {
+        let first = self.points.len();
+        self.points.extend_from_slice(points);
+        for i in 0..points.len() {
+            self.segments
+                .push([first + i, first + (i + 1) % points.len()]);
+        }
+        first
+    }
+
+    pub fn add_hole(&mut self, point: Point2d) {
+        self.holes.push(point);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Refinement {
+    // Lower bound on triangle angles in radians; zero disables the test.
+    // Bounds above about 33 degrees may not terminate.
+    pub min_angle: f64,
+    pub max_area: Option<f64>,
+    // Maximum number of Steiner points inserted before giving up.
+    pub max_points: usize,
+}
+
+impl Default for Refinement {
+    fn default() -> Self {
+        Refinement {
+            min_angle: 20f64.to_radians(),
+            max_area: None,
+            max_points: 1_000_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Triangulation2d {
+    // Input points first, in input order, followed by Steiner points.
+    // Duplicated or excluded input points are kept but not referenced.
+    pub points: Vec<Point2d>,
+    // Counterclockwise vertex indices.
+    pub triangles: Vec<[usize; 3]>,
+    // Constrained edges of the result, including boundary edges.
+    pub segments: Vec<[usize; 2]>,
+}
+
+impl Triangulation2d {
+    pub fn delaunay(points: &[Point2d]) -> Result<Self, &'static str> {
+        Self::constrained(&Domain2d::from_points(points.to_vec()))
+    }
+
+    // Triangles inside holes and, unless the domain keeps its convex hull,
+    // outside the outermost segment loops are removed.
+    pub fn constrained(domain: &Domain2d) -> Result<Self, &'static str> {
+        let cdt = Cdt::build(domain)?;
+        Ok(cdt.result())
+    }
+
+    pub fn refined(domain: &Domain2d, refinement: &Refinement) -> Result<Self, &'static str> {
+        let mut cdt = Cdt::build(domain)?;
+        cdt.refine(refinement)?;
+        Ok(cdt.result())
+    }
+
+    pub fn triangle_points(&self, index: usize) -> [Point2d; 3] {
+        self.triangles[index].map(|v| self.points[v])
+    }
+
+    pub fn area(&self) -> f64 {
+        (0..self.triangles.len())
+            .map(|i| {
+                let [a, b, c] = self.triangle_points(i);
+                0.5 * orient2d(&a, &b, &c)
+            })
+            .sum()
+    }
+
+    // Smallest interior angle over all triangles, in radians.
+    pub fn min_angle(&self) -> f64 {
+        (0..self.triangles.len())
+            .map(|i| {
+                let p = self.triangle_points(i);
+                (0..3)
+                    .map(|k| angle(&p[k], &p[(k + 1) % 3], &p[(k + 2) % 3]))
+                    .fold(f64::MAX, f64::min)
+            })
+            .fold(std::f64::consts::PI, f64::min)
+    }
+}
+
+// Angle at a in the triangle a, b, c.
+fn angle(a: &Point2d, b: &Point2d, c: &Point2d) -> f64 {
+    let (ux, uy) = (b.get_x() - a.get_x(), b.get_y() - a.get_y());
+    let (vx, vy) = (c.get_x() - a.get_x(), c.get_y() - a.get_y());
+    (ux * vy - uy * vx).abs().atan2(ux * vx + uy * vy)
+}
+
+fn circumcenter(a: &Point2d, b: &Point2d, c: &Point2d) -> Point2d {
+    let (bx, by) = (b.get_x() - a.get_x(), b.get_y() - a.get_y());
+    let (cx, cy) = (c.get_x() - a.get_x(), c.get_y() - a.get_y());
+    let (b2, c2) = (bx * bx + by * by, cx * cx + cy * cy);
+    let d = 2.0 * (bx * cy - by * cx);
+    Point2d::from_coords(
+        a.get_x() + (cy * b2 - by * c2) / d,
+        a.get_y() + (bx * c2 - cx * b2) / d,
+    )
+}
+
+enum Location {
+    Face(usize),
+    Edge(usize, usize),
+    Vertex(usize),
+}
+
+// Working triangulation closed by ghost triangles, which join each convex
+// hull edge to the vertex at infinity, vertex 0. Edge i of a triangle is the
+// edge opposite its vertex i.
+struct Cdt {
+    points: Vec<Point2d>,
+    // Steiner points on segments remember the input segment they split.
+    segment_of: Vec<Option<[usize; 2]>>,
+    tris: Vec<[usize; 3]>,
+    adj: Vec<[usize; 3]>,
+    fixed: Vec<[bool; 3]>,
+    inside: Vec<bool>,
+    vtri: Vec<usize>,
+    num_input: usize,
+    last: usize,
+    touched: Vec<usize>,
+}
+
+impl Cdt {
+    fn build(domain: &Domain2d) -> Result<Self, &'static str> {
+        if domain
+            .points
+            .iter()
+            .any(|p| !p.get_x().is_finite() || !p.get_y().is_finite())
+        {
+            return Err("triangulation points must be finite");
+        }
+        if domain
+            .segments
+            .iter()
+            .flatten()
+            .any(|&v| v >= domain.points.len())
+        {
+            return Err("segment index out of range");
+        }
+        let mut cdt = Cdt::new(&domain.points);
+        if !cdt.seed() {
+            return Ok(cdt);
+        }
+        let mut vertex = Vec::with_capacity(domain.points.len());
+        for i in 0..domain.points.len() {
+            vertex.push(cdt.insert(FIRST + i, cdt.last)?);
+            cdt.touched.clear();
+        }
+        for s in domain.segments.iter() {
+            cdt.insert_segment(vertex[s[0]], vertex[s[1]])?;
+        }
+        cdt.classify(
+            &domain.holes,
+            !domain.convex_hull && !domain.segments.is_empty(),
+        );
+        cdt.touched.clear();
+        Ok(cdt)
+    }
+
+    fn new(input: &[Point2d]) -> Self {
+        let mut points = vec![Point2d::from_coords(0.0, 0.0)];
+        points.extend_from_slice(input);
+        let n = points.len();
+        Cdt {
+            points,
+            segment_of: vec![None; n],
+            tris: Vec::new(),
+            adj: Vec::new(),
+            fixed: Vec::new(),
+            inside: Vec::new(),
+            vtri: vec![0; n],
+            num_input: input.len(),
+            last: 0,
+            touched: Vec::new(),
+        }
+    }
+
+    // Starts from the first three input points that are not collinear and
+    // the ghost triangles on their edges. False when all points are collinear.
+    fn seed(&mut self) -> bool {
+        let n = self.points.len();
+        let (x, y) = (self.points[FIRST].get_x(), self.points[FIRST].get_y());
+        let Some(b) =
+            (FIRST + 1..n).find(|&v| self.points[v].get_x() != x || self.points[v].get_y() != y)
+        else {
+            return false;
+        };
+        let Some(c) = (b + 1..n).find(|&v| self.orient(FIRST, b, v) != 0.0) else {
+            return false;
+        };
+        let (a, b) = if self.orient(FIRST, b, c) > 0.0 {
+            (FIRST, b)
+        } else {
+            (b, FIRST)
+        };
+        for vertices in [
+            [a, b, c],
+            [b, a, INFINITE],
+            [c, b, INFINITE],
+            [a, c, INFINITE],
+        ] {
+            let t = self.add_tri(false);
+            self.set_tri(t, vertices);
+        }
+        for t in 0..4 {
+            for i in 0..3 {
+                let (x, y) = self.edge(t, i);
+                self.adj[t][i] = (0..4)
+                    .find(|&u| (0..3).any(|k| self.edge(u, k) == (y, x)))
+                    .expect("inconsistent seed triangles");
+            }
+        }
+        true
+    }
+
+    fn is_ghost(&self, t: usize) -> bool {
+        self.tris[t].contains(&INFINITE)
+    }
+
+    fn orient(&self, a: usize, b: usize, c: usize) -> f64 {
+        orient2d(&self.points[a], &self.points[b], &self.points[c])
+    }
+
+    fn edge(&self, t: usize, i: usize) -> (usize, usize) {
+        (self.tris[t][(i + 1) % 3], self.tris[t][(i + 2) % 3])
+    }
+
+    fn index_of(&self, t: usize, v: usize) -> Option<usize> {
+        self.tris[t].iter().position(|&w| w == v)
+    }
+
+    // Index of the edge (b, a) in t.
+    fn opposite(&self, t: usize, a: usize, b: usize) -> usize {
+        (0..3)
+            .find(|&k| self.edge(t, k) == (b, a))
+            .expect("inconsistent triangle adjacency")
+    }
+
+    fn add_tri(&mut self, inside: bool) -> usize {
+        self.tris.push([NONE; 3]);
+        self.adj.push([NONE; 3]);
+        self.fixed.push([false; 3]);
+        self.inside.push(inside);
+        self.tris.len() - 1
+    }
+
+    fn set_tri(&mut self, t: usize, vertices: [usize; 3]) {
+        if vertices.contains(&INFINITE) {
+            self.inside[t] = false;
+        }
+        self.tris[t] = vertices;
+        for v in vertices {
+            self.vtri[v] = t;
+        }
+        self.touched.push(t);
+    }
+
+    fn link(&mut self, t: usize, i: usize, n: usize, fixed: bool) {
+        self.adj[t][i] = n;
+        self.fixed[t][i] = fixed;
+        if n != NONE {
+            let (a, b) = self.edge(t, i);
+            let k = self.opposite(n, a, b);
+            self.adj[n][k] = t;
+            self.fixed[n][k] = fixed;
+        }
+    }
+
+    fn outer(&self, t: usize, i: usize) -> (usize, bool) {
+        (self.adj[t][i], self.fixed[t][i])
+    }
+
+    // Triangles around v in rotational order.
+    fn around(&self, v: usize) -> Vec<usize> {
+        let start = self.vtri[v];
+        let mut fan = vec![start];
+        let mut t = start;
+        loop {
+            let k = self.index_of(t, v).expect("stale vertex triangle");
+            let n = self.adj[t][(k + 1) % 3];
+            if n == start {
+                return fan;
+            }
+            if n == NONE {
+                break;
+            }
+            fan.push(n);
+            t = n;
+        }
+        t = start;
+        loop {
+            let k = self.index_of(t, v).expect("stale vertex triangle");
+            let n = self.adj[t][(k + 2) % 3];
+            if n == NONE {
+                return fan;
+            }
+            fan.insert(0, n);
+            t = n;
+        }
+    }
+
+    fn find_edge(&self, a: usize, b: usize) -> Option<(usize, usize)> {
+        self.around(a).into_iter().find_map(|t| {
+            let k = self.index_of(t, a)?;
+            (self.tris[t][(k + 1) % 3] == b).then_some((t, (k + 2) % 3))
+        })
+    }
+
+    fn set_fixed(&mut self, a: usize, b: usize) -> bool {
+        match self.find_edge(a, b) {
+            Some((t, i)) => {
+                let n = self.adj[t][i];
+                self.link(t, i, n, true);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Walks towards p. With `blocking`, the walk stops at constrained edges
+    // and reports the edge it could not cross.
+    fn walk(&self, p: &Point2d, start: usize, blocking: bool) -> Result<Location, (usize, usize)> {
+        let mut t = if start < self.tris.len() { start } else { 0 };
+        for step in 0..4 * self.tris.len() + 16 {
+            let next = if self.is_ghost(t) {
+                match self.walk_ghost(t, p) {
+                    Ok(location) => return Ok(location),
+                    Err(i) => i,
+                }
+            } else {
+                let mut zeros = Vec::new();
+                let mut next = None;
+                for r in 0..3 {
+                    let i = (r + step) % 3;
+                    let (a, b) = self.edge(t, i);
+                    let o = orient2d(&self.points[a], &self.points[b], p);
+                    if o < 0.0 {
+                        next = Some(i);
+                        break;
+                    }
+                    if o == 0.0 {
+                        zeros.push(i);
+                    }
+                }
+                match next {
+                    Some(i) => i,
+                    None => {
+                        return Ok(match zeros.len() {
+                            0 => Location::Face(t),
+                            1 => Location::Edge(t, zeros[0]),
+                            _ => Location::Vertex(self.tris[t][3 - zeros[0] - zeros[1]]),
+                        });
+                    }
+                }
+            };
+            if self.adj[t][next] == NONE || (blocking && self.fixed[t][next]) {
+                return Err(self.edge(t, next));
+            }
+            t = self.adj[t][next];
+        }
+        // Walks in constrained triangulations can cycle; fall back to a scan.
+        (0..self.tris.len())
+            .find(|&t| match self.index_of(t, INFINITE) {
+                Some(k) => {
+                    let (a, b) = self.edge(t, k);
+                    orient2d(&self.points[a], &self.points[b], p) > 0.0
+                }
+                None => (0..3).all(|i| {
+                    let (a, b) = self.edge(t, i);
+                    orient2d(&self.points[a], &self.points[b], p) > 0.0
+                }),
+            })
+            .map(Location::Face)
+            .ok_or((NONE, NONE))
+    }
+
+    // A ghost triangle holds the open half-plane beyond its hull edge. Points
+    // on the line of the edge but outside it belong to a neighbouring ghost.
+    fn walk_ghost(&self, t: usize, p: &Point2d) -> Result<Location, usize> {
+        let k = self.index_of(t, INFINITE).expect("not a ghost triangle");
+        let (a, b) = self.edge(t, k);
+        let (pa, pb) = (&self.points[a], &self.points[b]);
+        let o = orient2d(pa, pb, p);
+        if o > 0.0 {
+            return Ok(Location::Face(t));
+        }
+        if o < 0.0 {
+            return Err(k);
+        }
+        // Collinear points compare exactly along an axis the edge is not
+        // perpendicular to.
+        let key = |q: &Point2d| {
+            if pa.get_x() != pb.get_x() {
+                q.get_x()
+            } else {
+                q.get_y()
+            }
+        };
+        let (ka, kb, kp) = (key(pa), key(pb), key(p));
+        let forward = ka < kb;
+        if kp == ka {
+            Ok(Location::Vertex(a))
+        } else if kp == kb {
+            Ok(Location::Vertex(b))
+        } else if (kp > kb) == forward {
+            Err((k + 1) % 3)
+        } else if (kp < ka) == forward {
+            Err((k + 2) % 3)
+        } else {
+            Ok(Location::Edge(t, k))
+        }
+    }
+
+    fn insert(&mut self, v: usize, start: usize) -> Result<usize, &'static str> {
+        let location = self
+            .walk(&self.points[v].clone(), start, false)
+            .map_err(|_| "point lies outside the triangulation")?;
+        Ok(self.insert_at(v, location))
+    }
+
+    fn insert_at(&mut self, v: usize, location: Location) -> usize {
+        let fan = match location {
+            Location::Vertex(w) => return w,
+            Location::Face(t) => self.split_face(t, v),
+            Location::Edge(t, i) => self.split_edge(t, i, v),
+        };
+        self.legalize(v, fan);
+        self.last = self.vtri[v];
+        v
+    }
+
+    fn split_face(&mut self, t: usize, v: usize) -> Vec<usize> {
+        let [a, b, c] = self.tris[t];
+        let (na, fa) = self.outer(t, 0);
+        let (nb, fb) = self.outer(t, 1);
+        let (nc, fc) = self.outer(t, 2);
+        let inside = self.inside[t];
+        let (t1, t2) = (self.add_tri(inside), self.add_tri(inside));
+        self.set_tri(t, [a, b, v]);
+        self.set_tri(t1, [b, c, v]);
+        self.set_tri(t2, [c, a, v]);
+        self.link(t, 2, nc, fc);
+        self.link(t1, 2, na, fa);
+        self.link(t2, 2, nb, fb);
+        self.link(t, 0, t1, false);
+        self.link(t1, 0, t2, false);
+        self.link(t2, 0, t, false);
+        vec![t, t1, t2]
+    }
+
+    fn split_edge(&mut self, t: usize, i: usize, v: usize) -> Vec<usize> {
+        let p = self.tris[t][i];
+        let (a, b) = self.edge(t, i);
+        let (u, fixed) = self.outer(t, i);
+        let a1 = self.outer(t, (i + 1) % 3);
+        let a2 = self.outer(t, (i + 2) % 3);
+        let t2 = self.add_tri(self.inside[t]);
+        self.set_tri(t, [p, a, v]);
+        self.set_tri(t2, [p, v, b]);
+        self.link(t, 2, a2.0, a2.1);
+        self.link(t2, 1, a1.0, a1.1);
+        self.link(t, 1, t2, false);
+        if u == NONE {
+            self.link(t, 0, NONE, fixed);
+            self.link(t2, 0, NONE, fixed);
+            return vec![t, t2];
+        }
+        let j = (0..3)
+            .find(|&k| {
+                let (x, y) = self.edge(u, k);
+                x == b && y == a
+            })
+            .expect("inconsistent triangle adjacency");
+        let q = self.tris[u][j];
+        let b1 = self.outer(u, (j + 1) % 3);
+        let b2 = self.outer(u, (j + 2) % 3);
+        let u2 = self.add_tri(self.inside[u]);
+        self.set_tri(u, [q, b, v]);
+        self.set_tri(u2, [q, v, a]);
+        self.link(u, 2, b2.0, b2.1);
+        self.link(u2, 1, b1.0, b1.1);
+        self.link(u, 1, u2, false);
+        self.link(t, 0, u2, fixed);
+        self.link(t2, 0, u, fixed);
+        vec![t, t2, u, u2]
+    }
+
+    // Replaces the diagonal opposite vertex i of t by the other diagonal of
+    // the quadrilateral formed with its neighbour. Returns both triangles,
+    // which keep the apex of t and the opposite apex at index 0.
+    fn flip(&mut self, t: usize, i: usize) -> (usize, usize) {
+        let p = self.tris[t][i];
+        let (a, b) = self.edge(t, i);
+        let u = self.adj[t][i];
+        let j = self.opposite(u, a, b);
+        let q = self.tris[u][j];
+        let a1 = self.outer(t, (i + 1) % 3);
+        let a2 = self.outer(t, (i + 2) % 3);
+        let b1 = self.outer(u, (j + 1) % 3);
+        let b2 = self.outer(u, (j + 2) % 3);
+        self.set_tri(t, [p, a, q]);
+        self.set_tri(u, [q, b, p]);
+        self.link(t, 0, b1.0, b1.1);
+        self.link(t, 2, a2.0, a2.1);
+        self.link(u, 0, a1.0, a1.1);
+        self.link(u, 2, b2.0, b2.1);
+        self.link(t, 1, u, false);
+        (t, u)
+    }
+
+    fn is_delaunay(&self, t: usize, i: usize) -> bool {
+        let u = self.adj[t][i];
+        if u == NONE || self.fixed[t][i] {
+            return true;
+        }
+        let (a, b) = self.edge(t, i);
+        let q = self.tris[u][self.opposite(u, a, b)];
+        if q == INFINITE {
+            return true;
+        }
+        match self.index_of(t, INFINITE) {
+            // The circumcircle of a ghost triangle degenerates to the
+            // half-plane beyond its hull edge.
+            Some(k) => {
+                let (x, y) = self.edge(t, k);
+                self.orient(x, y, q) <= 0.0
+            }
+            None => {
+                let [x, y, z] = self.tris[t];
+                incircle(
+                    &self.points[x],
+                    &self.points[y],
+                    &self.points[z],
+                    &self.points[q],
+                ) <= 0.0
+            }
+        }
+    }
+
+    fn legalize(&mut self, v: usize, mut stack: Vec<usize>) {
+        while let Some(t) = stack.pop() {
+            let Some(k) = self.index_of(t, v) else {
+                continue;
+            };
+            if !self.is_delaunay(t, k) {
+                let (t, u) = self.flip(t, k);
+                stack.push(t);
+                stack.push(u);
+            }
+        }
+    }
+
+    fn insert_segment(&mut self, a: usize, b: usize) -> Result<(), &'static str> {
+        let mut pending = vec![(a, b)];
+        while let Some((a, b)) = pending.pop() {
+            if a == b || self.set_fixed(a, b) {
+                continue;
+            }
+            let (crossed, stop) = self.crossings(a, b)?;
+            if stop != b {
+                pending.push((stop, b));
+            }
+            self.flip_out(a, stop, crossed)?;
+            if !self.set_fixed(a, stop) {
+                return Err("failed to recover constrained segment");
+            }
+        }
+        Ok(())
+    }
+
+    // Edges crossed by the segment from a up to b or the first vertex lying
+    // on it.
+    fn crossings(&self, a: usize, b: usize) -> Result<(VecDeque<Edge>, usize), &'static str> {
+        let (pa, pb) = (self.points[a], self.points[b]);
+        let ahead = |v: usize| {
+            let p = &self.points[v];
+            (p.get_x() - pa.get_x()) * (pb.get_x() - pa.get_x())
+                + (p.get_y() - pa.get_y()) * (pb.get_y() - pa.get_y())
+                > 0.0
+        };
+        let mut crossed = VecDeque::new();
+        let mut current = None;
+        for t in self.around(a) {
+            if self.is_ghost(t) {
+                continue;
+            }
+            let k = self.index_of(t, a).unwrap();
+            let (x, y) = self.edge(t, k);
+            let (ox, oy) = (self.orient(a, b, x), self.orient(a, b, y));
+            if ox == 0.0 && ahead(x) {
+                return Ok((crossed, x));
+            }
+            if oy == 0.0 && ahead(y) {
+                return Ok((crossed, y));
+            }
+            if ox < 0.0 && oy > 0.0 {
+                current = Some((t, x, y));
+                break;
+            }
+        }
+        let (mut t, mut x, mut y) = current.ok_or("failed to trace constrained segment")?;
+        loop {
+            let i = self.opposite(t, y, x);
+            if self.fixed[t][i] {
+                return Err("constrained segments intersect");
+            }
+            crossed.push_back((x, y));
+            let n = self.adj[t][i];
+            if n == NONE || self.is_ghost(n) {
+                return Err("failed to trace constrained segment");
+            }
+            let w = self.tris[n][self.opposite(n, x, y)];
+            if w == b {
+                return Ok((crossed, b));
+            }
+            let o = self.orient(a, b, w);
+            if o == 0.0 {
+                return Ok((crossed, w));
+            }
+            if o < 0.0 {
+                x = w;
+            } else {
+                y = w;
+            }
+            t = n;
+        }
+    }
+
+    // Sloan's edge flipping: removes the crossed edges, then restores the
+    // Delaunay property of the new edges other than the segment a, b.
+    fn flip_out(
+        &mut self,
+        a: usize,
+        b: usize,
+        mut crossed: VecDeque<Edge>,
+    ) -> Result<(), &'static str> {
+        let mut created = Vec::new();
+        let mut guard = 0usize;
+        while let Some((x, y)) = crossed.pop_front() {
+            guard += 1;
+            if guard > 1000 + 100 * self.tris.len() {
+                return Err("failed to recover constrained segment");
+            }
+            let (t, i) = self
+                .find_edge(x, y)
+                .ok_or("failed to recover constrained segment")?;
+            let p = self.tris[t][i];
+            let u = self.adj[t][i];
+            let q = self.tris[u][self.opposite(u, x, y)];
+            let (ox, oy) = (self.orient(p, q, x), self.orient(p, q, y));
+            if !(ox > 0.0 && oy < 0.0 || ox < 0.0 && oy > 0.0) {
+                crossed.push_back((x, y));
+                continue;
+            }
+            self.flip(t, i);
+            let crosses = ![a, b].contains(&p) && ![a, b].contains(&q) && {
+                let (op, oq) = (self.orient(a, b, p), self.orient(a, b, q));
+                op > 0.0 && oq < 0.0 || op < 0.0 && oq > 0.0
+            };
+            if crosses {
+                crossed.push_back((p, q));
+            } else {
+                created.push((p, q));
+            }
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for edge in created.iter_mut() {
+                let (p, q) = *edge;
+                if (p, q) == (a, b) || (q, p) == (a, b) {
+                    continue;
+                }
+                let Some((t, i)) = self.find_edge(p, q) else {
+                    continue;
+                };
+                if !self.is_delaunay(t, i) {
+                    let (t, u) = self.flip(t, i);
+                    *edge = (self.tris[t][0], self.tris[u][0]);
+                    changed = true;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn flood(&mut self, start: usize) {
+        let mut stack = vec![start];
+        while let Some(t) = stack.pop() {
+            if !self.inside[t] {
+                continue;
+            }
+            self.inside[t] = false;
+            for i in 0..3 {
+                let n = self.adj[t][i];
+                if n != NONE && !self.fixed[t][i] && self.inside[n] {
+                    stack.push(n);
+                }
+            }
+        }
+    }
+
+    fn classify(&mut self, holes: &[Point2d], eat_exterior: bool) {
+        for t in 0..self.tris.len() {
+            self.inside[t] = !self.is_ghost(t);
+        }
+        if eat_exterior {
+            // Ghost triangles are already outside; flood inwards from them.
+            for t in 0..self.tris.len() {
+                if self.is_ghost(t) {
+                    for i in 0..3 {
+                        if !self.fixed[t][i] {
+                            self.flood(self.adj[t][i]);
+                        }
+                    }
+                }
+            }
+        }
+        for hole in holes {
+            match self.walk(hole, self.last, false) {
+                Ok(Location::Face(t)) | Ok(Location::Edge(t, _)) => self.flood(t),
+                _ => {}
+            }
+        }
+        // Edges between kept and removed triangles bound the domain.
+        for t in 0..self.tris.len() {
+            for i in 0..3 {
+                let n = self.adj[t][i];
+                if self.inside[t] && (n == NONE || !self.inside[n]) {
+                    self.link(t, i, n, true);
+                }
+            }
+        }
+    }
+
+    fn boundary_apexes(&self, t: usize, i: usize) -> Vec<usize> {
+        let (a, b) = self.edge(t, i);
+        let mut apexes = Vec::new();
+        if self.inside[t] {
+            apexes.push(self.tris[t][i]);
+        }
+        let u = self.adj[t][i];
+        if u != NONE && self.inside[u] {
+            apexes.push(self.tris[u][self.opposite(u, a, b)]);
+        }
+        apexes
+    }
+
+    fn in_diametral_circle(&self, a: usize, b: usize, p: &Point2d) -> bool {
+        let (pa, pb) = (&self.points[a], &self.points[b]);
+        (pa.get_x() - p.get_x()) * (pb.get_x() - p.get_x())
+            + (pa.get_y() - p.get_y()) * (pb.get_y() - p.get_y())
+            < 0.0
+    }
+
+    fn is_encroached(&self, a: usize, b: usize) -> bool {
+        match self.find_edge(a, b) {
+            Some((t, i)) if self.fixed[t][i] => self
+                .boundary_apexes(t, i)
+                .into_iter()
+                .any(|w| self.in_diametral_circle(a, b, &self.points[w])),
+            _ => false,
+        }
+    }
+
+    // Segments encroached by a point about to be inserted in triangle start.
+    fn encroached_by(&self, start: usize, p: &Point2d) -> Vec<(usize, usize)> {
+        let mut result = Vec::new();
+        let mut visited = vec![start];
+        let mut stack = vec![start];
+        while let Some(t) = stack.pop() {
+            for i in 0..3 {
+                let (a, b) = self.edge(t, i);
+                if self.fixed[t][i] {
+                    if self.in_diametral_circle(a, b, p) {
+                        result.push((a, b));
+                    }
+                    continue;
+                }
+                let n = self.adj[t][i];
+                if n == NONE || self.is_ghost(n) || visited.contains(&n) {
+                    continue;
+                }
+                let [x, y, z] = self.tris[n];
+                if incircle(&self.points[x], &self.points[y], &self.points[z], p) > 0.0 {
+                    visited.push(n);
+                    stack.push(n);
+                }
+            }
+        }
+        result
+    }
+
+    fn split_point(&self, a: usize, b: usize) -> Point2d {
+        let (pa, pb) = (self.points[a], self.points[b]);
+        let length = self.distance(a, b);
+        // Concentric shells: split at a power of two distance from an input
+        // vertex so that segments meeting at small angles stop encroaching.
+        let shell = 2f64.powf((0.5 * length).log2().round()) / length;
+        let input = |v: usize| v >= FIRST && v < FIRST + self.num_input;
+        let s = match (input(a), input(b)) {
+            (true, false) => shell,
+            (false, true) => 1.0 - shell,
+            _ => 0.5,
+        };
+        Point2d::from_coords(
+            pa.get_x() + s * (pb.get_x() - pa.get_x()),
+            pa.get_y() + s * (pb.get_y() - pa.get_y()),
+        )
+    }
+
+    fn add_point(&mut self, p: Point2d, segment: Option<[usize; 2]>) -> usize {
+        self.points.push(p);
+        self.segment_of.push(segment);
+        self.vtri.push(0);
+        self.points.len() - 1
+    }
+
+    fn split_segment(&mut self, a: usize, b: usize) -> Option<usize> {
+        let (t, i) = self.find_edge(a, b)?;
+        let origin = self.segment_of[a].or(self.segment_of[b]).unwrap_or([a, b]);
+        let p = self.split_point(a, b);
+        let v = self.add_point(p, Some(origin));
+        let fan = self.split_edge(t, i, v);
+        self.legalize(v, fan);
+        self.last = self.vtri[v];
+        Some(v)
+    }
+
+    fn bad_quality(&self, t: usize, refinement: &Refinement) -> bool {
+        let p = self.tris[t].map(|v| self.points[v]);
+        let area = 0.5 * orient2d(&p[0], &p[1], &p[2]);
+        if refinement.max_area.is_some_and(|max| area > max) {
+            return true;
+        }
+        if refinement.min_angle <= 0.0 {
+            return false;
+        }
+        let lengths = [0, 1, 2].map(|k| {
+            let (u, v) = self.edge(t, k);
+            self.distance(u, v)
+        });
+        let shortest = (0..3)
+            .min_by(|&i, &j| lengths[i].total_cmp(&lengths[j]))
+            .unwrap();
+        // The smallest angle is opposite the shortest edge.
+        let sine = 2.0 * area / (lengths[(shortest + 1) % 3] * lengths[(shortest + 2) % 3]);
+        if sine >= refinement.min_angle.sin() {
+            return false;
+        }
+        !self.is_input_angle(t, shortest)
+    }
+
+    // Small angles between input segments cannot be removed by refinement:
+    // skip triangles whose smallest angle lies between two segments or whose
+    // shortest edge joins splits of two segments sharing an input vertex.
+    fn is_input_angle(&self, t: usize, shortest: usize) -> bool {
+        if self.fixed[t][(shortest + 1) % 3] && self.fixed[t][(shortest + 2) % 3] {
+            return true;
+        }
+        let (u, v) = self.edge(t, shortest);
+        let (Some(su), Some(sv)) = (self.segment_of[u], self.segment_of[v]) else {
+            return false;
+        };
+        match su.iter().find(|w| su != sv && sv.contains(w)) {
+            Some(&w) => {
+                let (du, dv) = (self.distance(w, u), self.distance(w, v));
+                (du - dv).abs() <= 1e-3 * du.max(dv)
+            }
+            None => false,
+        }
+    }
+
+    fn distance(&self, a: usize, b: usize) -> f64 {
+        let (pa, pb) = (&self.points[a], &self.points[b]);
+        (pa.get_x() - pb.get_x()).hypot(pa.get_y() - pb.get_y())
+    }
+
+    fn refine(&mut self, refinement: &Refinement) -> Result<(), &'static str> {
+        let limit = self.points.len() + refinement.max_points;
+        let mut segments: Vec<(usize, usize, bool)> = Vec::new();
+        for t in 0..self.tris.len() {
+            for i in 0..3 {
+                if self.fixed[t][i] && self.inside[t] {
+                    let (a, b) = self.edge(t, i);
+                    segments.push((a, b, false));
+                }
+            }
+        }
+        let mut bad: Vec<usize> = (0..self.tris.len()).collect();
+        self.touched.clear();
+        loop {
+            while let Some((a, b, forced)) = segments.pop() {
+                if !forced && !self.is_encroached(a, b) {
+                    continue;
+                }
+                if self.points.len() >= limit {
+                    return Err("refinement exceeded the Steiner point limit");
+                }
+                if let Some(v) = self.split_segment(a, b) {
+                    segments.push((a, v, false));
+                    segments.push((v, b, false));
+                }
+                self.collect(&mut segments, &mut bad);
+            }
+            let Some(t) = bad.pop() else {
+                break;
+            };
+            if !self.inside[t] || !self.bad_quality(t, refinement) {
+                continue;
+            }
+            let [x, y, z] = self.tris[t];
+            let c = circumcenter(&self.points[x], &self.points[y], &self.points[z]);
+            let location = match self.walk(&c, t, true) {
+                Ok(location) => location,
+                Err((a, b)) => {
+                    if a != NONE {
+                        segments.push((a, b, true));
+                        bad.push(t);
+                    }
+                    continue;
+                }
+            };
+            let start = match location {
+                Location::Face(s) | Location::Edge(s, _) => s,
+                Location::Vertex(_) => continue,
+            };
+            let encroached = self.encroached_by(start, &c);
+            if !encroached.is_empty() {
+                segments.extend(encroached.into_iter().map(|(a, b)| (a, b, true)));
+                bad.push(t);
+                continue;
+            }
+            if self.points.len() >= limit {
+                return Err("refinement exceeded the Steiner point limit");
+            }
+            let v = self.add_point(c, None);
+            self.insert_at(v, location);
+            self.collect(&mut segments, &mut bad);
+        }
+        Ok(())
+    }
+
+    fn collect(&mut self, segments: &mut Vec<(usize, usize, bool)>, bad: &mut Vec<usize>) {
+        let mut touched = std::mem::take(&mut self.touched);
+        touched.sort_unstable();
+        touched.dedup();
+        for &t in touched.iter() {
+            for i in 0..3 {
+                if self.fixed[t][i] && self.inside[t] {
+                    let (a, b) = self.edge(t, i);
+                    segments.push((a, b, false));
+                }
+            }
+            bad.push(t);
+        }
+    }
+
+    fn result(&self) -> Triangulation2d {
+        let mut triangles = Vec::new();
+        let mut segments = Vec::new();
+        for t in 0..self.tris.len() {
+            if !self.inside[t] {
+                continue;
+            }
+            triangles.push(self.tris[t].map(|v| v - FIRST));
+            for i in 0..3 {
+                let n = self.adj[t][i];
+                if self.fixed[t][i] && (n == NONE || !self.inside[n] || t < n) {
+                    let (a, b) = self.edge(t, i);
+                    segments.push([a - FIRST, b - FIRST]);
+                }
+            }
+        }
+        Triangulation2d {
+            points: self.points[FIRST..].to_vec(),
+            triangles,
+            segments,
+        }
+    }
+}
@@ -0,0 +1,940 @@
+use crate::mesh::Mesh;
+use crate::predicates::{insphere, orient2d, orient3d};
+use crate::{Point2d, Point3d};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+const NONE: usize = usize::MAX;
+const INFINITE: usize = 0;
+const FIRST: usize = 1;
+const FLAT: f64 = 1e-10;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Refinement {
+    // Upper bound on circumradius over shortest edge; zero disables the test.
+    // Bounds below 2 may not terminate.
+    pub max_radius_edge: f64,
+    pub max_volume: Option<f64>,
+    // Maximum number of Steiner points inserted before giving up.
+    pub max_points: usize,
+}
+
+impl Default for Refinement {
+    fn default() -> Self {
+        Refinement {
+            max_radius_edge: 2.0,
+            max_volume: None,
+            max_points: 1_000_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Tetrahedralization {
+    // Input points first, in input order, followed by Steiner points.
+    pub points: Vec<Point3d>,
+    // Positively oriented tetrahedra, see `predicates::orient3d`.
+    pub tetrahedra: Vec<[usize; 4]>,
+    // Boundary triangles with the orientation of the input surface, split
+    // where Steiner points were inserted on it.
+    pub boundary: Vec<[usize; 3]>,
+}
+
+impl Tetrahedralization {
+    // Delaunay tetrahedralization of the convex hull of the points.
+    pub fn delaunay(points: &[Point3d]) -> Result<Self, &'static str> {
+        let mut tets = Tets::new(points)?;
+        if tets.seed() {
+            for i in 0..points.len() {
+                tets.insert(FIRST + i)?;
+            }
+        }
+        let inside: Vec<bool> = (0..tets.tets.len())
+            .map(|t| tets.alive[t] && !tets.is_ghost(t))
+            .collect();
+        Ok(tets.result(&inside, &[]))
+    }
+
+    // Tetrahedralizes the volume bounded by a closed triangle surface. Missing
+    // boundary edges and faces are recovered by inserting Steiner points on
+    // the surface, so the result is Delaunay. Nested closed surfaces bound
+    // cavities.
+    pub fn from_surface(surface: &Mesh) -> Result<Self, &'static str> {
+        let (mut tets, mut boundary) = Tets::with_surface(surface)?;
+        let limit = tets.points.len() + Refinement::default().max_points;
+        let pending = (0..boundary.triangles.len()).collect();
+        tets.recover(&mut boundary, pending, limit)?;
+        let inside = tets.classify(&boundary);
+        Ok(tets.result(&inside, &boundary.triangles))
+    }
+
+    pub fn refined(surface: &Mesh, refinement: &Refinement) -> Result<Self, &'static str> {
+        let (mut tets, mut boundary) = Tets::with_surface(surface)?;
+        let limit = tets.points.len() + refinement.max_points;
+        tets.refine(&mut boundary, refinement, limit)?;
+        let inside = tets.classify(&boundary);
+        Ok(tets.result(&inside, &boundary.triangles))
+    }
+
+    pub fn tetrahedron_points(&self, index: usize) -> [Point3d; 4] {
+        self.tetrahedra[index].map(|v| self.points[v])
+    }
+
+    pub fn volume(&self) -> f64 {
+        (0..self.tetrahedra.len())
+            .map(|i| {
+                let [a, b, c, d] = self.tetrahedron_points(i);
+                orient3d(&a, &b, &c, &d) / 6.0
+            })
+            .sum()
+    }
+
+    // Largest ratio of circumradius to shortest edge over all tetrahedra.
+    pub fn max_radius_edge(&self) -> f64 {
+        (0..self.tetrahedra.len())
+            .map(|i| radius_edge(&self.tetrahedron_points(i)))
+            .fold(0.0, f64::max)
+    }
+}
+
+fn sub(a: &Point3d, b: &Point3d) -> [f64; 3] {
+    [
+        a.get_x() - b.get_x(),
+        a.get_y() - b.get_y(),
+        a.get_z() - b.get_z(),
+    ]
+}
+
+fn cross(u: &[f64; 3], v: &[f64; 3]) -> [f64; 3] {
+    [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ]
+}
+
+fn dot(u: &[f64; 3], v: &[f64; 3]) -> f64 {
+    u[0] * v[0] + u[1] * v[1] + u[2] * v[2]
+}
+
+fn distance(a: &Point3d, b: &Point3d) -> f64 {
+    dot(&sub(a, b), &sub(a, b)).sqrt()
+}
+
+fn circumcenter(p: &[Point3d; 4]) -> Point3d {
+    let (b, c, d) = (sub(&p[1], &p[0]), sub(&p[2], &p[0]), sub(&p[3], &p[0]));
+    let (cd, db, bc) = (cross(&c, &d), cross(&d, &b), cross(&b, &c));
+    let denominator = 2.0 * dot(&b, &cd);
+    let (b2, c2, d2) = (dot(&b, &b), dot(&c, &c), dot(&d, &d));
+    let offset = |k: usize| (b2 * cd[k] + c2 * db[k] + d2 * bc[k]) / denominator;
+    Point3d::from_coords(
+        p[0].get_x() + offset(0),
+        p[0].get_y() + offset(1),
+        p[0].get_z() + offset(2),
+    )
+}
+
+fn circumcircle(p: &[Point3d; 3]) -> (Point3d, f64) {
+    let (u, w) = (sub(&p[1], &p[0]), sub(&p[2], &p[0]));
+    let n = cross(&u, &w);
+    let (wn, nu) = (cross(&w, &n), cross(&n, &u));
+    let denominator = 2.0 * dot(&n, &n);
+    let (u2, w2) = (dot(&u, &u), dot(&w, &w));
+    let offset = |k: usize| (u2 * wn[k] + w2 * nu[k]) / denominator;
+    let center = Point3d::from_coords(
+        p[0].get_x() + offset(0),
+        p[0].get_y() + offset(1),
+        p[0].get_z() + offset(2),
+    );
+    (center, distance(&center, &p[0]))
+}
+
+fn radius_edge(p: &[Point3d; 4]) -> f64 {
+    let radius = distance(&circumcenter(p), &p[0]);
+    let shortest = (0..4)
+        .flat_map(|i| (i + 1..4).map(move |j| (i, j)))
+        .map(|(i, j)| distance(&p[i], &p[j]))
+        .fold(f64::MAX, f64::min);
+    radius / shortest
+}
+
+fn sorted3(mut f: [usize; 3]) -> [usize; 3] {
+    f.sort_unstable();
+    f
+}
+
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+// Boundary triangles with an index from edges to the triangles using them.
+struct Boundary {
+    triangles: Vec<[usize; 3]>,
+    edges: HashMap<(usize, usize), Vec<usize>>,
+}
+
+impl Boundary {
+    fn new(triangles: Vec<[usize; 3]>) -> Self {
+        let mut boundary = Boundary {
+            triangles,
+            edges: HashMap::new(),
+        };
+        for t in 0..boundary.triangles.len() {
+            boundary.index(t);
+        }
+        boundary
+    }
+
+    fn index(&mut self, t: usize) {
+        let f = self.triangles[t];
+        for k in 0..3 {
+            self.edges
+                .entry(edge_key(f[k], f[(k + 1) % 3]))
+                .or_default()
+                .push(t);
+        }
+    }
+
+    fn unindex(&mut self, t: usize) {
+        let f = self.triangles[t];
+        for k in 0..3 {
+            let key = edge_key(f[k], f[(k + 1) % 3]);
+            if let Some(list) = self.edges.get_mut(&key) {
+                list.retain(|&u| u != t);
+                if list.is_empty() {
+                    self.edges.remove(&key);
+                }
+            }
+        }
+    }
+
+    // Splits every triangle on the edge a, b at the new vertex m.
+    fn split(&mut self, a: usize, b: usize, m: usize) -> Vec<usize> {
+        let users = self.edges.get(&edge_key(a, b)).cloned().unwrap_or_default();
+        let mut split = Vec::new();
+        for t in users {
+            self.unindex(t);
+            let f = self.triangles[t];
+            let k = (0..3)
+                .find(|&k| edge_key(f[k], f[(k + 1) % 3]) == edge_key(a, b))
+                .unwrap();
+            let (x, y, z) = (f[k], f[(k + 1) % 3], f[(k + 2) % 3]);
+            self.triangles[t] = [x, m, z];
+            self.triangles.push([m, y, z]);
+            self.index(t);
+            self.index(self.triangles.len() - 1);
+            split.extend([t, self.triangles.len() - 1]);
+        }
+        split
+    }
+
+    // Flips the edge a, b between two flat triangles to the other diagonal
+    // of their convex quadrilateral if the pair is not Delaunay in its plane
+    // or only the other diagonal is present.
+    fn flip(
+        &mut self,
+        a: usize,
+        b: usize,
+        points: &[Point3d],
+        present: impl Fn(usize, usize) -> bool,
+    ) -> bool {
+        let Some(users) = self.edges.get(&edge_key(a, b)) else {
+            return false;
+        };
+        let (s, t) = (users[0], users[1]);
+        let opposite = |f: [usize; 3]| f.into_iter().find(|&v| v != a && v != b).unwrap();
+        let (c, d) = (opposite(self.triangles[s]), opposite(self.triangles[t]));
+        if self.edges.contains_key(&edge_key(c, d)) {
+            return false;
+        }
+        let (center, radius) = circumcircle(&[points[a], points[b], points[c]]);
+        // Forcing a present diagonal only when a, b is absent keeps nearly
+        // flat quadrilaterals with both diagonals from flipping back and forth.
+        let forced = present(c, d) && !present(a, b);
+        if !forced && distance(&points[d], &center) >= radius * (1.0 - FLAT) {
+            return false;
+        }
+        // Midpoints of split edges are rounded, so planar regions are only
+        // flat up to a relative tolerance.
+        let n = cross(&sub(&points[b], &points[a]), &sub(&points[c], &points[a]));
+        let height = orient3d(&points[a], &points[b], &points[c], &points[d]).abs();
+        if height > FLAT * dot(&n, &n).sqrt() * distance(&points[c], &points[d]) {
+            return false;
+        }
+        let side = |p: &Point3d| {
+            dot(
+                &n,
+                &cross(&sub(&points[d], &points[c]), &sub(p, &points[c])),
+            )
+        };
+        // Both new triangles must be convex and not needles, or splitting
+        // them later lands on their own vertices.
+        let (sa, sb) = (side(&points[a]), side(&points[b]));
+        let cd = distance(&points[c], &points[d]);
+        let thin = 1e-9 * dot(&n, &n).sqrt() * cd * cd;
+        if sa * sb >= 0.0 || sa.abs() < thin || sb.abs() < thin {
+            return false;
+        }
+        // Keep the orientation: with s = (a, b, c) the quadrilateral runs
+        // a, d, b, c.
+        let f = self.triangles[s];
+        let (a, b) = if (0..3).any(|k| f[k] == a && f[(k + 1) % 3] == b) {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.unindex(s);
+        self.unindex(t);
+        self.triangles[s] = [c, a, d];
+        self.triangles[t] = [d, b, c];
+        self.index(s);
+        self.index(t);
+        true
+    }
+
+    fn find(&self, face: [usize; 3]) -> Option<usize> {
+        self.edges
+            .get(&edge_key(face[0], face[1]))?
+            .iter()
+            .copied()
+            .find(|&u| sorted3(self.triangles[u]) == sorted3(face))
+    }
+
+    fn longest_edge(&self, t: usize, points: &[Point3d]) -> (usize, usize) {
+        let f = self.triangles[t];
+        (0..3)
+            .map(|k| (f[k], f[(k + 1) % 3]))
+            .max_by(|&(a, b), &(c, d)| {
+                distance(&points[a], &points[b]).total_cmp(&distance(&points[c], &points[d]))
+            })
+            .unwrap()
+    }
+}
+
+// Bowyer-Watson tetrahedralization closed by ghost tetrahedra, which join each
+// convex hull face to the vertex at infinity, vertex 0. Face i of a
+// tetrahedron is the face opposite its vertex i.
+struct Tets {
+    points: Vec<Point3d>,
+    tets: Vec<[usize; 4]>,
+    adj: Vec<[usize; 4]>,
+    alive: Vec<bool>,
+    free: Vec<usize>,
+    vtet: Vec<usize>,
+    last: usize,
+    // Faces deleted by insertions since the last boundary recovery.
+    lost: Vec<[usize; 3]>,
+}
+
+impl Tets {
+    fn new(input: &[Point3d]) -> Result<Self, &'static str> {
+        if input
+            .iter()
+            .any(|p| !p.get_x().is_finite() || !p.get_y().is_finite() || !p.get_z().is_finite())
+        {
+            return Err("tetrahedralization points must be finite");
+        }
+        let mut points = vec![Point3d::from_coords(0.0, 0.0, 0.0)];
+        points.extend_from_slice(input);
+        Ok(Tets {
+            vtet: vec![0; points.len()],
+            points,
+            tets: Vec::new(),
+            adj: Vec::new(),
+            alive: Vec::new(),
+            free: Vec::new(),
+            last: 0,
+            lost: Vec::new(),
+        })
+    }
+
+    // Starts from the first four input points that are not coplanar and the
+    // ghost tetrahedra on their faces. False when all points are coplanar.
+    fn seed(&mut self) -> bool {
+        let n = self.points.len();
+        let p = &self.points;
+        let Some(b) = (FIRST + 1..n).find(|&v| !p[v].is_equal(&p[FIRST], 0.0)) else {
+            return false;
+        };
+        // Collinear points are collinear in every coordinate projection.
+        let collinear = |c: usize| {
+            let project = |q: &Point3d, k: usize| {
+                let xyz = [q.get_x(), q.get_y(), q.get_z()];
+                Point2d::from_coords(xyz[k], xyz[(k + 1) % 3])
+            };
+            (0..3).all(|k| {
+                orient2d(
+                    &project(&p[FIRST], k),
+                    &project(&p[b], k),
+                    &project(&p[c], k),
+                ) == 0.0
+            })
+        };
+        let Some(c) = (b + 1..n).find(|&v| !collinear(v)) else {
+            return false;
+        };
+        let Some(d) = (c + 1..n).find(|&v| orient3d(&p[FIRST], &p[b], &p[c], &p[v]) != 0.0) else {
+            return false;
+        };
+        let (a, b) = if orient3d(&p[FIRST], &p[b], &p[c], &p[d]) > 0.0 {
+            (FIRST, b)
+        } else {
+            (b, FIRST)
+        };
+        // A ghost is positive for points beyond its hull face, on the other
+        // side from the vertex it replaces.
+        let real = [a, b, c, d];
+        let mut tets = vec![real];
+        for i in 0..4 {
+            let mut ghost = real;
+            ghost[i] = INFINITE;
+            ghost.swap((i + 1) % 4, (i + 2) % 4);
+            tets.push(ghost);
+        }
+        for vertices in tets.iter() {
+            let t = self.alloc();
+            self.tets[t] = *vertices;
+            self.alive[t] = true;
+            for &w in vertices {
+                self.vtet[w] = t;
+            }
+        }
+        for t in 0..5 {
+            for i in 0..4 {
+                let v = self.tets[t];
+                let face = sorted3([v[(i + 1) % 4], v[(i + 2) % 4], v[(i + 3) % 4]]);
+                self.adj[t][i] = (0..5)
+                    .find(|&u| {
+                        u != t
+                            && (0..4).any(|j| {
+                                let w = self.tets[u];
+                                sorted3([w[(j + 1) % 4], w[(j + 2) % 4], w[(j + 3) % 4]]) == face
+                            })
+                    })
+                    .expect("inconsistent seed tetrahedra");
+            }
+        }
+        true
+    }
+
+    fn is_ghost(&self, t: usize) -> bool {
+        self.tets[t].contains(&INFINITE)
+    }
+
+    fn with_surface(surface: &Mesh) -> Result<(Self, Boundary), &'static str> {
+        let mut triangles = Vec::with_capacity(surface.faces.len());
+        for face in surface.faces.iter() {
+            if face.len() != 3 {
+                return Err("surface faces must be triangles");
+            }
+            if face.iter().any(|&v| v >= surface.points.len()) {
+                return Err("surface face index out of range");
+            }
+            triangles.push([face[0], face[1], face[2]]);
+        }
+        let mut tets = Tets::new(&surface.points)?;
+        if !tets.seed() {
+            return Err("surface points must not be coplanar");
+        }
+        let mut vertex = Vec::with_capacity(surface.points.len());
+        for i in 0..surface.points.len() {
+            vertex.push(tets.insert(FIRST + i)?);
+        }
+        let triangles: Vec<[usize; 3]> = triangles.iter().map(|f| f.map(|v| vertex[v])).collect();
+        let boundary = Boundary::new(triangles);
+        if boundary
+            .triangles
+            .iter()
+            .any(|f| f[0] == f[1] || f[1] == f[2] || f[2] == f[0])
+            || boundary.edges.values().any(|users| users.len() != 2)
+        {
+            return Err("surface must be closed and manifold");
+        }
+        Ok((tets, boundary))
+    }
+
+    // Faces through the vertex at infinity never separate p from a ghost.
+    fn point_in(&self, t: usize, i: usize, p: &Point3d) -> f64 {
+        if self.tets[t][i] != INFINITE && self.is_ghost(t) {
+            return 1.0;
+        }
+        let mut q = self.tets[t].map(|v| &self.points[v]);
+        q[i] = p;
+        orient3d(q[0], q[1], q[2], q[3])
+    }
+
+    // The circumsphere of a ghost degenerates to the half-space beyond its
+    // hull face, plus the circumcircle of the face, which is where the
+    // circumsphere of the tetrahedron behind it meets the plane.
+    fn in_sphere(&self, t: usize, p: &Point3d) -> f64 {
+        if let Some(k) = self.tets[t].iter().position(|&v| v == INFINITE) {
+            let o = self.point_in(t, k, p);
+            return if o != 0.0 {
+                o
+            } else {
+                self.in_sphere(self.adj[t][k], p)
+            };
+        }
+        let q = self.tets[t].map(|v| &self.points[v]);
+        insphere(q[0], q[1], q[2], q[3], p)
+    }
+
+    fn contains(&self, t: usize, p: &Point3d) -> bool {
+        match self.tets[t].iter().position(|&v| v == INFINITE) {
+            Some(k) => self.point_in(t, k, p) > 0.0,
+            None => (0..4).all(|i| self.point_in(t, i, p) >= 0.0),
+        }
+    }
+
+    // Returns a ghost only for points strictly outside the convex hull.
+    fn locate(&self, p: &Point3d, start: usize) -> Result<usize, &'static str> {
+        let mut t = if start < self.tets.len() && self.alive[start] {
+            start
+        } else {
+            (0..self.tets.len()).find(|&t| self.alive[t]).unwrap()
+        };
+        'walk: for step in 0..8 * self.tets.len() + 16 {
+            if let Some(k) = self.tets[t].iter().position(|&v| v == INFINITE) {
+                if self.point_in(t, k, p) > 0.0 {
+                    return Ok(t);
+                }
+                t = self.adj[t][k];
+                continue 'walk;
+            }
+            for r in 0..4 {
+                let i = (r + step) % 4;
+                if self.point_in(t, i, p) < 0.0 {
+                    t = self.adj[t][i];
+                    if t == NONE {
+                        return Err("point lies outside the tetrahedralization");
+                    }
+                    continue 'walk;
+                }
+            }
+            return Ok(t);
+        }
+        (0..self.tets.len())
+            .find(|&t| self.alive[t] && self.contains(t, p))
+            .ok_or("point location failed")
+    }
+
+    // Bowyer-Watson cavity: tetrahedra whose circumsphere contains p, grown
+    // until every boundary face is visible from p.
+    fn cavity(&self, p: &Point3d, start: usize) -> Result<Vec<usize>, &'static str> {
+        let mut cavity = vec![start];
+        let mut member: HashSet<usize> = HashSet::from([start]);
+        let mut stack = vec![start];
+        while let Some(t) = stack.pop() {
+            for &n in self.adj[t].iter() {
+                if n != NONE && !member.contains(&n) && self.in_sphere(n, p) > 0.0 {
+                    member.insert(n);
+                    cavity.push(n);
+                    stack.push(n);
+                }
+            }
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for k in 0..cavity.len() {
+                let t = cavity[k];
+                for i in 0..4 {
+                    let n = self.adj[t][i];
+                    if (n == NONE || !member.contains(&n)) && self.point_in(t, i, p) <= 0.0 {
+                        if n == NONE {
+                            return Err("point lies outside the tetrahedralization");
+                        }
+                        member.insert(n);
+                        cavity.push(n);
+                        changed = true;
+                    }
+                }
+            }
+        }
+        Ok(cavity)
+    }
+
+    fn alloc(&mut self) -> usize {
+        match self.free.pop() {
+            Some(t) => t,
+            None => {
+                self.tets.push([0; 4]);
+                self.adj.push([NONE; 4]);
+                self.alive.push(false);
+                self.tets.len() - 1
+            }
+        }
+    }
+
+    fn fill(&mut self, v: usize, cavity: &[usize]) {
+        let member: HashSet<usize> = cavity.iter().copied().collect();
+        let mut faces = Vec::new();
+        for &t in cavity {
+            for i in 0..4 {
+                let n = self.adj[t][i];
+                if n == NONE || !member.contains(&n) {
+                    let k = if n == NONE {
+                        NONE
+                    } else {
+                        self.adj[n].iter().position(|&m| m == t).unwrap()
+                    };
+                    let mut vertices = self.tets[t];
+                    vertices[i] = v;
+                    faces.push((vertices, i, n, k));
+                }
+            }
+        }
+        for &t in cavity {
+            let vertices = self.tets[t];
+            for i in 0..4 {
+                if member.contains(&self.adj[t][i]) {
+                    self.lost.push(sorted3([
+                        vertices[(i + 1) % 4],
+                        vertices[(i + 2) % 4],
+                        vertices[(i + 3) % 4],
+                    ]));
+                }
+            }
+        }
+        for &t in cavity {
+            self.alive[t] = false;
+            self.free.push(t);
+        }
+        let mut open: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+        for (vertices, i, n, k) in faces {
+            let t = self.alloc();
+            self.tets[t] = vertices;
+            self.adj[t] = [NONE; 4];
+            self.alive[t] = true;
+            self.adj[t][i] = n;
+            if n != NONE {
+                self.adj[n][k] = t;
+            }
+            for w in vertices {
+                self.vtet[w] = t;
+            }
+            for j in (0..4).filter(|&j| j != i) {
+                let others: Vec<usize> = (0..4)
+                    .filter(|&m| m != i && m != j)
+                    .map(|m| vertices[m])
+                    .collect();
+                let key = edge_key(others[0], others[1]);
+                match open.remove(&key) {
+                    Some((u, l)) => {
+                        self.adj[t][j] = u;
+                        self.adj[u][l] = t;
+                    }
+                    None => {
+                        open.insert(key, (t, j));
+                    }
+                }
+            }
+            self.last = t;
+        }
+    }
+
+    // Inserts vertex v and returns it, or the existing vertex at its position.
+    fn insert(&mut self, v: usize) -> Result<usize, &'static str> {
+        let p = self.points[v];
+        let t = self.locate(&p, self.last)?;
+        if let Some(&w) = self.tets[t]
+            .iter()
+            .find(|&&w| w != INFINITE && self.points[w].is_equal(&p, 0.0))
+        {
+            return Ok(w);
+        }
+        let cavity = self.cavity(&p, t)?;
+        self.fill(v, &cavity);
+        Ok(v)
+    }
+
+    fn add_point(&mut self, p: Point3d, limit: usize) -> Result<usize, &'static str> {
+        if self.points.len() >= limit {
+            return Err("refinement exceeded the Steiner point limit");
+        }
+        self.points.push(p);
+        self.vtet.push(0);
+        Ok(self.points.len() - 1)
+    }
+
+    fn split_boundary_edge(
+        &mut self,
+        boundary: &mut Boundary,
+        a: usize,
+        b: usize,
+        limit: usize,
+    ) -> Result<Vec<usize>, &'static str> {
+        if !boundary.edges.contains_key(&edge_key(a, b)) {
+            return Ok(Vec::new());
+        }
+        let (pa, pb) = (self.points[a], self.points[b]);
+        let m = Point3d::from_coords(
+            0.5 * (pa.get_x() + pb.get_x()),
+            0.5 * (pa.get_y() + pb.get_y()),
+            0.5 * (pa.get_z() + pb.get_z()),
+        );
+        let v = self.add_point(m, limit)?;
+        let w = self.insert(v)?;
+        if w != v {
+            // A vertex already sits at the midpoint.
+            self.points.pop();
+            self.vtet.pop();
+            let users = &boundary.edges[&edge_key(a, b)];
+            if users.iter().any(|&u| boundary.triangles[u].contains(&w)) {
+                return Err("boundary edge is too short to split");
+            }
+        }
+        Ok(boundary.split(a, b, w))
+    }
+
+    // Tetrahedra around vertex a.
+    fn star(&self, a: usize) -> Vec<usize> {
+        let mut star = vec![self.vtet[a]];
+        let mut stack = star.clone();
+        while let Some(t) = stack.pop() {
+            for i in (0..4).filter(|&i| self.tets[t][i] != a) {
+                let n = self.adj[t][i];
+                if n != NONE && !star.contains(&n) {
+                    star.push(n);
+                    stack.push(n);
+                }
+            }
+        }
+        star
+    }
+
+    fn has_edge(&self, a: usize, b: usize) -> bool {
+        self.star(a).iter().any(|&t| self.tets[t].contains(&b))
+    }
+
+    fn has_face(&self, f: [usize; 3]) -> bool {
+        self.star(f[0])
+            .iter()
+            .any(|&t| self.tets[t].contains(&f[1]) && self.tets[t].contains(&f[2]))
+    }
+
+    // Recovers the given boundary triangles and those deleted by insertions
+    // since the last call. Coplanar triangles must be Delaunay within their
+    // plane, so an edge whose opposite diagonal is present is flipped;
+    // otherwise a missing edge, or failing that the longest edge, is split.
+    fn recover(
+        &mut self,
+        boundary: &mut Boundary,
+        pending: Vec<usize>,
+        limit: usize,
+    ) -> Result<(), &'static str> {
+        let mut queue = pending;
+        loop {
+            queue.extend(self.lost.drain(..).filter_map(|f| boundary.find(f)));
+            let Some(u) = queue.pop() else {
+                return Ok(());
+            };
+            let f = boundary.triangles[u];
+            if self.has_face(f) {
+                continue;
+            }
+            let flipped = (0..3).find_map(|k| {
+                let (a, b) = (f[k], f[(k + 1) % 3]);
+                let users = boundary.edges[&edge_key(a, b)].clone();
+                boundary
+                    .flip(a, b, &self.points, |c, d| self.has_edge(c, d))
+                    .then_some(users)
+            });
+            if let Some(users) = flipped {
+                queue.extend(users);
+                continue;
+            }
+            let absent: Vec<(usize, usize)> = (0..3)
+                .map(|k| (f[k], f[(k + 1) % 3]))
+                .filter(|&(a, b)| !self.has_edge(a, b))
+                .collect();
+            let (a, b) = match absent.first() {
+                Some(&edge) => edge,
+                None => boundary.longest_edge(u, &self.points),
+            };
+            queue.extend(self.split_boundary_edge(boundary, a, b, limit)?);
+        }
+    }
+
+    // Inside tetrahedra lie an odd number of boundary crossings away from
+    // the ghost tetrahedra.
+    fn classify(&self, boundary: &Boundary) -> Vec<bool> {
+        let walls: HashSet<[usize; 3]> = boundary.triangles.iter().map(|f| sorted3(*f)).collect();
+        let mut depth = vec![usize::MAX; self.tets.len()];
+        let mut queue = VecDeque::new();
+        for t in (0..self.tets.len()).filter(|&t| self.alive[t]) {
+            if self.is_ghost(t) {
+                depth[t] = 0;
+                queue.push_back(t);
+            }
+        }
+        // Zero-one breadth first search over faces, crossing walls costs one.
+        while let Some(t) = queue.pop_front() {
+            let v = self.tets[t];
+            for i in 0..4 {
+                let n = self.adj[t][i];
+                if n == NONE {
+                    continue;
+                }
+                let face = sorted3([v[(i + 1) % 4], v[(i + 2) % 4], v[(i + 3) % 4]]);
+                let cost = walls.contains(&face) as usize;
+                if depth[t] + cost < depth[n] {
+                    depth[n] = depth[t] + cost;
+                    if cost == 0 {
+                        queue.push_front(n);
+                    } else {
+                        queue.push_back(n);
+                    }
+                }
+            }
+        }
+        (0..self.tets.len())
+            .map(|t| self.alive[t] && depth[t] != usize::MAX && depth[t] % 2 == 1)
+            .collect()
+    }
+
+    fn is_bad(&self, t: usize, refinement: &Refinement) -> bool {
+        let p = self.tets[t].map(|v| self.points[v]);
+        let volume = orient3d(&p[0], &p[1], &p[2], &p[3]) / 6.0;
+        refinement.max_volume.is_some_and(|max| volume > max)
+            || refinement.max_radius_edge > 0.0 && radius_edge(&p) > refinement.max_radius_edge
+    }
+
+    // Splits the longest edge of each boundary triangle unless it is no
+    // longer than floor. Returns whether anything was split. The splits may
+    // delete other boundary faces, which must be present for the
+    // encroachment test to hold, so they are recovered at once.
+    fn split_faces(
+        &mut self,
+        boundary: &mut Boundary,
+        faces: &[usize],
+        floor: f64,
+        limit: usize,
+    ) -> Result<bool, &'static str> {
+        let edges: Vec<(usize, usize)> = faces
+            .iter()
+            .map(|&u| boundary.longest_edge(u, &self.points))
+            .filter(|&(a, b)| distance(&self.points[a], &self.points[b]) > floor)
+            .collect();
+        let mut pending = Vec::new();
+        for &(a, b) in edges.iter() {
+            pending.extend(self.split_boundary_edge(boundary, a, b, limit)?);
+        }
+        self.recover(boundary, pending, limit)?;
+        Ok(!edges.is_empty())
+    }
+
+    fn refine(
+        &mut self,
+        boundary: &mut Boundary,
+        refinement: &Refinement,
+        limit: usize,
+    ) -> Result<(), &'static str> {
+        // Tetrahedra that can be improved neither by their circumcenter nor
+        // by splitting their boundary faces.
+        let mut skipped: HashSet<[usize; 4]> = HashSet::new();
+        // Boundary edges are not split far below the input resolution or the
+        // requested size, where sharp dihedral angles would otherwise cascade
+        // indefinitely.
+        let floor = boundary
+            .edges
+            .keys()
+            .map(|&(a, b)| distance(&self.points[a], &self.points[b]))
+            .chain(refinement.max_volume.map(f64::cbrt))
+            .fold(f64::MAX, f64::min)
+            / 16.0;
+        self.recover(boundary, (0..boundary.triangles.len()).collect(), limit)?;
+        loop {
+            let inside = self.classify(boundary);
+            let bad: Vec<(usize, [usize; 4])> = (0..self.tets.len())
+                .filter(|&t| inside[t] && self.is_bad(t, refinement))
+                .filter(|&t| !skipped.contains(&self.tets[t]))
+                .map(|t| (t, self.tets[t]))
+                .collect();
+            if bad.is_empty() {
+                return Ok(());
+            }
+            for (t, vertices) in bad {
+                if !self.alive[t] || self.tets[t] != vertices {
+                    continue;
+                }
+                let p = vertices.map(|v| self.points[v]);
+                let mut c = circumcenter(&p);
+                // Circumcenters of nearly flat tetrahedra are inaccurate.
+                if ![c.get_x(), c.get_y(), c.get_z()]
+                    .iter()
+                    .all(|v| v.is_finite())
+                    || self.in_sphere(t, &c) <= 0.0
+                    || !self.locate(&c, t).is_ok_and(|s| !self.is_ghost(s))
+                {
+                    let faces: Vec<usize> = (0..4)
+                        .filter_map(|i| {
+                            boundary.find([
+                                vertices[(i + 1) % 4],
+                                vertices[(i + 2) % 4],
+                                vertices[(i + 3) % 4],
+                            ])
+                        })
+                        .collect();
+                    if !faces.is_empty() {
+                        if !self.split_faces(boundary, &faces, floor, limit)? {
+                            skipped.insert(vertices);
+                        }
+                        continue;
+                    }
+                    c = Point3d::from_coords(
+                        p.iter().map(|q| q.get_x()).sum::<f64>() / 4.0,
+                        p.iter().map(|q| q.get_y()).sum::<f64>() / 4.0,
+                        p.iter().map(|q| q.get_z()).sum::<f64>() / 4.0,
+                    );
+                }
+                let start = self.locate(&c, t)?;
+                if self.tets[start]
+                    .iter()
+                    .any(|&w| self.points[w].is_equal(&c, 0.0))
+                {
+                    skipped.insert(vertices);
+                    continue;
+                }
+                let cavity = self.cavity(&c, start)?;
+                // Inserting c must not delete boundary triangles; split the
+                // triangles it encroaches upon instead.
+                let member: HashSet<usize> = cavity.iter().copied().collect();
+                let mut encroached = Vec::new();
+                for &s in cavity.iter() {
+                    let v = self.tets[s];
+                    for i in 0..4 {
+                        let face = [v[(i + 1) % 4], v[(i + 2) % 4], v[(i + 3) % 4]];
+                        let Some(u) = boundary.find(face) else {
+                            continue;
+                        };
+                        let (center, radius) = circumcircle(&face.map(|w| self.points[w]));
+                        if member.contains(&self.adj[s][i]) || distance(&c, &center) < radius {
+                            encroached.push(u);
+                        }
+                    }
+                }
+                if encroached.is_empty() {
+                    let v = self.add_point(c, limit)?;
+                    self.fill(v, &cavity);
+                    continue;
+                }
+                encroached.sort_unstable();
+                encroached.dedup();
+                if !self.split_faces(boundary, &encroached, floor, limit)? {
+                    skipped.insert(vertices);
+                }
+            }
+        }
+    }
+
+    fn result(&self, inside: &[bool], boundary: &[[usize; 3]]) -> Tetrahedralization {
+        Tetrahedralization {
+            points: self.points[FIRST..].to_vec(),
+            tetrahedra: (0..self.tets.len())
+                .filter(|&t| inside[t])
+                .map(|t| self.tets[t].map(|v| v - FIRST))
+                .collect(),
+            boundary: boundary.iter().map(|f| f.map(|v| v - FIRST)).collect(),
+        }
+    }
+}
@@ -2,6 +2,7 @@ use crate::traits::FloatWithConst;
 use crate::XY;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
 pub struct Direction2d<T = f64> {
     pub xy: XY<T>,
 }
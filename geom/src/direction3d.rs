@@ -2,6 +2,7 @@ use crate::XYZ;
 use crate::traits::FloatWithConst;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
 pub struct Direction3d<T = f64> {
     pub xyz: XYZ<T>,
 }
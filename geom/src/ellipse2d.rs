@@ -1,7 +1,8 @@
 use crate::CoordinateSystem2d;
 
 #[derive(Debug, Clone, Copy)]
-pub struct Ellipse3d<T = f64> {
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Ellipse2d<T = f64> {
     pub position: CoordinateSystem2d<T>,
     pub major_radius: T,
     pub minor_radius: T,
@@ -1,8 +1,11 @@
 use crate::Axis3d;
+use crate::CoordinateSystem3d;
 use crate::Direction3d;
+use crate::Point3d;
 use crate::traits::FloatWithConst;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct GeneralCoordinateSystem3d<T = f64> {
     pub axis: Axis3d<T>,
     pub vydir: Direction3d<T>,
@@ -21,3 +24,42 @@ where
         )
     }
 }
+
+impl<T> GeneralCoordinateSystem3d<T>
+where
+    T: Copy + Default + FloatWithConst,
+{
+    pub fn from_location_direction_xdirection<P, N, VX>(
+        location: P,
+        direction: N,
+        xdirection: VX,
+    ) -> Self
+    where
+        P: Into<Point3d<T>>,
+        N: Into<Direction3d<T>>,
+        VX: Into<Direction3d<T>>,
+    {
+        let direction = direction.into();
+        let vxdir = direction.cross_cross_new(&xdirection.into(), &direction);
+        let vydir = direction.cross_new(&vxdir);
+        GeneralCoordinateSystem3d {
+            axis: Axis3d::from_location_direction(location, direction),
+            vydir,
+            vxdir,
+        }
+    }
+
+    pub fn get_origin(&self) -> &Point3d<T> {
+        &self.axis.location
+    }
+}
+
+impl<T> From<CoordinateSystem3d<T>> for GeneralCoordinateSystem3d<T> {
+    fn from(cs: CoordinateSystem3d<T>) -> Self {
+        GeneralCoordinateSystem3d {
+            axis: cs.axis,
+            vydir: cs.vydir,
+            vxdir: cs.vxdir,
+        }
+    }
+}
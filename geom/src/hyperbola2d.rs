@@ -1,6 +1,7 @@
 use crate::CoordinateSystem2d;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Hyperbola2d<T = f64> {
     pub position: CoordinateSystem2d<T>,
     pub major_radius: T,
@@ -1,6 +1,7 @@
 use crate::CoordinateSystem3d;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Hyperbola3d<T = f64> {
     pub position: CoordinateSystem3d<T>,
     pub major_radius: T,
@@ -0,0 +1,874 @@
+use super::step::StepGeometry;
+use super::{Error, Result};
+use crate::mesh::{Attribute, AttributeType, AttributeValues, Mesh};
+use crate::{
+    Axis3d, BSplineCurve3d, BSplineSurface, Circle3d, Cone, CoordinateSystem3d, Cylinder,
+    Direction3d, Ellipse3d, GeneralCoordinateSystem3d, Hyperbola3d, Line3d, Parabola3d, Plane,
+    Point3d, Sphere, Torus, Vector3d,
+};
+use std::io::Write;
+
+pub const MAGIC: [u8; 8] = *b"GEOMARC\0";
+pub const VERSION_MAJOR: u16 = 1;
+pub const VERSION_MINOR: u16 = 0;
+
+pub const KIND_GEOMETRY: u32 = 1;
+pub const KIND_POINTS: u32 = 2;
+pub const KIND_MESH: u32 = 3;
+
+// Readers that do not know a record kind must fail instead of skipping it.
+pub const FLAG_REQUIRED: u32 = 1;
+
+const HEADER_SIZE: usize = 16;
+const RECORD_HEADER_SIZE: usize = 24;
+
+fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    for (i, entry) in table.iter_mut().enumerate() {
+        let mut c = i as u32;
+        for _ in 0..8 {
+            c = if c & 1 != 0 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+        }
+        *entry = c;
+    }
+    table
+}
+
+// CRC-32 as used by zip and PNG.
+pub fn crc32(data: &[u8]) -> u32 {
+    let table = crc_table();
+    let mut crc = 0xFFFF_FFFFu32;
+    for &b in data {
+        crc = table[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    crc ^ 0xFFFF_FFFF
+}
+
+fn padding(len: usize) -> usize {
+    (8 - len % 8) % 8
+}
+
+struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    fn new() -> Self {
+        Encoder { buf: Vec::new() }
+    }
+
+    fn u8(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn u64(&mut self, v: u64) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn f64(&mut self, v: f64) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn str(&mut self, s: &str) {
+        self.u32(s.len() as u32);
+        self.buf.extend_from_slice(s.as_bytes());
+    }
+
+    fn align(&mut self) {
+        let n = padding(self.buf.len());
+        self.buf.resize(self.buf.len() + n, 0);
+    }
+
+    fn xyz(&mut self, x: f64, y: f64, z: f64) {
+        self.f64(x);
+        self.f64(y);
+        self.f64(z);
+    }
+
+    fn point(&mut self, p: &Point3d) {
+        self.xyz(p.get_x(), p.get_y(), p.get_z());
+    }
+
+    fn direction(&mut self, d: &Direction3d) {
+        self.xyz(d.get_x(), d.get_y(), d.get_z());
+    }
+
+    fn axis(&mut self, axis: &Axis3d) {
+        self.point(&axis.location);
+        self.direction(&axis.direction);
+    }
+
+    fn frame(&mut self, axis: &Axis3d, vxdir: &Direction3d, vydir: &Direction3d) {
+        self.axis(axis);
+        self.direction(vxdir);
+        self.direction(vydir);
+    }
+
+    fn reals(&mut self, values: &[f64]) {
+        self.u32(values.len() as u32);
+        for &v in values {
+            self.f64(v);
+        }
+    }
+
+    fn counts(&mut self, values: &[usize]) {
+        self.u32(values.len() as u32);
+        for &v in values {
+            self.u32(v as u32);
+        }
+    }
+
+    // Point arrays start on an 8-byte boundary so that they can be mapped directly.
+    fn points(&mut self, points: &[Point3d]) {
+        self.align();
+        self.u64(points.len() as u64);
+        for p in points {
+            self.point(p);
+        }
+    }
+
+    fn value(&mut self, data_type: AttributeType, v: f64) {
+        match data_type {
+            AttributeType::I8 => self.buf.push(v as i8 as u8),
+            AttributeType::U8 => self.buf.push(v as u8),
+            AttributeType::I16 => self.buf.extend_from_slice(&(v as i16).to_le_bytes()),
+            AttributeType::U16 => self.buf.extend_from_slice(&(v as u16).to_le_bytes()),
+            AttributeType::I32 => self.buf.extend_from_slice(&(v as i32).to_le_bytes()),
+            AttributeType::U32 => self.u32(v as u32),
+            AttributeType::F32 => self.buf.extend_from_slice(&(v as f32).to_le_bytes()),
+            AttributeType::F64 => self.f64(v),
+        }
+    }
+
+    fn attributes(&mut self, attributes: &[Attribute]) {
+        self.u32(attributes.len() as u32);
+        for attribute in attributes {
+            self.str(&attribute.name);
+            self.u8(type_code(attribute.data_type));
+            match &attribute.values {
+                AttributeValues::Scalar(values) => {
+                    self.u8(0);
+                    self.u64(values.len() as u64);
+                    for &v in values {
+                        self.value(attribute.data_type, v);
+                    }
+                }
+                AttributeValues::List(lists) => {
+                    self.u8(1);
+                    self.u64(lists.len() as u64);
+                    for list in lists {
+                        self.u32(list.len() as u32);
+                        for &v in list {
+                            self.value(attribute.data_type, v);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn geometry(&mut self, geometry: &StepGeometry) {
+        match geometry {
+            StepGeometry::Point(p) => {
+                self.u32(0);
+                self.point(p);
+            }
+            StepGeometry::Direction(d) => {
+                self.u32(1);
+                self.direction(d);
+            }
+            StepGeometry::Vector(v) => {
+                self.u32(2);
+                self.xyz(v.get_x(), v.get_y(), v.get_z());
+            }
+            StepGeometry::Axis(a) => {
+                self.u32(3);
+                self.axis(a);
+            }
+            StepGeometry::Placement(cs) => {
+                self.u32(4);
+                self.frame(&cs.axis, &cs.vxdir, &cs.vydir);
+            }
+            StepGeometry::Line(l) => {
+                self.u32(5);
+                self.axis(&l.pos);
+            }
+            StepGeometry::Circle(c) => {
+                self.u32(6);
+                self.frame(&c.position.axis, &c.position.vxdir, &c.position.vydir);
+                self.f64(c.radius);
+            }
+            StepGeometry::Ellipse(e) => {
+                self.u32(7);
+                self.frame(&e.position.axis, &e.position.vxdir, &e.position.vydir);
+                self.f64(e.major_radius);
+                self.f64(e.minor_radius);
+            }
+            StepGeometry::Hyperbola(h) => {
+                self.u32(8);
+                self.frame(&h.position.axis, &h.position.vxdir, &h.position.vydir);
+                self.f64(h.major_radius);
+                self.f64(h.minor_radius);
+            }
+            StepGeometry::Parabola(p) => {
+                self.u32(9);
+                self.frame(&p.pos.axis, &p.pos.vxdir, &p.pos.vydir);
+                self.f64(p.focal_length);
+            }
+            StepGeometry::Plane(p) => {
+                self.u32(10);
+                self.frame(&p.pos.axis, &p.pos.vxdir, &p.pos.vydir);
+            }
+            StepGeometry::CylindricalSurface(c) => {
+                self.u32(11);
+                self.frame(&c.position.axis, &c.position.vxdir, &c.position.vydir);
+                self.f64(c.radius);
+            }
+            StepGeometry::ConicalSurface(c) => {
+                self.u32(12);
+                self.frame(&c.position.axis, &c.position.vxdir, &c.position.vydir);
+                self.f64(c.radius);
+                self.f64(c.semi_angle);
+            }
+            StepGeometry::SphericalSurface(s) => {
+                self.u32(13);
+                self.frame(&s.pos.axis, &s.pos.vxdir, &s.pos.vydir);
+                self.f64(s.radius);
+            }
+            StepGeometry::ToroidalSurface(t) => {
+                self.u32(14);
+                self.frame(&t.pos.axis, &t.pos.vxdir, &t.pos.vydir);
+                self.f64(t.major_radius);
+                self.f64(t.minor_radius);
+            }
+            StepGeometry::BSplineCurve(c) => {
+                self.u32(15);
+                self.u32(c.degree as u32);
+                self.points(&c.poles);
+                match &c.weights {
+                    Some(w) => {
+                        self.u8(1);
+                        self.reals(w);
+                    }
+                    None => self.u8(0),
+                }
+                self.reals(&c.knots);
+                self.counts(&c.multiplicities);
+            }
+            StepGeometry::BSplineSurface(s) => {
+                self.u32(16);
+                self.u32(s.u_degree as u32);
+                self.u32(s.v_degree as u32);
+                self.u32(s.poles.len() as u32);
+                for row in s.poles.iter() {
+                    self.points(row);
+                }
+                match &s.weights {
+                    Some(w) => {
+                        self.u8(1);
+                        for row in w.iter() {
+                            self.reals(row);
+                        }
+                    }
+                    None => self.u8(0),
+                }
+                self.reals(&s.u_knots);
+                self.counts(&s.u_multiplicities);
+                self.reals(&s.v_knots);
+                self.counts(&s.v_multiplicities);
+            }
+        }
+    }
+}
+
+fn type_code(data_type: AttributeType) -> u8 {
+    match data_type {
+        AttributeType::I8 => 0,
+        AttributeType::U8 => 1,
+        AttributeType::I16 => 2,
+        AttributeType::U16 => 3,
+        AttributeType::I32 => 4,
+        AttributeType::U32 => 5,
+        AttributeType::F32 => 6,
+        AttributeType::F64 => 7,
+    }
+}
+
+fn type_from_code(code: u8) -> Result<AttributeType> {
+    Ok(match code {
+        0 => AttributeType::I8,
+        1 => AttributeType::U8,
+        2 => AttributeType::I16,
+        3 => AttributeType::U16,
+        4 => AttributeType::I32,
+        5 => AttributeType::U32,
+        6 => AttributeType::F32,
+        7 => AttributeType::F64,
+        _ => return Err(Error::Parse(format!("unknown attribute type {}", code))),
+    })
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::Parse(msg.to_string())
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PointArray<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> PointArray<'a> {
+    pub fn len(&self) -> usize {
+        self.data.len() / 24
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    // Raw little-endian x, y, z doubles borrowed from the archive buffer.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn get_coords(&self, index: usize) -> Option<[f64; 3]> {
+        let chunk = self.data.get(index * 24..index * 24 + 24)?;
+        let mut xyz = [0.0; 3];
+        for (k, v) in xyz.iter_mut().enumerate() {
+            *v = f64::from_le_bytes(chunk[k * 8..k * 8 + 8].try_into().unwrap());
+        }
+        Some(xyz)
+    }
+
+    pub fn get(&self, index: usize) -> Option<Point3d> {
+        self.get_coords(index)
+            .map(|[x, y, z]| Point3d::from_coords(x, y, z))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Point3d> + 'a {
+        let points = *self;
+        (0..points.len()).map(move |i| points.get(i).unwrap())
+    }
+
+    pub fn to_vec(&self) -> Vec<Point3d> {
+        self.iter().collect()
+    }
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Decoder { data, pos: 0 }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| invalid("unexpected end of record"))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f64(&mut self) -> Result<f64> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn str(&mut self) -> Result<String> {
+        let n = self.u32()? as usize;
+        String::from_utf8(self.take(n)?.to_vec()).map_err(|_| invalid("name is not valid UTF-8"))
+    }
+
+    fn align(&mut self) -> Result<()> {
+        self.take(padding(self.pos))?;
+        Ok(())
+    }
+
+    // Guards allocations against counts that cannot fit in the remaining data.
+    fn count(&mut self, n: u64, size: usize) -> Result<usize> {
+        let n = usize::try_from(n).map_err(|_| invalid("count out of range"))?;
+        match n.checked_mul(size) {
+            Some(bytes) if bytes <= self.data.len() - self.pos => Ok(n),
+            _ => Err(invalid("count exceeds record length")),
+        }
+    }
+
+    fn point(&mut self) -> Result<Point3d> {
+        Ok(Point3d::from_coords(self.f64()?, self.f64()?, self.f64()?))
+    }
+
+    fn direction(&mut self) -> Result<Direction3d> {
+        let (x, y, z) = (self.f64()?, self.f64()?, self.f64()?);
+        let length = (x * x + y * y + z * z).sqrt();
+        if !length.is_finite() || length == 0.0 {
+            return Err(invalid("direction must be finite and non-zero"));
+        }
+        Ok(Direction3d::from_coords(x, y, z))
+    }
+
+    fn axis(&mut self) -> Result<Axis3d> {
+        Ok(Axis3d {
+            location: self.point()?,
+            direction: self.direction()?,
+        })
+    }
+
+    fn frame(&mut self) -> Result<GeneralCoordinateSystem3d> {
+        let axis = self.axis()?;
+        let vxdir = self.direction()?;
+        let vydir = self.direction()?;
+        Ok(GeneralCoordinateSystem3d { axis, vydir, vxdir })
+    }
+
+    fn placement(&mut self) -> Result<CoordinateSystem3d> {
+        let frame = self.frame()?;
+        Ok(CoordinateSystem3d {
+            axis: frame.axis,
+            vydir: frame.vydir,
+            vxdir: frame.vxdir,
+        })
+    }
+
+    fn reals(&mut self) -> Result<Vec<f64>> {
+        let n = self.u32()? as u64;
+        let n = self.count(n, 8)?;
+        (0..n).map(|_| self.f64()).collect()
+    }
+
+    fn counts(&mut self) -> Result<Vec<usize>> {
+        let n = self.u32()? as u64;
+        let n = self.count(n, 4)?;
+        (0..n).map(|_| Ok(self.u32()? as usize)).collect()
+    }
+
+    fn point_array(&mut self) -> Result<PointArray<'a>> {
+        self.align()?;
+        let n = self.u64()?;
+        let n = self.count(n, 24)?;
+        Ok(PointArray {
+            data: self.take(n * 24)?,
+        })
+    }
+
+    fn value(&mut self, data_type: AttributeType) -> Result<f64> {
+        let bytes = self.take(data_type.size())?;
+        Ok(match data_type {
+            AttributeType::I8 => bytes[0] as i8 as f64,
+            AttributeType::U8 => bytes[0] as f64,
+            AttributeType::I16 => i16::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            AttributeType::U16 => u16::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            AttributeType::I32 => i32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            AttributeType::U32 => u32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            AttributeType::F32 => f32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            AttributeType::F64 => f64::from_le_bytes(bytes.try_into().unwrap()),
+        })
+    }
+
+    fn attributes(&mut self) -> Result<Vec<Attribute>> {
+        let n = self.u32()? as u64;
+        let n = self.count(n, 6)?;
+        let mut attributes = Vec::with_capacity(n);
+        for _ in 0..n {
+            let name = self.str()?;
+            let data_type = type_from_code(self.u8()?)?;
+            let values = match self.u8()? {
+                0 => {
+                    let n = self.u64()?;
+                    let n = self.count(n, data_type.size())?;
+                    AttributeValues::Scalar(
+                        (0..n)
+                            .map(|_| self.value(data_type))
+                            .collect::<Result<_>>()?,
+                    )
+                }
+                1 => {
+                    let n = self.u64()?;
+                    let n = self.count(n, 4)?;
+                    let mut lists = Vec::with_capacity(n);
+                    for _ in 0..n {
+                        let m = self.u32()? as u64;
+                        let m = self.count(m, data_type.size())?;
+                        lists.push(
+                            (0..m)
+                                .map(|_| self.value(data_type))
+                                .collect::<Result<_>>()?,
+                        );
+                    }
+                    AttributeValues::List(lists)
+                }
+                k => return Err(Error::Parse(format!("unknown attribute layout {}", k))),
+            };
+            attributes.push(Attribute {
+                name,
+                data_type,
+                values,
+            });
+        }
+        Ok(attributes)
+    }
+
+    fn geometry(&mut self) -> Result<StepGeometry> {
+        let tag = self.u32()?;
+        Ok(match tag {
+            0 => StepGeometry::Point(self.point()?),
+            1 => StepGeometry::Direction(self.direction()?),
+            2 => StepGeometry::Vector(Vector3d::from_coords(self.f64()?, self.f64()?, self.f64()?)),
+            3 => StepGeometry::Axis(self.axis()?),
+            4 => StepGeometry::Placement(self.placement()?),
+            5 => StepGeometry::Line(Line3d { pos: self.axis()? }),
+            6 => StepGeometry::Circle(Circle3d {
+                position: self.placement()?,
+                radius: self.f64()?,
+            }),
+            7 => StepGeometry::Ellipse(Ellipse3d {
+                position: self.placement()?,
+                major_radius: self.f64()?,
+                minor_radius: self.f64()?,
+            }),
+            8 => StepGeometry::Hyperbola(Hyperbola3d {
+                position: self.placement()?,
+                major_radius: self.f64()?,
+                minor_radius: self.f64()?,
+            }),
+            9 => StepGeometry::Parabola(Parabola3d {
+                pos: self.placement()?,
+                focal_length: self.f64()?,
+            }),
+            10 => StepGeometry::Plane(Plane { pos: self.frame()? }),
+            11 => StepGeometry::CylindricalSurface(Cylinder {
+                position: self.frame()?,
+                radius: self.f64()?,
+            }),
+            12 => StepGeometry::ConicalSurface(Cone {
+                position: self.frame()?,
+                radius: self.f64()?,
+                semi_angle: self.f64()?,
+            }),
+            13 => StepGeometry::SphericalSurface(Sphere {
+                pos: self.frame()?,
+                radius: self.f64()?,
+            }),
+            14 => StepGeometry::ToroidalSurface(Torus {
+                pos: self.frame()?,
+                major_radius: self.f64()?,
+                minor_radius: self.f64()?,
+            }),
+            15 => {
+                let degree = self.u32()? as usize;
+                let poles = self.point_array()?.to_vec();
+                let weights = match self.u8()? {
+                    0 => None,
+                    _ => Some(self.reals()?),
+                };
+                let knots = self.reals()?;
+                let multiplicities = self.counts()?;
+                let curve = match weights {
+                    Some(w) => BSplineCurve3d::from_poles_weights_knots(
+                        degree,
+                        poles,
+                        w,
+                        knots,
+                        multiplicities,
+                    ),
+                    None => BSplineCurve3d::from_poles_knots(degree, poles, knots, multiplicities),
+                };
+                StepGeometry::BSplineCurve(curve.map_err(invalid)?)
+            }
+            16 => {
+                let degrees = (self.u32()? as usize, self.u32()? as usize);
+                let n = self.u32()? as u64;
+                let n = self.count(n, 8)?;
+                let poles = (0..n)
+                    .map(|_| Ok(self.point_array()?.to_vec()))
+                    .collect::<Result<Vec<_>>>()?;
+                let weights = match self.u8()? {
+                    0 => None,
+                    _ => Some((0..n).map(|_| self.reals()).collect::<Result<Vec<_>>>()?),
+                };
+                let u_knots = (self.reals()?, self.counts()?);
+                let v_knots = (self.reals()?, self.counts()?);
+                let mut surface =
+                    BSplineSurface::from_poles_knots(degrees, poles, u_knots, v_knots)
+                        .map_err(invalid)?;
+                if let Some(w) = weights {
+                    surface.set_weights(w).map_err(invalid)?;
+                }
+                StepGeometry::BSplineSurface(surface)
+            }
+            _ => return Err(Error::Parse(format!("unknown geometry tag {}", tag))),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Item<'a> {
+    Geometry {
+        name: String,
+        geometry: StepGeometry,
+    },
+    Points {
+        name: String,
+        points: PointArray<'a>,
+    },
+    Mesh {
+        name: String,
+        mesh: Mesh,
+    },
+}
+
+impl Item<'_> {
+    pub fn name(&self) -> &str {
+        match self {
+            Item::Geometry { name, .. } | Item::Points { name, .. } | Item::Mesh { name, .. } => {
+                name
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Record<'a> {
+    pub kind: u32,
+    pub flags: u32,
+    pub payload: &'a [u8],
+}
+
+impl<'a> Record<'a> {
+    pub fn decode(&self) -> Result<Item<'a>> {
+        let mut d = Decoder::new(self.payload);
+        let name = d.str()?;
+        let item = match self.kind {
+            KIND_GEOMETRY => Item::Geometry {
+                name,
+                geometry: d.geometry()?,
+            },
+            KIND_POINTS => Item::Points {
+                name,
+                points: d.point_array()?,
+            },
+            KIND_MESH => {
+                let points = d.point_array()?.to_vec();
+                let n = d.u64()?;
+                let n = d.count(n, 8)?;
+                let offsets = (0..=n)
+                    .map(|_| Ok(d.u64()? as usize))
+                    .collect::<Result<Vec<_>>>()?;
+                let total = d.count(offsets[n] as u64, 4)?;
+                let indices = (0..total)
+                    .map(|_| Ok(d.u32()? as usize))
+                    .collect::<Result<Vec<_>>>()?;
+                if offsets.windows(2).any(|w| w[0] > w[1]) {
+                    return Err(invalid("mesh face offsets are not increasing"));
+                }
+                let faces = offsets
+                    .windows(2)
+                    .map(|w| indices[w[0]..w[1]].to_vec())
+                    .collect();
+                let mut mesh = Mesh::from_points_faces(points, faces);
+                mesh.point_attributes = d.attributes()?;
+                mesh.face_attributes = d.attributes()?;
+                if !mesh.is_valid() {
+                    return Err(invalid("mesh record is inconsistent"));
+                }
+                Item::Mesh { name, mesh }
+            }
+            kind => return Err(Error::Unsupported(format!("record kind {}", kind))),
+        };
+        Ok(item)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Archive<'a> {
+    pub version: (u16, u16),
+    pub flags: u32,
+    pub records: Vec<Record<'a>>,
+    // Kinds of optional records this reader does not understand.
+    pub skipped: Vec<u32>,
+}
+
+impl<'a> Archive<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Self> {
+        if data.len() < HEADER_SIZE || data[..8] != MAGIC {
+            return Err(invalid("not a geometry archive"));
+        }
+        let major = u16::from_le_bytes([data[8], data[9]]);
+        let minor = u16::from_le_bytes([data[10], data[11]]);
+        if major != VERSION_MAJOR {
+            return Err(Error::Unsupported(format!(
+                "archive version {}.{}",
+                major, minor
+            )));
+        }
+        let flags = u32::from_le_bytes(data[12..16].try_into().unwrap());
+        let mut archive = Archive {
+            version: (major, minor),
+            flags,
+            records: Vec::new(),
+            skipped: Vec::new(),
+        };
+        let mut pos = HEADER_SIZE;
+        while pos < data.len() {
+            if data.len() - pos < RECORD_HEADER_SIZE {
+                return Err(invalid("truncated record header"));
+            }
+            let mut d = Decoder::new(&data[pos..]);
+            let (kind, record_flags, length, crc) = (d.u32()?, d.u32()?, d.u64()?, d.u32()?);
+            let start = pos + RECORD_HEADER_SIZE;
+            let payload = usize::try_from(length)
+                .ok()
+                .and_then(|n| data.get(start..start.checked_add(n)?))
+                .ok_or_else(|| invalid("truncated record payload"))?;
+            if crc32(payload) != crc {
+                return Err(Error::Parse(format!(
+                    "checksum mismatch in record at offset {}",
+                    pos
+                )));
+            }
+            if matches!(kind, KIND_GEOMETRY | KIND_POINTS | KIND_MESH) {
+                archive.records.push(Record {
+                    kind,
+                    flags: record_flags,
+                    payload,
+                });
+            } else if record_flags & FLAG_REQUIRED != 0 {
+                return Err(Error::Unsupported(format!("required record kind {}", kind)));
+            } else {
+                archive.skipped.push(kind);
+            }
+            pos = start + payload.len();
+            pos = (pos + padding(pos)).min(data.len());
+        }
+        Ok(archive)
+    }
+
+    pub fn items(&self) -> Result<Vec<Item<'a>>> {
+        self.records.iter().map(|r| r.decode()).collect()
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<Item<'a>>> {
+        for record in self.records.iter() {
+            let item = record.decode()?;
+            if item.name() == name {
+                return Ok(Some(item));
+            }
+        }
+        Ok(None)
+    }
+}
+
+pub struct ArchiveWriter {
+    pub flags: u32,
+    records: Vec<u8>,
+}
+
+impl Default for ArchiveWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ArchiveWriter {
+    pub fn new() -> Self {
+        ArchiveWriter {
+            flags: 0,
+            records: Vec::new(),
+        }
+    }
+
+    pub fn add_record(&mut self, kind: u32, flags: u32, payload: &[u8]) {
+        self.records.extend_from_slice(&kind.to_le_bytes());
+        self.records.extend_from_slice(&flags.to_le_bytes());
+        self.records
+            .extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        self.records
+            .extend_from_slice(&crc32(payload).to_le_bytes());
+        self.records.extend_from_slice(&[0; 4]);
+        self.records.extend_from_slice(payload);
+        let n = padding(payload.len());
+        self.records.resize(self.records.len() + n, 0);
+    }
+
+    pub fn add_geometry(&mut self, name: &str, geometry: &StepGeometry) {
+        let mut e = Encoder::new();
+        e.str(name);
+        e.geometry(geometry);
+        self.add_record(KIND_GEOMETRY, 0, &e.buf);
+    }
+
+    pub fn add_points(&mut self, name: &str, points: &[Point3d]) {
+        let mut e = Encoder::new();
+        e.str(name);
+        e.points(points);
+        self.add_record(KIND_POINTS, 0, &e.buf);
+    }
+
+    pub fn add_mesh(&mut self, name: &str, mesh: &Mesh) -> Result<()> {
+        if !mesh.is_valid() {
+            return Err(invalid("mesh is inconsistent"));
+        }
+        let mut e = Encoder::new();
+        e.str(name);
+        e.points(&mesh.points);
+        e.u64(mesh.faces.len() as u64);
+        let mut offset = 0;
+        e.u64(0);
+        for face in mesh.faces.iter() {
+            offset += face.len();
+            e.u64(offset as u64);
+        }
+        for &index in mesh.faces.iter().flatten() {
+            e.u32(index as u32);
+        }
+        e.attributes(&mesh.point_attributes);
+        e.attributes(&mesh.face_attributes);
+        self.add_record(KIND_MESH, 0, &e.buf);
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.records.len());
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&VERSION_MAJOR.to_le_bytes());
+        bytes.extend_from_slice(&VERSION_MINOR.to_le_bytes());
+        bytes.extend_from_slice(&self.flags.to_le_bytes());
+        bytes.extend_from_slice(&self.records);
+        bytes
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<()> {
+        writer.write_all(&self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn write_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+}
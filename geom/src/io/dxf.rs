@@ -0,0 +1,829 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+
+use crate::BSplineCurve2d;
+use crate::Circle2d;
+use crate::CoordinateSystem2d;
+use crate::Curve2d;
+use crate::Direction2d;
+use crate::Ellipse2d;
+use crate::Point2d;
+use crate::TrimmedCurve2d;
+use crate::Trsf2d;
+use crate::XY;
+use crate::bspline::compress_knots;
+use crate::io::{Error, LengthUnit, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    pub name: String,
+    // ACI colour number; negative when the layer is switched off.
+    pub color: i32,
+}
+
+#[derive(Debug, Clone)]
+pub enum DxfGeometry {
+    // LINE, CIRCLE, ARC, ELLIPSE and SPLINE.
+    Curve(TrimmedCurve2d),
+    // LWPOLYLINE; `bulges[i]` belongs to the segment starting at `vertices[i]`,
+    // and missing bulges are zero.
+    Polyline {
+        vertices: Vec<Point2d>,
+        bulges: Vec<f64>,
+        closed: bool,
+    },
+    // INSERT; `trsf` maps block coordinates into the parent space.
+    Insert {
+        block: String,
+        trsf: Trsf2d,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct DxfEntity {
+    pub layer: String,
+    pub geometry: DxfGeometry,
+}
+
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub name: String,
+    pub base: Point2d,
+    pub entities: Vec<DxfEntity>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DxfDrawing {
+    pub units: Option<LengthUnit>,
+    pub layers: Vec<Layer>,
+    pub blocks: Vec<Block>,
+    pub entities: Vec<DxfEntity>,
+    // Entities that were not imported, with the reason.
+    pub skipped: Vec<(String, String)>,
+}
+
+type Conversion<T> = std::result::Result<T, String>;
+
+struct Record {
+    kind: String,
+    groups: Vec<(i32, String)>,
+}
+
+impl Record {
+    fn get(&self, code: i32) -> Option<&str> {
+        self.groups
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn real_or(&self, code: i32, default: f64) -> Conversion<f64> {
+        match self.get(code) {
+            None => Ok(default),
+            Some(v) => v
+                .trim()
+                .parse()
+                .map_err(|_| format!("group {} is not a number", code)),
+        }
+    }
+
+    fn real(&self, code: i32) -> Conversion<f64> {
+        self.get(code)
+            .ok_or_else(|| format!("missing group {}", code))?;
+        self.real_or(code, 0.0)
+    }
+
+    fn int_or(&self, code: i32, default: i64) -> Conversion<i64> {
+        match self.get(code) {
+            None => Ok(default),
+            Some(v) => v
+                .trim()
+                .parse()
+                .map_err(|_| format!("group {} is not an integer", code)),
+        }
+    }
+
+    fn point(&self, code: i32) -> Conversion<XY> {
+        Ok(XY::from_coords(self.real(code)?, self.real(code + 10)?))
+    }
+
+    fn layer(&self) -> String {
+        self.get(8).unwrap_or("0").to_string()
+    }
+
+    // True when the object coordinate system is mirrored (extrusion along -Z).
+    fn mirrored(&self) -> Conversion<bool> {
+        let (x, y, z) = (
+            self.real_or(210, 0.0)?,
+            self.real_or(220, 0.0)?,
+            self.real_or(230, 1.0)?,
+        );
+        if x.abs() > 1e-9 || y.abs() > 1e-9 || z == 0.0 {
+            return Err("entity does not lie in the XY plane".to_string());
+        }
+        Ok(z < 0.0)
+    }
+}
+
+fn records(text: &str) -> Result<Vec<Record>> {
+    let mut lines = text.lines();
+    let mut records: Vec<Record> = Vec::new();
+    let mut n = 0;
+    while let Some(code) = lines.next() {
+        n += 1;
+        let code = code.trim();
+        if code.is_empty() && lines.clone().all(|l| l.trim().is_empty()) {
+            break;
+        }
+        let code: i32 = code
+            .parse()
+            .map_err(|_| Error::Parse(format!("invalid group code '{}' at line {}", code, n)))?;
+        let value = lines
+            .next()
+            .ok_or_else(|| Error::Parse(format!("missing value for group code at line {}", n)))?
+            .trim_end_matches('\r')
+            .trim()
+            .to_string();
+        n += 1;
+        if code == 0 {
+            records.push(Record {
+                kind: value,
+                groups: Vec::new(),
+            });
+        } else if let Some(record) = records.last_mut() {
+            record.groups.push((code, value));
+        }
+    }
+    Ok(records)
+}
+
+fn arc(center: XY, radius: f64, first: f64, last: f64) -> TrimmedCurve2d {
+    TrimmedCurve2d::new(
+        Curve2d::Circle(Circle2d {
+            position: CoordinateSystem2d::from_origin_vydir_vxdir(
+                Point2d::from_xy(center),
+                Direction2d::from_coords(1.0, 0.0),
+            ),
+            radius,
+        }),
+        first,
+        last,
+    )
+}
+
+// Counterclockwise range starting at `first` and ending after it at `last`.
+fn ccw_range(first: f64, mut last: f64) -> (f64, f64) {
+    let tau = 2.0 * std::f64::consts::PI;
+    while last <= first {
+        last += tau;
+    }
+    while last - first > tau + 1e-12 {
+        last -= tau;
+    }
+    (first, last)
+}
+
+fn mirror_x(xy: XY, mirrored: bool) -> XY {
+    if mirrored {
+        XY::from_coords(-xy.x, xy.y)
+    } else {
+        xy
+    }
+}
+
+fn convert(record: &Record, bases: &BTreeMap<String, XY>) -> Conversion<DxfGeometry> {
+    let pi = std::f64::consts::PI;
+    let geometry = match record.kind.as_str() {
+        "LINE" => {
+            let (start, end) = (record.point(10)?, record.point(11)?);
+            DxfGeometry::Curve(TrimmedCurve2d::from_segment(
+                &Point2d::from_xy(start),
+                &Point2d::from_xy(end),
+            )?)
+        }
+        "CIRCLE" => {
+            let center = mirror_x(record.point(10)?, record.mirrored()?);
+            let radius = record.real(40)?;
+            if radius <= 0.0 {
+                return Err("circle radius must be positive".to_string());
+            }
+            DxfGeometry::Curve(arc(center, radius, 0.0, 2.0 * pi))
+        }
+        "ARC" => {
+            let mirrored = record.mirrored()?;
+            let center = mirror_x(record.point(10)?, mirrored);
+            let radius = record.real(40)?;
+            if radius <= 0.0 {
+                return Err("arc radius must be positive".to_string());
+            }
+            let (start, end) = (record.real(50)?.to_radians(), record.real(51)?.to_radians());
+            let (first, last) = if mirrored {
+                ccw_range(pi - end, pi - start)
+            } else {
+                ccw_range(start, end)
+            };
+            DxfGeometry::Curve(arc(center, radius, first, last))
+        }
+        "ELLIPSE" => {
+            // Center and major axis are in world coordinates; only the sense of
+            // the parametrisation follows the extrusion direction.
+            let center = record.point(10)?;
+            let major = record.point(11)?;
+            let ratio = record.real(40)?;
+            let major_radius = major.length();
+            if major_radius <= 0.0 || ratio <= 0.0 || ratio > 1.0 + 1e-9 {
+                return Err("invalid ellipse axes".to_string());
+            }
+            let (start, end) = (record.real_or(41, 0.0)?, record.real_or(42, 2.0 * pi)?);
+            let (first, last) = if record.mirrored()? {
+                ccw_range(-end, -start)
+            } else {
+                ccw_range(start, end)
+            };
+            DxfGeometry::Curve(TrimmedCurve2d::new(
+                Curve2d::Ellipse(Ellipse2d {
+                    position: CoordinateSystem2d::from_origin_vydir_vxdir(
+                        Point2d::from_xy(center),
+                        Direction2d::from_xy(major),
+                    ),
+                    major_radius,
+                    minor_radius: major_radius * ratio,
+                }),
+                first,
+                last,
+            ))
+        }
+        "LWPOLYLINE" => {
+            let mirrored = record.mirrored()?;
+            let sign = if mirrored { -1.0 } else { 1.0 };
+            let mut vertices = Vec::new();
+            let mut bulges: Vec<f64> = Vec::new();
+            let mut x = None;
+            for (code, value) in record.groups.iter() {
+                let parse = || {
+                    value
+                        .parse::<f64>()
+                        .map_err(|_| format!("group {} is not a number", code))
+                };
+                match code {
+                    10 => x = Some(parse()?),
+                    20 => {
+                        let vx = x.take().ok_or("vertex without x coordinate")?;
+                        vertices.push(Point2d::from_xy(mirror_x(
+                            XY::from_coords(vx, parse()?),
+                            mirrored,
+                        )));
+                        bulges.push(0.0);
+                    }
+                    42 => {
+                        if let Some(b) = bulges.last_mut() {
+                            *b = sign * parse()?;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            if vertices.len() < 2 {
+                return Err("polyline needs at least two vertices".to_string());
+            }
+            DxfGeometry::Polyline {
+                vertices,
+                bulges,
+                closed: record.int_or(70, 0)? & 1 != 0,
+            }
+        }
+        "SPLINE" => {
+            let degree = record.int_or(71, 3)?;
+            let all = |code: i32| -> Conversion<Vec<f64>> {
+                record
+                    .groups
+                    .iter()
+                    .filter(|(c, _)| *c == code)
+                    .map(|(_, v)| {
+                        v.parse::<f64>()
+                            .map_err(|_| format!("group {} is not a number", code))
+                    })
+                    .collect()
+            };
+            let (xs, ys) = (all(10)?, all(20)?);
+            if xs.is_empty() {
+                return Err("SPLINE defined only by fit points".to_string());
+            }
+            if xs.len() != ys.len() || degree < 1 {
+                return Err("invalid SPLINE control points".to_string());
+            }
+            let poles: Vec<Point2d> = xs
+                .iter()
+                .zip(ys.iter())
+                .map(|(&x, &y)| Point2d::from_coords(x, y))
+                .collect();
+            let (knots, multiplicities) = compress_knots(&all(40)?);
+            let weights = all(41)?;
+            let curve = if weights.is_empty() || weights.iter().all(|&w| w == weights[0]) {
+                BSplineCurve2d::from_poles_knots(degree as usize, poles, knots, multiplicities)?
+            } else {
+                BSplineCurve2d::from_poles_weights_knots(
+                    degree as usize,
+                    poles,
+                    weights,
+                    knots,
+                    multiplicities,
+                )?
+            };
+            DxfGeometry::Curve(TrimmedCurve2d::from_curve(Curve2d::BSpline(curve)))
+        }
+        "INSERT" => {
+            if record.int_or(70, 1)? > 1 || record.int_or(71, 1)? > 1 {
+                return Err("arrayed INSERT is not supported".to_string());
+            }
+            let name = record.get(2).ok_or("INSERT without block name")?;
+            let base = bases
+                .get(name)
+                .ok_or_else(|| format!("INSERT of undefined block '{}'", name))?;
+            let insertion = record.point(10)?;
+            let (sx, sy) = (record.real_or(41, 1.0)?, record.real_or(42, 1.0)?);
+            let angle = record.real_or(50, 0.0)?.to_radians();
+            let (c, s) = (angle.cos(), angle.sin());
+            // Translate(insertion) * Rotate(angle) * Scale(sx, sy) * Translate(-base).
+            let m = [[c * sx, -s * sy], [s * sx, c * sy]];
+            let mut values = [
+                [
+                    m[0][0],
+                    m[0][1],
+                    insertion.x - m[0][0] * base.x - m[0][1] * base.y,
+                ],
+                [
+                    m[1][0],
+                    m[1][1],
+                    insertion.y - m[1][0] * base.x - m[1][1] * base.y,
+                ],
+            ];
+            if record.mirrored()? {
+                values[0] = values[0].map(|v| -v);
+            }
+            DxfGeometry::Insert {
+                block: name.to_string(),
+                trsf: Trsf2d::from_values(values)?,
+            }
+        }
+        kind => return Err(format!("unsupported entity {}", kind)),
+    };
+    Ok(geometry)
+}
+
+impl DxfGeometry {
+    // Curves of a LWPOLYLINE or a single curve; inserts give no curves.
+    pub fn to_curves(&self) -> Vec<TrimmedCurve2d> {
+        match self {
+            DxfGeometry::Curve(curve) => vec![curve.clone()],
+            DxfGeometry::Insert { .. } => Vec::new(),
+            DxfGeometry::Polyline {
+                vertices,
+                bulges,
+                closed,
+            } => {
+                let n = vertices.len();
+                let segments = if *closed { n } else { n.saturating_sub(1) };
+                let mut curves = Vec::with_capacity(segments);
+                for i in 0..segments {
+                    let (p1, p2) = (vertices[i].get_xy(), vertices[(i + 1) % n].get_xy());
+                    let chord = p2 - p1;
+                    let d = chord.length();
+                    if d <= 0.0 {
+                        continue;
+                    }
+                    let b = bulges.get(i).copied().unwrap_or(0.0);
+                    if b == 0.0 {
+                        curves.push(
+                            TrimmedCurve2d::from_segment(&vertices[i], &vertices[(i + 1) % n])
+                                .unwrap(),
+                        );
+                        continue;
+                    }
+                    // Signed distance from the chord midpoint to the arc center.
+                    let h = d * (1.0 - b * b) / (4.0 * b);
+                    let normal = XY::from_coords(-chord.y / d, chord.x / d);
+                    let mut center = &(p1 + p2) * 0.5;
+                    center += &(&normal * h);
+                    let radius = d * (1.0 + b * b) / (4.0 * b.abs());
+                    let start = (p1.y - center.y).atan2(p1.x - center.x);
+                    curves.push(arc(center, radius, start, start + 4.0 * b.atan()));
+                }
+                curves
+            }
+        }
+    }
+
+    pub fn transformed(&self, t: &Trsf2d) -> Self {
+        match self {
+            DxfGeometry::Curve(curve) => DxfGeometry::Curve(curve.transformed(t)),
+            DxfGeometry::Polyline {
+                vertices,
+                bulges,
+                closed,
+            } => {
+                let sign = if t.is_negative() { -1.0 } else { 1.0 };
+                DxfGeometry::Polyline {
+                    vertices: vertices
+                        .iter()
+                        .map(|v| Point2d::from_xy(t.transform_xy(v.get_xy())))
+                        .collect(),
+                    bulges: bulges.iter().map(|b| sign * b).collect(),
+                    closed: *closed,
+                }
+            }
+            DxfGeometry::Insert { block, trsf } => DxfGeometry::Insert {
+                block: block.clone(),
+                trsf: t.multiply_new(trsf),
+            },
+        }
+    }
+}
+
+fn fmt_real(v: f64) -> String {
+    if v == 0.0 {
+        "0.0".to_string()
+    } else {
+        format!("{:?}", v)
+    }
+}
+
+struct Writer<'a, W: Write> {
+    out: &'a mut W,
+    handle: u64,
+}
+
+impl<W: Write> Writer<'_, W> {
+    fn group(&mut self, code: i32, value: &str) -> Result<()> {
+        writeln!(self.out, "{:>3}\n{}", code, value)?;
+        Ok(())
+    }
+
+    fn real(&mut self, code: i32, value: f64) -> Result<()> {
+        self.group(code, &fmt_real(value))
+    }
+
+    fn point(&mut self, code: i32, xy: &XY) -> Result<()> {
+        self.real(code, xy.x)?;
+        self.real(code + 10, xy.y)?;
+        self.real(code + 20, 0.0)
+    }
+
+    fn start(&mut self, kind: &str, layer: &str, subclass: &str) -> Result<()> {
+        self.handle += 1;
+        self.group(0, kind)?;
+        self.group(5, &format!("{:X}", self.handle))?;
+        self.group(100, "AcDbEntity")?;
+        self.group(8, layer)?;
+        self.group(100, subclass)
+    }
+
+    fn curve(&mut self, layer: &str, curve: &TrimmedCurve2d) -> Result<()> {
+        let (a, b) = if curve.first <= curve.last {
+            (curve.first, curve.last)
+        } else {
+            (curve.last, curve.first)
+        };
+        match &curve.basis {
+            Curve2d::Line(_) => {
+                if !curve.first.is_finite() || !curve.last.is_finite() {
+                    return Err(Error::Unsupported("unbounded line".to_string()));
+                }
+                self.start("LINE", layer, "AcDbLine")?;
+                self.point(10, curve.start_point().get_xy())?;
+                self.point(11, curve.end_point().get_xy())
+            }
+            Curve2d::Circle(c) => {
+                let kind = if curve.is_closed() { "CIRCLE" } else { "ARC" };
+                self.start(kind, layer, "AcDbCircle")?;
+                self.point(10, c.position.origin.get_xy())?;
+                self.real(40, c.radius)?;
+                if !curve.is_closed() {
+                    let (x, y) = c.position.vdir.get_coords();
+                    let offset = y.atan2(x);
+                    self.group(100, "AcDbArc")?;
+                    self.real(50, (a + offset).to_degrees())?;
+                    self.real(51, (b + offset).to_degrees())?;
+                }
+                Ok(())
+            }
+            Curve2d::Ellipse(e) => {
+                self.start("ELLIPSE", layer, "AcDbEllipse")?;
+                self.point(10, e.position.origin.get_xy())?;
+                self.point(11, &(e.position.vdir.get_xy() * e.major_radius))?;
+                self.real(40, e.minor_radius / e.major_radius)?;
+                let (a, b) = if curve.is_closed() {
+                    (0.0, 2.0 * std::f64::consts::PI)
+                } else {
+                    (a, b)
+                };
+                self.real(41, a)?;
+                self.real(42, b)
+            }
+            Curve2d::BSpline(s) => {
+                if a != s.first_parameter() || b != s.last_parameter() {
+                    return Err(Error::Unsupported("trimmed B-spline".to_string()));
+                }
+                let flat = s.get_flat_knots();
+                self.start("SPLINE", layer, "AcDbSpline")?;
+                self.group(210, "0.0")?;
+                self.group(220, "0.0")?;
+                self.group(230, "1.0")?;
+                self.group(70, if s.is_rational() { "12" } else { "8" })?;
+                self.group(71, &s.degree.to_string())?;
+                self.group(72, &flat.len().to_string())?;
+                self.group(73, &s.poles.len().to_string())?;
+                self.group(74, "0")?;
+                for k in flat.iter() {
+                    self.real(40, *k)?;
+                }
+                if let Some(weights) = &s.weights {
+                    for w in weights.iter() {
+                        self.real(41, *w)?;
+                    }
+                }
+                for p in s.poles.iter() {
+                    self.point(10, p.get_xy())?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn entity(&mut self, entity: &DxfEntity, bases: &BTreeMap<&str, XY>) -> Result<()> {
+        let layer = entity.layer.as_str();
+        match &entity.geometry {
+            DxfGeometry::Curve(curve) => self.curve(layer, curve),
+            DxfGeometry::Polyline {
+                vertices,
+                bulges,
+                closed,
+            } => {
+                self.start("LWPOLYLINE", layer, "AcDbPolyline")?;
+                self.group(90, &vertices.len().to_string())?;
+                self.group(70, if *closed { "1" } else { "0" })?;
+                for (i, v) in vertices.iter().enumerate() {
+                    self.real(10, v.get_x())?;
+                    self.real(20, v.get_y())?;
+                    match bulges.get(i) {
+                        Some(&b) if b != 0.0 => self.real(42, b)?,
+                        _ => {}
+                    }
+                }
+                Ok(())
+            }
+            DxfGeometry::Insert { block, trsf } => {
+                let base = bases
+                    .get(block.as_str())
+                    .ok_or_else(|| Error::Parse(format!("undefined block '{}'", block)))?;
+                let m = &trsf.matrix.m;
+                let mirror = if trsf.is_negative() { -1.0 } else { 1.0 };
+                self.start("INSERT", layer, "AcDbBlockReference")?;
+                self.group(2, block)?;
+                self.point(10, &trsf.transform_xy(base))?;
+                self.real(41, trsf.scale)?;
+                self.real(42, mirror * trsf.scale)?;
+                self.real(50, m[1][0].atan2(m[0][0]).to_degrees())
+            }
+        }
+    }
+}
+
+impl DxfDrawing {
+    pub fn new() -> Self {
+        DxfDrawing::default()
+    }
+
+    pub fn get_layer(&self, name: &str) -> Option<&Layer> {
+        self.layers.iter().find(|l| l.name == name)
+    }
+
+    pub fn get_block(&self, name: &str) -> Option<&Block> {
+        self.blocks.iter().find(|b| b.name == name)
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let records = records(text)?;
+        let mut drawing = DxfDrawing::new();
+        let mut bases = BTreeMap::new();
+        for record in records.iter().filter(|r| r.kind == "BLOCK") {
+            let name = record.get(2).unwrap_or("").to_string();
+            let base = record.point(10).unwrap_or(XY::from_coords(0.0, 0.0));
+            bases.insert(name, base);
+        }
+
+        let mut section = String::new();
+        let mut block: Option<Block> = None;
+        for record in records.iter() {
+            match record.kind.as_str() {
+                "SECTION" => {
+                    section = record.get(2).unwrap_or("").to_string();
+                    if section == "HEADER" {
+                        let mut groups = record.groups.iter();
+                        while let Some((code, value)) = groups.next() {
+                            if *code == 9 && value == "$INSUNITS" {
+                                drawing.units = match groups.next().map(|(_, v)| v.as_str()) {
+                                    Some("1") => Some(LengthUnit::Inch),
+                                    Some("4") => Some(LengthUnit::Millimetre),
+                                    Some("5") => Some(LengthUnit::Centimetre),
+                                    Some("6") => Some(LengthUnit::Metre),
+                                    _ => None,
+                                };
+                            }
+                        }
+                    }
+                    continue;
+                }
+                "ENDSEC" | "EOF" | "TABLE" | "ENDTAB" | "SEQEND" => continue,
+                _ => {}
+            }
+            match section.as_str() {
+                "TABLES" if record.kind == "LAYER" => {
+                    let name = record.get(2).unwrap_or("0").to_string();
+                    let color = record.int_or(62, 7).map_err(Error::Parse)? as i32;
+                    drawing.layers.push(Layer { name, color });
+                }
+                "BLOCKS" if record.kind == "BLOCK" => {
+                    let name = record.get(2).unwrap_or("").to_string();
+                    block = Some(Block {
+                        base: Point2d::from_xy(bases[&name]),
+                        name,
+                        entities: Vec::new(),
+                    });
+                }
+                "BLOCKS" if record.kind == "ENDBLK" => {
+                    if let Some(b) = block.take() {
+                        drawing.blocks.push(b);
+                    }
+                }
+                "BLOCKS" | "ENTITIES" => match convert(record, &bases) {
+                    Ok(geometry) => {
+                        let entity = DxfEntity {
+                            layer: record.layer(),
+                            geometry,
+                        };
+                        match block.as_mut() {
+                            Some(b) if section == "BLOCKS" => b.entities.push(entity),
+                            _ => drawing.entities.push(entity),
+                        }
+                    }
+                    Err(reason) => drawing.skipped.push((record.kind.clone(), reason)),
+                },
+                _ => {}
+            }
+        }
+        Ok(drawing)
+    }
+
+    // Entities with every INSERT replaced by the transformed block contents.
+    // Block entities on layer "0" take the layer of the INSERT.
+    pub fn explode(&self) -> Result<Vec<DxfEntity>> {
+        let mut result = Vec::new();
+        let mut stack = Vec::new();
+        self.explode_into(
+            &self.entities,
+            &Trsf2d::new(),
+            None,
+            &mut stack,
+            &mut result,
+        )?;
+        Ok(result)
+    }
+
+    fn explode_into<'a>(
+        &'a self,
+        entities: &'a [DxfEntity],
+        trsf: &Trsf2d,
+        layer: Option<&str>,
+        stack: &mut Vec<&'a str>,
+        result: &mut Vec<DxfEntity>,
+    ) -> Result<()> {
+        for entity in entities.iter() {
+            let layer = match layer {
+                Some(parent) if entity.layer == "0" => parent.to_string(),
+                _ => entity.layer.clone(),
+            };
+            match &entity.geometry {
+                DxfGeometry::Insert { block, trsf: local } => {
+                    if stack.contains(&block.as_str()) {
+                        return Err(Error::Parse(format!("block '{}' inserts itself", block)));
+                    }
+                    let b = self
+                        .get_block(block)
+                        .ok_or_else(|| Error::Parse(format!("undefined block '{}'", block)))?;
+                    stack.push(&b.name);
+                    self.explode_into(
+                        &b.entities,
+                        &trsf.multiply_new(local),
+                        Some(&layer),
+                        stack,
+                        result,
+                    )?;
+                    stack.pop();
+                }
+                geometry => result.push(DxfEntity {
+                    layer,
+                    geometry: geometry.transformed(trsf),
+                }),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write<W: Write>(&self, out: &mut W) -> Result<()> {
+        let mut w = Writer { out, handle: 0 };
+        w.group(0, "SECTION")?;
+        w.group(2, "HEADER")?;
+        w.group(9, "$ACADVER")?;
+        w.group(1, "AC1015")?;
+        if let Some(units) = self.units {
+            let code = match units {
+                LengthUnit::Inch => "1",
+                LengthUnit::Millimetre => "4",
+                LengthUnit::Centimetre => "5",
+                LengthUnit::Metre => "6",
+            };
+            w.group(9, "$INSUNITS")?;
+            w.group(70, code)?;
+        }
+        w.group(0, "ENDSEC")?;
+
+        // Every layer that is used must appear in the layer table.
+        let mut layers = self.layers.clone();
+        let used: BTreeSet<&str> = self
+            .entities
+            .iter()
+            .chain(self.blocks.iter().flat_map(|b| b.entities.iter()))
+            .map(|e| e.layer.as_str())
+            .chain(std::iter::once("0"))
+            .collect();
+        for name in used {
+            if !layers.iter().any(|l| l.name == name) {
+                layers.push(Layer {
+                    name: name.to_string(),
+                    color: 7,
+                });
+            }
+        }
+        w.group(0, "SECTION")?;
+        w.group(2, "TABLES")?;
+        w.group(0, "TABLE")?;
+        w.group(2, "LAYER")?;
+        w.group(70, &layers.len().to_string())?;
+        for layer in layers.iter() {
+            w.group(0, "LAYER")?;
+            w.group(100, "AcDbSymbolTableRecord")?;
+            w.group(100, "AcDbLayerTableRecord")?;
+            w.group(2, &layer.name)?;
+            w.group(70, "0")?;
+            w.group(62, &layer.color.to_string())?;
+            w.group(6, "CONTINUOUS")?;
+        }
+        w.group(0, "ENDTAB")?;
+        w.group(0, "ENDSEC")?;
+
+        let bases: BTreeMap<&str, XY> = self
+            .blocks
+            .iter()
+            .map(|b| (b.name.as_str(), *b.base.get_xy()))
+            .collect();
+        w.group(0, "SECTION")?;
+        w.group(2, "BLOCKS")?;
+        for block in self.blocks.iter() {
+            w.start("BLOCK", "0", "AcDbBlockBegin")?;
+            w.group(2, &block.name)?;
+            w.group(70, "0")?;
+            w.point(10, block.base.get_xy())?;
+            w.group(3, &block.name)?;
+            for entity in block.entities.iter() {
+                w.entity(entity, &bases)?;
+            }
+            w.start("ENDBLK", "0", "AcDbBlockEnd")?;
+        }
+        w.group(0, "ENDSEC")?;
+
+        w.group(0, "SECTION")?;
+        w.group(2, "ENTITIES")?;
+        for entity in self.entities.iter() {
+            w.entity(entity, &bases)?;
+        }
+        w.group(0, "ENDSEC")?;
+        w.group(0, "EOF")
+    }
+}
+
+pub fn read_str(text: &str) -> Result<DxfDrawing> {
+    DxfDrawing::parse(text)
+}
+
+pub fn read_file<P: AsRef<std::path::Path>>(path: P) -> Result<DxfDrawing> {
+    let bytes = std::fs::read(path)?;
+    read_str(&String::from_utf8_lossy(&bytes))
+}
+
+pub fn write_file<P: AsRef<std::path::Path>>(path: P, drawing: &DxfDrawing) -> Result<()> {
+    let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+    drawing.write(&mut out)?;
+    out.flush()?;
+    Ok(())
+}
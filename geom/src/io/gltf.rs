@@ -0,0 +1,502 @@
+use std::fmt::Write as _;
+
+use crate::Mesh;
+use crate::Quaternion;
+use crate::Trsf3d;
+use crate::XYZ;
+use crate::io::{Error, Result};
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const MODE_POINTS: u32 = 0;
+const MODE_TRIANGLES: u32 = 4;
+
+#[derive(Debug, Clone)]
+pub struct Material {
+    pub name: String,
+    pub base_color: [f64; 4],
+    pub metallic: f64,
+    pub roughness: f64,
+}
+
+impl Material {
+    pub fn from_color(name: &str, base_color: [f64; 4]) -> Self {
+        Material {
+            name: name.to_string(),
+            base_color,
+            metallic: 0.0,
+            roughness: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum NodeTransform {
+    Identity,
+    Trsf(Trsf3d),
+    Trs {
+        translation: XYZ,
+        rotation: Quaternion,
+        scale: XYZ,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub name: String,
+    pub mesh: Option<usize>,
+    pub transform: NodeTransform,
+    pub children: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SceneMesh {
+    pub name: String,
+    pub mesh: Mesh,
+    pub material: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Scene {
+    pub meshes: Vec<SceneMesh>,
+    pub materials: Vec<Material>,
+    pub nodes: Vec<Node>,
+    pub roots: Vec<usize>,
+}
+
+struct BufferBuilder {
+    data: Vec<u8>,
+    views: Vec<(usize, usize, u32)>,
+}
+
+impl BufferBuilder {
+    fn push_view(&mut self, bytes: &[u8], target: u32) -> usize {
+        while !self.data.len().is_multiple_of(4) {
+            self.data.push(0);
+        }
+        self.views.push((self.data.len(), bytes.len(), target));
+        self.data.extend_from_slice(bytes);
+        self.views.len() - 1
+    }
+}
+
+// Percent-encodes a relative file path for use as a URI reference.
+fn percent_encode(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for &b in path.as_bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                out.push(b as char)
+            }
+            _ => {
+                let _ = write!(out, "%{:02X}", b);
+            }
+        }
+    }
+    out
+}
+
+fn escape_json(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn json_number(value: f64) -> Result<String> {
+    if !value.is_finite() {
+        return Err(Error::Unsupported(
+            "glTF cannot store non-finite numbers".to_string(),
+        ));
+    }
+    Ok(format!("{}", value))
+}
+
+fn json_numbers(values: &[f64]) -> Result<String> {
+    let items: Result<Vec<String>> = values.iter().map(|&v| json_number(v)).collect();
+    Ok(format!("[{}]", items?.join(",")))
+}
+
+fn vertex_normals(mesh: &Mesh, triangles: &[[usize; 3]]) -> Vec<[f32; 3]> {
+    let mut normals = vec![XYZ::new(); mesh.num_points()];
+    for t in triangles.iter() {
+        let p0 = mesh.points[t[0]].get_xyz();
+        let e1 = &mesh.points[t[1]].get_xyz() - &p0;
+        let e2 = &mesh.points[t[2]].get_xyz() - &p0;
+        // The cross product is weighted by the triangle area.
+        let n = e1.cross_new(&e2);
+        for &i in t.iter() {
+            normals[i] += &n;
+        }
+    }
+    normals
+        .iter()
+        .map(|n| {
+            let length = n.length();
+            if length > 0.0 {
+                [
+                    (n.x / length) as f32,
+                    (n.y / length) as f32,
+                    (n.z / length) as f32,
+                ]
+            } else {
+                [0.0, 0.0, 1.0]
+            }
+        })
+        .collect()
+}
+
+pub fn triangulate_faces(faces: &[Vec<usize>]) -> Vec<[usize; 3]> {
+    let mut triangles = Vec::new();
+    for face in faces.iter() {
+        for i in 1..face.len().saturating_sub(1) {
+            triangles.push([face[0], face[i], face[i + 1]]);
+        }
+    }
+    triangles
+}
+
+fn transform_json(transform: &NodeTransform) -> Result<String> {
+    match transform {
+        NodeTransform::Identity => Ok(String::new()),
+        NodeTransform::Trsf(trsf) => {
+            let v = trsf.get_values();
+            // glTF matrices are column-major.
+            let columns = [
+                v[0][0], v[1][0], v[2][0], 0.0, v[0][1], v[1][1], v[2][1], 0.0, v[0][2], v[1][2],
+                v[2][2], 0.0, v[0][3], v[1][3], v[2][3], 1.0,
+            ];
+            Ok(format!(",\"matrix\":{}", json_numbers(&columns)?))
+        }
+        NodeTransform::Trs {
+            translation,
+            rotation,
+            scale,
+        } => {
+            let q = rotation.normalize_new();
+            Ok(format!(
+                ",\"translation\":{},\"rotation\":{},\"scale\":{}",
+                json_numbers(&[translation.x, translation.y, translation.z])?,
+                json_numbers(&[q.x, q.y, q.z, q.w])?,
+                json_numbers(&[scale.x, scale.y, scale.z])?
+            ))
+        }
+    }
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Scene::default()
+    }
+
+    pub fn add_material(&mut self, material: Material) -> usize {
+        self.materials.push(material);
+        self.materials.len() - 1
+    }
+
+    pub fn add_mesh(&mut self, name: &str, mesh: Mesh, material: Option<usize>) -> usize {
+        self.meshes.push(SceneMesh {
+            name: name.to_string(),
+            mesh,
+            material,
+        });
+        self.meshes.len() - 1
+    }
+
+    pub fn add_node(&mut self, name: &str, mesh: Option<usize>, transform: NodeTransform) -> usize {
+        self.nodes.push(Node {
+            name: name.to_string(),
+            mesh,
+            transform,
+            children: Vec::new(),
+        });
+        self.nodes.len() - 1
+    }
+
+    pub fn add_root(&mut self, node: usize) {
+        self.roots.push(node);
+    }
+
+    pub fn add_child(&mut self, parent: usize, child: usize) {
+        self.nodes[parent].children.push(child);
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut parent_count = vec![0usize; self.nodes.len()];
+        for node in self.nodes.iter() {
+            if node.mesh.is_some_and(|m| m >= self.meshes.len()) {
+                return Err(Error::Parse(format!(
+                    "node '{}' references a missing mesh",
+                    node.name
+                )));
+            }
+            for &child in node.children.iter() {
+                if child >= self.nodes.len() {
+                    return Err(Error::Parse(format!(
+                        "node '{}' has a missing child",
+                        node.name
+                    )));
+                }
+                parent_count[child] += 1;
+            }
+        }
+        for &root in self.roots.iter() {
+            if root >= self.nodes.len() || parent_count[root] != 0 {
+                return Err(Error::Parse(
+                    "scene roots must be existing parentless nodes".to_string(),
+                ));
+            }
+        }
+        if parent_count.iter().any(|&c| c > 1) {
+            return Err(Error::Parse("node hierarchy is not a forest".to_string()));
+        }
+        // With at most one parent per node, every node not reachable from a
+        // parentless node lies on a cycle.
+        let mut visited = vec![false; self.nodes.len()];
+        let mut stack: Vec<usize> = (0..self.nodes.len())
+            .filter(|&n| parent_count[n] == 0)
+            .collect();
+        while let Some(n) = stack.pop() {
+            visited[n] = true;
+            stack.extend(self.nodes[n].children.iter().copied());
+        }
+        if visited.iter().any(|v| !v) {
+            return Err(Error::Parse("node hierarchy contains a cycle".to_string()));
+        }
+        for mesh in self.meshes.iter() {
+            if !mesh.mesh.is_valid() {
+                return Err(Error::Parse(format!("mesh '{}' is invalid", mesh.name)));
+            }
+            if mesh.material.is_some_and(|m| m >= self.materials.len()) {
+                return Err(Error::Parse(format!(
+                    "mesh '{}' references a missing material",
+                    mesh.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    // Returns the glTF JSON document and the binary buffer it describes.
+    // `bin_uri` is the relative path of the buffer file, which is
+    // percent-encoded; when it is `None` the buffer is expected to live in a
+    // GLB container.
+    pub fn to_gltf(&self, bin_uri: Option<&str>) -> Result<(String, Vec<u8>)> {
+        self.validate()?;
+        let mut buffer = BufferBuilder {
+            data: Vec::new(),
+            views: Vec::new(),
+        };
+        let mut accessors: Vec<String> = Vec::new();
+        let mut meshes: Vec<String> = Vec::new();
+
+        for scene_mesh in self.meshes.iter() {
+            let mesh = &scene_mesh.mesh;
+            let mut min = [f32::MAX; 3];
+            let mut max = [f32::MIN; 3];
+            let mut positions = Vec::with_capacity(mesh.num_points() * 12);
+            for p in mesh.points.iter() {
+                let coords = [p.get_x() as f32, p.get_y() as f32, p.get_z() as f32];
+                for k in 0..3 {
+                    min[k] = min[k].min(coords[k]);
+                    max[k] = max[k].max(coords[k]);
+                    positions.extend_from_slice(&coords[k].to_le_bytes());
+                }
+            }
+            if mesh.num_points() == 0 {
+                return Err(Error::Unsupported(format!(
+                    "mesh '{}' has no points",
+                    scene_mesh.name
+                )));
+            }
+            let view = buffer.push_view(&positions, ARRAY_BUFFER);
+            accessors.push(format!(
+                "{{\"bufferView\":{},\"componentType\":{},\"count\":{},\"type\":\"VEC3\",\"min\":{},\"max\":{}}}",
+                view,
+                FLOAT,
+                mesh.num_points(),
+                json_numbers(&min.map(|v| v as f64))?,
+                json_numbers(&max.map(|v| v as f64))?
+            ));
+            let position_accessor = accessors.len() - 1;
+
+            let triangles = triangulate_faces(&mesh.faces);
+            let mut primitive = if triangles.is_empty() {
+                format!(
+                    "{{\"attributes\":{{\"POSITION\":{}}},\"mode\":{}",
+                    position_accessor, MODE_POINTS
+                )
+            } else {
+                let normals: Vec<u8> = vertex_normals(mesh, &triangles)
+                    .iter()
+                    .flat_map(|n| n.iter().flat_map(|c| c.to_le_bytes()))
+                    .collect();
+                let view = buffer.push_view(&normals, ARRAY_BUFFER);
+                accessors.push(format!(
+                    "{{\"bufferView\":{},\"componentType\":{},\"count\":{},\"type\":\"VEC3\"}}",
+                    view,
+                    FLOAT,
+                    mesh.num_points()
+                ));
+                let normal_accessor = accessors.len() - 1;
+
+                let indices: Vec<u8> = triangles
+                    .iter()
+                    .flat_map(|t| t.iter().flat_map(|&i| (i as u32).to_le_bytes()))
+                    .collect();
+                let view = buffer.push_view(&indices, ELEMENT_ARRAY_BUFFER);
+                accessors.push(format!(
+                    "{{\"bufferView\":{},\"componentType\":{},\"count\":{},\"type\":\"SCALAR\"}}",
+                    view,
+                    UNSIGNED_INT,
+                    triangles.len() * 3
+                ));
+                format!(
+                    "{{\"attributes\":{{\"POSITION\":{},\"NORMAL\":{}}},\"indices\":{},\"mode\":{}",
+                    position_accessor,
+                    normal_accessor,
+                    accessors.len() - 1,
+                    MODE_TRIANGLES
+                )
+            };
+            if let Some(material) = scene_mesh.material {
+                let _ = write!(primitive, ",\"material\":{}", material);
+            }
+            primitive.push('}');
+            meshes.push(format!(
+                "{{\"name\":\"{}\",\"primitives\":[{}]}}",
+                escape_json(&scene_mesh.name),
+                primitive
+            ));
+        }
+
+        let mut materials: Vec<String> = Vec::new();
+        for material in self.materials.iter() {
+            materials.push(format!(
+                "{{\"name\":\"{}\",\"pbrMetallicRoughness\":{{\"baseColorFactor\":{},\"metallicFactor\":{},\"roughnessFactor\":{}}}}}",
+                escape_json(&material.name),
+                json_numbers(&material.base_color)?,
+                json_number(material.metallic)?,
+                json_number(material.roughness)?
+            ));
+        }
+
+        let mut nodes: Vec<String> = Vec::new();
+        for node in self.nodes.iter() {
+            let mut json = format!("{{\"name\":\"{}\"", escape_json(&node.name));
+            if let Some(mesh) = node.mesh {
+                let _ = write!(json, ",\"mesh\":{}", mesh);
+            }
+            if !node.children.is_empty() {
+                let children: Vec<String> = node.children.iter().map(|c| c.to_string()).collect();
+                let _ = write!(json, ",\"children\":[{}]", children.join(","));
+            }
+            json.push_str(&transform_json(&node.transform)?);
+            json.push('}');
+            nodes.push(json);
+        }
+
+        while !buffer.data.len().is_multiple_of(4) {
+            buffer.data.push(0);
+        }
+        let views: Vec<String> = buffer
+            .views
+            .iter()
+            .map(|&(offset, length, target)| {
+                format!(
+                    "{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{},\"target\":{}}}",
+                    offset, length, target
+                )
+            })
+            .collect();
+        let uri = match bin_uri {
+            Some(uri) => format!(",\"uri\":\"{}\"", percent_encode(uri)),
+            None => String::new(),
+        };
+        let roots: Vec<String> = self.roots.iter().map(|r| r.to_string()).collect();
+
+        let mut json = String::from("{\"asset\":{\"version\":\"2.0\",\"generator\":\"geom\"}");
+        let _ = write!(
+            json,
+            ",\"scene\":0,\"scenes\":[{{\"nodes\":[{}]}}]",
+            roots.join(",")
+        );
+        if !nodes.is_empty() {
+            let _ = write!(json, ",\"nodes\":[{}]", nodes.join(","));
+        }
+        if !meshes.is_empty() {
+            let _ = write!(json, ",\"meshes\":[{}]", meshes.join(","));
+        }
+        if !materials.is_empty() {
+            let _ = write!(json, ",\"materials\":[{}]", materials.join(","));
+        }
+        if !accessors.is_empty() {
+            let _ = write!(json, ",\"accessors\":[{}]", accessors.join(","));
+            let _ = write!(json, ",\"bufferViews\":[{}]", views.join(","));
+            let _ = write!(
+                json,
+                ",\"buffers\":[{{\"byteLength\":{}{}}}]",
+                buffer.data.len(),
+                uri
+            );
+        }
+        json.push('}');
+        Ok((json, buffer.data))
+    }
+
+    pub fn to_glb(&self) -> Result<Vec<u8>> {
+        let (json, bin) = self.to_gltf(None)?;
+        let mut json = json.into_bytes();
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+        let mut length = 12 + 8 + json.len();
+        if !bin.is_empty() {
+            length += 8 + bin.len();
+        }
+        let mut glb = Vec::with_capacity(length);
+        glb.extend_from_slice(b"glTF");
+        glb.extend_from_slice(&2u32.to_le_bytes());
+        glb.extend_from_slice(&(length as u32).to_le_bytes());
+        glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        glb.extend_from_slice(b"JSON");
+        glb.extend_from_slice(&json);
+        if !bin.is_empty() {
+            glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+            glb.extend_from_slice(b"BIN\0");
+            glb.extend_from_slice(&bin);
+        }
+        Ok(glb)
+    }
+}
+
+pub fn write_gltf<P: AsRef<std::path::Path>>(path: P, scene: &Scene) -> Result<()> {
+    let path = path.as_ref();
+    let bin_path = path.with_extension("bin");
+    let bin_name = bin_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| Error::Parse("invalid glTF file name".to_string()))?;
+    let (json, bin) = scene.to_gltf(Some(bin_name))?;
+    std::fs::write(path, json)?;
+    std::fs::write(&bin_path, bin)?;
+    Ok(())
+}
+
+pub fn write_glb<P: AsRef<std::path::Path>>(path: P, scene: &Scene) -> Result<()> {
+    std::fs::write(path, scene.to_glb()?)?;
+    Ok(())
+}
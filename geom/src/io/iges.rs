@@ -0,0 +1,797 @@
+pub mod file;
+pub mod writer;
+
+use std::collections::BTreeMap;
+
+use crate::Axis3d;
+use crate::BSplineCurve3d;
+use crate::Circle3d;
+use crate::Cone;
+use crate::CoordinateSystem3d;
+use crate::Cylinder;
+use crate::Direction3d;
+use crate::Ellipse3d;
+use crate::GeneralCoordinateSystem3d;
+use crate::Hyperbola3d;
+use crate::Line3d;
+use crate::Parabola3d;
+use crate::Plane;
+use crate::Point3d;
+use crate::Sphere;
+use crate::Torus;
+use crate::Trsf3d;
+use crate::XYZ;
+use crate::bspline::compress_knots;
+use crate::io::Result;
+use file::{Entity, IgesFile, Parameter};
+pub use writer::IgesWriter;
+
+// Curves carry the parameter range they are trimmed to; rays and unbounded
+// lines use infinite bounds.
+#[derive(Debug, Clone)]
+pub enum IgesGeometry {
+    Point(Point3d),
+    Direction(Direction3d),
+    Transformation(Trsf3d),
+    Line {
+        line: Line3d,
+        first: f64,
+        last: f64,
+    },
+    CircularArc {
+        circle: Circle3d,
+        first: f64,
+        last: f64,
+    },
+    EllipticArc {
+        ellipse: Ellipse3d,
+        first: f64,
+        last: f64,
+    },
+    HyperbolicArc {
+        hyperbola: Hyperbola3d,
+        first: f64,
+        last: f64,
+    },
+    ParabolicArc {
+        parabola: Parabola3d,
+        first: f64,
+        last: f64,
+    },
+    BSplineCurve {
+        curve: BSplineCurve3d,
+        first: f64,
+        last: f64,
+    },
+    Plane(Plane),
+    CylindricalSurface(Cylinder),
+    ConicalSurface(Cone),
+    SphericalSurface(Sphere),
+    ToroidalSurface(Torus),
+}
+
+impl IgesGeometry {
+    // Entity type the geometry is written as.
+    pub fn entity_type(&self) -> i64 {
+        match self {
+            IgesGeometry::Point(_) => 116,
+            IgesGeometry::Direction(_) => 123,
+            IgesGeometry::Transformation(_) => 124,
+            IgesGeometry::Line { .. } => 110,
+            IgesGeometry::CircularArc { .. } => 100,
+            IgesGeometry::EllipticArc { .. }
+            | IgesGeometry::HyperbolicArc { .. }
+            | IgesGeometry::ParabolicArc { .. } => 104,
+            IgesGeometry::BSplineCurve { .. } => 126,
+            IgesGeometry::Plane(_) => 190,
+            IgesGeometry::CylindricalSurface(_) => 192,
+            IgesGeometry::ConicalSurface(_) => 194,
+            IgesGeometry::SphericalSurface(_) => 196,
+            IgesGeometry::ToroidalSurface(_) => 198,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IgesModel {
+    pub file: IgesFile,
+    // Metres per model unit, if the global section names a known unit.
+    pub length_unit: Option<f64>,
+    pub geometries: BTreeMap<usize, IgesGeometry>,
+    pub skipped: Vec<(usize, String)>,
+}
+
+impl IgesModel {
+    pub fn get(&self, de: usize) -> Option<&IgesGeometry> {
+        self.geometries.get(&de)
+    }
+}
+
+type Conversion<T> = std::result::Result<T, String>;
+
+fn arg(params: &[Parameter], index: usize) -> Conversion<&Parameter> {
+    params
+        .get(index)
+        .ok_or_else(|| format!("missing parameter {}", index + 1))
+}
+
+fn real(params: &[Parameter], index: usize) -> Conversion<f64> {
+    match arg(params, index)? {
+        Parameter::Integer(v) => Ok(*v as f64),
+        Parameter::Real(v) => Ok(*v),
+        Parameter::Default => Ok(0.0),
+        _ => Err(format!("parameter {} is not a number", index + 1)),
+    }
+}
+
+fn reals(params: &[Parameter], start: usize, count: usize) -> Conversion<Vec<f64>> {
+    match start.checked_add(count) {
+        Some(end) if end <= params.len() => (start..end).map(|i| real(params, i)).collect(),
+        _ => Err(format!(
+            "{} parameters from parameter {} exceed the {} present",
+            count,
+            start + 1,
+            params.len()
+        )),
+    }
+}
+
+// Parameter count `a * b + c` of a variable length entity.
+fn size(a: usize, b: usize, c: usize) -> Conversion<usize> {
+    a.checked_mul(b)
+        .and_then(|ab| ab.checked_add(c))
+        .ok_or_else(|| "parameter count overflows".to_string())
+}
+
+// Real without a default value, which must be present and finite.
+fn given(params: &[Parameter], index: usize, what: &str) -> Conversion<f64> {
+    let value = match arg(params, index)? {
+        Parameter::Default => return Err(format!("{} is missing", what)),
+        _ => real(params, index)?,
+    };
+    if !value.is_finite() {
+        return Err(format!("{} must be finite but is {}", what, value));
+    }
+    Ok(value)
+}
+
+// Radius, which must be finite and positive.
+fn length(params: &[Parameter], index: usize, what: &str) -> Conversion<f64> {
+    let value = given(params, index, what)?;
+    if value <= 0.0 {
+        return Err(format!("{} must be positive but is {}", what, value));
+    }
+    Ok(value)
+}
+
+fn integer(params: &[Parameter], index: usize) -> Conversion<i64> {
+    match arg(params, index)? {
+        Parameter::Integer(v) => Ok(*v),
+        Parameter::Default => Ok(0),
+        _ => Err(format!("parameter {} is not an integer", index + 1)),
+    }
+}
+
+fn count(params: &[Parameter], index: usize) -> Conversion<usize> {
+    usize::try_from(integer(params, index)?)
+        .map_err(|_| format!("parameter {} must not be negative", index + 1))
+}
+
+fn pointer(params: &[Parameter], index: usize) -> Conversion<usize> {
+    match integer(params, index)? {
+        v if v > 0 => Ok(v as usize),
+        _ => Err(format!("parameter {} is not an entity pointer", index + 1)),
+    }
+}
+
+fn direction(x: f64, y: f64, z: f64) -> Conversion<Direction3d> {
+    if x * x + y * y + z * z <= f64::MIN_POSITIVE {
+        return Err("zero-length direction".to_string());
+    }
+    Ok(Direction3d::from_coords(x, y, z))
+}
+
+// Some direction perpendicular to `d`, used where IGES leaves the x axis free.
+fn perpendicular(d: &Direction3d) -> Direction3d {
+    let (x, y, z) = d.get_coords();
+    let axis = if x.abs() <= y.abs() && x.abs() <= z.abs() {
+        Direction3d::from_coords(1.0, 0.0, 0.0)
+    } else if y.abs() <= z.abs() {
+        Direction3d::from_coords(0.0, 1.0, 0.0)
+    } else {
+        Direction3d::from_coords(0.0, 0.0, 1.0)
+    };
+    d.cross_new(&axis)
+}
+
+// Placement of a planar curve in definition space: origin (x, y, zt), z axis up.
+fn definition_frame(x: f64, y: f64, zt: f64, xdir: (f64, f64)) -> CoordinateSystem3d {
+    CoordinateSystem3d::from_location_direction_xdirection(
+        Point3d::from_coords(x, y, zt),
+        Direction3d::from_coords(0.0, 0.0, 1.0),
+        Direction3d::from_coords(xdir.0, xdir.1, 0.0),
+    )
+}
+
+// Parameter range of a closed curve traversed counterclockwise from `first` to `last`.
+fn closed_range(first: f64, last: f64) -> (f64, f64) {
+    let tau = 2.0 * std::f64::consts::PI;
+    let first = first.rem_euclid(tau);
+    let mut sweep = (last - first).rem_euclid(tau);
+    if sweep < 1e-12 {
+        sweep = tau;
+    }
+    (first, first + sweep)
+}
+
+fn transform_point(t: &Trsf3d, p: &Point3d) -> Point3d {
+    Point3d::from_xyz(t.transform_xyz(&p.get_xyz()))
+}
+
+fn transform_direction(t: &Trsf3d, d: &Direction3d) -> Direction3d {
+    Direction3d::from_xyz(t.transform_direction_xyz(d.get_xyz()))
+}
+
+// Keeps the curve parametrisation: x and y follow the matrix, z = x ^ y.
+fn transform_placement(t: &Trsf3d, cs: &CoordinateSystem3d) -> CoordinateSystem3d {
+    let vxdir = transform_direction(t, &cs.vxdir);
+    let vydir = transform_direction(t, &cs.vydir);
+    CoordinateSystem3d {
+        axis: Axis3d::from_location_direction(
+            transform_point(t, &cs.axis.location),
+            vxdir.cross_new(&vydir),
+        ),
+        vydir,
+        vxdir,
+    }
+}
+
+fn transform_general_placement(
+    t: &Trsf3d,
+    cs: &GeneralCoordinateSystem3d,
+) -> GeneralCoordinateSystem3d {
+    GeneralCoordinateSystem3d {
+        axis: Axis3d::from_location_direction(
+            transform_point(t, &cs.axis.location),
+            transform_direction(t, &cs.axis.direction),
+        ),
+        vydir: transform_direction(t, &cs.vydir),
+        vxdir: transform_direction(t, &cs.vxdir),
+    }
+}
+
+fn transform_geometry(t: &Trsf3d, geometry: IgesGeometry) -> IgesGeometry {
+    let s = t.scale;
+    match geometry {
+        IgesGeometry::Point(p) => IgesGeometry::Point(transform_point(t, &p)),
+        IgesGeometry::Direction(d) => IgesGeometry::Direction(transform_direction(t, &d)),
+        IgesGeometry::Transformation(m) => IgesGeometry::Transformation(t.multiply_new(&m)),
+        IgesGeometry::Line { line, first, last } => IgesGeometry::Line {
+            line: Line3d {
+                pos: Axis3d::from_location_direction(
+                    transform_point(t, &line.pos.location),
+                    transform_direction(t, &line.pos.direction),
+                ),
+            },
+            first: first * s,
+            last: last * s,
+        },
+        IgesGeometry::CircularArc {
+            circle,
+            first,
+            last,
+        } => IgesGeometry::CircularArc {
+            circle: Circle3d {
+                position: transform_placement(t, &circle.position),
+                radius: circle.radius * s,
+            },
+            first,
+            last,
+        },
+        IgesGeometry::EllipticArc {
+            ellipse,
+            first,
+            last,
+        } => IgesGeometry::EllipticArc {
+            ellipse: Ellipse3d {
+                position: transform_placement(t, &ellipse.position),
+                major_radius: ellipse.major_radius * s,
+                minor_radius: ellipse.minor_radius * s,
+            },
+            first,
+            last,
+        },
+        IgesGeometry::HyperbolicArc {
+            hyperbola,
+            first,
+            last,
+        } => IgesGeometry::HyperbolicArc {
+            hyperbola: Hyperbola3d {
+                position: transform_placement(t, &hyperbola.position),
+                major_radius: hyperbola.major_radius * s,
+                minor_radius: hyperbola.minor_radius * s,
+            },
+            first,
+            last,
+        },
+        IgesGeometry::ParabolicArc {
+            parabola,
+            first,
+            last,
+        } => IgesGeometry::ParabolicArc {
+            parabola: Parabola3d {
+                pos: transform_placement(t, &parabola.pos),
+                focal_length: parabola.focal_length * s,
+            },
+            first: first * s,
+            last: last * s,
+        },
+        IgesGeometry::BSplineCurve {
+            mut curve,
+            first,
+            last,
+        } => {
+            for pole in curve.poles.iter_mut() {
+                *pole = transform_point(t, pole);
+            }
+            IgesGeometry::BSplineCurve { curve, first, last }
+        }
+        IgesGeometry::Plane(plane) => IgesGeometry::Plane(Plane {
+            pos: transform_general_placement(t, &plane.pos),
+        }),
+        IgesGeometry::CylindricalSurface(c) => IgesGeometry::CylindricalSurface(Cylinder {
+            position: transform_general_placement(t, &c.position),
+            radius: c.radius * s,
+        }),
+        IgesGeometry::ConicalSurface(c) => IgesGeometry::ConicalSurface(Cone {
+            position: transform_general_placement(t, &c.position),
+            radius: c.radius * s,
+            semi_angle: c.semi_angle,
+        }),
+        IgesGeometry::SphericalSurface(c) => IgesGeometry::SphericalSurface(Sphere {
+            pos: transform_general_placement(t, &c.pos),
+            radius: c.radius * s,
+        }),
+        IgesGeometry::ToroidalSurface(c) => IgesGeometry::ToroidalSurface(Torus {
+            pos: transform_general_placement(t, &c.pos),
+            major_radius: c.major_radius * s,
+            minor_radius: c.minor_radius * s,
+        }),
+    }
+}
+
+// Conic A x^2 + B xy + C y^2 + D x + E y + F = 0 at height zt, from start to end point.
+fn conic(form: i64, p: &[f64], start: (f64, f64), end: (f64, f64)) -> Conversion<IgesGeometry> {
+    let (a, b, c, d, e, f, zt) = (p[0], p[1], p[2], p[3], p[4], p[5], p[6]);
+    let norm = a * a + b * b + c * c;
+    if norm <= f64::MIN_POSITIVE {
+        return Err("conic has no quadratic terms".to_string());
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    let form = match form {
+        1..=3 => form,
+        _ if discriminant.abs() <= 1e-12 * norm => 3,
+        _ if discriminant < 0.0 => 1,
+        _ => 2,
+    };
+    // Eigenvectors of the quadratic form [[a, b/2], [b/2, c]].
+    let theta = 0.5 * b.atan2(a - c);
+    let e1 = (theta.cos(), theta.sin());
+    let e2 = (-theta.sin(), theta.cos());
+    let l1 = a * e1.0 * e1.0 + b * e1.0 * e1.1 + c * e1.1 * e1.1;
+    let l2 = a + c - l1;
+    let dot = |u: (f64, f64), v: (f64, f64)| u.0 * v.0 + u.1 * v.1;
+
+    if form == 3 {
+        let (mut u, v, l) = if l1.abs() < l2.abs() {
+            (e1, e2, l2)
+        } else {
+            (e2, e1, l1)
+        };
+        let mut du = dot((d, e), u);
+        if du.abs() <= 1e-12 * l.abs() {
+            return Err("degenerate parabola".to_string());
+        }
+        if -du / l < 0.0 {
+            u = (-u.0, -u.1);
+            du = -du;
+        }
+        let dv = dot((d, e), v);
+        let v0 = -dv / (2.0 * l);
+        let u0 = (l * v0 * v0 - f) / du;
+        let vertex = (u0 * u.0 + v0 * v.0, u0 * u.1 + v0 * v.1);
+        let pos = definition_frame(vertex.0, vertex.1, zt, u);
+        let y = (-u.1, u.0);
+        let param = |q: (f64, f64)| dot((q.0 - vertex.0, q.1 - vertex.1), y);
+        return Ok(IgesGeometry::ParabolicArc {
+            parabola: Parabola3d {
+                pos,
+                focal_length: -du / l / 4.0,
+            },
+            first: param(start),
+            last: param(end),
+        });
+    }
+
+    let det = a * c - b * b / 4.0;
+    if det.abs() <= 1e-14 * norm {
+        return Err("degenerate conic".to_string());
+    }
+    let cx = (-d / 2.0 * c + e / 2.0 * b / 2.0) / det;
+    let cy = (-e / 2.0 * a + d / 2.0 * b / 2.0) / det;
+    let f0 = f + (d * cx + e * cy) / 2.0;
+    let (r1, r2) = (-f0 / l1, -f0 / l2);
+    let local = |q: (f64, f64), x: (f64, f64)| {
+        let q = (q.0 - cx, q.1 - cy);
+        (dot(q, x), dot(q, (-x.1, x.0)))
+    };
+
+    if form == 1 {
+        if r1 <= 0.0 || r2 <= 0.0 {
+            return Err("conic coefficients do not describe a real ellipse".to_string());
+        }
+        let (mut x, major, minor) = if r1 >= r2 {
+            (e1, r1.sqrt(), r2.sqrt())
+        } else {
+            (e2, r2.sqrt(), r1.sqrt())
+        };
+        if x.0 < -1e-12 || (x.0.abs() <= 1e-12 && x.1 < 0.0) {
+            x = (-x.0, -x.1);
+        }
+        let param = |q| {
+            let (u, v) = local(q, x);
+            (v / minor).atan2(u / major)
+        };
+        let (first, last) = closed_range(param(start), param(end));
+        return Ok(IgesGeometry::EllipticArc {
+            ellipse: Ellipse3d {
+                position: definition_frame(cx, cy, zt, x),
+                major_radius: major,
+                minor_radius: minor,
+            },
+            first,
+            last,
+        });
+    }
+
+    let (mut x, major, minor) = match (r1 > 0.0, r2 > 0.0) {
+        (true, false) => (e1, r1.sqrt(), (-r2).sqrt()),
+        (false, true) => (e2, r2.sqrt(), (-r1).sqrt()),
+        _ => return Err("conic coefficients do not describe a hyperbola".to_string()),
+    };
+    if local(start, x).0 < 0.0 {
+        x = (-x.0, -x.1);
+    }
+    let param = |q| (local(q, x).1 / minor).asinh();
+    Ok(IgesGeometry::HyperbolicArc {
+        hyperbola: Hyperbola3d {
+            position: definition_frame(cx, cy, zt, x),
+            major_radius: major,
+            minor_radius: minor,
+        },
+        first: param(start),
+        last: param(end),
+    })
+}
+
+struct Importer<'a> {
+    file: &'a IgesFile,
+}
+
+impl<'a> Importer<'a> {
+    fn entity(&self, de: usize, entity_type: i64) -> Conversion<&'a Entity> {
+        let entity = self
+            .file
+            .get(de)
+            .ok_or_else(|| format!("pointer to missing entity {}", de))?;
+        if entity.entity_type != entity_type {
+            return Err(format!(
+                "entity {} has type {}, expected {}",
+                de, entity.entity_type, entity_type
+            ));
+        }
+        Ok(entity)
+    }
+
+    // Matrix of a 124 entity composed with the transformations it points to.
+    fn transformation(&self, de: i64, depth: usize) -> Conversion<Option<Trsf3d>> {
+        if de == 0 {
+            return Ok(None);
+        }
+        if de < 0 || depth > 16 {
+            return Err(format!("invalid transformation pointer {}", de));
+        }
+        let entity = self.entity(de as usize, 124)?;
+        let v = reals(&entity.parameters, 0, 12)?;
+        let own = Trsf3d::from_values([
+            [v[0], v[1], v[2], v[3]],
+            [v[4], v[5], v[6], v[7]],
+            [v[8], v[9], v[10], v[11]],
+        ])?;
+        Ok(Some(
+            match self.transformation(entity.transform, depth + 1)? {
+                Some(parent) => parent.multiply_new(&own),
+                None => own,
+            },
+        ))
+    }
+
+    fn point(&self, de: usize) -> Conversion<Point3d> {
+        let v = reals(&self.entity(de, 116)?.parameters, 0, 3)?;
+        Ok(Point3d::from_coords(v[0], v[1], v[2]))
+    }
+
+    fn direction(&self, de: usize) -> Conversion<Direction3d> {
+        let v = reals(&self.entity(de, 123)?.parameters, 0, 3)?;
+        direction(v[0], v[1], v[2])
+    }
+
+    // Surface placement from location and axis pointers and an optional reference direction.
+    fn frame(
+        &self,
+        location: usize,
+        axis: Option<usize>,
+        reference: Option<usize>,
+    ) -> Conversion<GeneralCoordinateSystem3d> {
+        let location = self.point(location)?;
+        let axis = match axis {
+            Some(de) => self.direction(de)?,
+            None => Direction3d::from_coords(0.0, 0.0, 1.0),
+        };
+        let xdir = match reference {
+            Some(de) => self.direction(de)?,
+            None => perpendicular(&axis),
+        };
+        if axis.get_xyz().cross_new(xdir.get_xyz()).length() < 1e-12 {
+            return Err("reference direction is parallel to the axis".to_string());
+        }
+        Ok(GeneralCoordinateSystem3d::from_location_direction_xdirection(location, axis, xdir))
+    }
+
+    fn convert(&self, de: usize) -> Conversion<IgesGeometry> {
+        let entity = self.file.get(de).ok_or("missing entity")?;
+        let p = &entity.parameters;
+        let form1 = entity.form == 1;
+        let geometry = match entity.entity_type {
+            116 => {
+                let v = reals(p, 0, 3)?;
+                IgesGeometry::Point(Point3d::from_coords(v[0], v[1], v[2]))
+            }
+            123 => {
+                let v = reals(p, 0, 3)?;
+                IgesGeometry::Direction(direction(v[0], v[1], v[2])?)
+            }
+            124 => {
+                let t = self.transformation(de as i64, 0)?.unwrap();
+                return Ok(IgesGeometry::Transformation(t));
+            }
+            100 => {
+                let v = reals(p, 0, 7)?;
+                let (zt, cx, cy) = (v[0], v[1], v[2]);
+                let radius = (v[3] - cx).hypot(v[4] - cy);
+                if radius <= 0.0 {
+                    return Err("arc has zero radius".to_string());
+                }
+                let (first, last) =
+                    closed_range((v[4] - cy).atan2(v[3] - cx), (v[6] - cy).atan2(v[5] - cx));
+                IgesGeometry::CircularArc {
+                    circle: Circle3d {
+                        position: definition_frame(cx, cy, zt, (1.0, 0.0)),
+                        radius,
+                    },
+                    first,
+                    last,
+                }
+            }
+            104 => {
+                let v = reals(p, 0, 11)?;
+                conic(entity.form, &v, (v[7], v[8]), (v[9], v[10]))?
+            }
+            110 => {
+                let v = reals(p, 0, 6)?;
+                let start = XYZ::from_coords(v[0], v[1], v[2]);
+                let delta = &XYZ::from_coords(v[3], v[4], v[5]) - &start;
+                let length = delta.length();
+                if length <= 0.0 {
+                    return Err("line has coincident end points".to_string());
+                }
+                let (first, last) = match entity.form {
+                    1 => (0.0, f64::INFINITY),
+                    2 => (f64::NEG_INFINITY, f64::INFINITY),
+                    _ => (0.0, length),
+                };
+                IgesGeometry::Line {
+                    line: Line3d {
+                        pos: Axis3d::from_location_direction(
+                            Point3d::from_xyz(start),
+                            Direction3d::from_xyz(delta),
+                        ),
+                    },
+                    first,
+                    last,
+                }
+            }
+            112 => {
+                let n = count(p, 3)?;
+                if n == 0 {
+                    return Err("parametric spline has no segments".to_string());
+                }
+                let breaks = reals(p, 4, size(n, 1, 1)?)?;
+                let coefficients = reals(p, size(n, 1, 5)?, size(n, 12, 0)?)?;
+                let mut poles = Vec::with_capacity(3 * n + 1);
+                for (i, c) in coefficients.chunks(12).enumerate() {
+                    let h = breaks[i + 1] - breaks[i];
+                    // Power basis on [0, h] to cubic Bezier control points.
+                    let bezier = |k: usize| {
+                        let (a, b, c, d) = (c[4 * k], c[4 * k + 1], c[4 * k + 2], c[4 * k + 3]);
+                        [
+                            a,
+                            a + b * h / 3.0,
+                            a + 2.0 * b * h / 3.0 + c * h * h / 3.0,
+                            a + b * h + c * h * h + d * h * h * h,
+                        ]
+                    };
+                    let (x, y, z) = (bezier(0), bezier(1), bezier(2));
+                    for j in if i == 0 { 0 } else { 1 }..4 {
+                        poles.push(Point3d::from_coords(x[j], y[j], z[j]));
+                    }
+                }
+                let mut multiplicities = vec![3; n + 1];
+                multiplicities[0] = 4;
+                multiplicities[n] = 4;
+                let (first, last) = (breaks[0], breaks[n]);
+                IgesGeometry::BSplineCurve {
+                    curve: BSplineCurve3d::from_poles_knots(3, poles, breaks, multiplicities)?,
+                    first,
+                    last,
+                }
+            }
+            126 => {
+                let k = count(p, 0)?;
+                let m = count(p, 1)?;
+                let polynomial = integer(p, 4)? == 1;
+                let num_poles = size(k, 1, 1)?;
+                let num_knots = size(num_poles, 1, size(m, 1, 1)?)?;
+                let flat = reals(p, 6, num_knots)?;
+                let weights_at = size(num_knots, 1, 6)?;
+                let weights = reals(p, weights_at, num_poles)?;
+                let coords_at = size(num_poles, 1, weights_at)?;
+                let coords = reals(p, coords_at, size(num_poles, 3, 0)?)?;
+                let range = reals(p, size(num_poles, 3, coords_at)?, 2)?;
+                let poles = coords
+                    .chunks(3)
+                    .map(|c| Point3d::from_coords(c[0], c[1], c[2]))
+                    .collect();
+                let (knots, multiplicities) = compress_knots(&flat);
+                let curve = if polynomial || weights.iter().all(|&w| w == weights[0]) {
+                    BSplineCurve3d::from_poles_knots(m, poles, knots, multiplicities)?
+                } else {
+                    BSplineCurve3d::from_poles_weights_knots(
+                        m,
+                        poles,
+                        weights,
+                        knots,
+                        multiplicities,
+                    )?
+                };
+                IgesGeometry::BSplineCurve {
+                    curve,
+                    first: range[0],
+                    last: range[1],
+                }
+            }
+            108 => {
+                let v = reals(p, 0, 4)?;
+                let normal = direction(v[0], v[1], v[2])?;
+                let n = XYZ::from_coords(v[0], v[1], v[2]);
+                let symbol = XYZ::from_coords(real(p, 5)?, real(p, 6)?, real(p, 7)?);
+                let offset = (n.dot(&symbol) - v[3]) / n.squared_length();
+                let origin = &symbol - &(&n * offset);
+                IgesGeometry::Plane(Plane {
+                    pos: GeneralCoordinateSystem3d::from_location_direction_xdirection(
+                        Point3d::from_xyz(origin),
+                        normal,
+                        perpendicular(&normal),
+                    ),
+                })
+            }
+            190 => IgesGeometry::Plane(Plane {
+                pos: self.frame(
+                    pointer(p, 0)?,
+                    Some(pointer(p, 1)?),
+                    if form1 { Some(pointer(p, 2)?) } else { None },
+                )?,
+            }),
+            192 => IgesGeometry::CylindricalSurface(Cylinder {
+                position: self.frame(
+                    pointer(p, 0)?,
+                    Some(pointer(p, 1)?),
+                    if form1 { Some(pointer(p, 3)?) } else { None },
+                )?,
+                radius: length(p, 2, "cylinder radius")?,
+            }),
+            194 => {
+                // The radius at the axis point may be zero, at the apex.
+                let radius = given(p, 2, "cone radius")?;
+                let semi_angle = given(p, 3, "cone semi-angle")?;
+                if radius < 0.0 {
+                    return Err(format!(
+                        "cone radius must not be negative but is {}",
+                        radius
+                    ));
+                }
+                if !(semi_angle > 0.0 && semi_angle < 90.0) {
+                    return Err(format!(
+                        "cone semi-angle must lie between 0 and 90 degrees but is {}",
+                        semi_angle
+                    ));
+                }
+                IgesGeometry::ConicalSurface(Cone {
+                    position: self.frame(
+                        pointer(p, 0)?,
+                        Some(pointer(p, 1)?),
+                        if form1 { Some(pointer(p, 4)?) } else { None },
+                    )?,
+                    radius,
+                    semi_angle: semi_angle.to_radians(),
+                })
+            }
+            196 => IgesGeometry::SphericalSurface(Sphere {
+                pos: if form1 {
+                    self.frame(pointer(p, 0)?, Some(pointer(p, 2)?), Some(pointer(p, 3)?))?
+                } else {
+                    self.frame(pointer(p, 0)?, None, None)?
+                },
+                radius: length(p, 1, "sphere radius")?,
+            }),
+            198 => IgesGeometry::ToroidalSurface(Torus {
+                pos: self.frame(
+                    pointer(p, 0)?,
+                    Some(pointer(p, 1)?),
+                    if form1 { Some(pointer(p, 4)?) } else { None },
+                )?,
+                major_radius: length(p, 2, "torus major radius")?,
+                minor_radius: length(p, 3, "torus minor radius")?,
+            }),
+            t => return Err(format!("unsupported entity type {}", t)),
+        };
+        Ok(match self.transformation(entity.transform, 0)? {
+            Some(t) => transform_geometry(&t, geometry),
+            None => geometry,
+        })
+    }
+}
+
+const SUPPORTED: [i64; 14] = [
+    100, 104, 108, 110, 112, 116, 123, 124, 126, 190, 192, 194, 196, 198,
+];
+
+pub fn read_str(text: &str) -> Result<IgesModel> {
+    let file = IgesFile::parse(text)?;
+    let importer = Importer { file: &file };
+    let mut geometries = BTreeMap::new();
+    let mut skipped = Vec::new();
+    for (&de, entity) in file.entities.iter() {
+        if !SUPPORTED.contains(&entity.entity_type) {
+            continue;
+        }
+        match importer.convert(de) {
+            Ok(geometry) => {
+                geometries.insert(de, geometry);
+            }
+            Err(reason) => skipped.push((de, reason)),
+        }
+    }
+    let length_unit = file.length_unit();
+    Ok(IgesModel {
+        file,
+        length_unit,
+        geometries,
+        skipped,
+    })
+}
+
+pub fn read_file<P: AsRef<std::path::Path>>(path: P) -> Result<IgesModel> {
+    let bytes = std::fs::read(path)?;
+    read_str(&String::from_utf8_lossy(&bytes))
+}
@@ -0,0 +1,468 @@
+use std::collections::BTreeMap;
+
+use crate::io::{Error, Result, format_real};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Parameter {
+    Integer(i64),
+    Real(f64),
+    String(String),
+    Default,
+}
+
+// One directory entry together with its parameter data. Pointers to other
+// entities are directory entry sequence numbers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entity {
+    pub entity_type: i64,
+    pub structure: i64,
+    pub line_font: i64,
+    pub level: i64,
+    pub view: i64,
+    pub transform: i64,
+    pub label_display: i64,
+    pub status: String,
+    pub line_weight: i64,
+    pub color: i64,
+    pub form: i64,
+    pub label: String,
+    pub subscript: i64,
+    pub parameters: Vec<Parameter>,
+}
+
+impl Entity {
+    pub fn new(entity_type: i64, form: i64, parameters: Vec<Parameter>) -> Self {
+        Entity {
+            entity_type,
+            structure: 0,
+            line_font: 0,
+            level: 0,
+            view: 0,
+            transform: 0,
+            label_display: 0,
+            status: "00000000".to_string(),
+            line_weight: 0,
+            color: 0,
+            form,
+            label: String::new(),
+            subscript: 0,
+            parameters,
+        }
+    }
+
+    // Subordinate entity switch (status digits 3-4) is non-zero.
+    pub fn is_dependent(&self) -> bool {
+        self.status
+            .get(2..4)
+            .is_some_and(|s| s != "00" && s != "  ")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IgesFile {
+    pub start: Vec<String>,
+    pub global: Vec<Parameter>,
+    // Keyed by directory entry sequence number (1, 3, 5, ...).
+    pub entities: BTreeMap<usize, Entity>,
+}
+
+impl Default for IgesFile {
+    fn default() -> Self {
+        IgesFile::new()
+    }
+}
+
+fn parse_value(token: &str) -> Result<Parameter> {
+    let token = token.trim();
+    if token.is_empty() {
+        return Ok(Parameter::Default);
+    }
+    if let Ok(v) = token.parse::<i64>() {
+        return Ok(Parameter::Integer(v));
+    }
+    token
+        .replace(['D', 'd'], "E")
+        .parse::<f64>()
+        .map(Parameter::Real)
+        .map_err(|_| Error::Parse(format!("invalid IGES parameter '{}'", token)))
+}
+
+// Splits free-format parameter data; anything after the record delimiter is ignored.
+fn tokenize(text: &str, pd: char, rd: char) -> Result<Vec<Parameter>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut params = Vec::new();
+    let mut i = 0;
+    loop {
+        while i < chars.len() && chars[i] == ' ' {
+            i += 1;
+        }
+        if i >= chars.len() {
+            break;
+        }
+        let start = i;
+        while i < chars.len() && chars[i].is_ascii_digit() {
+            i += 1;
+        }
+        if i > start && i < chars.len() && chars[i] == 'H' {
+            let digits: String = chars[start..i].iter().collect();
+            let end = digits
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| (i + 1).checked_add(n))
+                .ok_or_else(|| Error::Parse(format!("invalid Hollerith length '{}'", digits)))?;
+            if end > chars.len() {
+                return Err(Error::Parse("truncated Hollerith string".to_string()));
+            }
+            params.push(Parameter::String(chars[i + 1..end].iter().collect()));
+            i = end;
+            while i < chars.len() && chars[i] == ' ' {
+                i += 1;
+            }
+        } else {
+            i = start;
+            while i < chars.len() && chars[i] != pd && chars[i] != rd {
+                i += 1;
+            }
+            params.push(parse_value(&chars[start..i].iter().collect::<String>())?);
+        }
+        if i >= chars.len() || chars[i] == rd {
+            break;
+        }
+        if chars[i] != pd {
+            return Err(Error::Parse(format!(
+                "expected delimiter after IGES parameter {}",
+                params.len()
+            )));
+        }
+        i += 1;
+    }
+    Ok(params)
+}
+
+fn delimiter(params: &[Parameter], index: usize, default: char) -> char {
+    match params.get(index) {
+        Some(Parameter::String(s)) => s.chars().next().unwrap_or(default),
+        _ => default,
+    }
+}
+
+fn field(line: &str, index: usize) -> &str {
+    line.get(index * 8..index * 8 + 8).unwrap_or("").trim()
+}
+
+fn int_field(line: &str, index: usize) -> Result<i64> {
+    let f = field(line, index);
+    if f.is_empty() {
+        return Ok(0);
+    }
+    f.parse()
+        .map_err(|_| Error::Parse(format!("invalid directory entry field '{}'", f)))
+}
+
+// Packs tokens into fixed-width lines; only tokens longer than a line are split.
+fn pack(tokens: &[String], width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current: Vec<char> = Vec::new();
+    for token in tokens.iter() {
+        let n = token.chars().count();
+        if current.len() + n > width && n <= width {
+            lines.push(current.drain(..).collect());
+        }
+        current.extend(token.chars());
+        while current.len() > width {
+            lines.push(current.drain(..width).collect());
+        }
+    }
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(current.into_iter().collect());
+    }
+    lines
+}
+
+fn tokens(head: Option<i64>, params: &[Parameter], pd: char, rd: char) -> Vec<String> {
+    let mut values: Vec<String> = head.map(|h| h.to_string()).into_iter().collect();
+    values.extend(params.iter().map(|p| p.to_string()));
+    let n = values.len();
+    values
+        .into_iter()
+        .enumerate()
+        .map(|(i, v)| format!("{}{}", v, if i + 1 == n { rd } else { pd }))
+        .collect()
+}
+
+impl IgesFile {
+    pub fn new() -> Self {
+        IgesFile {
+            start: Vec::new(),
+            global: vec![
+                Parameter::String(",".to_string()),
+                Parameter::String(";".to_string()),
+            ],
+            entities: BTreeMap::new(),
+        }
+    }
+
+    pub fn parameter_delimiter(&self) -> char {
+        delimiter(&self.global, 0, ',')
+    }
+
+    pub fn record_delimiter(&self) -> char {
+        delimiter(&self.global, 1, ';')
+    }
+
+    pub fn get(&self, de: usize) -> Option<&Entity> {
+        self.entities.get(&de)
+    }
+
+    // Appends an entity and returns its directory entry sequence number.
+    pub fn add(&mut self, entity: Entity) -> usize {
+        let de = 2 * self.entities.len() + 1;
+        self.entities.insert(de, entity);
+        de
+    }
+
+    // Metres per model unit from the global unit flag (and name for flag 3).
+    pub fn length_unit(&self) -> Option<f64> {
+        let name = match self.global.get(14) {
+            Some(Parameter::String(s)) => s.trim().to_uppercase(),
+            _ => String::new(),
+        };
+        let flag = match self.global.get(13) {
+            Some(Parameter::Integer(v)) => *v,
+            _ => 1,
+        };
+        let flag = match (flag, name.as_str()) {
+            (3, "IN" | "INCH") => 1,
+            (3, "MM") => 2,
+            (3, "FT") => 4,
+            (3, "MI") => 5,
+            (3, "M") => 6,
+            (3, "KM") => 7,
+            (3, "MIL") => 8,
+            (3, "UM") => 9,
+            (3, "CM") => 10,
+            (3, "UIN") => 11,
+            (flag, _) => flag,
+        };
+        match flag {
+            1 => Some(0.0254),
+            2 => Some(1e-3),
+            4 => Some(0.3048),
+            5 => Some(1609.344),
+            6 => Some(1.0),
+            7 => Some(1e3),
+            8 => Some(2.54e-5),
+            9 => Some(1e-6),
+            10 => Some(1e-2),
+            11 => Some(2.54e-8),
+            _ => None,
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut sections: [Vec<&str>; 4] = Default::default();
+        for (n, raw) in text.lines().enumerate() {
+            let line = raw.trim_end_matches('\r');
+            if line.trim().is_empty() {
+                continue;
+            }
+            let code = line.as_bytes().get(72).copied();
+            if !line.is_char_boundary(72) && code.is_some() {
+                return Err(Error::Parse(format!("non-ASCII data at line {}", n + 1)));
+            }
+            let index = match code {
+                Some(b'S') => 0,
+                Some(b'G') => 1,
+                Some(b'D') => 2,
+                Some(b'P') => 3,
+                Some(b'T') => continue,
+                Some(b'C') => {
+                    return Err(Error::Unsupported("compressed IGES files".to_string()));
+                }
+                _ => {
+                    return Err(Error::Parse(format!(
+                        "missing section letter in column 73 at line {}",
+                        n + 1
+                    )));
+                }
+            };
+            sections[index].push(&line[..72]);
+        }
+        let [start, global, directory, data] = sections;
+
+        let global: String = global.concat();
+        let mut rest = global.trim_start();
+        let mut read_delimiter = |default: char, pd: char| -> char {
+            if let Some(tail) = rest.strip_prefix("1H") {
+                let c = tail.chars().next().unwrap_or(default);
+                rest = tail.get(c.len_utf8()..).unwrap_or("");
+                rest = rest.strip_prefix(pd).unwrap_or(rest);
+                c
+            } else {
+                rest = rest.strip_prefix(pd).unwrap_or(rest);
+                default
+            }
+        };
+        let pd = read_delimiter(',', ',');
+        let rd = read_delimiter(';', pd);
+        let mut global_params = vec![
+            Parameter::String(pd.to_string()),
+            Parameter::String(rd.to_string()),
+        ];
+        if !rest.trim_start().starts_with(rd) {
+            global_params.extend(tokenize(rest, pd, rd)?);
+        }
+
+        if directory.len() % 2 != 0 {
+            return Err(Error::Parse(
+                "odd number of directory entry lines".to_string(),
+            ));
+        }
+        let mut entities = BTreeMap::new();
+        for (i, pair) in directory.chunks(2).enumerate() {
+            let (a, b) = (pair[0], pair[1]);
+            let entity_type = int_field(a, 0)?;
+            let pointer = int_field(a, 1)?;
+            let count = int_field(b, 3)?;
+            if pointer < 1 || count < 1 || (pointer + count - 1) as usize > data.len() {
+                return Err(Error::Parse(format!(
+                    "directory entry {} has invalid parameter data pointer",
+                    2 * i + 1
+                )));
+            }
+            let text: String = data[pointer as usize - 1..(pointer + count - 1) as usize]
+                .iter()
+                .map(|line| {
+                    line.get(..64).ok_or_else(|| {
+                        Error::Parse(format!("non-ASCII parameter data of entity {}", 2 * i + 1))
+                    })
+                })
+                .collect::<Result<_>>()?;
+            let mut parameters = tokenize(&text, pd, rd)?;
+            match parameters.first() {
+                Some(Parameter::Integer(t)) if *t == entity_type => {
+                    parameters.remove(0);
+                }
+                _ => {
+                    return Err(Error::Parse(format!(
+                        "parameter data of entity {} does not start with type {}",
+                        2 * i + 1,
+                        entity_type
+                    )));
+                }
+            }
+            entities.insert(
+                2 * i + 1,
+                Entity {
+                    entity_type,
+                    structure: int_field(a, 2)?,
+                    line_font: int_field(a, 3)?,
+                    level: int_field(a, 4)?,
+                    view: int_field(a, 5)?,
+                    transform: int_field(a, 6)?,
+                    label_display: int_field(a, 7)?,
+                    status: format!("{:0>8}", field(a, 8)),
+                    line_weight: int_field(b, 1)?,
+                    color: int_field(b, 2)?,
+                    form: int_field(b, 4)?,
+                    label: field(b, 7).to_string(),
+                    subscript: int_field(b, 8)?,
+                    parameters,
+                },
+            );
+        }
+        Ok(IgesFile {
+            start: start.iter().map(|s| s.trim_end().to_string()).collect(),
+            global: global_params,
+            entities,
+        })
+    }
+}
+
+impl std::fmt::Display for Parameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Parameter::Integer(v) => write!(f, "{}", v),
+            Parameter::Real(v) => write!(f, "{}", format_real(*v)),
+            Parameter::String(s) => write!(f, "{}H{}", s.chars().count(), s),
+            Parameter::Default => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Display for IgesFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (pd, rd) = (self.parameter_delimiter(), self.record_delimiter());
+        let mut start = Vec::new();
+        for line in self.start.iter() {
+            start.extend(pack(std::slice::from_ref(line), 72));
+        }
+        if start.is_empty() {
+            start.push(String::new());
+        }
+        for (i, line) in start.iter().enumerate() {
+            writeln!(f, "{:<72}S{:07}", line, i + 1)?;
+        }
+
+        let global = pack(&tokens(None, &self.global, pd, rd), 72);
+        for (i, line) in global.iter().enumerate() {
+            writeln!(f, "{:<72}G{:07}", line, i + 1)?;
+        }
+
+        let mut data = Vec::new();
+        let mut pointer = 1;
+        for (&de, entity) in self.entities.iter() {
+            let lines = pack(
+                &tokens(Some(entity.entity_type), &entity.parameters, pd, rd),
+                64,
+            );
+            writeln!(
+                f,
+                "{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}D{:07}",
+                entity.entity_type,
+                pointer,
+                entity.structure,
+                entity.line_font,
+                entity.level,
+                entity.view,
+                entity.transform,
+                entity.label_display,
+                entity.status,
+                de
+            )?;
+            writeln!(
+                f,
+                "{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}D{:07}",
+                entity.entity_type,
+                entity.line_weight,
+                entity.color,
+                lines.len(),
+                entity.form,
+                "",
+                "",
+                entity.label.chars().take(8).collect::<String>(),
+                entity.subscript,
+                de + 1
+            )?;
+            pointer += lines.len();
+            data.push((de, lines));
+        }
+        let mut count = 0;
+        for (de, lines) in data.iter() {
+            for line in lines.iter() {
+                count += 1;
+                writeln!(f, "{:<64} {:>7}P{:07}", line, de, count)?;
+            }
+        }
+        writeln!(
+            f,
+            "S{:07}G{:07}D{:07}P{:07}{:40}T{:07}",
+            start.len(),
+            global.len(),
+            2 * self.entities.len(),
+            count,
+            "",
+            1
+        )
+    }
+}
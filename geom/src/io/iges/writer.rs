@@ -0,0 +1,345 @@
+use crate::CoordinateSystem3d;
+use crate::Direction3d;
+use crate::GeneralCoordinateSystem3d;
+use crate::Point3d;
+use crate::Trsf3d;
+use crate::io::iges::IgesGeometry;
+use crate::io::iges::file::{Entity, IgesFile, Parameter};
+use crate::io::{LengthUnit, utc_now};
+
+#[derive(Debug, Clone)]
+pub struct IgesWriter {
+    pub length_unit: LengthUnit,
+    pub file_name: String,
+    pub author: String,
+    pub organization: String,
+    // IGES date format YYYYMMDD.HHNNSS.
+    pub timestamp: String,
+    file: IgesFile,
+    max_coordinate: f64,
+}
+
+fn s(value: &str) -> Parameter {
+    Parameter::String(value.to_string())
+}
+
+fn i(value: i64) -> Parameter {
+    Parameter::Integer(value)
+}
+
+fn reals(values: &[f64]) -> Vec<Parameter> {
+    values.iter().map(|&v| Parameter::Real(v)).collect()
+}
+
+fn now_timestamp() -> String {
+    let [year, month, day, hour, minute, second] = utc_now();
+    format!(
+        "{:04}{:02}{:02}.{:02}{:02}{:02}",
+        year, month, day, hour, minute, second
+    )
+}
+
+impl Default for IgesWriter {
+    fn default() -> Self {
+        IgesWriter::new()
+    }
+}
+
+impl IgesWriter {
+    pub fn new() -> Self {
+        IgesWriter {
+            length_unit: LengthUnit::Millimetre,
+            file_name: String::new(),
+            author: String::new(),
+            organization: String::new(),
+            timestamp: now_timestamp(),
+            file: IgesFile::new(),
+            max_coordinate: 0.0,
+        }
+    }
+
+    fn push(
+        &mut self,
+        entity_type: i64,
+        form: i64,
+        parameters: Vec<Parameter>,
+        transform: usize,
+        dependent: bool,
+    ) -> usize {
+        let mut entity = Entity::new(entity_type, form, parameters);
+        entity.transform = transform as i64;
+        if dependent {
+            entity.status = "00010000".to_string();
+        }
+        self.file.add(entity)
+    }
+
+    fn track(&mut self, values: &[f64]) {
+        for v in values.iter().filter(|v| v.is_finite()) {
+            self.max_coordinate = self.max_coordinate.max(v.abs());
+        }
+    }
+
+    fn write_point(&mut self, point: &Point3d, dependent: bool) -> usize {
+        let (x, y, z) = point.get_coords();
+        self.track(&[x, y, z]);
+        self.push(116, 0, reals(&[x, y, z]), 0, dependent)
+    }
+
+    fn write_direction(&mut self, direction: &Direction3d, dependent: bool) -> usize {
+        let (x, y, z) = direction.get_coords();
+        self.push(123, 0, reals(&[x, y, z]), 0, dependent)
+    }
+
+    fn write_transformation(&mut self, trsf: &Trsf3d) -> usize {
+        // Form 1 marks a matrix with negative determinant.
+        let m = &trsf.matrix.m;
+        let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+        let form = if det < 0.0 { 1 } else { 0 };
+        self.push(124, form, reals(&trsf.get_values().concat()), 0, false)
+    }
+
+    // Transformation entity from definition space into `position`, or 0 when it is the identity.
+    fn write_frame(&mut self, position: &CoordinateSystem3d) -> usize {
+        let trsf = Trsf3d::from_coordinate_system(position);
+        if trsf.get_values() == Trsf3d::new().get_values() {
+            return 0;
+        }
+        self.track(&[trsf.loc.x, trsf.loc.y, trsf.loc.z]);
+        self.write_transformation(&trsf)
+    }
+
+    // Closed conics are written counterclockwise; a reversed range flips the frame.
+    fn oriented(
+        position: &CoordinateSystem3d,
+        first: f64,
+        last: f64,
+    ) -> (CoordinateSystem3d, f64, f64) {
+        if first <= last {
+            return (*position, first, last);
+        }
+        let mut flipped = *position;
+        flipped.vydir = Direction3d::from_xyz(-position.vydir.get_xyz());
+        flipped.axis.direction = Direction3d::from_xyz(-position.axis.direction.get_xyz());
+        (flipped, -first, -last)
+    }
+
+    fn write_conic(
+        &mut self,
+        form: i64,
+        position: &CoordinateSystem3d,
+        coefficients: [f64; 6],
+        start: (f64, f64),
+        end: (f64, f64),
+    ) -> usize {
+        let transform = self.write_frame(position);
+        let mut values = coefficients.to_vec();
+        values.extend([0.0, start.0, start.1, end.0, end.1]);
+        self.push(104, form, reals(&values), transform, false)
+    }
+
+    fn write_surface_frame(
+        &mut self,
+        pos: &GeneralCoordinateSystem3d,
+    ) -> (Parameter, Parameter, Parameter) {
+        let location = self.write_point(&pos.axis.location, true);
+        let axis = self.write_direction(&pos.axis.direction, true);
+        let reference = self.write_direction(&pos.vxdir, true);
+        (i(location as i64), i(axis as i64), i(reference as i64))
+    }
+
+    // Writes `geometry` in model space and returns its directory entry sequence number.
+    pub fn add(&mut self, geometry: &IgesGeometry) -> usize {
+        match geometry {
+            IgesGeometry::Point(p) => self.write_point(p, false),
+            IgesGeometry::Direction(d) => self.write_direction(d, false),
+            IgesGeometry::Transformation(t) => self.write_transformation(t),
+            IgesGeometry::Line { line, first, last } => {
+                let location = line.pos.location.get_xyz();
+                let direction = line.pos.direction.get_xyz();
+                let at = |t: f64| &location + &(direction * t);
+                let (form, a, b) = match (first.is_finite(), last.is_finite()) {
+                    (true, true) => (0, at(*first), at(*last)),
+                    (true, false) => (1, at(*first), at(first + 1.0)),
+                    (false, true) => (1, at(*last), at(last - 1.0)),
+                    (false, false) => (2, at(0.0), at(1.0)),
+                };
+                let values = [a.x, a.y, a.z, b.x, b.y, b.z];
+                self.track(&values);
+                self.push(110, form, reals(&values), 0, false)
+            }
+            IgesGeometry::CircularArc {
+                circle,
+                first,
+                last,
+            } => {
+                let (position, first, last) = Self::oriented(&circle.position, *first, *last);
+                let transform = self.write_frame(&position);
+                let r = circle.radius;
+                let values = [
+                    0.0,
+                    0.0,
+                    0.0,
+                    r * first.cos(),
+                    r * first.sin(),
+                    r * last.cos(),
+                    r * last.sin(),
+                ];
+                self.track(&[r]);
+                self.push(100, 0, reals(&values), transform, false)
+            }
+            IgesGeometry::EllipticArc {
+                ellipse,
+                first,
+                last,
+            } => {
+                let (position, first, last) = Self::oriented(&ellipse.position, *first, *last);
+                let (a, b) = (ellipse.major_radius, ellipse.minor_radius);
+                self.write_conic(
+                    1,
+                    &position,
+                    [1.0 / (a * a), 0.0, 1.0 / (b * b), 0.0, 0.0, -1.0],
+                    (a * first.cos(), b * first.sin()),
+                    (a * last.cos(), b * last.sin()),
+                )
+            }
+            IgesGeometry::HyperbolicArc {
+                hyperbola,
+                first,
+                last,
+            } => {
+                let (a, b) = (hyperbola.major_radius, hyperbola.minor_radius);
+                self.write_conic(
+                    2,
+                    &hyperbola.position,
+                    [1.0 / (a * a), 0.0, -1.0 / (b * b), 0.0, 0.0, -1.0],
+                    (a * first.cosh(), b * first.sinh()),
+                    (a * last.cosh(), b * last.sinh()),
+                )
+            }
+            IgesGeometry::ParabolicArc {
+                parabola,
+                first,
+                last,
+            } => {
+                let f = parabola.focal_length;
+                self.write_conic(
+                    3,
+                    &parabola.pos,
+                    [0.0, 0.0, 1.0, -4.0 * f, 0.0, 0.0],
+                    (first * first / (4.0 * f), *first),
+                    (last * last / (4.0 * f), *last),
+                )
+            }
+            IgesGeometry::BSplineCurve { curve, first, last } => {
+                let n = curve.poles.len();
+                let mut values = vec![
+                    i(n as i64 - 1),
+                    i(curve.degree as i64),
+                    i(0),
+                    i(0),
+                    i(if curve.is_rational() { 0 } else { 1 }),
+                    i(0),
+                ];
+                values.extend(reals(&curve.get_flat_knots()));
+                match &curve.weights {
+                    Some(weights) => values.extend(reals(weights)),
+                    None => values.extend(reals(&vec![1.0; n])),
+                }
+                for pole in curve.poles.iter() {
+                    let (x, y, z) = pole.get_coords();
+                    self.track(&[x, y, z]);
+                    values.extend(reals(&[x, y, z]));
+                }
+                values.extend(reals(&[*first, *last, 0.0, 0.0, 0.0]));
+                self.push(126, 0, values, 0, false)
+            }
+            IgesGeometry::Plane(plane) => {
+                let (location, axis, reference) = self.write_surface_frame(&plane.pos);
+                self.push(190, 1, vec![location, axis, reference], 0, false)
+            }
+            IgesGeometry::CylindricalSurface(c) => {
+                let (location, axis, reference) = self.write_surface_frame(&c.position);
+                let values = vec![location, axis, Parameter::Real(c.radius), reference];
+                self.push(192, 1, values, 0, false)
+            }
+            IgesGeometry::ConicalSurface(c) => {
+                let (location, axis, reference) = self.write_surface_frame(&c.position);
+                let values = vec![
+                    location,
+                    axis,
+                    Parameter::Real(c.radius),
+                    Parameter::Real(c.semi_angle.to_degrees()),
+                    reference,
+                ];
+                self.push(194, 1, values, 0, false)
+            }
+            IgesGeometry::SphericalSurface(c) => {
+                let (location, axis, reference) = self.write_surface_frame(&c.pos);
+                let values = vec![location, Parameter::Real(c.radius), axis, reference];
+                self.push(196, 1, values, 0, false)
+            }
+            IgesGeometry::ToroidalSurface(c) => {
+                let (location, axis, reference) = self.write_surface_frame(&c.pos);
+                let values = vec![
+                    location,
+                    axis,
+                    Parameter::Real(c.major_radius),
+                    Parameter::Real(c.minor_radius),
+                    reference,
+                ];
+                self.push(198, 1, values, 0, false)
+            }
+        }
+    }
+
+    pub fn to_iges(&self) -> IgesFile {
+        let (flag, unit) = match self.length_unit {
+            LengthUnit::Millimetre => (2, "MM"),
+            LengthUnit::Centimetre => (10, "CM"),
+            LengthUnit::Metre => (6, "M"),
+            LengthUnit::Inch => (1, "INCH"),
+        };
+        let mut file = self.file.clone();
+        file.start = vec!["geom IGES export".to_string()];
+        file.global = vec![
+            s(","),
+            s(";"),
+            s(&self.file_name),
+            s(&self.file_name),
+            s("geom"),
+            s("geom"),
+            i(32),
+            i(38),
+            i(6),
+            i(308),
+            i(15),
+            s(&self.file_name),
+            Parameter::Real(1.0),
+            i(flag),
+            s(unit),
+            i(1),
+            Parameter::Real(1.0),
+            s(&self.timestamp),
+            Parameter::Real(1e-7),
+            Parameter::Real(self.max_coordinate),
+            s(&self.author),
+            s(&self.organization),
+            i(11),
+            i(0),
+            s(&self.timestamp),
+        ];
+        file
+    }
+
+    pub fn write_str(&self) -> String {
+        self.to_iges().to_string()
+    }
+
+    pub fn write_file<P: AsRef<std::path::Path>>(&self, path: P) -> crate::io::Result<()> {
+        std::fs::write(path, self.write_str())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,619 @@
+use std::io::{BufRead, Read, Write};
+
+use crate::Point3d;
+use crate::io::{Error, Result};
+use crate::mesh::{Attribute, AttributeType, AttributeValues, Mesh};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+impl Format {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Format::Ascii => "ascii",
+            Format::BinaryLittleEndian => "binary_little_endian",
+            Format::BinaryBigEndian => "binary_big_endian",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyType {
+    Scalar(AttributeType),
+    List(AttributeType, AttributeType),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub data_type: PropertyType,
+    pub data: AttributeValues,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Element {
+    pub name: String,
+    pub count: usize,
+    pub properties: Vec<Property>,
+}
+
+impl Element {
+    pub fn get_property(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlyFile {
+    pub format: Format,
+    pub comments: Vec<String>,
+    pub obj_info: Vec<String>,
+    pub elements: Vec<Element>,
+}
+
+fn type_from_str(name: &str) -> Result<AttributeType> {
+    match name {
+        "char" | "int8" => Ok(AttributeType::I8),
+        "uchar" | "uint8" => Ok(AttributeType::U8),
+        "short" | "int16" => Ok(AttributeType::I16),
+        "ushort" | "uint16" => Ok(AttributeType::U16),
+        "int" | "int32" => Ok(AttributeType::I32),
+        "uint" | "uint32" => Ok(AttributeType::U32),
+        "float" | "float32" => Ok(AttributeType::F32),
+        "double" | "float64" => Ok(AttributeType::F64),
+        _ => Err(Error::Parse(format!(
+            "unknown PLY property type '{}'",
+            name
+        ))),
+    }
+}
+
+fn type_to_str(data_type: AttributeType) -> &'static str {
+    match data_type {
+        AttributeType::I8 => "char",
+        AttributeType::U8 => "uchar",
+        AttributeType::I16 => "short",
+        AttributeType::U16 => "ushort",
+        AttributeType::I32 => "int",
+        AttributeType::U32 => "uint",
+        AttributeType::F32 => "float",
+        AttributeType::F64 => "double",
+    }
+}
+
+fn read_binary_value<R: Read>(
+    reader: &mut R,
+    data_type: AttributeType,
+    format: Format,
+) -> Result<f64> {
+    let mut buf = [0u8; 8];
+    let size = data_type.size();
+    reader.read_exact(&mut buf[..size])?;
+    if format == Format::BinaryBigEndian {
+        buf[..size].reverse();
+    }
+    let value = match data_type {
+        AttributeType::I8 => buf[0] as i8 as f64,
+        AttributeType::U8 => buf[0] as f64,
+        AttributeType::I16 => i16::from_le_bytes([buf[0], buf[1]]) as f64,
+        AttributeType::U16 => u16::from_le_bytes([buf[0], buf[1]]) as f64,
+        AttributeType::I32 => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+        AttributeType::U32 => u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+        AttributeType::F32 => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+        AttributeType::F64 => f64::from_le_bytes(buf),
+    };
+    Ok(value)
+}
+
+fn write_binary_value<W: Write>(
+    writer: &mut W,
+    data_type: AttributeType,
+    value: f64,
+    format: Format,
+) -> Result<()> {
+    let mut bytes: Vec<u8> = match data_type {
+        AttributeType::I8 => (value as i8).to_le_bytes().to_vec(),
+        AttributeType::U8 => (value as u8).to_le_bytes().to_vec(),
+        AttributeType::I16 => (value as i16).to_le_bytes().to_vec(),
+        AttributeType::U16 => (value as u16).to_le_bytes().to_vec(),
+        AttributeType::I32 => (value as i32).to_le_bytes().to_vec(),
+        AttributeType::U32 => (value as u32).to_le_bytes().to_vec(),
+        AttributeType::F32 => (value as f32).to_le_bytes().to_vec(),
+        AttributeType::F64 => value.to_le_bytes().to_vec(),
+    };
+    if format == Format::BinaryBigEndian {
+        bytes.reverse();
+    }
+    writer.write_all(&bytes)?;
+    Ok(())
+}
+
+fn format_ascii_value(data_type: AttributeType, value: f64) -> String {
+    match data_type {
+        AttributeType::F32 => format!("{}", value as f32),
+        AttributeType::F64 => format!("{}", value),
+        _ => format!("{}", value as i64),
+    }
+}
+
+fn parse_ascii_value(token: &str, data_type: AttributeType) -> Result<f64> {
+    let value = if data_type.is_integer() {
+        token.parse::<i64>().map(|v| v as f64).ok()
+    } else if data_type == AttributeType::F32 {
+        token.parse::<f32>().map(|v| v as f64).ok()
+    } else {
+        token.parse::<f64>().ok()
+    };
+    value.ok_or_else(|| Error::Parse(format!("invalid PLY value '{}'", token)))
+}
+
+fn list_count(value: f64) -> Result<usize> {
+    if !(value >= 0.0 && value.fract() == 0.0 && value <= max_list_count(AttributeType::F64)) {
+        return Err(Error::Parse(format!("invalid PLY list count {}", value)));
+    }
+    Ok(value as usize)
+}
+
+// Largest list length a count of this type holds exactly.
+fn max_list_count(count_type: AttributeType) -> f64 {
+    match count_type {
+        AttributeType::I8 => i8::MAX as f64,
+        AttributeType::U8 => u8::MAX as f64,
+        AttributeType::I16 => i16::MAX as f64,
+        AttributeType::U16 => u16::MAX as f64,
+        AttributeType::I32 => i32::MAX as f64,
+        AttributeType::U32 => u32::MAX as f64,
+        AttributeType::F32 => (1u64 << f32::MANTISSA_DIGITS) as f64,
+        AttributeType::F64 => (1u64 << f64::MANTISSA_DIGITS) as f64,
+    }
+}
+
+fn read_header_line<R: BufRead>(reader: &mut R) -> Result<String> {
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line)? == 0 {
+        return Err(Error::Parse("unexpected end of PLY header".to_string()));
+    }
+    let line = String::from_utf8(line)
+        .map_err(|_| Error::Parse("PLY header is not valid text".to_string()))?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+impl PlyFile {
+    pub fn new(format: Format) -> Self {
+        PlyFile {
+            format,
+            comments: Vec::new(),
+            obj_info: Vec::new(),
+            elements: Vec::new(),
+        }
+    }
+
+    pub fn get_element(&self, name: &str) -> Option<&Element> {
+        self.elements.iter().find(|e| e.name == name)
+    }
+
+    pub fn read<R: BufRead>(mut reader: R) -> Result<Self> {
+        if read_header_line(&mut reader)?.trim() != "ply" {
+            return Err(Error::Parse("missing 'ply' magic".to_string()));
+        }
+        let mut format = None;
+        let mut comments = Vec::new();
+        let mut obj_info = Vec::new();
+        let mut elements: Vec<Element> = Vec::new();
+        loop {
+            let line = read_header_line(&mut reader)?;
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match tokens.first().copied() {
+                None => continue,
+                Some("end_header") => break,
+                Some("format") => {
+                    format = Some(match tokens.get(1).copied() {
+                        Some("ascii") => Format::Ascii,
+                        Some("binary_little_endian") => Format::BinaryLittleEndian,
+                        Some("binary_big_endian") => Format::BinaryBigEndian,
+                        _ => return Err(Error::Parse(format!("invalid format line '{}'", line))),
+                    });
+                }
+                Some("comment") => {
+                    comments.push(line.trim_start()["comment".len()..].trim().to_string())
+                }
+                Some("obj_info") => {
+                    obj_info.push(line.trim_start()["obj_info".len()..].trim().to_string())
+                }
+                Some("element") => {
+                    if tokens.len() != 3 {
+                        return Err(Error::Parse(format!("invalid element line '{}'", line)));
+                    }
+                    let count = tokens[2].parse::<usize>().map_err(|_| {
+                        Error::Parse(format!("invalid element count '{}'", tokens[2]))
+                    })?;
+                    elements.push(Element {
+                        name: tokens[1].to_string(),
+                        count,
+                        properties: Vec::new(),
+                    });
+                }
+                Some("property") => {
+                    let element = elements
+                        .last_mut()
+                        .ok_or_else(|| Error::Parse("property before element".to_string()))?;
+                    let property = match tokens.as_slice() {
+                        ["property", "list", count, item, name] => Property {
+                            name: name.to_string(),
+                            data_type: PropertyType::List(
+                                type_from_str(count)?,
+                                type_from_str(item)?,
+                            ),
+                            data: AttributeValues::List(Vec::new()),
+                        },
+                        ["property", data_type, name] => Property {
+                            name: name.to_string(),
+                            data_type: PropertyType::Scalar(type_from_str(data_type)?),
+                            data: AttributeValues::Scalar(Vec::new()),
+                        },
+                        _ => return Err(Error::Parse(format!("invalid property line '{}'", line))),
+                    };
+                    element.properties.push(property);
+                }
+                Some(keyword) => {
+                    return Err(Error::Parse(format!(
+                        "unknown header keyword '{}'",
+                        keyword
+                    )));
+                }
+            }
+        }
+        let format = format.ok_or_else(|| Error::Parse("missing format line".to_string()))?;
+
+        if format == Format::Ascii {
+            let mut text = String::new();
+            reader.read_to_string(&mut text)?;
+            let mut tokens = text.split_whitespace();
+            let mut next = || {
+                tokens
+                    .next()
+                    .ok_or_else(|| Error::Parse("unexpected end of PLY data".to_string()))
+            };
+            // The counts come from the header and are not trusted, so the
+            // values grow as they are read and elements without properties
+            // are not looped over.
+            for element in elements.iter_mut().filter(|e| !e.properties.is_empty()) {
+                for _ in 0..element.count {
+                    for property in element.properties.iter_mut() {
+                        match (property.data_type, &mut property.data) {
+                            (PropertyType::Scalar(t), AttributeValues::Scalar(values)) => {
+                                values.push(parse_ascii_value(next()?, t)?);
+                            }
+                            (
+                                PropertyType::List(count_type, item_type),
+                                AttributeValues::List(values),
+                            ) => {
+                                let n = list_count(parse_ascii_value(next()?, count_type)?)?;
+                                let mut items = Vec::new();
+                                for _ in 0..n {
+                                    items.push(parse_ascii_value(next()?, item_type)?);
+                                }
+                                values.push(items);
+                            }
+                            _ => unreachable!(),
+                        }
+                    }
+                }
+            }
+        } else {
+            for element in elements.iter_mut().filter(|e| !e.properties.is_empty()) {
+                for _ in 0..element.count {
+                    for property in element.properties.iter_mut() {
+                        match (property.data_type, &mut property.data) {
+                            (PropertyType::Scalar(t), AttributeValues::Scalar(values)) => {
+                                values.push(read_binary_value(&mut reader, t, format)?);
+                            }
+                            (
+                                PropertyType::List(count_type, item_type),
+                                AttributeValues::List(values),
+                            ) => {
+                                let n = list_count(read_binary_value(
+                                    &mut reader,
+                                    count_type,
+                                    format,
+                                )?)?;
+                                let mut items = Vec::new();
+                                for _ in 0..n {
+                                    items.push(read_binary_value(&mut reader, item_type, format)?);
+                                }
+                                values.push(items);
+                            }
+                            _ => unreachable!(),
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(PlyFile {
+            format,
+            comments,
+            obj_info,
+            elements,
+        })
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<()> {
+        for element in self.elements.iter() {
+            for property in element.properties.iter() {
+                let consistent = matches!(
+                    (property.data_type, &property.data),
+                    (PropertyType::Scalar(_), AttributeValues::Scalar(_))
+                        | (PropertyType::List(_, _), AttributeValues::List(_))
+                );
+                if !consistent || property.data.len() != element.count {
+                    return Err(Error::Parse(format!(
+                        "property '{}' of element '{}' does not match its declaration",
+                        property.name, element.name
+                    )));
+                }
+                if let (PropertyType::List(count_type, _), AttributeValues::List(lists)) =
+                    (property.data_type, &property.data)
+                {
+                    let max = max_list_count(count_type);
+                    if lists.iter().any(|l| l.len() as f64 > max) {
+                        return Err(Error::Parse(format!(
+                            "list of property '{}' of element '{}' is too long for a {} count",
+                            property.name,
+                            element.name,
+                            type_to_str(count_type)
+                        )));
+                    }
+                }
+            }
+        }
+
+        writeln!(writer, "ply")?;
+        writeln!(writer, "format {} 1.0", self.format.as_str())?;
+        for comment in self.comments.iter() {
+            writeln!(writer, "comment {}", comment)?;
+        }
+        for info in self.obj_info.iter() {
+            writeln!(writer, "obj_info {}", info)?;
+        }
+        for element in self.elements.iter() {
+            writeln!(writer, "element {} {}", element.name, element.count)?;
+            for property in element.properties.iter() {
+                match property.data_type {
+                    PropertyType::Scalar(t) => {
+                        writeln!(writer, "property {} {}", type_to_str(t), property.name)?
+                    }
+                    PropertyType::List(count_type, item_type) => writeln!(
+                        writer,
+                        "property list {} {} {}",
+                        type_to_str(count_type),
+                        type_to_str(item_type),
+                        property.name
+                    )?,
+                }
+            }
+        }
+        writeln!(writer, "end_header")?;
+
+        for element in self.elements.iter() {
+            for row in 0..element.count {
+                let mut tokens: Vec<String> = Vec::new();
+                for property in element.properties.iter() {
+                    match (property.data_type, &property.data) {
+                        (PropertyType::Scalar(t), AttributeValues::Scalar(values)) => {
+                            if self.format == Format::Ascii {
+                                tokens.push(format_ascii_value(t, values[row]));
+                            } else {
+                                write_binary_value(&mut writer, t, values[row], self.format)?;
+                            }
+                        }
+                        (
+                            PropertyType::List(count_type, item_type),
+                            AttributeValues::List(values),
+                        ) => {
+                            let items = &values[row];
+                            if self.format == Format::Ascii {
+                                tokens.push(items.len().to_string());
+                                tokens.extend(
+                                    items.iter().map(|&v| format_ascii_value(item_type, v)),
+                                );
+                            } else {
+                                write_binary_value(
+                                    &mut writer,
+                                    count_type,
+                                    items.len() as f64,
+                                    self.format,
+                                )?;
+                                for &v in items.iter() {
+                                    write_binary_value(&mut writer, item_type, v, self.format)?;
+                                }
+                            }
+                        }
+                        _ => unreachable!(),
+                    }
+                }
+                if self.format == Format::Ascii {
+                    writeln!(writer, "{}", tokens.join(" "))?;
+                }
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn to_points(&self) -> Result<Vec<Point3d>> {
+        let vertex = self
+            .get_element("vertex")
+            .ok_or_else(|| Error::Parse("PLY file has no vertex element".to_string()))?;
+        let coord = |name: &str| -> Result<&Vec<f64>> {
+            match vertex.get_property(name).map(|p| &p.data) {
+                Some(AttributeValues::Scalar(values)) => Ok(values),
+                _ => Err(Error::Parse(format!(
+                    "vertex element has no scalar '{}' property",
+                    name
+                ))),
+            }
+        };
+        let (xs, ys, zs) = (coord("x")?, coord("y")?, coord("z")?);
+        Ok((0..vertex.count)
+            .map(|i| Point3d::from_coords(xs[i], ys[i], zs[i]))
+            .collect())
+    }
+
+    pub fn to_mesh(&self) -> Result<Mesh> {
+        let mut mesh = Mesh::new();
+        mesh.points = self.to_points()?;
+        let vertex = self.get_element("vertex").unwrap();
+        for property in vertex.properties.iter() {
+            if ["x", "y", "z"].contains(&property.name.as_str()) {
+                continue;
+            }
+            mesh.point_attributes.push(property_to_attribute(property));
+        }
+
+        if let Some(face) = self.get_element("face") {
+            for property in face.properties.iter() {
+                let is_indices =
+                    property.name == "vertex_indices" || property.name == "vertex_index";
+                match (&property.data, is_indices) {
+                    (AttributeValues::List(lists), true) => {
+                        let points = mesh.points.len();
+                        mesh.faces = lists
+                            .iter()
+                            .map(|l| {
+                                if l.len() < 3 {
+                                    return Err(Error::Parse(format!(
+                                        "face has {} vertices but needs at least 3",
+                                        l.len()
+                                    )));
+                                }
+                                l.iter()
+                                    .map(|&i| {
+                                        if i < 0.0 || i.fract() != 0.0 || i >= points as f64 {
+                                            return Err(Error::Parse(format!(
+                                                "face references a missing vertex {}",
+                                                i
+                                            )));
+                                        }
+                                        Ok(i as usize)
+                                    })
+                                    .collect()
+                            })
+                            .collect::<Result<_>>()?;
+                    }
+                    _ => mesh.face_attributes.push(property_to_attribute(property)),
+                }
+            }
+            if mesh.faces.len() != face.count {
+                return Err(Error::Parse(
+                    "face element has no vertex_indices list".to_string(),
+                ));
+            }
+        }
+        Ok(mesh)
+    }
+
+    pub fn from_mesh(mesh: &Mesh, format: Format, position_type: AttributeType) -> Result<Self> {
+        if !mesh.is_valid() {
+            return Err(Error::Parse(
+                "mesh faces or attributes are inconsistent".to_string(),
+            ));
+        }
+        let mut ply = PlyFile::new(format);
+        let mut vertex = Element {
+            name: "vertex".to_string(),
+            count: mesh.num_points(),
+            properties: Vec::new(),
+        };
+        let coords: [fn(&Point3d) -> f64; 3] = [|p| p.get_x(), |p| p.get_y(), |p| p.get_z()];
+        for (name, coord) in ["x", "y", "z"].iter().zip(coords.iter()) {
+            vertex.properties.push(Property {
+                name: name.to_string(),
+                data_type: PropertyType::Scalar(position_type),
+                data: AttributeValues::Scalar(mesh.points.iter().map(coord).collect()),
+            });
+        }
+        vertex
+            .properties
+            .extend(mesh.point_attributes.iter().map(attribute_to_property));
+        ply.elements.push(vertex);
+
+        if !mesh.faces.is_empty() || !mesh.face_attributes.is_empty() {
+            let max_len = mesh.faces.iter().map(|f| f.len()).max().unwrap_or(0);
+            let count_type = if max_len <= u8::MAX as usize {
+                AttributeType::U8
+            } else {
+                AttributeType::U32
+            };
+            let item_type = if mesh.num_points() <= i32::MAX as usize {
+                AttributeType::I32
+            } else {
+                AttributeType::U32
+            };
+            let mut face = Element {
+                name: "face".to_string(),
+                count: mesh.num_faces(),
+                properties: vec![Property {
+                    name: "vertex_indices".to_string(),
+                    data_type: PropertyType::List(count_type, item_type),
+                    data: AttributeValues::List(
+                        mesh.faces
+                            .iter()
+                            .map(|f| f.iter().map(|&i| i as f64).collect())
+                            .collect(),
+                    ),
+                }],
+            };
+            face.properties
+                .extend(mesh.face_attributes.iter().map(attribute_to_property));
+            ply.elements.push(face);
+        }
+        Ok(ply)
+    }
+}
+
+fn property_to_attribute(property: &Property) -> Attribute {
+    let data_type = match property.data_type {
+        PropertyType::Scalar(t) => t,
+        PropertyType::List(_, t) => t,
+    };
+    Attribute {
+        name: property.name.clone(),
+        data_type,
+        values: property.data.clone(),
+    }
+}
+
+fn attribute_to_property(attribute: &Attribute) -> Property {
+    let data_type = match &attribute.values {
+        AttributeValues::Scalar(_) => PropertyType::Scalar(attribute.data_type),
+        AttributeValues::List(lists) => {
+            let max_len = lists.iter().map(|l| l.len()).max().unwrap_or(0);
+            let count_type = if max_len <= u8::MAX as usize {
+                AttributeType::U8
+            } else {
+                AttributeType::U32
+            };
+            PropertyType::List(count_type, attribute.data_type)
+        }
+    };
+    Property {
+        name: attribute.name.clone(),
+        data_type,
+        data: attribute.values.clone(),
+    }
+}
+
+pub fn read_mesh<P: AsRef<std::path::Path>>(path: P) -> Result<Mesh> {
+    let file = std::fs::File::open(path)?;
+    PlyFile::read(std::io::BufReader::new(file))?.to_mesh()
+}
+
+pub fn write_mesh<P: AsRef<std::path::Path>>(path: P, mesh: &Mesh, format: Format) -> Result<()> {
+    let file = std::fs::File::create(path)?;
+    PlyFile::from_mesh(mesh, format, AttributeType::F64)?.write(std::io::BufWriter::new(file))
+}
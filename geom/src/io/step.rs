@@ -0,0 +1,676 @@
+pub mod part21;
+pub mod writer;
+
+use std::collections::BTreeMap;
+
+use crate::Axis3d;
+use crate::BSplineCurve3d;
+use crate::BSplineSurface;
+use crate::Circle3d;
+use crate::Cone;
+use crate::CoordinateSystem3d;
+use crate::Cylinder;
+use crate::Direction3d;
+use crate::Ellipse3d;
+use crate::GeneralCoordinateSystem3d;
+use crate::Hyperbola3d;
+use crate::Line3d;
+use crate::Parabola3d;
+use crate::Plane;
+use crate::Point3d;
+use crate::Sphere;
+use crate::Torus;
+use crate::Vector3d;
+pub use crate::io::LengthUnit;
+use crate::io::Result;
+use part21::{Instance, Parameter, Part21File, Record};
+pub use writer::StepWriter;
+
+#[derive(Debug, Clone)]
+pub enum StepGeometry {
+    Point(Point3d),
+    Direction(Direction3d),
+    Vector(Vector3d),
+    Axis(Axis3d),
+    Placement(CoordinateSystem3d),
+    Line(Line3d),
+    Circle(Circle3d),
+    Ellipse(Ellipse3d),
+    Hyperbola(Hyperbola3d),
+    Parabola(Parabola3d),
+    Plane(Plane),
+    CylindricalSurface(Cylinder),
+    ConicalSurface(Cone),
+    SphericalSurface(Sphere),
+    ToroidalSurface(Torus),
+    BSplineCurve(BSplineCurve3d),
+    BSplineSurface(BSplineSurface),
+}
+
+impl StepGeometry {
+    pub fn entity_name(&self) -> &str {
+        match self {
+            StepGeometry::Point(_) => "CARTESIAN_POINT",
+            StepGeometry::Direction(_) => "DIRECTION",
+            StepGeometry::Vector(_) => "VECTOR",
+            StepGeometry::Axis(_) => "AXIS1_PLACEMENT",
+            StepGeometry::Placement(_) => "AXIS2_PLACEMENT_3D",
+            StepGeometry::Line(_) => "LINE",
+            StepGeometry::Circle(_) => "CIRCLE",
+            StepGeometry::Ellipse(_) => "ELLIPSE",
+            StepGeometry::Hyperbola(_) => "HYPERBOLA",
+            StepGeometry::Parabola(_) => "PARABOLA",
+            StepGeometry::Plane(_) => "PLANE",
+            StepGeometry::CylindricalSurface(_) => "CYLINDRICAL_SURFACE",
+            StepGeometry::ConicalSurface(_) => "CONICAL_SURFACE",
+            StepGeometry::SphericalSurface(_) => "SPHERICAL_SURFACE",
+            StepGeometry::ToroidalSurface(_) => "TOROIDAL_SURFACE",
+            StepGeometry::BSplineCurve(_) => "B_SPLINE_CURVE_WITH_KNOTS",
+            StepGeometry::BSplineSurface(_) => "B_SPLINE_SURFACE_WITH_KNOTS",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StepModel {
+    pub file: Part21File,
+    // Size of the file length unit in metres, when the file declares one.
+    pub length_unit: Option<f64>,
+    // Size of the file plane angle unit in radians. Angles in `geometries`
+    // are already converted to radians.
+    pub plane_angle_unit: f64,
+    pub geometries: BTreeMap<u64, StepGeometry>,
+    pub skipped: Vec<(u64, String)>,
+}
+
+impl StepModel {
+    pub fn get(&self, id: u64) -> Option<&StepGeometry> {
+        self.geometries.get(&id)
+    }
+}
+
+type Conversion<T> = std::result::Result<T, String>;
+
+fn real(p: &Parameter) -> Conversion<f64> {
+    match p {
+        Parameter::Real(v) => Ok(*v),
+        Parameter::Integer(v) => Ok(*v as f64),
+        Parameter::Typed(_, items) if items.len() == 1 => real(&items[0]),
+        _ => Err(format!("expected a real value but found {}", p)),
+    }
+}
+
+// Radius, semi-axis or focal length, which must be finite and positive.
+fn length(p: &Parameter, what: &str) -> Conversion<f64> {
+    let value = real(p)?;
+    if !(value.is_finite() && value > 0.0) {
+        return Err(format!("{} must be positive but is {}", what, value));
+    }
+    Ok(value)
+}
+
+fn integer(p: &Parameter) -> Conversion<i64> {
+    match p {
+        Parameter::Integer(v) => Ok(*v),
+        _ => Err(format!("expected an integer but found {}", p)),
+    }
+}
+
+fn reference(p: &Parameter) -> Conversion<u64> {
+    match p {
+        Parameter::Reference(id) => Ok(*id),
+        _ => Err(format!("expected an entity reference but found {}", p)),
+    }
+}
+
+fn list(p: &Parameter) -> Conversion<&Vec<Parameter>> {
+    match p {
+        Parameter::List(items) => Ok(items),
+        _ => Err(format!("expected a list but found {}", p)),
+    }
+}
+
+fn reals(p: &Parameter) -> Conversion<Vec<f64>> {
+    list(p)?.iter().map(real).collect()
+}
+
+// Degree or multiplicity, which must be a non-negative integer.
+fn count(p: &Parameter) -> Conversion<usize> {
+    let value = integer(p)?;
+    usize::try_from(value).map_err(|_| format!("expected a count but found {}", value))
+}
+
+fn counts(p: &Parameter) -> Conversion<Vec<usize>> {
+    list(p)?.iter().map(count).collect()
+}
+
+fn arg(params: &[Parameter], index: usize) -> Conversion<&Parameter> {
+    params
+        .get(index)
+        .ok_or_else(|| format!("missing parameter {}", index))
+}
+
+fn xyz_from(values: &[f64]) -> Conversion<(f64, f64, f64)> {
+    match values {
+        [x, y, z] => Ok((*x, *y, *z)),
+        [x, y] => Ok((*x, *y, 0.0)),
+        _ => Err(format!(
+            "expected 2 or 3 coordinates but found {}",
+            values.len()
+        )),
+    }
+}
+
+fn is_parallel(a: &Direction3d, b: &Direction3d) -> bool {
+    a.get_xyz().cross_new(b.get_xyz()).length() <= 1e-12
+}
+
+fn si_prefix(prefix: &Parameter) -> f64 {
+    match prefix {
+        Parameter::Enumeration(p) => match p.as_str() {
+            "EXA" => 1e18,
+            "PETA" => 1e15,
+            "TERA" => 1e12,
+            "GIGA" => 1e9,
+            "MEGA" => 1e6,
+            "KILO" => 1e3,
+            "HECTO" => 1e2,
+            "DECA" => 1e1,
+            "DECI" => 1e-1,
+            "CENTI" => 1e-2,
+            "MILLI" => 1e-3,
+            "MICRO" => 1e-6,
+            "NANO" => 1e-9,
+            "PICO" => 1e-12,
+            _ => 1.0,
+        },
+        _ => 1.0,
+    }
+}
+
+struct Importer<'a> {
+    file: &'a Part21File,
+    angle_factor: f64,
+    cache: BTreeMap<u64, Conversion<StepGeometry>>,
+}
+
+impl<'a> Importer<'a> {
+    fn instance(&self, id: u64) -> Conversion<&'a Instance> {
+        self.file
+            .get(id)
+            .ok_or_else(|| format!("reference to missing instance #{}", id))
+    }
+
+    fn unit_factor(&self, id: u64, depth: usize) -> Conversion<f64> {
+        if depth > 8 {
+            return Err("unit definitions are nested too deeply".to_string());
+        }
+        let instance = self.instance(id)?;
+        if let Some(si) = instance.get_record("SI_UNIT") {
+            let n = si.parameters.len();
+            return Ok(if n >= 2 {
+                si_prefix(&si.parameters[n - 2])
+            } else {
+                1.0
+            });
+        }
+        if let Some(conversion) = instance.get_record("CONVERSION_BASED_UNIT") {
+            let measure = self.instance(reference(arg(&conversion.parameters, 1)?)?)?;
+            let params = &measure.records[0].parameters;
+            let value = real(arg(params, 0)?)?;
+            return Ok(value * self.unit_factor(reference(arg(params, 1)?)?, depth + 1)?);
+        }
+        Err(format!("#{} is not a supported unit", id))
+    }
+
+    fn units(&self) -> (Option<f64>, f64) {
+        let mut length = None;
+        let mut angle = 1.0;
+        let context = self
+            .file
+            .instances
+            .values()
+            .find_map(|i| i.get_record("GLOBAL_UNIT_ASSIGNED_CONTEXT"));
+        if let Some(context) = context {
+            let units = context
+                .parameters
+                .first()
+                .and_then(|p| list(p).ok())
+                .cloned()
+                .unwrap_or_default();
+            for unit in units.iter() {
+                let Ok(id) = reference(unit) else { continue };
+                let Ok(instance) = self.instance(id) else {
+                    continue;
+                };
+                if instance.has_record("LENGTH_UNIT") {
+                    length = self.unit_factor(id, 0).ok();
+                } else if instance.has_record("PLANE_ANGLE_UNIT") {
+                    angle = self.unit_factor(id, 0).unwrap_or(1.0);
+                }
+            }
+        }
+        (length, angle)
+    }
+
+    fn convert(&mut self, id: u64) -> Conversion<StepGeometry> {
+        if let Some(result) = self.cache.get(&id) {
+            return result.clone();
+        }
+        // Guards against reference cycles in malformed files.
+        self.cache
+            .insert(id, Err(format!("#{} references itself", id)));
+        let result = self.convert_instance(id);
+        self.cache.insert(id, result.clone());
+        result
+    }
+
+    fn point(&mut self, p: &Parameter) -> Conversion<Point3d> {
+        match self.convert(reference(p)?)? {
+            StepGeometry::Point(point) => Ok(point),
+            other => Err(format!(
+                "expected CARTESIAN_POINT but found {}",
+                other.entity_name()
+            )),
+        }
+    }
+
+    fn direction(&mut self, p: &Parameter) -> Conversion<Direction3d> {
+        match self.convert(reference(p)?)? {
+            StepGeometry::Direction(direction) => Ok(direction),
+            other => Err(format!(
+                "expected DIRECTION but found {}",
+                other.entity_name()
+            )),
+        }
+    }
+
+    fn placement(&mut self, p: &Parameter) -> Conversion<CoordinateSystem3d> {
+        match self.convert(reference(p)?)? {
+            StepGeometry::Placement(placement) => Ok(placement),
+            other => Err(format!(
+                "expected AXIS2_PLACEMENT but found {}",
+                other.entity_name()
+            )),
+        }
+    }
+
+    fn build_placement(
+        &mut self,
+        location: &Parameter,
+        axis: Option<&Parameter>,
+        ref_direction: Option<&Parameter>,
+    ) -> Conversion<CoordinateSystem3d> {
+        let location = self.point(location)?;
+        let axis = match axis {
+            Some(Parameter::Unset) | None => Direction3d::from_coords(0.0, 0.0, 1.0),
+            Some(p) => self.direction(p)?,
+        };
+        let ref_direction = match ref_direction {
+            Some(Parameter::Unset) | None => {
+                let x = Direction3d::from_coords(1.0, 0.0, 0.0);
+                if is_parallel(&axis, &x) {
+                    Direction3d::from_coords(0.0, 1.0, 0.0)
+                } else {
+                    x
+                }
+            }
+            Some(p) => self.direction(p)?,
+        };
+        if is_parallel(&axis, &ref_direction) {
+            return Err("placement axis and reference direction are parallel".to_string());
+        }
+        Ok(CoordinateSystem3d::from_location_direction_xdirection(
+            location,
+            axis,
+            ref_direction,
+        ))
+    }
+
+    fn poles(&mut self, p: &Parameter) -> Conversion<Vec<Point3d>> {
+        list(p)?.iter().map(|r| self.point(r)).collect()
+    }
+
+    fn bspline_curve(&mut self, instance: &Instance) -> Conversion<BSplineCurve3d> {
+        // The curve data lives either in a single record that carries the entity
+        // name first, or spread over the records of a complex instance.
+        let (curve, knot_record, name) = match instance.get_record("B_SPLINE_CURVE") {
+            Some(record) => {
+                let knots = [
+                    "B_SPLINE_CURVE_WITH_KNOTS",
+                    "BEZIER_CURVE",
+                    "UNIFORM_CURVE",
+                    "QUASI_UNIFORM_CURVE",
+                ]
+                .iter()
+                .find_map(|n| instance.get_record(n))
+                .ok_or("complex B_SPLINE_CURVE without knot information")?;
+                (record.parameters.clone(), knots.clone(), knots.name.clone())
+            }
+            None => {
+                let record = &instance.records[0];
+                let params = &record.parameters;
+                if params.len() < 6 {
+                    return Err(format!("{} has too few parameters", record.name));
+                }
+                let knots = Record::new(&record.name, params[6..].to_vec());
+                (params[1..6].to_vec(), knots, record.name.clone())
+            }
+        };
+        let degree = count(arg(&curve, 0)?)?;
+        let poles = self.poles(arg(&curve, 1)?)?;
+        let (knots, multiplicities) =
+            knot_vector(&name, &knot_record.parameters, degree, poles.len(), 0)?;
+        let result = match instance.get_record("RATIONAL_B_SPLINE_CURVE") {
+            Some(rational) => {
+                let weights = reals(arg(&rational.parameters, 0)?)?;
+                BSplineCurve3d::from_poles_weights_knots(
+                    degree,
+                    poles,
+                    weights,
+                    knots,
+                    multiplicities,
+                )
+            }
+            None => BSplineCurve3d::from_poles_knots(degree, poles, knots, multiplicities),
+        };
+        result.map_err(|e| e.to_string())
+    }
+
+    fn bspline_surface(&mut self, instance: &Instance) -> Conversion<BSplineSurface> {
+        let (surface, knot_record, name) = match instance.get_record("B_SPLINE_SURFACE") {
+            Some(record) => {
+                let knots = [
+                    "B_SPLINE_SURFACE_WITH_KNOTS",
+                    "BEZIER_SURFACE",
+                    "UNIFORM_SURFACE",
+                    "QUASI_UNIFORM_SURFACE",
+                ]
+                .iter()
+                .find_map(|n| instance.get_record(n))
+                .ok_or("complex B_SPLINE_SURFACE without knot information")?;
+                (record.parameters.clone(), knots.clone(), knots.name.clone())
+            }
+            None => {
+                let record = &instance.records[0];
+                let params = &record.parameters;
+                if params.len() < 8 {
+                    return Err(format!("{} has too few parameters", record.name));
+                }
+                let knots = Record::new(&record.name, params[8..].to_vec());
+                (params[1..8].to_vec(), knots, record.name.clone())
+            }
+        };
+        let u_degree = count(arg(&surface, 0)?)?;
+        let v_degree = count(arg(&surface, 1)?)?;
+        let poles: Vec<Vec<Point3d>> = list(arg(&surface, 2)?)?
+            .iter()
+            .map(|row| self.poles(row))
+            .collect::<Conversion<_>>()?;
+        let nu = poles.len();
+        let nv = poles.first().map_or(0, |r| r.len());
+        let u = knot_vector(&name, &knot_record.parameters, u_degree, nu, 0)?;
+        let v = knot_vector(&name, &knot_record.parameters, v_degree, nv, 1)?;
+        let mut result = BSplineSurface::from_poles_knots((u_degree, v_degree), poles, u, v)
+            .map_err(|e| e.to_string())?;
+        if let Some(rational) = instance.get_record("RATIONAL_B_SPLINE_SURFACE") {
+            let weights = list(arg(&rational.parameters, 0)?)?
+                .iter()
+                .map(reals)
+                .collect::<Conversion<Vec<_>>>()?;
+            result.set_weights(weights).map_err(|e| e.to_string())?;
+        }
+        Ok(result)
+    }
+
+    fn convert_instance(&mut self, id: u64) -> Conversion<StepGeometry> {
+        let instance = self.instance(id)?;
+        if instance.has_record("B_SPLINE_CURVE") {
+            return Ok(StepGeometry::BSplineCurve(self.bspline_curve(instance)?));
+        }
+        if instance.has_record("B_SPLINE_SURFACE") {
+            return Ok(StepGeometry::BSplineSurface(
+                self.bspline_surface(instance)?,
+            ));
+        }
+        if instance.is_complex() {
+            return Err("unsupported complex instance".to_string());
+        }
+        let record = &instance.records[0];
+        let p = &record.parameters;
+        let angle = self.angle_factor;
+        let geometry = match record.name.as_str() {
+            "CARTESIAN_POINT" => {
+                let (x, y, z) = xyz_from(&reals(arg(p, 1)?)?)?;
+                StepGeometry::Point(Point3d::from_coords(x, y, z))
+            }
+            "DIRECTION" => {
+                let (x, y, z) = xyz_from(&reals(arg(p, 1)?)?)?;
+                if (x * x + y * y + z * z).sqrt() <= f64::MIN_POSITIVE {
+                    return Err("DIRECTION has zero length".to_string());
+                }
+                StepGeometry::Direction(Direction3d::from_coords(x, y, z))
+            }
+            "VECTOR" => {
+                let direction = self.direction(arg(p, 1)?)?;
+                let magnitude = real(arg(p, 2)?)?;
+                StepGeometry::Vector(Vector3d::from_xyz(direction.get_xyz() * magnitude))
+            }
+            "AXIS1_PLACEMENT" => {
+                let location = self.point(arg(p, 1)?)?;
+                let axis = match arg(p, 2)? {
+                    Parameter::Unset => Direction3d::from_coords(0.0, 0.0, 1.0),
+                    r => self.direction(r)?,
+                };
+                StepGeometry::Axis(Axis3d::from_location_direction(location, axis))
+            }
+            "AXIS2_PLACEMENT_3D" => {
+                StepGeometry::Placement(self.build_placement(arg(p, 1)?, p.get(2), p.get(3))?)
+            }
+            "AXIS2_PLACEMENT_2D" => {
+                StepGeometry::Placement(self.build_placement(arg(p, 1)?, None, p.get(2))?)
+            }
+            "LINE" => {
+                let location = self.point(arg(p, 1)?)?;
+                let vector = match self.convert(reference(arg(p, 2)?)?)? {
+                    StepGeometry::Vector(v) => v,
+                    other => {
+                        return Err(format!("expected VECTOR but found {}", other.entity_name()));
+                    }
+                };
+                if vector.length() <= f64::MIN_POSITIVE {
+                    return Err("LINE has a zero length direction".to_string());
+                }
+                StepGeometry::Line(Line3d {
+                    pos: Axis3d::from_location_direction(
+                        location,
+                        Direction3d::from_xyz(vector.get_xyz()),
+                    ),
+                })
+            }
+            "CIRCLE" => StepGeometry::Circle(Circle3d {
+                position: self.placement(arg(p, 1)?)?,
+                radius: length(arg(p, 2)?, "CIRCLE radius")?,
+            }),
+            "ELLIPSE" => StepGeometry::Ellipse(Ellipse3d {
+                position: self.placement(arg(p, 1)?)?,
+                major_radius: length(arg(p, 2)?, "ELLIPSE semi-axis")?,
+                minor_radius: length(arg(p, 3)?, "ELLIPSE semi-axis")?,
+            }),
+            "HYPERBOLA" => StepGeometry::Hyperbola(Hyperbola3d {
+                position: self.placement(arg(p, 1)?)?,
+                major_radius: length(arg(p, 2)?, "HYPERBOLA semi-axis")?,
+                minor_radius: length(arg(p, 3)?, "HYPERBOLA semi-axis")?,
+            }),
+            "PARABOLA" => StepGeometry::Parabola(Parabola3d {
+                pos: self.placement(arg(p, 1)?)?,
+                focal_length: length(arg(p, 2)?, "PARABOLA focal length")?,
+            }),
+            "PLANE" => StepGeometry::Plane(Plane {
+                pos: GeneralCoordinateSystem3d::from(self.placement(arg(p, 1)?)?),
+            }),
+            "CYLINDRICAL_SURFACE" => StepGeometry::CylindricalSurface(Cylinder {
+                position: self.placement(arg(p, 1)?)?.into(),
+                radius: length(arg(p, 2)?, "CYLINDRICAL_SURFACE radius")?,
+            }),
+            "CONICAL_SURFACE" => {
+                // The radius at the placement may be zero, at the apex.
+                let radius = real(arg(p, 2)?)?;
+                let semi_angle = real(arg(p, 3)?)? * angle;
+                if !(radius.is_finite() && radius >= 0.0) {
+                    return Err(format!(
+                        "CONICAL_SURFACE radius must not be negative but is {}",
+                        radius
+                    ));
+                }
+                if !(semi_angle > 0.0 && semi_angle < std::f64::consts::FRAC_PI_2) {
+                    return Err(format!(
+                        "CONICAL_SURFACE semi-angle must be between 0 and a right angle but is {}",
+                        semi_angle
+                    ));
+                }
+                StepGeometry::ConicalSurface(Cone {
+                    position: self.placement(arg(p, 1)?)?.into(),
+                    radius,
+                    semi_angle,
+                })
+            }
+            "SPHERICAL_SURFACE" => StepGeometry::SphericalSurface(Sphere {
+                pos: self.placement(arg(p, 1)?)?.into(),
+                radius: length(arg(p, 2)?, "SPHERICAL_SURFACE radius")?,
+            }),
+            "TOROIDAL_SURFACE" => StepGeometry::ToroidalSurface(Torus {
+                pos: self.placement(arg(p, 1)?)?.into(),
+                major_radius: length(arg(p, 2)?, "TOROIDAL_SURFACE major radius")?,
+                minor_radius: length(arg(p, 3)?, "TOROIDAL_SURFACE minor radius")?,
+            }),
+            "B_SPLINE_CURVE_WITH_KNOTS"
+            | "BEZIER_CURVE"
+            | "UNIFORM_CURVE"
+            | "QUASI_UNIFORM_CURVE" => StepGeometry::BSplineCurve(self.bspline_curve(instance)?),
+            "B_SPLINE_SURFACE_WITH_KNOTS"
+            | "BEZIER_SURFACE"
+            | "UNIFORM_SURFACE"
+            | "QUASI_UNIFORM_SURFACE" => {
+                StepGeometry::BSplineSurface(self.bspline_surface(instance)?)
+            }
+            name => return Err(format!("unsupported entity {}", name)),
+        };
+        Ok(geometry)
+    }
+}
+
+// Knots and multiplicities for one parametric direction. `direction` selects
+// the u (0) or v (1) data of a surface record.
+fn knot_vector(
+    name: &str,
+    params: &[Parameter],
+    degree: usize,
+    num_poles: usize,
+    direction: usize,
+) -> Conversion<(Vec<f64>, Vec<usize>)> {
+    if name.ends_with("WITH_KNOTS") {
+        let surface = name.contains("SURFACE");
+        let (mults, knots) = if surface {
+            (arg(params, direction)?, arg(params, 2 + direction)?)
+        } else {
+            (arg(params, 0)?, arg(params, 1)?)
+        };
+        return Ok((reals(knots)?, counts(mults)?));
+    }
+    let order = degree
+        .checked_add(1)
+        .ok_or_else(|| format!("degree {} is too large", degree))?;
+    if name.starts_with("BEZIER") {
+        return Ok((vec![0.0, 1.0], vec![order, order]));
+    }
+    if name.starts_with("QUASI_UNIFORM") {
+        let spans = num_poles
+            .checked_sub(degree)
+            .filter(|&spans| spans > 0)
+            .ok_or_else(|| format!("{} poles are too few for degree {}", num_poles, degree))?;
+        let knots = (0..=spans).map(|k| k as f64).collect();
+        let mut mults = vec![1; spans + 1];
+        mults[0] = order;
+        mults[spans] = order;
+        return Ok((knots, mults));
+    }
+    if name.starts_with("UNIFORM") {
+        let n = num_poles
+            .checked_add(order)
+            .ok_or_else(|| format!("{} poles are too many", num_poles))?;
+        let knots = (0..n).map(|k| k as f64 - degree as f64).collect();
+        return Ok((knots, vec![1; n]));
+    }
+    Err(format!("unsupported knot specification {}", name))
+}
+
+pub fn read_str(text: &str) -> Result<StepModel> {
+    let file = Part21File::parse(text)?;
+    let mut importer = Importer {
+        file: &file,
+        angle_factor: 1.0,
+        cache: BTreeMap::new(),
+    };
+    let (length_unit, plane_angle_unit) = importer.units();
+    importer.angle_factor = plane_angle_unit;
+
+    let mut geometries = BTreeMap::new();
+    let mut skipped = Vec::new();
+    let supported = [
+        "CARTESIAN_POINT",
+        "DIRECTION",
+        "VECTOR",
+        "AXIS1_PLACEMENT",
+        "AXIS2_PLACEMENT_2D",
+        "AXIS2_PLACEMENT_3D",
+        "LINE",
+        "CIRCLE",
+        "ELLIPSE",
+        "HYPERBOLA",
+        "PARABOLA",
+        "PLANE",
+        "CYLINDRICAL_SURFACE",
+        "CONICAL_SURFACE",
+        "SPHERICAL_SURFACE",
+        "TOROIDAL_SURFACE",
+        "B_SPLINE_CURVE",
+        "B_SPLINE_CURVE_WITH_KNOTS",
+        "BEZIER_CURVE",
+        "UNIFORM_CURVE",
+        "QUASI_UNIFORM_CURVE",
+        "B_SPLINE_SURFACE",
+        "B_SPLINE_SURFACE_WITH_KNOTS",
+        "BEZIER_SURFACE",
+        "UNIFORM_SURFACE",
+        "QUASI_UNIFORM_SURFACE",
+    ];
+    for (&id, instance) in file.instances.iter() {
+        if !instance
+            .records
+            .iter()
+            .any(|r| supported.contains(&r.name.as_str()))
+        {
+            continue;
+        }
+        match importer.convert(id) {
+            Ok(geometry) => {
+                geometries.insert(id, geometry);
+            }
+            Err(reason) => skipped.push((id, reason)),
+        }
+    }
+    drop(importer);
+    Ok(StepModel {
+        file,
+        length_unit,
+        plane_angle_unit,
+        geometries,
+        skipped,
+    })
+}
+
+pub fn read_file<P: AsRef<std::path::Path>>(path: P) -> Result<StepModel> {
+    let bytes = std::fs::read(path)?;
+    read_str(&String::from_utf8_lossy(&bytes))
+}
@@ -0,0 +1,518 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use crate::io::{Error, Result, format_real};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Parameter {
+    Integer(i64),
+    Real(f64),
+    String(String),
+    Enumeration(String),
+    Binary(String),
+    Reference(u64),
+    List(Vec<Parameter>),
+    Typed(String, Vec<Parameter>),
+    Unset,
+    Derived,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+}
+
+impl Record {
+    pub fn new(name: &str, parameters: Vec<Parameter>) -> Self {
+        Record {
+            name: name.to_string(),
+            parameters,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instance {
+    pub id: u64,
+    pub records: Vec<Record>,
+}
+
+impl Instance {
+    pub fn is_complex(&self) -> bool {
+        self.records.len() > 1
+    }
+
+    pub fn get_record(&self, name: &str) -> Option<&Record> {
+        self.records.iter().find(|r| r.name == name)
+    }
+
+    pub fn has_record(&self, name: &str) -> bool {
+        self.get_record(name).is_some()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Part21File {
+    pub header: Vec<Record>,
+    pub instances: BTreeMap<u64, Instance>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Keyword(String),
+    Integer(i64),
+    Real(f64),
+    String(String),
+    Enumeration(String),
+    Binary(String),
+    Reference(u64),
+    LParen,
+    RParen,
+    Comma,
+    Semicolon,
+    Equals,
+    Dollar,
+    Star,
+}
+
+fn decode_string(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(pos) = rest.find('\\') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if let Some(tail) = rest.strip_prefix("\\\\") {
+            out.push('\\');
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("\\X2\\") {
+            let end = tail.find("\\X0\\").unwrap_or(tail.len());
+            let units: Vec<u16> = tail.as_bytes()[..end]
+                .chunks(4)
+                .filter_map(|c| u16::from_str_radix(std::str::from_utf8(c).ok()?, 16).ok())
+                .collect();
+            out.push_str(&String::from_utf16_lossy(&units));
+            rest = tail.get(end + 4..).unwrap_or("");
+        } else if let Some(tail) = rest.strip_prefix("\\X\\") {
+            match tail.get(..2).and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                Some(b) => {
+                    out.push(b as char);
+                    rest = &tail[2..];
+                }
+                None => {
+                    out.push_str("\\X\\");
+                    rest = tail;
+                }
+            }
+        } else {
+            out.push('\\');
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn encode_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('\'');
+    for c in value.chars() {
+        match c {
+            '\'' => out.push_str("''"),
+            '\\' => out.push_str("\\\\"),
+            ' '..='~' => out.push(c),
+            _ => {
+                out.push_str("\\X2\\");
+                let mut buf = [0u16; 2];
+                for unit in c.encode_utf16(&mut buf).iter() {
+                    let _ = write!(out, "{:04X}", unit);
+                }
+                out.push_str("\\X0\\");
+            }
+        }
+    }
+    out.push('\'');
+    out
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let err = |msg: &str, at: usize| {
+        let line = text[..at.min(text.len())].matches('\n').count() + 1;
+        Error::Parse(format!("{} at line {}", msg, line))
+    };
+    while i < bytes.len() {
+        let c = bytes[i];
+        match c {
+            b' ' | b'\t' | b'\r' | b'\n' => i += 1,
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = text[i + 2..]
+                    .find("*/")
+                    .ok_or_else(|| err("unterminated comment", i))?;
+                i += end + 4;
+            }
+            b'(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            b')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            b',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            b';' => {
+                tokens.push(Token::Semicolon);
+                i += 1;
+            }
+            b'=' => {
+                tokens.push(Token::Equals);
+                i += 1;
+            }
+            b'$' => {
+                tokens.push(Token::Dollar);
+                i += 1;
+            }
+            b'*' => {
+                tokens.push(Token::Star);
+                i += 1;
+            }
+            b'\'' => {
+                let mut raw = String::new();
+                i += 1;
+                loop {
+                    let pos = text[i..]
+                        .find('\'')
+                        .ok_or_else(|| err("unterminated string", i))?;
+                    raw.push_str(&text[i..i + pos]);
+                    i += pos + 1;
+                    if bytes.get(i) == Some(&b'\'') {
+                        raw.push('\'');
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+                let raw: String = raw.chars().filter(|&c| c != '\n' && c != '\r').collect();
+                tokens.push(Token::String(decode_string(&raw)));
+            }
+            b'"' => {
+                let end = text[i + 1..]
+                    .find('"')
+                    .ok_or_else(|| err("unterminated binary", i))?;
+                tokens.push(Token::Binary(text[i + 1..i + 1 + end].to_string()));
+                i += end + 2;
+            }
+            b'#' => {
+                let start = i + 1;
+                i = start;
+                while i < bytes.len() && bytes[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let id = text[start..i]
+                    .parse::<u64>()
+                    .map_err(|_| err("invalid entity reference", start))?;
+                tokens.push(Token::Reference(id));
+            }
+            b'.' if bytes
+                .get(i + 1)
+                .is_some_and(|b| b.is_ascii_alphabetic() || *b == b'_') =>
+            {
+                let end = text[i + 1..]
+                    .find('.')
+                    .ok_or_else(|| err("unterminated enumeration", i))?;
+                tokens.push(Token::Enumeration(text[i + 1..i + 1 + end].to_uppercase()));
+                i += end + 2;
+            }
+            b'+' | b'-' | b'.' | b'0'..=b'9' => {
+                let start = i;
+                i += 1;
+                while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') {
+                    i += 1;
+                }
+                if i < bytes.len() && (bytes[i] == b'E' || bytes[i] == b'e') {
+                    i += 1;
+                    if i < bytes.len() && (bytes[i] == b'+' || bytes[i] == b'-') {
+                        i += 1;
+                    }
+                    while i < bytes.len() && bytes[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+                let s = &text[start..i];
+                if s.contains(['.', 'E', 'e']) {
+                    let value = s
+                        .parse::<f64>()
+                        .or_else(|_| s.replace(".E", ".0E").replace(".e", ".0e").parse::<f64>())
+                        .map_err(|_| err(&format!("invalid real '{}'", s), start))?;
+                    tokens.push(Token::Real(value));
+                } else {
+                    let value = s
+                        .parse::<i64>()
+                        .map_err(|_| err(&format!("invalid integer '{}'", s), start))?;
+                    tokens.push(Token::Integer(value));
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == b'!' || c == b'_' => {
+                let start = i;
+                i += 1;
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'-')
+                {
+                    i += 1;
+                }
+                tokens.push(Token::Keyword(text[start..i].to_uppercase()));
+            }
+            _ => return Err(err(&format!("unexpected character '{}'", c as char), i)),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| Error::Parse("unexpected end of STEP file".to_string()))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        let token = self.next()?;
+        if token != expected {
+            return Err(Error::Parse(format!(
+                "expected {:?} but found {:?}",
+                expected, token
+            )));
+        }
+        Ok(())
+    }
+
+    fn keyword(&mut self) -> Result<String> {
+        match self.next()? {
+            Token::Keyword(k) => Ok(k),
+            token => Err(Error::Parse(format!(
+                "expected keyword but found {:?}",
+                token
+            ))),
+        }
+    }
+
+    fn parameter_list(&mut self) -> Result<Vec<Parameter>> {
+        self.expect(Token::LParen)?;
+        let mut parameters = Vec::new();
+        if self.peek() == Some(&Token::RParen) {
+            self.pos += 1;
+            return Ok(parameters);
+        }
+        loop {
+            parameters.push(self.parameter()?);
+            match self.next()? {
+                Token::Comma => continue,
+                Token::RParen => break,
+                token => {
+                    return Err(Error::Parse(format!(
+                        "expected ',' or ')' but found {:?}",
+                        token
+                    )));
+                }
+            }
+        }
+        Ok(parameters)
+    }
+
+    fn parameter(&mut self) -> Result<Parameter> {
+        match self.peek() {
+            Some(Token::LParen) => return Ok(Parameter::List(self.parameter_list()?)),
+            Some(Token::Keyword(_)) => {
+                let name = self.keyword()?;
+                return Ok(Parameter::Typed(name, self.parameter_list()?));
+            }
+            _ => {}
+        }
+        Ok(match self.next()? {
+            Token::Integer(v) => Parameter::Integer(v),
+            Token::Real(v) => Parameter::Real(v),
+            Token::String(v) => Parameter::String(v),
+            Token::Enumeration(v) => Parameter::Enumeration(v),
+            Token::Binary(v) => Parameter::Binary(v),
+            Token::Reference(v) => Parameter::Reference(v),
+            Token::Dollar => Parameter::Unset,
+            Token::Star => Parameter::Derived,
+            token => return Err(Error::Parse(format!("unexpected token {:?}", token))),
+        })
+    }
+
+    fn record(&mut self) -> Result<Record> {
+        let name = self.keyword()?;
+        Ok(Record {
+            name,
+            parameters: self.parameter_list()?,
+        })
+    }
+
+    fn instance_body(&mut self) -> Result<Vec<Record>> {
+        if self.peek() == Some(&Token::LParen) {
+            self.pos += 1;
+            let mut records = Vec::new();
+            while self.peek() != Some(&Token::RParen) {
+                records.push(self.record()?);
+            }
+            self.pos += 1;
+            if records.is_empty() {
+                return Err(Error::Parse("empty complex entity instance".to_string()));
+            }
+            Ok(records)
+        } else {
+            Ok(vec![self.record()?])
+        }
+    }
+}
+
+impl Part21File {
+    pub fn new() -> Self {
+        Part21File::default()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Instance> {
+        self.instances.get(&id)
+    }
+
+    pub fn get_header(&self, name: &str) -> Option<&Record> {
+        self.header.iter().find(|r| r.name == name)
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            pos: 0,
+        };
+        if parser.keyword()? != "ISO-10303-21" {
+            return Err(Error::Parse("missing ISO-10303-21 magic".to_string()));
+        }
+        parser.expect(Token::Semicolon)?;
+        let mut file = Part21File::new();
+        loop {
+            let section = parser.keyword()?;
+            match section.as_str() {
+                "END-ISO-10303-21" => {
+                    parser.expect(Token::Semicolon)?;
+                    break;
+                }
+                "HEADER" => {
+                    parser.expect(Token::Semicolon)?;
+                    while parser.peek() != Some(&Token::Keyword("ENDSEC".to_string())) {
+                        file.header.push(parser.record()?);
+                        parser.expect(Token::Semicolon)?;
+                    }
+                    parser.pos += 1;
+                    parser.expect(Token::Semicolon)?;
+                }
+                "DATA" => {
+                    if parser.peek() == Some(&Token::LParen) {
+                        parser.parameter_list()?;
+                    }
+                    parser.expect(Token::Semicolon)?;
+                    loop {
+                        match parser.next()? {
+                            Token::Keyword(k) if k == "ENDSEC" => break,
+                            Token::Reference(id) => {
+                                parser.expect(Token::Equals)?;
+                                let records = parser.instance_body()?;
+                                parser.expect(Token::Semicolon)?;
+                                if file
+                                    .instances
+                                    .insert(id, Instance { id, records })
+                                    .is_some()
+                                {
+                                    return Err(Error::Parse(format!(
+                                        "duplicate instance #{}",
+                                        id
+                                    )));
+                                }
+                            }
+                            token => {
+                                return Err(Error::Parse(format!(
+                                    "expected entity instance but found {:?}",
+                                    token
+                                )));
+                            }
+                        }
+                    }
+                    parser.expect(Token::Semicolon)?;
+                }
+                _ => return Err(Error::Parse(format!("unknown section '{}'", section))),
+            }
+        }
+        Ok(file)
+    }
+}
+
+impl std::fmt::Display for Parameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Parameter::Integer(v) => write!(f, "{}", v),
+            Parameter::Real(v) => write!(f, "{}", format_real(*v)),
+            Parameter::String(v) => write!(f, "{}", encode_string(v)),
+            Parameter::Enumeration(v) => write!(f, ".{}.", v),
+            Parameter::Binary(v) => write!(f, "\"{}\"", v),
+            Parameter::Reference(v) => write!(f, "#{}", v),
+            Parameter::List(items) => write!(f, "({})", join(items)),
+            Parameter::Typed(name, items) => write!(f, "{}({})", name, join(items)),
+            Parameter::Unset => write!(f, "$"),
+            Parameter::Derived => write!(f, "*"),
+        }
+    }
+}
+
+fn join(items: &[Parameter]) -> String {
+    items
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl std::fmt::Display for Record {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}({})", self.name, join(&self.parameters))
+    }
+}
+
+impl std::fmt::Display for Part21File {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "ISO-10303-21;")?;
+        writeln!(f, "HEADER;")?;
+        for record in self.header.iter() {
+            writeln!(f, "{};", record)?;
+        }
+        writeln!(f, "ENDSEC;")?;
+        writeln!(f, "DATA;")?;
+        for instance in self.instances.values() {
+            if instance.is_complex() {
+                let records: Vec<String> = instance.records.iter().map(|r| r.to_string()).collect();
+                writeln!(f, "#{}=({});", instance.id, records.join(""))?;
+            } else {
+                writeln!(f, "#{}={};", instance.id, instance.records[0])?;
+            }
+        }
+        writeln!(f, "ENDSEC;")?;
+        writeln!(f, "END-ISO-10303-21;")
+    }
+}
@@ -0,0 +1,579 @@
+use crate::Axis3d;
+use crate::BSplineCurve3d;
+use crate::BSplineSurface;
+use crate::Circle3d;
+use crate::Cone;
+use crate::CoordinateSystem3d;
+use crate::Cylinder;
+use crate::Direction3d;
+use crate::Ellipse3d;
+use crate::GeneralCoordinateSystem3d;
+use crate::Hyperbola3d;
+use crate::Line3d;
+use crate::Parabola3d;
+use crate::Plane;
+use crate::Point3d;
+use crate::Sphere;
+use crate::Torus;
+use crate::Vector3d;
+use crate::io::step::StepGeometry;
+use crate::io::step::part21::{Instance, Parameter, Part21File, Record};
+use crate::io::{Error, LengthUnit, Result, utc_now};
+
+// Writes AP214 (automotive_design) files.
+#[derive(Debug, Clone)]
+pub struct StepWriter {
+    pub length_unit: LengthUnit,
+    pub file_name: String,
+    pub author: String,
+    pub organization: String,
+    pub timestamp: String,
+    next_id: u64,
+    instances: Vec<Instance>,
+    items: Vec<u64>,
+}
+
+fn s(value: &str) -> Parameter {
+    Parameter::String(value.to_string())
+}
+
+fn e(value: &str) -> Parameter {
+    Parameter::Enumeration(value.to_string())
+}
+
+fn r(id: u64) -> Parameter {
+    Parameter::Reference(id)
+}
+
+// Part 21 has no notation for infinities and NaN.
+fn real(value: f64) -> Result<Parameter> {
+    if !value.is_finite() {
+        return Err(Error::Unsupported(format!("non-finite real {}", value)));
+    }
+    Ok(Parameter::Real(value))
+}
+
+fn reals(values: &[f64]) -> Result<Parameter> {
+    Ok(Parameter::List(
+        values.iter().map(|&v| real(v)).collect::<Result<_>>()?,
+    ))
+}
+
+fn integers(values: &[usize]) -> Parameter {
+    Parameter::List(
+        values
+            .iter()
+            .map(|&v| Parameter::Integer(v as i64))
+            .collect(),
+    )
+}
+
+fn refs(ids: &[u64]) -> Parameter {
+    Parameter::List(ids.iter().map(|&id| r(id)).collect())
+}
+
+fn rec(name: &str, parameters: Vec<Parameter>) -> Record {
+    Record::new(name, parameters)
+}
+
+// ISO 8601 timestamp of the current UTC time.
+fn now_timestamp() -> String {
+    let [year, month, day, hour, minute, second] = utc_now();
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year, month, day, hour, minute, second
+    )
+}
+
+impl Default for StepWriter {
+    fn default() -> Self {
+        StepWriter::new()
+    }
+}
+
+impl StepWriter {
+    pub fn new() -> Self {
+        StepWriter {
+            length_unit: LengthUnit::Millimetre,
+            file_name: String::new(),
+            author: String::new(),
+            organization: String::new(),
+            timestamp: now_timestamp(),
+            next_id: 1,
+            instances: Vec::new(),
+            items: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, records: Vec<Record>) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.instances.push(Instance { id, records });
+        id
+    }
+
+    fn push_simple(&mut self, name: &str, parameters: Vec<Parameter>) -> u64 {
+        self.push(vec![rec(name, parameters)])
+    }
+
+    fn write_point(&mut self, point: &Point3d) -> Result<u64> {
+        let (x, y, z) = point.get_coords();
+        Ok(self.push_simple("CARTESIAN_POINT", vec![s(""), reals(&[x, y, z])?]))
+    }
+
+    fn write_direction(&mut self, direction: &Direction3d) -> Result<u64> {
+        let (x, y, z) = direction.get_coords();
+        Ok(self.push_simple("DIRECTION", vec![s(""), reals(&[x, y, z])?]))
+    }
+
+    fn write_vector(&mut self, vector: &Vector3d) -> Result<u64> {
+        let magnitude = real(vector.length())?;
+        let direction = self.write_direction(&Direction3d::from_xyz(vector.get_xyz()))?;
+        Ok(self.push_simple("VECTOR", vec![s(""), r(direction), magnitude]))
+    }
+
+    fn write_axis(&mut self, axis: &Axis3d) -> Result<u64> {
+        let location = self.write_point(&axis.location)?;
+        let direction = self.write_direction(&axis.direction)?;
+        Ok(self.push_simple("AXIS1_PLACEMENT", vec![s(""), r(location), r(direction)]))
+    }
+
+    fn write_placement(&mut self, placement: &CoordinateSystem3d) -> Result<u64> {
+        let location = self.write_point(&placement.axis.location)?;
+        let axis = self.write_direction(&placement.axis.direction)?;
+        let ref_direction = self.write_direction(&placement.vxdir)?;
+        Ok(self.push_simple(
+            "AXIS2_PLACEMENT_3D",
+            vec![s(""), r(location), r(axis), r(ref_direction)],
+        ))
+    }
+
+    // AXIS2_PLACEMENT_3D derives the y axis as z ^ x, so only right-handed
+    // frames can be written.
+    fn write_general_placement(&mut self, placement: &GeneralCoordinateSystem3d) -> Result<u64> {
+        let normal = placement.vxdir.cross_new(&placement.vydir);
+        if normal.get_xyz().dot(placement.axis.direction.get_xyz()) < 0.0 {
+            return Err(Error::Unsupported(
+                "left-handed surface placement".to_string(),
+            ));
+        }
+        self.write_placement(&CoordinateSystem3d {
+            axis: placement.axis,
+            vydir: placement.vydir,
+            vxdir: placement.vxdir,
+        })
+    }
+
+    fn write_line(&mut self, line: &Line3d) -> Result<u64> {
+        let location = self.write_point(&line.pos.location)?;
+        let vector = self.write_vector(&Vector3d::from_xyz(*line.pos.direction.get_xyz()))?;
+        Ok(self.push_simple("LINE", vec![s(""), r(location), r(vector)]))
+    }
+
+    fn write_conic(
+        &mut self,
+        name: &str,
+        position: &CoordinateSystem3d,
+        values: &[f64],
+    ) -> Result<u64> {
+        let values = values
+            .iter()
+            .map(|&v| real(v))
+            .collect::<Result<Vec<_>>>()?;
+        let placement = self.write_placement(position)?;
+        let mut parameters = vec![s(""), r(placement)];
+        parameters.extend(values);
+        Ok(self.push_simple(name, parameters))
+    }
+
+    fn write_surface(
+        &mut self,
+        name: &str,
+        position: &GeneralCoordinateSystem3d,
+        values: &[f64],
+    ) -> Result<u64> {
+        let values = values
+            .iter()
+            .map(|&v| real(v))
+            .collect::<Result<Vec<_>>>()?;
+        let placement = self.write_general_placement(position)?;
+        let mut parameters = vec![s(""), r(placement)];
+        parameters.extend(values);
+        Ok(self.push_simple(name, parameters))
+    }
+
+    fn write_bspline_curve(&mut self, curve: &BSplineCurve3d) -> Result<u64> {
+        let poles = curve
+            .poles
+            .iter()
+            .map(|p| self.write_point(p))
+            .collect::<Result<Vec<_>>>()?;
+        let curve_params = vec![
+            Parameter::Integer(curve.degree as i64),
+            refs(&poles),
+            e("UNSPECIFIED"),
+            e("F"),
+            e("F"),
+        ];
+        let knot_params = vec![
+            integers(&curve.multiplicities),
+            reals(&curve.knots)?,
+            e("UNSPECIFIED"),
+        ];
+        match &curve.weights {
+            None => {
+                let mut parameters = vec![s("")];
+                parameters.extend(curve_params);
+                parameters.extend(knot_params);
+                Ok(self.push_simple("B_SPLINE_CURVE_WITH_KNOTS", parameters))
+            }
+            Some(weights) => Ok(self.push(vec![
+                rec("BOUNDED_CURVE", vec![]),
+                rec("B_SPLINE_CURVE", curve_params),
+                rec("B_SPLINE_CURVE_WITH_KNOTS", knot_params),
+                rec("CURVE", vec![]),
+                rec("GEOMETRIC_REPRESENTATION_ITEM", vec![]),
+                rec("RATIONAL_B_SPLINE_CURVE", vec![reals(weights)?]),
+                rec("REPRESENTATION_ITEM", vec![s("")]),
+            ])),
+        }
+    }
+
+    fn write_bspline_surface(&mut self, surface: &BSplineSurface) -> Result<u64> {
+        let poles = surface
+            .poles
+            .iter()
+            .map(|row| {
+                let ids = row
+                    .iter()
+                    .map(|p| self.write_point(p))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(refs(&ids))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let surface_params = vec![
+            Parameter::Integer(surface.u_degree as i64),
+            Parameter::Integer(surface.v_degree as i64),
+            Parameter::List(poles),
+            e("UNSPECIFIED"),
+            e("F"),
+            e("F"),
+            e("F"),
+        ];
+        let knot_params = vec![
+            integers(&surface.u_multiplicities),
+            integers(&surface.v_multiplicities),
+            reals(&surface.u_knots)?,
+            reals(&surface.v_knots)?,
+            e("UNSPECIFIED"),
+        ];
+        match &surface.weights {
+            None => {
+                let mut parameters = vec![s("")];
+                parameters.extend(surface_params);
+                parameters.extend(knot_params);
+                Ok(self.push_simple("B_SPLINE_SURFACE_WITH_KNOTS", parameters))
+            }
+            Some(weights) => {
+                let weights = Parameter::List(
+                    weights
+                        .iter()
+                        .map(|row| reals(row))
+                        .collect::<Result<_>>()?,
+                );
+                Ok(self.push(vec![
+                    rec("BOUNDED_SURFACE", vec![]),
+                    rec("B_SPLINE_SURFACE", surface_params),
+                    rec("B_SPLINE_SURFACE_WITH_KNOTS", knot_params),
+                    rec("GEOMETRIC_REPRESENTATION_ITEM", vec![]),
+                    rec("RATIONAL_B_SPLINE_SURFACE", vec![weights]),
+                    rec("REPRESENTATION_ITEM", vec![s("")]),
+                    rec("SURFACE", vec![]),
+                ]))
+            }
+        }
+    }
+
+    fn write_geometry(&mut self, geometry: &StepGeometry) -> Result<u64> {
+        match geometry {
+            StepGeometry::Point(p) => self.write_point(p),
+            StepGeometry::Direction(d) => self.write_direction(d),
+            StepGeometry::Vector(v) => self.write_vector(v),
+            StepGeometry::Axis(a) => self.write_axis(a),
+            StepGeometry::Placement(p) => self.write_placement(p),
+            StepGeometry::Line(l) => self.write_line(l),
+            StepGeometry::Circle(c) => self.write_conic("CIRCLE", &c.position, &[c.radius]),
+            StepGeometry::Ellipse(c) => {
+                self.write_conic("ELLIPSE", &c.position, &[c.major_radius, c.minor_radius])
+            }
+            StepGeometry::Hyperbola(c) => {
+                self.write_conic("HYPERBOLA", &c.position, &[c.major_radius, c.minor_radius])
+            }
+            StepGeometry::Parabola(c) => self.write_conic("PARABOLA", &c.pos, &[c.focal_length]),
+            StepGeometry::Plane(p) => self.write_surface("PLANE", &p.pos, &[]),
+            StepGeometry::CylindricalSurface(c) => {
+                self.write_surface("CYLINDRICAL_SURFACE", &c.position, &[c.radius])
+            }
+            StepGeometry::ConicalSurface(c) => {
+                self.write_surface("CONICAL_SURFACE", &c.position, &[c.radius, c.semi_angle])
+            }
+            StepGeometry::SphericalSurface(c) => {
+                self.write_surface("SPHERICAL_SURFACE", &c.pos, &[c.radius])
+            }
+            StepGeometry::ToroidalSurface(c) => self.write_surface(
+                "TOROIDAL_SURFACE",
+                &c.pos,
+                &[c.major_radius, c.minor_radius],
+            ),
+            StepGeometry::BSplineCurve(c) => self.write_bspline_curve(c),
+            StepGeometry::BSplineSurface(c) => self.write_bspline_surface(c),
+        }
+    }
+
+    // Adds a top-level representation item and returns its instance id. On
+    // error nothing is added.
+    pub fn add(&mut self, geometry: &StepGeometry) -> Result<u64> {
+        let (next_id, count) = (self.next_id, self.instances.len());
+        match self.write_geometry(geometry) {
+            Ok(id) => {
+                self.items.push(id);
+                Ok(id)
+            }
+            Err(err) => {
+                self.next_id = next_id;
+                self.instances.truncate(count);
+                Err(err)
+            }
+        }
+    }
+
+    pub fn add_point(&mut self, point: &Point3d) -> Result<u64> {
+        self.add(&StepGeometry::Point(*point))
+    }
+
+    pub fn add_axis(&mut self, axis: &Axis3d) -> Result<u64> {
+        self.add(&StepGeometry::Axis(*axis))
+    }
+
+    pub fn add_placement(&mut self, placement: &CoordinateSystem3d) -> Result<u64> {
+        self.add(&StepGeometry::Placement(*placement))
+    }
+
+    pub fn add_line(&mut self, line: &Line3d) -> Result<u64> {
+        self.add(&StepGeometry::Line(*line))
+    }
+
+    pub fn add_circle(&mut self, circle: &Circle3d) -> Result<u64> {
+        self.add(&StepGeometry::Circle(*circle))
+    }
+
+    pub fn add_ellipse(&mut self, ellipse: &Ellipse3d) -> Result<u64> {
+        self.add(&StepGeometry::Ellipse(*ellipse))
+    }
+
+    pub fn add_hyperbola(&mut self, hyperbola: &Hyperbola3d) -> Result<u64> {
+        self.add(&StepGeometry::Hyperbola(*hyperbola))
+    }
+
+    pub fn add_parabola(&mut self, parabola: &Parabola3d) -> Result<u64> {
+        self.add(&StepGeometry::Parabola(*parabola))
+    }
+
+    pub fn add_plane(&mut self, plane: &Plane) -> Result<u64> {
+        self.add(&StepGeometry::Plane(*plane))
+    }
+
+    pub fn add_cylinder(&mut self, cylinder: &Cylinder) -> Result<u64> {
+        self.add(&StepGeometry::CylindricalSurface(*cylinder))
+    }
+
+    pub fn add_cone(&mut self, cone: &Cone) -> Result<u64> {
+        self.add(&StepGeometry::ConicalSurface(*cone))
+    }
+
+    pub fn add_sphere(&mut self, sphere: &Sphere) -> Result<u64> {
+        self.add(&StepGeometry::SphericalSurface(*sphere))
+    }
+
+    pub fn add_torus(&mut self, torus: &Torus) -> Result<u64> {
+        self.add(&StepGeometry::ToroidalSurface(*torus))
+    }
+
+    pub fn add_bspline_curve(&mut self, curve: &BSplineCurve3d) -> Result<u64> {
+        self.add(&StepGeometry::BSplineCurve(curve.clone()))
+    }
+
+    pub fn add_bspline_surface(&mut self, surface: &BSplineSurface) -> Result<u64> {
+        self.add(&StepGeometry::BSplineSurface(surface.clone()))
+    }
+
+    fn write_units(&mut self) -> u64 {
+        let length = match self.length_unit {
+            LengthUnit::Millimetre => Some("MILLI"),
+            LengthUnit::Centimetre => Some("CENTI"),
+            LengthUnit::Metre => None,
+            LengthUnit::Inch => Some("MILLI"),
+        };
+        let prefix = length.map_or(Parameter::Unset, e);
+        let mut length = self.push(vec![
+            rec("LENGTH_UNIT", vec![]),
+            rec("NAMED_UNIT", vec![Parameter::Derived]),
+            rec("SI_UNIT", vec![prefix, e("METRE")]),
+        ]);
+        if self.length_unit == LengthUnit::Inch {
+            let measure = self.push_simple(
+                "LENGTH_MEASURE_WITH_UNIT",
+                vec![
+                    Parameter::Typed("LENGTH_MEASURE".to_string(), vec![Parameter::Real(25.4)]),
+                    r(length),
+                ],
+            );
+            let exponents = self.push_simple(
+                "DIMENSIONAL_EXPONENTS",
+                vec![
+                    Parameter::Real(1.0),
+                    Parameter::Real(0.0),
+                    Parameter::Real(0.0),
+                    Parameter::Real(0.0),
+                    Parameter::Real(0.0),
+                    Parameter::Real(0.0),
+                    Parameter::Real(0.0),
+                ],
+            );
+            length = self.push(vec![
+                rec("CONVERSION_BASED_UNIT", vec![s("INCH"), r(measure)]),
+                rec("LENGTH_UNIT", vec![]),
+                rec("NAMED_UNIT", vec![r(exponents)]),
+            ]);
+        }
+        let angle = self.push(vec![
+            rec("NAMED_UNIT", vec![Parameter::Derived]),
+            rec("PLANE_ANGLE_UNIT", vec![]),
+            rec("SI_UNIT", vec![Parameter::Unset, e("RADIAN")]),
+        ]);
+        let solid_angle = self.push(vec![
+            rec("NAMED_UNIT", vec![Parameter::Derived]),
+            rec("SI_UNIT", vec![Parameter::Unset, e("STERADIAN")]),
+            rec("SOLID_ANGLE_UNIT", vec![]),
+        ]);
+        let uncertainty = self.push_simple(
+            "UNCERTAINTY_MEASURE_WITH_UNIT",
+            vec![
+                Parameter::Typed("LENGTH_MEASURE".to_string(), vec![Parameter::Real(1e-7)]),
+                r(length),
+                s("distance_accuracy_value"),
+                s("confusion accuracy"),
+            ],
+        );
+        self.push(vec![
+            rec(
+                "GEOMETRIC_REPRESENTATION_CONTEXT",
+                vec![Parameter::Integer(3)],
+            ),
+            rec(
+                "GLOBAL_UNCERTAINTY_ASSIGNED_CONTEXT",
+                vec![refs(&[uncertainty])],
+            ),
+            rec(
+                "GLOBAL_UNIT_ASSIGNED_CONTEXT",
+                vec![refs(&[length, angle, solid_angle])],
+            ),
+            rec("REPRESENTATION_CONTEXT", vec![s("3D"), s("3D")]),
+        ])
+    }
+
+    fn write_product(&mut self, representation: u64) {
+        let application = self.push_simple(
+            "APPLICATION_CONTEXT",
+            vec![s("core data for automotive mechanical design processes")],
+        );
+        self.push_simple(
+            "APPLICATION_PROTOCOL_DEFINITION",
+            vec![
+                s("international standard"),
+                s("automotive_design"),
+                Parameter::Integer(2001),
+                r(application),
+            ],
+        );
+        let product_context = self.push_simple(
+            "PRODUCT_CONTEXT",
+            vec![s(""), r(application), s("mechanical")],
+        );
+        let name = if self.file_name.is_empty() {
+            "geometry".to_string()
+        } else {
+            self.file_name.clone()
+        };
+        let product = self.push_simple(
+            "PRODUCT",
+            vec![s(&name), s(&name), s(""), refs(&[product_context])],
+        );
+        let formation = self.push_simple(
+            "PRODUCT_DEFINITION_FORMATION",
+            vec![s(""), s(""), r(product)],
+        );
+        let definition_context = self.push_simple(
+            "PRODUCT_DEFINITION_CONTEXT",
+            vec![s("part definition"), r(application), s("design")],
+        );
+        let definition = self.push_simple(
+            "PRODUCT_DEFINITION",
+            vec![s("design"), s(""), r(formation), r(definition_context)],
+        );
+        let shape = self.push_simple(
+            "PRODUCT_DEFINITION_SHAPE",
+            vec![s(""), s(""), r(definition)],
+        );
+        self.push_simple(
+            "SHAPE_DEFINITION_REPRESENTATION",
+            vec![r(shape), r(representation)],
+        );
+    }
+
+    pub fn to_part21(&self) -> Part21File {
+        let mut writer = self.clone();
+        let context = writer.write_units();
+        let items = writer.items.clone();
+        let representation = writer.push_simple(
+            "SHAPE_REPRESENTATION",
+            vec![s(""), refs(&items), r(context)],
+        );
+        writer.write_product(representation);
+
+        let schema = "AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }";
+        let mut file = Part21File::new();
+        file.header = vec![
+            rec(
+                "FILE_DESCRIPTION",
+                vec![Parameter::List(vec![s("geom export")]), s("2;1")],
+            ),
+            rec(
+                "FILE_NAME",
+                vec![
+                    s(&self.file_name),
+                    s(&self.timestamp),
+                    Parameter::List(vec![s(&self.author)]),
+                    Parameter::List(vec![s(&self.organization)]),
+                    s("geom"),
+                    s("geom"),
+                    s(""),
+                ],
+            ),
+            rec("FILE_SCHEMA", vec![Parameter::List(vec![s(schema)])]),
+        ];
+        for instance in writer.instances.into_iter() {
+            file.instances.insert(instance.id, instance);
+        }
+        file
+    }
+
+    pub fn write_str(&self) -> String {
+        self.to_part21().to_string()
+    }
+
+    pub fn write_file<P: AsRef<std::path::Path>>(&self, path: P) -> crate::io::Result<()> {
+        std::fs::write(path, self.write_str())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,778 @@
+use crate::BSplineCurve2d;
+use crate::Circle2d;
+use crate::CoordinateSystem2d;
+use crate::Curve2d;
+use crate::Direction2d;
+use crate::Ellipse2d;
+use crate::Point2d;
+use crate::TrimmedCurve2d;
+use crate::XY;
+use crate::io::{Error, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgStyle {
+    pub stroke: String,
+    pub stroke_width: f64,
+    pub fill: String,
+}
+
+impl Default for SvgStyle {
+    fn default() -> Self {
+        SvgStyle {
+            stroke: "black".to_string(),
+            stroke_width: 1.0,
+            fill: "none".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Subpath {
+    pub curves: Vec<TrimmedCurve2d>,
+    pub closed: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvgPath {
+    pub id: Option<String>,
+    // Raw `transform` attribute; it is not applied to the curves.
+    pub transform: Option<String>,
+    pub subpaths: Vec<Subpath>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SvgWriter {
+    // min-x, min-y, width, height; fitted to the geometry when `None`.
+    pub view_box: Option<[f64; 4]>,
+    pub margin: f64,
+    pub width: Option<String>,
+    pub height: Option<String>,
+    // Flip the drawing so that +y points up as in model space.
+    pub y_up: bool,
+    pub precision: usize,
+    // Maximum deviation of the cubic approximation of rational or high degree splines.
+    pub tolerance: f64,
+    paths: Vec<(String, SvgStyle)>,
+    bounds: Option<[f64; 4]>,
+}
+
+fn fmt_number(value: f64, precision: usize) -> String {
+    let s = format!("{:.*}", precision, value);
+    let s = if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &s
+    };
+    if s == "-0" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+// Boehm insertion of `u` into a non-rational B-spline given by flat knots.
+fn insert_knot(degree: usize, knots: &mut Vec<f64>, poles: &mut Vec<XY>, u: f64) {
+    let n = poles.len();
+    let mut k = degree;
+    while k + 1 < n && knots[k + 1] <= u {
+        k += 1;
+    }
+    let mut inserted = Vec::with_capacity(n + 1);
+    for i in 0..=n {
+        if i + degree <= k {
+            inserted.push(poles[i]);
+        } else if i > k {
+            inserted.push(poles[i - 1]);
+        } else {
+            let alpha = (u - knots[i]) / (knots[i + degree] - knots[i]);
+            inserted.push(&(&poles[i - 1] * (1.0 - alpha)) + &(&poles[i] * alpha));
+        }
+    }
+    knots.insert(k + 1, u);
+    *poles = inserted;
+}
+
+// Exact Bezier pieces of a non-rational B-spline between `first` and `last`.
+fn bezier_pieces(curve: &BSplineCurve2d, first: f64, last: f64) -> Vec<Vec<XY>> {
+    let p = curve.degree;
+    let mut knots = curve.get_flat_knots();
+    let mut poles: Vec<XY> = curve.poles.iter().map(|q| *q.get_xy()).collect();
+    let mut breaks: Vec<f64> = vec![first];
+    breaks.extend(
+        curve
+            .knots
+            .iter()
+            .copied()
+            .filter(|&k| k > first && k < last),
+    );
+    breaks.push(last);
+    for &u in breaks.iter() {
+        let count = knots.iter().filter(|&&k| k == u).count();
+        for _ in count..p {
+            insert_knot(p, &mut knots, &mut poles, u);
+        }
+    }
+    breaks
+        .windows(2)
+        .map(|w| {
+            let j = knots.iter().rposition(|&k| k == w[0]).unwrap();
+            poles[j - p..=j].to_vec()
+        })
+        .collect()
+}
+
+// Cubic Bezier approximation of `f` on [a, b], subdivided until within `tolerance`.
+fn fit_cubics<F: Fn(f64) -> XY>(
+    f: &F,
+    a: f64,
+    b: f64,
+    tolerance: f64,
+    depth: usize,
+    out: &mut Vec<[XY; 4]>,
+) {
+    let h = 1e-6 * (b - a);
+    let derivative = |u: f64| &(&f(u + h) - &f(u - h)) * (1.0 / (2.0 * h));
+    let (p0, p3) = (f(a), f(b));
+    let third = (b - a) / 3.0;
+    let p1 = &p0 + &(&derivative(a) * third);
+    let p2 = &p3 - &(&derivative(b) * third);
+    let bezier = |t: f64| {
+        let s = 1.0 - t;
+        let mut xy = &p0 * (s * s * s);
+        xy += &(&p1 * (3.0 * s * s * t));
+        xy += &(&p2 * (3.0 * s * t * t));
+        xy += &(&p3 * (t * t * t));
+        xy
+    };
+    let error = [0.25, 0.5, 0.75]
+        .iter()
+        .map(|&t| (&bezier(t) - &f(a + t * (b - a))).length())
+        .fold(0.0, f64::max);
+    if error <= tolerance || depth == 0 {
+        out.push([p0, p1, p2, p3]);
+    } else {
+        let m = 0.5 * (a + b);
+        fit_cubics(f, a, m, tolerance, depth - 1, out);
+        fit_cubics(f, m, b, tolerance, depth - 1, out);
+    }
+}
+
+struct PathBuilder {
+    data: Vec<String>,
+    precision: usize,
+    current: Option<XY>,
+    start: Option<XY>,
+}
+
+impl PathBuilder {
+    fn xy(&self, xy: &XY) -> String {
+        format!(
+            "{} {}",
+            fmt_number(xy.x, self.precision),
+            fmt_number(xy.y, self.precision)
+        )
+    }
+
+    fn move_to(&mut self, xy: XY) {
+        let tolerance = 0.5 * 10f64.powi(-(self.precision as i32));
+        let connected = self
+            .current
+            .is_some_and(|c| (c.x - xy.x).abs() <= tolerance && (c.y - xy.y).abs() <= tolerance);
+        if !connected {
+            self.data.push(format!("M{}", self.xy(&xy)));
+            self.start = Some(xy);
+        }
+        self.current = Some(xy);
+    }
+
+    fn command(&mut self, letter: char, points: &[XY]) {
+        let coords: Vec<String> = points.iter().map(|p| self.xy(p)).collect();
+        self.data.push(format!("{}{}", letter, coords.join(" ")));
+        self.current = points.last().copied();
+    }
+
+    // Elliptical arc in the frame (`x_dir`, `y_dir`) swept from `first` to `last`.
+    fn arc(&mut self, center: &XY, x_dir: &XY, rx: f64, ry: f64, first: f64, last: f64) {
+        let y_dir = XY::from_coords(-x_dir.y, x_dir.x);
+        let at = |u: f64| {
+            let mut xy = *center;
+            xy += &(x_dir * (rx * u.cos()));
+            xy += &(&y_dir * (ry * u.sin()));
+            xy
+        };
+        self.move_to(at(first));
+        let sweep = last - first;
+        // Split sweeps above a half turn, so the large-arc flag is never set and closed
+        // arcs do not have coincident end points.
+        let pieces = if sweep.abs() > std::f64::consts::PI + 1e-9 {
+            2
+        } else {
+            1
+        };
+        let rotation = x_dir.y.atan2(x_dir.x).to_degrees();
+        for i in 1..=pieces {
+            let end = at(first + sweep * i as f64 / pieces as f64);
+            self.data.push(format!(
+                "A{} {} {} 0 {} {}",
+                fmt_number(rx, self.precision),
+                fmt_number(ry, self.precision),
+                fmt_number(rotation, self.precision),
+                (sweep > 0.0) as u8,
+                self.xy(&end)
+            ));
+            self.current = Some(end);
+        }
+    }
+
+    fn close_if_at_start(&mut self) {
+        if let (Some(c), Some(s)) = (self.current, self.start)
+            && self.xy(&c) == self.xy(&s)
+            && self.data.len() > 1
+        {
+            self.data.push("Z".to_string());
+            self.current = None;
+        }
+    }
+}
+
+// SVG path data for a chain of curves; a new subpath starts wherever the chain breaks.
+pub fn path_data(curves: &[TrimmedCurve2d], precision: usize, tolerance: f64) -> Result<String> {
+    let mut builder = PathBuilder {
+        data: Vec::new(),
+        precision,
+        current: None,
+        start: None,
+    };
+    for curve in curves.iter() {
+        let (first, last) = (curve.first, curve.last);
+        match &curve.basis {
+            Curve2d::Line(_) => {
+                if !first.is_finite() || !last.is_finite() {
+                    return Err(Error::Unsupported("unbounded line".to_string()));
+                }
+                builder.move_to(*curve.start_point().get_xy());
+                builder.command('L', &[*curve.end_point().get_xy()]);
+            }
+            Curve2d::Circle(c) => builder.arc(
+                c.position.origin.get_xy(),
+                c.position.vdir.get_xy(),
+                c.radius,
+                c.radius,
+                first,
+                last,
+            ),
+            Curve2d::Ellipse(e) => builder.arc(
+                e.position.origin.get_xy(),
+                e.position.vdir.get_xy(),
+                e.major_radius,
+                e.minor_radius,
+                first,
+                last,
+            ),
+            Curve2d::BSpline(b) => {
+                let (a, z) = if first <= last {
+                    (first, last)
+                } else {
+                    (last, first)
+                };
+                let mut pieces: Vec<Vec<XY>> = if b.is_rational() || b.degree > 3 {
+                    let f = |u: f64| *b.value(u.clamp(a, z)).get_xy();
+                    let mut cubics = Vec::new();
+                    fit_cubics(&f, a, z, tolerance, 16, &mut cubics);
+                    cubics.into_iter().map(|c| c.to_vec()).collect()
+                } else {
+                    bezier_pieces(b, a, z)
+                };
+                if first > last {
+                    pieces.reverse();
+                    pieces.iter_mut().for_each(|p| p.reverse());
+                }
+                for piece in pieces.iter() {
+                    builder.move_to(piece[0]);
+                    let letter = match piece.len() {
+                        2 => 'L',
+                        3 => 'Q',
+                        _ => 'C',
+                    };
+                    builder.command(letter, &piece[1..]);
+                }
+            }
+        }
+        builder.close_if_at_start();
+    }
+    Ok(builder.data.join(" "))
+}
+
+struct Tokens<'a> {
+    chars: &'a [u8],
+    pos: usize,
+}
+
+impl Tokens<'_> {
+    fn skip_separators(&mut self) {
+        while self.pos < self.chars.len()
+            && (self.chars[self.pos].is_ascii_whitespace() || self.chars[self.pos] == b',')
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        let c = *self.chars.get(self.pos)?;
+        if c.is_ascii_alphabetic() && c != b'e' && c != b'E' {
+            self.pos += 1;
+            Some(c)
+        } else {
+            None
+        }
+    }
+
+    fn has_number(&mut self) -> bool {
+        self.skip_separators();
+        self.chars
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, b'-' | b'+' | b'.'))
+    }
+
+    fn number(&mut self) -> Result<f64> {
+        self.skip_separators();
+        let start = self.pos;
+        let c = &self.chars;
+        let mut i = self.pos;
+        if i < c.len() && matches!(c[i], b'-' | b'+') {
+            i += 1;
+        }
+        let mut dot = false;
+        while i < c.len() && (c[i].is_ascii_digit() || (c[i] == b'.' && !dot)) {
+            dot |= c[i] == b'.';
+            i += 1;
+        }
+        if i < c.len() && matches!(c[i], b'e' | b'E') {
+            let mut j = i + 1;
+            if j < c.len() && matches!(c[j], b'-' | b'+') {
+                j += 1;
+            }
+            if j < c.len() && c[j].is_ascii_digit() {
+                i = j;
+                while i < c.len() && c[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+        }
+        self.pos = i;
+        std::str::from_utf8(&c[start..i])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| Error::Parse(format!("expected a number at offset {}", start)))
+    }
+
+    // Arc flags may be written without separators, e.g. "a1 1 0 01 2 2".
+    fn flag(&mut self) -> Result<bool> {
+        self.skip_separators();
+        match self.chars.get(self.pos) {
+            Some(b'0') => {
+                self.pos += 1;
+                Ok(false)
+            }
+            Some(b'1') => {
+                self.pos += 1;
+                Ok(true)
+            }
+            _ => Err(Error::Parse(format!(
+                "expected a flag at offset {}",
+                self.pos
+            ))),
+        }
+    }
+
+    fn point(&mut self, relative_to: Option<XY>) -> Result<XY> {
+        let (x, y) = (self.number()?, self.number()?);
+        Ok(match relative_to {
+            Some(c) => XY::from_coords(c.x + x, c.y + y),
+            None => XY::from_coords(x, y),
+        })
+    }
+}
+
+fn segment(start: XY, end: XY) -> Option<TrimmedCurve2d> {
+    TrimmedCurve2d::from_segment(&Point2d::from_xy(start), &Point2d::from_xy(end)).ok()
+}
+
+fn bezier(points: &[XY]) -> Option<TrimmedCurve2d> {
+    if points.iter().all(|p| (p - &points[0]).length() == 0.0) {
+        return None;
+    }
+    let poles = points.iter().map(|p| Point2d::from_xy(*p)).collect();
+    let curve = BSplineCurve2d::from_bezier(poles).ok()?;
+    Some(TrimmedCurve2d::from_curve(Curve2d::BSpline(curve)))
+}
+
+// Endpoint to center conversion of an SVG elliptical arc (SVG 1.1, appendix F.6.5).
+fn elliptical_arc(
+    start: XY,
+    end: XY,
+    rx: f64,
+    ry: f64,
+    rotation: f64,
+    large: bool,
+    sweep: bool,
+) -> Option<TrimmedCurve2d> {
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if (&end - &start).length() == 0.0 {
+        return None;
+    }
+    if rx == 0.0 || ry == 0.0 {
+        return segment(start, end);
+    }
+    let pi = std::f64::consts::PI;
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let (dx, dy) = (0.5 * (start.x - end.x), 0.5 * (start.y - end.y));
+    let (x1, y1) = (cos * dx + sin * dy, -sin * dx + cos * dy);
+    let lambda = x1 * x1 / (rx * rx) + y1 * y1 / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+    let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let mut coef = (numerator / denominator).max(0.0).sqrt();
+    if large == sweep {
+        coef = -coef;
+    }
+    let (cx1, cy1) = (coef * rx * y1 / ry, -coef * ry * x1 / rx);
+    let center = XY::from_coords(
+        cos * cx1 - sin * cy1 + 0.5 * (start.x + end.x),
+        sin * cx1 + cos * cy1 + 0.5 * (start.y + end.y),
+    );
+    let theta = ((y1 - cy1) / ry).atan2((x1 - cx1) / rx);
+    let mut delta = ((-y1 - cy1) / ry).atan2((-x1 - cx1) / rx) - theta;
+    if sweep && delta < 0.0 {
+        delta += 2.0 * pi;
+    } else if !sweep && delta > 0.0 {
+        delta -= 2.0 * pi;
+    }
+
+    let x_dir = Direction2d::from_coords(cos, sin);
+    let origin = Point2d::from_xy(center);
+    let basis = if (rx - ry).abs() <= 1e-12 * rx.max(ry) {
+        return Some(TrimmedCurve2d::new(
+            Curve2d::Circle(Circle2d {
+                position: CoordinateSystem2d::from_origin_vydir_vxdir(origin, x_dir),
+                radius: rx,
+            }),
+            theta,
+            theta + delta,
+        ));
+    } else if rx >= ry {
+        (x_dir, rx, ry, 0.0)
+    } else {
+        // Major axis along the local y axis; parameters shift by a quarter turn.
+        (Direction2d::from_coords(-sin, cos), ry, rx, -0.5 * pi)
+    };
+    let (vdir, major_radius, minor_radius, shift) = basis;
+    Some(TrimmedCurve2d::new(
+        Curve2d::Ellipse(Ellipse2d {
+            position: CoordinateSystem2d::from_origin_vydir_vxdir(origin, vdir),
+            major_radius,
+            minor_radius,
+        }),
+        theta + shift,
+        theta + delta + shift,
+    ))
+}
+
+// Parses SVG path data into subpaths of curves in SVG user coordinates.
+pub fn parse_path(data: &str) -> Result<Vec<Subpath>> {
+    let mut tokens = Tokens {
+        chars: data.as_bytes(),
+        pos: 0,
+    };
+    let mut subpaths: Vec<Subpath> = Vec::new();
+    let mut curves: Vec<TrimmedCurve2d> = Vec::new();
+    let origin = XY::from_coords(0.0, 0.0);
+    let (mut current, mut start) = (origin, origin);
+    // Last control point of a cubic or quadratic segment for S and T.
+    let mut last_cubic: Option<XY> = None;
+    let mut last_quadratic: Option<XY> = None;
+    let mut command = None;
+    let mut started = false;
+
+    let finish = |curves: &mut Vec<TrimmedCurve2d>, subpaths: &mut Vec<Subpath>, closed| {
+        if !curves.is_empty() {
+            subpaths.push(Subpath {
+                curves: std::mem::take(curves),
+                closed,
+            });
+        }
+    };
+
+    loop {
+        let letter = match tokens.command() {
+            Some(c) => c,
+            None if tokens.has_number() => match command {
+                // Coordinates following a moveto are implicit linetos.
+                Some(b'M') => b'L',
+                Some(b'm') => b'l',
+                Some(c) => c,
+                None => {
+                    return Err(Error::Parse(format!(
+                        "coordinates without a command at offset {}",
+                        tokens.pos
+                    )));
+                }
+            },
+            None if tokens.pos >= tokens.chars.len() => break,
+            None => {
+                return Err(Error::Parse(format!(
+                    "unexpected character at offset {}",
+                    tokens.pos
+                )));
+            }
+        };
+        if !started && !matches!(letter, b'M' | b'm') {
+            return Err(Error::Parse(
+                "path data must start with a moveto".to_string(),
+            ));
+        }
+        started = true;
+        command = Some(letter);
+        let relative = letter.is_ascii_lowercase().then_some(current);
+        let (mut cubic, mut quadratic) = (None, None);
+        match letter.to_ascii_uppercase() {
+            b'M' => {
+                finish(&mut curves, &mut subpaths, false);
+                current = tokens.point(relative)?;
+                start = current;
+            }
+            b'L' | b'H' | b'V' => {
+                let end = match letter.to_ascii_uppercase() {
+                    b'L' => tokens.point(relative)?,
+                    b'H' => {
+                        let x = tokens.number()?;
+                        XY::from_coords(
+                            if relative.is_some() { current.x + x } else { x },
+                            current.y,
+                        )
+                    }
+                    _ => {
+                        let y = tokens.number()?;
+                        XY::from_coords(
+                            current.x,
+                            if relative.is_some() { current.y + y } else { y },
+                        )
+                    }
+                };
+                curves.extend(segment(current, end));
+                current = end;
+            }
+            b'C' | b'S' => {
+                let c1 = if letter.eq_ignore_ascii_case(&b'C') {
+                    tokens.point(relative)?
+                } else {
+                    match last_cubic {
+                        Some(c) => &(&current * 2.0) - &c,
+                        None => current,
+                    }
+                };
+                let c2 = tokens.point(relative)?;
+                let end = tokens.point(relative)?;
+                curves.extend(bezier(&[current, c1, c2, end]));
+                cubic = Some(c2);
+                current = end;
+            }
+            b'Q' | b'T' => {
+                let c = if letter.eq_ignore_ascii_case(&b'Q') {
+                    tokens.point(relative)?
+                } else {
+                    match last_quadratic {
+                        Some(c) => &(&current * 2.0) - &c,
+                        None => current,
+                    }
+                };
+                let end = tokens.point(relative)?;
+                curves.extend(bezier(&[current, c, end]));
+                quadratic = Some(c);
+                current = end;
+            }
+            b'A' => {
+                let (rx, ry, rotation) = (tokens.number()?, tokens.number()?, tokens.number()?);
+                let (large, sweep) = (tokens.flag()?, tokens.flag()?);
+                let end = tokens.point(relative)?;
+                curves.extend(elliptical_arc(current, end, rx, ry, rotation, large, sweep));
+                current = end;
+            }
+            b'Z' => {
+                curves.extend(segment(current, start));
+                current = start;
+                finish(&mut curves, &mut subpaths, true);
+                command = None;
+            }
+            _ => {
+                return Err(Error::Unsupported(format!(
+                    "path command '{}'",
+                    letter as char
+                )));
+            }
+        }
+        last_cubic = cubic;
+        last_quadratic = quadratic;
+    }
+    finish(&mut curves, &mut subpaths, false);
+    Ok(subpaths)
+}
+
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag;
+    while let Some(i) = rest.find(name) {
+        let before = rest[..i].chars().last();
+        let after = rest[i + name.len()..].trim_start();
+        rest = &rest[i + name.len()..];
+        if !before.is_some_and(|c| c.is_whitespace()) {
+            continue;
+        }
+        let Some(value) = after.strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        let end = value[1..].find(quote)?;
+        return Some(unescape(&value[1..1 + end]));
+    }
+    None
+}
+
+// Every `<path>` element of an SVG document, in document order.
+pub fn read_str(svg: &str) -> Result<Vec<SvgPath>> {
+    let mut paths = Vec::new();
+    let mut rest = svg;
+    while let Some(i) = rest.find("<path") {
+        rest = &rest[i + 5..];
+        if !rest.starts_with(|c: char| c.is_whitespace() || c == '/' || c == '>') {
+            continue;
+        }
+        let end = rest
+            .find('>')
+            .ok_or_else(|| Error::Parse("unterminated path element".to_string()))?;
+        let tag = &rest[..end];
+        if let Some(data) = attribute(tag, "d") {
+            paths.push(SvgPath {
+                id: attribute(tag, "id"),
+                transform: attribute(tag, "transform"),
+                subpaths: parse_path(&data)?,
+            });
+        }
+        rest = &rest[end..];
+    }
+    Ok(paths)
+}
+
+pub fn read_file<P: AsRef<std::path::Path>>(path: P) -> Result<Vec<SvgPath>> {
+    read_str(&std::fs::read_to_string(path)?)
+}
+
+impl Default for SvgWriter {
+    fn default() -> Self {
+        SvgWriter::new()
+    }
+}
+
+impl SvgWriter {
+    pub fn new() -> Self {
+        SvgWriter {
+            view_box: None,
+            margin: 0.0,
+            width: None,
+            height: None,
+            y_up: true,
+            precision: 6,
+            tolerance: 1e-4,
+            paths: Vec::new(),
+            bounds: None,
+        }
+    }
+
+    pub fn add(&mut self, curves: &[TrimmedCurve2d], style: &SvgStyle) -> Result<()> {
+        let data = path_data(curves, self.precision, self.tolerance)?;
+        for curve in curves.iter() {
+            let samples = match curve.basis {
+                Curve2d::Line(_) => 1,
+                _ => 64,
+            };
+            for i in 0..=samples {
+                let u = curve.first + (curve.last - curve.first) * i as f64 / samples as f64;
+                let (x, y) = curve.value(u).get_coords();
+                let b = self.bounds.get_or_insert([x, y, x, y]);
+                *b = [b[0].min(x), b[1].min(y), b[2].max(x), b[3].max(y)];
+            }
+        }
+        self.paths.push((data, style.clone()));
+        Ok(())
+    }
+
+    fn fitted_view_box(&self) -> [f64; 4] {
+        let [x0, y0, x1, y1] = self.bounds.unwrap_or([0.0, 0.0, 1.0, 1.0]);
+        let m = self.margin;
+        let y = if self.y_up { -y1 } else { y0 };
+        [x0 - m, y - m, x1 - x0 + 2.0 * m, y1 - y0 + 2.0 * m]
+    }
+
+    pub fn to_svg(&self) -> String {
+        let p = self.precision;
+        let view_box = self.view_box.unwrap_or_else(|| self.fitted_view_box());
+        let mut svg = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        svg.push_str(&format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{}\"",
+            view_box.map(|v| fmt_number(v, p)).join(" ")
+        ));
+        if let Some(width) = &self.width {
+            svg.push_str(&format!(" width=\"{}\"", escape(width)));
+        }
+        if let Some(height) = &self.height {
+            svg.push_str(&format!(" height=\"{}\"", escape(height)));
+        }
+        svg.push_str(">\n");
+        if self.y_up {
+            svg.push_str("<g transform=\"scale(1,-1)\">\n");
+        }
+        for (data, style) in self.paths.iter() {
+            svg.push_str(&format!(
+                "<path d=\"{}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\"/>\n",
+                data,
+                escape(&style.fill),
+                escape(&style.stroke),
+                fmt_number(style.stroke_width, p)
+            ));
+        }
+        if self.y_up {
+            svg.push_str("</g>\n");
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn write_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.to_svg())?;
+        Ok(())
+    }
+}
@@ -2,28 +2,46 @@ pub(crate) mod traits;
 
 pub mod axis2d;
 pub mod axis3d;
+pub mod bounding;
+pub(crate) mod bspline;
+pub mod bspline_curve2d;
+pub mod bspline_curve3d;
+pub mod bspline_surface;
 pub mod circle2d;
 pub mod circle3d;
 pub mod cone;
+pub mod convex_hull;
 pub mod coordinate_system2d;
 pub mod coordinate_system3d;
+pub mod curve2d;
+pub mod cylinder;
+pub mod delaunay2d;
+pub mod delaunay3d;
 pub mod direction2d;
 pub mod direction3d;
+pub mod ellipse2d;
+pub mod ellipse3d;
 pub mod general_coordinate_system3d;
 pub mod hyperbola2d;
 pub mod hyperbola3d;
+pub mod io;
 pub mod line2d;
 pub mod line3d;
 pub mod matrix2;
 pub mod matrix3;
+pub mod mesh;
 pub mod parabola2d;
 pub mod parabola3d;
 pub mod plane;
 pub mod point2d;
 pub mod point3d;
+pub mod polygon2d;
+pub mod predicates;
 pub mod quaternion;
 pub mod quaternion_nlerp;
 pub mod quaternion_slerp;
+#[cfg(feature = "serde")]
+mod serialization;
 pub mod sphere;
 pub mod torus;
 pub mod trsf2d;
@@ -36,13 +54,24 @@ pub mod xyz;
 
 pub use axis2d::Axis2d;
 pub use axis3d::Axis3d;
+pub use bounding::{OrientedBox3d, Rectangle2d};
+pub use bspline_curve2d::BSplineCurve2d;
+pub use bspline_curve3d::BSplineCurve3d;
+pub use bspline_surface::BSplineSurface;
 pub use circle2d::Circle2d;
 pub use circle3d::Circle3d;
 pub use cone::Cone;
+pub use convex_hull::{ConvexHull2d, ConvexHull3d};
 pub use coordinate_system2d::CoordinateSystem2d;
 pub use coordinate_system3d::CoordinateSystem3d;
+pub use curve2d::{Curve2d, TrimmedCurve2d};
+pub use cylinder::Cylinder;
+pub use delaunay2d::{Domain2d, Triangulation2d};
+pub use delaunay3d::Tetrahedralization;
 pub use direction2d::Direction2d;
 pub use direction3d::Direction3d;
+pub use ellipse2d::Ellipse2d;
+pub use ellipse3d::Ellipse3d;
 pub use general_coordinate_system3d::GeneralCoordinateSystem3d;
 pub use hyperbola2d::Hyperbola2d;
 pub use hyperbola3d::Hyperbola3d;
@@ -50,11 +79,13 @@ pub use line2d::Line2d;
 pub use line3d::Line3d;
 pub use matrix2::Matrix2;
 pub use matrix3::Matrix3;
+pub use mesh::Mesh;
 pub use parabola2d::Parabola2d;
 pub use parabola3d::Parabola3d;
 pub use plane::Plane;
 pub use point2d::Point2d;
 pub use point3d::Point3d;
+pub use polygon2d::Polygon2d;
 pub use quaternion::Quaternion;
 pub use quaternion_nlerp::QuaternionNlerp;
 pub use quaternion_slerp::QuaternionSlerp;
@@ -1,6 +1,13 @@
 use crate::Axis3d;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        deserialize = "T: serde::Deserialize<'de> + Copy + Default + crate::traits::FloatWithConst"
+    ))
+)]
 pub struct Line3d<T = f64> {
     pub pos: Axis3d<T>,
 }
\ No newline at end of file
@@ -0,0 +1,188 @@
+use crate::Point3d;
+use crate::traits::FloatWithConst;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AttributeType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl AttributeType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            AttributeType::I8 => "I8",
+            AttributeType::U8 => "U8",
+            AttributeType::I16 => "I16",
+            AttributeType::U16 => "U16",
+            AttributeType::I32 => "I32",
+            AttributeType::U32 => "U32",
+            AttributeType::F32 => "F32",
+            AttributeType::F64 => "F64",
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            AttributeType::I8 | AttributeType::U8 => 1,
+            AttributeType::I16 | AttributeType::U16 => 2,
+            AttributeType::I32 | AttributeType::U32 | AttributeType::F32 => 4,
+            AttributeType::F64 => 8,
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        !matches!(self, AttributeType::F32 | AttributeType::F64)
+    }
+}
+
+// Every supported attribute type is exactly representable as f64, so the
+// values are stored widened and narrowed back on output.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AttributeValues {
+    Scalar(Vec<f64>),
+    List(Vec<Vec<f64>>),
+}
+
+impl AttributeValues {
+    pub fn len(&self) -> usize {
+        match self {
+            AttributeValues::Scalar(values) => values.len(),
+            AttributeValues::List(values) => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Attribute {
+    pub name: String,
+    pub data_type: AttributeType,
+    pub values: AttributeValues,
+}
+
+impl Attribute {
+    pub fn scalar(name: &str, data_type: AttributeType, values: Vec<f64>) -> Self {
+        Attribute {
+            name: name.to_string(),
+            data_type,
+            values: AttributeValues::Scalar(values),
+        }
+    }
+
+    pub fn list(name: &str, data_type: AttributeType, values: Vec<Vec<f64>>) -> Self {
+        Attribute {
+            name: name.to_string(),
+            data_type,
+            values: AttributeValues::List(values),
+        }
+    }
+
+    pub fn get_scalar(&self, index: usize) -> Option<f64> {
+        match &self.values {
+            AttributeValues::Scalar(values) => values.get(index).copied(),
+            AttributeValues::List(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Mesh<T = f64> {
+    pub points: Vec<Point3d<T>>,
+    pub faces: Vec<Vec<usize>>,
+    pub point_attributes: Vec<Attribute>,
+    pub face_attributes: Vec<Attribute>,
+}
+
+impl<T> Mesh<T>
+where
+    T: Copy + Default + FloatWithConst,
+{
+    pub fn new() -> Self {
+        Mesh {
+            points: Vec::new(),
+            faces: Vec::new(),
+            point_attributes: Vec::new(),
+            face_attributes: Vec::new(),
+        }
+    }
+
+    pub fn from_points_faces(points: Vec<Point3d<T>>, faces: Vec<Vec<usize>>) -> Self {
+        Mesh {
+            points,
+            faces,
+            point_attributes: Vec::new(),
+            face_attributes: Vec::new(),
+        }
+    }
+
+    pub fn num_points(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn num_faces(&self) -> usize {
+        self.faces.len()
+    }
+
+    pub fn get_point_attribute(&self, name: &str) -> Option<&Attribute> {
+        self.point_attributes.iter().find(|a| a.name == name)
+    }
+
+    pub fn get_face_attribute(&self, name: &str) -> Option<&Attribute> {
+        self.face_attributes.iter().find(|a| a.name == name)
+    }
+
+    pub fn add_point_attribute(&mut self, attribute: Attribute) -> Result<(), &'static str> {
+        if attribute.values.len() != self.points.len() {
+            return Err("Attribute length does not match number of points");
+        }
+        self.point_attributes.retain(|a| a.name != attribute.name);
+        self.point_attributes.push(attribute);
+        Ok(())
+    }
+
+    pub fn add_face_attribute(&mut self, attribute: Attribute) -> Result<(), &'static str> {
+        if attribute.values.len() != self.faces.len() {
+            return Err("Attribute length does not match number of faces");
+        }
+        self.face_attributes.retain(|a| a.name != attribute.name);
+        self.face_attributes.push(attribute);
+        Ok(())
+    }
+
+    pub fn is_valid(&self) -> bool {
+        let n = self.points.len();
+        self.faces
+            .iter()
+            .all(|f| f.len() >= 3 && f.iter().all(|&i| i < n))
+            && self
+                .point_attributes
+                .iter()
+                .all(|a| a.values.len() == self.points.len())
+            && self
+                .face_attributes
+                .iter()
+                .all(|a| a.values.len() == self.faces.len())
+    }
+}
+
+impl<T> Default for Mesh<T>
+where
+    T: Copy + Default + FloatWithConst,
+{
+    fn default() -> Self {
+        Mesh::new()
+    }
+}
@@ -1,6 +1,7 @@
 use crate::CoordinateSystem2d;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Parabola2d<T = f64> {
     pub pos: CoordinateSystem2d<T>,
     pub focal_length: T,
@@ -1,6 +1,7 @@
 use crate::CoordinateSystem3d;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Parabola3d<T = f64> {
     pub pos: CoordinateSystem3d<T>,
     pub focal_length: T,
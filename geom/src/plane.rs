@@ -1,6 +1,13 @@
 use crate::GeneralCoordinateSystem3d;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        deserialize = "T: serde::Deserialize<'de> + Copy + Default + crate::traits::FloatWithConst"
+    ))
+)]
 pub struct Plane<T = f64> {
     pub pos: GeneralCoordinateSystem3d<T>,
 }
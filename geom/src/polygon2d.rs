@@ -0,0 +1,762 @@
+use crate::Point2d;
+use crate::predicates::orient2d;
+use std::collections::HashMap;
+
+// Largest deviation of round joins from the true arc, relative to the
+// offset distance.
+const ARC_TOLERANCE: f64 = 1e-3;
+
+// Distance below which overlay vertices are merged, relative to the largest
+// coordinate.
+const SNAP_TOLERANCE: f64 = 1e-12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BooleanOp {
+    Union,
+    Intersection,
+    Difference,
+    Xor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Join {
+    // Sharp corners, squared off where the miter would exceed the given
+    // multiple of the offset distance.
+    Miter(f64),
+    Round,
+    // Both edges extended by the offset distance.
+    Square,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointLocation {
+    Inside,
+    Outside,
+    Boundary,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Polygon2d {
+    // Counterclockwise outer boundary and clockwise holes, so the interior is
+    // always on the left. Rings are implicitly closed.
+    pub outer: Vec<Point2d>,
+    pub holes: Vec<Vec<Point2d>>,
+}
+
+impl Polygon2d {
+    // Drops repeated points, including a repeated first point, and orients
+    // the rings.
+    pub fn new(outer: Vec<Point2d>, holes: Vec<Vec<Point2d>>) -> Self {
+        let ring = |mut points: Vec<Point2d>, ccw: bool| {
+            points.dedup_by(|a, b| a.is_equal(b, 0.0));
+            while points.len() > 1 && points[0].is_equal(&points[points.len() - 1], 0.0) {
+                points.pop();
+            }
+            if (signed_area(&points) > 0.0) != ccw {
+                points.reverse();
+            }
+            points
+        };
+        Polygon2d {
+            outer: ring(outer, true),
+            holes: holes.into_iter().map(|h| ring(h, false)).collect(),
+        }
+    }
+
+    pub fn rings(&self) -> impl Iterator<Item = &Vec<Point2d>> {
+        std::iter::once(&self.outer).chain(self.holes.iter())
+    }
+
+    pub fn area(&self) -> f64 {
+        self.rings().map(|r| signed_area(r)).sum()
+    }
+
+    pub fn centroid(&self) -> Result<Point2d, &'static str> {
+        let (mut cx, mut cy, mut area) = (0.0, 0.0, 0.0);
+        for ring in self.rings() {
+            for (a, b) in ring_edges(ring) {
+                let cross = a.get_x() * b.get_y() - b.get_x() * a.get_y();
+                cx += (a.get_x() + b.get_x()) * cross;
+                cy += (a.get_y() + b.get_y()) * cross;
+                area += 0.5 * cross;
+            }
+        }
+        if area == 0.0 {
+            return Err("polygon has no area");
+        }
+        Ok(Point2d::from_coords(cx / (6.0 * area), cy / (6.0 * area)))
+    }
+
+    pub fn locate(&self, point: &Point2d) -> PointLocation {
+        locate(point, self.rings().map(|r| r.as_slice()))
+    }
+
+    pub fn union(&self, other: &Polygon2d) -> Vec<Polygon2d> {
+        boolean(
+            std::slice::from_ref(self),
+            std::slice::from_ref(other),
+            BooleanOp::Union,
+        )
+    }
+
+    pub fn intersection(&self, other: &Polygon2d) -> Vec<Polygon2d> {
+        boolean(
+            std::slice::from_ref(self),
+            std::slice::from_ref(other),
+            BooleanOp::Intersection,
+        )
+    }
+
+    pub fn difference(&self, other: &Polygon2d) -> Vec<Polygon2d> {
+        boolean(
+            std::slice::from_ref(self),
+            std::slice::from_ref(other),
+            BooleanOp::Difference,
+        )
+    }
+
+    pub fn xor(&self, other: &Polygon2d) -> Vec<Polygon2d> {
+        boolean(
+            std::slice::from_ref(self),
+            std::slice::from_ref(other),
+            BooleanOp::Xor,
+        )
+    }
+
+    pub fn offset(&self, delta: f64, join: Join) -> Vec<Polygon2d> {
+        offset(std::slice::from_ref(self), delta, join)
+    }
+}
+
+// Positive for counterclockwise rings.
+pub fn signed_area(ring: &[Point2d]) -> f64 {
+    0.5 * ring_edges(ring)
+        .map(|(a, b)| a.get_x() * b.get_y() - b.get_x() * a.get_y())
+        .sum::<f64>()
+}
+
+pub fn is_counterclockwise(ring: &[Point2d]) -> bool {
+    signed_area(ring) > 0.0
+}
+
+fn ring_edges(ring: &[Point2d]) -> impl Iterator<Item = (Point2d, Point2d)> + '_ {
+    (0..ring.len()).map(move |i| (ring[i], ring[(i + 1) % ring.len()]))
+}
+
+// Even-odd point location against a set of rings.
+pub fn locate<'a>(point: &Point2d, rings: impl Iterator<Item = &'a [Point2d]>) -> PointLocation {
+    let mut inside = false;
+    for ring in rings {
+        for (a, b) in ring_edges(ring) {
+            let o = orient2d(&a, &b, point);
+            if o == 0.0
+                && point.get_x() >= a.get_x().min(b.get_x())
+                && point.get_x() <= a.get_x().max(b.get_x())
+                && point.get_y() >= a.get_y().min(b.get_y())
+                && point.get_y() <= a.get_y().max(b.get_y())
+            {
+                return PointLocation::Boundary;
+            }
+            let (ya, yb, y) = (a.get_y(), b.get_y(), point.get_y());
+            if (ya <= y && y < yb && o > 0.0) || (yb <= y && y < ya && o < 0.0) {
+                inside = !inside;
+            }
+        }
+    }
+    if inside {
+        PointLocation::Inside
+    } else {
+        PointLocation::Outside
+    }
+}
+
+pub fn boolean(subject: &[Polygon2d], clip: &[Polygon2d], op: BooleanOp) -> Vec<Polygon2d> {
+    let rings = |polygons: &[Polygon2d]| -> Vec<Vec<Point2d>> {
+        polygons.iter().flat_map(|p| p.rings().cloned()).collect()
+    };
+    let overlay = Overlay::new(&[rings(subject), rings(clip)]);
+    overlay.extract(|w| {
+        let (a, b) = (w[0] != 0, w[1] != 0);
+        match op {
+            BooleanOp::Union => a || b,
+            BooleanOp::Intersection => a && b,
+            BooleanOp::Difference => a && !b,
+            BooleanOp::Xor => a != b,
+        }
+    })
+}
+
+// Grows the polygons by delta, or shrinks them for negative delta, and
+// merges whatever overlaps.
+pub fn offset(polygons: &[Polygon2d], delta: f64, join: Join) -> Vec<Polygon2d> {
+    let rings: Vec<Vec<Point2d>> = polygons
+        .iter()
+        .flat_map(|p| p.rings())
+        .filter(|r| r.len() >= 3)
+        .map(|r| offset_ring(r, delta, join))
+        .collect();
+    // The raw offset rings loop back on themselves at concave corners; the
+    // positive winding fill removes those loops.
+    Overlay::new(&[rings]).extract(|w| w[0] > 0)
+}
+
+fn offset_ring(ring: &[Point2d], delta: f64, join: Join) -> Vec<Point2d> {
+    let n = ring.len();
+    let xy = |p: &Point2d| [p.get_x(), p.get_y()];
+    let at =
+        |p: [f64; 2], d: [f64; 2], s: f64| Point2d::from_coords(p[0] + s * d[0], p[1] + s * d[1]);
+    // Unit direction and outward normal of each edge.
+    let edges: Vec<([f64; 2], [f64; 2])> = (0..n)
+        .map(|i| {
+            let (a, b) = (xy(&ring[i]), xy(&ring[(i + 1) % n]));
+            let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+            let length = (dx * dx + dy * dy).sqrt();
+            let e = [dx / length, dy / length];
+            (e, [e[1], -e[0]])
+        })
+        .collect();
+    let mut out = Vec::new();
+    for i in 0..n {
+        let p = xy(&ring[i]);
+        let (e1, n1) = edges[(i + n - 1) % n];
+        let (e2, n2) = edges[i];
+        let turn = e1[0] * e2[1] - e1[1] * e2[0];
+        let dot = n1[0] * n2[0] + n1[1] * n2[1];
+        if turn * delta < 0.0 {
+            // Concave corner: the offset edges cross, loop through the vertex.
+            out.extend([at(p, n1, delta), ring[i], at(p, n2, delta)]);
+            continue;
+        }
+        if turn == 0.0 && dot > 0.0 {
+            out.push(at(p, n1, delta));
+            continue;
+        }
+        match join {
+            Join::Miter(limit) if (2.0 / (1.0 + dot)).sqrt() <= limit => {
+                let s = delta / (1.0 + dot);
+                out.push(Point2d::from_coords(
+                    p[0] + s * (n1[0] + n2[0]),
+                    p[1] + s * (n1[1] + n2[1]),
+                ));
+            }
+            Join::Round => {
+                let radius = delta.abs();
+                let sign = delta.signum();
+                let (d1, d2) = (n1.map(|v| sign * v), n2.map(|v| sign * v));
+                let mut sweep =
+                    (d1[0] * d2[1] - d1[1] * d2[0]).atan2(d1[0] * d2[0] + d1[1] * d2[1]);
+                if turn == 0.0 {
+                    sweep = std::f64::consts::PI * sign;
+                }
+                let step = 2.0 * (1.0 - ARC_TOLERANCE).acos();
+                let count = (sweep.abs() / step).ceil().max(1.0) as usize;
+                let start = d1[1].atan2(d1[0]);
+                for k in 0..=count {
+                    let angle = start + sweep * k as f64 / count as f64;
+                    out.push(at(p, [angle.cos(), angle.sin()], radius));
+                }
+            }
+            Join::Miter(limit) if 1.0 + dot > 1e-12 => {
+                // Cut the miter perpendicular to the bisector at the limit.
+                let c = ((1.0 + dot) / 2.0).sqrt();
+                let m = [
+                    delta.signum() * (n1[0] + n2[0]) / (2.0 * c),
+                    delta.signum() * (n1[1] + n2[1]) / (2.0 * c),
+                ];
+                let reach = (limit - c) * delta.abs();
+                let t1 = reach / (e1[0] * m[0] + e1[1] * m[1]);
+                let t2 = reach / (e2[0] * m[0] + e2[1] * m[1]);
+                out.push(Point2d::from_coords(
+                    p[0] + delta * n1[0] + t1 * e1[0],
+                    p[1] + delta * n1[1] + t1 * e1[1],
+                ));
+                out.push(Point2d::from_coords(
+                    p[0] + delta * n2[0] + t2 * e2[0],
+                    p[1] + delta * n2[1] + t2 * e2[1],
+                ));
+            }
+            _ => {
+                let s = delta.abs();
+                out.push(Point2d::from_coords(
+                    p[0] + delta * n1[0] + s * e1[0],
+                    p[1] + delta * n1[1] + s * e1[1],
+                ));
+                out.push(Point2d::from_coords(
+                    p[0] + delta * n2[0] - s * e2[0],
+                    p[1] + delta * n2[1] - s * e2[1],
+                ));
+            }
+        }
+    }
+    out
+}
+
+// Undirected edge of the arrangement with the net number of times each
+// operand runs along it from a to b.
+struct Segment {
+    a: usize,
+    b: usize,
+    net: Vec<i32>,
+}
+
+// Planar arrangement of the edges of several operands, split at all their
+// intersections. Vertices closer than the snapping tolerance are merged and
+// edges are split at every vertex within the tolerance of them, so nearly
+// coincident edges of different operands become shared segments.
+struct Overlay {
+    points: Vec<Point2d>,
+    segments: Vec<Segment>,
+    tolerance: f64,
+}
+
+// Vertices on a grid of the snapping tolerance, each merged with any
+// earlier vertex within the tolerance.
+struct Snap {
+    tolerance: f64,
+    points: Vec<Point2d>,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl Snap {
+    fn cell(&self, p: &Point2d) -> (i64, i64) {
+        (
+            (p.get_x() / self.tolerance).floor() as i64,
+            (p.get_y() / self.tolerance).floor() as i64,
+        )
+    }
+
+    fn vertex(&mut self, p: Point2d) -> usize {
+        let (cx, cy) = self.cell(&p);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for &v in self.cells.get(&(cx + dx, cy + dy)).into_iter().flatten() {
+                    let q = self.points[v];
+                    if (q.get_x() - p.get_x()).abs() <= self.tolerance
+                        && (q.get_y() - p.get_y()).abs() <= self.tolerance
+                    {
+                        return v;
+                    }
+                }
+            }
+        }
+        self.points.push(p);
+        self.cells
+            .entry((cx, cy))
+            .or_default()
+            .push(self.points.len() - 1);
+        self.points.len() - 1
+    }
+}
+
+impl Overlay {
+    fn new(operands: &[Vec<Vec<Point2d>>]) -> Self {
+        let scale = operands
+            .iter()
+            .flatten()
+            .flatten()
+            .fold(0.0f64, |m, p| m.max(p.get_x().abs()).max(p.get_y().abs()));
+        let mut snap = Snap {
+            tolerance: (SNAP_TOLERANCE * scale).max(f64::MIN_POSITIVE),
+            points: Vec::new(),
+            cells: HashMap::new(),
+        };
+        let tolerance = snap.tolerance;
+        // Raw edges between snapped vertices, with their operand.
+        let mut raw: Vec<(usize, usize, usize)> = Vec::new();
+        for (k, rings) in operands.iter().enumerate() {
+            for ring in rings {
+                let ids: Vec<usize> = ring.iter().map(|p| snap.vertex(*p)).collect();
+                raw.extend(
+                    (0..ids.len())
+                        .map(|i| (ids[i], ids[(i + 1) % ids.len()], k))
+                        .filter(|(a, b, _)| a != b),
+                );
+            }
+        }
+        let mut splits: Vec<Vec<usize>> = raw.iter().map(|&(a, b, _)| vec![a, b]).collect();
+        // Sweep over x to find the pairs whose bounding boxes, grown by the
+        // tolerance, overlap.
+        let bounds = |i: usize, points: &[Point2d]| {
+            let (a, b) = (points[raw[i].0], points[raw[i].1]);
+            (
+                a.get_x().min(b.get_x()) - tolerance,
+                a.get_x().max(b.get_x()) + tolerance,
+                a.get_y().min(b.get_y()) - tolerance,
+                a.get_y().max(b.get_y()) + tolerance,
+            )
+        };
+        let mut order: Vec<usize> = (0..raw.len()).collect();
+        order.sort_by(|&i, &j| {
+            bounds(i, &snap.points)
+                .0
+                .total_cmp(&bounds(j, &snap.points).0)
+        });
+        for (n, &i) in order.iter().enumerate() {
+            let bi = bounds(i, &snap.points);
+            for &j in order[n + 1..].iter() {
+                let bj = bounds(j, &snap.points);
+                if bj.0 > bi.1 {
+                    break;
+                }
+                if bj.2 > bi.3 || bj.3 < bi.2 {
+                    continue;
+                }
+                let (p, q) = ((raw[i].0, raw[i].1), (raw[j].0, raw[j].1));
+                // Endpoints on or next to the other edge.
+                for (edge, other, into) in [(p, q, i), (q, p, j)] {
+                    for c in [other.0, other.1] {
+                        if c != edge.0
+                            && c != edge.1
+                            && near(
+                                &snap.points[edge.0],
+                                &snap.points[edge.1],
+                                &snap.points[c],
+                                tolerance,
+                            )
+                        {
+                            splits[into].push(c);
+                        }
+                    }
+                }
+                if let Some(x) = crossing(&snap.points, p, q) {
+                    let v = snap.vertex(x);
+                    splits[i].push(v);
+                    splits[j].push(v);
+                }
+            }
+        }
+        let points = snap.points;
+        let mut segments: Vec<Segment> = Vec::new();
+        let mut keys: HashMap<(usize, usize), usize> = HashMap::new();
+        for (i, mut vertices) in splits.into_iter().enumerate() {
+            let (a, b, k) = raw[i];
+            let (pa, pb) = (points[a], points[b]);
+            let (dx, dy) = (pb.get_x() - pa.get_x(), pb.get_y() - pa.get_y());
+            let t = |v: &usize| {
+                let p = points[*v];
+                (p.get_x() - pa.get_x()) * dx + (p.get_y() - pa.get_y()) * dy
+            };
+            vertices.sort_by(|u, v| t(u).total_cmp(&t(v)).then(u.cmp(v)));
+            vertices.dedup();
+            for w in vertices.windows(2) {
+                let (u, v) = (w[0], w[1]);
+                let s = *keys.entry((u.min(v), u.max(v))).or_insert_with(|| {
+                    segments.push(Segment {
+                        a: u.min(v),
+                        b: u.max(v),
+                        net: vec![0; operands.len()],
+                    });
+                    segments.len() - 1
+                });
+                segments[s].net[k] += if u < v { 1 } else { -1 };
+            }
+        }
+        // Edges run along in both directions equally often separate equal
+        // regions.
+        segments.retain(|s| s.net.iter().any(|&n| n != 0));
+        Overlay {
+            points,
+            segments,
+            tolerance,
+        }
+    }
+
+    // Winding numbers of each operand on the left and right of every
+    // segment. The faces of the arrangement are traced from the half-edges,
+    // the face around each connected part gets its winding from a ray cast
+    // against the other parts, and windings then step across the edges.
+    fn windings(&self) -> Vec<(Vec<i32>, Vec<i32>)> {
+        let n = self.points.len();
+        let origin = |h: usize| {
+            let s = &self.segments[h / 2];
+            if h.is_multiple_of(2) { s.a } else { s.b }
+        };
+        let net = |h: usize| -> Vec<i32> {
+            let sign = if h.is_multiple_of(2) { 1 } else { -1 };
+            self.segments[h / 2].net.iter().map(|n| sign * n).collect()
+        };
+        // Outgoing half-edges of each vertex, counterclockwise.
+        let mut around: Vec<Vec<usize>> = vec![Vec::new(); n];
+        for h in 0..2 * self.segments.len() {
+            around[origin(h)].push(h);
+        }
+        let mut position = vec![0; 2 * self.segments.len()];
+        for (v, list) in around.iter_mut().enumerate() {
+            list.sort_by(|&g, &h| {
+                let angle = |h: usize| {
+                    let d = self.direction(v, origin(h ^ 1));
+                    d[1].atan2(d[0])
+                };
+                angle(g).total_cmp(&angle(h))
+            });
+            for (i, &h) in list.iter().enumerate() {
+                position[h] = i;
+            }
+        }
+        // The next half-edge around the face on the left leaves the end
+        // vertex first clockwise from the way back.
+        let next = |h: usize| {
+            let list = &around[origin(h ^ 1)];
+            list[(position[h ^ 1] + list.len() - 1) % list.len()]
+        };
+        let mut face = vec![usize::MAX; 2 * self.segments.len()];
+        let mut faces: Vec<(Vec<usize>, f64)> = Vec::new();
+        for first in 0..face.len() {
+            if face[first] != usize::MAX {
+                continue;
+            }
+            let (mut edges, mut area) = (Vec::new(), 0.0);
+            let mut h = first;
+            while face[h] == usize::MAX {
+                face[h] = faces.len();
+                let (p, q) = (self.points[origin(h)], self.points[origin(h ^ 1)]);
+                area += p.get_x() * q.get_y() - q.get_x() * p.get_y();
+                edges.push(h);
+                h = next(h);
+            }
+            faces.push((edges, area));
+        }
+        // Connected parts of the arrangement.
+        let mut part = vec![usize::MAX; n];
+        let mut parts = 0;
+        for v in 0..n {
+            if part[v] != usize::MAX || around[v].is_empty() {
+                continue;
+            }
+            let mut stack = vec![v];
+            part[v] = parts;
+            while let Some(u) = stack.pop() {
+                for &h in around[u].iter() {
+                    let w = origin(h ^ 1);
+                    if part[w] == usize::MAX {
+                        part[w] = parts;
+                        stack.push(w);
+                    }
+                }
+            }
+            parts += 1;
+        }
+        // The face around each part is the one traced clockwise, with the
+        // least signed area.
+        let mut outside: Vec<Option<usize>> = vec![None; parts];
+        for (f, (edges, area)) in faces.iter().enumerate() {
+            let c = part[origin(edges[0])];
+            if outside[c].is_none_or(|g| *area < faces[g].1) {
+                outside[c] = Some(f);
+            }
+        }
+        let mut winding: Vec<Option<Vec<i32>>> = vec![None; faces.len()];
+        for (c, f) in outside.iter().enumerate() {
+            let Some(f) = *f else { continue };
+            let p = self.points[origin(faces[f].0[0])];
+            let mut w = vec![0; self.segments.first().map_or(0, |s| s.net.len())];
+            for other in self.segments.iter().filter(|s| part[s.a] != c) {
+                let crossing = ray_crossing(&self.points[other.a], &self.points[other.b], &p);
+                if crossing != 0 {
+                    for (wk, nk) in w.iter_mut().zip(other.net.iter()) {
+                        *wk += crossing * nk;
+                    }
+                }
+            }
+            winding[f] = Some(w);
+            let mut queue = vec![f];
+            while let Some(f) = queue.pop() {
+                let left = winding[f].clone().unwrap_or_default();
+                for &h in faces[f].0.iter() {
+                    let g = face[h ^ 1];
+                    if winding[g].is_none() {
+                        winding[g] = Some(left.iter().zip(net(h)).map(|(w, n)| w - n).collect());
+                        queue.push(g);
+                    }
+                }
+            }
+        }
+        (0..self.segments.len())
+            .map(|s| {
+                let side = |h: usize| winding[face[h]].clone().unwrap_or_default();
+                (side(2 * s), side(2 * s + 1))
+            })
+            .collect()
+    }
+
+    // Boundary of the region where fill holds, as polygons.
+    fn extract(&self, fill: impl Fn(&[i32]) -> bool) -> Vec<Polygon2d> {
+        // Directed edges with the region on their left.
+        let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut edges: Vec<(usize, usize)> = Vec::new();
+        for (s, (left, right)) in self.windings().iter().enumerate() {
+            let (left, right) = (fill(left), fill(right));
+            if left == right {
+                continue;
+            }
+            let Segment { a, b, .. } = self.segments[s];
+            let edge = if left { (a, b) } else { (b, a) };
+            outgoing.entry(edge.0).or_default().push(edges.len());
+            edges.push(edge);
+        }
+        let mut used = vec![false; edges.len()];
+        let mut rings = Vec::new();
+        for first in 0..edges.len() {
+            if used[first] {
+                continue;
+            }
+            let mut ring = Vec::new();
+            let mut e = first;
+            loop {
+                used[e] = true;
+                let (u, v) = edges[e];
+                ring.push(u);
+                // Leave v by the first unused edge clockwise from the way
+                // back, which keeps the region on the left minimal.
+                let back = self.direction(v, u);
+                let next = outgoing
+                    .get(&v)
+                    .into_iter()
+                    .flatten()
+                    .copied()
+                    .filter(|&n| !used[n])
+                    .min_by(|&n, &m| {
+                        let cw = |n: usize| {
+                            let d = self.direction(v, edges[n].1);
+                            let angle = (d[0] * back[1] - d[1] * back[0])
+                                .atan2(d[0] * back[0] + d[1] * back[1]);
+                            if angle <= 0.0 {
+                                angle + 2.0 * std::f64::consts::PI
+                            } else {
+                                angle
+                            }
+                        };
+                        cw(n).total_cmp(&cw(m))
+                    });
+                match next {
+                    Some(n) => e = n,
+                    None => break,
+                }
+            }
+            let points: Vec<Point2d> = simplify(
+                ring.iter().map(|&v| self.points[v]).collect(),
+                self.tolerance,
+            );
+            if points.len() >= 3 {
+                rings.push(points);
+            }
+        }
+        assemble(rings)
+    }
+
+    fn direction(&self, from: usize, to: usize) -> [f64; 2] {
+        let (p, q) = (self.points[from], self.points[to]);
+        [q.get_x() - p.get_x(), q.get_y() - p.get_y()]
+    }
+}
+
+// Whether c lies within the tolerance of the segment from a to b.
+fn near(a: &Point2d, b: &Point2d, c: &Point2d, tolerance: f64) -> bool {
+    let (dx, dy) = (b.get_x() - a.get_x(), b.get_y() - a.get_y());
+    let (cx, cy) = (c.get_x() - a.get_x(), c.get_y() - a.get_y());
+    let t = ((cx * dx + cy * dy) / (dx * dx + dy * dy)).clamp(0.0, 1.0);
+    (cx - t * dx).abs() <= tolerance && (cy - t * dy).abs() <= tolerance
+}
+
+// Proper crossing of the segments between the given vertices, decided
+// exactly and rounded into both bounding boxes.
+fn crossing(
+    points: &[Point2d],
+    (p1, p2): (usize, usize),
+    (q1, q2): (usize, usize),
+) -> Option<Point2d> {
+    let (p1, p2, q1, q2) = (points[p1], points[p2], points[q1], points[q2]);
+    let o1 = orient2d(&p1, &p2, &q1);
+    let o2 = orient2d(&p1, &p2, &q2);
+    let o3 = orient2d(&q1, &q2, &p1);
+    let o4 = orient2d(&q1, &q2, &p2);
+    if o1 * o2 >= 0.0 || o3 * o4 >= 0.0 {
+        return None;
+    }
+    let t = o3 / (o3 - o4);
+    let clamp = |v: f64, a: f64, b: f64, c: f64, d: f64| {
+        v.clamp(a.min(b).max(c.min(d)), a.max(b).min(c.max(d)))
+    };
+    Some(Point2d::from_coords(
+        clamp(
+            p1.get_x() + t * (p2.get_x() - p1.get_x()),
+            p1.get_x(),
+            p2.get_x(),
+            q1.get_x(),
+            q2.get_x(),
+        ),
+        clamp(
+            p1.get_y() + t * (p2.get_y() - p1.get_y()),
+            p1.get_y(),
+            p2.get_y(),
+            q1.get_y(),
+            q2.get_y(),
+        ),
+    ))
+}
+
+// Signed crossing of the segment from a to b by the ray from m towards +x,
+// half open in y so that a ray through a vertex counts once.
+fn ray_crossing(a: &Point2d, b: &Point2d, m: &Point2d) -> i32 {
+    let y = m.get_y();
+    if a.get_y() <= y && y < b.get_y() && orient2d(a, b, m) > 0.0 {
+        1
+    } else if b.get_y() <= y && y < a.get_y() && orient2d(a, b, m) < 0.0 {
+        -1
+    } else {
+        0
+    }
+}
+
+// Removes vertices where the ring runs straight on, up to the tolerance, or
+// doubles back.
+fn simplify(mut ring: Vec<Point2d>, tolerance: f64) -> Vec<Point2d> {
+    let mut changed = true;
+    while changed && ring.len() >= 3 {
+        changed = false;
+        let mut i = 0;
+        while i < ring.len() && ring.len() >= 3 {
+            let n = ring.len();
+            let (a, b, c) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+            if orient2d(&a, &b, &c) == 0.0 || near(&a, &c, &b, tolerance) {
+                ring.remove(i);
+                changed = true;
+            } else {
+                i += 1;
+            }
+        }
+    }
+    ring
+}
+
+// Groups counterclockwise outer rings with the clockwise holes they
+// enclose, each hole going to the smallest enclosing ring.
+fn assemble(rings: Vec<Vec<Point2d>>) -> Vec<Polygon2d> {
+    let (mut outers, holes): (Vec<Vec<Point2d>>, Vec<Vec<Point2d>>) =
+        rings.into_iter().partition(|r| is_counterclockwise(r));
+    outers.sort_by(|a, b| signed_area(a).total_cmp(&signed_area(b)));
+    let mut polygons: Vec<Polygon2d> = outers
+        .into_iter()
+        .map(|outer| Polygon2d {
+            outer,
+            holes: Vec::new(),
+        })
+        .collect();
+    for hole in holes {
+        let encloses = |outer: &[Point2d]| {
+            let edge_midpoints = ring_edges(&hole).map(|(a, b)| {
+                Point2d::from_coords(0.5 * (a.get_x() + b.get_x()), 0.5 * (a.get_y() + b.get_y()))
+            });
+            hole.iter()
+                .copied()
+                .chain(edge_midpoints)
+                .map(|p| locate(&p, std::iter::once(outer)))
+                .find(|&l| l != PointLocation::Boundary)
+                == Some(PointLocation::Inside)
+        };
+        if let Some(polygon) = polygons.iter_mut().find(|p| encloses(&p.outer)) {
+            polygon.holes.push(hole);
+        }
+    }
+    polygons
+}
@@ -0,0 +1,358 @@
+use crate::{Point2d, Point3d};
+
+// Filtered exact orientation and in-circle tests, using the error bounds and
+// floating-point expansions of Shewchuk, "Adaptive Precision Floating-Point
+// Arithmetic and Fast Robust Geometric Predicates" (1997), but not his
+// intermediate adaptive stages.
+//
+// Every predicate first evaluates its determinant in plain floating point and
+// accepts the result when it exceeds a forward error bound. Uncertain cases
+// are evaluated exactly with expansions in one step. The sign of the returned
+// value is always exact; the magnitude is an approximation.
+
+const EPSILON: f64 = f64::EPSILON * 0.5;
+const CCW_ERRBOUND: f64 = (3.0 + 16.0 * EPSILON) * EPSILON;
+const O3D_ERRBOUND: f64 = (7.0 + 56.0 * EPSILON) * EPSILON;
+const ICC_ERRBOUND: f64 = (10.0 + 96.0 * EPSILON) * EPSILON;
+const ISP_ERRBOUND: f64 = (16.0 + 224.0 * EPSILON) * EPSILON;
+
+#[inline]
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let x = a + b;
+    let bv = x - a;
+    let av = x - bv;
+    (x, (a - av) + (b - bv))
+}
+
+#[inline]
+fn two_diff(a: f64, b: f64) -> (f64, f64) {
+    let x = a - b;
+    let bv = a - x;
+    let av = x + bv;
+    (x, (a - av) + (bv - b))
+}
+
+#[inline]
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let x = a * b;
+    (x, a.mul_add(b, -x))
+}
+
+// Expansions are sums of non-overlapping components ordered by increasing
+// magnitude, with zero components removed.
+type Expansion = Vec<f64>;
+
+fn grow(e: &[f64], b: f64) -> Expansion {
+    let mut h = Vec::with_capacity(e.len() + 1);
+    let mut q = b;
+    for &ei in e {
+        let (sum, err) = two_sum(q, ei);
+        if err != 0.0 {
+            h.push(err);
+        }
+        q = sum;
+    }
+    if q != 0.0 || h.is_empty() {
+        h.push(q);
+    }
+    h
+}
+
+fn sum(e: &[f64], f: &[f64]) -> Expansion {
+    f.iter().fold(e.to_vec(), |h, &fi| grow(&h, fi))
+}
+
+fn scale(e: &[f64], b: f64) -> Expansion {
+    let mut h = Vec::with_capacity(2 * e.len());
+    let mut q = 0.0;
+    for &ei in e {
+        let (product, product_err) = two_product(ei, b);
+        let (sum, err) = two_sum(q, product_err);
+        if err != 0.0 {
+            h.push(err);
+        }
+        let (next, err) = two_sum(product, sum);
+        if err != 0.0 {
+            h.push(err);
+        }
+        q = next;
+    }
+    if q != 0.0 || h.is_empty() {
+        h.push(q);
+    }
+    h
+}
+
+fn mul(e: &[f64], f: &[f64]) -> Expansion {
+    f.iter().fold(vec![0.0], |h, &fi| sum(&h, &scale(e, fi)))
+}
+
+fn neg(e: &[f64]) -> Expansion {
+    e.iter().map(|v| -v).collect()
+}
+
+fn diff(a: f64, b: f64) -> Expansion {
+    let (x, y) = two_diff(a, b);
+    if y == 0.0 { vec![x] } else { vec![y, x] }
+}
+
+// Summing from the smallest component keeps the sign of the largest one.
+fn estimate(e: &[f64]) -> f64 {
+    e.iter().sum()
+}
+
+fn det2(a: &[f64], b: &[f64], c: &[f64], d: &[f64]) -> Expansion {
+    sum(&mul(a, b), &neg(&mul(c, d)))
+}
+
+// Filtered orient2d: `Some` when the floating-point value is known to have
+// the correct sign.
+pub fn orient2d_filter(a: &Point2d, b: &Point2d, c: &Point2d) -> Option<f64> {
+    let left = (a.get_x() - c.get_x()) * (b.get_y() - c.get_y());
+    let right = (a.get_y() - c.get_y()) * (b.get_x() - c.get_x());
+    let det = left - right;
+    let detsum = if left > 0.0 {
+        if right <= 0.0 {
+            return Some(det);
+        }
+        left + right
+    } else if left < 0.0 {
+        if right >= 0.0 {
+            return Some(det);
+        }
+        -left - right
+    } else {
+        return Some(det);
+    };
+    let bound = CCW_ERRBOUND * detsum;
+    if det >= bound || -det >= bound {
+        Some(det)
+    } else {
+        None
+    }
+}
+
+fn orient2d_exact(a: &Point2d, b: &Point2d, c: &Point2d) -> f64 {
+    let acx = diff(a.get_x(), c.get_x());
+    let acy = diff(a.get_y(), c.get_y());
+    let bcx = diff(b.get_x(), c.get_x());
+    let bcy = diff(b.get_y(), c.get_y());
+    estimate(&det2(&acx, &bcy, &acy, &bcx))
+}
+
+// Positive when a, b, c are in counterclockwise order, negative when
+// clockwise and zero when they are collinear.
+pub fn orient2d(a: &Point2d, b: &Point2d, c: &Point2d) -> f64 {
+    orient2d_filter(a, b, c).unwrap_or_else(|| orient2d_exact(a, b, c))
+}
+
+pub fn orient3d_filter(a: &Point3d, b: &Point3d, c: &Point3d, d: &Point3d) -> Option<f64> {
+    let (adx, ady, adz) = (
+        a.get_x() - d.get_x(),
+        a.get_y() - d.get_y(),
+        a.get_z() - d.get_z(),
+    );
+    let (bdx, bdy, bdz) = (
+        b.get_x() - d.get_x(),
+        b.get_y() - d.get_y(),
+        b.get_z() - d.get_z(),
+    );
+    let (cdx, cdy, cdz) = (
+        c.get_x() - d.get_x(),
+        c.get_y() - d.get_y(),
+        c.get_z() - d.get_z(),
+    );
+    let (bdxcdy, cdxbdy) = (bdx * cdy, cdx * bdy);
+    let (cdxady, adxcdy) = (cdx * ady, adx * cdy);
+    let (adxbdy, bdxady) = (adx * bdy, bdx * ady);
+    let det = adz * (bdxcdy - cdxbdy) + bdz * (cdxady - adxcdy) + cdz * (adxbdy - bdxady);
+    let permanent = (bdxcdy.abs() + cdxbdy.abs()) * adz.abs()
+        + (cdxady.abs() + adxcdy.abs()) * bdz.abs()
+        + (adxbdy.abs() + bdxady.abs()) * cdz.abs();
+    let bound = O3D_ERRBOUND * permanent;
+    if det > bound || -det > bound {
+        Some(det)
+    } else {
+        None
+    }
+}
+
+fn orient3d_exact(a: &Point3d, b: &Point3d, c: &Point3d, d: &Point3d) -> f64 {
+    let (adx, ady, adz) = (
+        diff(a.get_x(), d.get_x()),
+        diff(a.get_y(), d.get_y()),
+        diff(a.get_z(), d.get_z()),
+    );
+    let (bdx, bdy, bdz) = (
+        diff(b.get_x(), d.get_x()),
+        diff(b.get_y(), d.get_y()),
+        diff(b.get_z(), d.get_z()),
+    );
+    let (cdx, cdy, cdz) = (
+        diff(c.get_x(), d.get_x()),
+        diff(c.get_y(), d.get_y()),
+        diff(c.get_z(), d.get_z()),
+    );
+    let bc = det2(&bdx, &cdy, &cdx, &bdy);
+    let ca = det2(&cdx, &ady, &adx, &cdy);
+    let ab = det2(&adx, &bdy, &bdx, &ady);
+    let det = sum(&sum(&mul(&adz, &bc), &mul(&bdz, &ca)), &mul(&cdz, &ab));
+    estimate(&det)
+}
+
+// Positive when d lies below the plane through a, b, c, where below means
+// that a, b, c appear counterclockwise when viewed from above; zero when the
+// four points are coplanar.
+pub fn orient3d(a: &Point3d, b: &Point3d, c: &Point3d, d: &Point3d) -> f64 {
+    orient3d_filter(a, b, c, d).unwrap_or_else(|| orient3d_exact(a, b, c, d))
+}
+
+pub fn incircle_filter(a: &Point2d, b: &Point2d, c: &Point2d, d: &Point2d) -> Option<f64> {
+    let (adx, ady) = (a.get_x() - d.get_x(), a.get_y() - d.get_y());
+    let (bdx, bdy) = (b.get_x() - d.get_x(), b.get_y() - d.get_y());
+    let (cdx, cdy) = (c.get_x() - d.get_x(), c.get_y() - d.get_y());
+    let (bdxcdy, cdxbdy) = (bdx * cdy, cdx * bdy);
+    let (cdxady, adxcdy) = (cdx * ady, adx * cdy);
+    let (adxbdy, bdxady) = (adx * bdy, bdx * ady);
+    let alift = adx * adx + ady * ady;
+    let blift = bdx * bdx + bdy * bdy;
+    let clift = cdx * cdx + cdy * cdy;
+    let det = alift * (bdxcdy - cdxbdy) + blift * (cdxady - adxcdy) + clift * (adxbdy - bdxady);
+    let permanent = (bdxcdy.abs() + cdxbdy.abs()) * alift
+        + (cdxady.abs() + adxcdy.abs()) * blift
+        + (adxbdy.abs() + bdxady.abs()) * clift;
+    let bound = ICC_ERRBOUND * permanent;
+    if det > bound || -det > bound {
+        Some(det)
+    } else {
+        None
+    }
+}
+
+fn incircle_exact(a: &Point2d, b: &Point2d, c: &Point2d, d: &Point2d) -> f64 {
+    let (adx, ady) = (diff(a.get_x(), d.get_x()), diff(a.get_y(), d.get_y()));
+    let (bdx, bdy) = (diff(b.get_x(), d.get_x()), diff(b.get_y(), d.get_y()));
+    let (cdx, cdy) = (diff(c.get_x(), d.get_x()), diff(c.get_y(), d.get_y()));
+    let alift = sum(&mul(&adx, &adx), &mul(&ady, &ady));
+    let blift = sum(&mul(&bdx, &bdx), &mul(&bdy, &bdy));
+    let clift = sum(&mul(&cdx, &cdx), &mul(&cdy, &cdy));
+    let bc = det2(&bdx, &cdy, &cdx, &bdy);
+    let ca = det2(&cdx, &ady, &adx, &cdy);
+    let ab = det2(&adx, &bdy, &bdx, &ady);
+    let det = sum(
+        &sum(&mul(&alift, &bc), &mul(&blift, &ca)),
+        &mul(&clift, &ab),
+    );
+    estimate(&det)
+}
+
+// Positive when d lies inside the circle through a, b, c given in
+// counterclockwise order, negative outside and zero when the four points are
+// cocircular. The sign is reversed for clockwise a, b, c.
+pub fn incircle(a: &Point2d, b: &Point2d, c: &Point2d, d: &Point2d) -> f64 {
+    incircle_filter(a, b, c, d).unwrap_or_else(|| incircle_exact(a, b, c, d))
+}
+
+pub fn insphere_filter(
+    a: &Point3d,
+    b: &Point3d,
+    c: &Point3d,
+    d: &Point3d,
+    e: &Point3d,
+) -> Option<f64> {
+    let rel = |p: &Point3d| {
+        (
+            p.get_x() - e.get_x(),
+            p.get_y() - e.get_y(),
+            p.get_z() - e.get_z(),
+        )
+    };
+    let (aex, aey, aez) = rel(a);
+    let (bex, bey, bez) = rel(b);
+    let (cex, cey, cez) = rel(c);
+    let (dex, dey, dez) = rel(d);
+    let (aexbey, bexaey) = (aex * bey, bex * aey);
+    let (bexcey, cexbey) = (bex * cey, cex * bey);
+    let (cexdey, dexcey) = (cex * dey, dex * cey);
+    let (dexaey, aexdey) = (dex * aey, aex * dey);
+    let (aexcey, cexaey) = (aex * cey, cex * aey);
+    let (bexdey, dexbey) = (bex * dey, dex * bey);
+    let ab = aexbey - bexaey;
+    let bc = bexcey - cexbey;
+    let cd = cexdey - dexcey;
+    let da = dexaey - aexdey;
+    let ac = aexcey - cexaey;
+    let bd = bexdey - dexbey;
+    let abc = aez * bc - bez * ac + cez * ab;
+    let bcd = bez * cd - cez * bd + dez * bc;
+    let cda = cez * da + dez * ac + aez * cd;
+    let dab = dez * ab + aez * bd + bez * da;
+    let alift = aex * aex + aey * aey + aez * aez;
+    let blift = bex * bex + bey * bey + bez * bez;
+    let clift = cex * cex + cey * cey + cez * cez;
+    let dlift = dex * dex + dey * dey + dez * dez;
+    let det = (dlift * abc - clift * dab) + (blift * cda - alift * bcd);
+
+    let (aezp, bezp, cezp, dezp) = (aez.abs(), bez.abs(), cez.abs(), dez.abs());
+    let ab_p = aexbey.abs() + bexaey.abs();
+    let bc_p = bexcey.abs() + cexbey.abs();
+    let cd_p = cexdey.abs() + dexcey.abs();
+    let da_p = dexaey.abs() + aexdey.abs();
+    let ac_p = aexcey.abs() + cexaey.abs();
+    let bd_p = bexdey.abs() + dexbey.abs();
+    let permanent = (cd_p * bezp + bd_p * cezp + bc_p * dezp) * alift
+        + (da_p * cezp + ac_p * dezp + cd_p * aezp) * blift
+        + (ab_p * dezp + bd_p * aezp + da_p * bezp) * clift
+        + (bc_p * aezp + ac_p * bezp + ab_p * cezp) * dlift;
+    let bound = ISP_ERRBOUND * permanent;
+    if det > bound || -det > bound {
+        Some(det)
+    } else {
+        None
+    }
+}
+
+fn insphere_exact(a: &Point3d, b: &Point3d, c: &Point3d, d: &Point3d, e: &Point3d) -> f64 {
+    let rel = |p: &Point3d| {
+        (
+            diff(p.get_x(), e.get_x()),
+            diff(p.get_y(), e.get_y()),
+            diff(p.get_z(), e.get_z()),
+        )
+    };
+    let (aex, aey, aez) = rel(a);
+    let (bex, bey, bez) = rel(b);
+    let (cex, cey, cez) = rel(c);
+    let (dex, dey, dez) = rel(d);
+    let ab = det2(&aex, &bey, &bex, &aey);
+    let bc = det2(&bex, &cey, &cex, &bey);
+    let cd = det2(&cex, &dey, &dex, &cey);
+    let da = det2(&dex, &aey, &aex, &dey);
+    let ac = det2(&aex, &cey, &cex, &aey);
+    let bd = det2(&bex, &dey, &dex, &bey);
+    let abc = sum(
+        &sum(&mul(&aez, &bc), &neg(&mul(&bez, &ac))),
+        &mul(&cez, &ab),
+    );
+    let bcd = sum(
+        &sum(&mul(&bez, &cd), &neg(&mul(&cez, &bd))),
+        &mul(&dez, &bc),
+    );
+    let cda = sum(&sum(&mul(&cez, &da), &mul(&dez, &ac)), &mul(&aez, &cd));
+    let dab = sum(&sum(&mul(&dez, &ab), &mul(&aez, &bd)), &mul(&bez, &da));
+    let lift = |x: &[f64], y: &[f64], z: &[f64]| sum(&sum(&mul(x, x), &mul(y, y)), &mul(z, z));
+    let alift = lift(&aex, &aey, &aez);
+    let blift = lift(&bex, &bey, &bez);
+    let clift = lift(&cex, &cey, &cez);
+    let dlift = lift(&dex, &dey, &dez);
+    let left = sum(&mul(&dlift, &abc), &neg(&mul(&clift, &dab)));
+    let right = sum(&mul(&blift, &cda), &neg(&mul(&alift, &bcd)));
+    estimate(&sum(&left, &right))
+}
+
+// Positive when e lies inside the sphere through a, b, c, d, which must be
+// ordered so that orient3d(a, b, c, d) is positive; negative outside and
+// zero when the five points are cospherical.
+pub fn insphere(a: &Point3d, b: &Point3d, c: &Point3d, d: &Point3d, e: &Point3d) -> f64 {
+    insphere_filter(a, b, c, d, e).unwrap_or_else(|| insphere_exact(a, b, c, d, e))
+}
@@ -1,7 +1,146 @@
+use crate::Matrix3;
+use crate::traits::FloatWithConst;
+
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Quaternion<T = f64> {
     pub x: T,
     pub y: T,
     pub z: T,
     pub w: T,
 }
+
+impl<T> std::fmt::Display for Quaternion<T>
+where
+    T: std::fmt::Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Quaternion({}, {}, {}, {})",
+            self.x, self.y, self.z, self.w
+        )
+    }
+}
+
+impl<T> Quaternion<T>
+where
+    T: Copy + Default + FloatWithConst,
+{
+    pub fn new() -> Self {
+        Quaternion {
+            x: T::zero(),
+            y: T::zero(),
+            z: T::zero(),
+            w: T::one(),
+        }
+    }
+
+    pub fn from_coords(x: T, y: T, z: T, w: T) -> Self {
+        Quaternion { x, y, z, w }
+    }
+
+    pub fn from_matrix(matrix: &Matrix3<T>) -> Self {
+        let mut q = Quaternion::new();
+        q.set_matrix(matrix);
+        q
+    }
+
+    pub fn get_coords(&self) -> (T, T, T, T) {
+        (self.x, self.y, self.z, self.w)
+    }
+
+    pub fn norm(&self) -> T {
+        (self.x * self.x + self.y * self.y + self.z * self.z + self.w * self.w).sqrt()
+    }
+
+    pub fn normalize(&mut self) {
+        let n = self.norm();
+        if n <= T::min_positive() {
+            panic!("Cannot normalize zero length quaternion");
+        }
+        self.x /= n;
+        self.y /= n;
+        self.z /= n;
+        self.w /= n;
+    }
+
+    pub fn normalize_new(&self) -> Self {
+        let mut q = *self;
+        q.normalize();
+        q
+    }
+
+    pub fn set_matrix(&mut self, matrix: &Matrix3<T>) {
+        let m = &matrix.m;
+        let one = T::one();
+        let half = T::from(0.5).unwrap();
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        if trace > T::zero() {
+            let s = half / (trace + one).sqrt();
+            self.w = T::from(0.25).unwrap() / s;
+            self.x = (m[2][1] - m[1][2]) * s;
+            self.y = (m[0][2] - m[2][0]) * s;
+            self.z = (m[1][0] - m[0][1]) * s;
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (one + m[0][0] - m[1][1] - m[2][2]).sqrt() * T::from(2.0).unwrap();
+            self.w = (m[2][1] - m[1][2]) / s;
+            self.x = T::from(0.25).unwrap() * s;
+            self.y = (m[0][1] + m[1][0]) / s;
+            self.z = (m[0][2] + m[2][0]) / s;
+        } else if m[1][1] > m[2][2] {
+            let s = (one + m[1][1] - m[0][0] - m[2][2]).sqrt() * T::from(2.0).unwrap();
+            self.w = (m[0][2] - m[2][0]) / s;
+            self.x = (m[0][1] + m[1][0]) / s;
+            self.y = T::from(0.25).unwrap() * s;
+            self.z = (m[1][2] + m[2][1]) / s;
+        } else {
+            let s = (one + m[2][2] - m[0][0] - m[1][1]).sqrt() * T::from(2.0).unwrap();
+            self.w = (m[1][0] - m[0][1]) / s;
+            self.x = (m[0][2] + m[2][0]) / s;
+            self.y = (m[1][2] + m[2][1]) / s;
+            self.z = T::from(0.25).unwrap() * s;
+        }
+        self.normalize();
+    }
+
+    pub fn get_matrix(&self) -> Matrix3<T> {
+        let q = self.normalize_new();
+        let one = T::one();
+        let two = T::from(2.0).unwrap();
+        let (x, y, z, w) = (q.x, q.y, q.z, q.w);
+        Matrix3::from_array([
+            [
+                one - two * (y * y + z * z),
+                two * (x * y - z * w),
+                two * (x * z + y * w),
+            ],
+            [
+                two * (x * y + z * w),
+                one - two * (x * x + z * z),
+                two * (y * z - x * w),
+            ],
+            [
+                two * (x * z - y * w),
+                two * (y * z + x * w),
+                one - two * (x * x + y * y),
+            ],
+        ])
+    }
+
+    pub fn is_equal(&self, other: &Self, tolerance: T) -> bool {
+        (self.x - other.x).abs() <= tolerance
+            && (self.y - other.y).abs() <= tolerance
+            && (self.z - other.z).abs() <= tolerance
+            && (self.w - other.w).abs() <= tolerance
+    }
+}
+
+impl<T> Default for Quaternion<T>
+where
+    T: Copy + Default + FloatWithConst,
+{
+    fn default() -> Self {
+        Quaternion::new()
+    }
+}
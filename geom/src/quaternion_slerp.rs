@@ -1,6 +1,7 @@
 use crate::Quaternion;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct QuaternionSlerp<T = f64> {
     pub start: Quaternion<T>,
     pub end: Quaternion<T>,
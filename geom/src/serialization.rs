@@ -0,0 +1,649 @@
+// Deserialisation of types with invariants; everything else derives serde directly.
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+
+use crate::mesh::Attribute;
+use crate::traits::FloatWithConst;
+use crate::{
+    Axis3d, BSplineCurve2d, BSplineCurve3d, BSplineSurface, Circle2d, Circle3d, Cone,
+    CoordinateSystem2d, CoordinateSystem3d, Cylinder, Direction2d, Direction3d, Ellipse2d,
+    Ellipse3d, GeneralCoordinateSystem3d, Hyperbola2d, Hyperbola3d, Matrix2, Matrix3, Mesh,
+    Parabola2d, Parabola3d, Point2d, Point3d, Sphere, Torus, Trsf2d, Trsf3d, TrsfForm, XY, XYZ,
+};
+
+fn check<E: Error>(ok: bool, message: &'static str) -> Result<(), E> {
+    if ok { Ok(()) } else { Err(E::custom(message)) }
+}
+
+fn tolerance<T: FloatWithConst>() -> T {
+    T::epsilon().sqrt()
+}
+
+fn positive<T: FloatWithConst, E: Error>(value: T, message: &'static str) -> Result<(), E> {
+    check(value.is_finite() && value > T::zero(), message)
+}
+
+fn orthogonal<T: Copy + Default + FloatWithConst, E: Error>(
+    a: &Direction3d<T>,
+    b: &Direction3d<T>,
+) -> Result<(), E> {
+    check(
+        a.xyz.dot(&b.xyz).abs() <= tolerance(),
+        "coordinate system axes are not orthogonal",
+    )
+}
+
+impl<'de, T> Deserialize<'de> for Direction2d<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let xy = XY::<T>::deserialize(deserializer)?;
+        let length = xy.length();
+        check(
+            length.is_finite() && length > T::min_positive(),
+            "direction must be a finite non-zero vector",
+        )?;
+        Ok(Direction2d {
+            xy: XY::from_coords(xy.x / length, xy.y / length),
+        })
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Direction3d<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let xyz = XYZ::<T>::deserialize(deserializer)?;
+        let length = xyz.length();
+        check(
+            length.is_finite() && length > T::min_positive(),
+            "direction must be a finite non-zero vector",
+        )?;
+        Ok(Direction3d {
+            xyz: XYZ::from_coords(xyz.x / length, xyz.y / length, xyz.z / length),
+        })
+    }
+}
+
+impl<'de, T> Deserialize<'de> for CoordinateSystem3d<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(
+            rename = "CoordinateSystem3d",
+            bound = "T: Deserialize<'de> + Copy + Default + FloatWithConst"
+        )]
+        struct Raw<T> {
+            axis: Axis3d<T>,
+            vydir: Direction3d<T>,
+            vxdir: Direction3d<T>,
+        }
+        let raw = Raw::<T>::deserialize(deserializer)?;
+        orthogonal(&raw.axis.direction, &raw.vxdir)?;
+        orthogonal(&raw.axis.direction, &raw.vydir)?;
+        orthogonal(&raw.vxdir, &raw.vydir)?;
+        let expected = raw.axis.direction.xyz.cross_new(&raw.vxdir.xyz);
+        check(
+            expected.dot(&raw.vydir.xyz) > T::zero(),
+            "coordinate system must be right-handed",
+        )?;
+        Ok(CoordinateSystem3d {
+            axis: raw.axis,
+            vydir: raw.vydir,
+            vxdir: raw.vxdir,
+        })
+    }
+}
+
+impl<'de, T> Deserialize<'de> for GeneralCoordinateSystem3d<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(
+            rename = "GeneralCoordinateSystem3d",
+            bound = "T: Deserialize<'de> + Copy + Default + FloatWithConst"
+        )]
+        struct Raw<T> {
+            axis: Axis3d<T>,
+            vydir: Direction3d<T>,
+            vxdir: Direction3d<T>,
+        }
+        let raw = Raw::<T>::deserialize(deserializer)?;
+        orthogonal(&raw.axis.direction, &raw.vxdir)?;
+        orthogonal(&raw.axis.direction, &raw.vydir)?;
+        orthogonal(&raw.vxdir, &raw.vydir)?;
+        Ok(GeneralCoordinateSystem3d {
+            axis: raw.axis,
+            vydir: raw.vydir,
+            vxdir: raw.vxdir,
+        })
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Circle2d<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(
+            rename = "Circle2d",
+            bound = "T: Deserialize<'de> + Copy + Default + FloatWithConst"
+        )]
+        struct Raw<T> {
+            position: CoordinateSystem2d<T>,
+            radius: T,
+        }
+        let raw = Raw::<T>::deserialize(deserializer)?;
+        positive(raw.radius, "circle radius must be positive")?;
+        Ok(Circle2d {
+            position: raw.position,
+            radius: raw.radius,
+        })
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Circle3d<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(
+            rename = "Circle3d",
+            bound = "T: Deserialize<'de> + Copy + Default + FloatWithConst"
+        )]
+        struct Raw<T> {
+            position: CoordinateSystem3d<T>,
+            radius: T,
+        }
+        let raw = Raw::<T>::deserialize(deserializer)?;
+        positive(raw.radius, "circle radius must be positive")?;
+        Ok(Circle3d {
+            position: raw.position,
+            radius: raw.radius,
+        })
+    }
+}
+
+fn ellipse_radii<T: FloatWithConst, E: Error>(major: T, minor: T) -> Result<(), E> {
+    positive(minor, "ellipse radii must be positive")?;
+    check(
+        major.is_finite() && major >= minor,
+        "ellipse major radius must not be less than the minor radius",
+    )
+}
+
+impl<'de, T> Deserialize<'de> for Ellipse2d<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(
+            rename = "Ellipse2d",
+            bound = "T: Deserialize<'de> + Copy + Default + FloatWithConst"
+        )]
+        struct Raw<T> {
+            position: CoordinateSystem2d<T>,
+            major_radius: T,
+            minor_radius: T,
+        }
+        let raw = Raw::<T>::deserialize(deserializer)?;
+        ellipse_radii(raw.major_radius, raw.minor_radius)?;
+        Ok(Ellipse2d {
+            position: raw.position,
+            major_radius: raw.major_radius,
+            minor_radius: raw.minor_radius,
+        })
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Ellipse3d<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(
+            rename = "Ellipse3d",
+            bound = "T: Deserialize<'de> + Copy + Default + FloatWithConst"
+        )]
+        struct Raw<T> {
+            position: CoordinateSystem3d<T>,
+            major_radius: T,
+            minor_radius: T,
+        }
+        let raw = Raw::<T>::deserialize(deserializer)?;
+        ellipse_radii(raw.major_radius, raw.minor_radius)?;
+        Ok(Ellipse3d {
+            position: raw.position,
+            major_radius: raw.major_radius,
+            minor_radius: raw.minor_radius,
+        })
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Hyperbola2d<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(
+            rename = "Hyperbola2d",
+            bound = "T: Deserialize<'de> + Copy + Default + FloatWithConst"
+        )]
+        struct Raw<T> {
+            position: CoordinateSystem2d<T>,
+            major_radius: T,
+            minor_radius: T,
+        }
+        let raw = Raw::<T>::deserialize(deserializer)?;
+        positive(raw.major_radius, "hyperbola radii must be positive")?;
+        positive(raw.minor_radius, "hyperbola radii must be positive")?;
+        Ok(Hyperbola2d {
+            position: raw.position,
+            major_radius: raw.major_radius,
+            minor_radius: raw.minor_radius,
+        })
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Hyperbola3d<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(
+            rename = "Hyperbola3d",
+            bound = "T: Deserialize<'de> + Copy + Default + FloatWithConst"
+        )]
+        struct Raw<T> {
+            position: CoordinateSystem3d<T>,
+            major_radius: T,
+            minor_radius: T,
+        }
+        let raw = Raw::<T>::deserialize(deserializer)?;
+        positive(raw.major_radius, "hyperbola radii must be positive")?;
+        positive(raw.minor_radius, "hyperbola radii must be positive")?;
+        Ok(Hyperbola3d {
+            position: raw.position,
+            major_radius: raw.major_radius,
+            minor_radius: raw.minor_radius,
+        })
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Parabola2d<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(
+            rename = "Parabola2d",
+            bound = "T: Deserialize<'de> + Copy + Default + FloatWithConst"
+        )]
+        struct Raw<T> {
+            pos: CoordinateSystem2d<T>,
+            focal_length: T,
+        }
+        let raw = Raw::<T>::deserialize(deserializer)?;
+        positive(raw.focal_length, "parabola focal length must be positive")?;
+        Ok(Parabola2d {
+            pos: raw.pos,
+            focal_length: raw.focal_length,
+        })
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Parabola3d<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(
+            rename = "Parabola3d",
+            bound = "T: Deserialize<'de> + Copy + Default + FloatWithConst"
+        )]
+        struct Raw<T> {
+            pos: CoordinateSystem3d<T>,
+            focal_length: T,
+        }
+        let raw = Raw::<T>::deserialize(deserializer)?;
+        positive(raw.focal_length, "parabola focal length must be positive")?;
+        Ok(Parabola3d {
+            pos: raw.pos,
+            focal_length: raw.focal_length,
+        })
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Cylinder<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(
+            rename = "Cylinder",
+            bound = "T: Deserialize<'de> + Copy + Default + FloatWithConst"
+        )]
+        struct Raw<T> {
+            position: GeneralCoordinateSystem3d<T>,
+            radius: T,
+        }
+        let raw = Raw::<T>::deserialize(deserializer)?;
+        positive(raw.radius, "cylinder radius must be positive")?;
+        Ok(Cylinder {
+            position: raw.position,
+            radius: raw.radius,
+        })
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Cone<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(
+            rename = "Cone",
+            bound = "T: Deserialize<'de> + Copy + Default + FloatWithConst"
+        )]
+        struct Raw<T> {
+            position: GeneralCoordinateSystem3d<T>,
+            radius: T,
+            semi_angle: T,
+        }
+        let raw = Raw::<T>::deserialize(deserializer)?;
+        check(
+            raw.radius.is_finite() && raw.radius >= T::zero(),
+            "cone radius must not be negative",
+        )?;
+        let angle = raw.semi_angle.abs();
+        check(
+            angle > tolerance() && angle < T::frac_pi_2() - tolerance(),
+            "cone semi-angle must lie strictly between 0 and pi/2 in magnitude",
+        )?;
+        Ok(Cone {
+            position: raw.position,
+            radius: raw.radius,
+            semi_angle: raw.semi_angle,
+        })
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Sphere<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(
+            rename = "Sphere",
+            bound = "T: Deserialize<'de> + Copy + Default + FloatWithConst"
+        )]
+        struct Raw<T> {
+            pos: GeneralCoordinateSystem3d<T>,
+            radius: T,
+        }
+        let raw = Raw::<T>::deserialize(deserializer)?;
+        positive(raw.radius, "sphere radius must be positive")?;
+        Ok(Sphere {
+            pos: raw.pos,
+            radius: raw.radius,
+        })
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Torus<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(
+            rename = "Torus",
+            bound = "T: Deserialize<'de> + Copy + Default + FloatWithConst"
+        )]
+        struct Raw<T> {
+            pos: GeneralCoordinateSystem3d<T>,
+            major_radius: T,
+            minor_radius: T,
+        }
+        let raw = Raw::<T>::deserialize(deserializer)?;
+        positive(raw.major_radius, "torus radii must be positive")?;
+        positive(raw.minor_radius, "torus radii must be positive")?;
+        Ok(Torus {
+            pos: raw.pos,
+            major_radius: raw.major_radius,
+            minor_radius: raw.minor_radius,
+        })
+    }
+}
+
+impl<'de, T> Deserialize<'de> for BSplineCurve2d<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(
+            rename = "BSplineCurve2d",
+            bound = "T: Deserialize<'de> + Copy + Default + FloatWithConst"
+        )]
+        struct Raw<T> {
+            degree: usize,
+            poles: Vec<Point2d<T>>,
+            weights: Option<Vec<T>>,
+            knots: Vec<T>,
+            multiplicities: Vec<usize>,
+        }
+        let raw = Raw::<T>::deserialize(deserializer)?;
+        match raw.weights {
+            None => BSplineCurve2d::from_poles_knots(
+                raw.degree,
+                raw.poles,
+                raw.knots,
+                raw.multiplicities,
+            ),
+            Some(weights) => BSplineCurve2d::from_poles_weights_knots(
+                raw.degree,
+                raw.poles,
+                weights,
+                raw.knots,
+                raw.multiplicities,
+            ),
+        }
+        .map_err(D::Error::custom)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for BSplineCurve3d<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(
+            rename = "BSplineCurve3d",
+            bound = "T: Deserialize<'de> + Copy + Default + FloatWithConst"
+        )]
+        struct Raw<T> {
+            degree: usize,
+            poles: Vec<Point3d<T>>,
+            weights: Option<Vec<T>>,
+            knots: Vec<T>,
+            multiplicities: Vec<usize>,
+        }
+        let raw = Raw::<T>::deserialize(deserializer)?;
+        match raw.weights {
+            None => BSplineCurve3d::from_poles_knots(
+                raw.degree,
+                raw.poles,
+                raw.knots,
+                raw.multiplicities,
+            ),
+            Some(weights) => BSplineCurve3d::from_poles_weights_knots(
+                raw.degree,
+                raw.poles,
+                weights,
+                raw.knots,
+                raw.multiplicities,
+            ),
+        }
+        .map_err(D::Error::custom)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for BSplineSurface<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(
+            rename = "BSplineSurface",
+            bound = "T: Deserialize<'de> + Copy + Default + FloatWithConst"
+        )]
+        struct Raw<T> {
+            u_degree: usize,
+            v_degree: usize,
+            poles: Vec<Vec<Point3d<T>>>,
+            weights: Option<Vec<Vec<T>>>,
+            u_knots: Vec<T>,
+            u_multiplicities: Vec<usize>,
+            v_knots: Vec<T>,
+            v_multiplicities: Vec<usize>,
+        }
+        let raw = Raw::<T>::deserialize(deserializer)?;
+        let mut surface = BSplineSurface::from_poles_knots(
+            (raw.u_degree, raw.v_degree),
+            raw.poles,
+            (raw.u_knots, raw.u_multiplicities),
+            (raw.v_knots, raw.v_multiplicities),
+        )
+        .map_err(D::Error::custom)?;
+        if let Some(weights) = raw.weights {
+            surface.set_weights(weights).map_err(D::Error::custom)?;
+        }
+        Ok(surface)
+    }
+}
+
+// The matrix part of a transformation is orthogonal; scaling is kept separately.
+fn orthonormal<E: Error>(rows: &[&[f64]]) -> Result<(), E> {
+    for (i, a) in rows.iter().enumerate() {
+        for (j, b) in rows.iter().enumerate() {
+            let dot: f64 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
+            let expected = if i == j { 1.0 } else { 0.0 };
+            check(
+                (dot - expected).abs() <= tolerance::<f64>(),
+                "transformation matrix must be orthogonal",
+            )?;
+        }
+    }
+    Ok(())
+}
+
+impl<'de> Deserialize<'de> for Trsf2d {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "Trsf2d")]
+        struct Raw {
+            matrix: Matrix2,
+            loc: XY,
+            trsf_type: TrsfForm,
+            scale: f64,
+        }
+        let raw = Raw::deserialize(deserializer)?;
+        let m = &raw.matrix.m;
+        orthonormal(&[&m[0], &m[1]])?;
+        check(
+            raw.scale.is_finite() && raw.scale != 0.0,
+            "transformation scale must be finite and non-zero",
+        )?;
+        check(
+            raw.loc.x.is_finite() && raw.loc.y.is_finite(),
+            "transformation translation must be finite",
+        )?;
+        Ok(Trsf2d {
+            matrix: raw.matrix,
+            loc: raw.loc,
+            trsf_type: raw.trsf_type,
+            scale: raw.scale,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Trsf3d {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "Trsf3d")]
+        struct Raw {
+            matrix: Matrix3,
+            loc: XYZ,
+            trsf_type: TrsfForm,
+            scale: f64,
+        }
+        let raw = Raw::deserialize(deserializer)?;
+        let m = &raw.matrix.m;
+        orthonormal(&[&m[0], &m[1], &m[2]])?;
+        check(
+            raw.scale.is_finite() && raw.scale != 0.0,
+            "transformation scale must be finite and non-zero",
+        )?;
+        check(
+            raw.loc.x.is_finite() && raw.loc.y.is_finite() && raw.loc.z.is_finite(),
+            "transformation translation must be finite",
+        )?;
+        Ok(Trsf3d {
+            matrix: raw.matrix,
+            loc: raw.loc,
+            trsf_type: raw.trsf_type,
+            scale: raw.scale,
+        })
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Mesh<T>
+where
+    T: Deserialize<'de> + Copy + Default + FloatWithConst,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(
+            rename = "Mesh",
+            bound = "T: Deserialize<'de> + Copy + Default + FloatWithConst"
+        )]
+        struct Raw<T> {
+            points: Vec<Point3d<T>>,
+            faces: Vec<Vec<usize>>,
+            point_attributes: Vec<Attribute>,
+            face_attributes: Vec<Attribute>,
+        }
+        let raw = Raw::<T>::deserialize(deserializer)?;
+        let mesh = Mesh {
+            points: raw.points,
+            faces: raw.faces,
+            point_attributes: raw.point_attributes,
+            face_attributes: raw.face_attributes,
+        };
+        check(
+            mesh.is_valid(),
+            "mesh faces or attributes do not match its points",
+        )?;
+        Ok(mesh)
+    }
+}
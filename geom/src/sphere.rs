@@ -1,6 +1,7 @@
 use crate::GeneralCoordinateSystem3d;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Sphere<T = f64> {
     pub pos: GeneralCoordinateSystem3d<T>,
     pub radius: T,
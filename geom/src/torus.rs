@@ -1,6 +1,7 @@
 use crate::GeneralCoordinateSystem3d;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Torus<T = f64> {
     pub pos: GeneralCoordinateSystem3d<T>,
     pub major_radius: T,
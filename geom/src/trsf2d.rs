@@ -3,9 +3,119 @@ use crate::TrsfForm;
 use crate::XY;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Trsf2d<T = f64> {
     pub matrix: Matrix2<T>,
     pub loc: XY,
     pub trsf_type: TrsfForm,
     pub scale: T,
-}
\ No newline at end of file
+}
+
+impl Trsf2d {
+    pub fn new() -> Self {
+        Trsf2d {
+            matrix: Matrix2::from_array([[1.0, 0.0], [0.0, 1.0]]),
+            loc: XY::new(),
+            trsf_type: TrsfForm::Identity,
+            scale: 1.0,
+        }
+    }
+
+    // The 2x2 part must be a rotation (possibly mirrored) times a uniform scale.
+    pub fn from_values(values: [[f64; 3]; 2]) -> Result<Self, &'static str> {
+        let [[a, b, tx], [c, d, ty]] = values;
+        let det = a * d - b * c;
+        if det.abs() < f64::EPSILON {
+            return Err("transformation matrix is singular");
+        }
+        let scale = det.abs().sqrt();
+        let tolerance = 1e-9 * scale * scale;
+        if (a * a + c * c - det.abs()).abs() > tolerance
+            || (b * b + d * d - det.abs()).abs() > tolerance
+            || (a * b + c * d).abs() > tolerance
+        {
+            return Err("transformation matrix is not a similarity");
+        }
+        let m = [[a / scale, b / scale], [c / scale, d / scale]];
+        let is_identity = m == [[1.0, 0.0], [0.0, 1.0]];
+        let is_translation = tx != 0.0 || ty != 0.0;
+        let trsf_type = match (is_identity, scale == 1.0, is_translation) {
+            (true, true, false) => TrsfForm::Identity,
+            (true, true, true) => TrsfForm::Translation,
+            (true, false, _) => TrsfForm::Scale,
+            (false, true, false) if det > 0.0 => TrsfForm::Rotation,
+            _ => TrsfForm::CompoundTrsf,
+        };
+        Ok(Trsf2d {
+            matrix: Matrix2::from_array(m),
+            loc: XY::from_coords(tx, ty),
+            trsf_type,
+            scale,
+        })
+    }
+
+    pub fn get_value(&self, row: usize, col: usize) -> f64 {
+        if row > 1 || col > 2 {
+            panic!("Index out of bounds");
+        }
+        match col {
+            2 => [self.loc.x, self.loc.y][row],
+            _ => self.scale * self.matrix.m[row][col],
+        }
+    }
+
+    pub fn get_values(&self) -> [[f64; 3]; 2] {
+        let mut values = [[0.0; 3]; 2];
+        for (row, r) in values.iter_mut().enumerate() {
+            for (col, v) in r.iter_mut().enumerate() {
+                *v = self.get_value(row, col);
+            }
+        }
+        values
+    }
+
+    // True when the transformation reverses orientation.
+    pub fn is_negative(&self) -> bool {
+        let m = &self.matrix.m;
+        m[0][0] * m[1][1] - m[0][1] * m[1][0] < 0.0
+    }
+
+    pub fn transform_xy(&self, xy: &XY) -> XY {
+        let mut result = &(xy * &self.matrix) * self.scale;
+        result += &self.loc;
+        result
+    }
+
+    // Rotates (and mirrors) `xy` without scaling or translating it.
+    pub fn transform_direction_xy(&self, xy: &XY) -> XY {
+        xy * &self.matrix
+    }
+
+    // Composition `self * other`: `other` is applied first.
+    pub fn multiply_new(&self, other: &Self) -> Self {
+        let mut m = [[0.0; 2]; 2];
+        for (i, r) in m.iter_mut().enumerate() {
+            for (j, v) in r.iter_mut().enumerate() {
+                *v = (0..2)
+                    .map(|k| self.matrix.m[i][k] * other.matrix.m[k][j])
+                    .sum();
+            }
+        }
+        let trsf_type = match (self.trsf_type, other.trsf_type) {
+            (TrsfForm::Identity, form) | (form, TrsfForm::Identity) => form,
+            _ => TrsfForm::CompoundTrsf,
+        };
+        Trsf2d {
+            matrix: Matrix2::from_array(m),
+            loc: self.transform_xy(&other.loc),
+            trsf_type,
+            scale: self.scale * other.scale,
+        }
+    }
+}
+
+impl Default for Trsf2d {
+    fn default() -> Self {
+        Trsf2d::new()
+    }
+}
@@ -1,11 +1,168 @@
+use crate::CoordinateSystem3d;
 use crate::Matrix3;
+use crate::Quaternion;
 use crate::TrsfForm;
 use crate::XYZ;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Trsf3d<T = f64> {
     pub matrix: Matrix3<T>,
     pub loc: XYZ,
     pub trsf_type: TrsfForm,
     pub scale: T,
 }
+
+impl Trsf3d {
+    pub fn new() -> Self {
+        Trsf3d {
+            matrix: Matrix3::from_array([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]),
+            loc: XYZ::new(),
+            trsf_type: TrsfForm::Identity,
+            scale: 1.0,
+        }
+    }
+
+    pub fn from_quaternion_translation(rotation: &Quaternion, translation: XYZ) -> Self {
+        let matrix = rotation.get_matrix();
+        let is_identity = matrix.m.iter().enumerate().all(|(i, r)| {
+            r.iter()
+                .enumerate()
+                .all(|(j, &v)| v == if i == j { 1.0 } else { 0.0 })
+        });
+        let is_translation = translation.x != 0.0 || translation.y != 0.0 || translation.z != 0.0;
+        let trsf_type = match (is_identity, is_translation) {
+            (true, false) => TrsfForm::Identity,
+            (true, true) => TrsfForm::Translation,
+            (false, false) => TrsfForm::Rotation,
+            (false, true) => TrsfForm::CompoundTrsf,
+        };
+        Trsf3d {
+            matrix,
+            loc: translation,
+            trsf_type,
+            scale: 1.0,
+        }
+    }
+
+    // Maps coordinates local to `position` into the global frame.
+    pub fn from_coordinate_system(position: &CoordinateSystem3d) -> Self {
+        let mut matrix = Matrix3::new();
+        matrix.set_col(0, position.vxdir.get_coords().into());
+        matrix.set_col(1, position.vydir.get_coords().into());
+        matrix.set_col(2, position.axis.direction.get_coords().into());
+        Trsf3d {
+            matrix,
+            loc: position.axis.location.get_xyz(),
+            trsf_type: TrsfForm::CompoundTrsf,
+            scale: 1.0,
+        }
+    }
+
+    // The 3x3 part must be a rotation (possibly mirrored) times a uniform scale.
+    pub fn from_values(values: [[f64; 4]; 3]) -> Result<Self, &'static str> {
+        let mut m = [[0.0; 3]; 3];
+        for (row, r) in m.iter_mut().enumerate() {
+            r.copy_from_slice(&values[row][..3]);
+        }
+        let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+        if det.abs() < f64::EPSILON {
+            return Err("transformation matrix is singular");
+        }
+        let scale = det.abs().cbrt();
+        for i in 0..3 {
+            for j in 0..3 {
+                let dot: f64 = (0..3).map(|k| m[k][i] * m[k][j]).sum::<f64>() / (scale * scale);
+                let expected = if i == j { 1.0 } else { 0.0 };
+                if (dot - expected).abs() > 1e-9 {
+                    return Err("transformation matrix is not a similarity");
+                }
+            }
+        }
+        for r in m.iter_mut() {
+            for v in r.iter_mut() {
+                *v /= scale;
+            }
+        }
+        let is_identity = m.iter().enumerate().all(|(i, r)| {
+            r.iter()
+                .enumerate()
+                .all(|(j, &v)| v == if i == j { 1.0 } else { 0.0 })
+        });
+        let is_translation = values.iter().any(|r| r[3] != 0.0);
+        let trsf_type = match (is_identity, scale == 1.0, is_translation) {
+            (true, true, false) => TrsfForm::Identity,
+            (true, true, true) => TrsfForm::Translation,
+            (true, false, _) => TrsfForm::Scale,
+            (false, true, false) if det > 0.0 => TrsfForm::Rotation,
+            _ => TrsfForm::CompoundTrsf,
+        };
+        Ok(Trsf3d {
+            matrix: Matrix3::from_array(m),
+            loc: XYZ::from_coords(values[0][3], values[1][3], values[2][3]),
+            trsf_type,
+            scale,
+        })
+    }
+
+    pub fn get_value(&self, row: usize, col: usize) -> f64 {
+        if row > 2 || col > 3 {
+            panic!("Index out of bounds");
+        }
+        match col {
+            3 => [self.loc.x, self.loc.y, self.loc.z][row],
+            _ => self.scale * self.matrix.m[row][col],
+        }
+    }
+
+    pub fn get_values(&self) -> [[f64; 4]; 3] {
+        let mut values = [[0.0; 4]; 3];
+        for (row, r) in values.iter_mut().enumerate() {
+            for (col, v) in r.iter_mut().enumerate() {
+                *v = self.get_value(row, col);
+            }
+        }
+        values
+    }
+
+    pub fn transform_xyz(&self, xyz: &XYZ) -> XYZ {
+        let mut result = &(xyz * &self.matrix) * self.scale;
+        result += &self.loc;
+        result
+    }
+
+    // Rotates (and mirrors) `xyz` without scaling or translating it.
+    pub fn transform_direction_xyz(&self, xyz: &XYZ) -> XYZ {
+        xyz * &self.matrix
+    }
+
+    // Composition `self * other`: `other` is applied first.
+    pub fn multiply_new(&self, other: &Self) -> Self {
+        let mut m = [[0.0; 3]; 3];
+        for (i, r) in m.iter_mut().enumerate() {
+            for (j, v) in r.iter_mut().enumerate() {
+                *v = (0..3)
+                    .map(|k| self.matrix.m[i][k] * other.matrix.m[k][j])
+                    .sum();
+            }
+        }
+        let trsf_type = match (self.trsf_type, other.trsf_type) {
+            (TrsfForm::Identity, form) | (form, TrsfForm::Identity) => form,
+            _ => TrsfForm::CompoundTrsf,
+        };
+        Trsf3d {
+            matrix: Matrix3::from_array(m),
+            loc: self.transform_xyz(&other.loc),
+            trsf_type,
+            scale: self.scale * other.scale,
+        }
+    }
+}
+
+impl Default for Trsf3d {
+    fn default() -> Self {
+        Trsf3d::new()
+    }
+}
@@ -2,6 +2,7 @@ use crate::XY;
 use crate::traits::FloatWithConst;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
 pub struct Vector2d<T = f64> {
     pub xy: XY<T>,
 }
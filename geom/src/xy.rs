@@ -1,6 +1,7 @@
 use crate::traits::FloatWithConst;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct XY<T = f64> {
     pub x: T,
     pub y: T,
@@ -0,0 +1,184 @@
+use geom::io::archive::{Archive, ArchiveWriter, FLAG_REQUIRED, Item, KIND_POINTS, MAGIC, crc32};
+use geom::io::step::StepGeometry;
+use geom::mesh::{Attribute, AttributeType, Mesh};
+use geom::{BSplineSurface, Circle3d, CoordinateSystem3d, Direction3d, Point3d};
+
+fn frame() -> CoordinateSystem3d {
+    CoordinateSystem3d::from_location_direction_xdirection(
+        Point3d::from_coords(1.0, 2.0, 3.0),
+        Direction3d::from_coords(0.0, 0.0, 1.0),
+        Direction3d::from_coords(1.0, 1.0, 0.0),
+    )
+}
+
+fn sample() -> ArchiveWriter {
+    let mut writer = ArchiveWriter::new();
+    writer.add_geometry(
+        "circle",
+        &StepGeometry::Circle(Circle3d {
+            position: frame(),
+            radius: 2.5,
+        }),
+    );
+    let mut surface = BSplineSurface::from_poles_knots(
+        (1, 1),
+        vec![
+            vec![
+                Point3d::from_coords(0.0, 0.0, 0.0),
+                Point3d::from_coords(0.0, 1.0, 0.0),
+            ],
+            vec![
+                Point3d::from_coords(1.0, 0.0, 0.0),
+                Point3d::from_coords(1.0, 1.0, 1.0),
+            ],
+        ],
+        (vec![0.0, 1.0], vec![2, 2]),
+        (vec![0.0, 1.0], vec![2, 2]),
+    )
+    .unwrap();
+    surface
+        .set_weights(vec![vec![1.0, 2.0], vec![1.0, 0.5]])
+        .unwrap();
+    writer.add_geometry("patch", &StepGeometry::BSplineSurface(surface));
+    writer.add_points(
+        "cloud",
+        &[
+            Point3d::from_coords(1.0, 2.0, 3.0),
+            Point3d::from_coords(-4.0, 5.5, 6.0),
+        ],
+    );
+    let mut mesh = Mesh::from_points_faces(
+        vec![
+            Point3d::from_coords(0.0, 0.0, 0.0),
+            Point3d::from_coords(1.0, 0.0, 0.0),
+            Point3d::from_coords(1.0, 1.0, 0.0),
+            Point3d::from_coords(0.0, 1.0, 0.0),
+        ],
+        vec![vec![0, 1, 2], vec![0, 2, 3]],
+    );
+    mesh.add_point_attribute(Attribute::scalar(
+        "temperature",
+        AttributeType::F32,
+        vec![1.5, 2.0, -3.25, 4.0],
+    ))
+    .unwrap();
+    mesh.add_face_attribute(Attribute::list(
+        "color",
+        AttributeType::U8,
+        vec![vec![255.0, 0.0, 0.0], vec![0.0, 128.0, 255.0]],
+    ))
+    .unwrap();
+    writer.add_mesh("plate", &mesh).unwrap();
+    writer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let bytes = sample().to_bytes();
+        assert_eq!(bytes[..8], MAGIC);
+        assert_eq!(bytes.len() % 8, 0);
+        let archive = Archive::parse(&bytes).unwrap();
+        assert_eq!(archive.version, (1, 0));
+        assert!(archive.skipped.is_empty());
+        let items = archive.items().unwrap();
+        assert_eq!(items.len(), 4);
+
+        match &items[0] {
+            Item::Geometry {
+                name,
+                geometry: StepGeometry::Circle(c),
+            } => {
+                assert_eq!(name, "circle");
+                assert_eq!(c.radius, 2.5);
+                assert!(c.position.vxdir.xyz.is_equal(&frame().vxdir.xyz, 0.0));
+                assert!(c.position.vydir.xyz.is_equal(&frame().vydir.xyz, 0.0));
+            }
+            _ => panic!("expected a circle"),
+        }
+        match archive.get("patch").unwrap() {
+            Some(Item::Geometry {
+                geometry: StepGeometry::BSplineSurface(s),
+                ..
+            }) => {
+                assert_eq!(s.weights, Some(vec![vec![1.0, 2.0], vec![1.0, 0.5]]));
+                assert!(s.poles[1][1].is_equal(&Point3d::from_coords(1.0, 1.0, 1.0), 0.0));
+            }
+            _ => panic!("expected a B-spline surface"),
+        }
+        match archive.get("plate").unwrap() {
+            Some(Item::Mesh { mesh, .. }) => {
+                assert_eq!(mesh.faces, vec![vec![0, 1, 2], vec![0, 2, 3]]);
+                let temperature = mesh.get_point_attribute("temperature").unwrap();
+                assert_eq!(temperature.data_type, AttributeType::F32);
+                assert_eq!(temperature.get_scalar(2), Some(-3.25));
+                let color = mesh.get_face_attribute("color").unwrap();
+                assert_eq!(color.values.len(), 2);
+            }
+            _ => panic!("expected a mesh"),
+        }
+        assert!(archive.get("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_point_array() {
+        let bytes = sample().to_bytes();
+        let archive = Archive::parse(&bytes).unwrap();
+        let points = match archive.get("cloud").unwrap() {
+            Some(Item::Points { points, .. }) => points,
+            _ => panic!("expected points"),
+        };
+        assert_eq!(points.len(), 2);
+        assert_eq!(points.get_coords(1), Some([-4.0, 5.5, 6.0]));
+        assert!(points.get(2).is_none());
+        assert_eq!(points.iter().count(), 2);
+
+        // The coordinates are borrowed from the input buffer, aligned to 8 bytes.
+        let raw = points.as_bytes();
+        let offset = raw.as_ptr() as usize - bytes.as_ptr() as usize;
+        assert_eq!(offset % 8, 0);
+        assert_eq!(raw.len(), 48);
+        assert_eq!(raw[..8], 1.0f64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_corruption() {
+        let mut bytes = sample().to_bytes();
+        let last = bytes.len() - 20;
+        bytes[last] ^= 0x40;
+        assert!(Archive::parse(&bytes).is_err());
+
+        let bytes = sample().to_bytes();
+        assert!(Archive::parse(&bytes[..bytes.len() - 8]).is_err());
+        assert!(Archive::parse(&bytes[..20]).is_err());
+        assert!(Archive::parse(b"GEOMARC").is_err());
+
+        let mut newer = bytes.clone();
+        newer[8] = 2;
+        assert!(Archive::parse(&newer).is_err());
+        let mut minor = bytes.clone();
+        minor[10] = 7;
+        assert_eq!(Archive::parse(&minor).unwrap().version, (1, 7));
+
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_unknown_records() {
+        let mut writer = ArchiveWriter::new();
+        writer.add_record(99, 0, b"future data");
+        writer.add_points("p", &[Point3d::from_coords(1.0, 1.0, 1.0)]);
+        let bytes = writer.to_bytes();
+        let archive = Archive::parse(&bytes).unwrap();
+        assert_eq!(archive.skipped, vec![99]);
+        assert_eq!(archive.records.len(), 1);
+        assert_eq!(archive.records[0].kind, KIND_POINTS);
+        assert_eq!(archive.items().unwrap()[0].name(), "p");
+
+        writer.add_record(100, FLAG_REQUIRED, &[]);
+        assert!(Archive::parse(&writer.to_bytes()).is_err());
+    }
+}
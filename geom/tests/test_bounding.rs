@@ -0,0 +1,323 @@
+use geom::bounding::{
+    approx_min_volume_box, min_area_rectangle, min_enclosing_circle, min_enclosing_sphere,
+};
+use geom::{OrientedBox3d, Point2d, Point3d};
+
+fn random(n: usize, seed: u64) -> Vec<f64> {
+    let mut state = seed;
+    (0..n)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        })
+        .collect()
+}
+
+fn distance3(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    (0..3).map(|k| (a[k] - b[k]).powi(2)).sum::<f64>().sqrt()
+}
+
+// Radius of the smallest ball through two, three or four of the points that
+// holds all of them.
+fn brute_force_sphere(p: &[[f64; 3]]) -> f64 {
+    let holds = |c: &[f64; 3], r: f64| p.iter().all(|q| distance3(c, q) <= r * (1.0 + 1e-9));
+    let mut best = f64::MAX;
+    for i in 0..p.len() {
+        for j in i + 1..p.len() {
+            let c = std::array::from_fn(|k| 0.5 * (p[i][k] + p[j][k]));
+            let r = distance3(&c, &p[i]);
+            if r < best && holds(&c, r) {
+                best = r;
+            }
+        }
+    }
+    // Centers equidistant from three points in their plane, when l == k, or
+    // from four points.
+    let n = p.len();
+    for i in 0..n {
+        for j in i + 1..n {
+            for k in j + 1..n {
+                for l in k..n {
+                    let s = [p[i], p[j], p[k], p[l]];
+                    let mut rows: Vec<[f64; 4]> = (1..4)
+                        .filter(|&m| l != k || m < 3)
+                        .map(|m| {
+                            let d: [f64; 3] = std::array::from_fn(|a| s[m][a] - s[0][a]);
+                            let rhs = 0.5 * (0..3).map(|a| d[a] * (s[m][a] + s[0][a])).sum::<f64>();
+                            [d[0], d[1], d[2], rhs]
+                        })
+                        .collect();
+                    if rows.len() == 2 {
+                        // Center in the plane of the three points.
+                        let u: [f64; 3] = std::array::from_fn(|a| s[1][a] - s[0][a]);
+                        let v: [f64; 3] = std::array::from_fn(|a| s[2][a] - s[0][a]);
+                        let nrm = [
+                            u[1] * v[2] - u[2] * v[1],
+                            u[2] * v[0] - u[0] * v[2],
+                            u[0] * v[1] - u[1] * v[0],
+                        ];
+                        let rhs = (0..3).map(|a| nrm[a] * s[0][a]).sum::<f64>();
+                        rows.push([nrm[0], nrm[1], nrm[2], rhs]);
+                    }
+                    let Some(c) = solve3(&rows) else {
+                        continue;
+                    };
+                    let r = distance3(&c, &s[0]);
+                    if r < best && holds(&c, r) {
+                        best = r;
+                    }
+                }
+            }
+        }
+    }
+    best
+}
+
+fn solve3(rows: &[[f64; 4]]) -> Option<[f64; 3]> {
+    let mut m: Vec<[f64; 4]> = rows.to_vec();
+    for c in 0..3 {
+        let pivot = (c..3).max_by(|&a, &b| m[a][c].abs().total_cmp(&m[b][c].abs()))?;
+        if m[pivot][c].abs() < 1e-12 {
+            return None;
+        }
+        m.swap(c, pivot);
+        for r in 0..3 {
+            if r != c {
+                let f = m[r][c] / m[c][c];
+                let pivot_row = m[c];
+                for (x, y) in m[r].iter_mut().zip(pivot_row) {
+                    *x -= f * y;
+                }
+            }
+        }
+    }
+    Some(std::array::from_fn(|c| m[c][3] / m[c][c]))
+}
+
+fn inside_box(b: &OrientedBox3d, p: &Point3d) -> bool {
+    let o = b.pos.get_origin();
+    let d = [
+        p.get_x() - o.get_x(),
+        p.get_y() - o.get_y(),
+        p.get_z() - o.get_z(),
+    ];
+    let axes = [b.pos.vxdir, b.pos.vydir, b.pos.axis.direction];
+    axes.iter().zip(b.extents.iter()).all(|(a, e)| {
+        let t = d[0] * a.get_x() + d[1] * a.get_y() + d[2] * a.get_z();
+        t.abs() <= 0.5 * e + 1e-9
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_min_enclosing_circle() {
+        let r = random(120, 1);
+        let points: Vec<Point2d> = r
+            .chunks(2)
+            .map(|c| Point2d::from_coords(c[0], 2.0 * c[1]))
+            .collect();
+        let circle = min_enclosing_circle(&points).unwrap();
+        let center = circle.position.origin;
+        let distances: Vec<f64> = points
+            .iter()
+            .map(|p| {
+                ((p.get_x() - center.get_x()).powi(2) + (p.get_y() - center.get_y()).powi(2)).sqrt()
+            })
+            .collect();
+        assert!(distances.iter().all(|&d| d <= circle.radius * (1.0 + 1e-9)));
+        let on = distances
+            .iter()
+            .filter(|&&d| (d - circle.radius).abs() < 1e-9)
+            .count();
+        assert!(on >= 2);
+
+        let square: Vec<Point2d> = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0), (0.5, 0.5)]
+            .into_iter()
+            .map(Point2d::from)
+            .collect();
+        let circle = min_enclosing_circle(&square).unwrap();
+        assert!(
+            circle
+                .position
+                .origin
+                .is_equal(&Point2d::from_coords(0.5, 0.5), 1e-12)
+        );
+        assert!((circle.radius - 0.5f64.sqrt()).abs() < 1e-12);
+
+        let line: Vec<Point2d> = (0..10)
+            .map(|i| Point2d::from_coords(i as f64, 3.0 * i as f64))
+            .collect();
+        let circle = min_enclosing_circle(&line).unwrap();
+        assert!((circle.radius - 0.5 * 90f64.sqrt() * 3.0).abs() < 1e-9);
+        let single = vec![Point2d::from_coords(2.0, 1.0); 4];
+        assert_eq!(min_enclosing_circle(&single).unwrap().radius, 0.0);
+        assert!(min_enclosing_circle(&[]).is_err());
+    }
+
+    #[test]
+    fn test_min_enclosing_sphere() {
+        let r = random(60, 2);
+        let p: Vec<[f64; 3]> = r.chunks(3).map(|c| [c[0], c[1], 0.5 * c[2]]).collect();
+        let points: Vec<Point3d> = p
+            .iter()
+            .map(|c| Point3d::from_coords(c[0], c[1], c[2]))
+            .collect();
+        let sphere = min_enclosing_sphere(&points).unwrap();
+        let o = sphere.pos.get_origin();
+        let center = [o.get_x(), o.get_y(), o.get_z()];
+        assert!(
+            p.iter()
+                .all(|q| distance3(&center, q) <= sphere.radius * (1.0 + 1e-9))
+        );
+        assert!((sphere.radius - brute_force_sphere(&p)).abs() < 1e-9);
+
+        let cube: Vec<Point3d> = (0..8)
+            .map(|i| Point3d::from_coords((i & 1) as f64, (i >> 1 & 1) as f64, (i >> 2) as f64))
+            .collect();
+        let sphere = min_enclosing_sphere(&cube).unwrap();
+        assert!(
+            sphere
+                .pos
+                .get_origin()
+                .is_equal(&Point3d::from_coords(0.5, 0.5, 0.5), 1e-12)
+        );
+        assert!((sphere.radius - 0.75f64.sqrt()).abs() < 1e-12);
+
+        // Coplanar points on a circle.
+        let ring: Vec<Point3d> = (0..12)
+            .map(|i| {
+                let t = i as f64 * std::f64::consts::PI / 6.0;
+                Point3d::from_coords(1.0 + 2.0 * t.cos(), 2.0 * t.sin(), 3.0)
+            })
+            .collect();
+        let sphere = min_enclosing_sphere(&ring).unwrap();
+        assert!((sphere.radius - 2.0).abs() < 1e-12);
+        assert!(min_enclosing_sphere(&[Point3d::from_coords(f64::INFINITY, 0.0, 0.0)]).is_err());
+    }
+
+    #[test]
+    fn test_min_area_rectangle() {
+        // Points filling a rotated 3 by 1 rectangle.
+        let (s, c) = 0.3f64.sin_cos();
+        let r = random(200, 3);
+        let mut local: Vec<(f64, f64)> = r.chunks(2).map(|q| (3.0 * q[0], q[1])).collect();
+        local.extend([(0.0, 0.0), (3.0, 0.0), (3.0, 1.0), (0.0, 1.0)]);
+        let points: Vec<Point2d> = local
+            .iter()
+            .map(|&(x, y)| Point2d::from_coords(c * x - s * y + 5.0, s * x + c * y - 2.0))
+            .collect();
+        let rectangle = min_area_rectangle(&points).unwrap();
+        assert!((rectangle.area() - 3.0).abs() < 1e-9);
+        let (w, h) = (
+            rectangle.width.max(rectangle.height),
+            rectangle.width.min(rectangle.height),
+        );
+        assert!((w - 3.0).abs() < 1e-9 && (h - 1.0).abs() < 1e-9);
+        let corners = rectangle.corners();
+        for q in [(0.0, 0.0), (3.0, 0.0), (3.0, 1.0), (0.0, 1.0)] {
+            let p = Point2d::from_coords(c * q.0 - s * q.1 + 5.0, s * q.0 + c * q.1 - 2.0);
+            assert!(corners.iter().any(|k| k.is_equal(&p, 1e-9)));
+        }
+
+        // Never larger than the rectangle of any other orientation.
+        let r = random(100, 4);
+        let points: Vec<Point2d> = r
+            .chunks(2)
+            .map(|q| Point2d::from_coords(q[0] + q[1], 0.5 * q[1] * q[1]))
+            .collect();
+        let area = min_area_rectangle(&points).unwrap().area();
+        for i in 0..360 {
+            let (s, c) = (i as f64 * std::f64::consts::PI / 360.0).sin_cos();
+            let (mut lo, mut hi) = ([f64::MAX; 2], [f64::MIN; 2]);
+            for p in points.iter() {
+                let t = [c * p.get_x() + s * p.get_y(), c * p.get_y() - s * p.get_x()];
+                for k in 0..2 {
+                    lo[k] = lo[k].min(t[k]);
+                    hi[k] = hi[k].max(t[k]);
+                }
+            }
+            assert!(area <= (hi[0] - lo[0]) * (hi[1] - lo[1]) + 1e-12);
+        }
+
+        let pair = [
+            Point2d::from_coords(0.0, 0.0),
+            Point2d::from_coords(3.0, 4.0),
+        ];
+        let rectangle = min_area_rectangle(&pair).unwrap();
+        assert!((rectangle.width - 5.0).abs() < 1e-12);
+        assert_eq!(rectangle.height, 0.0);
+        assert!(min_area_rectangle(&[]).is_err());
+    }
+
+    #[test]
+    fn test_min_volume_box() {
+        // Points filling a rotated 4 by 2 by 1 box.
+        let axis = [1.0f64, 2.0, 2.0].map(|v| v / 3.0);
+        let (s, c) = 0.7f64.sin_cos();
+        let rotate = |p: [f64; 3]| {
+            // Rodrigues' rotation about the axis.
+            let k = axis;
+            let d = k[0] * p[0] + k[1] * p[1] + k[2] * p[2];
+            let x = [
+                k[1] * p[2] - k[2] * p[1],
+                k[2] * p[0] - k[0] * p[2],
+                k[0] * p[1] - k[1] * p[0],
+            ];
+            let q: [f64; 3] = std::array::from_fn(|i| p[i] * c + x[i] * s + k[i] * d * (1.0 - c));
+            Point3d::from_coords(q[0] + 1.0, q[1] - 2.0, q[2] + 0.5)
+        };
+        let r = random(300, 5);
+        let mut local: Vec<[f64; 3]> = r
+            .chunks(3)
+            .map(|q| [4.0 * q[0], 2.0 * q[1], q[2]])
+            .collect();
+        local.extend((0..8).map(|i| {
+            [
+                4.0 * (i & 1) as f64,
+                2.0 * (i >> 1 & 1) as f64,
+                (i >> 2) as f64,
+            ]
+        }));
+        let points: Vec<Point3d> = local.into_iter().map(rotate).collect();
+        let b = approx_min_volume_box(&points).unwrap();
+        assert!((b.volume() - 8.0).abs() < 1e-9);
+        let mut extents = b.extents;
+        extents.sort_by(f64::total_cmp);
+        for (e, expected) in extents.iter().zip([1.0, 2.0, 4.0]) {
+            assert!((e - expected).abs() < 1e-9);
+        }
+        assert!(points.iter().all(|p| inside_box(&b, p)));
+        for corner in b.corners() {
+            assert!(points.iter().any(|p| p.is_equal(&corner, 1e-9)));
+        }
+
+        // Flat input gives a flat box.
+        let flat: Vec<Point3d> = [[0.0, 0.0], [2.0, 0.0], [2.0, 1.0], [0.0, 1.0], [1.0, 0.5]]
+            .map(|[x, y]| Point3d::from_coords(x, y, x + y))
+            .to_vec();
+        let b = approx_min_volume_box(&flat).unwrap();
+        assert!(b.volume().abs() < 1e-12);
+        assert!(flat.iter().all(|p| inside_box(&b, p)));
+        assert!(approx_min_volume_box(&[]).is_err());
+
+        // Every point on the hull: a box close to the cube around the sphere.
+        let r = random(1500, 11);
+        let sphere: Vec<Point3d> = r
+            .chunks(3)
+            .filter_map(|q| {
+                let v = [2.0 * q[0] - 1.0, 2.0 * q[1] - 1.0, 2.0 * q[2] - 1.0];
+                let length = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+                (length > 0.1)
+                    .then(|| Point3d::from_coords(v[0] / length, v[1] / length, v[2] / length))
+            })
+            .collect();
+        let b = approx_min_volume_box(&sphere).unwrap();
+        assert!(b.volume() > 7.0 && b.volume() < 8.0);
+        assert!(sphere.iter().all(|p| inside_box(&b, p)));
+    }
+}
@@ -0,0 +1,151 @@
+use geom::predicates::{orient2d, orient3d};
+use geom::{ConvexHull2d, ConvexHull3d, Point2d, Point3d};
+use std::collections::HashMap;
+
+fn random(n: usize, seed: u64) -> Vec<f64> {
+    let mut state = seed;
+    (0..n)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        })
+        .collect()
+}
+
+fn check_hull2d(hull: &ConvexHull2d, points: &[Point2d]) {
+    let n = hull.vertices.len();
+    for k in 0..n {
+        let (a, b) = (points[hull.vertices[k]], points[hull.vertices[(k + 1) % n]]);
+        for p in points.iter() {
+            assert!(orient2d(&a, &b, p) >= 0.0);
+        }
+        let c = points[hull.vertices[(k + 2) % n]];
+        assert!(orient2d(&a, &b, &c) > 0.0);
+    }
+}
+
+fn check_hull3d(hull: &ConvexHull3d, points: &[Point3d]) {
+    // Closed: every directed edge has its reverse.
+    let mut edges = HashMap::new();
+    for f in hull.faces.iter() {
+        for k in 0..3 {
+            *edges.entry((f[k], f[(k + 1) % 3])).or_insert(0) += 1;
+        }
+    }
+    for (&(a, b), &count) in edges.iter() {
+        assert_eq!(count, 1);
+        assert_eq!(edges.get(&(b, a)), Some(&1));
+    }
+    for (i, n) in hull.normals.iter().enumerate() {
+        let [a, b, c] = hull.face_points(i, points);
+        for p in points.iter() {
+            assert!(orient3d(&a, &b, &c, p) >= 0.0);
+        }
+        let centroid = [
+            (a.get_x() + b.get_x() + c.get_x()) / 3.0,
+            (a.get_y() + b.get_y() + c.get_y()) / 3.0,
+            (a.get_z() + b.get_z() + c.get_z()) / 3.0,
+        ];
+        let outward = Point3d::from_coords(
+            centroid[0] + n.get_x(),
+            centroid[1] + n.get_y(),
+            centroid[2] + n.get_z(),
+        );
+        assert!(orient3d(&a, &b, &c, &outward) < 0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convex_hull2d() {
+        let r = random(1000, 5);
+        let points: Vec<Point2d> = r
+            .chunks(2)
+            .map(|c| Point2d::from_coords(c[0], c[1]))
+            .collect();
+        let hull = ConvexHull2d::new(&points).unwrap();
+        assert!(hull.vertices.len() > 4);
+        check_hull2d(&hull, &points);
+
+        // Grid with collinear boundary points and duplicates.
+        let mut grid: Vec<Point2d> = (0..25)
+            .map(|i| Point2d::from_coords((i % 5) as f64, (i / 5) as f64))
+            .collect();
+        grid.push(Point2d::from_coords(4.0, 4.0));
+        grid.push(Point2d::from_coords(0.0, 0.0));
+        let hull = ConvexHull2d::new(&grid).unwrap();
+        assert_eq!(hull.vertices, vec![0, 4, 24, 20]);
+        check_hull2d(&hull, &grid);
+
+        let line: Vec<Point2d> = (0..5)
+            .map(|i| Point2d::from_coords(i as f64, 2.0 * i as f64))
+            .rev()
+            .collect();
+        assert_eq!(ConvexHull2d::new(&line).unwrap().vertices, vec![4, 0]);
+        let single = vec![Point2d::from_coords(1.0, 1.0); 3];
+        assert_eq!(ConvexHull2d::new(&single).unwrap().vertices, vec![0]);
+        assert!(ConvexHull2d::new(&[]).unwrap().vertices.is_empty());
+        assert!(ConvexHull2d::new(&[Point2d::from_coords(f64::NAN, 0.0)]).is_err());
+    }
+
+    #[test]
+    fn test_convex_hull3d() {
+        let r = random(1500, 9);
+        let points: Vec<Point3d> = r
+            .chunks(3)
+            .map(|c| Point3d::from_coords(c[0], c[1], c[2]))
+            .collect();
+        let hull = ConvexHull3d::new(&points).unwrap();
+        check_hull3d(&hull, &points);
+        // Points in general position give a simplicial polytope.
+        assert_eq!(hull.faces.len(), 2 * hull.vertices.len() - 4);
+
+        // Grid with points on faces and edges, and duplicated corners.
+        let mut grid: Vec<Point3d> = (0..27)
+            .map(|i| Point3d::from_coords((i % 3) as f64, (i / 3 % 3) as f64, (i / 9) as f64))
+            .collect();
+        grid.push(Point3d::from_coords(2.0, 2.0, 2.0));
+        grid.push(Point3d::from_coords(0.0, 0.0, 0.0));
+        let hull = ConvexHull3d::new(&grid).unwrap();
+        check_hull3d(&hull, &grid);
+        assert_eq!(hull.vertices, vec![0, 2, 6, 8, 18, 20, 24, 26]);
+        assert_eq!(hull.faces.len(), 12);
+        for n in hull.normals.iter() {
+            let axis = [n.get_x(), n.get_y(), n.get_z()];
+            assert_eq!(axis.iter().filter(|v| v.abs() == 1.0).count(), 1);
+        }
+
+        let flat: Vec<Point3d> = (0..10)
+            .map(|i| Point3d::from_coords(i as f64, (i * i) as f64, 0.0))
+            .collect();
+        let hull = ConvexHull3d::new(&flat).unwrap();
+        check_hull3d(&hull, &flat);
+        assert_eq!(hull.vertices, (0..10).collect::<Vec<_>>());
+        assert_eq!(hull.faces.len(), 2 * (10 - 2));
+        let up = hull.normals.iter().filter(|n| n.get_z() == 1.0).count();
+        let down = hull.normals.iter().filter(|n| n.get_z() == -1.0).count();
+        assert_eq!((up, down), (8, 8));
+
+        // Tilted square with its center and a duplicated corner.
+        let tilted: Vec<Point3d> = [(0, 0), (1, 0), (1, 1), (0, 1), (0, 0)]
+            .iter()
+            .map(|&(u, v)| Point3d::from_coords(u as f64, v as f64, (u + 2 * v) as f64))
+            .chain([Point3d::from_coords(0.5, 0.5, 1.5)])
+            .collect();
+        let hull = ConvexHull3d::new(&tilted).unwrap();
+        check_hull3d(&hull, &tilted);
+        assert_eq!(hull.vertices, vec![0, 1, 2, 3]);
+        assert_eq!(hull.faces.len(), 4);
+
+        let line: Vec<Point3d> = (0..5)
+            .map(|i| Point3d::from_coords(i as f64, 2.0 * i as f64, 0.0))
+            .collect();
+        assert!(ConvexHull3d::new(&line).is_err());
+        assert!(ConvexHull3d::new(&[]).is_err());
+    }
+}
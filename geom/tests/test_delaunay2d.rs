@@ -0,0 +1,240 @@
+use geom::delaunay2d::Refinement;
+use geom::predicates::{incircle, orient2d};
+use geom::{Domain2d, Point2d, Triangulation2d};
+use std::collections::HashSet;
+
+fn random_points(n: usize, seed: u64) -> Vec<Point2d> {
+    let mut state = seed;
+    let mut next = move || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 11) as f64 / (1u64 << 53) as f64
+    };
+    (0..n)
+        .map(|_| Point2d::from_coords(next(), next()))
+        .collect()
+}
+
+fn edges(tri: &Triangulation2d) -> HashSet<(usize, usize)> {
+    tri.triangles
+        .iter()
+        .flat_map(|t| (0..3).map(move |k| (t[k], t[(k + 1) % 3])))
+        .map(|(a, b)| (a.min(b), a.max(b)))
+        .collect()
+}
+
+fn square(domain: &mut Domain2d, x: f64, y: f64, size: f64) -> usize {
+    domain.add_loop(&[
+        Point2d::from_coords(x, y),
+        Point2d::from_coords(x + size, y),
+        Point2d::from_coords(x + size, y + size),
+        Point2d::from_coords(x, y + size),
+    ])
+}
+
+fn centroid(tri: &Triangulation2d, i: usize) -> (f64, f64) {
+    let p = tri.triangle_points(i);
+    (
+        p.iter().map(|q| q.get_x()).sum::<f64>() / 3.0,
+        p.iter().map(|q| q.get_y()).sum::<f64>() / 3.0,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delaunay() {
+        let points = random_points(300, 7);
+        let tri = Triangulation2d::delaunay(&points).unwrap();
+        for t in tri.triangles.iter() {
+            let [a, b, c] = t.map(|v| tri.points[v]);
+            assert!(orient2d(&a, &b, &c) > 0.0);
+            for p in points.iter() {
+                assert!(incircle(&a, &b, &c, p) <= 0.0);
+            }
+        }
+        // Euler: 2n - h - 2 triangles for h hull vertices.
+        let boundary: HashSet<usize> = tri.segments.iter().flatten().copied().collect();
+        assert_eq!(tri.triangles.len(), 2 * points.len() - boundary.len() - 2);
+        assert!(tri.area() > 0.9 && tri.area() < 1.0);
+
+        // Cocircular grid points and duplicates.
+        let mut grid: Vec<Point2d> = (0..25)
+            .map(|i| Point2d::from_coords((i % 5) as f64, (i / 5) as f64))
+            .collect();
+        grid.push(Point2d::from_coords(2.0, 2.0));
+        let tri = Triangulation2d::delaunay(&grid).unwrap();
+        assert_eq!(tri.points.len(), 26);
+        assert_eq!(tri.triangles.len(), 32);
+        assert!((tri.area() - 16.0).abs() < 1e-12);
+        assert!(tri.triangles.iter().flatten().all(|&v| v != 25));
+
+        let collinear: Vec<Point2d> = (0..4)
+            .map(|i| Point2d::from_coords(i as f64, 2.0 * i as f64))
+            .collect();
+        assert!(
+            Triangulation2d::delaunay(&collinear)
+                .unwrap()
+                .triangles
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_thin_hull() {
+        // Hull triangles whose circumcircles reach far outside are kept.
+        let sliver = [
+            Point2d::from_coords(0.0, 0.0),
+            Point2d::from_coords(10.0, 0.0),
+            Point2d::from_coords(5.0, 1e-9),
+        ];
+        let tri = Triangulation2d::delaunay(&sliver).unwrap();
+        assert_eq!(tri.triangles.len(), 1);
+        assert!((tri.area() - 5e-9).abs() < 1e-20);
+
+        let curve: Vec<Point2d> = (0..21)
+            .map(|i| Point2d::from_coords(i as f64, 1e-7 * ((i - 10) * (i - 10)) as f64))
+            .collect();
+        let tri = Triangulation2d::delaunay(&curve).unwrap();
+        assert_eq!(tri.triangles.len(), 19);
+        for t in tri.triangles.iter() {
+            let [a, b, c] = t.map(|v| tri.points[v]);
+            assert!(orient2d(&a, &b, &c) > 0.0);
+            for p in curve.iter() {
+                assert!(incircle(&a, &b, &c, p) <= 0.0);
+            }
+        }
+
+        let line: Vec<Point2d> = (0..5)
+            .map(|i| Point2d::from_coords(i as f64, 2.0 * i as f64))
+            .collect();
+        assert!(
+            Triangulation2d::delaunay(&line)
+                .unwrap()
+                .triangles
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_constrained() {
+        // A long thin diagonal segment crossing many Delaunay edges.
+        let mut domain = Domain2d::from_points(random_points(200, 11));
+        domain.convex_hull = true;
+        let a = domain.add_point(Point2d::from_coords(0.01, 0.02));
+        let b = domain.add_point(Point2d::from_coords(0.98, 0.95));
+        domain.add_segment(a, b);
+        let tri = Triangulation2d::constrained(&domain).unwrap();
+        assert!(edges(&tri).contains(&(a.min(b), a.max(b))));
+        assert!(tri.segments.contains(&[a, b]) || tri.segments.contains(&[b, a]));
+        let hull = Triangulation2d::delaunay(&domain.points).unwrap();
+        assert_eq!(tri.triangles.len(), hull.triangles.len());
+        assert!((tri.area() - hull.area()).abs() < 1e-12);
+        domain.convex_hull = false;
+        assert!(
+            Triangulation2d::constrained(&domain)
+                .unwrap()
+                .triangles
+                .is_empty()
+        );
+
+        // Segment through a collinear vertex is split there.
+        let mut domain = Domain2d::new();
+        square(&mut domain, 0.0, 0.0, 4.0);
+        let c = domain.add_point(Point2d::from_coords(2.0, 2.0));
+        let a = domain.add_point(Point2d::from_coords(1.0, 1.0));
+        let b = domain.add_point(Point2d::from_coords(3.0, 3.0));
+        domain.add_segment(a, b);
+        let tri = Triangulation2d::constrained(&domain).unwrap();
+        let e = edges(&tri);
+        assert!(e.contains(&(a.min(c), a.max(c))) && e.contains(&(c.min(b), c.max(b))));
+        assert_eq!(tri.segments.len(), 6);
+
+        let mut crossing = domain.clone();
+        let d = crossing.add_point(Point2d::from_coords(1.0, 2.5));
+        let e = crossing.add_point(Point2d::from_coords(2.5, 1.0));
+        crossing.add_segment(d, e);
+        assert!(Triangulation2d::constrained(&crossing).is_err());
+        domain.add_segment(0, 99);
+        assert!(Triangulation2d::constrained(&domain).is_err());
+    }
+
+    #[test]
+    fn test_holes() {
+        let mut domain = Domain2d::new();
+        // L-shaped outer boundary with a square hole.
+        domain.add_loop(&[
+            Point2d::from_coords(0.0, 0.0),
+            Point2d::from_coords(4.0, 0.0),
+            Point2d::from_coords(4.0, 2.0),
+            Point2d::from_coords(2.0, 2.0),
+            Point2d::from_coords(2.0, 4.0),
+            Point2d::from_coords(0.0, 4.0),
+        ]);
+        square(&mut domain, 0.5, 0.5, 1.0);
+        domain.add_hole(Point2d::from_coords(1.0, 1.0));
+        // A free point outside the domain is dropped.
+        domain.add_point(Point2d::from_coords(3.0, 3.0));
+        let tri = Triangulation2d::constrained(&domain).unwrap();
+        assert!((tri.area() - 11.0).abs() < 1e-12);
+        assert_eq!(tri.segments.len(), 10);
+        for i in 0..tri.triangles.len() {
+            let (x, y) = centroid(&tri, i);
+            assert!(!(x > 0.5 && x < 1.5 && y > 0.5 && y < 1.5));
+            assert!(!(x > 2.0 && y > 2.0));
+        }
+    }
+
+    #[test]
+    fn test_refinement() {
+        let mut domain = Domain2d::new();
+        square(&mut domain, 0.0, 0.0, 3.0);
+        square(&mut domain, 1.0, 1.0, 1.0);
+        domain.add_hole(Point2d::from_coords(1.5, 1.5));
+        let refinement = Refinement {
+            min_angle: 28f64.to_radians(),
+            max_area: Some(0.02),
+            ..Refinement::default()
+        };
+        let tri = Triangulation2d::refined(&domain, &refinement).unwrap();
+        assert!((tri.area() - 8.0).abs() < 1e-9);
+        assert!(tri.min_angle() >= 28f64.to_radians() - 1e-9);
+        for i in 0..tri.triangles.len() {
+            let [a, b, c] = tri.triangle_points(i);
+            assert!(0.5 * orient2d(&a, &b, &c) <= 0.02);
+        }
+        for (p, q) in tri.points.iter().zip(domain.points.iter()) {
+            assert!(p.is_equal(q, 0.0));
+        }
+        // Boundary subsegments stay on the input squares.
+        for s in tri.segments.iter() {
+            let (p, q) = (tri.points[s[0]], tri.points[s[1]]);
+            let on_square = |lo: f64, hi: f64| {
+                let on = |v: f64| (v - lo).abs() < 1e-12 || (v - hi).abs() < 1e-12;
+                (on(p.get_x()) && p.get_x() == q.get_x())
+                    || (on(p.get_y()) && p.get_y() == q.get_y())
+            };
+            assert!(on_square(0.0, 3.0) || on_square(1.0, 2.0));
+        }
+
+        // Small input angle: refinement must still terminate.
+        let mut domain = Domain2d::new();
+        domain.add_loop(&[
+            Point2d::from_coords(0.0, 0.0),
+            Point2d::from_coords(10.0, 0.0),
+            Point2d::from_coords(10.0, 1.0),
+        ]);
+        let tri = Triangulation2d::refined(&domain, &Refinement::default()).unwrap();
+        assert!((tri.area() - 5.0).abs() < 1e-9);
+
+        let limited = Refinement {
+            max_area: Some(1e-6),
+            max_points: 100,
+            ..Refinement::default()
+        };
+        assert!(Triangulation2d::refined(&domain, &limited).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use geom::delaunay3d::Refinement;
+use geom::predicates::{insphere, orient3d};
+use geom::{Mesh, Point3d, Tetrahedralization};
+use std::collections::HashSet;
+
+fn random_points(n: usize, seed: u64) -> Vec<Point3d> {
+    let mut state = seed;
+    let mut next = move || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 11) as f64 / (1u64 << 53) as f64
+    };
+    (0..n)
+        .map(|_| Point3d::from_coords(next(), next(), next()))
+        .collect()
+}
+
+// Triangulated box surface, outward facing unless inverted.
+fn add_box(mesh: &mut Mesh, lo: f64, hi: f64, inverted: bool) {
+    let base = mesh.points.len();
+    for i in 0..8 {
+        let c = |bit: usize| if i & bit == 0 { lo } else { hi };
+        mesh.points.push(Point3d::from_coords(c(1), c(2), c(4)));
+    }
+    let quads = [
+        [0, 2, 3, 1],
+        [4, 5, 7, 6],
+        [0, 1, 5, 4],
+        [2, 6, 7, 3],
+        [0, 4, 6, 2],
+        [1, 3, 7, 5],
+    ];
+    for q in quads {
+        for mut f in [vec![q[0], q[1], q[2]], vec![q[0], q[2], q[3]]] {
+            if inverted {
+                f.reverse();
+            }
+            mesh.faces.push(f.iter().map(|v| v + base).collect());
+        }
+    }
+}
+
+fn faces(tet: &Tetrahedralization) -> HashSet<[usize; 3]> {
+    let mut faces = HashSet::new();
+    for t in tet.tetrahedra.iter() {
+        for i in 0..4 {
+            let mut f = [t[(i + 1) % 4], t[(i + 2) % 4], t[(i + 3) % 4]];
+            f.sort_unstable();
+            faces.insert(f);
+        }
+    }
+    faces
+}
+
+fn check_boundary(tet: &Tetrahedralization, lo: f64, hi: f64) {
+    let faces = faces(tet);
+    for b in tet.boundary.iter() {
+        let mut f = *b;
+        f.sort_unstable();
+        assert!(faces.contains(&f));
+    }
+    for p in tet.points.iter() {
+        for v in [p.get_x(), p.get_y(), p.get_z()] {
+            assert!(v >= lo && v <= hi);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delaunay() {
+        let points = random_points(200, 3);
+        let tet = Tetrahedralization::delaunay(&points).unwrap();
+        for i in 0..tet.tetrahedra.len() {
+            let [a, b, c, d] = tet.tetrahedron_points(i);
+            assert!(orient3d(&a, &b, &c, &d) > 0.0);
+            for p in points.iter() {
+                assert!(insphere(&a, &b, &c, &d, p) <= 0.0);
+            }
+        }
+        assert!(tet.volume() > 0.8 && tet.volume() < 1.0);
+
+        // Cospherical grid points and a duplicate.
+        let mut grid: Vec<Point3d> = (0..27)
+            .map(|i| Point3d::from_coords((i % 3) as f64, (i / 3 % 3) as f64, (i / 9) as f64))
+            .collect();
+        grid.push(Point3d::from_coords(1.0, 1.0, 1.0));
+        let tet = Tetrahedralization::delaunay(&grid).unwrap();
+        assert_eq!(tet.points.len(), 28);
+        assert!((tet.volume() - 8.0).abs() < 1e-12);
+        assert!(tet.tetrahedra.iter().flatten().all(|&v| v != 27));
+        assert!(tet.tetrahedra.iter().all(|t| {
+            let [a, b, c, d] = t.map(|v| tet.points[v]);
+            orient3d(&a, &b, &c, &d) > 0.0
+        }));
+    }
+
+    #[test]
+    fn test_thin_hull() {
+        // Hull tetrahedra whose circumspheres reach far outside are kept.
+        let sliver = [
+            Point3d::from_coords(0.0, 0.0, 0.0),
+            Point3d::from_coords(10.0, 0.0, 0.0),
+            Point3d::from_coords(0.0, 10.0, 0.0),
+            Point3d::from_coords(3.0, 3.0, 1e-9),
+        ];
+        let tet = Tetrahedralization::delaunay(&sliver).unwrap();
+        assert_eq!(tet.tetrahedra.len(), 1);
+        assert!((tet.volume() - 50.0 / 3.0 * 1e-9).abs() < 1e-20);
+
+        // Nearly flat convex cap: every point is a hull vertex.
+        let cap: Vec<Point3d> = (0..25)
+            .map(|i| {
+                let (x, y) = ((i % 5) as f64 - 2.0, (i / 5) as f64 - 2.0);
+                Point3d::from_coords(x, y, 1e-7 * (x * x + y * y))
+            })
+            .collect();
+        let tet = Tetrahedralization::delaunay(&cap).unwrap();
+        assert!(!tet.tetrahedra.is_empty());
+        let used: HashSet<usize> = tet.tetrahedra.iter().flatten().copied().collect();
+        assert_eq!(used.len(), cap.len());
+        for i in 0..tet.tetrahedra.len() {
+            let [a, b, c, d] = tet.tetrahedron_points(i);
+            assert!(orient3d(&a, &b, &c, &d) > 0.0);
+            for p in cap.iter() {
+                assert!(insphere(&a, &b, &c, &d, p) <= 0.0);
+            }
+        }
+        // Below the plane of the corners lies the piecewise linear paraboloid,
+        // whose integral over each grid square is the mean of its corners.
+        let f = |x: f64, y: f64| 1e-7 * (x * x + y * y);
+        let below: f64 = (0..16)
+            .map(|i| {
+                let (x, y) = ((i % 4) as f64 - 2.0, (i / 4) as f64 - 2.0);
+                (f(x, y) + f(x + 1.0, y) + f(x, y + 1.0) + f(x + 1.0, y + 1.0)) / 4.0
+            })
+            .sum();
+        assert!((tet.volume() - (16.0 * f(2.0, 2.0) - below)).abs() < 1e-18);
+
+        let flat: Vec<Point3d> = cap
+            .iter()
+            .map(|p| Point3d::from_coords(p.get_x(), p.get_y(), 0.0))
+            .collect();
+        assert!(
+            Tetrahedralization::delaunay(&flat)
+                .unwrap()
+                .tetrahedra
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_surface() {
+        let mut mesh = Mesh::new();
+        add_box(&mut mesh, 0.0, 1.0, false);
+        let tet = Tetrahedralization::from_surface(&mesh).unwrap();
+        assert!((tet.volume() - 1.0).abs() < 1e-12);
+        check_boundary(&tet, 0.0, 1.0);
+        for (p, q) in tet.points.iter().zip(mesh.points.iter()) {
+            assert!(p.is_equal(q, 0.0));
+        }
+
+        // Box with a box shaped cavity.
+        let mut mesh = Mesh::new();
+        add_box(&mut mesh, 0.0, 3.0, false);
+        add_box(&mut mesh, 1.0, 2.0, true);
+        let tet = Tetrahedralization::from_surface(&mesh).unwrap();
+        assert!((tet.volume() - 26.0).abs() < 1e-9);
+        check_boundary(&tet, 0.0, 3.0);
+        for i in 0..tet.tetrahedra.len() {
+            let p = tet.tetrahedron_points(i);
+            let c = |k: fn(&Point3d) -> f64| p.iter().map(k).sum::<f64>() / 4.0;
+            let inner = |v: f64| v > 1.0 && v < 2.0;
+            assert!(
+                !(inner(c(Point3d::get_x)) && inner(c(Point3d::get_y)) && inner(c(Point3d::get_z)))
+            );
+        }
+
+        let mut open = mesh.clone();
+        open.faces.pop();
+        assert!(Tetrahedralization::from_surface(&open).is_err());
+        let mut quad = mesh.clone();
+        quad.faces[0].push(5);
+        assert!(Tetrahedralization::from_surface(&quad).is_err());
+    }
+
+    #[test]
+    fn test_refinement() {
+        let mut mesh = Mesh::new();
+        add_box(&mut mesh, 0.0, 1.0, false);
+        let refinement = Refinement {
+            max_volume: Some(0.005),
+            ..Refinement::default()
+        };
+        let tet = Tetrahedralization::refined(&mesh, &refinement).unwrap();
+        assert!((tet.volume() - 1.0).abs() < 1e-9);
+        assert!(tet.max_radius_edge() <= 2.0 + 1e-9);
+        assert!(tet.tetrahedra.len() >= 200);
+        for i in 0..tet.tetrahedra.len() {
+            let [a, b, c, d] = tet.tetrahedron_points(i);
+            assert!(orient3d(&a, &b, &c, &d) / 6.0 <= 0.005);
+        }
+        check_boundary(&tet, 0.0, 1.0);
+        let area: f64 = tet
+            .boundary
+            .iter()
+            .map(|f| {
+                let [a, b, c] = f.map(|v| tet.points[v]);
+                let u = [
+                    b.get_x() - a.get_x(),
+                    b.get_y() - a.get_y(),
+                    b.get_z() - a.get_z(),
+                ];
+                let w = [
+                    c.get_x() - a.get_x(),
+                    c.get_y() - a.get_y(),
+                    c.get_z() - a.get_z(),
+                ];
+                let n = [
+                    u[1] * w[2] - u[2] * w[1],
+                    u[2] * w[0] - u[0] * w[2],
+                    u[0] * w[1] - u[1] * w[0],
+                ];
+                0.5 * (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt()
+            })
+            .sum();
+        assert!((area - 6.0).abs() < 1e-9);
+
+        let limited = Refinement {
+            max_volume: Some(1e-6),
+            max_points: 100,
+            ..Refinement::default()
+        };
+        assert!(Tetrahedralization::refined(&mesh, &limited).is_err());
+    }
+}
@@ -0,0 +1,323 @@
+use geom::io::LengthUnit;
+use geom::io::dxf::{Block, DxfDrawing, DxfEntity, DxfGeometry, Layer, read_str};
+use geom::{BSplineCurve2d, Curve2d, Point2d, TrimmedCurve2d};
+
+fn close(a: &Point2d, x: f64, y: f64) -> bool {
+    (a.get_x() - x).abs() < 1e-9 && (a.get_y() - y).abs() < 1e-9
+}
+
+fn dxf(body: &[(i32, &str)]) -> String {
+    body.iter()
+        .map(|(code, value)| format!("{}\n{}\n", code, value))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_entities() {
+        let text = dxf(&[
+            (0, "SECTION"),
+            (2, "HEADER"),
+            (9, "$INSUNITS"),
+            (70, "4"),
+            (0, "ENDSEC"),
+            (0, "SECTION"),
+            (2, "TABLES"),
+            (0, "TABLE"),
+            (2, "LAYER"),
+            (0, "LAYER"),
+            (2, "Walls"),
+            (62, "1"),
+            (0, "ENDTAB"),
+            (0, "ENDSEC"),
+            (0, "SECTION"),
+            (2, "ENTITIES"),
+            (0, "LINE"),
+            (8, "Walls"),
+            (10, "0"),
+            (20, "0"),
+            (11, "3"),
+            (21, "4"),
+            (0, "ARC"),
+            (10, "1"),
+            (20, "1"),
+            (40, "2"),
+            (50, "0"),
+            (51, "90"),
+            (0, "ELLIPSE"),
+            (10, "0"),
+            (20, "0"),
+            (11, "0"),
+            (21, "2"),
+            (40, "0.5"),
+            (0, "LWPOLYLINE"),
+            (90, "3"),
+            (70, "1"),
+            (10, "0"),
+            (20, "0"),
+            (10, "2"),
+            (20, "0"),
+            (42, "1"),
+            (10, "2"),
+            (20, "2"),
+            (0, "SPLINE"),
+            (70, "8"),
+            (71, "2"),
+            (40, "0"),
+            (40, "0"),
+            (40, "0"),
+            (40, "1"),
+            (40, "1"),
+            (40, "1"),
+            (10, "0"),
+            (20, "0"),
+            (10, "1"),
+            (20, "2"),
+            (10, "2"),
+            (20, "0"),
+            (0, "SPLINE"),
+            (71, "3"),
+            (11, "0"),
+            (21, "0"),
+            (0, "HATCH"),
+            (0, "ENDSEC"),
+            (0, "EOF"),
+        ]);
+        let drawing = read_str(&text).unwrap();
+        assert_eq!(drawing.units, Some(LengthUnit::Millimetre));
+        assert_eq!(drawing.get_layer("Walls").unwrap().color, 1);
+        assert_eq!(drawing.entities.len(), 5);
+        assert_eq!(drawing.skipped.len(), 2);
+        assert_eq!(drawing.entities[0].layer, "Walls");
+        assert_eq!(drawing.entities[1].layer, "0");
+
+        let curves: Vec<TrimmedCurve2d> = drawing
+            .entities
+            .iter()
+            .flat_map(|e| e.geometry.to_curves())
+            .collect();
+        assert!(close(&curves[0].end_point(), 3.0, 4.0));
+        assert!(close(&curves[1].start_point(), 3.0, 1.0));
+        assert!(close(&curves[1].end_point(), 1.0, 3.0));
+        assert!(curves[2].is_closed());
+        assert!(close(
+            &curves[2].value(0.5 * std::f64::consts::PI),
+            -1.0,
+            0.0
+        ));
+
+        // Polyline: straight, semicircle bulge, closing straight.
+        assert_eq!(curves.len(), 7);
+        assert!(close(&curves[4].start_point(), 2.0, 0.0));
+        assert!(close(&curves[4].end_point(), 2.0, 2.0));
+        assert!(close(&curves[4].value(0.0), 3.0, 1.0));
+        assert!(close(&curves[6].value(0.5), 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_mirrored_extrusion() {
+        let text = dxf(&[
+            (0, "SECTION"),
+            (2, "ENTITIES"),
+            (0, "ARC"),
+            (10, "5"),
+            (20, "0"),
+            (40, "1"),
+            (50, "0"),
+            (51, "90"),
+            (230, "-1"),
+            (0, "ENDSEC"),
+            (0, "EOF"),
+        ]);
+        let drawing = read_str(&text).unwrap();
+        let curve = &drawing.entities[0].geometry.to_curves()[0];
+        // OCS x points along world -x.
+        assert!(close(&curve.start_point(), -5.0, 1.0));
+        assert!(close(&curve.end_point(), -6.0, 0.0));
+    }
+
+    #[test]
+    fn test_explode_inserts() {
+        let text = dxf(&[
+            (0, "SECTION"),
+            (2, "BLOCKS"),
+            (0, "BLOCK"),
+            (2, "Part"),
+            (10, "1"),
+            (20, "0"),
+            (0, "LINE"),
+            (8, "0"),
+            (10, "1"),
+            (20, "0"),
+            (11, "2"),
+            (21, "0"),
+            (0, "CIRCLE"),
+            (8, "Holes"),
+            (10, "1"),
+            (20, "1"),
+            (40, "0.5"),
+            (0, "ENDBLK"),
+            (0, "ENDSEC"),
+            (0, "SECTION"),
+            (2, "ENTITIES"),
+            (0, "INSERT"),
+            (8, "Parts"),
+            (2, "Part"),
+            (10, "10"),
+            (20, "10"),
+            (41, "2"),
+            (42, "2"),
+            (50, "90"),
+            (0, "ENDSEC"),
+            (0, "EOF"),
+        ]);
+        let drawing = read_str(&text).unwrap();
+        assert_eq!(drawing.get_block("Part").unwrap().entities.len(), 2);
+        let exploded = drawing.explode().unwrap();
+        assert_eq!(exploded.len(), 2);
+        assert_eq!(exploded[0].layer, "Parts");
+        assert_eq!(exploded[1].layer, "Holes");
+        let line = &exploded[0].geometry.to_curves()[0];
+        assert!(close(&line.start_point(), 10.0, 10.0));
+        assert!(close(&line.end_point(), 10.0, 12.0));
+        match &exploded[1].geometry {
+            DxfGeometry::Curve(c) => match &c.basis {
+                Curve2d::Circle(circle) => {
+                    assert!((circle.radius - 1.0).abs() < 1e-12);
+                    assert!(close(&circle.position.origin, 8.0, 10.0));
+                }
+                _ => panic!("expected a circle"),
+            },
+            _ => panic!("expected a curve"),
+        }
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let mark = TrimmedCurve2d::from_segment(
+            &Point2d::from_coords(0.0, 0.0),
+            &Point2d::from_coords(1.0, 1.0),
+        )
+        .unwrap();
+        let spline = BSplineCurve2d::from_bezier(vec![
+            Point2d::from_coords(0.0, 0.0),
+            Point2d::from_coords(1.0, 2.0),
+            Point2d::from_coords(3.0, 2.0),
+            Point2d::from_coords(4.0, 0.0),
+        ])
+        .unwrap();
+        let mut drawing = DxfDrawing::new();
+        drawing.units = Some(LengthUnit::Inch);
+        drawing.layers.push(Layer {
+            name: "Outline".to_string(),
+            color: 3,
+        });
+        drawing.blocks.push(Block {
+            name: "Mark".to_string(),
+            base: Point2d::from_coords(0.0, 0.0),
+            entities: vec![DxfEntity {
+                layer: "0".to_string(),
+                geometry: DxfGeometry::Curve(mark),
+            }],
+        });
+        let source = dxf(&[
+            (0, "SECTION"),
+            (2, "BLOCKS"),
+            (0, "BLOCK"),
+            (2, "Mark"),
+            (10, "0"),
+            (20, "0"),
+            (0, "ENDBLK"),
+            (0, "ENDSEC"),
+            (0, "SECTION"),
+            (2, "ENTITIES"),
+            (0, "INSERT"),
+            (2, "Mark"),
+            (10, "5"),
+            (20, "1"),
+            (41, "3"),
+            (42, "-3"),
+            (50, "30"),
+            (0, "ARC"),
+            (8, "Outline"),
+            (10, "0"),
+            (20, "0"),
+            (40, "2"),
+            (50, "270"),
+            (51, "45"),
+            (0, "ENDSEC"),
+            (0, "EOF"),
+        ]);
+        let source = read_str(&source).unwrap();
+        drawing.entities = source.entities.clone();
+        drawing.entities.push(DxfEntity {
+            layer: "Curves".to_string(),
+            geometry: DxfGeometry::Curve(TrimmedCurve2d::from_curve(Curve2d::BSpline(spline))),
+        });
+
+        let mut out = Vec::new();
+        drawing.write(&mut out).unwrap();
+        let read = read_str(&String::from_utf8(out).unwrap()).unwrap();
+        assert!(read.skipped.is_empty());
+        assert_eq!(read.units, Some(LengthUnit::Inch));
+        assert_eq!(read.get_layer("Outline").unwrap().color, 3);
+        assert!(read.get_layer("Curves").is_some());
+        assert_eq!(read.entities.len(), 3);
+
+        let before = drawing.explode().unwrap();
+        let after = read.explode().unwrap();
+        assert_eq!(before.len(), after.len());
+        for (a, b) in before.iter().zip(after.iter()) {
+            assert_eq!(a.layer, b.layer);
+            let (ca, cb) = (&a.geometry.to_curves()[0], &b.geometry.to_curves()[0]);
+            for t in [0.0, 0.3, 0.7, 1.0] {
+                let u = ca.first + t * (ca.last - ca.first);
+                let v = cb.first + t * (cb.last - cb.first);
+                let (p, q) = (ca.value(u), cb.value(v));
+                assert!(close(&p, q.get_x(), q.get_y()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_polyline_missing_bulges() {
+        let empty = DxfGeometry::Polyline {
+            vertices: Vec::new(),
+            bulges: Vec::new(),
+            closed: false,
+        };
+        assert!(empty.to_curves().is_empty());
+
+        // Only the first segment has a bulge; the others are straight.
+        let square = DxfGeometry::Polyline {
+            vertices: vec![
+                Point2d::from_coords(0.0, 0.0),
+                Point2d::from_coords(1.0, 0.0),
+                Point2d::from_coords(1.0, 1.0),
+                Point2d::from_coords(0.0, 1.0),
+            ],
+            bulges: vec![1.0],
+            closed: true,
+        };
+        assert_eq!(square.to_curves().len(), 4);
+        let mut drawing = DxfDrawing::new();
+        drawing.entities.push(DxfEntity {
+            layer: "0".to_string(),
+            geometry: square,
+        });
+        let mut out = Vec::new();
+        drawing.write(&mut out).unwrap();
+        let read = read_str(&String::from_utf8(out).unwrap()).unwrap();
+        let DxfGeometry::Polyline {
+            vertices, bulges, ..
+        } = &read.entities[0].geometry
+        else {
+            panic!()
+        };
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(bulges, &vec![1.0, 0.0, 0.0, 0.0]);
+    }
+}
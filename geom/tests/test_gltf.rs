@@ -0,0 +1,132 @@
+use geom::Matrix3;
+use geom::Mesh;
+use geom::Point3d;
+use geom::Quaternion;
+use geom::Trsf3d;
+use geom::TrsfForm;
+use geom::XYZ;
+use geom::io::gltf::{Material, NodeTransform, Scene};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quad() -> Mesh {
+        Mesh::from_points_faces(
+            vec![
+                Point3d::from_coords(0.0, 0.0, 0.0),
+                Point3d::from_coords(1.0, 0.0, 0.0),
+                Point3d::from_coords(1.0, 1.0, 0.0),
+                Point3d::from_coords(0.0, 1.0, 0.0),
+            ],
+            vec![vec![0, 1, 2, 3]],
+        )
+    }
+
+    fn sample_scene() -> Scene {
+        let mut scene = Scene::new();
+        let red = scene.add_material(Material::from_color("red", [1.0, 0.0, 0.0, 1.0]));
+        let mesh = scene.add_mesh("quad", quad(), Some(red));
+        let mut trsf = Trsf3d::new();
+        trsf.loc = XYZ::from_coords(1.0, 2.0, 3.0);
+        trsf.scale = 2.0;
+        let root = scene.add_node("root", None, NodeTransform::Trsf(trsf));
+        let child = scene.add_node(
+            "child",
+            Some(mesh),
+            NodeTransform::Trs {
+                translation: XYZ::from_coords(0.0, 0.0, 1.0),
+                rotation: Quaternion::from_coords(0.0, 0.0, 1.0, 1.0),
+                scale: XYZ::from_coords(1.0, 1.0, 1.0),
+            },
+        );
+        scene.add_child(root, child);
+        scene.add_root(root);
+        scene
+    }
+
+    #[test]
+    fn test_gltf_json() {
+        let (json, bin) = sample_scene().to_gltf(Some("scene.bin")).unwrap();
+        // 4 positions + 4 normals (12 bytes each) + 6 indices (4 bytes each)
+        assert_eq!(bin.len(), 4 * 12 + 4 * 12 + 6 * 4);
+        assert!(json.contains("\"version\":\"2.0\""));
+        assert!(json.contains("\"uri\":\"scene.bin\""));
+        assert!(json.contains(&format!("\"byteLength\":{}", bin.len())));
+        assert!(json.contains("\"baseColorFactor\":[1,0,0,1]"));
+        assert!(json.contains("\"matrix\":[2,0,0,0,0,2,0,0,0,0,2,0,1,2,3,1]"));
+        assert!(json.contains("\"children\":[1]"));
+        assert!(json.contains("\"min\":[0,0,0],\"max\":[1,1,0]"));
+        assert!(json.contains("\"rotation\":[0,0,0.707106781186"));
+
+        let (json, _) = sample_scene()
+            .to_gltf(Some("parts/my scene#1%.bin"))
+            .unwrap();
+        assert!(json.contains("\"uri\":\"parts/my%20scene%231%25.bin\""));
+    }
+
+    #[test]
+    fn test_glb_layout() {
+        let glb = sample_scene().to_glb().unwrap();
+        let u32_at = |i: usize| u32::from_le_bytes([glb[i], glb[i + 1], glb[i + 2], glb[i + 3]]);
+        assert_eq!(&glb[0..4], b"glTF");
+        assert_eq!(u32_at(4), 2);
+        assert_eq!(u32_at(8) as usize, glb.len());
+        let json_len = u32_at(12) as usize;
+        assert_eq!(json_len % 4, 0);
+        assert_eq!(&glb[16..20], b"JSON");
+        let json = std::str::from_utf8(&glb[20..20 + json_len]).unwrap();
+        assert!(!json.contains("\"uri\""));
+        let bin_header = 20 + json_len;
+        assert_eq!(u32_at(bin_header), 120);
+        assert_eq!(&glb[bin_header + 4..bin_header + 8], b"BIN\0");
+        let x = f32::from_le_bytes([
+            glb[bin_header + 20],
+            glb[bin_header + 21],
+            glb[bin_header + 22],
+            glb[bin_header + 23],
+        ]);
+        assert_eq!(x, 1.0);
+    }
+
+    #[test]
+    fn test_point_cloud_and_validation() {
+        let mut scene = Scene::new();
+        let cloud = Mesh::from_points_faces(vec![Point3d::from_coords(1.0, 2.0, 3.0)], vec![]);
+        let mesh = scene.add_mesh("cloud", cloud, None);
+        let node = scene.add_node("cloud", Some(mesh), NodeTransform::Identity);
+        scene.add_root(node);
+        let (json, _) = scene.to_gltf(None).unwrap();
+        assert!(json.contains("\"mode\":0"));
+
+        let a = scene.add_node("a", None, NodeTransform::Identity);
+        let b = scene.add_node("b", None, NodeTransform::Identity);
+        scene.add_child(a, b);
+        scene.add_child(b, a);
+        assert!(scene.to_gltf(None).is_err());
+    }
+
+    #[test]
+    fn test_quaternion_matrix() {
+        let q = Quaternion::from_coords(0.1, -0.3, 0.2, 0.9).normalize_new();
+        let m: Matrix3 = q.get_matrix();
+        let q2 = Quaternion::from_matrix(&m);
+        assert!(q.is_equal(&q2, 1e-12));
+        let half_turn = Quaternion::from_coords(1.0, 0.0, 0.0, 0.0);
+        let back = Quaternion::from_matrix(&half_turn.get_matrix());
+        assert!(back.is_equal(&half_turn, 1e-12));
+
+        let none = XYZ::new();
+        let shift = XYZ::from_coords(1.0, 2.0, 3.0);
+        let identity = Quaternion::new();
+        for (rotation, translation, form) in [
+            (&identity, none, TrsfForm::Identity),
+            (&identity, shift, TrsfForm::Translation),
+            (&q, none, TrsfForm::Rotation),
+            (&q, shift, TrsfForm::CompoundTrsf),
+        ] {
+            let trsf = Trsf3d::from_quaternion_translation(rotation, translation);
+            assert_eq!(trsf.trsf_type, form);
+        }
+    }
+}
@@ -0,0 +1,492 @@
+use geom::io::LengthUnit;
+use geom::io::iges::file::{Entity, IgesFile, Parameter};
+use geom::io::iges::{IgesGeometry, IgesWriter, read_str};
+use geom::{
+    BSplineCurve3d, Circle3d, Cone, CoordinateSystem3d, Direction3d, Ellipse3d, Hyperbola3d,
+    Line3d, Parabola3d, Point3d, Torus,
+};
+
+fn tilted() -> CoordinateSystem3d {
+    CoordinateSystem3d::from_location_direction_xdirection(
+        Point3d::from_coords(1.0, 2.0, 3.0),
+        Direction3d::from_coords(0.0, 1.0, 1.0),
+        Direction3d::from_coords(1.0, 0.0, 0.0),
+    )
+}
+
+fn at(position: &CoordinateSystem3d, u: f64, v: f64) -> Point3d {
+    let (ox, oy, oz) = position.get_origin().get_coords();
+    let (xx, xy, xz) = position.vxdir.get_coords();
+    let (yx, yy, yz) = position.vydir.get_coords();
+    Point3d::from_coords(
+        ox + u * xx + v * yx,
+        oy + u * xy + v * yy,
+        oz + u * xz + v * yz,
+    )
+}
+
+fn r(v: f64) -> Parameter {
+    Parameter::Real(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_round_trip() {
+        let mut file = IgesFile::new();
+        file.start = vec!["sample".to_string()];
+        file.global.push(Parameter::String("x".repeat(100)));
+        let mut entity = Entity::new(116, 0, vec![r(1.5), Parameter::Integer(-2), r(1e-9)]);
+        entity.label = "POINT".to_string();
+        entity.color = 3;
+        file.add(entity);
+        file.add(Entity::new(
+            406,
+            15,
+            vec![Parameter::Integer(1), Parameter::String("a,b;c".repeat(20))],
+        ));
+        let text = file.to_string();
+        for line in text.lines() {
+            assert_eq!(line.len(), 80, "{}", line);
+        }
+        assert!(
+            text.lines()
+                .last()
+                .unwrap()
+                .starts_with("S0000001G0000002D0000004P")
+        );
+        assert_eq!(IgesFile::parse(&text).unwrap(), file);
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        let global = |rest: &str| format!("{:<72}S{:07}\n{:<72}G{:07}\n", "", 1, rest, 1);
+        for rest in [
+            "99999999999999999999999H,,1H;;",
+            "18446744073709551615H,,1H;;",
+        ] {
+            assert!(IgesFile::parse(&global(rest)).is_err());
+        }
+        for rest in ["1H", "1H,,1H"] {
+            let _ = IgesFile::parse(&global(rest));
+        }
+
+        // No edit of a valid file makes the reader panic.
+        let mut writer = IgesWriter::new();
+        writer.add(&IgesGeometry::CircularArc {
+            circle: Circle3d {
+                position: tilted(),
+                radius: 2.0,
+            },
+            first: 0.5,
+            last: 2.0,
+        });
+        writer.add(&IgesGeometry::Line {
+            line: Line3d { pos: tilted().axis },
+            first: 1.0,
+            last: 3.0,
+        });
+        let text = writer.write_str();
+        assert!(read_str(&text).is_ok());
+        for i in 0..text.len() {
+            if text.as_bytes()[i] == b'\n' {
+                continue;
+            }
+            for c in ["9", ",", ";", "H", "-", " ", "é", "99999999999999999999"] {
+                let _ = read_str(&format!("{}{}{}", &text[..i], c, &text[i + 1..]));
+            }
+            let _ = read_str(&text[..i]);
+        }
+    }
+
+    #[test]
+    fn test_units() {
+        let mut writer = IgesWriter::new();
+        assert_eq!(
+            read_str(&writer.write_str()).unwrap().length_unit,
+            Some(1e-3)
+        );
+        writer.length_unit = LengthUnit::Inch;
+        assert_eq!(
+            read_str(&writer.write_str()).unwrap().length_unit,
+            Some(0.0254)
+        );
+    }
+
+    #[test]
+    fn test_curves_round_trip() {
+        let mut writer = IgesWriter::new();
+        let circle = Circle3d {
+            position: tilted(),
+            radius: 2.0,
+        };
+        let arc = writer.add(&IgesGeometry::CircularArc {
+            circle,
+            first: 0.5,
+            last: 2.0,
+        });
+        let reversed = writer.add(&IgesGeometry::CircularArc {
+            circle,
+            first: 2.0,
+            last: 0.5,
+        });
+        let ellipse = writer.add(&IgesGeometry::EllipticArc {
+            ellipse: Ellipse3d {
+                position: tilted(),
+                major_radius: 3.0,
+                minor_radius: 1.0,
+            },
+            first: 0.0,
+            last: 2.0 * std::f64::consts::PI,
+        });
+        let hyperbola = writer.add(&IgesGeometry::HyperbolicArc {
+            hyperbola: Hyperbola3d {
+                position: tilted(),
+                major_radius: 2.0,
+                minor_radius: 1.0,
+            },
+            first: -1.0,
+            last: 0.5,
+        });
+        let parabola = writer.add(&IgesGeometry::ParabolicArc {
+            parabola: Parabola3d {
+                pos: tilted(),
+                focal_length: 0.25,
+            },
+            first: -1.0,
+            last: 2.0,
+        });
+        let line = writer.add(&IgesGeometry::Line {
+            line: Line3d { pos: tilted().axis },
+            first: 1.0,
+            last: 3.0,
+        });
+        let h = 0.5f64.sqrt();
+        let quarter = BSplineCurve3d::from_poles_weights_knots(
+            2,
+            vec![
+                Point3d::from_coords(1.0, 0.0, 0.0),
+                Point3d::from_coords(1.0, 1.0, 0.0),
+                Point3d::from_coords(0.0, 1.0, 0.0),
+            ],
+            vec![1.0, h, 1.0],
+            vec![0.0, 1.0],
+            vec![3, 3],
+        )
+        .unwrap();
+        let spline = writer.add(&IgesGeometry::BSplineCurve {
+            curve: quarter,
+            first: 0.0,
+            last: 1.0,
+        });
+
+        let model = read_str(&writer.write_str()).unwrap();
+        assert!(model.skipped.is_empty(), "{:?}", model.skipped);
+        let IgesGeometry::CircularArc {
+            circle: c,
+            first,
+            last,
+        } = model.get(arc).unwrap()
+        else {
+            panic!()
+        };
+        assert!((c.radius - 2.0).abs() < 1e-12);
+        assert!((first - 0.5).abs() < 1e-12 && (last - 2.0).abs() < 1e-12);
+        assert!(c.position.vxdir.is_equal(&tilted().vxdir, 1e-12));
+        assert!(
+            c.position
+                .axis
+                .direction
+                .is_equal(&tilted().axis.direction, 1e-12)
+        );
+        assert!(
+            c.position
+                .get_origin()
+                .is_equal(&Point3d::from_coords(1.0, 2.0, 3.0), 1e-12)
+        );
+
+        // The reversed arc covers the same points with the opposite axis.
+        let IgesGeometry::CircularArc {
+            circle: c,
+            first,
+            last,
+        } = model.get(reversed).unwrap()
+        else {
+            panic!()
+        };
+        assert!((last - first - 1.5).abs() < 1e-12);
+        let start = at(&c.position, 2.0 * first.cos(), 2.0 * first.sin());
+        assert!(start.is_equal(
+            &at(&tilted(), 2.0 * 2.0f64.cos(), 2.0 * 2.0f64.sin()),
+            1e-12
+        ));
+
+        let IgesGeometry::EllipticArc {
+            ellipse: e,
+            first,
+            last,
+        } = model.get(ellipse).unwrap()
+        else {
+            panic!()
+        };
+        assert!((e.major_radius - 3.0).abs() < 1e-12 && (e.minor_radius - 1.0).abs() < 1e-12);
+        assert!((last - first - 2.0 * std::f64::consts::PI).abs() < 1e-12);
+        assert!(e.position.vxdir.is_equal(&tilted().vxdir, 1e-12));
+
+        let IgesGeometry::HyperbolicArc {
+            hyperbola: e,
+            first,
+            last,
+        } = model.get(hyperbola).unwrap()
+        else {
+            panic!()
+        };
+        assert!((e.major_radius - 2.0).abs() < 1e-12 && (e.minor_radius - 1.0).abs() < 1e-12);
+        assert!((first + 1.0).abs() < 1e-12 && (last - 0.5).abs() < 1e-12);
+        assert!(e.position.vydir.is_equal(&tilted().vydir, 1e-12));
+
+        let IgesGeometry::ParabolicArc {
+            parabola: p,
+            first,
+            last,
+        } = model.get(parabola).unwrap()
+        else {
+            panic!()
+        };
+        assert!((p.focal_length - 0.25).abs() < 1e-12);
+        assert!((first + 1.0).abs() < 1e-12 && (last - 2.0).abs() < 1e-12);
+        assert!(
+            p.pos
+                .get_origin()
+                .is_equal(&Point3d::from_coords(1.0, 2.0, 3.0), 1e-12)
+        );
+        assert!(p.pos.vxdir.is_equal(&tilted().vxdir, 1e-12));
+
+        let IgesGeometry::Line {
+            line: l,
+            first,
+            last,
+        } = model.get(line).unwrap()
+        else {
+            panic!()
+        };
+        assert!((last - first - 2.0).abs() < 1e-12);
+        assert!(l.pos.direction.is_equal(&tilted().axis.direction, 1e-12));
+
+        let IgesGeometry::BSplineCurve { curve, .. } = model.get(spline).unwrap() else {
+            panic!()
+        };
+        assert!(curve.is_rational());
+        let p = curve.value(0.5);
+        assert!(((p.get_x().powi(2) + p.get_y().powi(2)).sqrt() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_surfaces_round_trip() {
+        let mut writer = IgesWriter::new();
+        let cone = writer.add(&IgesGeometry::ConicalSurface(Cone {
+            position: tilted().into(),
+            radius: 4.0,
+            semi_angle: 0.5,
+        }));
+        let torus = writer.add(&IgesGeometry::ToroidalSurface(Torus {
+            pos: tilted().into(),
+            major_radius: 8.0,
+            minor_radius: 2.0,
+        }));
+        let model = read_str(&writer.write_str()).unwrap();
+        assert!(model.skipped.is_empty(), "{:?}", model.skipped);
+        let IgesGeometry::ConicalSurface(c) = model.get(cone).unwrap() else {
+            panic!()
+        };
+        assert!((c.radius - 4.0).abs() < 1e-12 && (c.semi_angle - 0.5).abs() < 1e-12);
+        assert!(c.position.vxdir.is_equal(&tilted().vxdir, 1e-12));
+        let IgesGeometry::ToroidalSurface(t) = model.get(torus).unwrap() else {
+            panic!()
+        };
+        assert_eq!((t.major_radius, t.minor_radius), (8.0, 2.0));
+        assert!(
+            t.pos
+                .axis
+                .direction
+                .is_equal(&tilted().axis.direction, 1e-12)
+        );
+        // Location and directions are written as dependent 116/123 entities.
+        assert!(model.file.get(1).unwrap().is_dependent());
+        assert!(matches!(model.get(1), Some(IgesGeometry::Point(_))));
+    }
+
+    #[test]
+    fn test_transformation_and_legacy_entities() {
+        let mut file = IgesFile::new();
+        // Quarter turn about z followed by a shift along x.
+        let trsf = file.add(Entity::new(
+            124,
+            0,
+            [0.0, -1.0, 0.0, 10.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0]
+                .iter()
+                .map(|&v| r(v))
+                .collect(),
+        ));
+        let mut arc = Entity::new(100, 0, [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0].map(r).to_vec());
+        arc.transform = trsf as i64;
+        let arc = file.add(arc);
+        // One linear segment from (0, 0, 0) to (2, 4, 0) over t in [0, 2].
+        let mut spline = vec![
+            Parameter::Integer(1),
+            Parameter::Integer(1),
+            Parameter::Integer(2),
+            Parameter::Integer(1),
+            r(0.0),
+            r(2.0),
+        ];
+        spline.extend(
+            [0.0, 1.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]
+                .map(r)
+                .to_vec(),
+        );
+        let spline = file.add(Entity::new(112, 0, spline));
+        let plane = file.add(Entity::new(
+            108,
+            0,
+            vec![
+                r(0.0),
+                r(0.0),
+                r(2.0),
+                r(6.0),
+                Parameter::Integer(0),
+                r(1.0),
+                r(1.0),
+                r(0.0),
+                r(0.0),
+            ],
+        ));
+        let bad = file.add(Entity::new(190, 0, vec![Parameter::Integer(99)]));
+
+        let model = read_str(&file.to_string()).unwrap();
+        let IgesGeometry::Transformation(t) = model.get(trsf).unwrap() else {
+            panic!()
+        };
+        let p = t.transform_xyz(&geom::XYZ::from_coords(1.0, 0.0, 0.0));
+        assert!(p.is_equal(&geom::XYZ::from_coords(10.0, 1.0, 0.0), 1e-12));
+
+        let IgesGeometry::CircularArc {
+            circle,
+            first,
+            last,
+        } = model.get(arc).unwrap()
+        else {
+            panic!()
+        };
+        assert!((last - first - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+        assert!(
+            circle
+                .position
+                .get_origin()
+                .is_equal(&Point3d::from_coords(10.0, 0.0, 0.0), 1e-12)
+        );
+        assert!(
+            circle
+                .position
+                .vxdir
+                .is_equal(&Direction3d::from_coords(0.0, 1.0, 0.0), 1e-12)
+        );
+
+        let IgesGeometry::BSplineCurve { curve, first, last } = model.get(spline).unwrap() else {
+            panic!()
+        };
+        assert_eq!((*first, *last), (0.0, 2.0));
+        assert!(
+            curve
+                .value(1.0)
+                .is_equal(&Point3d::from_coords(1.0, 2.0, 0.0), 1e-12)
+        );
+
+        let IgesGeometry::Plane(plane) = model.get(plane).unwrap() else {
+            panic!()
+        };
+        assert!(
+            plane
+                .pos
+                .get_origin()
+                .is_equal(&Point3d::from_coords(1.0, 1.0, 3.0), 1e-12)
+        );
+        assert!(
+            plane
+                .pos
+                .axis
+                .direction
+                .is_equal(&Direction3d::from_coords(0.0, 0.0, 1.0), 1e-12)
+        );
+
+        assert!(model.get(bad).is_none());
+        assert_eq!(model.skipped.len(), 1);
+        assert_eq!(model.skipped[0].0, bad);
+    }
+
+    #[test]
+    fn test_invalid_entities_are_skipped() {
+        let mut file = IgesFile::new();
+        let location = file.add(Entity::new(116, 0, [1.0, 2.0, 3.0].map(r).to_vec()));
+        let axis = file.add(Entity::new(123, 0, [0.0, 0.0, 1.0].map(r).to_vec()));
+        let (location, axis) = (
+            Parameter::Integer(location as i64),
+            Parameter::Integer(axis as i64),
+        );
+        let huge = Parameter::Integer(i64::MAX);
+        let spline = file.add(Entity::new(
+            112,
+            0,
+            vec![
+                Parameter::Integer(3),
+                Parameter::Integer(1),
+                Parameter::Integer(3),
+                huge.clone(),
+                r(0.0),
+            ],
+        ));
+        let mut nurbs = vec![huge.clone(), Parameter::Integer(1)];
+        nurbs.extend([0, 0, 1, 0].map(Parameter::Integer));
+        nurbs.extend([0.0, 0.0, 1.0, 1.0].map(r));
+        let nurbs = file.add(Entity::new(126, 0, nurbs));
+        let cylinder = file.add(Entity::new(
+            192,
+            0,
+            vec![location.clone(), axis.clone(), r(-1.0)],
+        ));
+        let cone = file.add(Entity::new(
+            194,
+            0,
+            vec![location.clone(), axis.clone(), r(1.0), r(95.0)],
+        ));
+        let sphere = file.add(Entity::new(
+            196,
+            0,
+            vec![location.clone(), Parameter::Default],
+        ));
+        let torus = file.add(Entity::new(
+            198,
+            0,
+            vec![location.clone(), axis.clone(), r(2.0), r(0.0)],
+        ));
+        let apex = file.add(Entity::new(194, 0, vec![location, axis, r(0.0), r(30.0)]));
+
+        let model = read_str(&file.to_string()).unwrap();
+        let reason = |de: usize| &model.skipped.iter().find(|s| s.0 == de).unwrap().1;
+        assert!(reason(spline).contains("exceed"));
+        assert!(reason(nurbs).contains("overflows") || reason(nurbs).contains("exceed"));
+        assert_eq!(
+            reason(cylinder),
+            "cylinder radius must be positive but is -1"
+        );
+        assert!(reason(cone).contains("semi-angle"));
+        assert_eq!(reason(sphere), "sphere radius is missing");
+        assert!(reason(torus).contains("torus minor radius"));
+        let Some(IgesGeometry::ConicalSurface(c)) = model.get(apex) else {
+            panic!()
+        };
+        assert_eq!(c.radius, 0.0);
+    }
+}
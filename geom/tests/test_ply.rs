@@ -0,0 +1,212 @@
+use geom::Mesh;
+use geom::Point3d;
+use geom::io::ply::{Format, PlyFile, PropertyType};
+use geom::mesh::{Attribute, AttributeType, AttributeValues};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_mesh() -> Mesh {
+        let mut mesh = Mesh::from_points_faces(
+            vec![
+                Point3d::from_coords(0.0, 0.0, 0.0),
+                Point3d::from_coords(1.5, 0.0, 0.0),
+                Point3d::from_coords(1.5, 2.25, 0.1),
+                Point3d::from_coords(0.0, 2.25, -0.1),
+            ],
+            vec![vec![0, 1, 2], vec![0, 2, 3]],
+        );
+        for (name, values) in [
+            ("red", vec![255.0, 0.0, 0.0, 12.0]),
+            ("green", vec![0.0, 255.0, 0.0, 34.0]),
+            ("blue", vec![0.0, 0.0, 255.0, 56.0]),
+        ] {
+            mesh.add_point_attribute(Attribute::scalar(name, AttributeType::U8, values))
+                .unwrap();
+        }
+        mesh.add_point_attribute(Attribute::scalar(
+            "intensity",
+            AttributeType::F32,
+            vec![0.1f32 as f64, 0.2f32 as f64, 0.3f32 as f64, 1e-7f32 as f64],
+        ))
+        .unwrap();
+        mesh.add_face_attribute(Attribute::scalar(
+            "stress",
+            AttributeType::F64,
+            vec![1.0 / 3.0, -2.5e10],
+        ))
+        .unwrap();
+        mesh.add_face_attribute(Attribute::list(
+            "tags",
+            AttributeType::I16,
+            vec![vec![-1.0, 2.0], vec![]],
+        ))
+        .unwrap();
+        mesh
+    }
+
+    fn round_trip(format: Format) -> (PlyFile, Mesh) {
+        let mesh = sample_mesh();
+        let ply = PlyFile::from_mesh(&mesh, format, AttributeType::F64).unwrap();
+        let mut buffer = Vec::new();
+        ply.write(&mut buffer).unwrap();
+        let read = PlyFile::read(buffer.as_slice()).unwrap();
+        assert_eq!(read, ply);
+        let mesh2 = read.to_mesh().unwrap();
+        (read, mesh2)
+    }
+
+    fn assert_same_mesh(a: &Mesh, b: &Mesh) {
+        assert_eq!(a.points.len(), b.points.len());
+        for (p, q) in a.points.iter().zip(b.points.iter()) {
+            assert_eq!(p.get_coords(), q.get_coords());
+        }
+        assert_eq!(a.faces, b.faces);
+        assert_eq!(a.point_attributes, b.point_attributes);
+        assert_eq!(a.face_attributes, b.face_attributes);
+    }
+
+    #[test]
+    fn test_round_trip_ascii() {
+        let (_, mesh) = round_trip(Format::Ascii);
+        assert_same_mesh(&mesh, &sample_mesh());
+    }
+
+    #[test]
+    fn test_round_trip_binary_little_endian() {
+        let (_, mesh) = round_trip(Format::BinaryLittleEndian);
+        assert_same_mesh(&mesh, &sample_mesh());
+    }
+
+    #[test]
+    fn test_round_trip_binary_big_endian() {
+        let (_, mesh) = round_trip(Format::BinaryBigEndian);
+        assert_same_mesh(&mesh, &sample_mesh());
+    }
+
+    #[test]
+    fn test_read_ascii_point_cloud() {
+        let text = "ply\nformat ascii 1.0\ncomment scanner A\nelement vertex 2\n\
+                    property float x\nproperty float y\nproperty float z\n\
+                    property uchar red\nproperty uint8 green\nproperty uchar blue\n\
+                    property float32 intensity\nend_header\n\
+                    1 2 3 10 20 30 0.5\n-1 -2 -3.25 40 50 60 0.75\n";
+        let ply = PlyFile::read(text.as_bytes()).unwrap();
+        assert_eq!(ply.format, Format::Ascii);
+        assert_eq!(ply.comments, vec!["scanner A".to_string()]);
+        let vertex = ply.get_element("vertex").unwrap();
+        assert_eq!(
+            vertex.get_property("green").unwrap().data_type,
+            PropertyType::Scalar(AttributeType::U8)
+        );
+        let mesh = ply.to_mesh().unwrap();
+        assert_eq!(mesh.num_points(), 2);
+        assert_eq!(mesh.num_faces(), 0);
+        assert_eq!(mesh.points[1].get_coords(), (-1.0, -2.0, -3.25));
+        assert_eq!(
+            mesh.get_point_attribute("blue").unwrap().get_scalar(1),
+            Some(60.0)
+        );
+        assert_eq!(
+            mesh.get_point_attribute("intensity").unwrap().values,
+            AttributeValues::Scalar(vec![0.5, 0.75])
+        );
+    }
+
+    #[test]
+    fn test_float_positions_are_preserved() {
+        let mesh = sample_mesh();
+        let ply =
+            PlyFile::from_mesh(&mesh, Format::BinaryLittleEndian, AttributeType::F32).unwrap();
+        let mut buffer = Vec::new();
+        ply.write(&mut buffer).unwrap();
+        let read = PlyFile::read(buffer.as_slice()).unwrap().to_mesh().unwrap();
+        assert_eq!(read.points[2].get_z(), 0.1f32 as f64);
+        assert_eq!(
+            read.get_point_attribute("intensity"),
+            mesh.get_point_attribute("intensity")
+        );
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(PlyFile::read("off\n".as_bytes()).is_err());
+        let truncated = "ply\nformat ascii 1.0\nelement vertex 2\nproperty float x\n\
+                         property float y\nproperty float z\nend_header\n1 2 3\n";
+        assert!(PlyFile::read(truncated.as_bytes()).is_err());
+        let bad_face = "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\n\
+                        property float y\nproperty float z\nelement face 1\n\
+                        property list uchar int vertex_indices\nend_header\n0 0 0\n3 0 1 2\n";
+        assert!(
+            PlyFile::read(bad_face.as_bytes())
+                .unwrap()
+                .to_mesh()
+                .is_err()
+        );
+        let negative = bad_face
+            .replace("3 0 1 2", "3 0 -1 0")
+            .replace("uchar int", "uchar char");
+        assert!(
+            PlyFile::read(negative.as_bytes())
+                .unwrap()
+                .to_mesh()
+                .is_err()
+        );
+
+        // Counts from the header are not trusted for allocation.
+        let huge = "ply\nformat binary_little_endian 1.0\nelement vertex 100000000000000000\n\
+                    property double x\nend_header\n";
+        assert!(PlyFile::read(huge.as_bytes()).is_err());
+        let empty = "ply\nformat ascii 1.0\nelement vertex 100000000000000000\nend_header\n";
+        assert_eq!(
+            PlyFile::read(empty.as_bytes()).unwrap().elements[0].count,
+            100000000000000000
+        );
+        let mut long_list = b"ply\nformat binary_little_endian 1.0\nelement face 1\n\
+                              property list uint int vertex_indices\nend_header\n"
+            .to_vec();
+        long_list.extend(u32::MAX.to_le_bytes());
+        assert!(PlyFile::read(long_list.as_slice()).is_err());
+        let negative_count = "ply\nformat ascii 1.0\nelement face 1\n\
+                              property list char int vertex_indices\nend_header\n-1\n";
+        assert!(PlyFile::read(negative_count.as_bytes()).is_err());
+        for count in ["1.5", "nan", "-0.5"] {
+            let float_count = "ply\nformat ascii 1.0\nelement face 1\n\
+                               property list float int vertex_indices\nend_header\n"
+                .to_string()
+                + count
+                + " 0 0\n";
+            assert!(PlyFile::read(float_count.as_bytes()).is_err(), "{}", count);
+        }
+        let mut nan_count = b"ply\nformat binary_little_endian 1.0\nelement face 1\n\
+                              property list double int vertex_indices\nend_header\n"
+            .to_vec();
+        nan_count.extend(f64::NAN.to_le_bytes());
+        assert!(PlyFile::read(nan_count.as_slice()).is_err());
+
+        let edge = bad_face.replace("3 0 1 2", "2 0 0");
+        assert!(PlyFile::read(edge.as_bytes()).unwrap().to_mesh().is_err());
+    }
+
+    #[test]
+    fn test_list_longer_than_count_type() {
+        let mut ply = PlyFile::read(
+            "ply\nformat binary_little_endian 1.0\nelement face 1\n\
+             property list uchar int vertex_indices\nend_header\n\x03\0\0\0\0\x01\0\0\0\x02\0\0\0"
+                .as_bytes(),
+        )
+        .unwrap();
+        let property = &mut ply.elements[0].properties[0];
+        assert_eq!(
+            property.data_type,
+            PropertyType::List(AttributeType::U8, AttributeType::I32)
+        );
+        property.data = AttributeValues::List(vec![vec![0.0; 300]]);
+        assert!(ply.write(Vec::new()).is_err());
+        ply.format = Format::Ascii;
+        assert!(ply.write(Vec::new()).is_err());
+        ply.elements[0].properties[0].data = AttributeValues::List(vec![vec![0.0; 255]]);
+        assert!(ply.write(Vec::new()).is_ok());
+    }
+}
@@ -0,0 +1,249 @@
+use geom::Point2d;
+use geom::polygon2d::{BooleanOp, Join, PointLocation, Polygon2d, boolean, is_counterclockwise};
+
+fn rectangle(x0: f64, y0: f64, x1: f64, y1: f64) -> Vec<Point2d> {
+    vec![
+        Point2d::from_coords(x0, y0),
+        Point2d::from_coords(x1, y0),
+        Point2d::from_coords(x1, y1),
+        Point2d::from_coords(x0, y1),
+    ]
+}
+
+fn regular(n: usize, cx: f64, cy: f64, r: f64, phase: f64) -> Vec<Point2d> {
+    (0..n)
+        .map(|i| {
+            let t = phase + 2.0 * std::f64::consts::PI * i as f64 / n as f64;
+            Point2d::from_coords(cx + r * t.cos(), cy + r * t.sin())
+        })
+        .collect()
+}
+
+fn area(polygons: &[Polygon2d]) -> f64 {
+    polygons.iter().map(|p| p.area()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_properties() {
+        // Clockwise input with a repeated closing point.
+        let mut outer = rectangle(0.0, 0.0, 4.0, 3.0);
+        outer.reverse();
+        outer.push(outer[0]);
+        let polygon = Polygon2d::new(outer, vec![rectangle(1.0, 1.0, 2.0, 2.0)]);
+        assert_eq!(polygon.outer.len(), 4);
+        assert!(is_counterclockwise(&polygon.outer));
+        assert!(!is_counterclockwise(&polygon.holes[0]));
+        assert_eq!(polygon.area(), 11.0);
+        let c = polygon.centroid().unwrap();
+        assert!((c.get_x() - (12.0 * 2.0 - 1.5) / 11.0).abs() < 1e-12);
+        assert!((c.get_y() - (12.0 * 1.5 - 1.5) / 11.0).abs() < 1e-12);
+
+        let at = |x, y| polygon.locate(&Point2d::from_coords(x, y));
+        assert_eq!(at(0.5, 0.5), PointLocation::Inside);
+        assert_eq!(at(1.5, 1.5), PointLocation::Outside);
+        assert_eq!(at(5.0, 1.0), PointLocation::Outside);
+        assert_eq!(at(4.0, 1.0), PointLocation::Boundary);
+        assert_eq!(at(1.0, 1.5), PointLocation::Boundary);
+        assert_eq!(at(0.0, 0.0), PointLocation::Boundary);
+        assert!(
+            Polygon2d::new(rectangle(0.0, 0.0, 1.0, 0.0), vec![])
+                .centroid()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_boolean_squares() {
+        let a = Polygon2d::new(rectangle(0.0, 0.0, 2.0, 2.0), vec![]);
+        let b = Polygon2d::new(rectangle(1.0, 1.0, 3.0, 3.0), vec![]);
+        let union = a.union(&b);
+        assert_eq!(union.len(), 1);
+        assert_eq!(union[0].outer.len(), 8);
+        assert_eq!(area(&union), 7.0);
+        let intersection = a.intersection(&b);
+        assert_eq!(intersection.len(), 1);
+        assert_eq!(intersection[0].outer.len(), 4);
+        assert_eq!(area(&intersection), 1.0);
+        assert_eq!(area(&a.difference(&b)), 3.0);
+        let xor = a.xor(&b);
+        assert_eq!(xor.len(), 2);
+        assert_eq!(area(&xor), 6.0);
+
+        // Shared edges merge and collinear vertices are dropped.
+        let c = Polygon2d::new(rectangle(2.0, 0.0, 4.0, 2.0), vec![]);
+        let union = a.union(&c);
+        assert_eq!(union.len(), 1);
+        assert_eq!(union[0].outer.len(), 4);
+        assert_eq!(area(&union), 8.0);
+        assert!(a.intersection(&c).is_empty());
+
+        // Squares touching at a corner stay apart.
+        let d = Polygon2d::new(rectangle(2.0, 2.0, 3.0, 3.0), vec![]);
+        assert_eq!(a.union(&d).len(), 2);
+
+        // Difference of a contained square leaves a hole.
+        let inner = Polygon2d::new(rectangle(0.5, 0.5, 1.5, 1.5), vec![]);
+        let holed = a.difference(&inner);
+        assert_eq!(holed.len(), 1);
+        assert_eq!(holed[0].holes.len(), 1);
+        assert_eq!(area(&holed), 3.0);
+        let refilled = holed[0].union(&inner);
+        assert_eq!(refilled.len(), 1);
+        assert!(refilled[0].holes.is_empty());
+        assert_eq!(area(&refilled), 4.0);
+
+        // Identical operands.
+        assert!(a.xor(&a).is_empty());
+        assert!(a.difference(&a).is_empty());
+        assert_eq!(area(&a.union(&a)), 4.0);
+        assert_eq!(area(&a.intersection(&a)), 4.0);
+    }
+
+    #[test]
+    fn test_boolean_identities() {
+        let a = Polygon2d::new(
+            regular(61, 0.0, 0.0, 1.0, 0.1),
+            vec![regular(17, 0.2, 0.1, 0.3, 0.0)],
+        );
+        let b = Polygon2d::new(regular(53, 0.7, 0.3, 0.9, 0.37), vec![]);
+        let (sa, sb) = (a.area(), b.area());
+        let op = |op| {
+            area(&boolean(
+                std::slice::from_ref(&a),
+                std::slice::from_ref(&b),
+                op,
+            ))
+        };
+        let (union, intersection) = (op(BooleanOp::Union), op(BooleanOp::Intersection));
+        assert!(intersection > 0.1);
+        assert!((union + intersection - sa - sb).abs() < 1e-9);
+        assert!((op(BooleanOp::Difference) - (sa - intersection)).abs() < 1e-9);
+        assert!((op(BooleanOp::Xor) - (union - intersection)).abs() < 1e-9);
+
+        // Several polygons on each side.
+        let left = [
+            Polygon2d::new(rectangle(0.0, 0.0, 1.0, 1.0), vec![]),
+            Polygon2d::new(rectangle(2.0, 0.0, 3.0, 1.0), vec![]),
+        ];
+        let right = [Polygon2d::new(rectangle(0.5, 0.25, 2.5, 0.75), vec![])];
+        let union = boolean(&left, &right, BooleanOp::Union);
+        assert_eq!(union.len(), 1);
+        assert_eq!(area(&union), 2.5);
+        let intersection = boolean(&left, &right, BooleanOp::Intersection);
+        assert_eq!(intersection.len(), 2);
+        assert_eq!(area(&intersection), 0.5);
+    }
+
+    #[test]
+    fn test_nearly_shared_edges() {
+        let a = Polygon2d::new(rectangle(0.0, 0.0, 1.0, 1.0), vec![]);
+        let near = |x: f64, y: f64| Polygon2d::new(rectangle(x, y, x + 1.0, y + 1.0), vec![]);
+        for b in [near(0.0, 1e-17), near(1e-17, 0.0), near(-1e-15, 1e-14)] {
+            for op in [BooleanOp::Union, BooleanOp::Intersection] {
+                let result = boolean(std::slice::from_ref(&a), std::slice::from_ref(&b), op);
+                assert_eq!(result.len(), 1);
+                assert!((area(&result) - 1.0).abs() < 1e-12);
+            }
+            assert!(area(&a.xor(&b)) < 1e-12);
+            assert!(area(&a.difference(&b)) < 1e-12);
+        }
+
+        // Neighbours sharing an edge up to rounding, and one running along
+        // part of it.
+        let b = near(1.0 + 1e-16, 1e-16);
+        let union = a.union(&b);
+        assert_eq!(union.len(), 1);
+        assert_eq!(union[0].outer.len(), 4);
+        assert!((area(&union) - 2.0).abs() < 1e-12);
+        assert!(area(&a.intersection(&b)) < 1e-12);
+        let c = Polygon2d::new(rectangle(1.0 - 1e-17, 0.25, 2.0, 0.5), vec![]);
+        let union = a.union(&c);
+        assert_eq!(union.len(), 1);
+        assert_eq!(union[0].outer.len(), 8);
+        assert!((area(&union) - 1.25).abs() < 1e-12);
+        assert!((area(&a.difference(&c)) - 1.0).abs() < 1e-12);
+
+        // A hole whose edge nearly touches the outer boundary.
+        let frame = Polygon2d::new(
+            rectangle(0.0, 0.0, 4.0, 4.0),
+            vec![rectangle(1.0, 1.0, 4.0 - 1e-16, 3.0)],
+        );
+        let filled = frame.union(&Polygon2d::new(rectangle(1.0, 1.0, 4.0, 3.0), vec![]));
+        assert_eq!(filled.len(), 1);
+        assert!(filled[0].holes.is_empty());
+        assert!((area(&filled) - 16.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_large_union() {
+        // Many vertices and many separate parts, both quick.
+        let a = Polygon2d::new(regular(4000, 0.0, 0.0, 1.0, 0.0), vec![]);
+        let b = Polygon2d::new(regular(4000, 0.5, 0.0, 1.0, 0.001), vec![]);
+        let union = a.union(&b);
+        assert_eq!(union.len(), 1);
+        let squares: Vec<Polygon2d> = (0..400)
+            .map(|i| {
+                let (x, y) = ((i % 20) as f64 * 2.0, (i / 20) as f64 * 2.0);
+                Polygon2d::new(rectangle(x, y, x + 1.0, y + 1.0), vec![])
+            })
+            .collect();
+        let cover = [Polygon2d::new(rectangle(-0.5, -0.5, 40.0, 0.5), vec![])];
+        let union = boolean(&squares, &cover, BooleanOp::Union);
+        assert_eq!(union.len(), 381);
+        assert!((area(&union) - (400.0 - 20.0 * 0.5 + 40.5)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_offset() {
+        let square = Polygon2d::new(rectangle(0.0, 0.0, 2.0, 2.0), vec![]);
+        for join in [Join::Miter(2.0), Join::Square] {
+            let grown = square.offset(1.0, join);
+            assert_eq!(grown.len(), 1);
+            assert!((area(&grown) - 16.0).abs() < 1e-12);
+        }
+        let round = square.offset(1.0, Join::Round);
+        assert!((area(&round) - (12.0 + std::f64::consts::PI)).abs() < 1e-2);
+        assert!(area(&round) < 12.0 + std::f64::consts::PI);
+        let shrunk = square.offset(-0.5, Join::Miter(2.0));
+        assert!((area(&shrunk) - 1.0).abs() < 1e-12);
+        assert!(square.offset(-1.5, Join::Round).is_empty());
+
+        // A right angle corner beyond the miter limit is squared off.
+        let beveled = square.offset(1.0, Join::Miter(1.2));
+        assert!((area(&beveled) - (16.0 - 4.0 * (2f64.sqrt() - 1.2).powi(2))).abs() < 1e-9);
+
+        // Concave L shape.
+        let l = Polygon2d::new(
+            vec![
+                Point2d::from_coords(0.0, 0.0),
+                Point2d::from_coords(2.0, 0.0),
+                Point2d::from_coords(2.0, 1.0),
+                Point2d::from_coords(1.0, 1.0),
+                Point2d::from_coords(1.0, 2.0),
+                Point2d::from_coords(0.0, 2.0),
+            ],
+            vec![],
+        );
+        let grown = l.offset(0.5, Join::Miter(2.0));
+        assert_eq!(grown.len(), 1);
+        assert_eq!(grown[0].outer.len(), 6);
+        assert!((area(&grown) - 3.0 * 3.0 + 1.0).abs() < 1e-12);
+
+        // The hole closes up when growing far enough.
+        let frame = Polygon2d::new(
+            rectangle(0.0, 0.0, 10.0, 10.0),
+            vec![rectangle(4.0, 4.0, 6.0, 6.0)],
+        );
+        let grown = frame.offset(0.5, Join::Miter(2.0));
+        assert_eq!(grown[0].holes.len(), 1);
+        assert!((area(&grown) - (121.0 - 1.0)).abs() < 1e-9);
+        let grown = frame.offset(1.5, Join::Miter(2.0));
+        assert_eq!(grown.len(), 1);
+        assert!(grown[0].holes.is_empty());
+        assert!((area(&grown) - 169.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,141 @@
+use geom::predicates::{incircle, insphere, orient2d, orient2d_filter, orient3d};
+use geom::{Point2d, Point3d};
+
+// Exact orient2d for coordinates 0.5 + k * 2^-53 via integer arithmetic.
+fn orient2d_reference(a: (i128, i128), b: (i128, i128), c: (i128, i128)) -> i128 {
+    ((a.0 - c.0) * (b.1 - c.1) - (a.1 - c.1) * (b.0 - c.0)).signum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orient2d_near_degenerate() {
+        let ulp = 2f64.powi(-53);
+        let b = Point2d::from_coords(12.0, 12.0);
+        let c = Point2d::from_coords(24.0, 24.0);
+        let (bi, ci) = (12i128 << 53, 24i128 << 53);
+        let mut naive_wrong = 0;
+        for i in 0..64 {
+            for j in 0..64 {
+                let a = Point2d::from_coords(0.5 + i as f64 * ulp, 0.5 + j as f64 * ulp);
+                let ai = ((1i128 << 52) + i, (1i128 << 52) + j);
+                let expected = orient2d_reference(ai, (bi, bi), (ci, ci));
+                assert_eq!(
+                    orient2d(&a, &b, &c).signum() as i128 * expected.abs(),
+                    expected
+                );
+                assert_eq!(
+                    orient2d(&b, &c, &a).signum() as i128 * expected.abs(),
+                    expected
+                );
+                assert_eq!(
+                    orient2d(&c, &b, &a).signum() as i128 * expected.abs(),
+                    -expected
+                );
+                let naive = (a.get_x() - c.get_x()) * (b.get_y() - c.get_y())
+                    - (a.get_y() - c.get_y()) * (b.get_x() - c.get_x());
+                if naive.signum() as i128 != expected && !(naive == 0.0 && expected == 0) {
+                    naive_wrong += 1;
+                }
+            }
+        }
+        // The grid is chosen so that plain evaluation gets many signs wrong.
+        assert!(naive_wrong > 0);
+
+        let a = Point2d::from_coords(0.0, 0.0);
+        let b = Point2d::from_coords(1.0, 0.0);
+        assert!(orient2d(&a, &b, &Point2d::from_coords(0.0, 1.0)) > 0.0);
+        assert!(orient2d_filter(&a, &b, &Point2d::from_coords(0.0, 1.0)).is_some());
+        assert!(orient2d_filter(&a, &b, &Point2d::from_coords(3.0, 1e-300)).is_some());
+        assert_eq!(orient2d(&a, &b, &Point2d::from_coords(7.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn test_orient3d() {
+        let a = Point3d::from_coords(0.0, 0.0, 0.0);
+        let b = Point3d::from_coords(1.0, 0.0, 0.0);
+        let c = Point3d::from_coords(0.0, 1.0, 0.0);
+        assert!(orient3d(&a, &b, &c, &Point3d::from_coords(0.2, 0.2, -1.0)) > 0.0);
+        assert!(orient3d(&a, &b, &c, &Point3d::from_coords(0.2, 0.2, 1.0)) < 0.0);
+
+        // Points on the plane z = x + y whose products need rounding.
+        let p = |x: f64, y: f64| Point3d::from_coords(x, y, x + y);
+        let (a, b, c) = (p(3.0, 0.125), p(1e8, -0.5), p(-3.0, 0.75));
+        let d = p(0.5, 0.5);
+        assert_eq!(orient3d(&a, &b, &c, &d), 0.0);
+        let up = Point3d::from_coords(0.5, 0.5, 1.0 + 2f64.powi(-52));
+        let down = Point3d::from_coords(0.5, 0.5, 1.0 - 2f64.powi(-53));
+        let (s_up, s_down) = (orient3d(&a, &b, &c, &up), orient3d(&a, &b, &c, &down));
+        assert!(s_up != 0.0 && s_down != 0.0);
+        assert_eq!(s_up.signum(), -s_down.signum());
+        assert_eq!(orient3d(&b, &a, &c, &up).signum(), -s_up.signum());
+    }
+
+    #[test]
+    fn test_incircle() {
+        let a = Point2d::from_coords(0.0, 0.0);
+        let b = Point2d::from_coords(1.0, 0.0);
+        let c = Point2d::from_coords(1.0, 1.0);
+        assert!(incircle(&a, &b, &c, &Point2d::from_coords(0.5, 0.5)) > 0.0);
+        assert!(incircle(&a, &b, &c, &Point2d::from_coords(2.0, 2.0)) < 0.0);
+        assert_eq!(incircle(&a, &b, &c, &Point2d::from_coords(0.0, 1.0)), 0.0);
+
+        let eps = 2f64.powi(-52);
+        let outside = Point2d::from_coords(0.0, 1.0 + eps);
+        let inside = Point2d::from_coords(0.0, 1.0 - eps / 2.0);
+        assert!(incircle(&a, &b, &c, &outside) < 0.0);
+        assert!(incircle(&a, &b, &c, &inside) > 0.0);
+        assert!(incircle(&a, &c, &b, &inside) < 0.0);
+
+        // A large translation keeps the configuration cocircular.
+        let t = 2f64.powi(30);
+        let shift = |p: &Point2d| Point2d::from_coords(p.get_x() + t, p.get_y() + t);
+        let d = Point2d::from_coords(0.0, 1.0);
+        let inside = Point2d::from_coords(0.0, 1.0 - 2f64.powi(-22));
+        assert_eq!(
+            incircle(&shift(&a), &shift(&b), &shift(&c), &shift(&d)),
+            0.0
+        );
+        assert!(incircle(&shift(&a), &shift(&b), &shift(&c), &shift(&inside)) > 0.0);
+    }
+
+    #[test]
+    fn test_insphere() {
+        let a = Point3d::from_coords(0.0, 0.0, 0.0);
+        let b = Point3d::from_coords(1.0, 0.0, 0.0);
+        let c = Point3d::from_coords(0.0, 1.0, 0.0);
+        let d = Point3d::from_coords(0.0, 0.0, 1.0);
+        // orient3d(a, b, c, d) must be positive.
+        let (b, c) = (c, b);
+        assert!(orient3d(&a, &b, &c, &d) > 0.0);
+        let e = Point3d::from_coords(1.0, 1.0, 1.0);
+        assert_eq!(insphere(&a, &b, &c, &d, &e), 0.0);
+        assert!(insphere(&a, &b, &c, &d, &Point3d::from_coords(0.5, 0.5, 0.5)) > 0.0);
+        assert!(insphere(&a, &b, &c, &d, &Point3d::from_coords(2.0, 2.0, 2.0)) < 0.0);
+
+        let eps = 2f64.powi(-52);
+        let outside = Point3d::from_coords(1.0, 1.0, 1.0 + eps);
+        let inside = Point3d::from_coords(1.0, 1.0, 1.0 - eps / 2.0);
+        assert!(insphere(&a, &b, &c, &d, &outside) < 0.0);
+        assert!(insphere(&a, &b, &c, &d, &inside) > 0.0);
+
+        let t = 2f64.powi(25);
+        let inside = Point3d::from_coords(1.0, 1.0, 1.0 - 2f64.powi(-27));
+        let shift = |p: &Point3d| Point3d::from_coords(p.get_x() + t, p.get_y() - t, p.get_z() + t);
+        assert_eq!(
+            insphere(&shift(&a), &shift(&b), &shift(&c), &shift(&d), &shift(&e)),
+            0.0
+        );
+        assert!(
+            insphere(
+                &shift(&a),
+                &shift(&b),
+                &shift(&c),
+                &shift(&d),
+                &shift(&inside)
+            ) > 0.0
+        );
+    }
+}
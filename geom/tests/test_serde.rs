@@ -0,0 +1,141 @@
+#![cfg(feature = "serde")]
+
+use geom::mesh::Mesh;
+use geom::{
+    BSplineCurve3d, Circle3d, Cone, CoordinateSystem3d, Direction3d, GeneralCoordinateSystem3d,
+    Point3d, Quaternion, Trsf3d, TrsfForm,
+};
+
+fn frame() -> CoordinateSystem3d {
+    CoordinateSystem3d::from_location_direction_xdirection(
+        Point3d::from_coords(1.0, 2.0, 3.0),
+        Direction3d::from_coords(0.0, 1.0, 1.0),
+        Direction3d::from_coords(1.0, 0.0, 0.0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let point = Point3d::from_coords(1.0, 2.5, -3.0);
+        let json = serde_json::to_string(&point).unwrap();
+        assert_eq!(json, r#"{"x":1.0,"y":2.5,"z":-3.0}"#);
+        let read: Point3d = serde_json::from_str(&json).unwrap();
+        assert!(read.is_equal(&point, 0.0));
+
+        let circle = Circle3d {
+            position: frame(),
+            radius: 2.0,
+        };
+        let json = serde_json::to_string(&circle).unwrap();
+        let read: Circle3d = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.radius, 2.0);
+        assert!(
+            read.position
+                .vydir
+                .xyz
+                .is_equal(&circle.position.vydir.xyz, 0.0)
+        );
+
+        let curve = BSplineCurve3d::from_poles_weights_knots(
+            2,
+            vec![
+                Point3d::from_coords(0.0, 0.0, 0.0),
+                Point3d::from_coords(1.0, 1.0, 0.0),
+                Point3d::from_coords(2.0, 0.0, 1.0),
+            ],
+            vec![1.0, 0.5, 1.0],
+            vec![0.0, 1.0],
+            vec![3, 3],
+        )
+        .unwrap();
+        let json = serde_json::to_string(&curve).unwrap();
+        let read: BSplineCurve3d = serde_json::from_str(&json).unwrap();
+        assert!(read.value(0.3).is_equal(&curve.value(0.3), 0.0));
+
+        let trsf = Trsf3d::from_quaternion_translation(
+            &Quaternion::from_coords(0.0, 0.0, 0.5f64.sqrt(), 0.5f64.sqrt()),
+            (1.0, 2.0, 3.0).into(),
+        );
+        let json = serde_json::to_string(&trsf).unwrap();
+        assert!(json.contains(r#""trsf_type":"CompoundTrsf""#));
+        let read: Trsf3d = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.get_values(), trsf.get_values());
+        assert_eq!(read.trsf_type, TrsfForm::CompoundTrsf);
+
+        let mesh = Mesh::from_points_faces(
+            vec![
+                Point3d::from_coords(0.0, 0.0, 0.0),
+                Point3d::from_coords(1.0, 0.0, 0.0),
+                Point3d::from_coords(0.0, 1.0, 0.0),
+            ],
+            vec![vec![0, 1, 2]],
+        );
+        let read: Mesh = serde_json::from_str(&serde_json::to_string(&mesh).unwrap()).unwrap();
+        assert_eq!(read.faces, mesh.faces);
+        assert!(
+            serde_json::from_str::<Mesh>(
+                r#"{"points":[],"faces":[[0]],"point_attributes":[],"face_attributes":[]}"#
+            )
+            .is_err()
+        );
+        let json = serde_json::to_string(&mesh).unwrap();
+        assert!(serde_json::from_str::<Mesh>(&json.replace("[0,1,2]", "[0,1]")).is_err());
+    }
+
+    #[test]
+    fn test_directions() {
+        let d: Direction3d = serde_json::from_str(r#"{"x":3,"y":0,"z":4}"#).unwrap();
+        assert!(
+            d.xyz
+                .is_equal(&Direction3d::from_coords(0.6, 0.0, 0.8).xyz, 1e-15)
+        );
+        assert!(serde_json::from_str::<Direction3d>(r#"{"x":0,"y":0,"z":0}"#).is_err());
+        assert!(serde_json::from_str::<Direction3d>(r#"{"x":1,"y":0}"#).is_err());
+    }
+
+    #[test]
+    fn test_validation() {
+        let mut value = serde_json::to_value(frame()).unwrap();
+        assert!(serde_json::from_value::<CoordinateSystem3d>(value.clone()).is_ok());
+
+        // Direction vectors may be unnormalised but must stay orthogonal.
+        value["vxdir"] = serde_json::json!({"x": 2.0, "y": 0.0, "z": 0.0});
+        assert!(serde_json::from_value::<CoordinateSystem3d>(value.clone()).is_ok());
+        value["vxdir"] = serde_json::json!({"x": 1.0, "y": 1.0, "z": 0.0});
+        assert!(serde_json::from_value::<CoordinateSystem3d>(value.clone()).is_err());
+
+        // A left-handed frame is only valid as a general coordinate system.
+        let mut value = serde_json::to_value(frame()).unwrap();
+        value["vxdir"] = serde_json::json!({"x": -1.0, "y": 0.0, "z": 0.0});
+        assert!(serde_json::from_value::<CoordinateSystem3d>(value.clone()).is_err());
+        let general: GeneralCoordinateSystem3d = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(general.vxdir.get_x(), -1.0);
+
+        let cone = serde_json::json!({"position": value, "radius": 1.0, "semi_angle": 0.3});
+        assert!(serde_json::from_value::<Cone>(cone.clone()).is_ok());
+        let mut bad = cone.clone();
+        bad["semi_angle"] = serde_json::json!(2.0);
+        assert!(serde_json::from_value::<Cone>(bad).is_err());
+
+        let circle =
+            serde_json::json!({"position": serde_json::to_value(frame()).unwrap(), "radius": -1.0});
+        assert!(serde_json::from_value::<Circle3d>(circle).is_err());
+
+        let spline = serde_json::json!({
+            "degree": 2,
+            "poles": [{"x": 0.0, "y": 0.0, "z": 0.0}, {"x": 1.0, "y": 0.0, "z": 0.0}],
+            "weights": null,
+            "knots": [0.0, 1.0],
+            "multiplicities": [3, 3],
+        });
+        assert!(serde_json::from_value::<BSplineCurve3d>(spline).is_err());
+
+        let mut trsf = serde_json::to_value(Trsf3d::new()).unwrap();
+        trsf["matrix"]["m"][0][1] = serde_json::json!(0.5);
+        assert!(serde_json::from_value::<Trsf3d>(trsf).is_err());
+    }
+}
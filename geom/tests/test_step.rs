@@ -0,0 +1,238 @@
+use geom::io::step::part21::{Parameter, Part21File};
+use geom::io::step::{StepGeometry, read_str};
+
+const SAMPLE: &str = "ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('supplier part'),'2;1');
+FILE_NAME('part.stp','2024-01-01T00:00:00',('Designer'),('O\\X2\\00DC\\X0\\'),'','','');
+FILE_SCHEMA(('AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }'));
+ENDSEC;
+DATA;
+/* placement */
+#1=CARTESIAN_POINT('',(1.,2.,3.));
+#2=DIRECTION('',(0.,0.,2.));
+#3=DIRECTION('',(1.,1.,0.));
+#4=AXIS2_PLACEMENT_3D('',#1,#2,#3);
+#5=CIRCLE('',#4,10.);
+#6=ELLIPSE('',#4,5.,2.5);
+#7=HYPERBOLA('',#4,3.,1.);
+#8=PARABOLA('',#4,0.75);
+#9=PLANE('',#4);
+#10=CYLINDRICAL_SURFACE('',#4,4.);
+#11=CONICAL_SURFACE('',#4,4.,30.);
+#12=SPHERICAL_SURFACE('',#4,6.);
+#13=TOROIDAL_SURFACE('',#4,8.,2.);
+#14=VECTOR('',#3,5.);
+#15=LINE('',#1,#14);
+#20=CARTESIAN_POINT('',(1.,0.,0.));
+#21=CARTESIAN_POINT('',(1.,1.,0.));
+#22=CARTESIAN_POINT('',(0.,1.,0.));
+#23=(BOUNDED_CURVE()B_SPLINE_CURVE(2,(#20,#21,#22),.CIRCULAR_ARC.,.F.,.F.)
+B_SPLINE_CURVE_WITH_KNOTS((3,3),(0.,1.),.PIECEWISE_BEZIER_KNOTS.)CURVE()
+GEOMETRIC_REPRESENTATION_ITEM()RATIONAL_B_SPLINE_CURVE((1.,0.707106781186548,1.))
+REPRESENTATION_ITEM(''));
+#24=B_SPLINE_CURVE_WITH_KNOTS('',1,(#20,#21,#22),.POLYLINE_FORM.,.F.,.F.,(2,1,2),(0.,0.5,1.),.UNSPECIFIED.);
+#25=CARTESIAN_POINT('',(0.,0.,0.));
+#26=CARTESIAN_POINT('',(0.,1.,0.));
+#27=CARTESIAN_POINT('',(1.,0.,1.));
+#28=CARTESIAN_POINT('',(1.,1.,1.));
+#29=B_SPLINE_SURFACE_WITH_KNOTS('',1,1,((#25,#26),(#27,#28)),.UNSPECIFIED.,.F.,.F.,.F.,(2,2),(2,2),(0.,1.),(0.,1.),.UNSPECIFIED.);
+#30=AXIS2_PLACEMENT_3D('',#1,#2,#2);
+#31=CIRCLE('',#30,1.);
+#32=CIRCLE('',#99,1.);
+#33=CIRCLE('',#4,-1.);
+#34=CIRCLE('',#4,0.);
+#35=ELLIPSE('',#4,2.,0.);
+#36=HYPERBOLA('',#4,1.,-1.);
+#37=PARABOLA('',#4,0.);
+#38=SPHERICAL_SURFACE('',#4,-2.);
+#39=CONICAL_SURFACE('',#4,1.,0.);
+#40=(LENGTH_UNIT()NAMED_UNIT(*)SI_UNIT(.MILLI.,.METRE.));
+#41=(NAMED_UNIT(*)PLANE_ANGLE_UNIT()SI_UNIT($,.RADIAN.));
+#42=DIMENSIONAL_EXPONENTS(0.,0.,0.,0.,0.,0.,0.);
+#43=PLANE_ANGLE_MEASURE_WITH_UNIT(PLANE_ANGLE_MEASURE(0.0174532925199433),#41);
+#44=(CONVERSION_BASED_UNIT('DEGREE',#43)NAMED_UNIT(#42)PLANE_ANGLE_UNIT());
+#45=(NAMED_UNIT(*)SI_UNIT($,.STERADIAN.)SOLID_ANGLE_UNIT());
+#46=UNCERTAINTY_MEASURE_WITH_UNIT(LENGTH_MEASURE(1.E-07),#40,'distance_accuracy_value','');
+#47=(GEOMETRIC_REPRESENTATION_CONTEXT(3)GLOBAL_UNCERTAINTY_ASSIGNED_CONTEXT((#46))
+GLOBAL_UNIT_ASSIGNED_CONTEXT((#40,#44,#45))REPRESENTATION_CONTEXT('',''));
+ENDSEC;
+END-ISO-10303-21;
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part21_parse() {
+        let file = Part21File::parse(SAMPLE).unwrap();
+        let name = file.get_header("FILE_NAME").unwrap();
+        assert_eq!(
+            name.parameters[3],
+            Parameter::List(vec![Parameter::String("O\u{dc}".to_string())])
+        );
+        let context = file.get(47).unwrap();
+        assert!(context.is_complex());
+        assert_eq!(context.records.len(), 4);
+        let rational = file
+            .get(23)
+            .unwrap()
+            .get_record("RATIONAL_B_SPLINE_CURVE")
+            .unwrap();
+        assert_eq!(rational.parameters.len(), 1);
+        let measure = file.get(46).unwrap();
+        assert_eq!(
+            measure.records[0].parameters[0],
+            Parameter::Typed("LENGTH_MEASURE".to_string(), vec![Parameter::Real(1e-7)])
+        );
+    }
+
+    #[test]
+    fn test_part21_write_round_trip() {
+        let file = Part21File::parse(SAMPLE).unwrap();
+        let text = file.to_string();
+        let again = Part21File::parse(&text).unwrap();
+        assert_eq!(file, again);
+    }
+
+    #[test]
+    fn test_units() {
+        let model = read_str(SAMPLE).unwrap();
+        assert_eq!(model.length_unit, Some(1e-3));
+        assert!((model.plane_angle_unit - 0.0174532925199433).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_placement_and_conics() {
+        let model = read_str(SAMPLE).unwrap();
+        let StepGeometry::Placement(placement) = model.get(4).unwrap() else {
+            panic!("expected placement");
+        };
+        assert_eq!(placement.get_origin().get_coords(), (1.0, 2.0, 3.0));
+        assert_eq!(placement.axis.direction.get_coords(), (0.0, 0.0, 1.0));
+        let h = 0.5f64.sqrt();
+        assert!(
+            placement
+                .vxdir
+                .is_equal(&geom::Direction3d::from_coords(h, h, 0.0), 1e-12)
+        );
+        assert!(
+            placement
+                .vydir
+                .is_equal(&geom::Direction3d::from_coords(-h, h, 0.0), 1e-12)
+        );
+
+        let StepGeometry::Circle(circle) = model.get(5).unwrap() else {
+            panic!()
+        };
+        assert_eq!(circle.radius, 10.0);
+        let StepGeometry::Ellipse(ellipse) = model.get(6).unwrap() else {
+            panic!()
+        };
+        assert_eq!((ellipse.major_radius, ellipse.minor_radius), (5.0, 2.5));
+        let StepGeometry::Hyperbola(hyperbola) = model.get(7).unwrap() else {
+            panic!()
+        };
+        assert_eq!((hyperbola.major_radius, hyperbola.minor_radius), (3.0, 1.0));
+        let StepGeometry::Parabola(parabola) = model.get(8).unwrap() else {
+            panic!()
+        };
+        assert_eq!(parabola.focal_length, 0.75);
+        let StepGeometry::Line(line) = model.get(15).unwrap() else {
+            panic!()
+        };
+        assert!(
+            line.pos
+                .direction
+                .is_equal(&geom::Direction3d::from_coords(h, h, 0.0), 1e-12)
+        );
+    }
+
+    #[test]
+    fn test_surfaces() {
+        let model = read_str(SAMPLE).unwrap();
+        assert!(matches!(model.get(9), Some(StepGeometry::Plane(_))));
+        let StepGeometry::CylindricalSurface(cylinder) = model.get(10).unwrap() else {
+            panic!()
+        };
+        assert_eq!(cylinder.radius, 4.0);
+        let StepGeometry::ConicalSurface(cone) = model.get(11).unwrap() else {
+            panic!()
+        };
+        assert!((cone.semi_angle - std::f64::consts::FRAC_PI_6).abs() < 1e-12);
+        let StepGeometry::SphericalSurface(sphere) = model.get(12).unwrap() else {
+            panic!()
+        };
+        assert_eq!(sphere.radius, 6.0);
+        let StepGeometry::ToroidalSurface(torus) = model.get(13).unwrap() else {
+            panic!()
+        };
+        assert_eq!((torus.major_radius, torus.minor_radius), (8.0, 2.0));
+    }
+
+    #[test]
+    fn test_bsplines() {
+        let model = read_str(SAMPLE).unwrap();
+        let StepGeometry::BSplineCurve(arc) = model.get(23).unwrap() else {
+            panic!()
+        };
+        assert!(arc.is_rational());
+        for i in 0..=10 {
+            let p = arc.value(i as f64 / 10.0);
+            let r = (p.get_x().powi(2) + p.get_y().powi(2)).sqrt();
+            assert!((r - 1.0).abs() < 1e-12);
+        }
+        let StepGeometry::BSplineCurve(polyline) = model.get(24).unwrap() else {
+            panic!()
+        };
+        assert!(
+            polyline
+                .value(0.25)
+                .is_equal(&geom::Point3d::from_coords(1.0, 0.5, 0.0), 1e-12)
+        );
+        let StepGeometry::BSplineSurface(surface) = model.get(29).unwrap() else {
+            panic!()
+        };
+        assert!(
+            surface
+                .value(0.5, 0.25)
+                .is_equal(&geom::Point3d::from_coords(0.5, 0.25, 0.5), 1e-12)
+        );
+    }
+
+    #[test]
+    fn test_invalid_entities_are_skipped() {
+        let model = read_str(SAMPLE).unwrap();
+        let skipped: Vec<u64> = model.skipped.iter().map(|(id, _)| *id).collect();
+        assert_eq!(skipped, vec![30, 31, 32, 33, 34, 35, 36, 37, 38, 39]);
+        let reason = |id: u64| &model.skipped.iter().find(|s| s.0 == id).unwrap().1;
+        assert_eq!(reason(33), "CIRCLE radius must be positive but is -1");
+        assert!(reason(35).contains("ELLIPSE semi-axis"));
+        assert!(model.get(31).is_none());
+        assert!(read_str("ISO-10303-21;\nDATA;\n#1=FOO(;\nENDSEC;\n").is_err());
+    }
+
+    #[test]
+    fn test_invalid_knot_data_is_skipped() {
+        let text = "ISO-10303-21;
+DATA;
+#1=CARTESIAN_POINT('',(0.,0.,0.));
+#2=CARTESIAN_POINT('',(1.,1.,0.));
+#3=CARTESIAN_POINT('',(2.,0.,0.));
+#4=QUASI_UNIFORM_CURVE('',2,(#1,#2,#3),.UNSPECIFIED.,.F.,.F.);
+#5=QUASI_UNIFORM_CURVE('',3,(#1,#2,#3),.UNSPECIFIED.,.F.,.F.);
+#6=BEZIER_CURVE('',-1,(#1,#2,#3),.UNSPECIFIED.,.F.,.F.);
+#7=B_SPLINE_CURVE_WITH_KNOTS('',1,(#1,#2,#3),.UNSPECIFIED.,.F.,.F.,(2,-1,2),(0.,0.5,1.),.UNSPECIFIED.);
+ENDSEC;
+END-ISO-10303-21;
+";
+        let model = read_str(text).unwrap();
+        assert!(matches!(model.get(4), Some(StepGeometry::BSplineCurve(_))));
+        let skipped: Vec<u64> = model.skipped.iter().map(|(id, _)| *id).collect();
+        assert_eq!(skipped, vec![5, 6, 7]);
+        let reason = |id: u64| &model.skipped.iter().find(|s| s.0 == id).unwrap().1;
+        assert_eq!(reason(5), "3 poles are too few for degree 3");
+        assert_eq!(reason(6), "expected a count but found -1");
+    }
+}
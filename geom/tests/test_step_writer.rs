@@ -0,0 +1,208 @@
+use geom::io::step::part21::{Parameter, Part21File};
+use geom::io::step::{LengthUnit, StepGeometry, StepWriter, read_str};
+use geom::{
+    BSplineCurve3d, BSplineSurface, Circle3d, Cone, CoordinateSystem3d, Direction3d,
+    GeneralCoordinateSystem3d, Point3d, Sphere,
+};
+
+fn placement() -> CoordinateSystem3d {
+    CoordinateSystem3d::from_location_direction_xdirection(
+        Point3d::from_coords(1.0, 2.0, 3.0),
+        Direction3d::from_coords(0.0, 0.0, 1.0),
+        Direction3d::from_coords(1.0, 1.0, 0.0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_and_references() {
+        let mut writer = StepWriter::new();
+        writer.file_name = "part.stp".to_string();
+        writer
+            .add_point(&Point3d::from_coords(1.0, 2.0, 3.0))
+            .unwrap();
+        writer.add_placement(&placement()).unwrap();
+        let text = writer.write_str();
+        assert!(text.starts_with("ISO-10303-21;"));
+        assert!(text.contains("FILE_SCHEMA(('AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }'));"));
+
+        let file = Part21File::parse(&text).unwrap();
+        assert_eq!(
+            file.get_header("FILE_NAME").unwrap().parameters[0],
+            Parameter::String("part.stp".to_string())
+        );
+        for instance in file.instances.values() {
+            for record in instance.records.iter() {
+                let mut stack: Vec<_> = record.parameters.iter().collect();
+                while let Some(p) = stack.pop() {
+                    match p {
+                        Parameter::Reference(id) => {
+                            assert!(file.get(*id).is_some(), "dangling #{}", id)
+                        }
+                        Parameter::List(items) | Parameter::Typed(_, items) => {
+                            stack.extend(items.iter())
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        let representation = file
+            .instances
+            .values()
+            .find_map(|i| i.get_record("SHAPE_REPRESENTATION"))
+            .unwrap();
+        assert_eq!(representation.parameters[1].to_string(), "(#1,#5)");
+    }
+
+    #[test]
+    fn test_units_round_trip() {
+        let mut writer = StepWriter::new();
+        assert_eq!(
+            read_str(&writer.write_str()).unwrap().length_unit,
+            Some(1e-3)
+        );
+        writer.length_unit = LengthUnit::Metre;
+        assert_eq!(
+            read_str(&writer.write_str()).unwrap().length_unit,
+            Some(1.0)
+        );
+        writer.length_unit = LengthUnit::Inch;
+        let model = read_str(&writer.write_str()).unwrap();
+        assert!((model.length_unit.unwrap() - 0.0254).abs() < 1e-15);
+        assert_eq!(model.plane_angle_unit, 1.0);
+    }
+
+    #[test]
+    fn test_geometry_round_trip() {
+        let mut writer = StepWriter::new();
+        let circle = writer
+            .add_circle(&Circle3d {
+                position: placement(),
+                radius: 10.0,
+            })
+            .unwrap();
+        let cone = writer
+            .add_cone(&Cone {
+                position: placement().into(),
+                radius: 4.0,
+                semi_angle: 0.5,
+            })
+            .unwrap();
+        let sphere = writer
+            .add_sphere(&Sphere {
+                pos: placement().into(),
+                radius: 6.0,
+            })
+            .unwrap();
+
+        let model = read_str(&writer.write_str()).unwrap();
+        assert!(model.skipped.is_empty());
+        let StepGeometry::Circle(c) = model.get(circle).unwrap() else {
+            panic!()
+        };
+        assert_eq!(c.radius, 10.0);
+        assert!(c.position.vxdir.is_equal(&placement().vxdir, 1e-12));
+        assert!(c.position.vydir.is_equal(&placement().vydir, 1e-12));
+        assert_eq!(c.position.get_origin().get_coords(), (1.0, 2.0, 3.0));
+        let StepGeometry::ConicalSurface(c) = model.get(cone).unwrap() else {
+            panic!()
+        };
+        assert_eq!((c.radius, c.semi_angle), (4.0, 0.5));
+        let StepGeometry::SphericalSurface(s) = model.get(sphere).unwrap() else {
+            panic!()
+        };
+        assert_eq!(s.radius, 6.0);
+        assert_eq!(s.pos.get_origin().get_coords(), (1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn test_nurbs_round_trip() {
+        let h = 0.5f64.sqrt();
+        let arc = BSplineCurve3d::from_poles_weights_knots(
+            2,
+            vec![
+                Point3d::from_coords(1.0, 0.0, 0.0),
+                Point3d::from_coords(1.0, 1.0, 0.0),
+                Point3d::from_coords(0.0, 1.0, 0.0),
+            ],
+            vec![1.0, h, 1.0],
+            vec![0.0, 1.0],
+            vec![3, 3],
+        )
+        .unwrap();
+        let surface = BSplineSurface::from_poles_knots(
+            (1, 1),
+            vec![
+                vec![
+                    Point3d::from_coords(0.0, 0.0, 0.0),
+                    Point3d::from_coords(0.0, 1.0, 0.0),
+                ],
+                vec![
+                    Point3d::from_coords(1.0, 0.0, 1.0),
+                    Point3d::from_coords(1.0, 1.0, 1.0),
+                ],
+            ],
+            (vec![0.0, 1.0], vec![2, 2]),
+            (vec![0.0, 1.0], vec![2, 2]),
+        )
+        .unwrap();
+        let mut writer = StepWriter::new();
+        let arc_id = writer.add_bspline_curve(&arc).unwrap();
+        let surface_id = writer.add_bspline_surface(&surface).unwrap();
+        let text = writer.write_str();
+        assert!(text.contains("RATIONAL_B_SPLINE_CURVE("));
+
+        let model = read_str(&text).unwrap();
+        let StepGeometry::BSplineCurve(c) = model.get(arc_id).unwrap() else {
+            panic!()
+        };
+        assert!(c.is_rational());
+        let p = c.value(0.5);
+        assert!(((p.get_x() * p.get_x() + p.get_y() * p.get_y()).sqrt() - 1.0).abs() < 1e-12);
+        let StepGeometry::BSplineSurface(s) = model.get(surface_id).unwrap() else {
+            panic!()
+        };
+        assert!(s.value(0.5, 0.5).is_equal(&surface.value(0.5, 0.5), 1e-12));
+    }
+
+    #[test]
+    fn test_invalid_geometry_is_rejected() {
+        let mut writer = StepWriter::new();
+        writer
+            .add_point(&Point3d::from_coords(1.0, 2.0, 3.0))
+            .unwrap();
+        let text = writer.write_str();
+
+        let mut left_handed: GeneralCoordinateSystem3d = placement().into();
+        let (x, y, z) = left_handed.vydir.get_coords();
+        left_handed.vydir = Direction3d::from_coords(-x, -y, -z);
+        assert!(
+            writer
+                .add_sphere(&Sphere {
+                    pos: left_handed,
+                    radius: 1.0,
+                })
+                .is_err()
+        );
+        assert!(
+            writer
+                .add_circle(&Circle3d {
+                    position: placement(),
+                    radius: f64::NAN,
+                })
+                .is_err()
+        );
+        assert!(
+            writer
+                .add_point(&Point3d::from_coords(f64::INFINITY, 0.0, 0.0))
+                .is_err()
+        );
+        // Failed additions leave no instances behind.
+        assert_eq!(writer.write_str(), text);
+        assert!(!text.contains("NAN"));
+    }
+}
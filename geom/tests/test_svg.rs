@@ -0,0 +1,229 @@
+use geom::io::svg::{SvgStyle, SvgWriter, parse_path, path_data, read_str};
+use geom::{
+    BSplineCurve2d, Circle2d, CoordinateSystem2d, Curve2d, Direction2d, Ellipse2d, Point2d,
+    TrimmedCurve2d,
+};
+
+fn samples(curve: &TrimmedCurve2d, n: usize) -> Vec<Point2d> {
+    (0..=n)
+        .map(|i| curve.value(curve.first + (curve.last - curve.first) * i as f64 / n as f64))
+        .collect()
+}
+
+fn segment_distance(p: &Point2d, a: &Point2d, b: &Point2d) -> f64 {
+    let (dx, dy) = (b.get_x() - a.get_x(), b.get_y() - a.get_y());
+    let (px, py) = (p.get_x() - a.get_x(), p.get_y() - a.get_y());
+    let t = ((px * dx + py * dy) / (dx * dx + dy * dy)).clamp(0.0, 1.0);
+    ((px - t * dx).powi(2) + (py - t * dy).powi(2)).sqrt()
+}
+
+// Largest distance from points of `a` to the densely sampled curves of `b`.
+fn deviation(a: &[TrimmedCurve2d], b: &[TrimmedCurve2d]) -> f64 {
+    let dense: Vec<Vec<Point2d>> = b.iter().map(|c| samples(c, 2000)).collect();
+    a.iter()
+        .flat_map(|c| samples(c, 20))
+        .map(|p| {
+            dense
+                .iter()
+                .flat_map(|d| d.windows(2).map(|w| segment_distance(&p, &w[0], &w[1])))
+                .fold(f64::MAX, f64::min)
+        })
+        .fold(0.0, f64::max)
+}
+
+fn frame(x: f64, y: f64, angle: f64) -> CoordinateSystem2d {
+    CoordinateSystem2d::from_origin_vydir_vxdir(
+        Point2d::from_coords(x, y),
+        Direction2d::from_coords(angle.cos(), angle.sin()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let pi = std::f64::consts::PI;
+        let spline = BSplineCurve2d::from_poles_knots(
+            3,
+            vec![
+                Point2d::from_coords(0.0, 0.0),
+                Point2d::from_coords(1.0, 2.0),
+                Point2d::from_coords(2.0, -1.0),
+                Point2d::from_coords(3.0, 1.0),
+                Point2d::from_coords(4.0, 0.0),
+            ],
+            vec![0.0, 0.4, 1.0],
+            vec![4, 1, 4],
+        )
+        .unwrap();
+        let rational = BSplineCurve2d::from_poles_weights_knots(
+            2,
+            vec![
+                Point2d::from_coords(1.0, 0.0),
+                Point2d::from_coords(1.0, 1.0),
+                Point2d::from_coords(0.0, 1.0),
+            ],
+            vec![1.0, 0.5f64.sqrt(), 1.0],
+            vec![0.0, 1.0],
+            vec![3, 3],
+        )
+        .unwrap();
+        let curves = [
+            TrimmedCurve2d::from_segment(
+                &Point2d::from_coords(-1.0, 0.0),
+                &Point2d::from_coords(0.0, 0.0),
+            )
+            .unwrap(),
+            TrimmedCurve2d::new(Curve2d::BSpline(spline), 0.1, 0.9),
+            TrimmedCurve2d::new(
+                Curve2d::Circle(Circle2d {
+                    position: frame(5.0, 5.0, 0.3),
+                    radius: 2.0,
+                }),
+                2.0,
+                -1.5,
+            ),
+            TrimmedCurve2d::new(
+                Curve2d::Ellipse(Ellipse2d {
+                    position: frame(-3.0, 1.0, 1.0),
+                    major_radius: 3.0,
+                    minor_radius: 1.0,
+                }),
+                0.0,
+                2.0 * pi,
+            ),
+            TrimmedCurve2d::from_curve(Curve2d::BSpline(rational)),
+        ];
+        for curve in curves.iter() {
+            let data = path_data(std::slice::from_ref(curve), 9, 1e-6).unwrap();
+            let subpaths = parse_path(&data).unwrap();
+            let parsed: Vec<TrimmedCurve2d> = subpaths.into_iter().flat_map(|s| s.curves).collect();
+            let (start, end) = (parsed[0].start_point(), parsed.last().unwrap().end_point());
+            assert!(start.is_equal(&curve.start_point(), 1e-8), "{}", data);
+            assert!(end.is_equal(&curve.end_point(), 1e-8), "{}", data);
+            assert!(
+                deviation(std::slice::from_ref(curve), &parsed) < 1e-5,
+                "{}",
+                data
+            );
+            assert!(
+                deviation(&parsed, std::slice::from_ref(curve)) < 1e-5,
+                "{}",
+                data
+            );
+        }
+
+        let data = path_data(&curves[1..2], 6, 1e-6).unwrap();
+        assert_eq!(data.matches('C').count(), 2);
+        let data = path_data(&curves[3..4], 6, 1e-6).unwrap();
+        assert!(data.ends_with('Z'));
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let subpaths =
+            parse_path("m10,10 h10 v10 H10 z M0 0 q5-5 10 0 t10 0 c0 5 5 5 5 0 s5-5 5 0 5 5 30 0")
+                .unwrap();
+        assert_eq!(subpaths.len(), 2);
+        assert!(subpaths[0].closed);
+        assert_eq!(subpaths[0].curves.len(), 4);
+        assert!(
+            subpaths[0].curves[3]
+                .end_point()
+                .is_equal(&Point2d::from_coords(10.0, 10.0), 1e-12)
+        );
+        let open = &subpaths[1];
+        assert!(!open.closed);
+        // q, t, c, s and one implicit repetition of s.
+        assert_eq!(open.curves.len(), 5);
+        // The smooth quadratic reflects the previous control point to (15, 5).
+        assert!(
+            open.curves[1]
+                .value(0.5)
+                .is_equal(&Point2d::from_coords(15.0, 2.5), 1e-12)
+        );
+        assert!(
+            open.curves[4]
+                .end_point()
+                .is_equal(&Point2d::from_coords(60.0, 0.0), 1e-12)
+        );
+
+        // Compact arc flags and an implicit lineto after moveto.
+        let subpaths = parse_path("M0 0 10 0A5 5 0 014 0a10 5 90 1 1-20 0").unwrap();
+        let curves = &subpaths[0].curves;
+        assert_eq!(curves.len(), 3);
+        match &curves[1].basis {
+            Curve2d::Circle(c) => {
+                assert!((c.radius - 5.0).abs() < 1e-12);
+                assert!(
+                    curves[1]
+                        .value(0.5 * (curves[1].first + curves[1].last))
+                        .is_equal(&Point2d::from_coords(7.0, 1.0), 1e-12)
+                );
+            }
+            _ => panic!("expected a circle"),
+        }
+        match &curves[2].basis {
+            Curve2d::Ellipse(e) => {
+                // Radii are scaled up until the arc reaches the end point.
+                assert!((e.major_radius - 20.0).abs() < 1e-9);
+                assert!((e.minor_radius - 10.0).abs() < 1e-9);
+            }
+            _ => panic!("expected an ellipse"),
+        }
+        assert!(
+            curves[2]
+                .end_point()
+                .is_equal(&Point2d::from_coords(-16.0, 0.0), 1e-9)
+        );
+
+        assert!(parse_path("L1 1").is_err());
+        assert!(parse_path("M0 0 X1").is_err());
+    }
+
+    #[test]
+    fn test_document() {
+        let mut writer = SvgWriter::new();
+        writer.margin = 1.0;
+        writer.width = Some("100mm".to_string());
+        let style = SvgStyle {
+            stroke: "#ff0000".to_string(),
+            ..SvgStyle::default()
+        };
+        let circle = TrimmedCurve2d::from_curve(Curve2d::Circle(Circle2d {
+            position: frame(0.0, 2.0, 0.0),
+            radius: 1.0,
+        }));
+        writer.add(&[circle], &style).unwrap();
+        let svg = writer.to_svg();
+        assert!(svg.contains("viewBox=\"-2 -4 4 4\""));
+        assert!(svg.contains("width=\"100mm\""));
+        assert!(svg.contains("scale(1,-1)"));
+        assert!(svg.contains("stroke=\"#ff0000\""));
+
+        let paths = read_str(&svg).unwrap();
+        assert_eq!(paths.len(), 1);
+        assert!(paths[0].subpaths[0].closed);
+        assert_eq!(paths[0].subpaths[0].curves.len(), 2);
+
+        let unbounded = TrimmedCurve2d::from_curve(
+            TrimmedCurve2d::from_segment(
+                &Point2d::from_coords(0.0, 0.0),
+                &Point2d::from_coords(1.0, 0.0),
+            )
+            .unwrap()
+            .basis,
+        );
+        assert!(writer.add(&[unbounded], &style).is_err());
+
+        let paths = read_str(
+            "<svg><pathology/><path id='a&amp;b' transform=\"translate(1 2)\" d=\"M0 0L1 1\"/></svg>",
+        )
+        .unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].id.as_deref(), Some("a&b"));
+        assert_eq!(paths[0].transform.as_deref(), Some("translate(1 2)"));
+    }
+}